@@ -0,0 +1,99 @@
+//! Timings for the EKF covariance update on growing maps, next to the SEIF's
+//! sparse information update on the same maps.
+//!
+//! `cargo bench` uses the nalgebra backend, `cargo bench --features faer` the faer one,
+//! and with `faer` on also times both backends' rank-2 covariance update side by side.
+
+use std::f32::consts::TAU;
+use std::hint::black_box;
+use std::time::Instant;
+
+use slam_simulator::config::Config;
+use slam_simulator::simulation::Observation;
+use slam_simulator::slam::{EkfSlam, SeifSlam, SlamFilter, linalg};
+
+const MAP_SIZES: [usize; 4] = [50, 100, 200, 400];
+const CORRECTIONS: usize = 200;
+
+fn ring_observations(count: usize) -> Vec<Observation> {
+    (0..count)
+        .map(|id| Observation {
+            id,
+            range: 80.0 + (id % 7) as f32 * 10.0,
+            bearing: TAU * id as f32 / count as f32 - TAU / 2.0,
+        })
+        .collect()
+}
+
+/*
+ * milliseconds per predict and single-landmark correction, after a first
+ * sighting of the whole ring
+ */
+fn time_corrections(slam: &mut dyn SlamFilter, observations: &[Observation], cfg: &Config) -> f64 {
+    slam.update(observations, cfg); // first sighting initializes every landmark
+
+    let start = Instant::now();
+    for step in 0..CORRECTIONS {
+        slam.predict(10.0, 0.1, 1.0 / 60.0, cfg);
+        let index = step % observations.len();
+        slam.update(&observations[index..index + 1], cfg);
+    }
+    let elapsed = start.elapsed();
+    black_box(&*slam);
+    elapsed.as_secs_f64() * 1000.0 / CORRECTIONS as f64
+}
+
+/*
+ * milliseconds per rank-2 update of an n x n covariance, the kernel the
+ * backends differ in
+ */
+#[cfg(feature = "faer")]
+fn time_rank2(update: fn(&mut nalgebra::DMatrix<f32>, f32, &nalgebra::MatrixXx2<f32>, &nalgebra::MatrixXx2<f32>), size: usize) -> f64 {
+    use nalgebra::{DMatrix, MatrixXx2};
+
+    let mut p = DMatrix::<f32>::identity(size, size);
+    let u = MatrixXx2::<f32>::from_fn(size, |row, col| 1e-3 * (row + col) as f32);
+    let v = MatrixXx2::<f32>::from_fn(size, |row, col| 1e-3 * (row * col) as f32);
+
+    let start = Instant::now();
+    for _ in 0..CORRECTIONS {
+        update(&mut p, -1.0, &u, &v);
+    }
+    let elapsed = start.elapsed();
+    black_box(&p);
+    elapsed.as_secs_f64() * 1000.0 / CORRECTIONS as f64
+}
+
+fn main() {
+    let cfg = Config::default();
+    println!("backend: {}", linalg::BACKEND);
+
+    for landmarks in MAP_SIZES {
+        let observations = ring_observations(landmarks);
+        let mut ekf = EkfSlam::new();
+        let ekf_time = time_corrections(&mut ekf, &observations, &cfg);
+        let mut seif = SeifSlam::new();
+        let seif_time = time_corrections(&mut seif, &observations, &cfg);
+
+        println!(
+            "{:>4} landmarks ({:>4}x{:<4} covariance): EKF {:>8.3} ms, SEIF {:>8.3} ms per correction ({} active links)",
+            landmarks,
+            ekf.covariance.nrows(),
+            ekf.covariance.ncols(),
+            ekf_time,
+            seif_time,
+            seif.active.len()
+        );
+    }
+
+    #[cfg(feature = "faer")]
+    for landmarks in MAP_SIZES {
+        let size = 3 + 2 * landmarks;
+        println!(
+            "{:>4} landmarks rank-2 update: nalgebra {:>8.3} ms, faer {:>8.3} ms",
+            landmarks,
+            time_rank2(linalg::rank2_update_nalgebra, size),
+            time_rank2(linalg::rank2_update_faer, size)
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use macroquad::prelude::*;
+
+use crate::math::Pose2;
+use crate::simulation::{Landmark, Observation, Robot};
+use crate::slam::SlamFilter;
+
+/*
+ * what a plugin is handed every simulated frame, after the filters have taken
+ * the frame's observations. Filters go by the names the determinism audit
+ * uses: "ekf", "fast", "seif", "odom", and "robo" and "mht" while they run
+ */
+pub struct FrameContext<'a> {
+    pub time: f32,
+    pub robot: &'a Robot,
+    pub landmarks: &'a [Landmark],
+    pub observations: &'a [Observation],
+    pub filters: &'a [(&'a str, &'a dyn SlamFilter)],
+}
+
+impl FrameContext<'_> {
+    pub fn filter(&self, name: &str) -> Option<&dyn SlamFilter> {
+        self.filters.iter().find(|(filter, _)| *filter == name).map(|&(_, slam)| slam)
+    }
+}
+
+/*
+ * a research extension fed the ground truth, the filters, and the
+ * observations every frame. Its metrics are listed in the HUD, and its overlay
+ * is drawn in world coordinates over the ground truth view
+ */
+pub trait Analytics {
+    fn name(&self) -> &str;
+
+    fn on_frame(&mut self, frame: &FrameContext);
+
+    fn metrics(&self) -> Vec<(String, f32)> {
+        Vec::new()
+    }
+
+    fn draw_overlay(&self) {}
+}
+
+pub struct AnalyticsRegistry {
+    plugins: Vec<Box<dyn Analytics>>,
+}
+
+impl AnalyticsRegistry {
+    pub fn new() -> Self {
+        Self { plugins: Vec::new() }
+    }
+
+    pub fn register(&mut self, plugin: impl Analytics + 'static) {
+        self.plugins.push(Box::new(plugin));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    pub fn on_frame(&mut self, frame: &FrameContext) {
+        for plugin in &mut self.plugins {
+            plugin.on_frame(frame);
+        }
+    }
+
+    // every plugin's metrics, each name prefixed with its plugin's
+    pub fn metrics(&self) -> Vec<(String, f32)> {
+        self.plugins.iter()
+            .flat_map(|plugin| plugin.metrics().into_iter().map(|(name, value)| (format!("{}: {name}", plugin.name()), value)))
+            .collect()
+    }
+
+    pub fn draw_overlays(&self) {
+        for plugin in &self.plugins {
+            plugin.draw_overlay();
+        }
+    }
+}
+
+/*
+ * the plugins the simulator runs with. Register new ones here behind a cargo
+ * feature of their own, so they stay out of builds that don't ask for them;
+ * `example-analytics` turns on the example below
+ */
+pub fn registered() -> AnalyticsRegistry {
+    #[allow(unused_mut)]
+    let mut registry = AnalyticsRegistry::new();
+    #[cfg(feature = "example-analytics")]
+    registry.register(ErrorEnvelope::new("ekf"));
+    registry
+}
+
+/*
+ * example plugin: one filter's position error now and at its worst, and how
+ * often the truth was within its 3 sigma position ellipse. Draws the error as
+ * a line from the true robot to the estimate and rings the worst spot
+ */
+pub struct ErrorEnvelope {
+    filter: &'static str,
+    frames: usize,
+    frames_within: usize,
+    pub error: f32,
+    pub peak_error: f32,
+    pub peak_at: Option<(f32, f32)>, // where the robot truly was at the worst
+    segment: Option<((f32, f32), (f32, f32))>,
+}
+
+impl ErrorEnvelope {
+    pub fn new(filter: &'static str) -> Self {
+        Self { filter, frames: 0, frames_within: 0, error: 0.0, peak_error: 0.0, peak_at: None, segment: None }
+    }
+
+    pub fn within_share(&self) -> f32 {
+        self.frames_within as f32 / self.frames.max(1) as f32
+    }
+}
+
+impl Analytics for ErrorEnvelope {
+    fn name(&self) -> &str {
+        "error envelope"
+    }
+
+    fn on_frame(&mut self, frame: &FrameContext) {
+        let Some(slam) = frame.filter(self.filter) else { return; };
+        let Pose2 { x, y, .. } = slam.estimated_pose();
+        let truth = (frame.robot.x, frame.robot.y);
+        let error = nalgebra::Vector2::new(x - truth.0, y - truth.1);
+
+        self.frames += 1;
+        let covariance = slam.pose_covariance().fixed_view::<2, 2>(0, 0).into_owned();
+        // squared Mahalanobis distance under 9 is inside the 3 sigma ellipse
+        if covariance.try_inverse().is_some_and(|information| (error.transpose() * information * error)[(0, 0)] <= 9.0) {
+            self.frames_within += 1;
+        }
+
+        self.error = error.norm();
+        if self.error > self.peak_error {
+            self.peak_error = self.error;
+            self.peak_at = Some(truth);
+        }
+        self.segment = Some((truth, (x, y)));
+    }
+
+    fn metrics(&self) -> Vec<(String, f32)> {
+        vec![
+            (format!("{} error", self.filter), self.error),
+            (format!("{} peak error", self.filter), self.peak_error),
+            (format!("{} within 3 sigma %", self.filter), 100.0 * self.within_share()),
+        ]
+    }
+
+    fn draw_overlay(&self) {
+        if let Some((truth, estimate)) = self.segment {
+            draw_line(truth.0, truth.1, estimate.0, estimate.1, 1.5, ORANGE);
+        }
+        if let Some((x, y)) = self.peak_at {
+            draw_circle_lines(x, y, self.peak_error.max(3.0), 1.5, ORANGE);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use macroquad::prelude::*;
+use crate::monte_carlo::CovarianceValidation;
+use super::plots::{self, Series};
+use super::{font_size, line_spacing};
+
+const ACTUAL_COLOR: Color = Color::new(1.0, 0.4, 0.4, 0.9);
+const REPORTED_COLOR: Color = Color::new(0.4, 0.8, 1.0, 0.9);
+
+const STATES: [&str; 3] = ["x", "y", "heading"];
+
+/*
+ * Monte Carlo covariance check: for each pose state, the standard deviation of
+ * the real errors across runs ("Real") overlaid on the one the filter reported
+ * ("Est"). Tab cycles through the filters.
+ */
+pub async fn run(validations: Vec<(&str, CovarianceValidation)>, font: &Font) {
+    if validations.is_empty() { return; }
+
+    let mut selected = 0;
+
+    loop {
+        if is_key_pressed(KeyCode::Tab) { selected = (selected + 1) % validations.len(); }
+        if is_key_pressed(KeyCode::Escape) { break; }
+
+        let (name, validation) = &validations[selected];
+
+        clear_background(Color::new(0.1, 0.1, 0.1, 1.0));
+
+        draw_label(font, &format!("Covariance check: {name} over {} runs (Tab to switch)", validation.runs), 20.0, 25.0, WHITE);
+
+        let ratios = validation.variance_ratios();
+        let top = 25.0 + 2.0 * line_spacing();
+        let chart_height = (screen_height() - top) / STATES.len() as f32;
+        for (state, label) in STATES.iter().enumerate() {
+            let series: [Series; 2] = [
+                ("Real", validation.empirical_stdev(state), ACTUAL_COLOR),
+                ("Est", validation.reported_stdev(state), REPORTED_COLOR),
+            ];
+            let area = Rect::new(60.0, top + state as f32 * chart_height, screen_width() - 80.0, chart_height - line_spacing() - 20.0);
+            plots::draw_chart(font, &format!("{label} stdev, variance ratio {:.2}", ratios[state]), &series, area);
+        }
+
+        next_frame().await
+    }
+}
+
+fn draw_label(font: &Font, text: &str, x: f32, y: f32, color: Color) {
+    draw_text_ex(
+        text,
+        x,
+        y,
+        TextParams {
+            font: Some(font),
+            font_size: font_size(),
+            color,
+            ..Default::default()
+        }
+    );
+}
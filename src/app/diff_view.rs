@@ -0,0 +1,141 @@
+use macroquad::prelude::*;
+use crate::recording::{Bookmark, ErrorSummary, Recording};
+use super::{font_size, line_spacing};
+
+const RUN_A_COLOR: Color = Color::new(0.0, 1.0, 0.0, 0.8);
+const RUN_B_COLOR: Color = Color::new(1.0, 0.6, 0.0, 0.8);
+
+/*
+ * side-by-side comparison of two recorded runs: overlaid trajectories, error
+ * curves over time, and a numeric summary. Tab cycles through the estimators
+ * both recordings share.
+ */
+pub async fn run(a: Recording, b: Recording, a_name: &str, b_name: &str, font: &Font) {
+    let shared: Vec<(usize, usize)> = a.estimator_names.iter()
+        .enumerate()
+        .filter_map(|(i, name)| b.estimator_index(name).map(|j| (i, j)))
+        .collect();
+
+    if shared.is_empty() {
+        eprintln!("recordings have no estimator in common");
+        return;
+    }
+
+    let mut selected = 0;
+
+    loop {
+        if is_key_pressed(KeyCode::Tab) { selected = (selected + 1) % shared.len(); }
+        if is_key_pressed(KeyCode::Escape) { break; }
+
+        let (index_a, index_b) = shared[selected];
+
+        clear_background(Color::new(0.1, 0.1, 0.1, 1.0));
+
+        let w = screen_width();
+        let h = screen_height();
+
+        draw_label(font, &format!("Run diff: {} (Tab to switch)", a.estimator_names[index_a]), 20.0, 25.0, WHITE);
+        draw_label(font, &format!("A: {a_name}"), 20.0, 25.0 + line_spacing(), RUN_A_COLOR);
+        draw_label(font, &format!("B: {b_name}"), w / 2.0, 25.0 + line_spacing(), RUN_B_COLOR);
+
+        draw_trajectories(&a, &b, index_a, index_b, Rect::new(20.0, 80.0, w - 40.0, h * 0.45));
+        draw_error_curves(&a.position_errors(index_a), &b.position_errors(index_b), [&a.bookmarks, &b.bookmarks], font, Rect::new(60.0, h * 0.45 + 100.0, w - 80.0, h * 0.22));
+        draw_summary(&a.error_summary(index_a), &b.error_summary(index_b), font, h * 0.67 + 130.0);
+
+        next_frame().await
+    }
+}
+
+fn draw_label(font: &Font, text: &str, x: f32, y: f32, color: Color) {
+    draw_text_ex(
+        text,
+        x,
+        y,
+        TextParams {
+            font: Some(font),
+            font_size: font_size(),
+            color,
+            ..Default::default()
+        }
+    );
+}
+
+fn draw_trajectories(a: &Recording, b: &Recording, index_a: usize, index_b: usize, area: Rect) {
+    let gt: Vec<(f32, f32)> = a.frames.iter().map(|f| f.ground_truth.position()).collect();
+    let est_a: Vec<(f32, f32)> = a.frames.iter().map(|f| f.estimates[index_a].position()).collect();
+    let est_b: Vec<(f32, f32)> = b.frames.iter().map(|f| f.estimates[index_b].position()).collect();
+    let gt_b: Vec<(f32, f32)> = b.frames.iter().map(|f| f.ground_truth.position()).collect();
+
+    // fit every point into the area, keeping aspect ratio
+    let all = gt.iter().chain(&est_a).chain(&est_b).chain(&gt_b);
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (f32::INFINITY, f32::INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
+    for &(x, y) in all {
+        min_x = min_x.min(x);
+        min_y = min_y.min(y);
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+    }
+    if !min_x.is_finite() { return; }
+
+    let scale = (area.w / (max_x - min_x).max(1.0)).min(area.h / (max_y - min_y).max(1.0));
+    let to_screen = |(x, y): (f32, f32)| (area.x + (x - min_x) * scale, area.y + (y - min_y) * scale);
+
+    draw_rectangle_lines(area.x, area.y, area.w, area.h, 1.0, DARKGRAY);
+    draw_polyline(&gt_b, &to_screen, Color::new(1.0, 1.0, 1.0, 0.3));
+    draw_polyline(&gt, &to_screen, WHITE);
+    draw_polyline(&est_a, &to_screen, RUN_A_COLOR);
+    draw_polyline(&est_b, &to_screen, RUN_B_COLOR);
+}
+
+fn draw_polyline(points: &[(f32, f32)], to_screen: &dyn Fn((f32, f32)) -> (f32, f32), color: Color) {
+    for pair in points.windows(2) {
+        let (x1, y1) = to_screen(pair[0]);
+        let (x2, y2) = to_screen(pair[1]);
+        draw_line(x1, y1, x2, y2, 1.5, color);
+    }
+}
+
+/*
+ * the runs' bookmarks are drawn across the curves at their times, A's notes
+ * above the plot and B's below it
+ */
+fn draw_error_curves(errors_a: &[(f32, f32)], errors_b: &[(f32, f32)], bookmarks: [&[Bookmark]; 2], font: &Font, area: Rect) {
+    let max_time = errors_a.iter().chain(errors_b).map(|(t, _)| *t).fold(1e-3, f32::max);
+    let max_error = errors_a.iter().chain(errors_b).map(|(_, e)| *e).fold(1e-3, f32::max);
+
+    let to_screen = |(t, e): (f32, f32)| (area.x + t / max_time * area.w, area.y + area.h - e / max_error * area.h);
+
+    draw_line(area.x, area.y + area.h, area.x + area.w, area.y + area.h, 1.0, GRAY);
+    draw_line(area.x, area.y, area.x, area.y + area.h, 1.0, GRAY);
+    draw_label(font, &format!("{max_error:.0}"), area.x - 45.0, area.y + 10.0, GRAY);
+    draw_label(font, &format!("{max_time:.0} s"), area.x + area.w - 50.0, area.y + area.h + 20.0, GRAY);
+
+    for ((bookmarks, color), label_y) in bookmarks.into_iter().zip([RUN_A_COLOR, RUN_B_COLOR]).zip([area.y - 5.0, area.y + area.h + 20.0]) {
+        for bookmark in bookmarks.iter().filter(|bookmark| bookmark.time <= max_time) {
+            let x = area.x + bookmark.time / max_time * area.w;
+            draw_line(x, area.y, x, area.y + area.h, 1.0, Color { a: 0.4, ..color });
+            let label = if bookmark.note.is_empty() { format!("{:.1} s", bookmark.time) } else { bookmark.note.clone() };
+            draw_label(font, &label, x + 3.0, label_y, color);
+        }
+    }
+
+    draw_polyline(errors_a, &to_screen, RUN_A_COLOR);
+    draw_polyline(errors_b, &to_screen, RUN_B_COLOR);
+}
+
+fn draw_summary(a: &ErrorSummary, b: &ErrorSummary, font: &Font, top: f32) {
+    let rows = [
+        ("", ["RMSE".to_string(), "mean".to_string(), "max".to_string(), "final".to_string()], LIGHTGRAY),
+        ("A", [a.rmse, a.mean, a.max, a.last].map(|v| format!("{v:.2}")), RUN_A_COLOR),
+        ("B", [b.rmse, b.mean, b.max, b.last].map(|v| format!("{v:.2}")), RUN_B_COLOR),
+        ("B-A", [b.rmse - a.rmse, b.mean - a.mean, b.max - a.max, b.last - a.last].map(|v| format!("{v:+.2}")), WHITE),
+    ];
+
+    for (i, (name, values, color)) in rows.iter().enumerate() {
+        let y = top + (i as f32) * (line_spacing() * 0.8);
+        draw_label(font, name, 20.0, y, *color);
+        for (j, value) in values.iter().enumerate() {
+            draw_label(font, value, 100.0 + (j as f32) * 140.0, y, *color);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use macroquad::prelude::*;
+use macroquad::models::Vertex;
+use crate::config::Config;
+use crate::slam::SlamFilter;
+
+const MAX_SEGMENTS: usize = 48;
+const MIN_SEGMENTS: usize = 8;
+
+/*
+ * segments for an ellipse outline whose major half axis is `major_pixels` on
+ * screen: none below `ellipse_min_pixels`, else about one per
+ * `ellipse_segment_pixels` of circumference
+ */
+pub fn lod_segments(major_pixels: f32, cfg: &Config) -> usize {
+    if major_pixels.is_nan() || major_pixels < cfg.ellipse_min_pixels { return 0; }
+    let circumference = std::f32::consts::TAU * major_pixels;
+    ((circumference / cfg.ellipse_segment_pixels) as usize).clamp(MIN_SEGMENTS, MAX_SEGMENTS)
+}
+
+/*
+ * ellipse outlines collected into meshes, so a whole map of them goes to the
+ * GPU in a draw call or a few instead of one line per segment
+ */
+pub struct EllipseBatch {
+    meshes: Vec<Mesh>,
+}
+
+impl EllipseBatch {
+    pub fn new() -> Self {
+        Self { meshes: Vec::new() }
+    }
+
+    pub fn meshes(&self) -> &[Mesh] {
+        &self.meshes
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.meshes.iter().map(|mesh| mesh.vertices.len()).sum()
+    }
+
+    /*
+     * the `sigmas` ellipse of `covariance` around `center` as a ring of quads
+     * `thickness` wide, in `segments` segments
+     */
+    pub fn add(&mut self, center: (f32, f32), covariance: &nalgebra::Matrix2<f32>, sigmas: f32, thickness: f32, segments: usize, color: Color) {
+        if segments == 0 { return; }
+        let eigen = covariance.symmetric_eigen();
+        let half_axes: Vec<_> = (0..2)
+            .map(|i| eigen.eigenvectors.column(i) * sigmas * eigen.eigenvalues[i].max(0.0).sqrt())
+            .collect();
+
+        // indices are 16 bit, so a mesh is closed off before it overflows them
+        let needed = 2 * segments;
+        if self.meshes.last().is_none_or(|mesh| mesh.vertices.len() + needed > u16::MAX as usize) {
+            self.meshes.push(Mesh { vertices: Vec::new(), indices: Vec::new(), texture: None });
+        }
+        let mesh = self.meshes.last_mut().unwrap();
+        let base = mesh.vertices.len() as u16;
+
+        for i in 0..segments {
+            let angle = std::f32::consts::TAU * i as f32 / segments as f32;
+            let offset = half_axes[0] * angle.cos() + half_axes[1] * angle.sin();
+            // inner and outer edge along the outward normal of the outline
+            let tangent = half_axes[1] * angle.cos() - half_axes[0] * angle.sin();
+            let normal = vec2(tangent.y, -tangent.x).normalize_or_zero() * (0.5 * thickness);
+            let point = vec2(center.0 + offset.x, center.1 + offset.y);
+            mesh.vertices.push(Vertex::new2((point - normal).extend(0.0), Vec2::ZERO, color));
+            mesh.vertices.push(Vertex::new2((point + normal).extend(0.0), Vec2::ZERO, color));
+        }
+        for i in 0..segments as u16 {
+            let next = (i + 1) % segments as u16;
+            let (inner, outer, next_inner, next_outer) = (base + 2 * i, base + 2 * i + 1, base + 2 * next, base + 2 * next + 1);
+            mesh.indices.extend_from_slice(&[inner, outer, next_outer, inner, next_outer, next_inner]);
+        }
+    }
+
+    pub fn draw(&self) {
+        for mesh in &self.meshes {
+            draw_mesh(mesh);
+        }
+    }
+}
+
+/*
+ * the standard deviations an ellipse is drawn at: every whole one below
+ * `ellipse_sigmas`, then `ellipse_sigmas` itself, so 1, 2, and 3 by default
+ */
+pub fn sigma_levels(cfg: &Config) -> Vec<f32> {
+    let mut levels: Vec<f32> = (1..).map(|sigmas| sigmas as f32).take_while(|&sigmas| sigmas < cfg.ellipse_sigmas).collect();
+    levels.push(cfg.ellipse_sigmas);
+    levels
+}
+
+/*
+ * the nested `sigma_levels` ellipses of `covariance` around `center`, fading
+ * outward, unless the outermost falls outside `visible`
+ */
+fn add_nested(batch: &mut EllipseBatch, center: (f32, f32), covariance: &nalgebra::Matrix2<f32>, visible: Rect, pixels_per_unit: f32, color: Color, cfg: &Config) {
+    // the major half axis bounds the ellipse, so it decides both culling and detail
+    let stdev = covariance.symmetric_eigenvalues().max().max(0.0).sqrt();
+    let (x, y) = center;
+    let outer = cfg.ellipse_sigmas * stdev;
+    if x + outer < visible.x || x - outer > visible.x + visible.w || y + outer < visible.y || y - outer > visible.y + visible.h {
+        return;
+    }
+    let thickness = 1.5 / pixels_per_unit;
+    let levels = sigma_levels(cfg);
+    for (index, &sigmas) in levels.iter().enumerate() {
+        let fade = 1.0 - 0.5 * index as f32 / (levels.len() - 1).max(1) as f32;
+        let color = Color { a: color.a * fade, ..color };
+        batch.add(center, covariance, sigmas, thickness, lod_segments(sigmas * stdev * pixels_per_unit, cfg), color);
+    }
+}
+
+/*
+ * the landmark estimates' covariance ellipses of `slam` that fall inside
+ * `visible` (world coordinates) at `pixels_per_unit`, with level of detail
+ */
+pub fn add_landmark_ellipses(batch: &mut EllipseBatch, slam: &dyn SlamFilter, visible: Rect, pixels_per_unit: f32, cfg: &Config) {
+    let color = Color { a: 0.8, ..slam.color() };
+    for (id, x, y) in slam.estimated_map() {
+        let Some(covariance) = slam.landmark_covariance(id) else { continue; };
+        add_nested(batch, (x, y), &covariance, visible, pixels_per_unit, color, cfg);
+    }
+}
+
+/*
+ * the ellipses of the position block of `slam`'s pose covariance around its
+ * estimate, culled and detailed like the landmarks'
+ */
+pub fn add_pose_ellipses(batch: &mut EllipseBatch, slam: &dyn SlamFilter, visible: Rect, pixels_per_unit: f32, cfg: &Config) {
+    let covariance = slam.pose_covariance().fixed_view::<2, 2>(0, 0).into_owned();
+    add_nested(batch, slam.estimated_pose().position(), &covariance, visible, pixels_per_unit, Color { a: 0.8, ..slam.color() }, cfg);
+}
@@ -0,0 +1,151 @@
+use crate::app::input::Anchoring;
+use crate::config::Config;
+use crate::consistency::ConsistencyMonitor;
+use crate::health::LocalizationHealth;
+use crate::math::Pose2;
+use crate::metrics::LandmarkHistory;
+use crate::scan_map::ScanMap;
+use crate::simulation::Observation;
+use crate::slam::{Backend, BearingOnlyEkf, EkfSlam, GraphSlam, OdometryBaseline, SlamFilter};
+use crate::trails::{Trails, UncertaintyTube};
+
+/*
+ * the robot's true pose plus the configured prior error
+ */
+pub fn prior_pose(true_pose: Pose2, cfg: &Config) -> Pose2 {
+    let error = cfg.initial_pose_error;
+    Pose2::new(true_pose.x + error.x, true_pose.y + error.y, true_pose.theta + error.theta)
+}
+
+/*
+ * the window's filters and what it keeps of their history, which start over
+ * together whenever the filters are restarted. The EKF, which most of the tools
+ * work on, FastSLAM and the SEIF always run; the other backends follow the
+ * settings, in the order they were switched on, and are missing until the next
+ * frame creates them
+ */
+pub struct Estimates {
+    pub filters: Vec<(Backend, Box<dyn SlamFilter>)>,
+    pub graph_slam: GraphSlam,
+    pub bearing_only: Option<(BearingOnlyEkf, BearingOnlyEkf)>, // inverse depth, triangulation
+    pub odometry_baseline: OdometryBaseline,
+    pub trails: Trails,
+    pub uncertainty_tube: UncertaintyTube,
+    pub localization_health: LocalizationHealth,
+    pub consistency: ConsistencyMonitor,
+    pub scan_map: ScanMap,
+    pub landmark_history: LandmarkHistory,
+    pub last_anchoring: Option<Anchoring>,
+}
+
+impl Estimates {
+    pub const ALWAYS_RUNNING: [Backend; 3] = [Backend::Ekf, Backend::FastSlam, Backend::Seif];
+
+    /*
+     * fresh filters believing the robot, truly at `true_pose`, is at its `prior_pose`
+     */
+    pub fn new(true_pose: Pose2, cfg: &Config) -> Self {
+        Self::resumed(true_pose, Vec::new(), cfg)
+    }
+
+    /*
+     * carries on from restored filters, starting any that always run but weren't
+     * restored from the prior; dead reckoning and GraphSLAM start from wherever
+     * the EKF is
+     */
+    pub fn resumed(true_pose: Pose2, restored: Vec<(Backend, Box<dyn SlamFilter>)>, cfg: &Config) -> Self {
+        let mut filters = restored;
+        for backend in Self::ALWAYS_RUNNING {
+            if filters.iter().all(|(running, _)| *running != backend) {
+                filters.push((backend, backend.build(prior_pose(true_pose, cfg), cfg)));
+            }
+        }
+        let ekf = &filters.iter().find(|(running, _)| *running == Backend::Ekf).expect("the EKF always runs").1;
+
+        Self {
+            odometry_baseline: OdometryBaseline::from_slam(ekf.as_ref()),
+            graph_slam: GraphSlam::with_prior(ekf.estimated_pose(), cfg.initial_stdev_position, cfg.initial_stdev_heading),
+            filters,
+            bearing_only: None,
+            trails: Trails::new(true_pose, cfg.trail_length),
+            uncertainty_tube: UncertaintyTube::new(cfg),
+            localization_health: LocalizationHealth::new(),
+            consistency: ConsistencyMonitor::new(cfg.metrics_history_len),
+            scan_map: ScanMap::new(cfg.scan_map_points),
+            landmark_history: LandmarkHistory::new(cfg.landmark_history_len),
+            last_anchoring: None,
+        }
+    }
+
+    /*
+     * starts every filter over from the configured prior around `true_pose`,
+     * dropping the history of the old estimates with them
+     */
+    pub fn restart_filters(&mut self, true_pose: Pose2, cfg: &Config) {
+        *self = Self::new(true_pose, cfg);
+    }
+
+    pub fn filter(&self, backend: Backend) -> Option<&dyn SlamFilter> {
+        self.filters.iter().find(|(running, _)| *running == backend).map(|(_, slam)| slam.as_ref())
+    }
+
+    pub fn is_running(&self, backend: Backend) -> bool {
+        self.filter(backend).is_some()
+    }
+
+    // one of the `ALWAYS_RUNNING` backends
+    pub fn running(&self, backend: Backend) -> &dyn SlamFilter {
+        self.filter(backend).unwrap_or_else(|| panic!("{} always runs", backend.name()))
+    }
+
+    pub fn ekf(&self) -> &EkfSlam {
+        ekf_in(&self.filters)
+    }
+
+    pub fn ekf_mut(&mut self) -> &mut EkfSlam {
+        self.filters.iter_mut()
+            .find(|(running, _)| *running == Backend::Ekf)
+            .and_then(|(_, slam)| slam.as_ekf_mut())
+            .expect("the EKF always runs")
+    }
+
+    /*
+     * the EKF's health and innovation checks of a frame's observations, before
+     * the filters take them in
+     */
+    pub fn record_innovations(&mut self, time: f32, observations: &[Observation], cfg: &Config) {
+        let ekf = ekf_in(&self.filters);
+        self.localization_health.record(time, ekf, observations, cfg);
+        self.consistency.record_innovations(ekf, observations, cfg);
+    }
+
+    /*
+     * the EKF's consistency, landmark and uncertainty history after the update
+     */
+    pub fn record_history(&mut self, time: f32, true_pose: Pose2, cfg: &Config) {
+        let ekf = ekf_in(&self.filters);
+        self.consistency.record(time, ekf, true_pose, cfg);
+        self.landmark_history.record(time, ekf);
+        self.uncertainty_tube.record(ekf);
+    }
+
+    /*
+     * starts `backend` from the prior around `true_pose` when `on` and it isn't
+     * running yet, or drops it when it is and shouldn't be
+     */
+    pub fn set_running(&mut self, backend: Backend, on: bool, true_pose: Pose2, cfg: &Config) {
+        if on == self.is_running(backend) { return; }
+        if on {
+            self.filters.push((backend, backend.build(prior_pose(true_pose, cfg), cfg)));
+        } else {
+            self.filters.retain(|(running, _)| *running != backend);
+        }
+    }
+}
+
+fn ekf_in(filters: &[(Backend, Box<dyn SlamFilter>)]) -> &EkfSlam {
+    filters.iter()
+        .find(|(running, _)| *running == Backend::Ekf)
+        .and_then(|(_, slam)| slam.as_ekf())
+        .expect("the EKF always runs")
+}
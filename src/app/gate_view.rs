@@ -0,0 +1,100 @@
+use macroquad::prelude::*;
+
+use crate::config::Config;
+use crate::gating::GatePoint;
+use super::plots::draw_chart;
+use super::{font_size, line_spacing};
+
+const RECOMMENDED_COLOR: Color = Color::new(0.2, 1.0, 0.4, 1.0);
+
+// chi-squared quantiles for 2 degrees of freedom, range and bearing
+const QUANTILES: [(f32, &str); 4] = [(4.61, "90%"), (5.99, "95%"), (9.21, "99%"), (18.42, "99.99%")];
+
+fn draw_label(font: &Font, text: &str, x: f32, y: f32, color: Color) {
+    draw_text_ex(text, x, y, TextParams { font: Some(font), font_size: font_size(), color, ..Default::default() });
+}
+
+/*
+ * acceptance and association error rates over the swept gate thresholds, on a
+ * log axis. The arrows (Shift for larger steps) or the mouse move a cursor to
+ * read off any threshold; the recommended one and the configured
+ * `association_gate` are marked. Esc closes
+ */
+pub async fn run(points: &[GatePoint], samples: usize, recommended: Option<GatePoint>, font: &Font, cfg: &Config) {
+    if points.is_empty() { return; }
+    let mut cursor = recommended
+        .and_then(|best| points.iter().position(|point| point.threshold == best.threshold))
+        .unwrap_or(0);
+
+    let acceptance: Vec<(f32, f32)> = points.iter().map(|point| (point.threshold.log10(), 100.0 * point.acceptance)).collect();
+    let errors: Vec<(f32, f32)> = points.iter().map(|point| (point.threshold.log10(), 100.0 * point.error_rate)).collect();
+    let (min_x, max_x) = (acceptance[0].0, acceptance[acceptance.len() - 1].0);
+
+    loop {
+        if is_key_pressed(KeyCode::Escape) { break; }
+        let step = if is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift) { 10 } else { 1 };
+        if is_key_pressed(KeyCode::Right) { cursor = (cursor + step).min(points.len() - 1); }
+        if is_key_pressed(KeyCode::Left) { cursor = cursor.saturating_sub(step); }
+
+        clear_background(Color::new(0.1, 0.1, 0.1, 1.0));
+        let (w, h) = (screen_width(), screen_height());
+        let top = Rect::new(80.0, 80.0 + 2.0 * line_spacing(), w - 120.0, h * 0.3);
+        let bottom = Rect::new(80.0, top.y + top.h + 2.0 * line_spacing(), w - 120.0, h * 0.3);
+        let to_x = |threshold: f32| top.x + (threshold.log10() - min_x) / (max_x - min_x).max(1e-6) * top.w;
+
+        // the mouse over either chart picks the nearest threshold
+        let (mouse_x, mouse_y) = mouse_position();
+        if mouse_x >= top.x && mouse_x <= top.x + top.w && mouse_y >= top.y && mouse_y <= bottom.y + bottom.h {
+            cursor = points.iter()
+                .enumerate()
+                .min_by(|a, b| (to_x(a.1.threshold) - mouse_x).abs().total_cmp(&(to_x(b.1.threshold) - mouse_x).abs()))
+                .map_or(cursor, |(i, _)| i);
+        }
+
+        draw_label(font, &format!("Gate threshold sweep over {samples} measurements of mapped landmarks (arrows or mouse to move, Esc to close)"), 20.0, 25.0, WHITE);
+        let point = points[cursor];
+        draw_label(
+            font,
+            &format!("gate {:.2}: {:.1}% accepted, {:.2}% wrong associations", point.threshold, 100.0 * point.acceptance, 100.0 * point.error_rate),
+            20.0,
+            25.0 + line_spacing(),
+            LIGHTGRAY
+        );
+        if let Some(best) = recommended {
+            draw_label(
+                font,
+                &format!("recommended {:.2} ({:.1}% accepted, {:.2}% wrong), configured {:.2}", best.threshold, 100.0 * best.acceptance, 100.0 * best.error_rate, cfg.association_gate),
+                20.0,
+                25.0 + 2.0 * line_spacing(),
+                RECOMMENDED_COLOR
+            );
+        }
+
+        draw_chart(font, "Acceptance %", &[("", acceptance.clone(), SKYBLUE)], top);
+        draw_chart(font, "Association error %", &[("", errors.clone(), ORANGE)], bottom);
+
+        // markers across both charts, and the threshold axis under them
+        let marker = |threshold: f32, color: Color| {
+            let x = to_x(threshold);
+            if x < top.x || x > top.x + top.w { return; }
+            draw_line(x, top.y, x, top.y + top.h, 1.0, color);
+            draw_line(x, bottom.y, x, bottom.y + bottom.h, 1.0, color);
+        };
+        marker(cfg.association_gate, GRAY);
+        for (quantile, label) in QUANTILES {
+            marker(quantile, DARKGRAY);
+            draw_label(font, label, to_x(quantile) + 3.0, bottom.y + bottom.h - 5.0, DARKGRAY);
+        }
+        if let Some(best) = recommended { marker(best.threshold, RECOMMENDED_COLOR); }
+        marker(point.threshold, WHITE);
+
+        let mut decade = min_x.ceil();
+        while decade <= max_x {
+            let threshold = 10f32.powf(decade);
+            draw_label(font, &format!("{threshold}"), to_x(threshold) - 10.0, bottom.y + bottom.h + line_spacing(), GRAY);
+            decade += 1.0;
+        }
+
+        next_frame().await
+    }
+}
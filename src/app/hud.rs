@@ -1,65 +1,161 @@
 use macroquad::prelude::*;
+use crate::app::input::{Anchoring, Annotation, CovarianceEdit, Injection, Placement, Scatter, Tool};
+use crate::app::layout::{self, Panel};
+use crate::app::locale::Language;
 use crate::app::user_settings::UserSettings;
-use crate::slam::{EkfSlam, FastSlam};
-use super::{FONT_SIZE, LINE_SPACING};
+use crate::config::Config;
+use crate::consistency::{ConsistencySample, Inconsistency, RunningStat};
+use crate::events::EventLog;
+use crate::health::{Health, HealthReport};
+use crate::metrics::{LandmarkHistory, TrackStdevs};
+use crate::math::Pose2;
+use crate::mission::Mission;
+use crate::navigation::cruise::CruiseControl;
+use crate::observability::WeakDirection;
+use crate::scenario::Playback;
+use crate::simulation::Robot;
+use crate::trails::Trails;
+use crate::slam::{AdaptiveNoise, BearingOnlyEkf, EkfSlam, FastSlam, GraphSlam, MultiHypothesisEkf, RobocentricEkf, SeifSlam, SlamFilter, UkfSlam};
+use super::plots::{Series, draw_sparkline};
+use super::{font_size, line_spacing, scale};
 
+// the settings cog in the top left corner, at scale 1
 const COG_X: f32 = 20.0;
-const COG_Y: f32 = 20.0;
+const COG_Y: f32 = 35.0;
 const COG_R: f32 = 5.0;
 const COG_THICKNESS: f32 = 5.0;
 
-pub fn draw_legend(font: &Font) {
-    let right_offset = screen_width() - 115.0;
-    let top_offset = screen_height() - 20.0;
+// the readouts column: seven lines, then the SEIF sparsity pattern and its caption
+const READOUT_ROWS: f32 = 14.0;
+const SPARSITY_SIZE: f32 = 160.0;
 
+/*
+ * the top left of row `row` of a `panel` block `rows` tall, for a line `width`
+ * wide, wherever the panel is anchored
+ */
+fn panel_row(panel: Panel, row: f32, rows: f32, width: f32) -> Vec2 {
+    layout::current().row(panel, row, rows, width, line_spacing(), (screen_width(), screen_height()))
+}
 
-    let algorithms = [
+/*
+ * a line of text as row `row` of a `panel` block `rows` tall, placed by its
+ * own width so right and center anchors line it up at its end or middle
+ */
+fn draw_panel_line(font: &Font, text: &str, panel: Panel, row: f32, rows: f32, color: Color) {
+    let width = measure_text(text, Some(font), font_size(), 1.0).width;
+    let at = panel_row(panel, row, rows, width);
+    draw_text_ex(
+        text,
+        at.x,
+        at.y + 0.7 * line_spacing(),
+        TextParams {
+            font: Some(font),
+            font_size: font_size(),
+            color,
+            ..Default::default()
+        }
+    );
+}
+
+pub fn draw_legend(font: &Font, lang: Language, show_robocentric: bool, show_ukf: bool, show_mht: bool, show_bearing_only: bool, show_trails: bool) {
+    let mut algorithms = vec![
+        ("GraphSLAM", GraphSlam::COLOR),
+        ("SEIF", SeifSlam::COLOR),
         ("FastSLAM", FastSlam::COLOR),
         ("EKF-SLAM", EkfSlam::COLOR)
     ];
+    if show_robocentric { algorithms.insert(0, ("Robo EKF", RobocentricEkf::COLOR)); }
+    if show_ukf { algorithms.insert(0, ("UKF-SLAM", UkfSlam::COLOR)); }
+    if show_mht { algorithms.insert(0, ("MHT EKF", MultiHypothesisEkf::COLOR)); }
+    if show_bearing_only {
+        algorithms.insert(0, ("Bearing tri.", BearingOnlyEkf::TRIANGULATION_COLOR));
+        algorithms.insert(0, ("Bearing inv. d.", BearingOnlyEkf::INVERSE_DEPTH_COLOR));
+    }
+    if show_trails {
+        algorithms.insert(0, (lang.tr("Dead reck."), Trails::DEAD_RECKONING_COLOR));
+        algorithms.insert(0, (lang.tr("Commanded"), Trails::COMMANDED_COLOR));
+    }
 
+    // a color swatch, then the name; the EKF comes nearest the bottom
+    let swatch = 20.0 * scale();
+    let rows = algorithms.len() as f32;
     for (i, (name, color)) in algorithms.iter().enumerate() {
+        let at = panel_row(Panel::Legend, rows - 1.0 - i as f32, rows, 120.0 * scale());
+        draw_rectangle(at.x, at.y + (line_spacing() - swatch) / 2.0, swatch, swatch, *color);
         draw_text_ex(
             name,
-            right_offset,
-            top_offset - (i as f32) * LINE_SPACING,
+            at.x + 25.0 * scale(),
+            at.y + 0.7 * line_spacing(),
             TextParams {
                 font: Some(font),
-                font_size: FONT_SIZE,
+                font_size: font_size(),
                 color: LIGHTGRAY,
                 ..Default::default()
             }
         );
-        draw_rectangle(
-            right_offset - 25.0,
-            top_offset - 17.5 - (i as f32) * LINE_SPACING,
-            20.0,
-            20.0,
-            *color
-        );
     }
 }
 
 pub fn draw_settings(font: &Font, user_settings: &mut UserSettings) {
-    let offset = screen_width() / 4.0;
-    let padding = 30.0;
-
-    // panel width, height, position
-    let w = 400.0;
-    let h = 8.0 * LINE_SPACING;
-    let panel_center_x = offset + w / 2.0;
-    let panel_center_y = screen_height() / 2.0;
+    let lang = user_settings.language;
+    let line_spacing = line_spacing();
+    let padding = 30.0 * scale();
 
     // text
     let mut text = [
-        ("EKF-SLAM State", &mut user_settings.show_ekf_state),
-        ("FastSLAM State", &mut user_settings.show_fast_state),
-        ("GraphSLAM State", &mut user_settings.show_graph_state),
-        ("EKF-SLAM landmarks", &mut user_settings.show_ekf_landmarks),
-        ("FastSLAM landmarks", &mut user_settings.show_fast_landmarks),
-        ("GraphSLAM landmarks", &mut user_settings.show_graph_landmarks),
+        (lang.tr("EKF-SLAM State"), &mut user_settings.show_ekf_state),
+        (lang.tr("FastSLAM State"), &mut user_settings.show_fast_state),
+        (lang.tr("GraphSLAM State"), &mut user_settings.show_graph_state),
+        (lang.tr("SEIF State"), &mut user_settings.show_seif_state),
+        (lang.tr("EKF-SLAM landmarks"), &mut user_settings.show_ekf_landmarks),
+        (lang.tr("FastSLAM landmarks"), &mut user_settings.show_fast_landmarks),
+        (lang.tr("GraphSLAM landmarks"), &mut user_settings.show_graph_landmarks),
+        (lang.tr("SEIF landmarks"), &mut user_settings.show_seif_landmarks),
+        (lang.tr("FastSLAM particles"), &mut user_settings.show_fast_particles),
+        (lang.tr("SEIF sparsity"), &mut user_settings.show_seif_sparsity),
+        (lang.tr("Weak directions"), &mut user_settings.show_weak_directions),
+        (lang.tr("Track uncertainty"), &mut user_settings.show_track_uncertainty),
+        (lang.tr("Motion gauges"), &mut user_settings.show_motion_gauges),
+        (lang.tr("Robocentric EKF"), &mut user_settings.robocentric_ekf),
+        (lang.tr("UKF-SLAM"), &mut user_settings.ukf_slam),
+        (lang.tr("FEJ EKF"), &mut user_settings.fej_ekf),
+        (lang.tr("Adaptive noise"), &mut user_settings.adaptive_noise),
+        (lang.tr("MHT EKF (no ids)"), &mut user_settings.mht_ekf),
+        (lang.tr("Bearing-only EKFs"), &mut user_settings.bearing_only_ekfs),
+        (lang.tr("Uncertainty colors"), &mut user_settings.color_by_uncertainty),
+        (lang.tr("Cov. ellipses"), &mut user_settings.show_covariance_ellipses),
+        (lang.tr("Ghost trails"), &mut user_settings.show_trails),
+        (lang.tr("Overlay trails"), &mut user_settings.overlay_trails),
+        (lang.tr("Uncertainty tube"), &mut user_settings.show_uncertainty_tube),
+        (lang.tr("Health light"), &mut user_settings.show_health),
+        (lang.tr("Coverage map"), &mut user_settings.show_coverage_map),
+        (lang.tr("Observation heatmap"), &mut user_settings.show_observation_heatmap),
+        (lang.tr("Lidar scan"), &mut user_settings.show_lidar),
+        (lang.tr("Sensing wedge"), &mut user_settings.show_sensor_wedge),
+        (lang.tr("Believed walls"), &mut user_settings.show_believed_walls),
+        (lang.tr("DWA avoidance"), &mut user_settings.dwa_avoidance),
+        (lang.tr("Stuck ZUPT"), &mut user_settings.contact_zupt),
+        (lang.tr("Parked ZUPT"), &mut user_settings.parked_zupt),
+        (lang.tr("Predict delayed cmds"), &mut user_settings.predict_delayed_commands),
+        (lang.tr("Robot-centric view"), &mut user_settings.robot_centric_view),
+        (lang.tr("Split view"), &mut user_settings.split_view),
+        (lang.tr("Link cameras"), &mut user_settings.link_cameras),
+        (lang.tr("Idle throttling"), &mut user_settings.idle_throttling),
     ];
 
+    // panel width, height, position; entries fill columns of `rows` and the panel grows sideways,
+    // with the layout section under them
+    let rows = 12;
+    let column_width = 270.0 * scale();
+    let columns = text.len().div_ceil(rows);
+    let layout_rows = Panel::ALL.len().div_ceil(columns);
+    let w = columns as f32 * column_width + padding;
+    let h = (rows.min(text.len()) as f32 + 3.5 + layout_rows as f32) * line_spacing;
+    let panel_center_x = screen_width() / 2.0;
+    let panel_center_y = screen_height() / 2.0;
+    let panel_left = panel_center_x - w / 2.0;
+    let panel_top = panel_center_y - h / 2.0;
+
     draw_rectangle_ex(
         panel_center_x,
         panel_center_y,
@@ -72,26 +168,30 @@ pub fn draw_settings(font: &Font, user_settings: &mut UserSettings) {
         }
     );
 
+    // the title also says how to switch the language, in the current one's own name
+    let title = format!("{}  (L: {})", lang.tr("Visibility Menu"), lang.name());
+    let title_width = measure_text(&title, Some(font), font_size(), 1.0).width;
     draw_text_ex(
-        "Visibility Menu",
-        offset + padding + 80.0,
-        panel_center_y - 2.625 * LINE_SPACING - 7.5,
+        &title,
+        panel_center_x - title_width / 2.0,
+        panel_top + 1.375 * line_spacing - 7.5 * scale(),
         TextParams {
             font: Some(font),
-            font_size: FONT_SIZE,
+            font_size: font_size(),
             ..Default::default()
         }
     );
 
+    let (mouse_x, mouse_y) = mouse_position();
+    let checkbox_size = 20.0 * scale();
     for (i, (label, value)) in text.iter_mut().enumerate() {
         // checkbox position
-        let checkbox_x = offset + padding;
-        let checkbox_y = panel_center_y - (2.0 - i as f32) * LINE_SPACING;
-        let checkbox_size = 20.0;
+        let (column, row) = (i / rows, i % rows);
+        let checkbox_x = panel_left + padding + column as f32 * column_width;
+        let checkbox_y = panel_top + (2.0 + row as f32) * line_spacing;
 
         // check if hovered
-        let (mouse_x, mouse_y) = mouse_position();
-        let is_hovered = mouse_x < panel_center_x + w / 2.0 - padding &&
+        let is_hovered = mouse_x < checkbox_x + column_width - padding &&
                          mouse_x > checkbox_x - checkbox_size / 2.0 &&
                          mouse_y < checkbox_y + checkbox_size / 2.0 &&
                          mouse_y > checkbox_y - checkbox_size / 2.0;
@@ -109,7 +209,7 @@ pub fn draw_settings(font: &Font, user_settings: &mut UserSettings) {
                 checkbox_y + checkbox_size / 4.0,
                 checkbox_x + checkbox_size / 4.0,
                 checkbox_y - checkbox_size / 4.0,
-                2.0,
+                2.0 * scale(),
                 color
             );
         }
@@ -118,8 +218,8 @@ pub fn draw_settings(font: &Font, user_settings: &mut UserSettings) {
             checkbox_y,
             checkbox_size,
             checkbox_size,
-            2.0,
-            DrawRectangleParams { 
+            2.0 * scale(),
+            DrawRectangleParams {
                 offset: vec2(0.5, 0.5),
                 color,
                 ..Default::default()
@@ -127,33 +227,172 @@ pub fn draw_settings(font: &Font, user_settings: &mut UserSettings) {
         );
         draw_text_ex(
             label,
-            offset + padding + checkbox_size,
-            panel_center_y - (1.5 - i as f32) * LINE_SPACING - 7.5,
+            checkbox_x + checkbox_size,
+            panel_top + (2.5 + row as f32) * line_spacing - 7.5 * scale(),
             TextParams {
                 font: Some(font),
-                font_size: FONT_SIZE,
+                font_size: font_size(),
                 color,
                 ..Default::default()
             }
         );
 
     }
+
+    // the layout: the scale, and a click on a panel moves it on to the next anchor
+    let layout_top = panel_top + (rows.min(text.len()) as f32 + 2.5) * line_spacing;
+    let hud_layout = &mut user_settings.hud_layout;
+    draw_text_ex(
+        &format!("{} x{:.2}  {}", lang.tr("UI scale"), hud_layout.scale, lang.tr("(Ctrl + and Ctrl - resize, Ctrl 0 resets; click a panel to move it)")),
+        panel_left + padding,
+        layout_top,
+        TextParams {
+            font: Some(font),
+            font_size: font_size(),
+            color: GRAY,
+            ..Default::default()
+        }
+    );
+    for (i, panel) in Panel::ALL.into_iter().enumerate() {
+        let (column, row) = (i % columns, i / columns);
+        let x = panel_left + padding + column as f32 * column_width;
+        let y = layout_top + (1.0 + row as f32) * line_spacing;
+
+        let is_hovered = mouse_x > x && mouse_x < x + column_width - padding &&
+                         mouse_y > y - 0.7 * line_spacing && mouse_y < y + 0.3 * line_spacing;
+        if is_hovered && is_mouse_button_released(MouseButton::Left) {
+            hud_layout.set_anchor(panel, hud_layout.anchor(panel).next());
+        }
+
+        draw_text_ex(
+            &format!("{}: {}", lang.tr(panel.label()), lang.tr(hud_layout.anchor(panel).label())),
+            x,
+            y,
+            TextParams {
+                font: Some(font),
+                font_size: font_size(),
+                color: if is_hovered { WHITE } else { LIGHTGRAY },
+                ..Default::default()
+            }
+        );
+    }
+}
+
+/*
+ * which state blocks the SEIF information matrix links, robot first; the
+ * robot's row and column shows the active landmarks
+ */
+pub fn draw_seif_sparsity(font: &Font, lang: Language, seif: &dyn SlamFilter) {
+    let size = SPARSITY_SIZE * scale();
+    let at = panel_row(Panel::Readouts, 7.0, READOUT_ROWS, size);
+    let (left_offset, top_offset) = (at.x, at.y);
+
+    let Some((ids, pattern)) = seif.link_pattern() else { return; };
+    let active = pattern[0][1..].iter().filter(|&&linked| linked).count();
+    let cell = size / pattern.len() as f32;
+
+    draw_rectangle(left_offset, top_offset, size, size, Color::new(0.05, 0.05, 0.05, 0.9));
+    for (row, links) in pattern.iter().enumerate() {
+        for (column, &linked) in links.iter().enumerate() {
+            if !linked { continue; }
+            let color = if row == 0 || column == 0 { seif.color() } else { LIGHTGRAY };
+            draw_rectangle(left_offset + column as f32 * cell, top_offset + row as f32 * cell, cell, cell, color);
+        }
+    }
+    draw_rectangle_lines(left_offset, top_offset, size, size, 1.0, DARKGRAY);
+
+    draw_text_ex(
+        &format!("{}: {} {}, {} {}", lang.tr("SEIF links"), ids.len(), lang.tr("landmarks"), active, lang.tr("active")),
+        left_offset,
+        top_offset + size + 0.75 * line_spacing(),
+        TextParams {
+            font: Some(font),
+            font_size: font_size(),
+            color: LIGHTGRAY,
+            ..Default::default()
+        }
+    );
+}
+
+/*
+ * size of the EKF's least constrained direction and how much of it is global
+ * translation or rotation, under the cruise line
+ */
+pub fn draw_weak_direction(font: &Font, lang: Language, direction: &WeakDirection) {
+    let text = format!(
+        "{}: {} {:.1}, {:.0}% {}, {:.0}% {}",
+        lang.tr("Weakest direction"), lang.tr("stdev"), direction.stdev,
+        100.0 * direction.translation_share, lang.tr("translation"),
+        100.0 * direction.rotation_share, lang.tr("rotation")
+    );
+    draw_panel_line(font, &text, Panel::Readouts, 2.0, READOUT_ROWS, LIGHTGRAY);
+}
+
+/*
+ * the EKF's position stdevs along and across its heading
+ */
+pub fn draw_track_uncertainty(font: &Font, lang: Language, track: TrackStdevs) {
+    let text = format!("{}: {} {:.1}, {} {:.1}", lang.tr("Position stdev"), lang.tr("along-track"), track.along, lang.tr("cross-track"), track.cross);
+    draw_panel_line(font, &text, Panel::Readouts, 6.0, READOUT_ROWS, LIGHTGRAY);
+}
+
+/*
+ * measurement noise the EKF has learned, next to the configured one
+ */
+pub fn draw_adaptive_noise(font: &Font, lang: Language, adaptive: &AdaptiveNoise, configured: (f32, f32)) {
+    let (range, bearing) = adaptive.stdevs();
+    let text = format!(
+        "{}: {} {:.2} ({} {:.2}), {} {:.3} ({} {:.3})",
+        lang.tr("Adapted R"), lang.tr("range"), range, lang.tr("set"), configured.0,
+        lang.tr("bearing"), bearing, lang.tr("set"), configured.1
+    );
+    draw_panel_line(font, &text, Panel::Readouts, 3.0, READOUT_ROWS, LIGHTGRAY);
+}
+
+/*
+ * live association hypotheses of the MHT EKF and how often its best one
+ * disagrees with the true landmark ids
+ */
+pub fn draw_hypotheses(font: &Font, lang: Language, mht: &dyn SlamFilter, max_hypotheses: usize) {
+    let hypotheses = mht.hypotheses();
+    let Some(best) = hypotheses.first() else { return; };
+    let text = format!(
+        "{}: {}/{}, {} {} {} {}",
+        lang.tr("Hypotheses"), hypotheses.len(), max_hypotheses,
+        lang.tr("wrong associations"), best.mistakes, lang.tr("of"), best.associations
+    );
+    draw_panel_line(font, &text, Panel::Readouts, 4.0, READOUT_ROWS, LIGHTGRAY);
+}
+
+/*
+ * commanded velocities next to the ones the wheels are executing, while
+ * control latency or a command rate holds them apart
+ */
+pub fn draw_actuation(font: &Font, lang: Language, robot: &Robot) {
+    let (linear, angular) = robot.commanded_velocity();
+    let text = format!(
+        "{} v {:.1} w {:.2}, {} v {:.1} w {:.2}",
+        lang.tr("Commanded"), linear, angular, lang.tr("executed"), robot.linear_velocity, robot.angular_velocity
+    );
+    draw_panel_line(font, &text, Panel::Readouts, 5.0, READOUT_ROWS, LIGHTGRAY);
 }
 
 pub fn draw_cog() {
-    let effective_radius = COG_R + COG_THICKNESS;
+    let (cog_x, cog_y) = (COG_X * scale(), COG_Y * scale());
+    let thickness = COG_THICKNESS * scale();
+    let effective_radius = COG_R * scale() + thickness;
     let color = if is_cog_hovered() { DARKGRAY } else { LIGHTGRAY };
-    
-    draw_circle_lines(COG_X, COG_Y, COG_R, COG_THICKNESS, color);
+
+    draw_circle_lines(cog_x, cog_y, COG_R * scale(), thickness, color);
 
     for i in 0..8 {
         let angle = std::f32::consts::FRAC_PI_4 * (i as f32);
         draw_rectangle_ex(
-            COG_X + effective_radius * angle.cos(),
-            COG_Y + effective_radius * angle.sin(),
-            COG_THICKNESS,
-            COG_THICKNESS,
-            DrawRectangleParams { 
+            cog_x + effective_radius * angle.cos(),
+            cog_y + effective_radius * angle.sin(),
+            thickness,
+            thickness,
+            DrawRectangleParams {
                 offset: vec2(0.5, 0.5),
                 rotation: angle,
                 color
@@ -164,11 +403,445 @@ pub fn draw_cog() {
 
 pub fn is_cog_hovered() -> bool {
     let (mouse_x, mouse_y) = mouse_position();
-    let effective_radius = COG_R + COG_THICKNESS;
+    let (cog_x, cog_y) = (COG_X * scale(), COG_Y * scale());
+    let effective_radius = (COG_R + COG_THICKNESS) * scale();
+
+    mouse_x > cog_x - effective_radius &&
+    mouse_y > cog_y - effective_radius &&
+    mouse_x < cog_x + effective_radius &&
+    mouse_y < cog_y + effective_radius
+}
+
+pub fn draw_tool(font: &Font, lang: Language, tool: Tool, snap_to_grid: bool, scatter: &Scatter) {
+    let detail = match tool {
+        Tool::Obstruction if snap_to_grid => format!(" [{}]", lang.tr("snap")),
+        Tool::Scatter => format!(
+            " [{} {:.2}, {} {:.0}]",
+            lang.tr("density"), scatter.density, lang.tr("separation"), scatter.separation
+        ),
+        _ => String::new(),
+    };
+    let text = format!(
+        "{}: {}{} {}",
+        lang.tr("Tool"), lang.tr(tool.label()), detail,
+        lang.tr("(1 obstruction, 2 goal, 3 navigate, 4 inject, 5 select, 6 anchor, 7 dropout, 8 scatter, 9 inspect)")
+    );
+    draw_panel_line(font, &text, Panel::Readouts, 0.0, READOUT_ROWS, LIGHTGRAY);
+}
+
+/*
+ * cruise control setpoints, under the tool line
+ */
+pub fn draw_cruise(font: &Font, lang: Language, cruise: &CruiseControl) {
+    let text = format!(
+        "{}: {} {:.0}, {} {:.0} {} ({})",
+        lang.tr("Cruise"), lang.tr("speed"), cruise.speed,
+        lang.tr("heading"), cruise.heading.to_degrees(), lang.tr("deg"), lang.tr("space to release")
+    );
+    draw_panel_line(font, &text, Panel::Readouts, 1.0, READOUT_ROWS, Color::new(0.0, 0.8, 1.0, 1.0));
+}
+
+/*
+ * mission progress panel, in the top right corner by default
+ */
+pub fn draw_mission(font: &Font, lang: Language, mission: &Mission) {
+    if mission.goals.is_empty() { return; }
+
+    let status = if mission.is_active() { "in progress" } else { "complete" };
+    let mut lines = vec![
+        format!("{}: {}", lang.tr("Mission"), lang.tr(status)),
+        format!("{}: {}/{}", lang.tr("Goals"), mission.completed_count(), mission.goals.len()),
+        format!("{}: {:.1} s", lang.tr("Time"), mission.elapsed_time),
+        format!("{}: {:.0}", lang.tr("Distance"), mission.distance_traveled),
+    ];
+    if let Some(last) = mission.goals.iter().rev().find_map(|goal| goal.reached_at) {
+        lines.push(format!("{}: {:.1} s", lang.tr("Last goal at"), last));
+    }
+
+    // one left edge for the whole panel, the width of its widest line
+    let width = lines.iter().map(|line| measure_text(line, Some(font), font_size(), 1.0).width).fold(0.0, f32::max);
+    for (i, line) in lines.iter().enumerate() {
+        let at = panel_row(Panel::Mission, i as f32, lines.len() as f32, width);
+        draw_text_ex(
+            line,
+            at.x,
+            at.y + 0.7 * line_spacing(),
+            TextParams {
+                font: Some(font),
+                font_size: font_size(),
+                color: LIGHTGRAY,
+                ..Default::default()
+            }
+        );
+    }
+}
+
+/*
+ * lines of the reports panel, which keeps `below` rows clear for the legend
+ * or other reports that share its edge
+ */
+fn draw_report(font: &Font, lines: &[String], below: f32) {
+    let width = lines.iter().map(|line| measure_text(line, Some(font), font_size(), 1.0).width).fold(0.0, f32::max);
+    let rows = lines.len() as f32 + below;
+    for (i, line) in lines.iter().enumerate() {
+        let at = panel_row(Panel::Reports, i as f32, rows, width);
+        draw_text_ex(
+            line,
+            at.x,
+            at.y + 0.7 * line_spacing(),
+            TextParams {
+                font: Some(font),
+                font_size: font_size(),
+                color: LIGHTGRAY,
+                ..Default::default()
+            }
+        );
+    }
+}
+
+/*
+ * the last injected measurement and its effect, above the legend
+ */
+pub fn draw_injection(font: &Font, lang: Language, injection: &Injection) {
+    let Pose2 { x: dx, y: dy, theta: dtheta } = injection.pose_change;
+    let lines = [
+        format!("{} {}", lang.tr("Injected landmark"), injection.observation.id),
+        format!(
+            "{} {:.1}, {} {:.1} {}",
+            lang.tr("Range"), injection.observation.range,
+            lang.tr("bearing"), injection.observation.bearing.to_degrees(), lang.tr("deg")
+        ),
+        format!("{} {:.2}, {:.2}, {:.2} {}", lang.tr("Pose moved"), dx, dy, dtheta.to_degrees(), lang.tr("deg")),
+        format!("{} {:.3} -> {:.3}", lang.tr("Pose cov trace"), injection.trace_before, injection.trace_after),
+    ];
+    draw_report(font, &lines, 5.0);
+}
+
+/*
+ * the last anchored landmark and how much it shrank the EKF's total uncertainty,
+ * above the legend
+ */
+pub fn draw_anchoring(font: &Font, lang: Language, anchoring: &Anchoring) {
+    let lines = [
+        format!("{} {}", lang.tr("Anchored landmark"), anchoring.id),
+        format!("{} {:.1} -> {:.1}", lang.tr("State cov trace"), anchoring.trace_before, anchoring.trace_after),
+    ];
+    draw_report(font, &lines, 5.0);
+}
+
+/*
+ * the inspected landmark's estimate history, above the covariance edit readout:
+ * sparklines of its covariance trace and position over the kept frames, ticked
+ * where it moved most in one frame
+ */
+pub fn draw_landmark_inspector(font: &Font, lang: Language, id: usize, history: &LandmarkHistory) {
+    let rows = 8.0;
+    let below = 12.0;
+    let width = 300.0 * scale();
+    let label_width = 110.0 * scale();
+    let text = |text: &str, at: Vec2, color: Color| {
+        draw_text_ex(text, at.x, at.y + 0.7 * line_spacing(), TextParams { font: Some(font), font_size: font_size(), color, ..Default::default() });
+    };
+
+    let Some(latest) = history.samples(id).and_then(|samples| samples.back().copied()) else {
+        text(&format!("{} {id}: {}", lang.tr("Landmark"), lang.tr("no history")), panel_row(Panel::Reports, rows - 1.0, rows + below, width), LIGHTGRAY);
+        return;
+    };
+    text(&format!("{} {id}", lang.tr("Landmark")), panel_row(Panel::Reports, 0.0, rows + below, width), WHITE);
 
-    mouse_x > COG_X - effective_radius &&
-    mouse_y > COG_Y - effective_radius &&
-    mouse_x < COG_X + effective_radius && 
-    mouse_y < COG_Y + effective_radius
+    let jump = history.largest_jump(id);
+    let labels = [format!("{} {:.2}", lang.tr("trace"), latest.trace), format!("x {:.1}", latest.x), format!("y {:.1}", latest.y)];
+    let lines: [Series; 3] = [
+        (&labels[0], history.series(id, |sample| sample.trace), ORANGE),
+        (&labels[1], history.series(id, |sample| sample.x), EkfSlam::COLOR),
+        (&labels[2], history.series(id, |sample| sample.y), EkfSlam::COLOR),
+    ];
+    for (i, (label, points, color)) in lines.iter().enumerate() {
+        let at = panel_row(Panel::Reports, 1.0 + 2.0 * i as f32, rows + below, width);
+        text(label, at + vec2(0.0, 0.5 * line_spacing()), LIGHTGRAY);
+        let area = Rect::new(at.x + label_width, at.y + 0.2 * line_spacing(), width - label_width, 1.6 * line_spacing());
+        draw_rectangle(area.x, area.y, area.w, area.h, Color::new(0.0, 0.0, 0.0, 0.4));
+        draw_sparkline(points, area, *color);
+
+        if let (Some((time, _)), Some(&(start, _)), Some(&(end, _))) = (jump, points.first(), points.last()) && end > start {
+            let x = area.x + (time - start) / (end - start) * area.w;
+            draw_line(x, area.y, x, area.y + area.h, 1.0, RED);
+        }
+    }
+
+    if let Some((time, distance)) = jump {
+        text(&format!("{} {distance:.2} {} t = {time:.1} s", lang.tr("Largest jump"), lang.tr("at")), panel_row(Panel::Reports, 7.0, rows + below, width), RED);
+    }
+}
+
+/*
+ * above the injection and anchoring readouts, which it can share the pause with
+ */
+pub fn draw_covariance_edit(font: &Font, lang: Language, edit: &CovarianceEdit) {
+    let target = match edit.landmark {
+        Some(id) => format!("{} {id}", lang.tr("landmark")),
+        None => lang.tr("pose").to_string(),
+    };
+    let lines = [
+        format!("{}, {target}: x{:.3}", lang.tr("EKF covariance"), edit.factor),
+        format!("{} {:.2} -> {:.2}", lang.tr("Block trace"), edit.trace_before, edit.trace_after),
+    ];
+    draw_report(font, &lines, 9.0);
+}
+
+/*
+ * the pose being typed in, centered at the top; red while it doesn't parse
+ */
+pub fn draw_placement(font: &Font, lang: Language, placement: &Placement) {
+    let lines = [
+        (format!("{}: {}_", lang.tr("Place robot (x y heading)"), placement.text), if placement.pose().is_some() { WHITE } else { RED }),
+        (lang.tr("Arrows/Q/E nudge, Shift finer, Enter place, Esc cancel").to_string(), LIGHTGRAY),
+    ];
+
+    for (i, (line, color)) in lines.iter().enumerate() {
+        let width = measure_text(line, Some(font), font_size(), 1.0).width;
+        draw_text_ex(
+            line,
+            (screen_width() - width) / 2.0,
+            20.0 * scale() + (i as f32 + 1.0) * line_spacing(),
+            TextParams {
+                font: Some(font),
+                font_size: font_size(),
+                color: *color,
+                ..Default::default()
+            }
+        );
+    }
+}
+
+/*
+ * the bookmark note being typed, centered at the top
+ */
+pub fn draw_annotation(font: &Font, lang: Language, annotation: &Annotation) {
+    let lines = [
+        (format!("{} {:.1} s, {}: {}_", lang.tr("Bookmark at"), annotation.time, lang.tr("note"), annotation.text), WHITE),
+        (lang.tr("Enter drop (the note is optional), Esc cancel").to_string(), LIGHTGRAY),
+    ];
+
+    for (i, (line, color)) in lines.iter().enumerate() {
+        let width = measure_text(line, Some(font), font_size(), 1.0).width;
+        draw_text_ex(
+            line,
+            (screen_width() - width) / 2.0,
+            20.0 * scale() + (i as f32 + 1.0) * line_spacing(),
+            TextParams {
+                font: Some(font),
+                font_size: font_size(),
+                color: *color,
+                ..Default::default()
+            }
+        );
+    }
+}
+
+/*
+ * recent events, newest at the bottom left
+ */
+pub fn draw_events(font: &Font, lang: Language, event_log: &EventLog, now: f32, window: f32) {
+    for (i, event) in event_log.recent(now, window).take(5).enumerate() {
+        let alpha = 1.0 - (now - event.time) / window;
+        let text = format!("{:.1} s  {} {}", event.time, lang.tr(event.kind.label()), event.detail);
+        draw_panel_line(font, &text, Panel::Events, 4.0 - i as f32, 5.0, Color::new(1.0, 0.6, 0.3, alpha));
+    }
+}
+
+/*
+ * metrics from the analytics plugins, above the events; plugins name their
+ * own metrics, so they are shown as they come
+ */
+pub fn draw_analytics(font: &Font, metrics: &[(String, f32)]) {
+    let rows = metrics.len() as f32 + 6.0;
+    for (i, (name, value)) in metrics.iter().enumerate() {
+        draw_panel_line(font, &format!("{name} {value:.2}"), Panel::Events, i as f32, rows, ORANGE);
+    }
+}
+
+pub fn draw_restore_prompt(font: &Font, lang: Language, sim_time: f32) {
+    let lines = [
+        lang.tr("A recovery point from the last session was found.").to_string(),
+        format!("t = {:.1} s, {}", sim_time, lang.tr("restore it? (Y / N)")),
+    ];
+
+    for (i, line) in lines.iter().enumerate() {
+        draw_text_ex(
+            line,
+            screen_width() / 2.0 - 220.0 * scale(),
+            screen_height() / 2.0 + (i as f32) * line_spacing(),
+            TextParams {
+                font: Some(font),
+                font_size: font_size(),
+                color: LIGHTGRAY,
+                ..Default::default()
+            }
+        );
+    }
 }
 
+/*
+ * bars at the bottom center, filled from zero to the velocity the wheels
+ * settled at after drag and caps, with ticks for the commanded velocity (white)
+ * and the noisy one the pose actually moved with (yellow); below them the
+ * acceleration inputs of the frame. Each bar spans its limit either way
+ */
+pub fn draw_motion_gauges(font: &Font, lang: Language, robot: &Robot, cfg: &Config) {
+    let (commanded_linear, commanded_angular) = robot.commanded_velocity();
+    let (noisy_linear, noisy_angular) = robot.noisy_velocity();
+    let gauges = [
+        (lang.tr("speed"), cfg.max_linear_speed, robot.linear_velocity, Some((commanded_linear, noisy_linear))),
+        (lang.tr("turn rate"), cfg.max_angular_speed, robot.angular_velocity, Some((commanded_angular, noisy_angular))),
+        (lang.tr("accel."), cfg.linear_acc, robot.acceleration.0, None),
+        (lang.tr("ang. accel."), cfg.angular_acc, robot.acceleration.1, None),
+    ];
+
+    // a legend row over the gauges, and room under them for the playback line
+    let label_width = 130.0 * scale();
+    let width = 240.0 * scale();
+    let height = 12.0 * scale();
+    let rows = gauges.len() as f32 + 2.5;
+    let bar_x = |at: Vec2| at.x + label_width;
+    let to_x = |left: f32, value: f32, limit: f32| left + width / 2.0 * (1.0 + (value / limit).clamp(-1.0, 1.0));
+
+    let legend = [(lang.tr("actual"), SKYBLUE), (lang.tr("commanded"), WHITE), (lang.tr("noisy"), YELLOW)];
+    let at = panel_row(Panel::Gauges, 0.0, rows, label_width + width);
+    let mut x = bar_x(at);
+    for (text, color) in legend {
+        let dimensions = draw_text_ex(
+            text,
+            x,
+            at.y + 0.7 * line_spacing(),
+            TextParams {
+                font: Some(font),
+                font_size: font_size(),
+                color,
+                ..Default::default()
+            }
+        );
+        x += dimensions.width + 15.0 * scale();
+    }
+
+    for (i, (label, limit, value, ticks)) in gauges.into_iter().enumerate() {
+        let at = panel_row(Panel::Gauges, 1.0 + i as f32, rows, label_width + width);
+        let (left, y) = (bar_x(at), at.y + (line_spacing() - height) / 2.0);
+        draw_text_ex(
+            &format!("{label} {value:.2}"),
+            at.x,
+            at.y + 0.7 * line_spacing(),
+            TextParams {
+                font: Some(font),
+                font_size: font_size(),
+                color: LIGHTGRAY,
+                ..Default::default()
+            }
+        );
+
+        draw_rectangle(left, y, width, height, Color::new(0.2, 0.2, 0.2, 0.8));
+        let (from, to) = (to_x(left, 0.0, limit), to_x(left, value, limit));
+        draw_rectangle(from.min(to), y, (to - from).abs(), height, if ticks.is_some() { SKYBLUE } else { ORANGE });
+        draw_line(from, y - 2.0, from, y + height + 2.0, 1.0, GRAY);
+
+        if let Some((commanded, noisy)) = ticks {
+            let commanded = to_x(left, commanded, limit);
+            draw_line(commanded, y - 3.0, commanded, y + height + 3.0, 2.0, WHITE);
+            let noisy = to_x(left, noisy, limit);
+            draw_line(noisy, y - 3.0, noisy, y + height + 3.0, 2.0, YELLOW);
+        }
+    }
+}
+
+/*
+ * scenario playback progress at the bottom center; while paused the queued
+ * landmark events wait for simulated time to reach them
+ */
+pub fn draw_playback(font: &Font, lang: Language, playback: &Playback, paused: bool) {
+    let queued = playback.pending_events().len();
+    let held = if paused { format!(" ({})", lang.tr("held while paused")) } else { String::new() };
+    let text = format!(
+        "{}: t = {:.1} / {:.1} s, {queued} {}{held}",
+        playback.scenario.name,
+        playback.time(),
+        playback.scenario.duration(),
+        lang.tr(if queued == 1 { "event queued" } else { "events queued" }),
+    );
+    draw_panel_line(font, &text, Panel::Playback, 0.0, 1.0, LIGHTGRAY);
+}
+
+/*
+ * localization health as a traffic light at the top, the lit lamp followed by
+ * the numbers behind it
+ */
+pub fn draw_health(font: &Font, lang: Language, report: &HealthReport) {
+    let health = report.health();
+    let radius = 6.0 * scale();
+    let y = 20.0 * scale();
+    let mut x = screen_width() / 2.0 + 40.0 * scale();
+    draw_rectangle(x - 2.0 * radius, y - 2.0 * radius, 7.0 * radius * Health::ALL.len() as f32 / 2.0 + radius, 4.0 * radius, Color::new(0.05, 0.05, 0.05, 0.8));
+    for lamp in Health::ALL {
+        let color = lamp.color();
+        draw_circle(x, y, radius, if lamp == health { color } else { Color { a: 0.15, ..color } });
+        x += 3.0 * radius;
+    }
+
+    let nis = report.mean_nis.map_or("-".to_string(), |nis| format!("{nis:.1}"));
+    let text = format!(
+        "{}: {} (NIS {nis}, {} {:.1}, {:.1} {})",
+        lang.tr("Localization"), lang.tr(health.label()), lang.tr("stdev"), report.position_stdev, report.measurement_rate, lang.tr("meas./frame")
+    );
+    draw_text_ex(
+        &text,
+        x,
+        y + 7.5 * scale(),
+        TextParams {
+            font: Some(font),
+            font_size: font_size(),
+            color: health.color(),
+            ..Default::default()
+        }
+    );
+}
+
+/*
+ * warning that the EKF's running NEES or NIS has stayed outside its bounds,
+ * under the health light
+ */
+pub fn draw_consistency_warning(font: &Font, lang: Language, warning: Inconsistency, sample: &ConsistencySample) {
+    let stat = |name: &str, stat: Option<RunningStat>| stat.map_or(String::new(), |stat| {
+        format!(", {name} {:.2} ({:.2}-{:.2})", stat.mean, stat.lower, stat.upper)
+    });
+    let text = format!(
+        "{}: {}{}{}",
+        lang.tr("EKF inconsistent"), lang.tr(warning.label()), stat("NEES", sample.nees), stat("NIS", sample.nis)
+    );
+    draw_text_ex(
+        &text,
+        screen_width() / 2.0 - 30.0 * scale(),
+        45.0 * scale(),
+        TextParams {
+            font: Some(font),
+            font_size: font_size(),
+            color: ORANGE,
+            ..Default::default()
+        }
+    );
+}
+
+pub fn draw_recording_indicator(font: &Font, lang: Language) {
+    let x = screen_width() / 2.0 - 30.0 * scale();
+    let y = 20.0 * scale();
+    draw_circle(x, y, 6.0 * scale(), RED);
+    draw_text_ex(
+        lang.tr("REC"),
+        x + 12.0 * scale(),
+        y + 7.5 * scale(),
+        TextParams {
+            font: Some(font),
+            font_size: font_size(),
+            color: RED,
+            ..Default::default()
+        }
+    );
+}
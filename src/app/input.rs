@@ -1,6 +1,201 @@
 use macroquad::prelude::*;
 use crate::config::{Config};
-use crate::simulation::{Landmark, Robot};
+use crate::events::EventKind;
+use crate::math::Pose2;
+use crate::mission::Mission;
+use crate::navigation::{Navigator, cruise::CruiseControl};
+use crate::simulation::{DropoutZone, Landmark, Observation, Robot, VisibilitySector};
+use crate::template::{self, Template};
+
+/*
+ * what a left click in the world does
+ */
+#[derive(Clone, Copy, PartialEq)]
+pub enum Tool {
+    Obstruction,
+    Goal,
+    Navigate,
+    Inject,
+    Select,
+    Anchor,
+    Dropout,
+    Scatter,
+    Inspect,
+}
+
+/*
+ * select tool state: the area being dragged or last selected, and the
+ * clipboard that copies and templates are pasted from
+ */
+pub struct Selection {
+    pub drag_start: Option<Vec2>,
+    pub area: Option<Rect>,
+    pub clipboard: Option<Template>,
+    next_template: usize,
+}
+
+impl Selection {
+    pub fn new() -> Self {
+        Self { drag_start: None, area: None, clipboard: None, next_template: 0 }
+    }
+}
+
+/*
+ * scatter tool state: the area being dragged and the density and minimum
+ * separation the next one is filled with
+ */
+pub struct Scatter {
+    pub drag_start: Option<Vec2>,
+    pub area: Option<Rect>,
+    pub density: f32,
+    pub separation: f32,
+}
+
+impl Scatter {
+    pub fn new(cfg: &Config) -> Self {
+        Self { drag_start: None, area: None, density: cfg.scatter_density, separation: cfg.scatter_separation }
+    }
+}
+
+/*
+ * a hand-made measurement and what it did to the EKF pose estimate
+ */
+pub struct Injection {
+    pub observation: Observation,
+    pub pose_change: Pose2,
+    pub trace_before: f32,
+    pub trace_after: f32,
+}
+
+/*
+ * a landmark pinned as the EKF's gauge anchor and what it did to the total uncertainty
+ */
+pub struct Anchoring {
+    pub id: usize,
+    pub trace_before: f32,
+    pub trace_after: f32,
+}
+
+/*
+ * a what-if scaling of the EKF's pose (`landmark` None) or landmark covariance
+ * and what it did to that block's uncertainty
+ */
+pub struct CovarianceEdit {
+    pub landmark: Option<usize>,
+    pub factor: f32, // of every edit to the same block in a row
+    pub trace_before: f32,
+    pub trace_after: f32,
+}
+
+/*
+ * typed ground-truth pose, "x y heading" with the heading in degrees
+ */
+pub struct Placement {
+    pub text: String,
+}
+
+pub enum PlacementInput {
+    Editing,
+    Cancel,
+    Commit(Pose2),
+}
+
+impl Placement {
+    pub fn new(robot: &Robot) -> Self {
+        Self { text: Self::format(robot.x, robot.y, robot.theta) }
+    }
+
+    /*
+     * the typed pose, heading in radians, if the text is three numbers
+     */
+    pub fn pose(&self) -> Option<Pose2> {
+        let numbers: Vec<f32> = self.text.split([' ', ',']).filter(|part| !part.is_empty())
+            .map(|part| part.parse().ok())
+            .collect::<Option<_>>()?;
+        match numbers[..] {
+            [x, y, heading] => Some(Pose2::new(x, y, heading.to_radians())),
+            _ => None,
+        }
+    }
+
+    fn format(x: f32, y: f32, theta: f32) -> String {
+        format!("{x:.1} {y:.1} {:.1}", theta.to_degrees())
+    }
+}
+
+/*
+ * a bookmark being dropped at `time`, waiting for its optional note
+ */
+pub struct Annotation {
+    pub time: f32,
+    pub text: String,
+}
+
+pub enum AnnotationInput {
+    Editing,
+    Cancel,
+    Commit,
+}
+
+impl Tool {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Tool::Obstruction => "Obstruction",
+            Tool::Goal => "Goal",
+            Tool::Navigate => "Navigate",
+            Tool::Inject => "Inject",
+            Tool::Select => "Select",
+            Tool::Anchor => "Anchor",
+            Tool::Dropout => "Dropout",
+            Tool::Scatter => "Scatter",
+            Tool::Inspect => "Inspect",
+        }
+    }
+}
+
+pub fn tool_input(tool: &mut Tool) {
+    if is_key_pressed(KeyCode::Key1) { *tool = Tool::Obstruction; }
+    if is_key_pressed(KeyCode::Key2) { *tool = Tool::Goal; }
+    if is_key_pressed(KeyCode::Key3) { *tool = Tool::Navigate; }
+    if is_key_pressed(KeyCode::Key4) { *tool = Tool::Inject; }
+    if is_key_pressed(KeyCode::Key5) { *tool = Tool::Select; }
+    if is_key_pressed(KeyCode::Key6) { *tool = Tool::Anchor; }
+    if is_key_pressed(KeyCode::Key7) { *tool = Tool::Dropout; }
+    if is_key_pressed(KeyCode::Key8) { *tool = Tool::Scatter; }
+    if is_key_pressed(KeyCode::Key9) { *tool = Tool::Inspect; }
+}
+
+/*
+ * seconds since the last key, click or mouse movement; macroquad has no
+ * focus events, so a long quiet spell stands in for an unfocused window
+ */
+pub struct IdleTracker {
+    last_mouse: (f32, f32),
+    pub idle_for: f32,
+}
+
+impl IdleTracker {
+    pub fn new() -> Self {
+        Self { last_mouse: mouse_position(), idle_for: 0.0 }
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        let mouse = mouse_position();
+        let active = mouse != self.last_mouse
+            || get_last_key_pressed().is_some()
+            || !get_keys_down().is_empty()
+            || is_mouse_button_down(MouseButton::Left)
+            || is_mouse_button_down(MouseButton::Right)
+            || mouse_wheel() != (0.0, 0.0);
+
+        self.last_mouse = mouse;
+        self.idle_for = if active { 0.0 } else { self.idle_for + delta_time };
+    }
+}
+
+pub fn is_movement_key_down() -> bool {
+    is_key_down(KeyCode::Up) || is_key_down(KeyCode::Down) || is_key_down(KeyCode::Left) || is_key_down(KeyCode::Right)
+}
 
 pub fn movement_input(robot: &mut Robot, cfg: &Config, delta_time: f32) {
     // movement
@@ -18,14 +213,41 @@ pub fn movement_input(robot: &mut Robot, cfg: &Config, delta_time: f32) {
     }
 }
 
+/*
+ * while cruising the arrow keys nudge the setpoints instead of accelerating
+ */
+pub fn cruise_input(cruise: &mut CruiseControl, cfg: &Config) {
+    if is_key_pressed(KeyCode::Up) { cruise.adjust_speed(1.0, cfg); }
+    if is_key_pressed(KeyCode::Down) { cruise.adjust_speed(-1.0, cfg); }
+    if is_key_pressed(KeyCode::Left) { cruise.adjust_heading(1.0, cfg); }
+    if is_key_pressed(KeyCode::Right) { cruise.adjust_heading(-1.0, cfg); }
+}
+
+/*
+ * left click places or removes an obstruction; G toggles grid snapping, shift
+ * keeps the new obstruction in the row or column of the last one, and D repeats
+ * the last placement step
+ */
 pub fn obstructions_input(
     camera: &Camera2D,
     obstructions: &mut Vec<Rect>,
+    snap_to_grid: &mut bool,
     cfg: &Config)
 {
     let mouse_screen = mouse_position();
     let mouse_world = camera.screen_to_world(vec2(mouse_screen.0, mouse_screen.1));
 
+    if is_key_pressed(KeyCode::G) { *snap_to_grid = !*snap_to_grid; }
+
+    if is_key_pressed(KeyCode::D) && let Some(&last) = obstructions.last() {
+        // the offset between the last two placements, or one width to the right
+        let offset = match obstructions.len() {
+            1 => vec2(last.w, 0.0),
+            n => last.point() - obstructions[n - 2].point(),
+        };
+        obstructions.push(last.offset(offset));
+    }
+
     if is_mouse_button_released(MouseButton::Left) {
         // delete the obstruction if mouse is touching it
         let mut removed = false;
@@ -36,27 +258,111 @@ pub fn obstructions_input(
             }
         }
         if !removed {
-            obstructions.push(
-                Rect::new(
-                    mouse_world.x - cfg.obstruction_width / 2.0,
-                    mouse_world.y - cfg.obstruction_height / 2.0,
-                    cfg.obstruction_width,
-                    cfg.obstruction_height
-                )
-            );
+            let mut corner = vec2(mouse_world.x - cfg.obstruction_width / 2.0, mouse_world.y - cfg.obstruction_height / 2.0);
+
+            if *snap_to_grid {
+                corner = (corner / cfg.snap_unit).round() * cfg.snap_unit;
+            }
+
+            // align with the last obstruction along whichever axis is closer
+            let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+            if shift && let Some(last) = obstructions.last() {
+                if (corner.x - last.x).abs() < (corner.y - last.y).abs() { corner.x = last.x; } else { corner.y = last.y; }
+            }
+
+            obstructions.push(Rect::new(corner.x, corner.y, cfg.obstruction_width, cfg.obstruction_height));
+        }
+    }
+}
+
+/*
+ * dragging with the left button paints sensor-denied discs along the way,
+ * spaced half a brush apart; with Shift it wipes away the ones it passes over
+ */
+pub fn dropout_input(camera: &Camera2D, zones: &mut Vec<DropoutZone>, cfg: &Config) {
+    if !is_mouse_button_down(MouseButton::Left) { return; }
+
+    let mouse_screen = mouse_position();
+    let mouse_world = camera.screen_to_world(vec2(mouse_screen.0, mouse_screen.1));
+    let point = (mouse_world.x, mouse_world.y);
+
+    if is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift) {
+        zones.retain(|zone| !zone.contains(point));
+        return;
+    }
+
+    let spacing = 0.5 * cfg.dropout_brush_radius;
+    let covered = zones.iter().any(|zone| (zone.x - point.0).powi(2) + (zone.y - point.1).powi(2) < spacing.powi(2));
+    if !covered {
+        zones.push(DropoutZone { x: point.0, y: point.1, radius: cfg.dropout_brush_radius });
+    }
+}
+
+/*
+ * drag an area to fill it with landmarks when the button is released; = and -
+ * raise and lower the density, and with Shift the minimum separation. Returns
+ * how many were placed when a drag ends
+ */
+pub fn scatter_input(camera: &Camera2D, scatter: &mut Scatter, landmarks: &mut Vec<Landmark>, cfg: &Config) -> Option<usize> {
+    let mouse_screen = mouse_position();
+    let mouse_world = camera.screen_to_world(vec2(mouse_screen.0, mouse_screen.1));
+    let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+    let control = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+
+    // with Ctrl held the keys resize the HUD instead
+    let step = if control { 0.0 } else if is_key_pressed(KeyCode::Equal) { 1.0 } else if is_key_pressed(KeyCode::Minus) { -1.0 } else { 0.0 };
+    if shift {
+        scatter.separation = (scatter.separation + step * cfg.scatter_separation_step).max(0.0);
+    } else {
+        scatter.density *= cfg.scatter_density_factor.powf(step);
+    }
+
+    if is_mouse_button_pressed(MouseButton::Left) {
+        scatter.drag_start = Some(mouse_world);
+    }
+    if let Some(start) = scatter.drag_start {
+        let min = start.min(mouse_world);
+        let size = (start - mouse_world).abs();
+        scatter.area = Some(Rect::new(min.x, min.y, size.x, size.y));
+
+        if is_mouse_button_released(MouseButton::Left) {
+            let placed = template::scatter(Rect::new(min.x, min.y, size.x, size.y), scatter.density, scatter.separation, landmarks);
+            scatter.drag_start = None;
+            scatter.area = None;
+            return Some(placed);
         }
     }
+    None
 }
 
+/*
+ * right click places or removes a landmark; with Shift it gives the landmark a
+ * visibility sector facing the robot, or takes it away again
+ */
 pub fn landmarks_input(
     gt_camera: &Camera2D,
     landmarks: &mut Vec<Landmark>,
+    robot: &Robot,
     cfg: &Config
 ) {
     let mouse_screen = mouse_position();
     let mouse_world = gt_camera.screen_to_world(vec2(mouse_screen.0, mouse_screen.1));
+    let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
 
-    if is_mouse_button_released(MouseButton::Right) {
+    if shift && is_mouse_button_released(MouseButton::Right) {
+        let hovered = landmarks.iter_mut().find(|landmark| {
+            (mouse_world.x - landmark.x).abs() < cfg.landmark_radius && (mouse_world.y - landmark.y).abs() < cfg.landmark_radius
+        });
+        if let Some(landmark) = hovered {
+            landmark.sensor.visibility = match landmark.sensor.visibility {
+                Some(_) => None,
+                None => Some(VisibilitySector {
+                    facing: f32::atan2(robot.y - landmark.y, robot.x - landmark.x),
+                    width: cfg.visibility_sector_width,
+                }),
+            };
+        }
+    } else if is_mouse_button_released(MouseButton::Right) {
         let mut removed = false;
         for (i, landmark) in landmarks.iter().enumerate() {
             if mouse_world.x < landmark.x + cfg.landmark_radius &&
@@ -70,13 +376,197 @@ pub fn landmarks_input(
         }
         if !removed {
             let id = landmarks.last().map(|l| l.id + 1).unwrap_or(0);
-            landmarks.push(
-                Landmark {
-                    id,
-                    x: mouse_world.x, 
-                    y: mouse_world.y 
+            landmarks.push(Landmark::new(id, mouse_world.x, mouse_world.y));
+        }
+    }
+}
+
+pub fn goals_input(
+    camera: &Camera2D,
+    mission: &mut Mission,
+    cfg: &Config
+) {
+    let mouse_screen = mouse_position();
+    let mouse_world = camera.screen_to_world(vec2(mouse_screen.0, mouse_screen.1));
+
+    if is_mouse_button_released(MouseButton::Left) &&
+       !mission.remove_goal_near(mouse_world.x, mouse_world.y, cfg.goal_tolerance) {
+        mission.add_goal(mouse_world.x, mouse_world.y);
+    }
+    if is_key_pressed(KeyCode::C) {
+        mission.clear();
+    }
+}
+
+/*
+ * a click measures the clicked point from the true robot pose and attributes it
+ * to the nearest landmark, so clicking off a landmark injects a biased measurement
+ */
+pub fn injection_input(camera: &Camera2D, robot: &Robot, landmarks: &[Landmark]) -> Option<Observation> {
+    if !is_mouse_button_released(MouseButton::Left) { return None; }
+
+    let mouse_screen = mouse_position();
+    let mouse_world = camera.screen_to_world(vec2(mouse_screen.0, mouse_screen.1));
+
+    let nearest = landmarks.iter().min_by(|a, b| {
+        let distance_a = (a.x - mouse_world.x).powi(2) + (a.y - mouse_world.y).powi(2);
+        let distance_b = (b.x - mouse_world.x).powi(2) + (b.y - mouse_world.y).powi(2);
+        distance_a.total_cmp(&distance_b)
+    })?;
+
+    let (range, bearing) = robot.pose().range_bearing((mouse_world.x, mouse_world.y));
+    Some(Observation { id: nearest.id, range, bearing })
+}
+
+/*
+ * a click picks the landmark estimate under the mouse to anchor
+ */
+pub fn anchor_input(camera: &Camera2D, estimates: &[(usize, f32, f32)], cfg: &Config) -> Option<usize> {
+    if !is_mouse_button_released(MouseButton::Left) { return None; }
+    landmark_under_mouse(camera, estimates, cfg)
+}
+
+/*
+ * a click picks the landmark estimate under the mouse to inspect, or clears
+ * the pick when there is none; None while not clicked
+ */
+pub fn inspect_input(camera: &Camera2D, estimates: &[(usize, f32, f32)], cfg: &Config) -> Option<Option<usize>> {
+    if !is_mouse_button_released(MouseButton::Left) { return None; }
+    Some(landmark_under_mouse(camera, estimates, cfg))
+}
+
+/*
+ * the landmark estimate under the mouse, if any
+ */
+pub fn landmark_under_mouse(camera: &Camera2D, estimates: &[(usize, f32, f32)], cfg: &Config) -> Option<usize> {
+    let mouse_screen = mouse_position();
+    let mouse_world = camera.screen_to_world(vec2(mouse_screen.0, mouse_screen.1));
+
+    estimates.iter()
+        .find(|&&(_, x, y)| (x - mouse_world.x).powi(2) + (y - mouse_world.y).powi(2) < (2.0 * cfg.landmark_radius).powi(2))
+        .map(|&(id, _, _)| id)
+}
+
+/*
+ * ] inflates and [ deflates by `covariance_edit_factor` the covariance of the
+ * landmark estimate under the mouse, or the pose's when there is none;
+ * returns the target and the factor
+ */
+pub fn covariance_edit_input(camera: &Camera2D, estimates: &[(usize, f32, f32)], cfg: &Config) -> Option<(Option<usize>, f32)> {
+    let factor = if is_key_pressed(KeyCode::RightBracket) { cfg.covariance_edit_factor }
+        else if is_key_pressed(KeyCode::LeftBracket) { 1.0 / cfg.covariance_edit_factor }
+        else { return None; };
+
+    Some((landmark_under_mouse(camera, estimates, cfg), factor))
+}
+
+/*
+ * drag selects an area; ctrl+C copies it, ctrl+V pastes at the mouse, ctrl+S
+ * saves the clipboard as a template, and T loads the next saved template;
+ * returns how saving or loading went, for the event log
+ */
+pub fn select_input(
+    camera: &Camera2D,
+    selection: &mut Selection,
+    landmarks: &mut Vec<Landmark>,
+    obstructions: &mut Vec<Rect>
+) -> Option<(EventKind, String)> {
+    let mouse_screen = mouse_position();
+    let mouse_world = camera.screen_to_world(vec2(mouse_screen.0, mouse_screen.1));
+    let ctrl = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+
+    if is_mouse_button_pressed(MouseButton::Left) {
+        selection.drag_start = Some(mouse_world);
+    }
+    if let Some(start) = selection.drag_start {
+        let min = start.min(mouse_world);
+        let size = (start - mouse_world).abs();
+        selection.area = Some(Rect::new(min.x, min.y, size.x, size.y));
+        if is_mouse_button_released(MouseButton::Left) { selection.drag_start = None; }
+    }
+
+    if ctrl && is_key_pressed(KeyCode::C) && let Some(area) = selection.area {
+        let copied = Template::capture(area, landmarks, obstructions);
+        selection.clipboard = (!copied.is_empty()).then_some(copied);
+    }
+
+    if ctrl && is_key_pressed(KeyCode::V) && let Some(clipboard) = &selection.clipboard {
+        clipboard.paste(mouse_world, landmarks, obstructions);
+    }
+
+    if ctrl && is_key_pressed(KeyCode::S) && let Some(clipboard) = &selection.clipboard {
+        let path = crate::recording::timestamped_path(template::TEMPLATE_DIR, "template", "json");
+        return Some(match clipboard.save(&path) {
+            Ok(()) => (EventKind::Saved, path.display().to_string()),
+            Err(error) => (EventKind::Failed, format!("template: {error}")),
+        });
+    }
+
+    if is_key_pressed(KeyCode::T) {
+        let templates = template::list(std::path::Path::new(template::TEMPLATE_DIR));
+        if let Some(path) = templates.get(selection.next_template % templates.len().max(1)) {
+            selection.next_template += 1;
+            return Some(match Template::load(path) {
+                Ok(loaded) => {
+                    selection.clipboard = Some(loaded);
+                    (EventKind::Loaded, path.display().to_string())
                 }
-            );
+                Err(error) => (EventKind::Failed, format!("{}: {error}", path.display())),
+            });
         }
     }
+    None
+}
+
+pub fn navigation_input(camera: &Camera2D, navigator: &mut Navigator) {
+    let mouse_screen = mouse_position();
+    let mouse_world = camera.screen_to_world(vec2(mouse_screen.0, mouse_screen.1));
+
+    if is_mouse_button_released(MouseButton::Left) {
+        navigator.set_goal(mouse_world.x, mouse_world.y);
+    }
+}
+
+/*
+ * typing edits the pose, arrow keys nudge the position and Q/E the heading
+ * (Shift for a tenth of the step), Enter places the robot and Escape cancels
+ */
+pub fn placement_input(placement: &mut Placement, cfg: &Config) -> PlacementInput {
+    while let Some(character) = get_char_pressed() {
+        if character.is_ascii_digit() || matches!(character, '.' | '-' | ' ' | ',') {
+            placement.text.push(character);
+        }
+    }
+    if is_key_pressed(KeyCode::Backspace) { placement.text.pop(); }
+
+    if let Some(pose) = placement.pose() {
+        let Pose2 { x, y, theta } = pose;
+        let fine = if is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift) { 0.1 } else { 1.0 };
+        let step = fine * cfg.placement_step;
+        let turn = (fine * cfg.placement_turn_step).to_radians();
+
+        let nudge = if is_key_pressed(KeyCode::Left) { Some((x - step, y, theta)) }
+            else if is_key_pressed(KeyCode::Right) { Some((x + step, y, theta)) }
+            else if is_key_pressed(KeyCode::Up) { Some((x, y + step, theta)) }
+            else if is_key_pressed(KeyCode::Down) { Some((x, y - step, theta)) }
+            else if is_key_pressed(KeyCode::Q) { Some((x, y, theta + turn)) }
+            else if is_key_pressed(KeyCode::E) { Some((x, y, theta - turn)) }
+            else { None };
+        if let Some((x, y, theta)) = nudge { placement.text = Placement::format(x, y, theta); }
+
+        if is_key_pressed(KeyCode::Enter) { return PlacementInput::Commit(pose); }
+    }
+
+    if is_key_pressed(KeyCode::Escape) { PlacementInput::Cancel } else { PlacementInput::Editing }
+}
+
+pub fn annotation_input(annotation: &mut Annotation) -> AnnotationInput {
+    while let Some(character) = get_char_pressed() {
+        if !character.is_control() { annotation.text.push(character); }
+    }
+    if is_key_pressed(KeyCode::Backspace) { annotation.text.pop(); }
+
+    if is_key_pressed(KeyCode::Enter) { AnnotationInput::Commit }
+    else if is_key_pressed(KeyCode::Escape) { AnnotationInput::Cancel }
+    else { AnnotationInput::Editing }
 }
@@ -0,0 +1,164 @@
+use std::cell::Cell;
+use macroquad::prelude::Vec2;
+use serde::{Deserialize, Serialize};
+
+// space between the panels and the window edges, and left for the settings cog, at scale 1
+const MARGIN: f32 = 20.0;
+const COG_ROOM: f32 = 25.0;
+
+/*
+ * where on the screen a panel sits: a corner, or the middle of an edge
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Right,
+    BottomRight,
+    Bottom,
+    BottomLeft,
+    Left,
+}
+
+impl Anchor {
+    pub const ALL: [Anchor; 8] = [
+        Anchor::TopLeft, Anchor::Top, Anchor::TopRight, Anchor::Right,
+        Anchor::BottomRight, Anchor::Bottom, Anchor::BottomLeft, Anchor::Left,
+    ];
+
+    // clockwise around the screen
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&anchor| anchor == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Anchor::TopLeft => "top left",
+            Anchor::Top => "top",
+            Anchor::TopRight => "top right",
+            Anchor::Right => "right",
+            Anchor::BottomRight => "bottom right",
+            Anchor::Bottom => "bottom",
+            Anchor::BottomLeft => "bottom left",
+            Anchor::Left => "left",
+        }
+    }
+}
+
+/*
+ * the HUD's movable groups of readouts
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Panel {
+    Readouts, // tool, cruise, and filter readouts, and the SEIF sparsity pattern
+    Mission,
+    Legend,
+    Reports,  // what the last injection, anchoring, or covariance edit did
+    Events,   // recent events and the analytics plugins' metrics
+    Gauges,
+    Playback,
+}
+
+impl Panel {
+    pub const ALL: [Panel; 7] = [
+        Panel::Readouts, Panel::Mission, Panel::Legend, Panel::Reports,
+        Panel::Events, Panel::Gauges, Panel::Playback,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Panel::Readouts => "Readouts",
+            Panel::Mission => "Mission",
+            Panel::Legend => "Legend",
+            Panel::Reports => "Reports",
+            Panel::Events => "Events",
+            Panel::Gauges => "Gauges",
+            Panel::Playback => "Playback",
+        }
+    }
+
+    pub fn default_anchor(self) -> Anchor {
+        match self {
+            Panel::Readouts => Anchor::TopLeft,
+            Panel::Mission => Anchor::TopRight,
+            Panel::Legend | Panel::Reports => Anchor::BottomRight,
+            Panel::Events => Anchor::BottomLeft,
+            Panel::Gauges | Panel::Playback => Anchor::Bottom,
+        }
+    }
+}
+
+/*
+ * how big the HUD is drawn and where each panel sits. Sizes are given at
+ * scale 1 and multiplied by `scale`, so a 4K display or a projector can make
+ * everything larger at once
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HudLayout {
+    pub scale: f32,
+    anchors: [Anchor; Panel::ALL.len()],
+}
+
+impl HudLayout {
+    pub const MIN_SCALE: f32 = 0.5;
+    pub const MAX_SCALE: f32 = 4.0;
+
+    pub fn new() -> Self {
+        Self { scale: 1.0, anchors: Panel::ALL.map(Panel::default_anchor) }
+    }
+
+    pub fn anchor(&self, panel: Panel) -> Anchor {
+        self.anchors[panel as usize]
+    }
+
+    pub fn set_anchor(&mut self, panel: Panel, anchor: Anchor) {
+        self.anchors[panel as usize] = anchor;
+    }
+
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale.clamp(Self::MIN_SCALE, Self::MAX_SCALE);
+    }
+
+    /*
+     * the top left corner of row `row` of a panel block `rows` tall, with the
+     * row `width` wide (already scaled) and `line_spacing` apart, on a screen
+     * of `screen` size. Rows run top to bottom whatever the anchor, so a panel
+     * that keeps clear of another at its edge reserves rows after its own
+     */
+    pub fn row(&self, panel: Panel, row: f32, rows: f32, width: f32, line_spacing: f32, screen: (f32, f32)) -> Vec2 {
+        let anchor = self.anchor(panel);
+        let margin = MARGIN * self.scale;
+        let height = rows * line_spacing;
+
+        let x = match anchor {
+            // the settings cog has the very corner
+            Anchor::TopLeft => margin + COG_ROOM * self.scale,
+            Anchor::Left | Anchor::BottomLeft => margin,
+            Anchor::Top | Anchor::Bottom => (screen.0 - width) / 2.0,
+            Anchor::TopRight | Anchor::Right | Anchor::BottomRight => screen.0 - margin - width,
+        };
+        let top = match anchor {
+            Anchor::TopLeft | Anchor::Top | Anchor::TopRight => margin,
+            Anchor::Left | Anchor::Right => (screen.1 - height) / 2.0,
+            Anchor::BottomLeft | Anchor::Bottom | Anchor::BottomRight => screen.1 - margin - height,
+        };
+        Vec2::new(x, top + row * line_spacing)
+    }
+}
+
+thread_local! {
+    static CURRENT: Cell<HudLayout> = Cell::new(HudLayout::new());
+}
+
+/*
+ * the layout the HUD draws with, set once a frame from the user settings
+ */
+pub fn current() -> HudLayout {
+    CURRENT.with(Cell::get)
+}
+
+pub fn set_current(layout: HudLayout) {
+    CURRENT.with(|current| current.set(layout));
+}
@@ -0,0 +1,241 @@
+use serde::{Deserialize, Serialize};
+
+/*
+ * the language of the on-screen text. HUD strings are written in English and
+ * looked up in the language's table when drawn, so a string without a
+ * translation still shows, in English
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    Spanish,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::Spanish];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&language| language == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    // in the language itself, so it can be found by someone who can't read the others
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Spanish => "Español",
+        }
+    }
+
+    pub fn table(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Language::English => &[],
+            Language::Spanish => SPANISH,
+        }
+    }
+
+    pub fn tr(self, text: &'static str) -> &'static str {
+        self.table().iter()
+            .find(|(english, _)| *english == text)
+            .map_or(text, |(_, translated)| translated)
+    }
+}
+
+// values are spliced in between these by the HUD, so they are fragments rather than sentences
+const SPANISH: &[(&str, &str)] = &[
+    // legend
+    ("Commanded", "Comandado"),
+    ("Dead reck.", "Odometría"),
+
+    // settings menu
+    ("Visibility Menu", "Menú de visibilidad"),
+    ("EKF-SLAM State", "Estado EKF-SLAM"),
+    ("FastSLAM State", "Estado FastSLAM"),
+    ("GraphSLAM State", "Estado GraphSLAM"),
+    ("SEIF State", "Estado SEIF"),
+    ("EKF-SLAM landmarks", "Balizas EKF-SLAM"),
+    ("FastSLAM landmarks", "Balizas FastSLAM"),
+    ("FastSLAM particles", "Partículas FastSLAM"),
+    ("GraphSLAM landmarks", "Balizas GraphSLAM"),
+    ("SEIF landmarks", "Balizas SEIF"),
+    ("SEIF sparsity", "Dispersión SEIF"),
+    ("Weak directions", "Direcciones débiles"),
+    ("Track uncertainty", "Incert. de trayectoria"),
+    ("Motion gauges", "Indicadores de movimiento"),
+    ("Robocentric EKF", "EKF robocéntrico"),
+    ("UKF-SLAM", "SLAM con UKF"),
+    ("FEJ EKF", "EKF FEJ"),
+    ("Adaptive noise", "Ruido adaptativo"),
+    ("MHT EKF (no ids)", "EKF MHT (sin ids)"),
+    ("Bearing-only EKFs", "EKF solo rumbo"),
+    ("Uncertainty colors", "Color incertidumbre"),
+    ("Cov. ellipses", "Elipses de cov."),
+    ("Ghost trails", "Rastros fantasma"),
+    ("Overlay trails", "Superponer rastros"),
+    ("Uncertainty tube", "Tubo de incertidumbre"),
+    ("Health light", "Semáforo de salud"),
+    ("Coverage map", "Mapa de cobertura"),
+    ("Observation heatmap", "Mapa de observación"),
+    ("Lidar scan", "Escaneo lidar"),
+    ("Sensing wedge", "Cuña de detección"),
+    ("Believed walls", "Paredes estimadas"),
+    ("DWA avoidance", "Evasión DWA"),
+    ("Stuck ZUPT", "ZUPT atascado"),
+    ("Parked ZUPT", "ZUPT aparcado"),
+    ("Predict delayed cmds", "Predecir con retardo"),
+    ("Robot-centric view", "Vista desde el robot"),
+    ("Split view", "Vista dividida"),
+    ("Link cameras", "Enlazar cámaras"),
+    ("Idle throttling", "Ahorro en reposo"),
+
+    // HUD layout
+    ("UI scale", "Escala de la interfaz"),
+    ("(Ctrl + and Ctrl - resize, Ctrl 0 resets; click a panel to move it)", "(Ctrl + y Ctrl - cambian el tamaño, Ctrl 0 lo restablece; clic en un panel para moverlo)"),
+    ("Readouts", "Lecturas"),
+    ("Legend", "Leyenda"),
+    ("Reports", "Informes"),
+    ("Events", "Eventos"),
+    ("Gauges", "Indicadores"),
+    ("Playback", "Reproducción"),
+    ("top left", "arriba a la izquierda"),
+    ("top", "arriba"),
+    ("top right", "arriba a la derecha"),
+    ("right", "derecha"),
+    ("bottom right", "abajo a la derecha"),
+    ("bottom", "abajo"),
+    ("bottom left", "abajo a la izquierda"),
+    ("left", "izquierda"),
+
+    // filter readouts
+    ("SEIF links", "Enlaces SEIF"),
+    ("landmarks", "balizas"),
+    ("active", "activas"),
+    ("Weakest direction", "Dirección más débil"),
+    ("stdev", "desv."),
+    ("translation", "traslación"),
+    ("rotation", "rotación"),
+    ("Position stdev", "Desv. de posición"),
+    ("along-track", "longitudinal"),
+    ("cross-track", "transversal"),
+    ("Adapted R", "R adaptada"),
+    ("range", "distancia"),
+    ("bearing", "rumbo"),
+    ("set", "fijada"),
+    ("Hypotheses", "Hipótesis"),
+    ("wrong associations", "asociaciones erróneas"),
+    ("of", "de"),
+    ("executed", "ejecutado"),
+    ("Localization", "Localización"),
+    ("good", "buena"),
+    ("fair", "regular"),
+    ("poor", "mala"),
+    ("meas./frame", "med./cuadro"),
+    ("EKF inconsistent", "EKF inconsistente"),
+    ("overconfident", "demasiado seguro"),
+    ("underconfident", "demasiado cauto"),
+
+    // tools and driving
+    ("Tool", "Herramienta"),
+    ("snap", "ajuste"),
+    ("Obstruction", "Obstáculo"),
+    ("Goal", "Meta"),
+    ("Navigate", "Navegar"),
+    ("Inject", "Inyectar"),
+    ("Select", "Seleccionar"),
+    ("Anchor", "Anclar"),
+    ("Dropout", "Niebla"),
+    ("Scatter", "Esparcir"),
+    ("Inspect", "Inspeccionar"),
+    ("density", "densidad"),
+    ("separation", "separación"),
+    (
+        "(1 obstruction, 2 goal, 3 navigate, 4 inject, 5 select, 6 anchor, 7 dropout, 8 scatter, 9 inspect)",
+        "(1 obstáculo, 2 meta, 3 navegar, 4 inyectar, 5 seleccionar, 6 anclar, 7 niebla, 8 esparcir, 9 inspeccionar)",
+    ),
+    ("Cruise", "Crucero"),
+    ("speed", "velocidad"),
+    ("heading", "rumbo"),
+    ("turn rate", "giro"),
+    ("accel.", "acel."),
+    ("ang. accel.", "acel. ang."),
+    ("actual", "real"),
+    ("commanded", "comandada"),
+    ("noisy", "con ruido"),
+    ("deg", "grados"),
+    ("space to release", "espacio para soltar"),
+
+    // mission
+    ("Mission", "Misión"),
+    ("in progress", "en curso"),
+    ("complete", "completada"),
+    ("Goals", "Metas"),
+    ("Time", "Tiempo"),
+    ("Distance", "Distancia"),
+    ("Last goal at", "Última meta a"),
+
+    // injection, anchoring, covariance edits
+    ("Injected landmark", "Baliza inyectada"),
+    ("Range", "Distancia"),
+    ("Pose moved", "Pose movida"),
+    ("Pose cov trace", "Traza cov. pose"),
+    ("Anchored landmark", "Baliza anclada"),
+    ("State cov trace", "Traza cov. estado"),
+    ("Landmark", "Baliza"),
+    ("no history", "sin historial"),
+    ("trace", "traza"),
+    ("Largest jump", "Mayor salto"),
+    ("at", "en"),
+    ("EKF covariance", "Covarianza EKF"),
+    ("landmark", "baliza"),
+    ("pose", "pose"),
+    ("Block trace", "Traza del bloque"),
+
+    // typed input
+    ("Place robot (x y heading)", "Colocar robot (x y rumbo)"),
+    ("Arrows/Q/E nudge, Shift finer, Enter place, Esc cancel", "Flechas/Q/E mover, Shift más fino, Enter colocar, Esc cancelar"),
+    ("Bookmark at", "Marcador en"),
+    ("note", "nota"),
+    ("Enter drop (the note is optional), Esc cancel", "Enter dejar (la nota es opcional), Esc cancelar"),
+
+    // events
+    ("bump", "choque"),
+    ("stuck", "atascado"),
+    ("unstuck", "liberado"),
+    ("bookmark", "marcador"),
+    ("saved", "guardado"),
+    ("loaded", "cargado"),
+    ("failed", "falló"),
+    ("finished", "terminado"),
+    ("landmarks scattered", "balizas esparcidas"),
+
+    // session
+    ("A recovery point from the last session was found.", "Se encontró un punto de recuperación de la última sesión."),
+    ("restore it? (Y / N)", "¿restaurarlo? (Y / N)"),
+    ("event queued", "evento en cola"),
+    ("events queued", "eventos en cola"),
+    ("held while paused", "retenidos en pausa"),
+    ("REC", "GRAB"),
+
+    // end-of-run summary
+    ("finished (EKF-SLAM)", "terminado (EKF-SLAM)"),
+    ("Session summary (EKF-SLAM)", "Resumen de la sesión (EKF-SLAM)"),
+    ("Duration", "Duración"),
+    ("Distance traveled", "Distancia recorrida"),
+    ("Landmarks mapped", "Balizas mapeadas"),
+    ("Map RMSE", "RMSE del mapa"),
+    ("unmatched", "sin pareja"),
+    ("spurious", "espurias"),
+    ("Final position error", "Error de posición final"),
+    ("ATE (position RMSE)", "ATE (RMSE de posición)"),
+    ("Dead reckoning ATE", "ATE de odometría"),
+    ("with SLAM", "con SLAM"),
+    ("Loop closures", "Cierres de bucle"),
+    ("Average pose NEES", "NEES medio de la pose"),
+    ("consistent near 3", "consistente cerca de 3"),
+    ("Peak position covariance trace", "Traza máx. de cov. de posición"),
+    ("E: export as JSON, H: HTML report, Enter: continue", "E: exportar como JSON, H: informe HTML, Enter: continuar"),
+    ("Saved to", "Guardado en"),
+    ("Could not save summary", "No se pudo guardar el resumen"),
+    ("Could not save report", "No se pudo guardar el informe"),
+];
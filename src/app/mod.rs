@@ -1,8 +1,37 @@
+pub mod covariance_view;
+pub mod diff_view;
+pub mod ellipses;
+pub mod estimates;
+pub mod gate_view;
 pub mod hud;
 pub mod input;
+pub mod layout;
+pub mod locale;
+pub mod noise_widget;
+pub mod plots;
 pub mod renderer;
+pub mod summary_view;
 pub mod user_settings;
+pub mod video_view;
+pub mod view;
 
+// sizes at a UI scale of 1; they are drawn at the current layout's scale
 const SHADOW_OFFSET: f32 = 16.0;
-const FONT_SIZE: u16 = 20;
+const FONT_SIZE: f32 = 20.0;
 const LINE_SPACING: f32 = 30.0;
+
+fn scale() -> f32 {
+    layout::current().scale
+}
+
+fn shadow_offset() -> f32 {
+    SHADOW_OFFSET * scale()
+}
+
+fn font_size() -> u16 {
+    (FONT_SIZE * scale()).round() as u16
+}
+
+fn line_spacing() -> f32 {
+    LINE_SPACING * scale()
+}
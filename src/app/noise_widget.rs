@@ -0,0 +1,120 @@
+use macroquad::prelude::*;
+use crate::config::Config;
+use crate::simulation::{Robot, sensor};
+use crate::slam::EkfSlam;
+use super::renderer;
+use super::{font_size, line_spacing};
+
+pub const SENSOR_COLOR: Color = Color::new(1.0, 0.6, 0.3, 0.9);
+
+const WIDTH: f32 = 380.0;
+const TRACK_LEFT: f32 = 210.0; // from the panel's left edge
+const TRACK_WIDTH: f32 = 150.0;
+
+/*
+ * sliders for the measurement noise, with the 2 sigma ellipse of a reading
+ * `range` ahead of the robot drawn in the world, so the settings can be judged
+ * at their real size before driving
+ */
+pub struct NoiseWidget {
+    pub range: f32,
+    dragging: Option<usize>,
+}
+
+impl NoiseWidget {
+    pub fn new(cfg: &Config) -> Self {
+        Self { range: 0.5 * cfg.sensor_range, dragging: None }
+    }
+
+    /*
+     * above the event log in the bottom left
+     */
+    pub fn area() -> Rect {
+        let height = 7.0 * line_spacing();
+        Rect::new(20.0, screen_height() - 20.0 - 5.0 * line_spacing() - height, WIDTH, height)
+    }
+
+    // (label, value, min, max, decimals)
+    fn sliders<'a>(&'a mut self, cfg: &'a mut Config) -> [(&'static str, &'a mut f32, f32, f32, usize); 5] {
+        let max_range = cfg.sensor_range;
+        [
+            ("Sensor range stdev", &mut cfg.real_stdev_range, 0.0, 20.0, 1),
+            ("Sensor bearing stdev", &mut cfg.real_stdev_bearing, 0.0, 0.3, 3),
+            ("Filter range stdev", &mut cfg.est_stdev_range, 0.0, 20.0, 1),
+            ("Filter bearing stdev", &mut cfg.est_stdev_bearing, 0.0, 0.3, 3),
+            ("At range", &mut self.range, 10.0, max_range, 0),
+        ]
+    }
+
+    fn track(row: usize) -> Rect {
+        let area = Self::area();
+        Rect::new(area.x + TRACK_LEFT, area.y + (row as f32 + 1.5) * line_spacing() - 5.0, TRACK_WIDTH, 10.0)
+    }
+
+    /*
+     * left drag on a track moves its slider; returns whether the widget has the mouse
+     */
+    pub fn input(&mut self, cfg: &mut Config) -> bool {
+        let mouse: Vec2 = mouse_position().into();
+        if is_mouse_button_pressed(MouseButton::Left) {
+            self.dragging = (0..5).find(|&row| {
+                let track = Self::track(row);
+                Rect::new(track.x - 5.0, track.y - 5.0, track.w + 10.0, track.h + 10.0).contains(mouse)
+            });
+        }
+        if !is_mouse_button_down(MouseButton::Left) { self.dragging = None; }
+
+        if let Some(row) = self.dragging {
+            let track = Self::track(row);
+            let fraction = ((mouse.x - track.x) / track.w).clamp(0.0, 1.0);
+            let (_, value, min, max, _) = &mut self.sliders(cfg)[row];
+            **value = *min + fraction * (*max - *min);
+        }
+        self.dragging.is_some() || Self::area().contains(mouse)
+    }
+
+    /*
+     * the sensor's and the filter's ellipses, in world coordinates
+     */
+    pub fn draw_ellipses(&self, robot: &Robot, cfg: &Config) {
+        let (x, y) = (robot.x + self.range * robot.theta.cos(), robot.y + self.range * robot.theta.sin());
+        draw_line(robot.x, robot.y, x, y, 1.0, Color::new(1.0, 1.0, 1.0, 0.3));
+        draw_circle(x, y, 2.0, WHITE);
+
+        let sensor = sensor::cartesian_covariance(self.range, robot.theta, cfg.real_stdev_range, cfg.real_stdev_bearing);
+        let filter = sensor::cartesian_covariance(self.range, robot.theta, cfg.est_stdev_range, cfg.est_stdev_bearing);
+        renderer::draw_covariance_ellipse(x, y, &sensor, 2.0, 2.0, SENSOR_COLOR);
+        renderer::draw_covariance_ellipse(x, y, &filter, 2.0, 2.0, EkfSlam::COLOR);
+    }
+
+    pub fn draw(&mut self, font: &Font, cfg: &mut Config) {
+        let area = Self::area();
+        draw_rectangle(area.x, area.y, area.w, area.h, Color::new(0.05, 0.05, 0.05, 0.9));
+        let text = |text: &str, x: f32, y: f32, color: Color| {
+            draw_text_ex(text, x, y, TextParams { font: Some(font), font_size: font_size(), color, ..Default::default() });
+        };
+
+        text("Measurement noise (2 sigma)", area.x + 10.0, area.y + line_spacing() - 7.5, WHITE);
+        let range = self.range;
+        for (row, (label, value, min, max, decimals)) in self.sliders(cfg).into_iter().enumerate() {
+            let track = Self::track(row);
+            let color = if row < 2 { SENSOR_COLOR } else if row < 4 { EkfSlam::COLOR } else { LIGHTGRAY };
+            text(&format!("{label} {:.*}", decimals, *value), area.x + 10.0, track.y + 10.0, color);
+
+            draw_line(track.x, track.y + 5.0, track.x + track.w, track.y + 5.0, 2.0, DARKGRAY);
+            let knob = track.x + track.w * ((*value - min) / (max - min)).clamp(0.0, 1.0);
+            draw_circle(knob, track.y + 5.0, 6.0, color);
+        }
+
+        // the ellipse's half axes: along the beam and across it
+        text(
+            &format!(
+                "Sensor: {:.1} along, {:.1} across at {:.0}",
+                2.0 * cfg.real_stdev_range, 2.0 * range * cfg.real_stdev_bearing, range
+            ),
+            area.x + 10.0,
+            area.y + 6.5 * line_spacing() + 2.5,
+            SENSOR_COLOR
+        );
+    }
+}
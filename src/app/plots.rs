@@ -0,0 +1,151 @@
+use macroquad::prelude::*;
+use crate::consistency::{ConsistencyMonitor, ConsistencySample, RunningStat};
+use crate::metrics::MetricsHistory;
+use crate::slam::{BearingOnlyEkf, EkfSlam, FastSlam, OdometryBaseline, RobocentricEkf};
+use super::{font_size, line_spacing};
+
+/// named (time, value) line drawn in a chart
+pub type Series<'a> = (&'a str, Vec<(f32, f32)>, Color);
+
+/*
+ * where the metrics pane is shown; cycled with P
+ */
+#[derive(Clone, Copy, PartialEq)]
+pub enum PlotPane {
+    Hidden,
+    Docked,
+    Overlay,
+}
+
+impl PlotPane {
+    pub fn next(self) -> Self {
+        match self {
+            PlotPane::Hidden => PlotPane::Docked,
+            PlotPane::Docked => PlotPane::Overlay,
+            PlotPane::Overlay => PlotPane::Hidden,
+        }
+    }
+}
+
+pub fn draw_plot_pane(font: &Font, pane: PlotPane, history: &MetricsHistory, consistency: &ConsistencyMonitor) {
+    let area = match pane {
+        PlotPane::Hidden => return,
+        PlotPane::Docked => Rect::new(screen_width() * 0.6, 0.0, screen_width() * 0.4, screen_height()),
+        PlotPane::Overlay => Rect::new(0.0, 0.0, screen_width(), screen_height()),
+    };
+
+    draw_rectangle(area.x, area.y, area.w, area.h, Color::new(0.05, 0.05, 0.05, 0.95));
+
+    // a running statistic of the EKF between its chi-square bounds
+    let bounded = |stat: fn(&ConsistencySample) -> Option<RunningStat>| vec![
+        ("EKF", consistency.series(|s| stat(s).map(|stat| stat.mean)), EkfSlam::COLOR),
+        ("95%", consistency.series(|s| stat(s).map(|stat| stat.upper)), GRAY),
+        ("", consistency.series(|s| stat(s).map(|stat| stat.lower)), GRAY),
+    ];
+
+    let charts: [(&str, Vec<Series>); 7] = [
+        ("Position error", vec![
+            ("EKF", history.series(|s| s.ekf_error), EkfSlam::COLOR),
+            ("Fast", history.series(|s| s.fast_error), FastSlam::COLOR),
+            ("Robo", history.optional_series(|s| s.robocentric_error), RobocentricEkf::COLOR),
+            ("Odom", history.series(|s| s.odometry_error), OdometryBaseline::COLOR),
+            ("IDepth", history.optional_series(|s| s.inverse_depth_error), BearingOnlyEkf::INVERSE_DEPTH_COLOR),
+            ("Tri", history.optional_series(|s| s.triangulation_error), BearingOnlyEkf::TRIANGULATION_COLOR),
+        ]),
+        ("Position covariance trace", vec![
+            ("EKF", history.series(|s| s.ekf_trace), EkfSlam::COLOR),
+            ("Fast", history.series(|s| s.fast_trace), FastSlam::COLOR),
+            ("Robo", history.optional_series(|s| s.robocentric_trace), RobocentricEkf::COLOR),
+        ]),
+        ("EKF track stdev", vec![
+            ("Along", history.series(|s| s.ekf_along_track), EkfSlam::COLOR),
+            ("Cross", history.series(|s| s.ekf_cross_track), ORANGE),
+        ]),
+        ("Pose NEES", vec![
+            ("EKF", history.optional_series(|s| s.ekf_nees), EkfSlam::COLOR),
+            ("Robo", history.optional_series(|s| s.robocentric_nees), RobocentricEkf::COLOR),
+        ]),
+        ("Running NEES", bounded(|s| s.nees)),
+        ("Running NIS", bounded(|s| s.nis)),
+        ("Mapped landmarks", vec![
+            ("EKF", history.series(|s| s.ekf_landmarks as f32), EkfSlam::COLOR),
+            ("IDepth", history.optional_series(|s| s.inverse_depth_landmarks.map(|count| count as f32)), BearingOnlyEkf::INVERSE_DEPTH_COLOR),
+            ("Tri", history.optional_series(|s| s.triangulation_landmarks.map(|count| count as f32)), BearingOnlyEkf::TRIANGULATION_COLOR),
+        ]),
+    ];
+
+    let padding = 20.0;
+    let chart_height = (area.h - padding) / charts.len() as f32;
+    for (i, (title, series)) in charts.iter().enumerate() {
+        let chart_area = Rect::new(
+            area.x + padding + 40.0,
+            area.y + padding + i as f32 * chart_height + line_spacing(),
+            area.w - 2.0 * padding - 40.0,
+            chart_height - line_spacing() - padding
+        );
+        draw_chart(font, title, series, chart_area);
+    }
+}
+
+/*
+ * line chart of one or more (time, value) series sharing both axes
+ */
+pub fn draw_chart(font: &Font, title: &str, series: &[Series], area: Rect) {
+    let points = series.iter().flat_map(|(_, points, _)| points.iter());
+    let (mut min_t, mut max_t, mut max_v) = (f32::INFINITY, f32::NEG_INFINITY, 1e-3f32);
+    for &(t, v) in points {
+        min_t = min_t.min(t);
+        max_t = max_t.max(t);
+        if v.is_finite() { max_v = max_v.max(v); }
+    }
+
+    let label = |text: &str, x: f32, y: f32, color: Color| {
+        draw_text_ex(text, x, y, TextParams { font: Some(font), font_size: font_size(), color, ..Default::default() });
+    };
+
+    label(title, area.x, area.y - 8.0, LIGHTGRAY);
+    label(&format!("{max_v:.1}"), area.x - 45.0, area.y + 12.0, GRAY);
+    draw_line(area.x, area.y + area.h, area.x + area.w, area.y + area.h, 1.0, GRAY);
+    draw_line(area.x, area.y, area.x, area.y + area.h, 1.0, GRAY);
+
+    if !min_t.is_finite() || max_t <= min_t { return; }
+
+    let to_screen = |(t, v): (f32, f32)| (
+        area.x + (t - min_t) / (max_t - min_t) * area.w,
+        area.y + area.h - (v.min(max_v) / max_v) * area.h
+    );
+
+    for (i, (name, points, color)) in series.iter().enumerate() {
+        for pair in points.windows(2) {
+            let (x1, y1) = to_screen(pair[0]);
+            let (x2, y2) = to_screen(pair[1]);
+            draw_line(x1, y1, x2, y2, 1.5, *color);
+        }
+        label(name, area.x + area.w - 60.0 * (i as f32 + 1.0), area.y - 8.0, *color);
+    }
+}
+
+/*
+ * a bare line through (time, value) points stretched to fill `area`, for small
+ * inline histories; non-finite values leave gaps
+ */
+pub fn draw_sparkline(points: &[(f32, f32)], area: Rect, color: Color) {
+    let finite = points.iter().filter(|(_, v)| v.is_finite());
+    let (mut min_t, mut max_t, mut min_v, mut max_v) = (f32::INFINITY, f32::NEG_INFINITY, f32::INFINITY, f32::NEG_INFINITY);
+    for &(t, v) in finite {
+        (min_t, max_t, min_v, max_v) = (min_t.min(t), max_t.max(t), min_v.min(v), max_v.max(v));
+    }
+    if !min_t.is_finite() || max_t <= min_t { return; }
+
+    // a flat line sits in the middle
+    let span = (max_v - min_v).max(1e-6);
+    let to_screen = |(t, v): (f32, f32)| (
+        area.x + (t - min_t) / (max_t - min_t) * area.w,
+        if max_v > min_v { area.y + area.h - (v - min_v) / span * area.h } else { area.y + area.h / 2.0 }
+    );
+    for pair in points.windows(2).filter(|pair| pair[0].1.is_finite() && pair[1].1.is_finite()) {
+        let (x1, y1) = to_screen(pair[0]);
+        let (x2, y2) = to_screen(pair[1]);
+        draw_line(x1, y1, x2, y2, 1.0, color);
+    }
+}
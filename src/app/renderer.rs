@@ -1,8 +1,18 @@
 use macroquad::prelude::*;
+use std::collections::VecDeque;
 
-use crate::simulation::{Landmark};
-use crate::slam::{Slam};
-use super::{SHADOW_OFFSET};
+use crate::events::EventLog;
+use crate::math::Pose2;
+use crate::exploration::{ObservationHeatmap, Viewpoint};
+use crate::metrics::TrackStdevs;
+use crate::mission::Mission;
+use crate::observability::WeakDirection;
+use crate::simulation::{DropoutZone, Landmark, Scan};
+use crate::template::Template;
+use crate::scan_map::{ScanMap, believed_corners};
+use crate::trails::{Trails, UncertaintyTube};
+use crate::slam::{EkfSlam, GraphSlam, SlamFilter};
+use super::shadow_offset;
 
 pub fn draw_gridlines(
     robot_x: f32,
@@ -44,7 +54,7 @@ pub fn draw_gridlines(
 
 pub fn draw_obstructions_shadows(obstructions: &[Rect]) {
     for obstruction in obstructions.iter() {
-        for i in 0..SHADOW_OFFSET as i32 {
+        for i in 0..shadow_offset() as i32 {
             draw_rectangle(
                 obstruction.x - (i as f32),
                 obstruction.y - (i as f32),
@@ -62,9 +72,18 @@ pub fn draw_obstructions(obstructions: &[Rect]) {
     }
 }
 
+/*
+ * sensor-denied zones as a faint fog; overlapping discs read a little denser
+ */
+pub fn draw_dropout_zones(zones: &[DropoutZone]) {
+    for zone in zones {
+        draw_circle(zone.x, zone.y, zone.radius, Color::new(0.7, 0.75, 0.85, 0.12));
+    }
+}
+
 pub fn draw_landmarks_shadows(landmarks: &[Landmark], landmark_radius: f32) {
     for landmark in landmarks.iter() {
-        for i in 0..(SHADOW_OFFSET as i32 / 2) {
+        for i in 0..(shadow_offset() as i32 / 2) {
             draw_circle(
                 landmark.x - (i as f32),
                 landmark.y - (i as f32),
@@ -75,15 +94,22 @@ pub fn draw_landmarks_shadows(landmarks: &[Landmark], landmark_radius: f32) {
     }
 }
 
+/*
+ * landmarks with a visibility sector get an arc around them over the directions they can be seen from
+ */
 pub fn draw_landmarks(landmarks: &[Landmark], landmark_radius: f32) {
     for landmark in landmarks.iter() {
         draw_circle(landmark.x, landmark.y, landmark_radius, WHITE);
+        if let Some(sector) = landmark.sensor.visibility {
+            let start = (sector.facing - 0.5 * sector.width).to_degrees();
+            draw_arc(landmark.x, landmark.y, 24, 2.0 * landmark_radius, start, 2.0, sector.width.to_degrees(), Color::new(1.0, 1.0, 1.0, 0.6));
+        }
     }
 }
 
 pub fn draw_robot_shadow(x: f32, y: f32, radius: f32) {
     // shadow
-    for i in 0..(SHADOW_OFFSET as i32 / 2) {
+    for i in 0..(shadow_offset() as i32 / 2) {
         draw_circle(
             x - (i as f32),
             y - (i as f32),
@@ -93,14 +119,15 @@ pub fn draw_robot_shadow(x: f32, y: f32, radius: f32) {
     }
 }
 
-pub fn draw_robot(x: f32, y: f32, theta: f32, radius: f32, fill_color: Color, eye_color: Color) {
+pub fn draw_robot(pose: Pose2, radius: f32, fill_color: Color, eye_color: Color) {
+    let Pose2 { x, y, theta } = pose;
     draw_circle(x, y, radius, fill_color);
     draw_circle(x + 0.5 * radius * (theta - 0.8).cos(), y + 0.5 * radius * (theta - 0.8).sin(), radius / 6.0, eye_color);
     draw_circle(x + 0.5 * radius * (theta + 0.8).cos(), y + 0.5 * radius * (theta + 0.8).sin(), radius / 6.0, eye_color);
 }
 
-pub fn draw_slam_state(slam: &dyn Slam, radius: f32) {
-    let (x, y, theta) = slam.get_state();
+pub fn draw_slam_state(slam: &dyn SlamFilter, radius: f32) {
+    let Pose2 { x, y, theta } = slam.estimated_pose();
     let thickness = radius / 2.0;
     draw_circle_lines(x, y, radius, thickness, slam.color());
 
@@ -115,8 +142,357 @@ pub fn draw_slam_state(slam: &dyn Slam, radius: f32) {
     );
 }
 
-pub fn draw_slam_landmarks(slam: &dyn Slam, radius: f32) {
-    for landmark in slam.get_landmarks() {
+pub fn draw_slam_landmarks(slam: &dyn SlamFilter, radius: f32) {
+    for landmark in slam.estimated_map() {
         draw_circle(landmark.1, landmark.2, radius, slam.color());
     }
 }
+
+/*
+ * GraphSLAM's optimized keyframes joined into its trajectory, out to the
+ * dead-reckoned current pose
+ */
+pub fn draw_graph_trajectory(graph: &GraphSlam) {
+    let trajectory = graph.trajectory();
+    let color = graph.color();
+    for pair in trajectory.windows(2) {
+        draw_line(pair[0].0, pair[0].1, pair[1].0, pair[1].1, 1.5, color);
+    }
+    for &(x, y) in &trajectory[..trajectory.len() - 1] {
+        draw_circle(x, y, 2.0, color);
+    }
+}
+
+/*
+ * every FastSLAM particle as a dot with a heading tick, and the map of the
+ * heaviest particle as rings with their 2-sigma ellipses, next to the
+ * weight-averaged estimates `draw_slam_landmarks` shows
+ */
+pub fn draw_particles(fast: &dyn SlamFilter, robot_radius: f32, landmark_radius: f32) {
+    let color = fast.color();
+    let dot = Color::new(color.r, color.g, color.b, 0.6);
+    for particle in fast.particles() {
+        draw_circle(particle.x, particle.y, 0.15 * robot_radius, dot);
+        draw_line(
+            particle.x,
+            particle.y,
+            particle.x + 0.6 * robot_radius * particle.theta.cos(),
+            particle.y + 0.6 * robot_radius * particle.theta.sin(),
+            0.05 * robot_radius,
+            dot
+        );
+    }
+
+    let Some(best) = fast.best_particle() else { return; };
+    for landmark in best.landmarks.values() {
+        draw_circle_lines(landmark.mu.x, landmark.mu.y, 0.6 * landmark_radius, landmark_radius / 4.0, color);
+        draw_covariance_ellipse(landmark.mu.x, landmark.mu.y, &landmark.sigma, 2.0, landmark_radius / 6.0, dot);
+    }
+}
+
+/*
+ * fills each landmark estimate on a green (confident) to red (uncertain) gradient
+ * keyed to its covariance trace; the outline keeps the algorithm's color
+ */
+pub fn draw_slam_landmarks_uncertainty(slam: &dyn SlamFilter, radius: f32, max_trace: f32) {
+    for landmark in slam.estimated_map() {
+        let trace = slam.landmark_covariance(landmark.0).map(|sigma| sigma.trace()).unwrap_or(max_trace);
+        draw_circle(landmark.1, landmark.2, radius, uncertainty_color(trace, max_trace));
+        draw_circle_lines(landmark.1, landmark.2, radius, radius / 3.0, slam.color());
+    }
+}
+
+fn uncertainty_color(trace: f32, max_trace: f32) -> Color {
+    let t = (trace / max_trace).clamp(0.0, 1.0);
+    Color::new(t, 1.0 - t, 0.0, 0.8)
+}
+
+/*
+ * outline of the `sigmas` standard deviation ellipse of `covariance` around (x, y)
+ */
+pub fn draw_covariance_ellipse(x: f32, y: f32, covariance: &nalgebra::Matrix2<f32>, sigmas: f32, thickness: f32, color: Color) {
+    let eigen = covariance.symmetric_eigen();
+    let axes: Vec<_> = (0..2)
+        .map(|i| eigen.eigenvectors.column(i) * sigmas * eigen.eigenvalues[i].max(0.0).sqrt())
+        .collect();
+
+    let segments = 48;
+    let point = |i: usize| {
+        let angle = std::f32::consts::TAU * i as f32 / segments as f32;
+        let offset = axes[0] * angle.cos() + axes[1] * angle.sin();
+        (x + offset.x, y + offset.y)
+    };
+    for i in 0..segments {
+        let (start, end) = (point(i), point(i + 1));
+        draw_line(start.0, start.1, end.0, end.1, thickness, color);
+    }
+}
+
+/*
+ * squares around the EKF estimates of anchored landmarks
+ */
+pub fn draw_anchors(ekf: &EkfSlam, radius: f32) {
+    for &id in &ekf.anchors {
+        let Some(index) = ekf.observed_landmarks.get(id) else { continue; };
+        let size = 3.0 * radius;
+        draw_rectangle_lines(ekf.state[index] - size / 2.0, ekf.state[index + 1] - size / 2.0, size, size, 2.0, WHITE);
+    }
+}
+
+/*
+ * a ring around the EKF estimate of the inspected landmark
+ */
+pub fn draw_inspected(ekf: &EkfSlam, id: usize, radius: f32) {
+    let Some(index) = ekf.observed_landmarks.get(id) else { return; };
+    draw_circle_lines(ekf.state[index], ekf.state[index + 1], 2.0 * radius, 2.0, ORANGE);
+}
+
+/*
+ * how the robot and each landmark estimate move along a weak direction, as
+ * segments reaching three standard deviations either way (its sign is arbitrary)
+ */
+pub fn draw_weak_direction(robot: (f32, f32), direction: &WeakDirection, color: Color) {
+    let scale = 3.0 * direction.stdev;
+    let segments = std::iter::once((robot.0, robot.1, direction.robot.x, direction.robot.y)).chain(direction.landmarks.iter().copied());
+
+    for (x, y, dx, dy) in segments {
+        draw_line(x - scale * dx, y - scale * dy, x + scale * dx, y + scale * dy, 2.0, color);
+        draw_circle(x, y, 2.0, color);
+    }
+}
+
+/*
+ * bars through the estimate reaching three standard deviations along its
+ * heading and across it
+ */
+pub fn draw_track_uncertainty(slam: &dyn SlamFilter, track: TrackStdevs, color: Color) {
+    let Pose2 { x, y, theta } = slam.estimated_pose();
+    let (sin, cos) = theta.sin_cos();
+    for (stdev, dx, dy) in [(track.along, cos, sin), (track.cross, -sin, cos)] {
+        let reach = 3.0 * stdev;
+        draw_line(x - reach * dx, y - reach * dy, x + reach * dx, y + reach * dy, 2.0, color);
+        for end in [-reach, reach] {
+            // short ticks across each end
+            let (ex, ey) = (x + end * dx, y + end * dy);
+            draw_line(ex - 3.0 * dy, ey + 3.0 * dx, ex + 3.0 * dy, ey - 3.0 * dx, 2.0, color);
+        }
+    }
+}
+
+/*
+ * shades scored cells by expected information gain and marks the best
+ * `num_suggested` cells as suggested next viewpoints
+ */
+pub fn draw_coverage_map(viewpoints: &[Viewpoint], cell_size: f32, num_suggested: usize) {
+    let max_score = viewpoints.first().map(|v| v.score).unwrap_or(0.0);
+    if max_score <= 0.0 { return; }
+
+    for viewpoint in viewpoints.iter() {
+        let intensity = viewpoint.score / max_score;
+        draw_rectangle(
+            viewpoint.x - cell_size / 2.0,
+            viewpoint.y - cell_size / 2.0,
+            cell_size,
+            cell_size,
+            Color::new(1.0, 0.8, 0.0, 0.25 * intensity)
+        );
+    }
+
+    for viewpoint in viewpoints.iter().take(num_suggested) {
+        let arm = cell_size / 4.0;
+        draw_line(viewpoint.x - arm, viewpoint.y - arm, viewpoint.x + arm, viewpoint.y + arm, 3.0, GOLD);
+        draw_line(viewpoint.x - arm, viewpoint.y + arm, viewpoint.x + arm, viewpoint.y - arm, 3.0, GOLD);
+        draw_circle_lines(viewpoint.x, viewpoint.y, arm * 1.5, 2.0, GOLD);
+    }
+}
+
+/*
+ * observation density, blue for rarely measured cells through red for the most
+ * measured; log scaled so a few heavily revisited cells don't wash out the rest
+ */
+pub fn draw_observation_heatmap(heatmap: &ObservationHeatmap) {
+    if heatmap.max_count == 0 { return; }
+    let max_log = (heatmap.max_count as f32).ln_1p();
+
+    for (&(i, j), &count) in &heatmap.counts {
+        let intensity = (count as f32).ln_1p() / max_log;
+        draw_rectangle(
+            i as f32 * heatmap.cell_size,
+            j as f32 * heatmap.cell_size,
+            heatmap.cell_size,
+            heatmap.cell_size,
+            Color::new(intensity, 0.2, 1.0 - intensity, 0.15 + 0.35 * intensity)
+        );
+    }
+}
+
+/*
+ * selected area, and an outline of the clipboard where it would be pasted
+ */
+pub fn draw_selection(area: Option<Rect>, clipboard: Option<&Template>, paste_at: Vec2, landmark_radius: f32) {
+    let color = Color::new(0.0, 0.8, 1.0, 0.8);
+
+    if let Some(area) = area {
+        draw_rectangle(area.x, area.y, area.w, area.h, Color::new(0.0, 0.8, 1.0, 0.1));
+        draw_rectangle_lines(area.x, area.y, area.w, area.h, 1.5, color);
+    }
+
+    if let Some(clipboard) = clipboard {
+        for landmark in &clipboard.landmarks {
+            draw_circle_lines(landmark.x + paste_at.x, landmark.y + paste_at.y, landmark_radius, 1.5, color);
+        }
+        for &(x, y, w, h) in &clipboard.obstructions {
+            draw_rectangle_lines(x + paste_at.x, y + paste_at.y, w, h, 1.5, color);
+        }
+    }
+}
+
+/*
+ * goals as rings connected in visiting order; the current goal is highlighted
+ * and reached goals are dimmed
+ */
+pub fn draw_mission(mission: &Mission) {
+    let current = mission.goals.iter().position(|goal| goal.reached_at.is_none());
+
+    for (i, pair) in mission.goals.windows(2).enumerate() {
+        let color = if current.is_some_and(|c| i + 1 >= c) { Color::new(0.0, 0.8, 1.0, 0.6) } else { Color::new(0.5, 0.5, 0.5, 0.4) };
+        draw_line(pair[0].x, pair[0].y, pair[1].x, pair[1].y, 1.5, color);
+    }
+
+    for (i, goal) in mission.goals.iter().enumerate() {
+        let color = if goal.reached_at.is_some() {
+            Color::new(0.5, 0.5, 0.5, 0.6)
+        } else if current == Some(i) {
+            Color::new(0.0, 0.8, 1.0, 1.0)
+        } else {
+            Color::new(0.0, 0.8, 1.0, 0.5)
+        };
+        draw_circle_lines(goal.x, goal.y, mission.tolerance, 2.0, color);
+        draw_circle(goal.x, goal.y, 3.0, color);
+    }
+}
+
+/*
+ * what the sensor can see from a pose: `range` deep over `fov` radians around
+ * the heading, less the blind zone within `min_range`, with its outline
+ */
+pub fn draw_sensor_wedge(pose: Pose2, range: f32, min_range: f32, fov: f32) {
+    let fill = Color::new(1.0, 1.0, 0.6, 0.06);
+    let outline = Color::new(1.0, 1.0, 0.6, 0.3);
+    let fov = fov.min(std::f32::consts::TAU);
+    let segments = (64.0 * fov / std::f32::consts::TAU).ceil().max(2.0) as usize;
+    let point = |radius: f32, bearing: f32| Vec2::from(pose.from_range_bearing(radius, bearing));
+
+    for i in 0..segments {
+        let start = -0.5 * fov + fov * i as f32 / segments as f32;
+        let end = -0.5 * fov + fov * (i + 1) as f32 / segments as f32;
+        let (outer_start, outer_end) = (point(range, start), point(range, end));
+        let (inner_start, inner_end) = (point(min_range, start), point(min_range, end));
+        draw_triangle(outer_start, outer_end, inner_end, fill);
+        draw_triangle(outer_start, inner_end, inner_start, fill);
+        draw_line(outer_start.x, outer_start.y, outer_end.x, outer_end.y, 1.0, outline);
+    }
+    if fov < std::f32::consts::TAU {
+        for bearing in [-0.5 * fov, 0.5 * fov] {
+            let (inner, outer) = (point(min_range, bearing), point(range, bearing));
+            draw_line(inner.x, inner.y, outer.x, outer.y, 1.0, outline);
+        }
+    }
+}
+
+/*
+ * a trail as a polyline fading out towards its oldest point
+ */
+fn draw_trail(trail: &VecDeque<(f32, f32)>, color: Color) {
+    for (i, (start, end)) in trail.iter().zip(trail.iter().skip(1)).enumerate() {
+        let alpha = color.a * Trails::fade(i + 1, trail.len());
+        draw_line(start.0, start.1, end.0, end.1, 1.5, Color { a: alpha, ..color });
+    }
+}
+
+/*
+ * the trails of where the robot was and was told to go, for the ground truth pane
+ */
+pub fn draw_true_trails(trails: &Trails) {
+    draw_trail(&trails.ground_truth, Trails::GROUND_TRUTH_COLOR);
+    draw_trail(&trails.commanded, Trails::COMMANDED_COLOR);
+}
+
+/*
+ * the trails of where the robot was believed to be, the estimate's in `estimate_color`
+ */
+pub fn draw_estimated_trails(trails: &Trails, estimate_color: Color) {
+    draw_trail(&trails.dead_reckoning, Trails::DEAD_RECKONING_COLOR);
+    draw_trail(&trails.estimate, estimate_color);
+}
+
+/*
+ * the tube as quads between consecutive samples, each side three cross-track
+ * stdevs from the path, with its outline
+ */
+pub fn draw_uncertainty_tube(tube: &UncertaintyTube, color: Color) {
+    let edges: Vec<((f32, f32), (f32, f32))> = tube.samples.iter()
+        .map(|sample| {
+            let (sin, cos) = sample.theta.sin_cos();
+            let reach = 3.0 * sample.cross;
+            ((sample.x - reach * sin, sample.y + reach * cos), (sample.x + reach * sin, sample.y - reach * cos))
+        })
+        .collect();
+    let fill = Color { a: 0.2, ..color };
+    for (before, after) in edges.iter().zip(edges.iter().skip(1)) {
+        let ((left_a, right_a), (left_b, right_b)) = (*before, *after);
+        draw_triangle(left_a.into(), right_a.into(), left_b.into(), fill);
+        draw_triangle(right_a.into(), right_b.into(), left_b.into(), fill);
+        draw_line(left_a.0, left_a.1, left_b.0, left_b.1, 1.0, color);
+        draw_line(right_a.0, right_a.1, right_b.0, right_b.1, 1.0, color);
+    }
+}
+
+pub fn draw_path(start: (f32, f32), path: &[(f32, f32)], color: Color) {
+    let mut previous = start;
+    for &point in path.iter() {
+        draw_line(previous.0, previous.1, point.0, point.1, 2.0, color);
+        previous = point;
+    }
+    if let Some(&(x, y)) = path.last() {
+        draw_circle_lines(x, y, 6.0, 2.0, color);
+    }
+}
+
+pub fn draw_scan(pose: Pose2, scan: &Scan) {
+    for (i, range) in scan.ranges.iter().enumerate() {
+        let (end_x, end_y) = pose.from_range_bearing(*range, scan.angle(i));
+        draw_line(pose.x, pose.y, end_x, end_y, 1.0, Color::new(1.0, 0.3, 0.3, 0.15));
+        if *range < scan.max_range {
+            draw_circle(end_x, end_y, 2.0, Color::new(1.0, 0.3, 0.3, 0.9));
+        }
+    }
+}
+
+/*
+ * the obstructions moved by the gap between the true and the estimated pose,
+ * over the lidar hits placed from past estimated poses
+ */
+pub fn draw_believed_walls<'a>(obstructions: impl IntoIterator<Item = &'a Rect>, scan_map: &ScanMap, truth: Pose2, estimate: Pose2, color: Color) {
+    for &(x, y) in &scan_map.points {
+        draw_rectangle(x - 1.0, y - 1.0, 2.0, 2.0, Color { a: 0.5, ..color });
+    }
+    for rect in obstructions {
+        let corners = believed_corners(*rect, truth, estimate);
+        for (i, &(x, y)) in corners.iter().enumerate() {
+            let (next_x, next_y) = corners[(i + 1) % corners.len()];
+            draw_line(x, y, next_x, next_y, 1.5, color);
+        }
+    }
+}
+
+/*
+ * fading markers where recent events happened
+ */
+pub fn draw_events(event_log: &EventLog, now: f32, window: f32) {
+    for event in event_log.recent(now, window) {
+        let Some((x, y)) = event.position else { continue; };
+        let alpha = 1.0 - (now - event.time) / window;
+        draw_circle_lines(x, y, 8.0, 2.0, Color::new(1.0, 0.6, 0.3, alpha));
+    }
+}
@@ -0,0 +1,80 @@
+use macroquad::prelude::*;
+use crate::app::locale::Language;
+use crate::recording;
+use crate::report::Report;
+use crate::summary::SessionSummary;
+use super::{font_size, line_spacing};
+
+/*
+ * end-of-run statistics; E exports them as JSON to `summaries/`, H writes the
+ * full HTML report to `reports/`, Enter or Esc (or closing the window) moves on
+ */
+pub async fn run(summary: &SessionSummary, report: &Report, title: &str, font: &Font, lang: Language) {
+    let mut exported: Option<String> = None;
+
+    loop {
+        clear_background(Color::new(0.1, 0.1, 0.1, 1.0));
+
+        let lines = [
+            format!("{}: {:.1} s", lang.tr("Duration"), summary.duration),
+            format!("{}: {:.0}", lang.tr("Distance traveled"), summary.distance_traveled),
+            format!("{}: {} {} {}", lang.tr("Landmarks mapped"), summary.landmarks_mapped, lang.tr("of"), summary.landmarks_total),
+            format!(
+                "{}: {:.2} ({} {}, {} {})",
+                lang.tr("Map RMSE"), summary.map_rmse,
+                summary.unmatched_landmarks, lang.tr("unmatched"), summary.spurious_landmarks, lang.tr("spurious")
+            ),
+            format!("{}: {:.2}", lang.tr("Final position error"), summary.final_error),
+            format!("{}: {:.2}", lang.tr("ATE (position RMSE)"), summary.ate),
+            format!(
+                "{}: {:.2} ({:+.0}% {})",
+                lang.tr("Dead reckoning ATE"), summary.odometry_ate,
+                100.0 * (summary.ate - summary.odometry_ate) / summary.odometry_ate.max(1e-6), lang.tr("with SLAM")
+            ),
+            format!("{}: {}", lang.tr("Loop closures"), summary.loop_closures),
+            format!("{}: {:.2} ({})", lang.tr("Average pose NEES"), summary.mean_nees, lang.tr("consistent near 3")),
+            format!("{}: {:.1}", lang.tr("Peak position covariance trace"), summary.peak_covariance_trace),
+        ];
+
+        let left = screen_width() / 2.0 - 220.0;
+        let top = screen_height() / 2.0 - (lines.len() as f32 / 2.0 + 2.0) * line_spacing();
+        draw_label(font, title, left, top, WHITE);
+        for (i, line) in lines.iter().enumerate() {
+            draw_label(font, line, left, top + (i as f32 + 1.5) * line_spacing(), LIGHTGRAY);
+        }
+        let footer = exported.as_deref().unwrap_or(lang.tr("E: export as JSON, H: HTML report, Enter: continue"));
+        draw_label(font, footer, left, top + (lines.len() as f32 + 2.5) * line_spacing(), GRAY);
+
+        // input after the first frame, so the key or close request that ended the run doesn't also end this
+        next_frame().await;
+        if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::Enter) || is_quit_requested() { break; }
+        if is_key_pressed(KeyCode::E) {
+            let path = recording::timestamped_path("summaries", "summary", "json");
+            exported = Some(match summary.save(&path) {
+                Ok(()) => format!("{} {}", lang.tr("Saved to"), path.display()),
+                Err(error) => format!("{}: {error}", lang.tr("Could not save summary")),
+            });
+        }
+        if is_key_pressed(KeyCode::H) {
+            let path = recording::timestamped_path("reports", "report", "html");
+            exported = Some(match report.save(&path) {
+                Ok(()) => format!("{} {}", lang.tr("Saved to"), path.display()),
+                Err(error) => format!("{}: {error}", lang.tr("Could not save report")),
+            });
+        }
+    }
+}
+
+fn draw_label(font: &Font, text: &str, x: f32, y: f32, color: Color) {
+    draw_text_ex(
+        text,
+        x,
+        y,
+        TextParams {
+            font: Some(font),
+            font_size: font_size(),
+            color,
+            ..Default::default()
+        }
+    );
+}
@@ -1,13 +1,138 @@
+use serde::{Deserialize, Serialize};
+use crate::app::layout::HudLayout;
+use crate::app::locale::Language;
+use crate::slam::Backend;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct UserSettings {
     // states
     pub show_ekf_state: bool,
     pub show_fast_state: bool,
     pub show_graph_state: bool,
+    pub show_seif_state: bool,
 
     // landmark estimates
     pub show_ekf_landmarks: bool,
     pub show_fast_landmarks: bool,
     pub show_graph_landmarks: bool,
+    pub show_seif_landmarks: bool,
+
+    // FastSLAM's particle cloud and the map its heaviest particle carries
+    pub show_fast_particles: bool,
+
+    // run the robocentric EKF next to the world-centric one
+    pub robocentric_ekf: bool,
+    // run UKF-SLAM next to the EKF
+    pub ukf_slam: bool,
+
+    // evaluate the world-centric EKF's jacobians at first estimates
+    pub fej_ekf: bool,
+
+    // run an EKF that matches measurements to landmarks itself, keeping a few
+    // association hypotheses, instead of being told the ids
+    pub mht_ekf: bool,
+
+    // run two EKFs that use only the bearings, one initializing landmarks with
+    // inverse depth and one by triangulation
+    pub bearing_only_ekfs: bool,
+
+    // learn the EKF's measurement noise from its residuals instead of trusting `Config`
+    pub adaptive_noise: bool,
+
+    // information matrix links of the SEIF
+    pub show_seif_sparsity: bool,
+
+    // least constrained direction of the EKF state, drawn as arrows on its estimates
+    pub show_weak_directions: bool,
+
+    // EKF position uncertainty along and across the heading, drawn at its estimate
+    pub show_track_uncertainty: bool,
+
+    // commanded, actual, and noisy velocities and the acceleration inputs, as bars at the bottom
+    pub show_motion_gauges: bool,
+
+    // landmark estimate styling
+    pub color_by_uncertainty: bool,
+    pub show_covariance_ellipses: bool,
+
+    // ghost trajectories of the commanded motion and of dead reckoning, next to
+    // the true and estimated ones
+    pub show_trails: bool,
+    // in split view, every trail in both panes instead of the true ones with the
+    // world and the believed ones with the estimates
+    pub overlay_trails: bool,
+
+    // the estimated trajectory as a tube three cross-track stdevs wide at each past pose
+    pub show_uncertainty_tube: bool,
+
+    // traffic light of how far to trust the EKF's estimate
+    pub show_health: bool,
+
+    // exploration overlays
+    pub show_coverage_map: bool,
+    pub show_observation_heatmap: bool,
+
+    // lidar and local obstacle avoidance
+    pub show_lidar: bool,
+
+    // the area the landmark sensor sees, by its range and field of view
+    pub show_sensor_wedge: bool,
+
+    // the walls where the EKF believes them: moved by its pose error, and its
+    // lidar hits placed from its estimated poses
+    pub show_believed_walls: bool,
+    pub dwa_avoidance: bool,
+
+    // feed stuck detection to the filters as a zero-velocity update
+    pub contact_zupt: bool,
+
+    // under control latency, predict from the commands as the wheels execute them
+    // instead of as they were issued
+    pub predict_delayed_commands: bool,
+
+    // hold the estimate still while parked instead of letting process noise wander it
+    pub parked_zupt: bool,
+
+    // follow the EKF estimate with its heading pointing up instead of a north-up world view
+    pub robot_centric_view: bool,
+
+    // ground truth on the left, estimates on the right, each with its own pan and zoom
+    // unless the cameras are linked
+    pub split_view: bool,
+    pub link_cameras: bool,
+
+    // obstruction tool snaps corners to `Config::snap_unit`
+    pub snap_to_grid: bool,
+
+    // drop to a low redraw rate while paused or left alone
+    pub idle_throttling: bool,
+
+    // of the HUD text, switched with L
+    pub language: Language,
+
+    // HUD scale, changed with Ctrl + and Ctrl -, and where each panel sits
+    pub hud_layout: HudLayout,
+}
+
+impl UserSettings {
+    // the filters without a toggle of their own are drawn whenever they run
+    pub fn shows_state(&self, backend: Backend) -> bool {
+        match backend {
+            Backend::Ekf | Backend::FejEkf => self.show_ekf_state,
+            Backend::FastSlam => self.show_fast_state,
+            Backend::Seif => self.show_seif_state,
+            Backend::RobocentricEkf | Backend::Ukf | Backend::MhtEkf => true,
+        }
+    }
+
+    pub fn shows_landmarks(&self, backend: Backend) -> bool {
+        match backend {
+            Backend::Ekf | Backend::FejEkf => self.show_ekf_landmarks,
+            Backend::FastSlam => self.show_fast_landmarks,
+            Backend::Seif => self.show_seif_landmarks,
+            Backend::RobocentricEkf | Backend::Ukf | Backend::MhtEkf => true,
+        }
+    }
 }
 
 impl Default for UserSettings {
@@ -17,8 +142,43 @@ impl Default for UserSettings {
             show_fast_state: true,
             show_ekf_landmarks: true,
             show_fast_landmarks: true,
+            show_fast_particles: true,
             show_graph_state: true,
             show_graph_landmarks: true,
+            show_seif_state: true,
+            show_seif_landmarks: true,
+            show_seif_sparsity: false,
+            show_weak_directions: false,
+            show_track_uncertainty: false,
+            show_motion_gauges: false,
+            robocentric_ekf: false,
+            ukf_slam: false,
+            fej_ekf: false,
+            mht_ekf: false,
+            bearing_only_ekfs: false,
+            adaptive_noise: false,
+            color_by_uncertainty: false,
+            show_covariance_ellipses: false,
+            show_trails: false,
+            overlay_trails: false,
+            show_uncertainty_tube: false,
+            show_health: true,
+            show_coverage_map: false,
+            show_observation_heatmap: false,
+            show_lidar: false,
+            show_sensor_wedge: true,
+            show_believed_walls: false,
+            dwa_avoidance: true,
+            contact_zupt: false,
+            predict_delayed_commands: false,
+            parked_zupt: true,
+            robot_centric_view: false,
+            split_view: false,
+            link_cameras: true,
+            snap_to_grid: false,
+            idle_throttling: true,
+            language: Language::English,
+            hud_layout: HudLayout::new(),
         }
     }
 }
@@ -0,0 +1,151 @@
+use std::path::Path;
+use macroquad::prelude::*;
+
+use crate::config::Config;
+use crate::math::Pose2;
+use crate::recording::Recording;
+use crate::slam::{EkfSlam, FastSlam, OdometryBaseline, SeifSlam, SlamFilter};
+use crate::video::{self, VideoSettings};
+use super::renderer;
+
+// longest trail drawn per pose source; longer runs are thinned to this many points
+const MAX_TRAIL_POINTS: usize = 2000;
+
+fn estimator_color(name: &str) -> Color {
+    let color = match name {
+        "ekf" => EkfSlam::COLOR,
+        "fast" => FastSlam::COLOR,
+        "seif" => SeifSlam::COLOR,
+        "odom" => OdometryBaseline::COLOR,
+        _ => GRAY,
+    };
+    Color { a: 0.9, ..color }
+}
+
+/*
+ * renders the recording offline into `dir` as a PNG per video frame, at the
+ * settings' size and a fixed 1/fps apart, whatever the window is doing. Each
+ * frame shows the recorded frame due at its time: the true and estimated
+ * trails so far, the poses, the observations of the frame, and, when the
+ * recording kept its inputs, the map of an EKF replayed alongside. Returns the
+ * number of frames written
+ */
+pub async fn run(recording: &Recording, settings: VideoSettings, dir: &Path, font: &Font, cfg: &Config) -> Result<usize, String> {
+    std::fs::create_dir_all(dir).map_err(|error| format!("could not create {}: {error}", dir.display()))?;
+
+    let times: Vec<f32> = recording.frames.iter().map(|frame| frame.time).collect();
+    let schedule = video::frame_schedule(&times, settings.fps);
+    if schedule.is_empty() { return Err("the recording has no frames".to_string()); }
+
+    // the whole run fits in the frame, at the frame's aspect ratio
+    let (width, height) = (settings.width as f32, settings.height as f32);
+    let bounds = video::world_bounds(recording, cfg.video_margin);
+    let units = bounds.w.max(bounds.h * width / height);
+    let center = bounds.center();
+    let pixel = units / width; // world units per pixel
+
+    let target = render_target(settings.width, settings.height);
+    target.texture.set_filter(FilterMode::Linear);
+    // a render target camera isn't flipped, so the world stays y-up and the text camera is y-down
+    let world_camera = Camera2D {
+        zoom: vec2(2.0 / units, 2.0 * width / (units * height)),
+        target: center,
+        render_target: Some(target.clone()),
+        ..Default::default()
+    };
+    let text_camera = Camera2D {
+        zoom: vec2(2.0 / width, -2.0 / height),
+        target: vec2(width / 2.0, height / 2.0),
+        render_target: Some(target.clone()),
+        ..Default::default()
+    };
+
+    // an EKF fed the recorded inputs, for the map; only when the recording has them
+    let ekf_start = recording.estimator_index("ekf").and_then(|index| recording.start.get(index).copied());
+    let mut ekf = ekf_start
+        .filter(|_| recording.inputs.len() == recording.frames.len())
+        .map(|start| EkfSlam::with_prior(start, cfg.initial_stdev_position, cfg.initial_stdev_heading));
+    let mut ekf_frames = 0;
+
+    let stride = (recording.frames.len() / MAX_TRAIL_POINTS).max(1);
+    let font_size = (height / 36.0) as u16;
+    let line = height / 28.0;
+
+    for (frame, &index) in schedule.iter().enumerate() {
+        let recorded = &recording.frames[index];
+
+        if let Some(ekf) = &mut ekf {
+            for inputs in &recording.inputs[ekf_frames..=index] {
+                if let Some((linear, angular)) = inputs.odometry { ekf.predict(linear, angular, inputs.delta_time, cfg); }
+                ekf.update(&inputs.observations, cfg);
+            }
+            ekf_frames = index + 1;
+        }
+
+        set_camera(&world_camera);
+        clear_background(Color::new(0.1, 0.1, 0.1, 1.0));
+        renderer::draw_gridlines(center.x, center.y, width, height, units, cfg.grid_unit);
+
+        if let Some(ekf) = &ekf { renderer::draw_slam_landmarks(ekf, cfg.landmark_radius); }
+
+        // the frame's observations, from the true pose
+        let Pose2 { x, y, .. } = recorded.ground_truth;
+        if let Some(inputs) = recording.inputs.get(index) {
+            for observation in &inputs.observations {
+                let (end_x, end_y) = recorded.ground_truth.from_range_bearing(observation.range, observation.bearing);
+                draw_line(x, y, end_x, end_y, pixel, Color::new(1.0, 1.0, 1.0, 0.3));
+                draw_circle(end_x, end_y, 3.0 * pixel, Color::new(1.0, 1.0, 1.0, 0.6));
+            }
+        }
+
+        let trail = |pose: &dyn Fn(usize) -> Pose2, color: Color| {
+            let points: Vec<Pose2> = (0..=index).step_by(stride).chain(std::iter::once(index)).map(pose).collect();
+            for pair in points.windows(2) {
+                draw_line(pair[0].x, pair[0].y, pair[1].x, pair[1].y, 2.0 * pixel, color);
+            }
+        };
+        trail(&|i| recording.frames[i].ground_truth, WHITE);
+        for (estimator, name) in recording.estimator_names.iter().enumerate() {
+            trail(&|i| recording.frames[i].estimates[estimator], estimator_color(name));
+        }
+
+        for (name, &estimate) in recording.estimator_names.iter().zip(&recorded.estimates) {
+            renderer::draw_robot(estimate, cfg.robot_radius, Color { a: 0.5, ..estimator_color(name) }, WHITE);
+        }
+        renderer::draw_robot(recorded.ground_truth, cfg.robot_radius, BLUE, WHITE);
+
+        // time and each estimator's position error
+        set_camera(&text_camera);
+        let label = |text: &str, row: usize, color: Color| {
+            draw_text_ex(text, 0.02 * width, line * (1.0 + row as f32), TextParams { font: Some(font), font_size, color, ..Default::default() });
+        };
+        label(&format!("t = {:.2} s", recorded.time - recording.frames[0].time), 0, WHITE);
+        for (row, (name, estimate)) in recording.estimator_names.iter().zip(&recorded.estimates).enumerate() {
+            let error = (estimate.x - x).hypot(estimate.y - y);
+            label(&format!("{name}: error {error:.1}"), row + 1, estimator_color(name));
+        }
+
+        unsafe { get_internal_gl() }.flush();
+        let path = video::frame_path(dir, frame);
+        target.texture.get_texture_data().export_png(&path.to_string_lossy());
+
+        // a preview with the progress in the window
+        set_default_camera();
+        clear_background(BLACK);
+        let scale = (screen_width() / width).min((screen_height() - 40.0) / height);
+        draw_texture_ex(&target.texture, 0.0, 40.0, WHITE, DrawTextureParams {
+            dest_size: Some(vec2(width * scale, height * scale)),
+            flip_y: true,
+            ..Default::default()
+        });
+        draw_text_ex(
+            &format!("rendering frame {} of {} to {}", frame + 1, schedule.len(), dir.display()),
+            10.0,
+            25.0,
+            TextParams { font: Some(font), font_size: 20, color: WHITE, ..Default::default() }
+        );
+        next_frame().await;
+    }
+
+    Ok(schedule.len())
+}
@@ -0,0 +1,83 @@
+use macroquad::prelude::*;
+use crate::config::Config;
+
+/*
+ * pan and zoom of one world pane, on top of following the robot
+ */
+#[derive(Clone, Copy)]
+pub struct PaneView {
+    pub pan: Vec2, // world offset from the followed point
+    pub zoom: f32,
+    drag_from: Option<Vec2>, // screen position of the middle-drag so far
+}
+
+impl PaneView {
+    pub fn new() -> Self {
+        Self { pan: Vec2::ZERO, zoom: 1.0, drag_from: None }
+    }
+
+    /*
+     * world units across `area`; a half-width pane keeps the full view's scale
+     */
+    pub fn units(&self, area: Rect, cfg: &Config) -> f32 {
+        cfg.horizontal_units * area.w / screen_width() / self.zoom
+    }
+
+    /*
+     * camera looking at `follow` plus the pan, drawn into `area`; its viewport is in
+     * logical pixels, which `screen_to_world` expects, so draw through `for_drawing`
+     */
+    pub fn camera(&self, follow: Vec2, rotation: f32, area: Rect, cfg: &Config) -> Camera2D {
+        let units = self.units(area, cfg);
+        Camera2D {
+            target: follow + self.pan,
+            rotation,
+            zoom: vec2(2.0 / units, -2.0 / units * area.w / area.h),
+            viewport: Some((area.x as i32, (screen_height() - area.y - area.h) as i32, area.w as i32, area.h as i32)),
+            ..Default::default()
+        }
+    }
+
+    /*
+     * mouse wheel zooms and middle-drag pans while the mouse is over `area`
+     */
+    pub fn input(&mut self, camera: &Camera2D, area: Rect) {
+        let mouse: Vec2 = mouse_position().into();
+
+        if !is_mouse_button_down(MouseButton::Middle) {
+            self.drag_from = None;
+        } else if let Some(from) = self.drag_from {
+            self.pan += camera.screen_to_world(from) - camera.screen_to_world(mouse);
+            self.drag_from = Some(mouse);
+        } else if area.contains(mouse) {
+            self.drag_from = Some(mouse);
+        }
+
+        let (_, wheel) = mouse_wheel();
+        if wheel != 0.0 && area.contains(mouse) {
+            let step = if wheel > 0.0 { 1.1 } else { 1.0 / 1.1 };
+            self.zoom = (self.zoom * step).clamp(0.1, 20.0);
+        }
+    }
+}
+
+/*
+ * the same camera with its viewport in framebuffer pixels, as drawing needs on
+ * high-dpi screens
+ */
+pub fn for_drawing(camera: &Camera2D) -> Camera2D {
+    let scale = screen_dpi_scale();
+    Camera2D {
+        rotation: camera.rotation,
+        zoom: camera.zoom,
+        target: camera.target,
+        offset: camera.offset,
+        viewport: camera.viewport.map(|(x, y, w, h)| (
+            (x as f32 * scale) as i32,
+            (y as f32 * scale) as i32,
+            (w as f32 * scale) as i32,
+            (h as f32 * scale) as i32,
+        )),
+        ..Default::default()
+    }
+}
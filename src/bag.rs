@@ -0,0 +1,381 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::math::Pose2;
+use crate::simulation::{Landmark, Scan};
+use crate::slam::SlamFilter;
+
+const MAGIC: &[u8] = b"\x89MCAP0\r\n";
+
+// MCAP record opcodes
+const OP_HEADER: u8 = 0x01;
+const OP_FOOTER: u8 = 0x02;
+const OP_SCHEMA: u8 = 0x03;
+const OP_CHANNEL: u8 = 0x04;
+const OP_MESSAGE: u8 = 0x05;
+const OP_DATA_END: u8 = 0x0F;
+
+// visualization_msgs/Marker types
+const SPHERE: i32 = 2;
+const CYLINDER: i32 = 3;
+
+/*
+ * ROS 2 message definitions, in the concatenated form ros2msg schemas carry:
+ * the message itself, then every type it uses
+ */
+const TIME: (&str, &str) = ("builtin_interfaces/Time", "int32 sec\nuint32 nanosec");
+const DURATION: (&str, &str) = ("builtin_interfaces/Duration", "int32 sec\nuint32 nanosec");
+const HEADER: (&str, &str) = ("std_msgs/Header", "builtin_interfaces/Time stamp\nstring frame_id");
+const COLOR: (&str, &str) = ("std_msgs/ColorRGBA", "float32 r\nfloat32 g\nfloat32 b\nfloat32 a");
+const POINT: (&str, &str) = ("geometry_msgs/Point", "float64 x\nfloat64 y\nfloat64 z");
+const VECTOR3: (&str, &str) = ("geometry_msgs/Vector3", "float64 x\nfloat64 y\nfloat64 z");
+const QUATERNION: (&str, &str) = ("geometry_msgs/Quaternion", "float64 x\nfloat64 y\nfloat64 z\nfloat64 w");
+const POSE: (&str, &str) = ("geometry_msgs/Pose", "geometry_msgs/Point position\ngeometry_msgs/Quaternion orientation");
+const POSE_WITH_COVARIANCE: (&str, &str) = ("geometry_msgs/PoseWithCovariance", "geometry_msgs/Pose pose\nfloat64[36] covariance");
+const TRANSFORM: (&str, &str) = ("geometry_msgs/Transform", "geometry_msgs/Vector3 translation\ngeometry_msgs/Quaternion rotation");
+const TRANSFORM_STAMPED: (&str, &str) = ("geometry_msgs/TransformStamped", "std_msgs/Header header\nstring child_frame_id\ngeometry_msgs/Transform transform");
+const MARKER: (&str, &str) = ("visualization_msgs/Marker", "std_msgs/Header header\nstring ns\nint32 id\nint32 type\nint32 action\n\
+    geometry_msgs/Pose pose\ngeometry_msgs/Vector3 scale\nstd_msgs/ColorRGBA color\nbuiltin_interfaces/Duration lifetime\nbool frame_locked\n\
+    geometry_msgs/Point[] points\nstd_msgs/ColorRGBA[] colors\nstring text\nstring mesh_resource\nbool mesh_use_embedded_materials");
+
+struct MessageType {
+    name: &'static str,
+    definition: &'static str,
+    dependencies: &'static [(&'static str, &'static str)],
+}
+
+const POSE_STAMPED: MessageType = MessageType {
+    name: "geometry_msgs/msg/PoseStamped",
+    definition: "std_msgs/Header header\ngeometry_msgs/Pose pose",
+    dependencies: &[HEADER, TIME, POSE, POINT, QUATERNION],
+};
+const POSE_WITH_COVARIANCE_STAMPED: MessageType = MessageType {
+    name: "geometry_msgs/msg/PoseWithCovarianceStamped",
+    definition: "std_msgs/Header header\ngeometry_msgs/PoseWithCovariance pose",
+    dependencies: &[HEADER, TIME, POSE_WITH_COVARIANCE, POSE, POINT, QUATERNION],
+};
+const TF_MESSAGE: MessageType = MessageType {
+    name: "tf2_msgs/msg/TFMessage",
+    definition: "geometry_msgs/TransformStamped[] transforms",
+    dependencies: &[TRANSFORM_STAMPED, HEADER, TIME, TRANSFORM, VECTOR3, QUATERNION],
+};
+const LASER_SCAN: MessageType = MessageType {
+    name: "sensor_msgs/msg/LaserScan",
+    definition: "std_msgs/Header header\nfloat32 angle_min\nfloat32 angle_max\nfloat32 angle_increment\nfloat32 time_increment\n\
+        float32 scan_time\nfloat32 range_min\nfloat32 range_max\nfloat32[] ranges\nfloat32[] intensities",
+    dependencies: &[HEADER, TIME],
+};
+const MARKER_ARRAY: MessageType = MessageType {
+    name: "visualization_msgs/msg/MarkerArray",
+    definition: "visualization_msgs/Marker[] markers",
+    dependencies: &[MARKER, HEADER, TIME, POSE, POINT, QUATERNION, VECTOR3, COLOR, DURATION],
+};
+
+impl MessageType {
+    fn schema_text(&self) -> String {
+        let mut text = self.definition.to_string();
+        for (name, definition) in self.dependencies {
+            text += &format!("\n{}\nMSG: {name}\n{definition}", "=".repeat(80));
+        }
+        text
+    }
+}
+
+/*
+ * a session written as an MCAP file of CDR-encoded ROS 2 messages, for
+ * Foxglove Studio or `ros2 bag play`. Everything lives in the `map` frame:
+ * - /tf: map -> base_link (ground truth) and map -> <estimator>/base_link
+ * - /ground_truth/pose and /<estimator>/pose (with the pose covariance)
+ * - /scan: the lidar, in base_link
+ * - /landmarks and /<estimator>/landmarks: markers, estimates drawn as their 3 sigma ellipses
+ * World units are scaled by `meters_per_unit`.
+ */
+pub struct BagWriter {
+    buffer: Vec<u8>,
+    meters_per_unit: f64,
+    marker_period: f32,
+    landmark_radius: f32,
+    last_markers: Option<f32>,
+    schemas: HashMap<&'static str, u16>,
+    channels: HashMap<String, u16>,
+    sequence: u32,
+}
+
+impl BagWriter {
+    pub fn new(cfg: &Config) -> Self {
+        let mut bag = Self {
+            buffer: MAGIC.to_vec(),
+            meters_per_unit: cfg.bag_meters_per_unit as f64,
+            marker_period: cfg.bag_marker_period,
+            landmark_radius: cfg.landmark_radius,
+            last_markers: None,
+            schemas: HashMap::new(),
+            channels: HashMap::new(),
+            sequence: 0,
+        };
+        let mut header = Vec::new();
+        put_string(&mut header, "ros2");
+        put_string(&mut header, "slam-simulator");
+        bag.record(OP_HEADER, &header);
+        bag
+    }
+
+    /*
+     * one simulation frame; the landmark markers are only rewritten every
+     * `marker_period` seconds, as they barely change between frames
+     */
+    pub fn push(&mut self, time: f32, ground_truth: Pose2, estimators: &[(&str, &dyn SlamFilter)], landmarks: &[Landmark], scan: Option<&Scan>) {
+        let mut transforms = vec![("base_link".to_string(), ground_truth)];
+        transforms.extend(estimators.iter().map(|(name, slam)| (format!("{name}/base_link"), slam.estimated_pose())));
+        let mut tf = Cdr::new();
+        tf.u32(transforms.len() as u32);
+        for (child, Pose2 { x, y, theta }) in &transforms {
+            tf.header(time, "map");
+            tf.string(child);
+            tf.point(self.scaled(*x), self.scaled(*y));
+            tf.quaternion(*theta);
+        }
+        self.message("/tf", &TF_MESSAGE, time, tf);
+
+        let mut pose = Cdr::new();
+        pose.header(time, "map");
+        pose.pose(self.scaled(ground_truth.x), self.scaled(ground_truth.y), ground_truth.theta);
+        self.message("/ground_truth/pose", &POSE_STAMPED, time, pose);
+
+        for (name, slam) in estimators {
+            let Pose2 { x, y, theta } = slam.estimated_pose();
+            let covariance = slam.pose_covariance();
+            let mut pose = Cdr::new();
+            pose.header(time, "map");
+            pose.pose(self.scaled(x), self.scaled(y), theta);
+            // x, y and yaw sit at rows 0, 1 and 5 of the 6x6 (x, y, z, roll, pitch, yaw) covariance
+            let rows = [0, 1, 5];
+            let mut full = [0.0; 36];
+            for i in 0..3 {
+                for j in 0..3 {
+                    let unit = |state: usize| if state < 2 { self.meters_per_unit } else { 1.0 };
+                    full[rows[i] * 6 + rows[j]] = covariance[(i, j)] as f64 * unit(i) * unit(j);
+                }
+            }
+            pose.align(8);
+            for value in full { pose.f64(value); }
+            self.message(&format!("/{name}/pose"), &POSE_WITH_COVARIANCE_STAMPED, time, pose);
+        }
+
+        if let Some(scan) = scan {
+            let count = scan.ranges.len();
+            let increment = std::f32::consts::TAU / count as f32;
+            let mut message = Cdr::new();
+            message.header(time, "base_link");
+            for value in [scan.angle(0), scan.angle(count.saturating_sub(1)), increment, 0.0, 0.0, 0.0, scan.max_range * self.meters_per_unit as f32] {
+                message.f32(value);
+            }
+            // beams that hit nothing are reported as out of range
+            message.u32(count as u32);
+            for &range in &scan.ranges {
+                message.f32(if range < scan.max_range { range * self.meters_per_unit as f32 } else { f32::INFINITY });
+            }
+            message.u32(0);
+            self.message("/scan", &LASER_SCAN, time, message);
+        }
+
+        if self.last_markers.is_none_or(|last| time - last >= self.marker_period) {
+            self.last_markers = Some(time);
+
+            let truth: Vec<_> = landmarks.iter().map(|landmark| (landmark.id, landmark.x, landmark.y, None)).collect();
+            let markers = self.markers(time, "landmarks", &truth, CYLINDER, [1.0, 1.0, 1.0, 1.0]);
+            self.message("/landmarks", &MARKER_ARRAY, time, markers);
+
+            for (name, slam) in estimators {
+                let estimates: Vec<_> = slam.estimated_map().into_iter()
+                    .map(|(id, x, y)| (id, x, y, slam.landmark_covariance(id)))
+                    .collect();
+                let color = slam.color();
+                let markers = self.markers(time, name, &estimates, SPHERE, [color.r, color.g, color.b, 0.5]);
+                self.message(&format!("/{name}/landmarks"), &MARKER_ARRAY, time, markers);
+            }
+        }
+    }
+
+    pub fn save(mut self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.finish())
+    }
+
+    /*
+     * closes the data section; without a summary section readers scan the
+     * messages in order, which is all a single recording needs
+     */
+    pub fn finish(&mut self) -> Vec<u8> {
+        self.record(OP_DATA_END, &0u32.to_le_bytes());
+        self.record(OP_FOOTER, &[0; 20]);
+        let mut bytes = std::mem::take(&mut self.buffer);
+        bytes.extend_from_slice(MAGIC);
+        bytes
+    }
+
+    fn scaled(&self, value: f32) -> f64 {
+        value as f64 * self.meters_per_unit
+    }
+
+    /*
+     * one marker per landmark; with a covariance the marker is its 3 sigma ellipse
+     */
+    fn markers(&self, time: f32, namespace: &str, landmarks: &[(usize, f32, f32, Option<nalgebra::Matrix2<f32>>)], shape: i32, color: [f32; 4]) -> Cdr {
+        let mut message = Cdr::new();
+        message.u32(landmarks.len() as u32);
+        for &(id, x, y, covariance) in landmarks {
+            let (angle, major, minor) = match covariance {
+                Some(sigma) => {
+                    let eigen = sigma.symmetric_eigen();
+                    let axis = eigen.eigenvectors.column(0);
+                    (axis[1].atan2(axis[0]), eigen.eigenvalues[0].max(0.0).sqrt(), eigen.eigenvalues[1].max(0.0).sqrt())
+                }
+                None => (0.0, 1.0, 1.0),
+            };
+            message.header(time, "map");
+            message.string(namespace);
+            message.i32(id as i32);
+            message.i32(shape);
+            message.i32(0); // ADD
+            message.pose(self.scaled(x), self.scaled(y), angle);
+            // scale: the 3 sigma ellipse's axes, or the landmark's diameter
+            match covariance {
+                Some(_) => { message.f64(6.0 * self.scaled(major)); message.f64(6.0 * self.scaled(minor)); }
+                None => { message.f64(2.0 * self.scaled(self.landmark_radius)); message.f64(2.0 * self.scaled(self.landmark_radius)); }
+            }
+            message.f64(2.0 * self.scaled(self.landmark_radius));
+            for channel in color { message.f32(channel); }
+            message.i32(0); // lifetime 0 keeps the marker until it is replaced
+            message.u32(0);
+            message.bool(false);
+            message.u32(0);
+            message.u32(0);
+            message.string("");
+            message.string("");
+            message.bool(false);
+        }
+        message
+    }
+
+    fn message(&mut self, topic: &str, message_type: &MessageType, time: f32, message: Cdr) {
+        let channel = self.channel(topic, message_type);
+        let nanoseconds = (time.max(0.0) as f64 * 1e9) as u64;
+
+        let mut content = Vec::with_capacity(22 + message.bytes.len());
+        content.extend_from_slice(&channel.to_le_bytes());
+        content.extend_from_slice(&self.sequence.to_le_bytes());
+        content.extend_from_slice(&nanoseconds.to_le_bytes()); // log time
+        content.extend_from_slice(&nanoseconds.to_le_bytes()); // publish time
+        content.extend_from_slice(&message.bytes);
+        self.record(OP_MESSAGE, &content);
+        self.sequence += 1;
+    }
+
+    /*
+     * channel of `topic`, writing its schema and channel records the first time
+     */
+    fn channel(&mut self, topic: &str, message_type: &MessageType) -> u16 {
+        if let Some(&id) = self.channels.get(topic) { return id; }
+
+        let schema = match self.schemas.get(message_type.name) {
+            Some(&id) => id,
+            None => {
+                let id = self.schemas.len() as u16 + 1; // schema id 0 means "no schema"
+                let text = message_type.schema_text();
+                let mut content = id.to_le_bytes().to_vec();
+                put_string(&mut content, message_type.name);
+                put_string(&mut content, "ros2msg");
+                put_string(&mut content, &text);
+                self.record(OP_SCHEMA, &content);
+                self.schemas.insert(message_type.name, id);
+                id
+            }
+        };
+
+        let id = self.channels.len() as u16;
+        let mut content = id.to_le_bytes().to_vec();
+        content.extend_from_slice(&schema.to_le_bytes());
+        put_string(&mut content, topic);
+        put_string(&mut content, "cdr");
+        content.extend_from_slice(&0u32.to_le_bytes()); // no metadata
+        self.record(OP_CHANNEL, &content);
+        self.channels.insert(topic.to_string(), id);
+        id
+    }
+
+    fn record(&mut self, opcode: u8, content: &[u8]) {
+        self.buffer.push(opcode);
+        self.buffer.extend_from_slice(&(content.len() as u64).to_le_bytes());
+        self.buffer.extend_from_slice(content);
+    }
+}
+
+fn put_string(bytes: &mut Vec<u8>, text: &str) {
+    bytes.extend_from_slice(&(text.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(text.as_bytes());
+}
+
+/*
+ * little-endian CDR: every primitive is aligned to its own size, counted from
+ * the end of the 4 byte encapsulation header
+ */
+struct Cdr {
+    bytes: Vec<u8>,
+}
+
+impl Cdr {
+    fn new() -> Self {
+        Self { bytes: vec![0x00, 0x01, 0x00, 0x00] }
+    }
+
+    fn align(&mut self, size: usize) {
+        while !(self.bytes.len() - 4).is_multiple_of(size) { self.bytes.push(0); }
+    }
+
+    fn bool(&mut self, value: bool) { self.bytes.push(value as u8); }
+    fn i32(&mut self, value: i32) { self.align(4); self.bytes.extend_from_slice(&value.to_le_bytes()); }
+    fn u32(&mut self, value: u32) { self.align(4); self.bytes.extend_from_slice(&value.to_le_bytes()); }
+    fn f32(&mut self, value: f32) { self.align(4); self.bytes.extend_from_slice(&value.to_le_bytes()); }
+    fn f64(&mut self, value: f64) { self.align(8); self.bytes.extend_from_slice(&value.to_le_bytes()); }
+
+    // strings carry their terminating nul in the length
+    fn string(&mut self, text: &str) {
+        self.u32(text.len() as u32 + 1);
+        self.bytes.extend_from_slice(text.as_bytes());
+        self.bytes.push(0);
+    }
+
+    fn header(&mut self, time: f32, frame_id: &str) {
+        let time = time.max(0.0) as f64;
+        self.i32(time.floor() as i32);
+        self.u32((time.fract() * 1e9) as u32);
+        self.string(frame_id);
+    }
+
+    // a point (or vector, or translation) in the plane
+    fn point(&mut self, x: f64, y: f64) {
+        self.f64(x);
+        self.f64(y);
+        self.f64(0.0);
+    }
+
+    // a rotation about z
+    fn quaternion(&mut self, yaw: f32) {
+        let half = yaw as f64 / 2.0;
+        self.f64(0.0);
+        self.f64(0.0);
+        self.f64(half.sin());
+        self.f64(half.cos());
+    }
+
+    fn pose(&mut self, x: f64, y: f64, yaw: f32) {
+        self.point(x, y);
+        self.quaternion(yaw);
+    }
+}
@@ -1,3 +1,8 @@
+use crate::math::Pose2;
+use crate::motion::Integrator;
+use crate::slam::{Association, Backend, RobustKernel};
+
+#[derive(Clone, Debug)]
 pub struct Config {
     pub linear_acc: f32,
     pub angular_acc: f32,
@@ -10,22 +15,87 @@ pub struct Config {
     // process noise scaling factors
     pub real_stdev_linear: f32,
     pub real_stdev_angular: f32,
+
+    // motion integration for the ground truth and for the filters' motion models;
+    // picking different ones adds model mismatch
+    pub sim_integrator: Integrator,
+    pub filter_integrator: Integrator,
     
     // sensor constants
     pub sensor_range: f32,
+    pub sensor_fov: f32, // radians, centered on the heading; 2 PI sees all around
     pub real_stdev_range: f32,
     pub real_stdev_bearing: f32,
+    pub detection_probability: f32,
+    pub sensor_sweeps: usize, // readings per frame, for sensors faster than the filters
+    pub outlier_probability: f32, // chance a reading is clutter anywhere in range instead of the landmark
+    pub reflection_probability: f32, // chance each obstruction face mirrors a landmark into a ghost reading
+
+    // sensor degradation with speed, as fractions of `max_linear_speed`: noise stdevs grow by
+    // `speed_noise_gain` times it, detection drops by `speed_detection_loss` times it, and
+    // bearings lag the turn by half of `blur_exposure` seconds of angular velocity
+    pub speed_noise_gain: f32,
+    pub speed_detection_loss: f32,
+    pub blur_exposure: f32,
+
+    // sensor resolution: readings come rounded to multiples of these (0 = continuous),
+    // and landmarks closer than `min_sensor_range` fall in a blind zone
+    pub range_resolution: f32,
+    pub bearing_resolution: f32, // radians
+    pub min_sensor_range: f32,
+    pub visibility_sector_width: f32, // radians, of the sector Shift + right click gives a landmark
+
+    // sensor-denied zones: the detection probability inside them, and the radius of the
+    // discs the dropout tool paints them with
+    pub dropout_detection_probability: f32,
+    pub dropout_brush_radius: f32,
+
+    // the scatter tool: landmarks per 100 x 100 units and the minimum spacing it starts
+    // with, and how much one key press changes each
+    pub scatter_density: f32,
+    pub scatter_separation: f32,
+    pub scatter_density_factor: f32,
+    pub scatter_separation_step: f32,
+
+    // how the EKF weighs landmark sensor innovations
+    pub landmark_kernel: RobustKernel,
     
     // decay factor (friction) scalings
     pub drag_linear: f32,
     pub drag_angular: f32,
 
+    // actuation: commands are sampled at `command_rate` Hz (0 = every frame) and
+    // reach the wheels `control_latency` seconds later
+    pub command_rate: f32,
+    pub control_latency: f32,
+
+    // ground truth plausibility: acceleration limits and no reversing within a frame,
+    // optionally with the motors' torque falling off toward top speed
+    pub enforce_dynamics: bool,
+    pub actuator_saturation: bool,
+
+    // walls around the sandbox world, (width, height) centered on the origin, or unbounded
+    // if None; canned scenarios bring their own
+    pub world_boundary: Option<(f32, f32)>,
+    pub boundary_thickness: f32,
+
     // landmark size
     pub landmark_radius: f32,
 
+    // covariance trace at which landmark estimates are drawn fully red
+    pub max_landmark_trace: f32,
+
+    // robot and landmark covariance ellipses: the outermost one's size in standard
+    // deviations (every whole one inside it is drawn too), and their level of detail, skipped below `ellipse_min_pixels` across and otherwise
+    // with a segment per `ellipse_segment_pixels` of outline
+    pub ellipse_sigmas: f32,
+    pub ellipse_min_pixels: f32,
+    pub ellipse_segment_pixels: f32,
+
     // obstruction size
     pub obstruction_width: f32,
     pub obstruction_height: f32,
+    pub snap_unit: f32, // grid that obstruction corners snap to
 
     // camera
     pub horizontal_units: f32, // number of units horizontally for camera viewport
@@ -33,11 +103,235 @@ pub struct Config {
     // grid
     pub grid_unit: f32,
 
+    // cell size of the observation density heatmap
+    pub heatmap_cell_size: f32,
+
+    // coverage map and viewpoint suggestions
+    pub coverage_cell_size: f32,
+    pub viewpoint_search_radius: f32,
+    pub unexplored_cell_gain: f32,
+    pub num_suggested_viewpoints: usize,
+
+    // world units per pixel of the exported probability map
+    pub map_export_resolution: f32,
+
+    // MCAP export: the physical size of a world unit, and how often the landmark markers are rewritten
+    pub bag_meters_per_unit: f32,
+    pub bag_marker_period: f32,
+
+    // distance from a mission goal that counts as reaching it
+    pub goal_tolerance: f32,
+
+    // click-to-go navigation
+    pub nav_cell_size: f32,
+    pub nav_clearance: f32, // extra distance kept from obstructions on top of the robot radius
+    pub nav_search_margin: f32,
+    pub nav_replan_interval: f32,
+    pub nav_lookahead: f32,
+    pub nav_cruise_speed: f32,
+
+    // cruise control: setpoint change per arrow key tap, and heading hold gain
+    pub cruise_speed_step: f32,
+    pub cruise_heading_step: f32,
+    pub cruise_heading_gain: f32,
+
+    // dynamic window local controller
+    pub dwa_window: f32, // seconds of acceleration considered reachable
+    pub dwa_horizon: f32, // seconds each candidate is rolled out for
+    pub dwa_clearance_weight: f32,
+    pub dwa_clearance_cap: f32, // clearance beyond which no extra reward is given
+
+    // lidar
+    pub lidar_num_beams: usize,
+    pub lidar_range: f32,
+    pub lidar_stdev_range: f32,
+
+    // speeds below which the robot counts as parked
+    pub rest_linear_speed: f32,
+    pub rest_angular_speed: f32,
+
+    // stuck detection
+    pub stuck_min_speed: f32, // commanded speed above which the robot is expected to move
+    pub stuck_time: f32,
+
+    // seconds an event stays on screen
+    pub event_display_time: f32,
+
+    // farthest an estimate can be from a true landmark and still be matched to it in map metrics
+    pub map_match_gate: f32,
+
+    // in stress scenarios, a filter whose ATE exceeds this has broken down
+    pub stress_max_ate: f32,
+
+    // frames of history kept by each ghost trail
+    pub trail_length: usize,
+
+    // samples kept by the uncertainty tube, and how far the estimate must move
+    // before another is added
+    pub tube_length: usize,
+    pub tube_spacing: f32,
+
+    // the believed-walls map: lidar hits kept, and how far the estimate must move or
+    // turn (radians) before another scan is added
+    pub scan_map_points: usize,
+    pub scan_map_spacing: f32,
+    pub scan_map_turn: f32,
+
+    // a landmark seen again after this many seconds unseen counts as a loop closure in the run summary
+    pub loop_closure_gap: f32,
+
+    // seconds between the path samples kept for HTML run reports
+    pub report_sample_interval: f32,
+
+    // localization health light: seconds of measurements it looks back over, the
+    // mean NIS (as a multiple of the expected 2) at which it stops trusting the
+    // filter, the position stdev it has no trust left at, and the measurements
+    // per frame it needs for full trust
+    pub health_window: f32,
+    pub health_max_nis: f32,
+    pub health_max_position_stdev: f32,
+    pub health_min_measurements: f32,
+
+    // consistency monitor: NEES and NIS values averaged, and seconds either average
+    // must stay outside its chi-square bounds before the monitor warns
+    pub consistency_window: usize,
+    pub consistency_warn_time: f32,
+
+    // offline video export: the default frame size and rate, and the room left around
+    // the run's extent
+    pub video_width: u32,
+    pub video_height: u32,
+    pub video_fps: f32,
+    pub video_margin: f32,
+
+    // seconds between writes of the recovery file
+    pub autosave_interval: f32,
+
+    // fewest particles or candidate viewpoints handed to one rayon worker;
+    // smaller workloads run on the calling thread
+    pub parallel_chunk_size: usize,
+
+    // redraw rate once idle, and seconds without input before the window counts as idle
+    pub idle_fps: f32,
+    pub idle_timeout: f32,
+
+    // frames of metrics history kept for the plot pane, and of each landmark
+    // estimate's history for the inspector
+    pub metrics_history_len: usize,
+    pub landmark_history_len: usize,
+
+    // landmarks first seen further away than this aren't initialized until seen
+    // closer; far-field range-bearing initializations are badly skewed
+    pub max_init_range: f32,
+
+    // bearing-only EKFs: the range an inverse-depth landmark starts at and the
+    // stdev of its inverse depth relative to that, and the angle (radians) two rays
+    // to a landmark need between them before a triangulation is trusted
+    pub inverse_depth_initial_range: f32,
+    pub inverse_depth_stdev: f32,
+    pub triangulation_min_parallax: f32,
+
+    // EKF map culling: landmarks in sensor range but unseen for this many updates in a
+    // row are archived (0 keeps them forever)
+    pub cull_after_misses: u32,
+    // a new landmark within this chi-squared gate of an archived one is re-identified as it
+    pub reid_gate: f32,
+
+    // EKF map pruning: landmarks seen in fewer than `prune_min_sightings` updates and
+    // then unseen anywhere for `prune_after_updates` updates in a row are discarded
+    // as spurious (0 keeps them forever)
+    pub prune_after_updates: u32,
+    pub prune_min_sightings: u32,
+
+    // EKF without known correspondences: chi-squared gate on the squared Mahalanobis
+    // distance for matches, with a measurement that passes it for no landmark
+    // starting a new one, the cost difference (in nats) under which a match is ambiguous
+    // and splits the hypothesis, and how many hypotheses survive, and how far behind the best;
+    // `association` matches a frame's measurements one by one or jointly. With
+    // `known_ids` off, the plain EKF ignores the ids too and takes the nearest
+    // landmark within the gate
+    pub known_ids: bool,
+    pub association: Association,
+    pub association_gate: f32,
+    pub ambiguity_margin: f32,
+    pub max_hypotheses: usize,
+    pub hypothesis_prune: f32,
+
+    // exponential aging of the EKF map for slowly drifting landmarks: every
+    // landmark's variance grows by e^(rate * dt) per prediction, so old
+    // sightings count for less; 0 never forgets
+    pub landmark_aging_rate: f32,
+
+    // headless convergence: a run has converged once `convergence_window` seconds pass
+    // (0 never) without newly covered cells or mapped landmarks and with the ATE within
+    // `convergence_tolerance`, a fraction, of where it was; `stop_at_convergence` ends it there
+    pub convergence_window: f32,
+    pub convergence_tolerance: f32,
+    pub stop_at_convergence: bool,
+
+    // the estimator `timeline` and `check` run, unless `--filter` picks another
+    pub headless_filter: Backend,
+
+    // thresholds the `gate` command sweeps, spread in log space
+    pub gate_sweep_min: f32,
+    pub gate_sweep_max: f32,
+    pub gate_sweep_steps: usize,
+
+    // `tune`: evaluations of the black-box search, seeds each scenario is run
+    // with per evaluation, the first relative step (in log space), the weight of
+    // the NEES term against the ATE, and the seed the candidates are drawn from
+    pub tune_iterations: usize,
+    pub tune_seeds: u64,
+    pub tune_initial_step: f32,
+    pub tune_nees_weight: f32,
+    pub tune_seed: u64,
+
+    // keyboard placement nudges, in world units and degrees
+    pub placement_step: f32,
+    pub placement_turn_step: f32,
+
+    // initial pose belief; the error is added to the true starting pose so the
+    // filters can be started from a deliberately wrong prior
+    pub initial_stdev_position: f32,
+    pub initial_stdev_heading: f32,
+    pub initial_pose_error: Pose2,
+
+    // landmarks pinned with the anchor tool are held to their estimate this tightly;
+    // without one, the initial pose prior above is all that fixes the gauge
+    pub anchor_stdev: f32,
+
+    // while paused, [ and ] divide or multiply a covariance block by this
+    pub covariance_edit_factor: f32,
+
+    // FastSLAM: particles, each with its own pose and map
+    pub fast_slam_particles: usize,
+
+    // SEIF: landmarks kept linked to the robot, and relaxation sweeps per update
+    // spent recovering the mean
+    pub seif_max_active: usize,
+    pub seif_recovery_sweeps: usize,
+
+    // GraphSLAM: a keyframe every this much travel or turn, and a batch solve
+    // of the whole graph every `graph_optimize_every` keyframes
+    pub graph_keyframe_distance: f32,
+    pub graph_keyframe_angle: f32,
+    pub graph_optimize_every: usize,
+    pub graph_max_iterations: usize, // Levenberg-Marquardt steps per solve
+
+    // UKF-SLAM: spread (alpha), prior knowledge of the distribution (beta,
+    // 2 is optimal for Gaussians), and secondary scaling (kappa) of the sigma points
+    pub ukf_alpha: f32,
+    pub ukf_beta: f32,
+    pub ukf_kappa: f32,
+
     // belief standard deviations for EKF SLAM
     pub est_stdev_linear: f32,
     pub est_stdev_angular: f32,
     pub est_stdev_range: f32,
     pub est_stdev_bearing: f32,
+
+    // weight kept by the adapted EKF measurement noise at each correction
+    pub adaptive_noise_forgetting: f32,
 }
 
 impl Config {
@@ -50,20 +344,153 @@ impl Config {
             max_angular_speed: 1.5,
             real_stdev_linear: 0.03,
             real_stdev_angular: 0.01,
+            sim_integrator: Integrator::Midpoint,
+            filter_integrator: Integrator::Midpoint,
             sensor_range: 200.0,
+            sensor_fov: std::f32::consts::TAU,
             real_stdev_range: 5.0,
             real_stdev_bearing: 0.05,
+            detection_probability: 1.0,
+            sensor_sweeps: 1,
+            outlier_probability: 0.0,
+            reflection_probability: 0.0,
+            speed_noise_gain: 0.0,
+            speed_detection_loss: 0.0,
+            blur_exposure: 0.0,
+            range_resolution: 0.0,
+            bearing_resolution: 0.0,
+            min_sensor_range: 0.0,
+            visibility_sector_width: std::f32::consts::PI,
+            dropout_detection_probability: 0.002,
+            dropout_brush_radius: 40.0,
+            scatter_density: 2.0,
+            scatter_separation: 30.0,
+            scatter_density_factor: 1.25,
+            scatter_separation_step: 5.0,
+            landmark_kernel: RobustKernel::Gaussian,
             drag_linear: 1.9,
             drag_angular: 4.0,
+            command_rate: 0.0,
+            control_latency: 0.0,
+            enforce_dynamics: true,
+            actuator_saturation: false,
+            world_boundary: None,
+            boundary_thickness: 20.0,
             landmark_radius: 6.0,
+            max_landmark_trace: 200.0,
+            ellipse_sigmas: 3.0,
+            ellipse_min_pixels: 2.0,
+            ellipse_segment_pixels: 6.0,
             obstruction_width: 50.0,
             obstruction_height: 50.0,
+            snap_unit: 25.0,
             horizontal_units: 500.0,
             grid_unit: 50.0,
+            heatmap_cell_size: 25.0,
+            coverage_cell_size: 50.0,
+            viewpoint_search_radius: 400.0,
+            unexplored_cell_gain: 0.1,
+            num_suggested_viewpoints: 3,
+            map_export_resolution: 2.0,
+            bag_meters_per_unit: 0.01,
+            bag_marker_period: 0.5,
+            goal_tolerance: 30.0,
+            nav_cell_size: 10.0,
+            nav_clearance: 6.0,
+            nav_search_margin: 300.0,
+            nav_replan_interval: 1.0,
+            nav_lookahead: 40.0,
+            nav_cruise_speed: 40.0,
+            cruise_speed_step: 10.0,
+            cruise_heading_step: std::f32::consts::PI / 12.0,
+            cruise_heading_gain: 3.0,
+            dwa_window: 0.25,
+            dwa_horizon: 1.5,
+            dwa_clearance_weight: 0.5,
+            dwa_clearance_cap: 40.0,
+            lidar_num_beams: 72,
+            lidar_range: 150.0,
+            lidar_stdev_range: 1.0,
+            rest_linear_speed: 0.5,
+            rest_angular_speed: 0.01,
+            stuck_min_speed: 10.0,
+            stuck_time: 0.5,
+            event_display_time: 5.0,
+            map_match_gate: 50.0,
+            stress_max_ate: 30.0,
+            trail_length: 3600,
+            tube_length: 1000,
+            tube_spacing: 5.0,
+            scan_map_points: 20_000,
+            scan_map_spacing: 10.0,
+            scan_map_turn: 0.2,
+            loop_closure_gap: 10.0,
+            report_sample_interval: 0.1,
+            health_window: 2.0,
+            health_max_nis: 5.0,
+            health_max_position_stdev: 20.0,
+            health_min_measurements: 2.0,
+            consistency_window: 300,
+            consistency_warn_time: 3.0,
+            video_width: 1920,
+            video_height: 1080,
+            video_fps: 30.0,
+            video_margin: 60.0,
+            autosave_interval: 30.0,
+            parallel_chunk_size: 64,
+            idle_fps: 5.0,
+            idle_timeout: 2.0,
+            metrics_history_len: 3600,
+            landmark_history_len: 1800,
+            max_init_range: 150.0,
+            inverse_depth_initial_range: 100.0,
+            inverse_depth_stdev: 1.0,
+            triangulation_min_parallax: 0.1,
+            cull_after_misses: 0,
+            prune_after_updates: 0,
+            prune_min_sightings: 3,
+            reid_gate: 9.21,
+            known_ids: true,
+            association: Association::NearestNeighbor,
+            association_gate: 18.42,
+            ambiguity_margin: 1.0,
+            max_hypotheses: 4,
+            hypothesis_prune: 10.0,
+            landmark_aging_rate: 0.0,
+            convergence_window: 10.0,
+            convergence_tolerance: 0.05,
+            stop_at_convergence: false,
+            headless_filter: Backend::Ekf,
+            gate_sweep_min: 0.5,
+            gate_sweep_max: 100.0,
+            gate_sweep_steps: 200,
+            tune_iterations: 40,
+            tune_seeds: 3,
+            tune_initial_step: 0.5,
+            tune_nees_weight: 2.0,
+            tune_seed: 0,
+            placement_step: 1.0,
+            placement_turn_step: 1.0,
+            initial_stdev_position: 0.1,
+            initial_stdev_heading: 0.1,
+            initial_pose_error: Pose2::IDENTITY,
+            anchor_stdev: 0.01,
+            covariance_edit_factor: 2.0,
+            fast_slam_particles: 100,
+            seif_max_active: 6,
+            seif_recovery_sweeps: 3,
+            graph_keyframe_distance: 20.0,
+            graph_keyframe_angle: 0.4,
+            graph_optimize_every: 5,
+            graph_max_iterations: 10,
+            ukf_alpha: 1.0,
+            ukf_beta: 2.0,
+            ukf_kappa: 0.0,
             est_stdev_linear: 0.03,
             est_stdev_angular: 0.01,
             est_stdev_range: 5.0,
             est_stdev_bearing: 0.05,
+            adaptive_noise_forgetting: 0.999,
         }
     }
 }
@@ -0,0 +1,183 @@
+use std::collections::VecDeque;
+
+use crate::config::Config;
+use crate::math::Pose2;
+use crate::metrics::pose_nees;
+use crate::simulation::{Observation, sensor};
+use crate::slam::EkfSlam;
+
+// standard normal quantile of the bounds' two-sided 95% interval
+const BOUND_Z: f32 = 1.96;
+const NEES_DOF: usize = 3; // x, y, heading
+const NIS_DOF: usize = 2; // range, bearing
+
+/*
+ * normalized innovation squared of each of `observations` of an already mapped
+ * landmark, against `ekf` as it is before the update that uses them; chi-square
+ * with 2 degrees of freedom for a consistent filter
+ */
+pub fn measurement_nis(ekf: &EkfSlam, observations: &[Observation], cfg: &Config) -> Vec<f32> {
+    sensor::compress(observations).into_iter()
+        .filter_map(|(observation, readings)| ekf.association_cost(&observation, observation.id, readings, cfg))
+        .map(|(_, squared_distance)| squared_distance)
+        .collect()
+}
+
+/*
+ * approximate `p`-quantile of the chi-square distribution with `dof` degrees of
+ * freedom, from the standard normal quantile `z` (Wilson-Hilferty)
+ */
+pub fn chi_square_quantile(z: f32, dof: usize) -> f32 {
+    let k = dof as f32;
+    let spread = (2.0 / (9.0 * k)).sqrt();
+    k * (1.0 - 2.0 / (9.0 * k) + z * spread).max(0.0).powi(3)
+}
+
+/*
+ * 95% bounds on the mean of `samples` chi-square values with `dof` degrees of
+ * freedom each
+ */
+pub fn mean_bounds(samples: usize, dof: usize) -> (f32, f32) {
+    let total = samples * dof;
+    let n = samples.max(1) as f32;
+    (chi_square_quantile(-BOUND_Z, total) / n, chi_square_quantile(BOUND_Z, total) / n)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RunningStat {
+    pub mean: f32,
+    pub lower: f32,
+    pub upper: f32,
+}
+
+impl RunningStat {
+    /*
+     * the mean of `window`, bounded as the mean of `independent` samples
+     */
+    fn of(window: &VecDeque<f32>, independent: usize, dof: usize) -> Option<Self> {
+        if window.is_empty() { return None; }
+        let (lower, upper) = mean_bounds(independent, dof);
+        Some(Self { mean: window.iter().sum::<f32>() / window.len() as f32, lower, upper })
+    }
+
+    pub fn is_inside(&self) -> bool {
+        self.mean >= self.lower && self.mean <= self.upper
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConsistencySample {
+    pub time: f32,
+    pub nees: Option<RunningStat>,
+    pub nis: Option<RunningStat>,
+}
+
+/*
+ * which way the filter is off, judged by the statistic that left its bounds
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Inconsistency {
+    Overconfident, // errors larger than its covariance allows
+    Underconfident,
+}
+
+impl Inconsistency {
+    pub fn label(self) -> &'static str {
+        match self {
+            Inconsistency::Overconfident => "overconfident",
+            Inconsistency::Underconfident => "underconfident",
+        }
+    }
+}
+
+/*
+ * the EKF's pose NEES against the ground truth and NIS of every measurement
+ * update, averaged over the last `consistency_window` of each, next to the 95%
+ * chi-square bounds on those averages. Innovations of a consistent filter are
+ * white, so the NIS bounds tighten with the window; a pose error carries over
+ * from frame to frame, so the window of NEES values is bounded as the single
+ * sample it nearly is. A mean outside either bound for `consistency_warn_time`
+ * seconds raises a warning
+ */
+pub struct ConsistencyMonitor {
+    nees: VecDeque<f32>,
+    nis: VecDeque<f32>,
+    pub history: VecDeque<ConsistencySample>,
+    capacity: usize,
+    outside_since: Option<(f32, Inconsistency)>,
+    pub warning: Option<Inconsistency>,
+}
+
+impl ConsistencyMonitor {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            nees: VecDeque::new(),
+            nis: VecDeque::new(),
+            history: VecDeque::with_capacity(capacity),
+            capacity,
+            outside_since: None,
+            warning: None,
+        }
+    }
+
+    /*
+     * takes the NIS of the frame's measurements; call it before the update
+     */
+    pub fn record_innovations(&mut self, ekf: &EkfSlam, observations: &[Observation], cfg: &Config) {
+        for nis in measurement_nis(ekf, observations, cfg) {
+            if self.nis.len() == cfg.consistency_window { self.nis.pop_front(); }
+            self.nis.push_back(nis);
+        }
+    }
+
+    /*
+     * takes the pose NEES after the frame's update and refreshes the running
+     * values and the warning
+     */
+    pub fn record(&mut self, time: f32, ekf: &EkfSlam, ground_truth: Pose2, cfg: &Config) {
+        if let Some(nees) = pose_nees(ekf, ground_truth).filter(|nees| nees.is_finite()) {
+            if self.nees.len() == cfg.consistency_window { self.nees.pop_front(); }
+            self.nees.push_back(nees);
+        }
+        let sample = ConsistencySample {
+            time,
+            nees: RunningStat::of(&self.nees, 1, NEES_DOF),
+            nis: RunningStat::of(&self.nis, self.nis.len(), NIS_DOF),
+        };
+        if self.history.len() == self.capacity { self.history.pop_front(); }
+        self.history.push_back(sample);
+
+        // too large a mean is the more telling, so it wins when the two disagree;
+        // neither is judged before its window fills, so a restart doesn't warn
+        // off its first few values
+        let full = |window: &VecDeque<f32>| window.len() == cfg.consistency_window;
+        let verdict = [(sample.nees, full(&self.nees)), (sample.nis, full(&self.nis))].into_iter()
+            .filter_map(|(stat, full)| stat.filter(|_| full))
+            .filter(|stat| !stat.is_inside())
+            .map(|stat| if stat.mean > stat.upper { Inconsistency::Overconfident } else { Inconsistency::Underconfident })
+            .min_by_key(|verdict| *verdict != Inconsistency::Overconfident);
+        self.outside_since = match (verdict, self.outside_since) {
+            (Some(verdict), Some((since, previous))) if verdict == previous => Some((since, previous)),
+            (Some(verdict), _) => Some((time, verdict)),
+            (None, _) => None,
+        };
+        self.warning = self.outside_since
+            .filter(|(since, _)| time - since >= cfg.consistency_warn_time)
+            .map(|(_, verdict)| verdict);
+    }
+
+    pub fn latest(&self) -> Option<&ConsistencySample> {
+        self.history.back()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new(self.capacity);
+    }
+
+    /*
+     * (time, value) points of one running statistic, or of one of its bounds
+     */
+    pub fn series(&self, value: impl Fn(&ConsistencySample) -> Option<f32>) -> Vec<(f32, f32)> {
+        self.history.iter().filter_map(|sample| value(sample).map(|v| (sample.time, v))).collect()
+    }
+}
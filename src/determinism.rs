@@ -0,0 +1,184 @@
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::math::Pose2;
+use crate::scenario::{self, Playback, Scenario, TIME_STEP};
+use crate::simulation::Robot;
+use crate::slam::{EkfSlam, FastSlam, MultiHypothesisEkf, OdometryBaseline, RobocentricEkf, SeifSlam, SlamFilter};
+use macroquad::prelude::rand;
+
+/*
+ * FNV-1a over the exact bits of `values`, so two states only match when every
+ * float in them is bit-identical (and -0.0 differs from 0.0)
+ */
+pub fn checksum(values: impl IntoIterator<Item = f32>) -> u64 {
+    values.into_iter()
+        .flat_map(|value| value.to_bits().to_le_bytes())
+        .fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/*
+ * one component's state in a frame: `state` covers the values (pose, pose
+ * covariance, and the landmark estimates sorted by id), `order` the order the
+ * landmark ids were reported in
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct ComponentChecksum {
+    pub state: u64,
+    pub order: u64,
+}
+
+fn filter_checksum(slam: &dyn SlamFilter) -> ComponentChecksum {
+    let Pose2 { x, y, theta } = slam.estimated_pose();
+    let mut landmarks = slam.estimated_map();
+    let order = checksum(landmarks.iter().map(|&(id, _, _)| id as f32));
+    landmarks.sort_by_key(|&(id, _, _)| id);
+
+    let covariance = slam.pose_covariance();
+    let values = [x, y, theta].into_iter()
+        .chain(covariance.iter().copied())
+        .chain(landmarks.into_iter().flat_map(|(id, x, y)| [id as f32, x, y]));
+    ComponentChecksum { state: checksum(values), order }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum DivergenceKind {
+    Order, // same values, reported in another order
+    State,
+}
+
+impl DivergenceKind {
+    // the usual culprit, for the report
+    pub fn likely_source(&self) -> &'static str {
+        match self {
+            DivergenceKind::Order => "landmarks reported in HashMap iteration order",
+            DivergenceKind::State => "random numbers drawn in a varying order (threads) or HashMap order feeding the arithmetic",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Divergence {
+    pub component: String,
+    pub frame: usize,
+    pub kind: DivergenceKind,
+}
+
+/*
+ * the first divergence of each component between two runs of the same seed;
+ * `Order` ones are harmless to the filters themselves but make anything that
+ * iterates the estimates (recordings, map export, metrics sums) vary
+ */
+#[derive(Clone, Debug, Serialize)]
+pub struct AuditReport {
+    pub scenario: String,
+    pub seed: u64,
+    pub frames: usize,
+    pub divergences: Vec<Divergence>,
+}
+
+impl AuditReport {
+    pub fn is_deterministic(&self) -> bool {
+        self.divergences.iter().all(|divergence| divergence.kind == DivergenceKind::Order)
+    }
+}
+
+/*
+ * drives the scenario the way `scenario::run` does, with every filter fed the
+ * same inputs, and checksums the robot and each filter after every frame. The
+ * step is always `TIME_STEP`, so the window's frame time never enters
+ */
+pub fn trace(
+    scenario: &Scenario,
+    filters: &mut [(&str, Box<dyn SlamFilter>)],
+    seed: u64,
+    cfg: &Config,
+) -> Vec<Vec<ComponentChecksum>> {
+    rand::srand(seed);
+
+    let mut world = scenario.world(cfg);
+    let mut frames = Vec::new();
+
+    let mut playback = Playback::new(scenario.clone());
+    while let Some(segment) = playback.step(&mut world.landmarks) {
+        world.robot.command_velocity(segment.linear, segment.angular, cfg, TIME_STEP);
+        world.step_robot(TIME_STEP, cfg);
+        let robot = &world.robot;
+        let observations = world.sense(robot, cfg);
+
+        let mut frame = vec![ComponentChecksum {
+            state: checksum([robot.x, robot.y, robot.theta, robot.linear_velocity, robot.angular_velocity]),
+            order: 0,
+        }];
+        for (_, slam) in filters.iter_mut() {
+            slam.predict(robot.linear_velocity, robot.angular_velocity, TIME_STEP, cfg);
+            slam.update(&observations, cfg);
+            frame.push(filter_checksum(slam.as_ref()));
+        }
+        frames.push(frame);
+    }
+    frames
+}
+
+/*
+ * every filter the simulator can run, fresh from the configured prior at the
+ * robot's starting pose, with the simulator's particle count
+ */
+pub fn default_filters(cfg: &Config) -> Vec<(&'static str, Box<dyn SlamFilter>)> {
+    let robot = Robot::new();
+    let start = robot.pose();
+    let (stdev_position, stdev_heading) = (cfg.initial_stdev_position, cfg.initial_stdev_heading);
+
+    let ekf = EkfSlam::with_prior(start, stdev_position, stdev_heading);
+    let odometry = OdometryBaseline::from_slam(&ekf);
+    vec![
+        ("ekf", Box::new(ekf)),
+        ("fast", Box::new(FastSlam::with_prior(cfg.fast_slam_particles, start, stdev_position, stdev_heading))),
+        ("seif", Box::new(SeifSlam::with_prior(start, stdev_position, stdev_heading))),
+        ("robo", Box::new(RobocentricEkf::with_prior(start, stdev_position, stdev_heading))),
+        ("mht", Box::new(MultiHypothesisEkf::with_prior(start, stdev_position, stdev_heading, cfg.max_hypotheses))),
+        ("odom", Box::new(odometry)),
+    ]
+}
+
+/*
+ * runs the scenario twice in-process with the same seed and compares the
+ * traces frame by frame. The runs share nothing but the seed, and each
+ * HashMap gets its own random hasher keys, so anything depending on their
+ * iteration order shows up here
+ */
+pub fn audit(
+    scenario: &Scenario,
+    make_filters: impl Fn() -> Vec<(&'static str, Box<dyn SlamFilter>)>,
+    seed: u64,
+    cfg: &Config,
+) -> AuditReport {
+    // filters like FastSLAM draw their prior samples when made, from a separate stream
+    let seeded_filters = || { scenario::seed_prior(seed); make_filters() };
+
+    let mut first = seeded_filters();
+    let names: Vec<String> = std::iter::once("robot").chain(first.iter().map(|(name, _)| *name)).map(str::to_string).collect();
+    let a = trace(scenario, &mut first, seed, cfg);
+    let b = trace(scenario, &mut seeded_filters(), seed, cfg);
+
+    let mut divergences: Vec<Divergence> = Vec::new();
+    for (frame, (a, b)) in a.iter().zip(&b).enumerate() {
+        for (component, (a, b)) in a.iter().zip(b).enumerate() {
+            let kind = if a.state != b.state {
+                DivergenceKind::State
+            } else if a.order != b.order {
+                DivergenceKind::Order
+            } else {
+                continue;
+            };
+
+            // keep the first of each kind per component
+            let name = &names[component];
+            if !divergences.iter().any(|divergence| &divergence.component == name && divergence.kind == kind) {
+                divergences.push(Divergence { component: name.clone(), frame, kind });
+            }
+        }
+    }
+
+    AuditReport { scenario: scenario.name.clone(), seed, frames: a.len().min(b.len()), divergences }
+}
@@ -0,0 +1,74 @@
+#[derive(Clone, Copy, PartialEq)]
+pub enum EventKind {
+    Bump,
+    Stuck,
+    Unstuck,
+    Bookmark,
+    Saved,
+    Loaded,
+    Failed,
+    Finished,
+    Scattered,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 9] = [
+        EventKind::Bump, EventKind::Stuck, EventKind::Unstuck, EventKind::Bookmark,
+        EventKind::Saved, EventKind::Loaded, EventKind::Failed, EventKind::Finished, EventKind::Scattered,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EventKind::Bump => "bump",
+            EventKind::Stuck => "stuck",
+            EventKind::Unstuck => "unstuck",
+            EventKind::Bookmark => "bookmark",
+            EventKind::Saved => "saved",
+            EventKind::Loaded => "loaded",
+            EventKind::Failed => "failed",
+            EventKind::Finished => "finished",
+            EventKind::Scattered => "landmarks scattered",
+        }
+    }
+}
+
+pub struct Event {
+    pub time: f32,
+    pub kind: EventKind,
+    // where in the world it happened, for the ones that happened somewhere
+    pub position: Option<(f32, f32)>,
+    // shown after the label, untranslated: a path, a count, an error
+    pub detail: String,
+}
+
+/*
+ * time-stamped log of discrete things that happened to the ground truth robot,
+ * and of what the app did on request, like saving a file
+ */
+pub struct EventLog {
+    pub events: Vec<Event>,
+}
+
+impl EventLog {
+    pub fn new() -> Self {
+        Self { events: Vec::new() }
+    }
+
+    pub fn push(&mut self, time: f32, kind: EventKind, x: f32, y: f32) {
+        self.events.push(Event { time, kind, position: Some((x, y)), detail: String::new() });
+    }
+
+    /*
+     * an event with no place in the world, reported with `detail`
+     */
+    pub fn note(&mut self, time: f32, kind: EventKind, detail: impl Into<String>) {
+        self.events.push(Event { time, kind, position: None, detail: detail.into() });
+    }
+
+    /*
+     * events no older than `window` seconds, newest first
+     */
+    pub fn recent(&self, now: f32, window: f32) -> impl Iterator<Item = &Event> {
+        self.events.iter().rev().take_while(move |event| now - event.time <= window)
+    }
+}
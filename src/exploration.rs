@@ -0,0 +1,152 @@
+use std::collections::{HashMap, HashSet};
+use nalgebra::Matrix2;
+use rayon::prelude::*;
+
+use crate::config::Config;
+use crate::slam::SlamFilter;
+
+pub struct Viewpoint {
+    pub x: f32,
+    pub y: f32,
+    pub score: f32,
+}
+
+/*
+ * grid of world cells that have been inside the sensor footprint of the
+ * estimated robot pose at least once
+ */
+pub struct CoverageMap {
+    pub cell_size: f32,
+    pub covered: HashSet<(i32, i32)>,
+}
+
+/*
+ * number of measurements that landed in each world cell, binned at the
+ * measured landmark position
+ */
+pub struct ObservationHeatmap {
+    pub cell_size: f32,
+    pub counts: HashMap<(i32, i32), u32>,
+    pub max_count: u32,
+}
+
+impl ObservationHeatmap {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            counts: HashMap::new(),
+            max_count: 0,
+        }
+    }
+
+    pub fn record(&mut self, x: f32, y: f32) {
+        let cell = ((x / self.cell_size).floor() as i32, (y / self.cell_size).floor() as i32);
+        let count = self.counts.entry(cell).or_insert(0);
+        *count += 1;
+        self.max_count = self.max_count.max(*count);
+    }
+}
+
+impl CoverageMap {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            covered: HashSet::new(),
+        }
+    }
+
+    pub fn cell_of(&self, x: f32, y: f32) -> (i32, i32) {
+        ((x / self.cell_size).floor() as i32, (y / self.cell_size).floor() as i32)
+    }
+
+    pub fn cell_center(&self, cell: (i32, i32)) -> (f32, f32) {
+        ((cell.0 as f32 + 0.5) * self.cell_size, (cell.1 as f32 + 0.5) * self.cell_size)
+    }
+
+    /*
+     * marks every cell whose center lies within range of (x, y)
+     */
+    pub fn mark_observed(&mut self, x: f32, y: f32, range: f32) {
+        for cell in self.cells_within(x, y, range) {
+            self.covered.insert(cell);
+        }
+    }
+
+    pub fn is_covered(&self, cell: (i32, i32)) -> bool {
+        self.covered.contains(&cell)
+    }
+
+    fn cells_within(&self, x: f32, y: f32, range: f32) -> Vec<(i32, i32)> {
+        let (center_i, center_j) = self.cell_of(x, y);
+        let reach = (range / self.cell_size).ceil() as i32 + 1;
+        let mut cells = Vec::new();
+
+        for i in (center_i - reach)..=(center_i + reach) {
+            for j in (center_j - reach)..=(center_j + reach) {
+                let (cx, cy) = self.cell_center((i, j));
+                if (cx - x).powi(2) + (cy - y).powi(2) <= range * range {
+                    cells.push((i, j));
+                }
+            }
+        }
+
+        cells
+    }
+
+    /*
+     * expected information gain of standing at (x, y): the entropy reduction of
+     * every mapped landmark in range after one range-bearing measurement, plus a
+     * fixed bonus for each not yet covered cell inside the sensor footprint
+     */
+    pub fn expected_gain(&self, x: f32, y: f32, slam: &dyn SlamFilter, cfg: &Config) -> f32 {
+        let r_inverse = Matrix2::new(
+            1.0 / cfg.est_stdev_range.powi(2), 0.0,
+            0.0, 1.0 / cfg.est_stdev_bearing.powi(2)
+        );
+
+        let mut gain = 0.0;
+        for (id, landmark_x, landmark_y) in slam.estimated_map() {
+            let distance_x = landmark_x - x;
+            let distance_y = landmark_y - y;
+            let distance_sq = (distance_x * distance_x + distance_y * distance_y).max(1e-6);
+            if distance_sq > cfg.sensor_range * cfg.sensor_range { continue; }
+            let distance = distance_sq.sqrt();
+
+            let Some(p_ll) = slam.landmark_covariance(id) else { continue; };
+
+            // jacobian of the measurement with respect to the landmark
+            let h_l = Matrix2::new(
+                distance_x / distance, distance_y / distance,
+                -distance_y / distance_sq, distance_x / distance_sq
+            );
+
+            // 0.5 ln det(I + P H^T R^-1 H) is the entropy drop of the landmark
+            let information = Matrix2::identity() + p_ll * h_l.transpose() * r_inverse * h_l;
+            gain += 0.5 * information.determinant().max(1.0).ln();
+        }
+
+        let unexplored = self.cells_within(x, y, cfg.sensor_range)
+            .iter()
+            .filter(|cell| !self.is_covered(**cell))
+            .count();
+
+        gain + unexplored as f32 * cfg.unexplored_cell_gain
+    }
+
+    /*
+     * scores every cell around (x, y) and returns them, best first
+     */
+    pub fn score_viewpoints(&self, x: f32, y: f32, slam: &dyn SlamFilter, cfg: &Config) -> Vec<Viewpoint> {
+        let mut viewpoints: Vec<Viewpoint> = self.cells_within(x, y, cfg.viewpoint_search_radius)
+            .into_par_iter()
+            .with_min_len(cfg.parallel_chunk_size)
+            .map(|cell| {
+                let (cx, cy) = self.cell_center(cell);
+                Viewpoint { x: cx, y: cy, score: self.expected_gain(cx, cy, slam, cfg) }
+            })
+            .collect();
+
+        viewpoints.sort_by(|a, b| b.score.total_cmp(&a.score));
+        viewpoints
+    }
+}
@@ -0,0 +1,138 @@
+use macroquad::prelude::KeyCode;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::math::{self, Pose2};
+use crate::scenario::ControlSegment;
+use crate::simulation::{Observation, Robot, SensorOverrides, World};
+
+/*
+ * the four keys a keyboard-driven robot answers to: forward, back, left, right
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum KeySet {
+    Arrows,
+    Numpad, // 8, 2, 4, 6
+}
+
+impl KeySet {
+    pub fn keys(self) -> [KeyCode; 4] {
+        match self {
+            KeySet::Arrows => [KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right],
+            KeySet::Numpad => [KeyCode::Kp8, KeyCode::Kp2, KeyCode::Kp4, KeyCode::Kp6],
+        }
+    }
+}
+
+/*
+ * what drives a robot. Gamepads can be declared in scenario files, but
+ * macroquad doesn't read them yet, so a gamepad robot sits idle
+ */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ControlSource {
+    Keyboard(KeySet),
+    Gamepad(usize),
+    Script(Vec<ControlSegment>), // held still once it runs out
+}
+
+/*
+ * a robot a scenario declares next to the one the filters estimate: where it
+ * starts, what drives it, and how its sensor differs from the configured one
+ */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RobotSpec {
+    pub name: String,
+    #[serde(with = "math::pose_array")]
+    pub start: Pose2,
+    pub control: ControlSource,
+    #[serde(default)]
+    pub sensor: SensorOverrides, // its visibility is ignored, that belongs to landmarks
+}
+
+impl RobotSpec {
+    /*
+     * `cfg` with the robot's sensor settings in place of the configured ones;
+     * per-landmark overrides still apply on top
+     */
+    pub fn sensor_config(&self, cfg: &Config) -> Config {
+        let sensor = &self.sensor;
+        Config {
+            sensor_range: sensor.range.unwrap_or(cfg.sensor_range),
+            real_stdev_range: sensor.stdev_range.unwrap_or(cfg.real_stdev_range),
+            real_stdev_bearing: sensor.stdev_bearing.unwrap_or(cfg.real_stdev_bearing),
+            detection_probability: sensor.detection_probability.unwrap_or(cfg.detection_probability),
+            outlier_probability: sensor.outlier_probability.unwrap_or(cfg.outlier_probability),
+            ..cfg.clone()
+        }
+    }
+}
+
+pub struct FleetMember {
+    pub spec: RobotSpec,
+    pub robot: Robot,
+    elapsed: f32, // into the script
+}
+
+/*
+ * the declared robots, each moved by its own control source
+ */
+pub struct Fleet {
+    pub members: Vec<FleetMember>,
+}
+
+impl Fleet {
+    pub fn new(specs: &[RobotSpec]) -> Self {
+        let members = specs.iter()
+            .map(|spec| {
+                let mut robot = Robot::new();
+                robot.place(spec.start.x, spec.start.y, spec.start.theta);
+                FleetMember { spec: spec.clone(), robot, elapsed: 0.0 }
+            })
+            .collect();
+        Self { members }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /*
+     * commands every robot from its source and moves it; `key_down` reads the
+     * keyboard, so headless callers can press keys for it
+     */
+    pub fn step(&mut self, delta_time: f32, cfg: &Config, world: &World, key_down: impl Fn(KeyCode) -> bool) {
+        for member in &mut self.members {
+            let robot = &mut member.robot;
+            match &member.spec.control {
+                ControlSource::Keyboard(keys) => {
+                    // accelerates like the arrow keys do for the main robot
+                    let [forward, back, left, right] = keys.keys();
+                    let axis = |positive: KeyCode, negative: KeyCode| key_down(positive) as i32 as f32 - key_down(negative) as i32 as f32;
+                    robot.linear_velocity += axis(forward, back) * cfg.linear_acc * delta_time;
+                    robot.angular_velocity += axis(left, right) * cfg.angular_acc * delta_time;
+                }
+                ControlSource::Gamepad(_) => {}
+                ControlSource::Script(segments) => {
+                    let mut start = 0.0;
+                    let segment = segments.iter().find(|segment| {
+                        start += segment.duration;
+                        member.elapsed < start
+                    });
+                    let (linear, angular) = segment.map_or((0.0, 0.0), |segment| (segment.linear, segment.angular));
+                    robot.command_velocity(linear, angular, cfg, delta_time);
+                }
+            }
+            member.elapsed += delta_time;
+            robot.update(delta_time, cfg, world);
+        }
+    }
+
+    /*
+     * what each robot's sensor reads this frame, in member order
+     */
+    pub fn sense(&self, world: &World, cfg: &Config) -> Vec<Vec<Observation>> {
+        self.members.iter()
+            .map(|member| world.sense(&member.robot, &member.spec.sensor_config(cfg)))
+            .collect()
+    }
+}
@@ -0,0 +1,180 @@
+use std::panic::{self, AssertUnwindSafe};
+use macroquad::prelude::{Rect, rand};
+
+use crate::config::Config;
+use crate::math::Pose2;
+use crate::scenario::{self, ControlSegment, Criteria, Scenario};
+use crate::simulation::Landmark;
+use crate::slam::{EkfSlam, FastSlam, SlamFilter};
+
+/*
+ * one randomly generated world, drive, and noise configuration
+ */
+pub struct FuzzCase {
+    pub seed: u64,
+    pub scenario: Scenario,
+    pub cfg: Config,
+}
+
+pub struct FuzzFailure {
+    pub case: FuzzCase,
+    pub error: String,
+}
+
+impl FuzzCase {
+    /*
+     * everything is derived from `seed`, so a failing seed reproduces exactly
+     */
+    pub fn generate(seed: u64) -> Self {
+        rand::srand(seed);
+
+        let landmarks = (0..rand::gen_range(0, 30))
+            .map(|id| Landmark::new(id, rand::gen_range(-300.0, 300.0), rand::gen_range(-300.0, 300.0)))
+            .collect();
+
+        let obstructions = (0..rand::gen_range(0, 8))
+            .map(|_| Rect::new(rand::gen_range(-300.0, 300.0), rand::gen_range(-300.0, 300.0), rand::gen_range(5.0, 120.0), rand::gen_range(5.0, 120.0)))
+            .collect();
+
+        let controls = (0..rand::gen_range(1, 6))
+            .map(|_| ControlSegment {
+                duration: rand::gen_range(0.1, 3.0),
+                linear: rand::gen_range(-200.0, 200.0),
+                angular: rand::gen_range(-3.0, 3.0),
+            })
+            .collect();
+
+        // extreme and mismatched noise between the world and the filter's belief
+        let mut cfg = Config::default();
+        let noise_scale = |base: f32| base * 10f32.powf(rand::gen_range(-1.5, 1.0));
+        cfg.real_stdev_linear = noise_scale(cfg.real_stdev_linear);
+        cfg.real_stdev_angular = noise_scale(cfg.real_stdev_angular);
+        cfg.real_stdev_range = noise_scale(cfg.real_stdev_range);
+        cfg.real_stdev_bearing = noise_scale(cfg.real_stdev_bearing);
+        cfg.est_stdev_linear = noise_scale(cfg.est_stdev_linear);
+        cfg.est_stdev_angular = noise_scale(cfg.est_stdev_angular);
+        cfg.est_stdev_range = noise_scale(cfg.est_stdev_range);
+        cfg.est_stdev_bearing = noise_scale(cfg.est_stdev_bearing);
+
+        Self {
+            seed,
+            scenario: Scenario {
+                name: format!("fuzz {seed}"),
+                landmarks,
+                obstructions,
+                boundary: None,
+                dropout_zones: Vec::new(),
+                controls,
+                landmark_events: Vec::new(),
+                checkpoints: Vec::new(),
+                criteria: Criteria::default(),
+                robots: Vec::new(),
+            },
+            cfg,
+        }
+    }
+
+    /*
+     * runs every backend on the case, turning panics, non-finite estimates,
+     * and broken filter invariants into errors
+     */
+    pub fn check(&self) -> Result<(), String> {
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+            scenario::run_observed(&self.scenario, &mut EkfSlam::new(), self.seed, &self.cfg, |ekf, _| {
+                ekf.check_invariants().and_then(|_| check_estimate(ekf))
+            }).map_err(|error| format!("EKF-SLAM {error}"))?;
+
+            scenario::run_observed(&self.scenario, &mut FastSlam::new(10), self.seed, &self.cfg, |fast, _| {
+                check_estimate(fast)
+            }).map_err(|error| format!("FastSLAM {error}"))?;
+
+            Ok(())
+        }));
+
+        match outcome {
+            Ok(result) => result,
+            Err(payload) => {
+                let message = payload.downcast_ref::<&str>().map(|s| s.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".to_string());
+                Err(format!("panicked: {message}"))
+            }
+        }
+    }
+}
+
+/*
+ * filter-agnostic checks through the SlamFilter trait
+ */
+pub fn check_estimate(slam: &(impl SlamFilter + ?Sized)) -> Result<(), String> {
+    let Pose2 { x, y, theta } = slam.estimated_pose();
+    if !(x.is_finite() && y.is_finite() && theta.is_finite()) {
+        return Err(format!("non-finite pose ({x}, {y}, {theta})"));
+    }
+    if slam.pose_covariance().iter().any(|value| !value.is_finite()) {
+        return Err("non-finite pose covariance".to_string());
+    }
+    if let Some((id, _, _)) = slam.estimated_map().into_iter().find(|(_, x, y)| !(x.is_finite() && y.is_finite())) {
+        return Err(format!("non-finite estimate for landmark {id}"));
+    }
+
+    Ok(())
+}
+
+/*
+ * greedily drops landmarks, obstructions, and control segments, and shortens
+ * segments, as long as the case keeps failing
+ */
+pub fn minimize(failure: FuzzFailure) -> FuzzFailure {
+    let mut current = failure;
+
+    loop {
+        let scenario = &current.case.scenario;
+        let mut candidates = Vec::new();
+
+        for i in 0..scenario.landmarks.len() {
+            let mut candidate = scenario.clone();
+            candidate.landmarks.remove(i);
+            candidates.push(candidate);
+        }
+        for i in 0..scenario.obstructions.len() {
+            let mut candidate = scenario.clone();
+            candidate.obstructions.remove(i);
+            candidates.push(candidate);
+        }
+        for i in 0..scenario.controls.len() {
+            if scenario.controls.len() > 1 {
+                let mut candidate = scenario.clone();
+                candidate.controls.remove(i);
+                candidates.push(candidate);
+            }
+            if scenario.controls[i].duration > 0.1 {
+                let mut candidate = scenario.clone();
+                candidate.controls[i].duration /= 2.0;
+                candidates.push(candidate);
+            }
+        }
+
+        let smaller = candidates.into_iter()
+            .map(|scenario| FuzzCase { seed: current.case.seed, scenario, cfg: current.case.cfg.clone() })
+            .find_map(|case| case.check().err().map(|error| FuzzFailure { case, error }));
+
+        match smaller {
+            Some(failure) => current = failure,
+            None => return current,
+        }
+    }
+}
+
+/*
+ * checks `runs` consecutive seeds starting at `first_seed`, returning the
+ * minimized failures
+ */
+pub fn soak(first_seed: u64, runs: u64) -> Vec<FuzzFailure> {
+    (first_seed..first_seed + runs)
+        .filter_map(|seed| {
+            let case = FuzzCase::generate(seed);
+            case.check().err().map(|error| minimize(FuzzFailure { case, error }))
+        })
+        .collect()
+}
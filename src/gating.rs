@@ -0,0 +1,92 @@
+use crate::config::Config;
+use crate::recording::Recording;
+use crate::simulation::sensor;
+
+/*
+ * one recorded measurement of a mapped landmark, seen by an EKF that knows the
+ * ids: the squared Mahalanobis distance of its innovation against the right
+ * landmark, and against the closest wrong one, if any other is mapped
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GateSample {
+    pub true_distance: f32,
+    pub impostor_distance: Option<f32>,
+}
+
+/*
+ * what a chi-squared gate on the squared Mahalanobis distance would have done
+ * with the samples: the share of measurements of a mapped landmark it lets
+ * through to their landmark, and the share nearest-neighbor association within
+ * it would have given to the wrong landmark
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GatePoint {
+    pub threshold: f32,
+    pub acceptance: f32,
+    pub error_rate: f32,
+}
+
+/*
+ * replays the recording's inputs through an EKF from its recorded start and
+ * gathers a sample for every measurement of an already mapped landmark, taken
+ * before the update that uses it
+ */
+pub fn collect(recording: &Recording, cfg: &Config) -> Result<Vec<GateSample>, String> {
+    if recording.inputs.is_empty() {
+        return Err("the recording has no filter inputs; record it again to tune the gate on it".to_string());
+    }
+    let mut samples = Vec::new();
+    recording.replay_ekf(
+        cfg,
+        |_, _, inputs, ekf| {
+            for (observation, readings) in sensor::compress(&inputs.observations) {
+                let Some((_, true_distance)) = ekf.association_cost(&observation, observation.id, readings, cfg) else { continue; };
+                let impostor_distance = ekf.observed_landmarks.ids()
+                    .filter(|&id| id != observation.id)
+                    .filter_map(|id| ekf.association_cost(&observation, id, readings, cfg).map(|(_, distance)| distance))
+                    .min_by(f32::total_cmp);
+                samples.push(GateSample { true_distance, impostor_distance });
+            }
+            true
+        },
+        |_, _, _, _| {},
+    );
+    Ok(samples)
+}
+
+/*
+ * `count` thresholds spread evenly in log space from `min` to `max`, since
+ * chi-squared gates worth comparing span orders of magnitude
+ */
+pub fn thresholds(min: f32, max: f32, count: usize) -> Vec<f32> {
+    if count < 2 { return vec![min]; }
+    let ratio = (max / min).ln();
+    (0..count).map(|i| min * (ratio * i as f32 / (count - 1) as f32).exp()).collect()
+}
+
+pub fn sweep(samples: &[GateSample], thresholds: &[f32]) -> Vec<GatePoint> {
+    let total = samples.len().max(1) as f32;
+    thresholds.iter()
+        .map(|&threshold| {
+            let accepted = samples.iter().filter(|sample| sample.true_distance <= threshold).count();
+            // the closest landmark within the gate is a wrong one
+            let wrong = samples.iter()
+                .filter(|sample| sample.impostor_distance.is_some_and(|impostor| impostor <= threshold && impostor < sample.true_distance))
+                .count();
+            GatePoint { threshold, acceptance: accepted as f32 / total, error_rate: wrong as f32 / total }
+        })
+        .collect()
+}
+
+/*
+ * the point with the fewest wrong decisions, rejected good measurements plus
+ * wrong associations, and the smallest threshold among equals
+ */
+pub fn recommend(points: &[GatePoint]) -> Option<GatePoint> {
+    points.iter()
+        .copied()
+        .min_by(|a, b| {
+            let cost = |point: &GatePoint| 1.0 - point.acceptance + point.error_rate;
+            cost(a).total_cmp(&cost(b)).then(a.threshold.total_cmp(&b.threshold))
+        })
+}
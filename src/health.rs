@@ -0,0 +1,139 @@
+use std::collections::VecDeque;
+use macroquad::prelude::{Color, GREEN, RED, YELLOW};
+
+use crate::config::Config;
+use crate::consistency::measurement_nis;
+use crate::simulation::Observation;
+use crate::slam::{EkfSlam, SlamFilter};
+
+/*
+ * how far to trust the estimate, as a traffic light
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Health {
+    Poor,
+    Fair,
+    Good,
+}
+
+impl Health {
+    pub const ALL: [Health; 3] = [Health::Poor, Health::Fair, Health::Good];
+
+    pub fn from_score(score: f32) -> Self {
+        if score >= 0.6 {
+            Health::Good
+        } else if score >= 0.3 {
+            Health::Fair
+        } else {
+            Health::Poor
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Health::Poor => "poor",
+            Health::Fair => "fair",
+            Health::Good => "good",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Health::Poor => RED,
+            Health::Fair => YELLOW,
+            Health::Good => GREEN,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct FrameSample {
+    time: f32,
+    nis: f32,            // summed over the frame's measurements of mapped landmarks
+    nis_count: usize,
+    measurements: usize, // all of the frame's measurements, new landmarks included
+}
+
+/*
+ * the three things a score is made of, each in [0, 1] with 1 the best
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HealthReport {
+    pub mean_nis: Option<f32>, // per measurement, 2 when the filter is consistent
+    pub position_stdev: f32,
+    pub measurement_rate: f32, // measurements per frame
+    pub consistency: f32,
+    pub certainty: f32,
+    pub support: f32,
+}
+
+impl HealthReport {
+    /*
+     * the weakest of the three: a confident filter that has stopped seeing
+     * landmarks, or one whose innovations are too large for its covariance,
+     * shouldn't be trusted however well the rest looks
+     */
+    pub fn score(&self) -> f32 {
+        self.consistency.min(self.certainty).min(self.support)
+    }
+
+    pub fn health(&self) -> Health {
+        Health::from_score(self.score())
+    }
+}
+
+/*
+ * the EKF's recent normalized innovation squared, measurement count, and pose
+ * uncertainty, over the last `health_window` seconds
+ */
+pub struct LocalizationHealth {
+    frames: VecDeque<FrameSample>,
+}
+
+impl LocalizationHealth {
+    pub fn new() -> Self {
+        Self { frames: VecDeque::new() }
+    }
+
+    /*
+     * takes the NIS of each of the frame's measurements of an already mapped
+     * landmark; call it before the update that uses them
+     */
+    pub fn record(&mut self, time: f32, ekf: &EkfSlam, observations: &[Observation], cfg: &Config) {
+        let nis = measurement_nis(ekf, observations, cfg);
+        self.frames.push_back(FrameSample { time, nis: nis.iter().sum(), nis_count: nis.len(), measurements: observations.len() });
+        while self.frames.front().is_some_and(|frame| time - frame.time > cfg.health_window) {
+            self.frames.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /*
+     * the window's report, with `slam`'s current pose uncertainty; None before
+     * the first frame
+     */
+    pub fn report<S: SlamFilter + ?Sized>(&self, slam: &S, cfg: &Config) -> Option<HealthReport> {
+        if self.frames.is_empty() { return None; }
+        let nis_count: usize = self.frames.iter().map(|frame| frame.nis_count).sum();
+        let mean_nis = (nis_count > 0).then(|| self.frames.iter().map(|frame| frame.nis).sum::<f32>() / nis_count as f32);
+        let measurement_rate = self.frames.iter().map(|frame| frame.measurements).sum::<usize>() as f32 / self.frames.len() as f32;
+
+        let covariance = slam.pose_covariance();
+        let position_stdev = (covariance[(0, 0)] + covariance[(1, 1)]).max(0.0).sqrt();
+
+        // an overconfident filter loses trust as its NIS grows past the expected 2,
+        // down to none at `health_max_nis` times that; a cautious one keeps it
+        let consistency = mean_nis.map_or(1.0, |nis| 1.0 - (nis / 2.0).max(1.0).ln() / cfg.health_max_nis.ln());
+        Some(HealthReport {
+            mean_nis,
+            position_stdev,
+            measurement_rate,
+            consistency: consistency.clamp(0.0, 1.0),
+            certainty: (1.0 - position_stdev / cfg.health_max_position_stdev).clamp(0.0, 1.0),
+            support: (measurement_rate / cfg.health_min_measurements).min(1.0),
+        })
+    }
+}
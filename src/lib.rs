@@ -0,0 +1,41 @@
+// the simulator keeps plain `new()` constructors and an inherent `Config::default()`
+#![allow(clippy::new_without_default, clippy::should_implement_trait)]
+
+pub mod analytics;
+pub mod app;
+pub mod bag;
+pub mod config;
+pub mod consistency;
+pub mod determinism;
+pub mod events;
+pub mod exploration;
+pub mod fleet;
+pub mod fuzz;
+pub mod gating;
+pub mod health;
+pub mod map_export;
+pub mod math;
+pub mod metrics;
+pub mod mission;
+pub mod monte_carlo;
+pub mod motion;
+pub mod navigation;
+pub mod observability;
+pub mod recording;
+pub mod replay;
+pub mod report;
+pub mod scan_map;
+pub mod scenario;
+pub mod session;
+pub mod utils;
+pub mod video;
+pub mod simulation;
+pub mod slam;
+pub mod snapshot;
+pub mod state_dump;
+pub mod stress;
+pub mod summary;
+pub mod template;
+pub mod trails;
+pub mod tuning;
+pub mod update_trace;
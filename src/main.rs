@@ -1,18 +1,41 @@
-use macroquad::prelude::*;
+use std::time::Duration;
 
-mod app;
-mod config;
-mod utils;
-mod simulation;
-mod slam; 
+use macroquad::prelude::*;
 
-use app::{hud, renderer, user_settings};
-use config::Config;
+use slam_simulator::analytics::{self, FrameContext};
+use slam_simulator::app::{covariance_view, diff_view, ellipses::{self, EllipseBatch}, estimates::{Estimates, prior_pose}, gate_view, hud, layout, plots::{self, PlotPane}, noise_widget::NoiseWidget, renderer, summary_view, user_settings, video_view, view::{self, PaneView}};
+use slam_simulator::bag::BagWriter;
+use slam_simulator::config::Config;
+use slam_simulator::determinism;
+use slam_simulator::events::{EventKind, EventLog};
+use slam_simulator::exploration::{CoverageMap, ObservationHeatmap};
+use slam_simulator::fleet::Fleet;
+use slam_simulator::fuzz;
+use slam_simulator::gating::{self, GatePoint};
+use slam_simulator::map_export::ProbabilityMap;
+use slam_simulator::math::{Pose2, normalize_angle};
+use slam_simulator::metrics::{MetricsHistory, track_stdevs};
+use slam_simulator::mission::Mission;
+use slam_simulator::monte_carlo::{self, CovarianceValidation};
+use slam_simulator::navigation::{Navigator, cruise::CruiseControl, planner::BelievedMap};
+use slam_simulator::observability;
+use slam_simulator::recording::{self, FrameInputs, Recording};
+use slam_simulator::replay;
+use slam_simulator::report::{Report, ReportSection, SessionTraces};
+use slam_simulator::scenario::{self, Playback, Scenario, TIME_STEP};
+use slam_simulator::session::{self, Session};
+use slam_simulator::simulation::{self, Actuator, Scan, StuckDetector, World};
+use slam_simulator::snapshot;
+use slam_simulator::state_dump;
+use slam_simulator::stress::{self, StressKind};
+use slam_simulator::slam::{Backend, BearingOnlyEkf, EkfSlam, FastSlam, Initialization, RobocentricEkf, SeifSlam, SlamFilter, UkfSlam};
+use slam_simulator::summary::SessionStats;
+use slam_simulator::tuning::{self, NoiseParameters, TuningSource};
+use slam_simulator::update_trace;
+use slam_simulator::video::{self, VideoSettings};
 use user_settings::UserSettings;
-use simulation::Landmark;
-use slam::{EkfSlam, FastSlam, Slam};
 
-use crate::app::{hud::is_cog_hovered, input};
+use slam_simulator::app::{hud::is_cog_hovered, input::{self, Anchoring, Annotation, AnnotationInput, CovarianceEdit, IdleTracker, Injection, Placement, PlacementInput, Scatter, Selection, Tool}};
 
 // loads font
 const FONT_BYTES: &[u8] = include_bytes!("../assets/fonts/GoogleSansCode-Medium.ttf");
@@ -29,25 +52,692 @@ fn window_conf() -> Conf {
     }
 }
 
-#[macroquad::main(window_conf)]
-async fn main() {
-    let cfg = Config::default();
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    // headless modes never open a window
+    if args.get(1).map(String::as_str) == Some("fuzz") {
+        std::process::exit(fuzz_command(&args));
+    }
+    if args.get(1).map(String::as_str) == Some("montecarlo") {
+        let validations = monte_carlo_command(&args, &Config::default());
+        if !args.iter().any(|arg| arg == "--no-plot") {
+            macroquad::Window::from_config(window_conf(), covariance_overlay(validations));
+        }
+        return;
+    }
+
+    if args.get(1).map(String::as_str) == Some("gate") {
+        let Some((points, samples, recommended)) = gate_command(&args, &Config::default()) else { std::process::exit(1); };
+        if !args.iter().any(|arg| arg == "--no-plot") {
+            macroquad::Window::from_config(window_conf(), gate_overlay(points, samples, recommended));
+        }
+        return;
+    }
+
+    if args.get(1).map(String::as_str) == Some("timeline") {
+        std::process::exit(timeline_command(&args, &Config::default()));
+    }
+
+    if args.get(1).map(String::as_str) == Some("check") {
+        std::process::exit(check_command(&args, &Config::default()));
+    }
+
+    if args.get(1).map(String::as_str) == Some("replay") {
+        std::process::exit(replay_command(&args, &Config::default()));
+    }
+
+    if args.get(1).map(String::as_str) == Some("stress") {
+        std::process::exit(stress_command(&args, &Config::default()));
+    }
+
+    if args.get(1).map(String::as_str) == Some("audit") {
+        std::process::exit(audit_command(&args, &Config::default()));
+    }
+
+    if args.get(1).map(String::as_str) == Some("tune") {
+        std::process::exit(tune_command(&args, &Config::default()));
+    }
+
+    if args.get(1).map(String::as_str) == Some("trace") {
+        std::process::exit(trace_command(&args, &Config::default()));
+    }
+
+    if args.get(1).map(String::as_str) == Some("report") {
+        std::process::exit(report_command(&args, &Config::default()));
+    }
+
+    macroquad::Window::from_config(window_conf(), simulator(args));
+}
+
+/*
+ * `fuzz [runs] [first seed]` soaks the filters in random worlds and returns the exit code
+ */
+fn fuzz_command(args: &[String]) -> i32 {
+    let runs = args.get(2).and_then(|value| value.parse().ok()).unwrap_or(1000);
+    let first_seed = args.get(3).and_then(|value| value.parse().ok()).unwrap_or(0);
+    let failures = fuzz::soak(first_seed, runs);
+
+    for failure in &failures {
+        let scenario = &failure.case.scenario;
+        println!(
+            "seed {}: {} (minimized to {} landmarks, {} obstructions, {} control segments)",
+            failure.case.seed,
+            failure.error,
+            scenario.landmarks.len(),
+            scenario.obstructions.len(),
+            scenario.controls.len()
+        );
+    }
+    println!("{} of {} runs failed", failures.len(), runs);
+
+    if failures.is_empty() { 0 } else { 1 }
+}
+
+/*
+ * the arguments without `--converge`, and `cfg` set to end headless runs at
+ * convergence if it was among them
+ */
+fn convergence_option(args: &[String], cfg: &Config) -> (Vec<String>, Config) {
+    let stop_at_convergence = args.iter().any(|arg| arg == "--converge");
+    let args = args.iter().filter(|arg| *arg != "--converge").cloned().collect();
+    (args, Config { stop_at_convergence, ..cfg.clone() })
+}
+
+/*
+ * the arguments without `--filter <name>` (or `--ukf`, short for `--filter ukf`),
+ * and `cfg` with the backend headless runs estimate with set to the named one
+ */
+fn filter_option(args: &[String], cfg: &Config) -> Result<(Vec<String>, Config), String> {
+    let mut args = args.to_vec();
+    let mut headless_filter = cfg.headless_filter;
+    if let Some(position) = args.iter().position(|arg| arg == "--ukf") {
+        args.remove(position);
+        headless_filter = Backend::Ukf;
+    }
+    if let Some(position) = args.iter().position(|arg| arg == "--filter") {
+        let names = Backend::ALL.map(|backend| backend.name().to_lowercase().replace(' ', "_")).join("|");
+        let name = args.get(position + 1).ok_or(format!("--filter needs one of {names}"))?;
+        headless_filter = Backend::by_name(name).ok_or(format!("unknown filter {name}; pick one of {names}"))?;
+        args.drain(position..=position + 1);
+    }
+    Ok((args, Config { headless_filter, ..cfg.clone() }))
+}
+
+/*
+ * the arguments without `--noise <file.json>`, and `cfg` with the noise
+ * parameters from that file (as written by `tune`) if it was among them
+ */
+fn noise_option(args: &[String], cfg: &Config) -> Result<(Vec<String>, Config), String> {
+    let Some(position) = args.iter().position(|arg| arg == "--noise") else { return Ok((args.to_vec(), cfg.clone())); };
+    let path = args.get(position + 1).ok_or("--noise needs a file")?;
+    let noise = NoiseParameters::load(path.as_ref()).map_err(|error| format!("could not load noise parameters from {path}: {error}"))?;
+    let args = args.iter().enumerate().filter(|&(index, _)| index != position && index != position + 1).map(|(_, arg)| arg.clone()).collect();
+    Ok((args, noise.apply(cfg)))
+}
+
+// a fresh `headless_filter` at the robot's starting pose
+fn headless_filter(cfg: &Config) -> Box<dyn SlamFilter> {
+    let robot = simulation::Robot::new();
+    cfg.headless_filter.build(robot.pose(), cfg)
+}
+
+fn convergence_summary(report: &scenario::RunReport) -> String {
+    match report.convergence_time {
+        Some(time) => format!("converged at {time:.1} s"),
+        None => "did not converge".to_string(),
+    }
+}
+
+/*
+ * `timeline <scenario> [seed] [--converge] [--filter name] [--noise file.json]` runs a scenario headlessly
+ * with the EKF, prints the metrics at each of its checkpoints, and saves the
+ * whole report with the filter snapshots as JSON; --converge ends the run once
+ * coverage, mapping, and the ATE plateau, --filter runs another backend
+ * instead (--ukf for UKF-SLAM), and --noise takes the filter noise from a file
+ * written by `tune`
+ */
+fn timeline_command(args: &[String], cfg: &Config) -> i32 {
+    let (args, cfg) = match noise_option(args, cfg) {
+        Ok(parsed) => parsed,
+        Err(error) => { eprintln!("{error}"); return 1; }
+    };
+    let (args, cfg) = convergence_option(&args, &cfg);
+    let (args, cfg) = match filter_option(&args, &cfg) {
+        Ok(parsed) => parsed,
+        Err(error) => { eprintln!("{error}"); return 1; }
+    };
+    let scenario = match args.get(2).map(|name| Scenario::resolve(name)) {
+        Some(Ok(scenario)) => scenario,
+        Some(Err(error)) => { eprintln!("{error}"); return 1; }
+        None => {
+            eprintln!("usage: timeline <square_loop|corridor|changing_loop|long_loop|fog_loop|drifting_loop|file.json> [seed] [--converge] [--filter name] [--noise file.json]");
+            return 1;
+        }
+    };
+    let seed = args.get(3).and_then(|value| value.parse().ok()).unwrap_or(0);
+    let report = scenario::run(&scenario, headless_filter(&cfg).as_mut(), seed, &cfg);
+
+    println!("{:>8}{:>10}{:>10}{:>10}{:>10}", "time", "ATE", "error", "NEES", "mapped");
+    for checkpoint in &report.checkpoints {
+        println!(
+            "{:>8.1}{:>10.2}{:>10.2}{:>10.2}{:>10}",
+            checkpoint.time, checkpoint.ate, checkpoint.error, checkpoint.mean_nees, checkpoint.landmarks_initialized
+        );
+    }
+    println!("{:>8}{:>10.2}{:>10.2}{:>10.2}{:>10}", "end", report.ate, report.final_error, report.mean_nees, report.landmarks_initialized);
+    println!("{}", convergence_summary(&report));
+    let failures = scenario.criteria.failures(&report);
+    for failure in &failures { println!("FAILED: {failure}"); }
+
+    let path = recording::timestamped_path("timelines", &scenario.name.replace(' ', "_"), "json");
+    match snapshot::save(&report, &path) {
+        Ok(()) => println!("saved timeline to {}", path.display()),
+        Err(error) => { eprintln!("could not save timeline: {error}"); return 1; }
+    }
+    if failures.is_empty() { 0 } else { 1 }
+}
+
+/*
+ * `check [scenario] [seed] [--converge] [--filter name] [--noise file.json]` runs the EKF (or the
+ * backend --filter names) through one canned scenario, or all of them, and holds each run to its
+ * scenario's criteria; the exit code is 1 if any missed one
+ */
+fn check_command(args: &[String], cfg: &Config) -> i32 {
+    let (args, cfg) = match noise_option(args, cfg) {
+        Ok(parsed) => parsed,
+        Err(error) => { eprintln!("{error}"); return 1; }
+    };
+    let (args, cfg) = convergence_option(&args, &cfg);
+    let (args, cfg) = match filter_option(&args, &cfg) {
+        Ok(parsed) => parsed,
+        Err(error) => { eprintln!("{error}"); return 1; }
+    };
+    let scenarios = match args.get(2) {
+        Some(name) => match Scenario::resolve(name) {
+            Ok(scenario) => vec![scenario],
+            Err(error) => {
+                eprintln!("{error}; usage: check [square_loop|corridor|changing_loop|long_loop|fog_loop|drifting_loop|file.json] [seed] [--converge] [--filter name] [--noise file.json]");
+                return 1;
+            }
+        },
+        None => Scenario::all(),
+    };
+    let seed = args.get(3).and_then(|value| value.parse().ok()).unwrap_or(0);
+
+    let mut failed = 0;
+    for scenario in &scenarios {
+        scenario::seed_prior(seed);
+        let report = scenario::run(scenario, headless_filter(&cfg).as_mut(), seed, &cfg);
+        let failures = scenario.criteria.failures(&report);
+        if failures.is_empty() {
+            println!(
+                "{}: passed (ATE {:.2}, NEES {:.2}, {} mapped, {})",
+                scenario.name, report.ate, report.mean_nees, report.landmarks_initialized, convergence_summary(&report)
+            );
+        } else {
+            println!("{}: FAILED, {}", scenario.name, failures.join(", "));
+            failed += 1;
+        }
+    }
+    println!("{} of {} scenarios passed", scenarios.len() - failed, scenarios.len());
+    if failed == 0 { 0 } else { 1 }
+}
+
+/*
+ * `tune <scenario|recording.csv>...` searches the EKF's noise parameters for the
+ * lowest ATE with a consistent NEES over the given scenarios (each run with
+ * `tune_seeds` seeds) and recordings, and writes the best to `tuning/` for
+ * `--noise`
+ */
+fn tune_command(args: &[String], cfg: &Config) -> i32 {
+    if args.len() < 3 {
+        eprintln!("usage: tune <square_loop|corridor|changing_loop|long_loop|fog_loop|drifting_loop|file.json|recording.csv>...");
+        return 1;
+    }
+    let sources = match args[2..].iter().map(|name| TuningSource::resolve(name)).collect::<Result<Vec<_>, _>>() {
+        Ok(sources) => sources,
+        Err(error) => { eprintln!("{error}"); return 1; }
+    };
+    println!(
+        "tuning on {} over {} evaluations",
+        sources.iter().map(TuningSource::name).collect::<Vec<_>>().join(", "),
+        cfg.tune_iterations
+    );
+
+    let result = tuning::tune(&sources, cfg);
+    println!("{:<10}{:>10}{:>10}{:>10}{:>10}{:>10}{:>10}{:>10}", "", "linear", "angular", "range", "bearing", "ATE", "NEES", "cost");
+    for (name, noise, score) in [("default", result.initial, result.initial_score), ("tuned", result.best, result.best_score)] {
+        println!(
+            "{name:<10}{:>10.4}{:>10.4}{:>10.3}{:>10.4}{:>10.2}{:>10.2}{:>10.2}",
+            noise.est_stdev_linear, noise.est_stdev_angular, noise.est_stdev_range, noise.est_stdev_bearing, score.ate, score.mean_nees, score.cost
+        );
+    }
+
+    let path = recording::timestamped_path("tuning", "noise", "json");
+    match result.best.save(&path) {
+        Ok(()) => println!("saved the tuned noise to {}; pass it to timeline or check with --noise", path.display()),
+        Err(error) => { eprintln!("could not save the tuned noise: {error}"); return 1; }
+    }
+    0
+}
+
+/*
+ * `replay <recording.csv>` runs the recorded filter inputs through the current
+ * EKF, FastSLAM, and SEIF with the default settings, prints their errors next to
+ * the recorded ones, and saves the replayed run next to the original for `diff`
+ */
+fn replay_command(args: &[String], cfg: &Config) -> i32 {
+    let Some(path) = args.get(2).map(std::path::Path::new) else {
+        eprintln!("usage: replay <recording.csv>");
+        return 1;
+    };
+    let original = match Recording::load(path) {
+        Ok(original) => original,
+        Err(error) => { eprintln!("could not load recording: {error}"); return 1; }
+    };
+
+    // every filter starts from its recorded start pose with the prior's uncertainty and an
+    // empty map, so a recording begun right after a restart (X) replays exactly
+    rand::srand(0);
+    let mut ekf = EkfSlam::with_prior(original.start_pose("ekf"), cfg.initial_stdev_position, cfg.initial_stdev_heading);
+    let mut fast = FastSlam::with_prior(cfg.fast_slam_particles, original.start_pose("fast"), cfg.initial_stdev_position, cfg.initial_stdev_heading);
+    let mut seif = SeifSlam::with_prior(original.start_pose("seif"), cfg.initial_stdev_position, cfg.initial_stdev_heading);
+
+    let replayed = match replay::replay(&original, &mut [("ekf", &mut ekf), ("fast", &mut fast), ("seif", &mut seif)], cfg) {
+        Ok(replayed) => replayed,
+        Err(error) => { eprintln!("could not replay {}: {error}", path.display()); return 1; }
+    };
+
+    println!("{:>6}{:>12}{:>12}{:>10}{:>12}{:>12}", "", "RMSE", "replayed", "change", "final", "replayed");
+    for (index, name) in replayed.estimator_names.iter().enumerate() {
+        let before = original.error_summary(original.estimator_index(name).unwrap_or(index));
+        let after = replayed.error_summary(index);
+        println!(
+            "{name:>6}{:>12.2}{:>12.2}{:>+10.2}{:>12.2}{:>12.2}",
+            before.rmse, after.rmse, after.rmse - before.rmse, before.last, after.last
+        );
+    }
+
+    let output = path.with_extension("replay.csv");
+    match replayed.save(&output) {
+        Ok(()) => { println!("saved the replayed run to {}; compare with `diff {} {}`", output.display(), path.display(), output.display()); 0 }
+        Err(error) => { eprintln!("could not save the replayed run: {error}"); 1 }
+    }
+}
+
+/*
+ * `trace <recording.csv> [start] [end]` writes every EKF correction of the
+ * frames between `start` and `end` seconds, with all its intermediate
+ * quantities, to a JSON lines file
+ */
+fn trace_command(args: &[String], cfg: &Config) -> i32 {
+    let Some(path) = args.get(2).map(std::path::Path::new) else {
+        eprintln!("usage: trace <recording.csv> [start seconds] [end seconds]");
+        return 1;
+    };
+    let recording = match Recording::load(path) {
+        Ok(recording) => recording,
+        Err(error) => { eprintln!("could not load recording: {error}"); return 1; }
+    };
+    let start = args.get(3).and_then(|value| value.parse().ok()).unwrap_or(0.0);
+    let end = args.get(4).and_then(|value| value.parse().ok()).unwrap_or(f32::INFINITY);
+
+    let entries = match update_trace::trace_recording(&recording, start, end, cfg) {
+        Ok(entries) => entries,
+        Err(error) => { eprintln!("{error}"); return 1; }
+    };
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("recording");
+    let output = recording::timestamped_path("traces", stem, "jsonl");
+    match update_trace::save(&entries, &output) {
+        Ok(()) => { println!("traced {} corrections to {}", entries.len(), output.display()); 0 }
+        Err(error) => { eprintln!("could not save the trace: {error}"); 1 }
+    }
+}
+
+/*
+ * `stress [kind] [steps] [seed]` runs every backend through the adversarial
+ * scenarios (all kinds by default) at `steps` difficulties from 0 to 1 and
+ * prints each one's ATE and the difficulty it broke down at
+ */
+fn stress_command(args: &[String], cfg: &Config) -> i32 {
+    let kinds = match args.get(2) {
+        Some(name) => match StressKind::by_name(name) {
+            Some(kind) => vec![kind],
+            None => {
+                eprintln!("usage: stress [aliased_corridor|symmetric_room|sparse_desert] [steps] [seed]");
+                return 1;
+            }
+        },
+        None => StressKind::ALL.to_vec(),
+    };
+    let steps = args.get(3).and_then(|value| value.parse().ok()).unwrap_or(5).max(1);
+    let seed = args.get(4).and_then(|value| value.parse().ok()).unwrap_or(0);
+
+    for kind in kinds {
+        println!("{} (seed {seed}), ATE by difficulty; * broken down (ATE over {:.0})", kind.name(), cfg.stress_max_ate);
+        print!("{:<10}", "");
+        for step in 0..steps { print!("{:>9.2}", step as f32 / (steps - 1).max(1) as f32); }
+        println!("{:>12}", "breaks at");
+
+        for backend in Backend::ALL {
+            let points = stress::characterize(kind, || headless_filter(&Config { headless_filter: backend, ..cfg.clone() }), steps, seed, cfg);
+            print!("{:<10}", backend.name());
+            for point in &points {
+                print!("{:>8.1}{}", point.report.ate, if point.broken(cfg) { "*" } else { " " });
+            }
+            match stress::breaking_point(&points, cfg) {
+                Some(difficulty) => println!("{difficulty:>12.2}"),
+                None => println!("{:>12}", "-"),
+            }
+        }
+        println!();
+    }
+    0
+}
+
+/*
+ * `audit [scenario] [seed]` runs a scenario (all of them by default) twice with
+ * every filter and reports the first frame each component stopped being
+ * bit-identical; the exit code says whether the state of any did
+ */
+fn audit_command(args: &[String], cfg: &Config) -> i32 {
+    let scenarios = match args.get(2) {
+        Some(name) => match Scenario::resolve(name) {
+            Ok(scenario) => vec![scenario],
+            Err(error) => {
+                eprintln!("{error}; usage: audit [square_loop|corridor|changing_loop|long_loop|fog_loop|drifting_loop|file.json] [seed]");
+                return 1;
+            }
+        },
+        None => Scenario::all(),
+    };
+    let seed = args.get(3).and_then(|value| value.parse().ok()).unwrap_or(0);
+
+    let mut deterministic = true;
+    for scenario in &scenarios {
+        let report = determinism::audit(scenario, || determinism::default_filters(cfg), seed, cfg);
+        deterministic &= report.is_deterministic();
+        if report.divergences.is_empty() {
+            println!("{}, seed {}: {} frames bit-identical", report.scenario, report.seed, report.frames);
+        }
+        for divergence in &report.divergences {
+            println!(
+                "{}, seed {}: {} {:?} diverged at frame {} of {} (likely {})",
+                report.scenario, report.seed, divergence.component, divergence.kind,
+                divergence.frame, report.frames, divergence.kind.likely_source()
+            );
+        }
+    }
+
+    if deterministic { 0 } else { 1 }
+}
+
+/*
+ * `report [scenario] [seed]` runs every filter through a scenario (all of them
+ * by default) and writes the results as one self-contained HTML report
+ */
+fn report_command(args: &[String], cfg: &Config) -> i32 {
+    let scenarios = match args.get(2) {
+        Some(name) => match Scenario::resolve(name) {
+            Ok(scenario) => vec![scenario],
+            Err(error) => {
+                eprintln!("{error}; usage: report [square_loop|corridor|changing_loop|long_loop|fog_loop|drifting_loop|file.json] [seed]");
+                return 1;
+            }
+        },
+        None => Scenario::all(),
+    };
+    let seed = args.get(3).and_then(|value| value.parse().ok()).unwrap_or(0);
+
+    let sections = scenarios.iter()
+        .map(|scenario| {
+            println!("running {}", scenario.name);
+            scenario::seed_prior(seed);
+            ReportSection::from_scenario(scenario, determinism::default_filters(cfg), seed, cfg)
+        })
+        .collect();
+    let report = Report::new(&format!("SLAM run report (seed {seed})"), sections, cfg);
+
+    let path = recording::timestamped_path("reports", "report", "html");
+    match report.save(&path) {
+        Ok(()) => { println!("saved to {}", path.display()); 0 }
+        Err(error) => { eprintln!("could not save report: {error}"); 1 }
+    }
+}
+
+/*
+ * `montecarlo [runs] [first seed] [--no-plot]` drives each EKF variant through the
+ * square loop once per seed and prints how the actual pose error variance compares
+ * to the reported one; the results go on to the overlay view unless --no-plot
+ */
+fn monte_carlo_command(args: &[String], cfg: &Config) -> Vec<(&'static str, CovarianceValidation)> {
+    let mut numbers = args.iter().skip(2).filter_map(|value| value.parse().ok());
+    let runs = numbers.next().unwrap_or(50);
+    let first_seed = numbers.next().unwrap_or(0);
+    let seeds = first_seed..first_seed + runs;
+    let scenario = Scenario::square_loop();
+    let sample_every = 30;
+    let ekf_from = |pose, cfg: &Config| EkfSlam::with_prior(pose, cfg.initial_stdev_position, cfg.initial_stdev_heading);
+
+    let validations = vec![
+        ("EKF", monte_carlo::validate(&scenario, |pose| ekf_from(pose, cfg), seeds.clone(), sample_every, cfg)),
+        ("FEJ EKF", monte_carlo::validate(&scenario, |pose| ekf_from(pose, cfg).with_first_estimates(), seeds.clone(), sample_every, cfg)),
+        ("Robo EKF", monte_carlo::validate(
+            &scenario,
+            |pose| RobocentricEkf::with_prior(pose, cfg.initial_stdev_position, cfg.initial_stdev_heading),
+            seeds.clone(),
+            sample_every,
+            cfg
+        )),
+        ("UKF", monte_carlo::validate(
+            &scenario,
+            |pose| UkfSlam::with_prior(pose, cfg.initial_stdev_position, cfg.initial_stdev_heading),
+            seeds,
+            sample_every,
+            cfg
+        )),
+    ];
+
+    println!("actual / reported variance over {runs} runs of the {} (above 1 is overconfident)", scenario.name);
+    println!("{:<10}{:>10}{:>10}{:>10}", "", "x", "y", "heading");
+    for (name, validation) in &validations {
+        let [x, y, heading] = validation.variance_ratios();
+        println!("{name:<10}{x:>10.2}{y:>10.2}{heading:>10.3}");
+    }
+
+    validations
+}
+
+/*
+ * `gate <recording.csv>` sweeps the association gate over the recording's
+ * innovations, prints the recommendation and the rates at the configured gate,
+ * and returns the sweep for the plot
+ */
+fn gate_command(args: &[String], cfg: &Config) -> Option<(Vec<GatePoint>, usize, Option<GatePoint>)> {
+    let Some(path) = args.get(2) else {
+        eprintln!("usage: gate <recording.csv> [--no-plot]");
+        return None;
+    };
+    let samples = match Recording::load(path.as_ref()).map_err(|error| error.to_string()).and_then(|recording| gating::collect(&recording, cfg)) {
+        Ok(samples) => samples,
+        Err(error) => { eprintln!("could not tune the gate on {path}: {error}"); return None; }
+    };
+    if samples.is_empty() {
+        eprintln!("{path} has no measurements of mapped landmarks");
+        return None;
+    }
+
+    let points = gating::sweep(&samples, &gating::thresholds(cfg.gate_sweep_min, cfg.gate_sweep_max, cfg.gate_sweep_steps));
+    let recommended = gating::recommend(&points);
+    let configured = gating::sweep(&samples, &[cfg.association_gate])[0];
+    println!("{} measurements of mapped landmarks", samples.len());
+    println!("configured gate {:.2}: {:.1}% accepted, {:.2}% wrong associations", configured.threshold, 100.0 * configured.acceptance, 100.0 * configured.error_rate);
+    if let Some(best) = recommended {
+        println!("recommended gate {:.2}: {:.1}% accepted, {:.2}% wrong associations", best.threshold, 100.0 * best.acceptance, 100.0 * best.error_rate);
+    }
+    Some((points, samples.len(), recommended))
+}
+
+async fn gate_overlay(points: Vec<GatePoint>, samples: usize, recommended: Option<GatePoint>) {
+    let font = load_ttf_font_from_bytes(FONT_BYTES).unwrap();
+    gate_view::run(&points, samples, recommended, &font, &Config::default()).await;
+}
+
+async fn covariance_overlay(validations: Vec<(&'static str, CovarianceValidation)>) {
+    let font = load_ttf_font_from_bytes(FONT_BYTES).unwrap();
+    covariance_view::run(validations, &font).await;
+}
+
+/*
+ * gridlines filling one pane; a rotated grid has to reach the corners, so it
+ * covers the pane's diagonal
+ */
+fn draw_pane_gridlines(camera: &Camera2D, area: Rect, units: f32, grid_unit: f32) {
+    let (x, y) = (camera.target.x, camera.target.y);
+    if camera.rotation == 0.0 {
+        renderer::draw_gridlines(x, y, area.w, area.h, units, grid_unit);
+    } else {
+        let diagonal = area.w.hypot(area.h);
+        renderer::draw_gridlines(x, y, diagonal, diagonal, units * diagonal / area.w, grid_unit);
+    }
+}
+
+/*
+ * renders the recording to `videos/<name>/` as PNG frames at a fixed timestep,
+ * then encodes them into `videos/<name>.mp4` when ffmpeg is installed
+ */
+async fn video_export(args: &[String], font: &Font, cfg: &Config) {
+    let Some(path) = args.get(2).map(std::path::Path::new) else {
+        eprintln!("usage: video <recording.csv> [--size WxH] [--fps N]");
+        return;
+    };
+    let settings = match VideoSettings::from_args(args, cfg) {
+        Ok(settings) => settings,
+        Err(error) => { eprintln!("{error}"); return; }
+    };
+    let recording = match Recording::load(path) {
+        Ok(recording) => recording,
+        Err(error) => { eprintln!("could not load recording: {error}"); return; }
+    };
+
+    let name = path.file_stem().map_or("run".into(), |stem| stem.to_string_lossy());
+    let dir = std::path::Path::new(video::VIDEO_DIR).join(name.as_ref());
+    match video_view::run(&recording, settings, &dir, font, cfg).await {
+        Ok(frames) => println!("rendered {frames} frames at {}x{}, {} fps, to {}", settings.width, settings.height, settings.fps, dir.display()),
+        Err(error) => { eprintln!("could not render {}: {error}", path.display()); return; }
+    }
+
+    let output = dir.with_extension("mp4");
+    match video::encode_mp4(&dir, settings.fps, &output) {
+        Ok(()) => println!("saved video to {}", output.display()),
+        Err(error) => eprintln!("{error}; the frames are still in {}", dir.display()),
+    }
+}
+
+async fn simulator(args: Vec<String>) {
+    let mut cfg = Config::default();
 
-    // settings
-    let mut pause = false;
-    let mut user_settings: UserSettings = Default::default();
-    
     // font
     let font = load_ttf_font_from_bytes(FONT_BYTES)
         .unwrap();
 
-    // rectangles and landmarks
-    let mut obstructions: Vec<Rect> = Vec::new();
-    let mut landmarks: Vec<Landmark> = Vec::new();
+    // `diff <a.csv> <b.csv>` compares two recorded runs instead of simulating
+    if args.len() == 4 && args[1] == "diff" {
+        match (Recording::load(args[2].as_ref()), Recording::load(args[3].as_ref())) {
+            (Ok(a), Ok(b)) => diff_view::run(a, b, &args[2], &args[3], &font).await,
+            (Err(error), _) | (_, Err(error)) => eprintln!("could not load recording: {error}"),
+        }
+        return;
+    }
+
+    // `video <recording.csv> [--size WxH] [--fps N]` renders a recorded run to frames and an MP4
+    if args.get(1).map(String::as_str) == Some("video") {
+        video_export(&args, &font, &cfg).await;
+        return;
+    }
+
+    // settings
+    let mut pause = false;
+    let mut user_settings: UserSettings = Default::default();
+
+    // the robot, rectangles, walls, landmarks, and dropout zones
+    let mut world = World::new(&cfg);
 
-    let mut robot = simulation::Robot::new();
-    let mut ekf_slam = EkfSlam::new();
-    let mut fast_slam = FastSlam::new(100);
+    let mut estimates = Estimates::new(world.robot.pose(), &cfg);
+    let mut coverage_map = CoverageMap::new(cfg.coverage_cell_size);
+    let mut observation_heatmap = ObservationHeatmap::new(cfg.heatmap_cell_size);
+    let mut mission = Mission::new(cfg.goal_tolerance);
+    let mut navigator = Navigator::new();
+    let mut tool = Tool::Obstruction;
+    let mut latest_scan: Option<Scan> = None;
+    let mut stuck_detector = StuckDetector::new();
+    let mut event_log = EventLog::new();
+    let mut sim_time = 0.0;
+    let mut recording: Option<(Recording, BagWriter)> = None;
+    let mut autosave_timer = cfg.autosave_interval;
+    let mut metrics = MetricsHistory::new(cfg.metrics_history_len);
+    let mut plot_pane = PlotPane::Hidden;
+    let mut idle_tracker = IdleTracker::new();
+    let mut last_injection: Option<Injection> = None;
+    let mut inspected: Option<usize> = None; // EKF landmark picked with the inspect tool
+    let mut last_covariance_edit: Option<CovarianceEdit> = None;
+    let mut placement: Option<Placement> = None;
+    let mut annotation: Option<Annotation> = None;
+    let mut cruise: Option<CruiseControl> = None;
+    let mut selection = Selection::new();
+    let mut scatter = Scatter::new(&cfg);
+    let mut gt_view = PaneView::new();
+    let mut estimate_view = PaneView::new();
+    let mut noise_widget: Option<NoiseWidget> = None;
+    let mut session_stats = SessionStats::new();
+    let mut session_traces = SessionTraces::new(&cfg);
+    let mut analytics = analytics::registered();
+
+    // `play <scenario> [seed]` drives a canned scenario in the window, one fixed
+    // time step per frame, so it unfolds as in a headless run however it's paused
+    let mut playback = None;
+    let mut fleet = Fleet::new(&[]);
+    if args.len() >= 3 && args[1] == "play" {
+        let scenario = match Scenario::resolve(&args[2]) {
+            Ok(scenario) => scenario,
+            Err(error) => {
+                eprintln!("{error}; try square_loop, corridor, changing_loop, long_loop, fog_loop, drifting_loop, or a scenario file");
+                return;
+            }
+        };
+        rand::srand(args.get(3).and_then(|seed| seed.parse().ok()).unwrap_or(0));
+        world = scenario.world(&cfg);
+        fleet = Fleet::new(&scenario.robots);
+        playback = Some(Playback::new(scenario));
+    }
+
+    // offer to pick up where the last session left off
+    let recovery_path = std::path::Path::new(session::RECOVERY_PATH);
+    if playback.is_none() && let Some(recovered) = Session::load_recovery(recovery_path) {
+        let restore = loop {
+            clear_background(Color::new(0.1, 0.1, 0.1, 1.0));
+            hud::draw_restore_prompt(&font, user_settings.language, recovered.sim_time);
+            if is_key_pressed(KeyCode::Y) { break true; }
+            if is_key_pressed(KeyCode::N) { break false; }
+            next_frame().await
+        };
+
+        if restore {
+            world.obstructions = recovered.obstruction_rects();
+            world.dropout_zones = recovered.dropout_zones;
+            sim_time = recovered.sim_time;
+            world.robot = recovered.robot;
+            world.landmarks = recovered.landmarks;
+            user_settings = recovered.user_settings;
+            estimates = Estimates::resumed(world.robot.pose(), Session::restore(recovered.filters), &cfg);
+        } else {
+            Session::discard_recovery(recovery_path);
+        }
+    }
+
+    // closing the window saves a last recovery point instead of quitting outright
+    prevent_quit();
 
     loop {
         /*
@@ -56,81 +746,602 @@ async fn main() {
         let viewport_height = screen_height();
         let viewport_width = screen_width();
         
-        let gt_camera = Camera2D {
-            target: vec2(robot.x, robot.y),
-            zoom: vec2(2.0 / cfg.horizontal_units, 2.0 / -cfg.horizontal_units * viewport_width / viewport_height),
-            ..Default::default()
+        // robot-centric view pins the estimated pose to the center with its heading pointing up
+        let (view_x, view_y, view_rotation) = if user_settings.robot_centric_view {
+            let Pose2 { x: est_x, y: est_y, theta: est_theta } = estimates.ekf().estimated_pose();
+            (est_x, est_y, 90.0 - est_theta.to_degrees())
+        } else {
+            (world.robot.x, world.robot.y, 0.0)
+        };
+
+        // split view gives the estimates the right half; linked panes share one pan and zoom
+        let (gt_area, estimate_area) = if user_settings.split_view {
+            (Rect::new(0.0, 0.0, viewport_width / 2.0, viewport_height), Some(Rect::new(viewport_width / 2.0, 0.0, viewport_width / 2.0, viewport_height)))
+        } else {
+            (Rect::new(0.0, 0.0, viewport_width, viewport_height), None)
         };
+        let follow = vec2(view_x, view_y);
+        let gt_camera = gt_view.camera(follow, view_rotation, gt_area, &cfg);
+        let estimate_pane = estimate_area.map(|area| {
+            let pane_view = if user_settings.link_cameras { &gt_view } else { &estimate_view };
+            (pane_view.camera(follow, view_rotation, area, &cfg), area)
+        });
         
-        let delta_time: f32 = get_frame_time();
+        let delta_time: f32 = if playback.is_some() { TIME_STEP } else { get_frame_time() };
 
         /*
          * user input
          */
+        // N opens the measurement noise sliders, which work paused or not and keep their clicks from the world
+        if placement.is_none() && annotation.is_none() && is_key_pressed(KeyCode::N) {
+            noise_widget = if noise_widget.is_some() { None } else { Some(NoiseWidget::new(&cfg)) };
+        }
+        // L switches the HUD language, paused or not, since the settings menu says so
+        if placement.is_none() && annotation.is_none() && is_key_pressed(KeyCode::L) {
+            user_settings.language = user_settings.language.next();
+        }
+        // I prints the EKF's state and covariance, or the pose and the landmark under the mouse, and copies it
+        if placement.is_none() && annotation.is_none() && is_key_pressed(KeyCode::I) {
+            let camera = estimate_pane.as_ref().map_or(&gt_camera, |(camera, _)| camera);
+            let landmark = input::landmark_under_mouse(camera, &estimates.ekf().estimated_map(), &cfg);
+            if let Some(text) = state_dump::dump(estimates.ekf(), landmark) {
+                println!("{text}");
+                miniquad::window::clipboard_set(&text);
+            }
+        }
+        // Ctrl + and Ctrl - scale the HUD, Ctrl 0 resets it
+        if is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl) {
+            let layout = &mut user_settings.hud_layout;
+            if is_key_pressed(KeyCode::Equal) { layout.set_scale(layout.scale * 1.1); }
+            if is_key_pressed(KeyCode::Minus) { layout.set_scale(layout.scale / 1.1); }
+            if is_key_pressed(KeyCode::Key0) { layout.set_scale(1.0); }
+        }
+        let over_widget = noise_widget.as_mut().is_some_and(|widget| widget.input(&mut cfg));
+
         if is_cog_hovered() && is_mouse_button_released(MouseButton::Left) {
             pause = !pause;
+        } else if let Some(editing) = placement.as_mut() {
+            // the world holds still while a pose is typed in
+            match input::placement_input(editing, &cfg) {
+                PlacementInput::Editing => {}
+                PlacementInput::Cancel => placement = None,
+                PlacementInput::Commit(pose) => {
+                    world.robot.place(pose.x, pose.y, pose.theta);
+                    estimates.restart_filters(world.robot.pose(), &cfg);
+                    // whatever the robot was driving toward was relative to where it stood
+                    navigator.cancel();
+                    cruise = None;
+                    placement = None;
+                }
+            }
+        } else if let Some(editing) = annotation.as_mut() {
+            // likewise while a bookmark's note is typed, which then goes into the recording if one is running
+            match input::annotation_input(editing) {
+                AnnotationInput::Editing => {}
+                AnnotationInput::Cancel => annotation = None,
+                AnnotationInput::Commit => {
+                    event_log.push(editing.time, EventKind::Bookmark, world.robot.x, world.robot.y);
+                    if let Some((recording, _)) = recording.as_mut() { recording.bookmark(editing.time, editing.text.trim()); }
+                    annotation = None;
+                }
+            }
         } else if !pause {
-            input::movement_input(&mut robot, &cfg, delta_time);
-            input::obstructions_input(&gt_camera, &mut obstructions, &cfg);
-            input::landmarks_input(&gt_camera, &mut landmarks, &cfg);
+            // space engages cruise control at the current speed and heading
+            if is_key_pressed(KeyCode::Space) {
+                cruise = match cruise {
+                    Some(_) => None,
+                    None => Some(CruiseControl::new(world.robot.linear_velocity, world.robot.theta)),
+                };
+                navigator.cancel();
+            }
+
+            match cruise.as_mut() {
+                Some(cruise) => input::cruise_input(cruise, &cfg),
+                None if playback.is_some() => {} // the scenario has the wheel
+                None => input::movement_input(&mut world.robot, &cfg, delta_time),
+            }
+            // Z resets pan and zoom
+            if is_key_pressed(KeyCode::Z) {
+                gt_view = PaneView::new();
+                estimate_view = PaneView::new();
+            }
+            gt_view.input(&gt_camera, gt_area);
+            if let Some((camera, area)) = &estimate_pane {
+                if user_settings.link_cameras { gt_view.input(camera, *area); } else { estimate_view.input(camera, *area); }
+            }
+
+            // the anchor tool picks estimates, so it works in the estimate pane; navigation goals
+            // can be set in either pane, and the rest act on the world
+            let anchor_camera = estimate_pane.as_ref().map_or(&gt_camera, |(camera, _)| camera);
+            let over_world = gt_area.contains(mouse_position().into()) && !over_widget;
+            let over_estimates = estimate_pane.as_ref().filter(|(_, area)| area.contains(mouse_position().into()) && !over_widget);
+
+            input::tool_input(&mut tool);
+            if (over_world || matches!(tool, Tool::Anchor | Tool::Inspect) || (tool == Tool::Navigate && over_estimates.is_some())) && !over_widget { match tool {
+                Tool::Obstruction => input::obstructions_input(&gt_camera, &mut world.obstructions, &mut user_settings.snap_to_grid, &cfg),
+                Tool::Goal => input::goals_input(&gt_camera, &mut mission, &cfg),
+                Tool::Dropout => input::dropout_input(&gt_camera, &mut world.dropout_zones, &cfg),
+                Tool::Scatter => if let Some(placed) = input::scatter_input(&gt_camera, &mut scatter, &mut world.landmarks, &cfg) {
+                    event_log.note(sim_time, EventKind::Scattered, placed.to_string());
+                },
+                Tool::Select => if let Some((kind, detail)) = input::select_input(&gt_camera, &mut selection, &mut world.landmarks, &mut world.obstructions) {
+                    event_log.note(sim_time, kind, detail);
+                },
+                Tool::Navigate => {
+                    input::navigation_input(over_estimates.map_or(&gt_camera, |(camera, _)| camera), &mut navigator);
+                    if navigator.is_active() { cruise = None; } // a new autopilot goal ends cruising
+                }
+                Tool::Inject => if let Some(observation) = input::injection_input(&gt_camera, &world.robot, &world.landmarks) {
+                    let before = estimates.ekf().estimated_pose();
+                    let trace_before = estimates.ekf().pose_covariance().trace();
+
+                    for (_, slam) in &mut estimates.filters { slam.update(std::slice::from_ref(&observation), &cfg); }
+
+                    let after = estimates.ekf().estimated_pose();
+                    last_injection = Some(Injection {
+                        observation,
+                        pose_change: Pose2::new(after.x - before.x, after.y - before.y, normalize_angle(after.theta - before.theta)),
+                        trace_before,
+                        trace_after: estimates.ekf().pose_covariance().trace(),
+                    });
+                },
+                Tool::Anchor => if let Some(id) = input::anchor_input(anchor_camera, &estimates.ekf().estimated_map(), &cfg) {
+                    let trace_before = estimates.ekf().covariance.trace();
+                    if estimates.ekf_mut().anchor_landmark(id, cfg.anchor_stdev) {
+                        estimates.last_anchoring = Some(Anchoring { id, trace_before, trace_after: estimates.ekf().covariance.trace() });
+                    }
+                },
+                Tool::Inspect => if let Some(pick) = input::inspect_input(anchor_camera, &estimates.ekf().estimated_map(), &cfg) {
+                    inspected = pick;
+                },
+            } }
+            if over_world { input::landmarks_input(&gt_camera, &mut world.landmarks, &world.robot, &cfg); }
+
+            if is_key_pressed(KeyCode::P) { plot_pane = plot_pane.next(); }
+
+            // R starts a recording, pressing it again saves it
+            if is_key_pressed(KeyCode::R) {
+                match recording.take() {
+                    Some((finished, bag)) => {
+                        // the same run also goes out as an MCAP file of ROS 2 messages
+                        let path = recording::timestamped_path("recordings", "run", "csv");
+                        match finished.save(&path).and_then(|()| bag.save(&path.with_extension("mcap"))) {
+                            Ok(()) => event_log.note(sim_time, EventKind::Saved, format!("{} and .mcap", path.display())),
+                            Err(error) => event_log.note(sim_time, EventKind::Failed, format!("recording: {error}")),
+                        }
+                    }
+                    None => {
+                        let start = vec![estimates.ekf().estimated_pose(), estimates.running(Backend::FastSlam).estimated_pose(), estimates.running(Backend::Seif).estimated_pose(), estimates.odometry_baseline.estimated_pose()];
+                        recording = Some((Recording::new(&["ekf", "fast", "seif", "odom"]).starting_from(start), BagWriter::new(&cfg)));
+                    }
+                }
+            }
+
+            // M exports the EKF's landmark map as a map_server probability image
+            if is_key_pressed(KeyCode::M) {
+                let path = recording::timestamped_path("maps", "map", "pgm");
+                match ProbabilityMap::from_slam(estimates.ekf(), &cfg).map(|map| map.save(&path)) {
+                    Some(Ok(())) => event_log.note(sim_time, EventKind::Saved, path.display().to_string()),
+                    Some(Err(error)) => event_log.note(sim_time, EventKind::Failed, format!("map: {error}")),
+                    None => event_log.note(sim_time, EventKind::Failed, "map: no landmarks mapped yet"),
+                }
+            }
+
+            // X restarts the filters from the configured prior around the current true pose
+            if is_key_pressed(KeyCode::X) {
+                estimates.restart_filters(world.robot.pose(), &cfg);
+            }
+
+            // K types in an exact ground-truth pose; placing the robot restarts the filters there
+            if is_key_pressed(KeyCode::K) { placement = Some(Placement::new(&world.robot)); }
+
+            // B bookmarks the current moment, with a note typed in after
+            if is_key_pressed(KeyCode::B) {
+                while get_char_pressed().is_some() {} // not the B itself
+                annotation = Some(Annotation { time: sim_time, text: String::new() });
+            }
+
+            // manual driving takes over from the autopilot
+            if input::is_movement_key_down() { navigator.cancel(); }
         }
         
+        // paused, [ and ] scale the EKF's confidence to see how it recovers from being over- or underconfident
+        if pause && placement.is_none() && annotation.is_none() {
+            let camera = estimate_pane.as_ref().map_or(&gt_camera, |(camera, _)| camera);
+            if let Some((landmark, factor)) = input::covariance_edit_input(camera, &estimates.ekf().estimated_map(), &cfg) {
+                let block_trace = |ekf: &EkfSlam| match landmark {
+                    Some(id) => ekf.landmark_covariance(id).map_or(0.0, |covariance| covariance.trace()),
+                    None => ekf.pose_covariance().trace(),
+                };
+                let trace_before = block_trace(estimates.ekf());
+                if estimates.ekf_mut().scale_covariance(landmark, factor) {
+                    // edits to the same block during one pause add up
+                    let (factor, trace_before) = match &last_covariance_edit {
+                        Some(edit) if edit.landmark == landmark => (edit.factor * factor, edit.trace_before),
+                        _ => (factor, trace_before),
+                    };
+                    last_covariance_edit = Some(CovarianceEdit { landmark, factor, trace_before, trace_after: block_trace(estimates.ekf()) });
+                }
+            }
+        } else if !pause {
+            last_covariance_edit = None;
+        }
+
         /*
          * update logic
          */
-        if !pause {
+        if !pause && placement.is_none() && annotation.is_none() {
+            sim_time += delta_time;
+
+            // scenario playback applies the landmark events due by now and drives the robot
+            if let Some(player) = playback.as_mut() {
+                match player.step(&mut world.landmarks) {
+                    Some(segment) => world.robot.command_velocity(segment.linear, segment.angular, &cfg, delta_time),
+                    None => {
+                        event_log.note(sim_time, EventKind::Finished, format!("{} at t = {:.1} s", player.scenario.name, player.time()));
+                        let title = format!("{} {}", player.scenario.name, user_settings.language.tr("finished (EKF-SLAM)"));
+                        let summary = session_stats.summary(estimates.ekf(), &world.landmarks, &cfg);
+                        let mut section = ReportSection::new(&title, &world.landmarks, world.solid().copied().collect(), world.dropout_zones.clone());
+                        section.filters = session_traces.filter_runs(&summary, estimates.ekf(), &estimates.odometry_baseline);
+                        let report = Report::new(&title, vec![section], &cfg);
+                        summary_view::run(&summary, &report, &title, &font, user_settings.language).await;
+                        playback = None;
+                    }
+                }
+            }
+
+            // autopilot drives from the estimated pose, around the map as the EKF believes it to be
+            let scan = world.robot.scan(&world, &cfg);
+            let believed_landmarks = estimates.ekf().estimated_map();
+            let believed_map = BelievedMap { landmarks: &believed_landmarks, landmark_radius: cfg.landmark_radius, scan_map: &estimates.scan_map };
+            if let Some((linear, angular)) = navigator.update(
+                estimates.ekf().estimated_pose(),
+                (world.robot.linear_velocity, world.robot.angular_velocity),
+                &believed_map,
+                user_settings.dwa_avoidance.then_some(&scan),
+                &cfg,
+                delta_time
+            ) {
+                world.robot.command_velocity(linear, angular, &cfg, delta_time);
+            } else if let Some(cruise) = &cruise {
+                // heading hold uses the true heading, like a compass would
+                let (linear, angular) = cruise.command(world.robot.theta, &cfg);
+                world.robot.command_velocity(linear, angular, &cfg, delta_time);
+            }
+            // the estimate hasn't seen this frame's motion yet, so it is of the pose the scan was taken from
+            estimates.scan_map.record(&scan, estimates.ekf().estimated_pose(), &cfg);
+            latest_scan = Some(scan);
+
             // ground truth robot update
-            robot.update(delta_time, &cfg, &obstructions);
+            let was_bumped = world.robot.bumped;
+            world.step_robot(delta_time, &cfg);
+            fleet.step(delta_time, &cfg, &world, is_key_down);
+            mission.update(world.robot.x, world.robot.y, delta_time);
+
+            // contact sensing
+            if world.robot.bumped && !was_bumped { event_log.push(sim_time, EventKind::Bump, world.robot.x, world.robot.y); }
+            let was_stuck = stuck_detector.stuck;
+            stuck_detector.update(&world.robot, delta_time, &cfg);
+            if stuck_detector.stuck != was_stuck {
+                let kind = if stuck_detector.stuck { EventKind::Stuck } else { EventKind::Unstuck };
+                event_log.push(sim_time, kind, world.robot.x, world.robot.y);
+            }
+
+            // the filters predict from the commands as issued unless told to use the delayed ones
+            let (commanded_linear, commanded_angular) = if user_settings.predict_delayed_commands {
+                (world.robot.linear_velocity, world.robot.angular_velocity)
+            } else {
+                world.robot.commanded_velocity()
+            };
+
+            // while stuck, the zero-velocity pseudo-measurement replaces the commanded linear velocity
+            let odometry_linear = if user_settings.contact_zupt && stuck_detector.stuck { 0.0 } else { commanded_linear };
+
+            // parked with nothing commanding motion; a noiseless zero-velocity
+            // pseudo-measurement would undo the prediction exactly, so skip it instead
+            let parked = user_settings.parked_zupt
+                && !navigator.is_active()
+                && !input::is_movement_key_down()
+                && world.robot.is_at_rest(&cfg);
+
+            // FEJ follows the settings toggle, picking up the current estimates when switched on
+            estimates.ekf_mut().set_first_estimates(user_settings.fej_ekf);
+            estimates.ekf_mut().set_adaptive_noise(user_settings.adaptive_noise, &cfg);
+
+            // the robocentric and MHT EKFs and UKF-SLAM start from the prior whenever they are switched on
+            estimates.set_running(Backend::RobocentricEkf, user_settings.robocentric_ekf, world.robot.pose(), &cfg);
+            estimates.set_running(Backend::Ukf, user_settings.ukf_slam, world.robot.pose(), &cfg);
+            estimates.set_running(Backend::MhtEkf, user_settings.mht_ekf, world.robot.pose(), &cfg);
+
+            if user_settings.bearing_only_ekfs != estimates.bearing_only.is_some() {
+                let start = |initialization| BearingOnlyEkf::with_prior(prior_pose(world.robot.pose(), &cfg), cfg.initial_stdev_position, cfg.initial_stdev_heading, initialization);
+                estimates.bearing_only = user_settings.bearing_only_ekfs
+                    .then(|| (start(Initialization::InverseDepth), start(Initialization::Triangulation)));
+            }
 
             // ekf prediction step
-            ekf_slam.predict(robot.linear_velocity, robot.angular_velocity, delta_time, &cfg);
-            fast_slam.predict(robot.linear_velocity, robot.angular_velocity, delta_time, &cfg);
+            if !parked {
+                for (_, slam) in &mut estimates.filters { slam.predict(odometry_linear, commanded_angular, delta_time, &cfg); }
+                estimates.graph_slam.predict(odometry_linear, commanded_angular, delta_time, &cfg);
+                if let Some((inverse_depth, triangulation)) = estimates.bearing_only.as_mut() {
+                    inverse_depth.predict(odometry_linear, commanded_angular, delta_time, &cfg);
+                    triangulation.predict(odometry_linear, commanded_angular, delta_time, &cfg);
+                }
+                estimates.odometry_baseline.predict(odometry_linear, commanded_angular, delta_time, &cfg);
+            }
             
             // ekf correction step
-            let observations = robot.sense(&landmarks,&obstructions, &cfg);
-            ekf_slam.update(&observations, &cfg);
-            fast_slam.update(&observations, &cfg);
+            let observations = world.sense(&world.robot, &cfg);
+            estimates.record_innovations(sim_time, &observations, &cfg);
+            for (_, slam) in &mut estimates.filters { slam.update(&observations, &cfg); }
+            estimates.graph_slam.update(&observations, &cfg);
+            if let Some((inverse_depth, triangulation)) = estimates.bearing_only.as_mut() {
+                inverse_depth.update(&observations, &cfg);
+                triangulation.update(&observations, &cfg);
+            }
+
+            // mark the sensor footprint around the estimated pose as covered
+            let Pose2 { x: est_x, y: est_y, .. } = estimates.ekf().estimated_pose();
+            coverage_map.mark_observed(est_x, est_y, cfg.sensor_range);
+
+            for observation in &observations {
+                let (x, y) = world.robot.pose().from_range_bearing(observation.range, observation.bearing);
+                observation_heatmap.record(x, y);
+            }
+
+            metrics.record(sim_time, world.robot.pose(), estimates.ekf(), estimates.running(Backend::FastSlam), &estimates.odometry_baseline, estimates.filter(Backend::RobocentricEkf));
+            estimates.record_history(sim_time, world.robot.pose(), &cfg);
+            if let Some((inverse_depth, triangulation)) = &estimates.bearing_only { metrics.record_bearing_only((world.robot.x, world.robot.y), inverse_depth, triangulation); }
+            session_stats.record(sim_time, &world.robot, estimates.ekf(), &estimates.odometry_baseline, &observations, &cfg);
+            session_traces.record(sim_time, (world.robot.x, world.robot.y), estimates.ekf(), &estimates.odometry_baseline);
+
+            if !analytics.is_empty() {
+                let mut filters: Vec<(&str, &dyn SlamFilter)> = estimates.filters.iter().map(|(backend, slam)| (backend.key(), slam.as_ref())).collect();
+                filters.extend([("graph", &estimates.graph_slam as &dyn SlamFilter), ("odom", &estimates.odometry_baseline)]);
+                if let Some((inverse_depth, triangulation)) = &estimates.bearing_only { filters.extend([("idepth", inverse_depth as &dyn SlamFilter), ("tri", triangulation)]); }
+                analytics.on_frame(&FrameContext { time: sim_time, robot: &world.robot, landmarks: &world.landmarks, observations: &observations, filters: &filters });
+            }
+            estimates.trails.record(&world.robot, estimates.odometry_baseline.estimated_pose(), estimates.ekf().estimated_pose(), delta_time, &cfg);
+
+            if let Some((recording, bag)) = recording.as_mut() {
+                recording.push(
+                    sim_time,
+                    world.robot.pose(),
+                    vec![estimates.ekf().estimated_pose(), estimates.running(Backend::FastSlam).estimated_pose(), estimates.running(Backend::Seif).estimated_pose(), estimates.odometry_baseline.estimated_pose()]
+                );
+                recording.push_inputs(FrameInputs {
+                    delta_time,
+                    odometry: (!parked).then_some((odometry_linear, commanded_angular)),
+                    observations: observations.clone(),
+                });
+                let estimators: [(&str, &dyn SlamFilter); 4] = [("ekf", estimates.ekf()), ("fast", estimates.running(Backend::FastSlam)), ("seif", estimates.running(Backend::Seif)), ("odom", &estimates.odometry_baseline)];
+                bag.push(sim_time, world.robot.pose(), &estimators, &world.landmarks, latest_scan.as_ref());
+            }
         }
         
         /*
          * simulation rendering
          */
         clear_background(Color::new(0.1, 0.1, 0.1, 1.0));
-        set_camera(&gt_camera);
-        
-        // gridlines
-        renderer::draw_gridlines(robot.x, robot.y, viewport_width, viewport_height, cfg.horizontal_units, cfg.grid_unit);
+        set_camera(&view::for_drawing(&gt_camera));
+        draw_pane_gridlines(&gt_camera, gt_area, gt_view.units(gt_area, &cfg), cfg.grid_unit);
 
         // shadows
-        renderer::draw_landmarks_shadows(&landmarks, cfg.landmark_radius);
-        renderer::draw_robot_shadow(robot.x, robot.y, cfg.robot_radius);
-        renderer::draw_obstructions_shadows(&obstructions);
+        renderer::draw_landmarks_shadows(&world.landmarks, cfg.landmark_radius);
+        renderer::draw_robot_shadow(world.robot.x, world.robot.y, cfg.robot_radius);
+        renderer::draw_obstructions_shadows(&world.obstructions);
+        renderer::draw_obstructions_shadows(&world.walls);
+
+        if user_settings.show_observation_heatmap { renderer::draw_observation_heatmap(&observation_heatmap); }
+
+        // information gain heatmap and suggested viewpoints
+        if user_settings.show_coverage_map {
+            let Pose2 { x: est_x, y: est_y, .. } = estimates.ekf().estimated_pose();
+            let viewpoints = coverage_map.score_viewpoints(est_x, est_y, estimates.ekf(), &cfg);
+            renderer::draw_coverage_map(&viewpoints, cfg.coverage_cell_size, cfg.num_suggested_viewpoints);
+        }
+
+        if user_settings.show_sensor_wedge {
+            renderer::draw_sensor_wedge(world.robot.pose(), cfg.sensor_range, cfg.min_sensor_range, cfg.sensor_fov);
+        }
 
         // draw obstructions and landmarks
-        renderer::draw_obstructions(&obstructions);
-        renderer::draw_landmarks(&landmarks, cfg.landmark_radius);
+        renderer::draw_obstructions(&world.obstructions);
+        renderer::draw_obstructions(&world.walls);
+        renderer::draw_landmarks(&world.landmarks, cfg.landmark_radius);
+        renderer::draw_dropout_zones(&world.dropout_zones);
+
+        if user_settings.show_uncertainty_tube { renderer::draw_uncertainty_tube(&estimates.uncertainty_tube, EkfSlam::COLOR); }
+        if user_settings.show_trails {
+            renderer::draw_true_trails(&estimates.trails);
+            if estimate_pane.is_none() || user_settings.overlay_trails { renderer::draw_estimated_trails(&estimates.trails, EkfSlam::COLOR); }
+        }
+
+        // mission goals and autopilot path
+        renderer::draw_mission(&mission);
+        if navigator.is_active() {
+            let Pose2 { x: est_x, y: est_y, .. } = estimates.ekf().estimated_pose();
+            renderer::draw_path((est_x, est_y), &navigator.path, ORANGE);
+        }
 
-        // draw "robot"
-        renderer::draw_robot(robot.x, robot.y, robot.theta, cfg.robot_radius, BLUE, WHITE);
+        // lidar beams
+        if user_settings.show_lidar && let Some(scan) = &latest_scan {
+            renderer::draw_scan(world.robot.pose(), scan);
+        }
+
+        // draw "robot", and the scenario's other robots
+        renderer::draw_robot(world.robot.pose(), cfg.robot_radius, if world.robot.bumped { RED } else { BLUE }, WHITE);
+        for member in &fleet.members {
+            renderer::draw_robot(member.robot.pose(), cfg.robot_radius, if member.robot.bumped { RED } else { DARKPURPLE }, WHITE);
+        }
+        if let Some(pose) = placement.as_ref().and_then(Placement::pose) {
+            renderer::draw_robot(pose, cfg.robot_radius, Color::new(0.0, 0.47, 0.95, 0.4), WHITE);
+        }
+        if let Some(widget) = &noise_widget { widget.draw_ellipses(&world.robot, &cfg); }
+        renderer::draw_events(&event_log, sim_time, cfg.event_display_time);
+        analytics.draw_overlays();
+
+        if tool == Tool::Select {
+            let mouse_world = gt_camera.screen_to_world(mouse_position().into());
+            renderer::draw_selection(selection.area, selection.clipboard.as_ref(), mouse_world, cfg.landmark_radius);
+        }
+        if tool == Tool::Scatter {
+            renderer::draw_selection(scatter.area, None, Vec2::ZERO, cfg.landmark_radius);
+        }
+
+        // in split view the estimates get their own pane, next to the world
+        if let Some((camera, area)) = &estimate_pane {
+            let pane_view = if user_settings.link_cameras { &gt_view } else { &estimate_view };
+            set_camera(&view::for_drawing(camera));
+            draw_pane_gridlines(camera, *area, pane_view.units(*area, &cfg), cfg.grid_unit);
+        }
+
+        // in split view the believed trails move over with the estimates
+        if user_settings.show_trails && estimate_pane.is_some() {
+            renderer::draw_estimated_trails(&estimates.trails, EkfSlam::COLOR);
+            if user_settings.overlay_trails { renderer::draw_true_trails(&estimates.trails); }
+        }
+
+        if user_settings.show_believed_walls {
+            renderer::draw_believed_walls(world.solid(), &estimates.scan_map, world.robot.pose(), estimates.ekf().estimated_pose(), EkfSlam::COLOR);
+        }
 
         // SLAM "ghosts"
-        if user_settings.show_ekf_state { renderer::draw_slam_state(&ekf_slam, cfg.robot_radius * 1.5) };
-        if user_settings.show_fast_state { renderer::draw_slam_state(&fast_slam, cfg.robot_radius * 1.5) };
+        for (_, slam) in estimates.filters.iter().filter(|(backend, _)| user_settings.shows_state(*backend)) { renderer::draw_slam_state(slam.as_ref(), cfg.robot_radius * 1.5) };
+        if user_settings.show_fast_particles { renderer::draw_particles(estimates.running(Backend::FastSlam), cfg.robot_radius, cfg.landmark_radius) };
+        if user_settings.show_graph_state {
+            renderer::draw_graph_trajectory(&estimates.graph_slam);
+            renderer::draw_slam_state(&estimates.graph_slam, cfg.robot_radius * 1.5);
+        }
+        for slam in estimates.bearing_only.iter().flat_map(|(inverse_depth, triangulation)| [inverse_depth, triangulation]) { renderer::draw_slam_state(slam, cfg.robot_radius * 1.5) };
 
         // draw landmark estimates
-        if user_settings.show_ekf_landmarks { renderer::draw_slam_landmarks(&ekf_slam, cfg.landmark_radius); }
-        if user_settings.show_fast_landmarks { renderer::draw_slam_landmarks(&fast_slam, cfg.landmark_radius); }
+        if user_settings.color_by_uncertainty {
+            for (_, slam) in estimates.filters.iter().filter(|(backend, _)| user_settings.shows_landmarks(*backend)) { renderer::draw_slam_landmarks_uncertainty(slam.as_ref(), cfg.landmark_radius, cfg.max_landmark_trace); }
+            if user_settings.show_graph_landmarks { renderer::draw_slam_landmarks_uncertainty(&estimates.graph_slam, cfg.landmark_radius, cfg.max_landmark_trace); }
+            for slam in estimates.bearing_only.iter().flat_map(|(inverse_depth, triangulation)| [inverse_depth, triangulation]) { renderer::draw_slam_landmarks_uncertainty(slam, cfg.landmark_radius, cfg.max_landmark_trace); }
+        } else {
+            for (_, slam) in estimates.filters.iter().filter(|(backend, _)| user_settings.shows_landmarks(*backend)) { renderer::draw_slam_landmarks(slam.as_ref(), cfg.landmark_radius); }
+            if user_settings.show_graph_landmarks { renderer::draw_slam_landmarks(&estimates.graph_slam, cfg.landmark_radius); }
+            for slam in estimates.bearing_only.iter().flat_map(|(inverse_depth, triangulation)| [inverse_depth, triangulation]) { renderer::draw_slam_landmarks(slam, cfg.landmark_radius); }
+        }
+
+        // covariance ellipses of the shown estimates and poses, culled to the pane and sent as one batch
+        if user_settings.show_covariance_ellipses {
+            let (camera, area, units) = match &estimate_pane {
+                Some((camera, area)) => (camera, *area, if user_settings.link_cameras { &gt_view } else { &estimate_view }.units(*area, &cfg)),
+                None => (&gt_camera, gt_area, gt_view.units(gt_area, &cfg)),
+            };
+            // a square around the view's center that holds it however it is rotated
+            let reach = 0.5 * units * (area.h / area.w).hypot(1.0);
+            let visible = Rect::new(camera.target.x - reach, camera.target.y - reach, 2.0 * reach, 2.0 * reach);
+
+            let mut batch = EllipseBatch::new();
+            let mut shown: Vec<(bool, &dyn SlamFilter)> = vec![(user_settings.show_graph_landmarks, &estimates.graph_slam)];
+            shown.extend(estimates.filters.iter().map(|(backend, slam)| (user_settings.shows_landmarks(*backend), slam.as_ref())));
+            for slam in shown.into_iter().filter_map(|(show, slam)| show.then_some(slam)) {
+                ellipses::add_landmark_ellipses(&mut batch, slam, visible, area.w / units, &cfg);
+            }
+            let mut states: Vec<(bool, &dyn SlamFilter)> = vec![(user_settings.show_graph_state, &estimates.graph_slam)];
+            states.extend(estimates.filters.iter().map(|(backend, slam)| (user_settings.shows_state(*backend), slam.as_ref())));
+            for slam in states.into_iter().filter_map(|(show, slam)| show.then_some(slam)) {
+                ellipses::add_pose_ellipses(&mut batch, slam, visible, area.w / units, &cfg);
+            }
+            batch.draw();
+        }
+
+        if user_settings.show_ekf_landmarks { renderer::draw_anchors(estimates.ekf(), cfg.landmark_radius); }
+        if tool == Tool::Inspect && let Some(id) = inspected { renderer::draw_inspected(estimates.ekf(), id, cfg.landmark_radius); }
+
+        let weak_direction = if user_settings.show_weak_directions { observability::weakest_directions(estimates.ekf(), 1).pop() } else { None };
+        if let Some(direction) = &weak_direction {
+            renderer::draw_weak_direction(estimates.ekf().estimated_pose().position(), direction, EkfSlam::COLOR);
+        }
+
+        let track = user_settings.show_track_uncertainty.then(|| track_stdevs(estimates.ekf()));
+        if let Some(track) = track { renderer::draw_track_uncertainty(estimates.ekf(), track, EkfSlam::COLOR); }
 
         /*
          * HUD
          */
         set_default_camera();
+        layout::set_current(user_settings.hud_layout);
+
+        if let Some((_, area)) = &estimate_pane { draw_line(area.x, area.y, area.x, area.y + area.h, 2.0, GRAY); }
+
+        plots::draw_plot_pane(&font, plot_pane, &metrics, &estimates.consistency);
 
         if pause { hud::draw_settings(&font, &mut user_settings); }
-        hud::draw_legend(&font);
+        hud::draw_legend(&font, user_settings.language, estimates.is_running(Backend::RobocentricEkf), estimates.is_running(Backend::Ukf), estimates.is_running(Backend::MhtEkf), estimates.bearing_only.is_some(), user_settings.show_trails);
+        if let Some(mht) = estimates.filter(Backend::MhtEkf) { hud::draw_hypotheses(&font, user_settings.language, mht, cfg.max_hypotheses); }
+        if Actuator::is_active(&cfg) { hud::draw_actuation(&font, user_settings.language, &world.robot); }
+        if user_settings.show_motion_gauges { hud::draw_motion_gauges(&font, user_settings.language, &world.robot, &cfg); }
+        if user_settings.show_seif_sparsity { hud::draw_seif_sparsity(&font, user_settings.language, estimates.running(Backend::Seif)); }
+        if let Some(direction) = &weak_direction { hud::draw_weak_direction(&font, user_settings.language, direction); }
+        if let Some(track) = track { hud::draw_track_uncertainty(&font, user_settings.language, track); }
+        if let Some(adaptive) = &estimates.ekf().adaptive_noise { hud::draw_adaptive_noise(&font, user_settings.language, adaptive, (cfg.est_stdev_range, cfg.est_stdev_bearing)); }
+        hud::draw_mission(&font, user_settings.language, &mission);
+        hud::draw_tool(&font, user_settings.language, tool, user_settings.snap_to_grid, &scatter);
+        if let Some(cruise) = &cruise { hud::draw_cruise(&font, user_settings.language, cruise); }
+        hud::draw_events(&font, user_settings.language, &event_log, sim_time, cfg.event_display_time);
+        if !analytics.is_empty() { hud::draw_analytics(&font, &analytics.metrics()); }
+        if recording.is_some() { hud::draw_recording_indicator(&font, user_settings.language); }
+        if let Some(warning) = estimates.consistency.warning && let Some(sample) = estimates.consistency.latest() { hud::draw_consistency_warning(&font, user_settings.language, warning, sample); }
+        if user_settings.show_health && let Some(report) = estimates.localization_health.report(estimates.ekf(), &cfg) { hud::draw_health(&font, user_settings.language, &report); }
+        if let Some(playback) = &playback { hud::draw_playback(&font, user_settings.language, playback, pause || placement.is_some() || annotation.is_some()); }
+        if tool == Tool::Inject && let Some(injection) = &last_injection { hud::draw_injection(&font, user_settings.language, injection); }
+        if tool == Tool::Anchor && let Some(anchoring) = &estimates.last_anchoring { hud::draw_anchoring(&font, user_settings.language, anchoring); }
+        if tool == Tool::Inspect && let Some(id) = inspected { hud::draw_landmark_inspector(&font, user_settings.language, id, &estimates.landmark_history); }
+        if let Some(edit) = &last_covariance_edit { hud::draw_covariance_edit(&font, user_settings.language, edit); }
+        if let Some(placement) = &placement { hud::draw_placement(&font, user_settings.language, placement); }
+        if let Some(annotation) = &annotation { hud::draw_annotation(&font, user_settings.language, annotation); }
+        if let Some(widget) = noise_widget.as_mut() { widget.draw(&font, &mut cfg); }
         
         hud::draw_cog();
 
+        /*
+         * autosave
+         */
+        autosave_timer -= delta_time;
+        let quitting = is_quit_requested();
+        if autosave_timer <= 0.0 || quitting {
+            autosave_timer = cfg.autosave_interval;
+            let session = Session {
+                sim_time,
+                robot: world.robot.clone(),
+                landmarks: world.landmarks.clone(),
+                obstructions: Session::rects_to_tuples(&world.obstructions),
+                dropout_zones: world.dropout_zones.clone(),
+                user_settings: user_settings.clone(),
+                filters: Session::snapshots(&estimates.filters),
+            };
+            if let Err(error) = session.save_recovery(recovery_path) {
+                eprintln!("could not write recovery file: {error}");
+            }
+        }
+        if quitting {
+            let title = user_settings.language.tr("Session summary (EKF-SLAM)");
+            let summary = session_stats.summary(estimates.ekf(), &world.landmarks, &cfg);
+            let mut section = ReportSection::new(title, &world.landmarks, world.solid().copied().collect(), world.dropout_zones.clone());
+            section.filters = session_traces.filter_runs(&summary, estimates.ekf(), &estimates.odometry_baseline);
+            let report = Report::new(title, vec![section], &cfg);
+            summary_view::run(&summary, &report, title, &font, user_settings.language).await;
+            break;
+        }
+
+        /*
+         * idle throttling: nothing to animate, so stop redrawing at full rate
+         */
+        idle_tracker.update(delta_time);
+        let at_rest = !navigator.is_active() && world.robot.is_at_rest(&cfg);
+        let idle = user_settings.idle_throttling
+            && idle_tracker.idle_for > cfg.idle_timeout
+            && (pause || at_rest);
+        if idle {
+            let remaining = 1.0 / cfg.idle_fps - get_frame_time();
+            if remaining > 0.0 { std::thread::sleep(Duration::from_secs_f32(remaining)); }
+        }
+
         next_frame().await
     }
 }
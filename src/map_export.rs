@@ -0,0 +1,90 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::slam::SlamFilter;
+
+/*
+ * estimated landmark map rasterized into occupancy probabilities, row 0 at the
+ * top (largest y), as ROS map_server expects
+ */
+pub struct ProbabilityMap {
+    pub resolution: f32, // world units per cell
+    pub origin: (f32, f32), // world position of the bottom left corner
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<f32>, // row-major occupancy probabilities in [0, 1]
+}
+
+impl ProbabilityMap {
+    /*
+     * each landmark is a disc of `landmark_radius` smeared by its estimate's
+     * covariance; a cell's probability is that of any landmark covering it
+     */
+    pub fn from_slam(slam: &dyn SlamFilter, cfg: &Config) -> Option<Self> {
+        let landmarks: Vec<_> = slam.estimated_map().into_iter()
+            .filter_map(|(id, x, y)| {
+                let spread = slam.landmark_covariance(id)?
+                    + nalgebra::Matrix2::identity() * cfg.landmark_radius.powi(2);
+                Some((x, y, spread.try_inverse()?, spread.trace().sqrt()))
+            })
+            .collect();
+        if landmarks.is_empty() { return None; }
+
+        // the map reaches a few standard deviations past the outermost landmarks
+        let reach = |(x, y, _, extent): &(f32, f32, _, f32), sign: f32| (x + sign * 3.0 * extent, y + sign * 3.0 * extent);
+        let (min_x, min_y) = landmarks.iter().map(|l| reach(l, -1.0)).fold((f32::MAX, f32::MAX), |a, b| (a.0.min(b.0), a.1.min(b.1)));
+        let (max_x, max_y) = landmarks.iter().map(|l| reach(l, 1.0)).fold((f32::MIN, f32::MIN), |a, b| (a.0.max(b.0), a.1.max(b.1)));
+
+        let resolution = cfg.map_export_resolution;
+        let width = ((max_x - min_x) / resolution).ceil() as usize;
+        let height = ((max_y - min_y) / resolution).ceil() as usize;
+
+        let mut cells = vec![0.0; width * height];
+        for row in 0..height {
+            for column in 0..width {
+                let x = min_x + (column as f32 + 0.5) * resolution;
+                let y = min_y + ((height - 1 - row) as f32 + 0.5) * resolution;
+
+                let free = landmarks.iter().fold(1.0, |free, (lx, ly, information, _)| {
+                    let offset = nalgebra::Vector2::new(x - lx, y - ly);
+                    free * (1.0 - (-0.5 * (offset.transpose() * information * offset)[(0, 0)]).exp())
+                });
+                cells[row * width + column] = 1.0 - free;
+            }
+        }
+
+        Some(Self { resolution, origin: (min_x, min_y), width, height, cells })
+    }
+
+    pub fn probability_at(&self, x: f32, y: f32) -> Option<f32> {
+        let column = ((x - self.origin.0) / self.resolution).floor();
+        let row_from_bottom = ((y - self.origin.1) / self.resolution).floor();
+        if column < 0.0 || row_from_bottom < 0.0 || column as usize >= self.width || row_from_bottom as usize >= self.height {
+            return None;
+        }
+        Some(self.cells[(self.height - 1 - row_from_bottom as usize) * self.width + column as usize])
+    }
+
+    /*
+     * binary PGM, dark = occupied (map_server's `trinary` mode with negate 0),
+     * plus the YAML metadata map_server loads next to it
+     */
+    pub fn save(&self, image_path: &Path) -> io::Result<()> {
+        if let Some(parent) = image_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut image = format!("P5\n{} {}\n255\n", self.width, self.height).into_bytes();
+        image.extend(self.cells.iter().map(|p| (255.0 * (1.0 - p.clamp(0.0, 1.0))).round() as u8));
+        fs::write(image_path, image)?;
+
+        let image_name = image_path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+        let metadata = format!(
+            "image: {image_name}\nmode: trinary\nresolution: {}\norigin: [{}, {}, 0.0]\nnegate: 0\noccupied_thresh: 0.65\nfree_thresh: 0.196\n",
+            self.resolution, self.origin.0, self.origin.1,
+        );
+        fs::write(image_path.with_extension("yaml"), metadata)
+    }
+}
@@ -0,0 +1,143 @@
+use nalgebra::{Matrix3, Vector3};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/*
+ * `angle` wrapped to (-PI, PI]
+ */
+pub fn normalize_angle(angle: f32) -> f32 {
+    f32::atan2(angle.sin(), angle.cos())
+}
+
+/*
+ * a pose in the plane, which doubles as the rigid transform from its own frame
+ * into the one it is expressed in: a point (1, 0) in the pose's frame lies one
+ * unit ahead of it. Headings come out of every operation normalized
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Pose2 {
+    pub x: f32,
+    pub y: f32,
+    pub theta: f32,
+}
+
+impl Pose2 {
+    pub const IDENTITY: Pose2 = Pose2 { x: 0.0, y: 0.0, theta: 0.0 };
+
+    pub fn new(x: f32, y: f32, theta: f32) -> Self {
+        Self { x, y, theta }
+    }
+
+    pub fn position(&self) -> (f32, f32) {
+        (self.x, self.y)
+    }
+
+    /*
+     * `other`, given in this pose's frame, in the frame this pose is given in
+     */
+    pub fn compose(&self, other: &Pose2) -> Pose2 {
+        let (x, y) = self.transform_point(other.position());
+        Pose2::new(x, y, normalize_angle(self.theta + other.theta))
+    }
+
+    /*
+     * the frame this pose is given in, seen from the pose
+     */
+    pub fn inverse(&self) -> Pose2 {
+        let (sin, cos) = self.theta.sin_cos();
+        Pose2::new(-(cos * self.x + sin * self.y), -(cos * self.y - sin * self.x), normalize_angle(-self.theta))
+    }
+
+    /*
+     * `other` seen from this pose, both given in the same frame; the inverse of
+     * `compose`, so `a.compose(&a.between(&b))` is `b`
+     */
+    pub fn between(&self, other: &Pose2) -> Pose2 {
+        let (x, y) = self.inverse_transform_point(other.position());
+        Pose2::new(x, y, normalize_angle(other.theta - self.theta))
+    }
+
+    /*
+     * a point in this pose's frame, in the frame the pose is given in
+     */
+    pub fn transform_point(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (sin, cos) = self.theta.sin_cos();
+        (self.x + cos * x - sin * y, self.y + sin * x + cos * y)
+    }
+
+    /*
+     * a point in the frame this pose is given in, in the pose's own frame
+     */
+    pub fn inverse_transform_point(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (sin, cos) = self.theta.sin_cos();
+        let (dx, dy) = (x - self.x, y - self.y);
+        (cos * dx + sin * dy, cos * dy - sin * dx)
+    }
+
+    /*
+     * the point `range` away at `bearing` from the heading, as the sensor reads it
+     */
+    pub fn from_range_bearing(&self, range: f32, bearing: f32) -> (f32, f32) {
+        let angle = self.theta + bearing;
+        (self.x + range * angle.cos(), self.y + range * angle.sin())
+    }
+
+    /*
+     * range and bearing, in (-PI, PI], of a point; the inverse of `from_range_bearing`
+     */
+    pub fn range_bearing(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (dx, dy) = (x - self.x, y - self.y);
+        ((dx * dx + dy * dy).sqrt(), normalize_angle(f32::atan2(dy, dx) - self.theta))
+    }
+
+    /*
+     * homogeneous transform taking [x, y, 1] in this pose's frame to its parent's
+     */
+    pub fn to_matrix(&self) -> Matrix3<f32> {
+        let (sin, cos) = self.theta.sin_cos();
+        Matrix3::new(
+            cos, -sin, self.x,
+            sin, cos, self.y,
+            0.0, 0.0, 1.0
+        )
+    }
+
+    pub fn from_matrix(matrix: &Matrix3<f32>) -> Self {
+        Pose2::new(matrix[(0, 2)], matrix[(1, 2)], f32::atan2(matrix[(1, 0)], matrix[(0, 0)]))
+    }
+
+    pub fn to_vector(&self) -> Vector3<f32> {
+        Vector3::new(self.x, self.y, self.theta)
+    }
+
+    pub fn from_vector(vector: &Vector3<f32>) -> Self {
+        Pose2::new(vector[0], vector[1], vector[2])
+    }
+}
+
+/*
+ * serde for a pose written as an [x, y, heading] array, the way scenario files
+ * have it: `#[serde(with = "math::pose_array")]`
+ */
+pub mod pose_array {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(pose: &Pose2, serializer: S) -> Result<S::Ok, S::Error> {
+        <(f32, f32, f32)>::from(*pose).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pose2, D::Error> {
+        <(f32, f32, f32)>::deserialize(deserializer).map(Pose2::from)
+    }
+}
+
+impl From<(f32, f32, f32)> for Pose2 {
+    fn from((x, y, theta): (f32, f32, f32)) -> Self {
+        Pose2::new(x, y, theta)
+    }
+}
+
+impl From<Pose2> for (f32, f32, f32) {
+    fn from(pose: Pose2) -> Self {
+        (pose.x, pose.y, pose.theta)
+    }
+}
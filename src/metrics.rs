@@ -0,0 +1,311 @@
+use std::collections::{HashMap, VecDeque};
+use nalgebra::{Matrix2, Vector2, Vector3};
+use serde::Serialize;
+
+use crate::math::{Pose2, normalize_angle};
+use crate::slam::SlamFilter;
+
+pub struct MetricSample {
+    pub time: f32,
+    pub ekf_error: f32,
+    pub fast_error: f32,
+    pub odometry_error: f32, // of the dead reckoning baseline
+    pub ekf_trace: f32,
+    pub fast_trace: f32,
+    pub ekf_landmarks: usize,
+    pub ekf_nees: Option<f32>,
+    pub robocentric_error: Option<f32>, // only while the robocentric EKF runs
+    pub robocentric_trace: Option<f32>,
+    pub robocentric_nees: Option<f32>,
+    pub ekf_along_track: f32, // position stdevs along and across the heading
+    pub ekf_cross_track: f32,
+    pub inverse_depth_error: Option<f32>, // only while the bearing-only EKFs run
+    pub triangulation_error: Option<f32>,
+    pub inverse_depth_landmarks: Option<usize>,
+    pub triangulation_landmarks: Option<usize>,
+}
+
+/*
+ * rolling per-frame metrics for the plot pane
+ */
+pub struct MetricsHistory {
+    pub samples: VecDeque<MetricSample>,
+    pub capacity: usize,
+}
+
+impl MetricsHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn record(&mut self, time: f32, ground_truth: Pose2, ekf: &dyn SlamFilter, fast: &dyn SlamFilter, odometry: &dyn SlamFilter, robocentric: Option<&dyn SlamFilter>) {
+        let position = ground_truth.position();
+        let track = track_stdevs(ekf);
+
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+
+        self.samples.push_back(MetricSample {
+            time,
+            ekf_error: position_error(ekf, position),
+            fast_error: position_error(fast, position),
+            odometry_error: position_error(odometry, position),
+            ekf_trace: ekf.pose_covariance().fixed_view::<2, 2>(0, 0).trace(),
+            fast_trace: fast.pose_covariance().fixed_view::<2, 2>(0, 0).trace(),
+            ekf_landmarks: ekf.estimated_map().len(),
+            ekf_nees: pose_nees(ekf, ground_truth),
+            robocentric_error: robocentric.map(|slam| position_error(slam, position)),
+            robocentric_trace: robocentric.map(|slam| slam.pose_covariance().fixed_view::<2, 2>(0, 0).trace()),
+            robocentric_nees: robocentric.and_then(|slam| pose_nees(slam, ground_truth)),
+            ekf_along_track: track.along,
+            ekf_cross_track: track.cross,
+            inverse_depth_error: None,
+            triangulation_error: None,
+            inverse_depth_landmarks: None,
+            triangulation_landmarks: None,
+        });
+    }
+
+    /*
+     * adds the bearing-only EKFs, inverse depth and triangulating, to the sample
+     * just recorded
+     */
+    pub fn record_bearing_only(&mut self, ground_truth: (f32, f32), inverse_depth: &dyn SlamFilter, triangulation: &dyn SlamFilter) {
+        let Some(sample) = self.samples.back_mut() else { return; };
+        sample.inverse_depth_error = Some(position_error(inverse_depth, ground_truth));
+        sample.triangulation_error = Some(position_error(triangulation, ground_truth));
+        sample.inverse_depth_landmarks = Some(inverse_depth.estimated_map().len());
+        sample.triangulation_landmarks = Some(triangulation.estimated_map().len());
+    }
+
+    pub fn series(&self, value: impl Fn(&MetricSample) -> f32) -> Vec<(f32, f32)> {
+        self.samples.iter().map(|sample| (sample.time, value(sample))).collect()
+    }
+
+    /*
+     * like `series`, skipping samples without a value
+     */
+    pub fn optional_series(&self, value: impl Fn(&MetricSample) -> Option<f32>) -> Vec<(f32, f32)> {
+        self.samples.iter().filter_map(|sample| value(sample).map(|v| (sample.time, v))).collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LandmarkSample {
+    pub time: f32,
+    pub x: f32,
+    pub y: f32,
+    pub trace: f32, // of the position covariance, NaN if the filter has none
+}
+
+/*
+ * rolling history of every landmark estimate of one filter, for the inspector:
+ * where it was and how sure, the last `capacity` frames it was mapped. Culled
+ * landmarks keep what they had
+ */
+pub struct LandmarkHistory {
+    pub landmarks: HashMap<usize, VecDeque<LandmarkSample>>,
+    pub capacity: usize,
+}
+
+impl LandmarkHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self { landmarks: HashMap::new(), capacity }
+    }
+
+    pub fn record(&mut self, time: f32, slam: &dyn SlamFilter) {
+        for (id, x, y) in slam.estimated_map() {
+            let trace = slam.landmark_covariance(id).map_or(f32::NAN, |covariance| covariance.trace());
+            let samples = self.landmarks.entry(id).or_insert_with(|| VecDeque::with_capacity(self.capacity));
+            if samples.len() == self.capacity {
+                samples.pop_front();
+            }
+            samples.push_back(LandmarkSample { time, x, y, trace });
+        }
+    }
+
+    pub fn samples(&self, id: usize) -> Option<&VecDeque<LandmarkSample>> {
+        self.landmarks.get(&id)
+    }
+
+    pub fn series(&self, id: usize, value: impl Fn(&LandmarkSample) -> f32) -> Vec<(f32, f32)> {
+        self.samples(id).map_or_else(Vec::new, |samples| samples.iter().map(|sample| (sample.time, value(sample))).collect())
+    }
+
+    /*
+     * the frame the estimate moved most in, as its time and how far it moved;
+     * an update that jerks a landmark, like a bad association, shows up here
+     */
+    pub fn largest_jump(&self, id: usize) -> Option<(f32, f32)> {
+        let samples = self.samples(id)?;
+        samples.iter()
+            .zip(samples.iter().skip(1))
+            .map(|(before, after)| (after.time, (after.x - before.x).hypot(after.y - before.y)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    pub fn clear(&mut self) {
+        self.landmarks.clear();
+    }
+}
+
+pub fn position_error<S: SlamFilter + ?Sized>(slam: &S, ground_truth: (f32, f32)) -> f32 {
+    let Pose2 { x, y, .. } = slam.estimated_pose();
+    ((x - ground_truth.0).powi(2) + (y - ground_truth.1).powi(2)).sqrt()
+}
+
+/*
+ * normalized estimation error squared of the pose; averages near 3 for a
+ * consistent filter, and None while the covariance is singular
+ */
+pub fn pose_nees<S: SlamFilter + ?Sized>(slam: &S, ground_truth: Pose2) -> Option<f32> {
+    let estimate = slam.estimated_pose();
+    let angle_error = estimate.theta - ground_truth.theta;
+    let error = Vector3::new(estimate.x - ground_truth.x, estimate.y - ground_truth.y, normalize_angle(angle_error));
+
+    let information = slam.pose_covariance().try_inverse()?;
+    Some((error.transpose() * information * error)[(0, 0)])
+}
+
+/*
+ * position uncertainty split along the direction of travel and across it: the
+ * first is how unsure the filter is of how far it has come, the second how far
+ * off its line it may have wandered
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrackStdevs {
+    pub along: f32,
+    pub cross: f32,
+}
+
+/*
+ * standard deviations of the 2x2 position covariance projected onto the unit
+ * vector along `heading` and the one left of it. Only the axis matters, so
+ * driving in reverse gives the same split
+ */
+pub fn project_onto_track(position_covariance: &Matrix2<f32>, heading: f32) -> TrackStdevs {
+    let along = Vector2::new(heading.cos(), heading.sin());
+    let cross = Vector2::new(-heading.sin(), heading.cos());
+    let variance = |axis: Vector2<f32>| (axis.transpose() * position_covariance * axis)[(0, 0)].max(0.0);
+    TrackStdevs { along: variance(along).sqrt(), cross: variance(cross).sqrt() }
+}
+
+// the robot can turn on the spot, so the estimated heading stands in for the direction of motion
+pub fn track_stdevs<S: SlamFilter + ?Sized>(slam: &S) -> TrackStdevs {
+    project_onto_track(&slam.pose_covariance().fixed_view::<2, 2>(0, 0).into_owned(), slam.estimated_pose().theta)
+}
+
+/*
+ * minimum-cost assignment of rows to columns (Hungarian algorithm, O(n^3));
+ * `cost` is rows x columns with rows <= columns, and entry i of the result is
+ * the column row i gets
+ */
+pub fn hungarian(cost: &[Vec<f32>]) -> Vec<usize> {
+    let rows = cost.len();
+    let columns = cost.first().map_or(0, Vec::len);
+    assert!(rows <= columns, "more rows than columns");
+
+    // potentials and matching are 1-based, with column 0 as the free root
+    let mut row_potential = vec![0.0f64; rows + 1];
+    let mut column_potential = vec![0.0f64; columns + 1];
+    let mut row_of = vec![0usize; columns + 1];
+    let mut way = vec![0usize; columns + 1];
+
+    for row in 1..=rows {
+        row_of[0] = row;
+        let mut column = 0;
+        let mut min_slack = vec![f64::INFINITY; columns + 1];
+        let mut used = vec![false; columns + 1];
+
+        // grow an alternating tree until it reaches a free column
+        loop {
+            used[column] = true;
+            let current_row = row_of[column];
+            let mut delta = f64::INFINITY;
+            let mut next = 0;
+            for candidate in 1..=columns {
+                if used[candidate] { continue; }
+                let slack = cost[current_row - 1][candidate - 1] as f64 - row_potential[current_row] - column_potential[candidate];
+                if slack < min_slack[candidate] {
+                    min_slack[candidate] = slack;
+                    way[candidate] = column;
+                }
+                if min_slack[candidate] < delta {
+                    delta = min_slack[candidate];
+                    next = candidate;
+                }
+            }
+            for candidate in 0..=columns {
+                if used[candidate] {
+                    row_potential[row_of[candidate]] += delta;
+                    column_potential[candidate] -= delta;
+                } else {
+                    min_slack[candidate] -= delta;
+                }
+            }
+            column = next;
+            if row_of[column] == 0 { break; }
+        }
+
+        // flip the augmenting path
+        while column != 0 {
+            let previous = way[column];
+            row_of[column] = row_of[previous];
+            column = previous;
+        }
+    }
+
+    let mut assignment = vec![0; rows];
+    for column in 1..=columns {
+        if row_of[column] != 0 { assignment[row_of[column] - 1] = column - 1; }
+    }
+    assignment
+}
+
+/*
+ * how well an estimated map matches the true landmarks when ids can't be
+ * trusted: estimates are assigned to true landmarks to minimize the total
+ * squared distance, and pairs farther apart than the gate don't count
+ */
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct MapQuality {
+    pub rmse: f32, // over the matched pairs
+    pub matched: usize,
+    pub unmatched: usize, // true landmarks without an estimate
+    pub spurious: usize, // estimates without a true landmark
+    pub pairs: Vec<(usize, usize)>, // (estimate id, true id)
+}
+
+pub fn map_quality(estimated: &[(usize, f32, f32)], truth: &[(usize, f32, f32)], gate: f32) -> MapQuality {
+    let squared_distance = |a: &(usize, f32, f32), b: &(usize, f32, f32)| (a.1 - b.1).powi(2) + (a.2 - b.2).powi(2);
+
+    // pairs beyond the gate cost the same as leaving both unmatched
+    let cap = gate * gate;
+    let (rows, columns, transposed) = if estimated.len() <= truth.len() { (estimated, truth, false) } else { (truth, estimated, true) };
+    let cost: Vec<Vec<f32>> = rows.iter()
+        .map(|row| columns.iter().map(|column| squared_distance(row, column).min(cap)).collect())
+        .collect();
+
+    let mut pairs = Vec::new();
+    let mut squared_error_sum = 0.0;
+    for (row, &column) in hungarian(&cost).iter().enumerate() {
+        let distance_squared = squared_distance(&rows[row], &columns[column]);
+        if distance_squared > cap { continue; }
+        squared_error_sum += distance_squared;
+        let (estimate, true_landmark) = if transposed { (&columns[column], &rows[row]) } else { (&rows[row], &columns[column]) };
+        pairs.push((estimate.0, true_landmark.0));
+    }
+
+    let matched = pairs.len();
+    MapQuality {
+        rmse: if matched > 0 { (squared_error_sum / matched as f32).sqrt() } else { 0.0 },
+        matched,
+        unmatched: truth.len() - matched,
+        spurious: estimated.len() - matched,
+        pairs,
+    }
+}
@@ -0,0 +1,90 @@
+pub struct Goal {
+    pub x: f32,
+    pub y: f32,
+    pub reached_at: Option<f32>, // mission time at which the goal was reached
+}
+
+/*
+ * ordered list of goal points the robot has to visit; progress is checked
+ * against the ground truth pose
+ */
+pub struct Mission {
+    pub goals: Vec<Goal>,
+    pub tolerance: f32,
+    pub elapsed_time: f32,
+    pub distance_traveled: f32,
+    prev_position: Option<(f32, f32)>,
+}
+
+impl Mission {
+    pub fn new(tolerance: f32) -> Self {
+        Self {
+            goals: Vec::new(),
+            tolerance,
+            elapsed_time: 0.0,
+            distance_traveled: 0.0,
+            prev_position: None,
+        }
+    }
+
+    pub fn add_goal(&mut self, x: f32, y: f32) {
+        self.goals.push(Goal { x, y, reached_at: None });
+    }
+
+    /*
+     * removes the goal within `radius` of (x, y); returns whether one was removed
+     */
+    pub fn remove_goal_near(&mut self, x: f32, y: f32, radius: f32) -> bool {
+        let position = self.goals.iter().position(|goal| {
+            (goal.x - x).powi(2) + (goal.y - y).powi(2) < radius * radius
+        });
+
+        match position {
+            Some(i) => { self.goals.remove(i); true }
+            None => false,
+        }
+    }
+
+    pub fn current_goal(&self) -> Option<&Goal> {
+        self.goals.iter().find(|goal| goal.reached_at.is_none())
+    }
+
+    pub fn completed_count(&self) -> usize {
+        self.goals.iter().filter(|goal| goal.reached_at.is_some()).count()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.current_goal().is_some()
+    }
+
+    /*
+     * advances the mission clock while goals remain and marks the current goal
+     * reached once the ground truth robot is within tolerance
+     */
+    pub fn update(&mut self, robot_x: f32, robot_y: f32, delta_time: f32) {
+        if !self.is_active() {
+            self.prev_position = None;
+            return;
+        }
+
+        self.elapsed_time += delta_time;
+        if let Some((prev_x, prev_y)) = self.prev_position {
+            self.distance_traveled += ((robot_x - prev_x).powi(2) + (robot_y - prev_y).powi(2)).sqrt();
+        }
+        self.prev_position = Some((robot_x, robot_y));
+
+        let elapsed_time = self.elapsed_time;
+        let tolerance = self.tolerance;
+        if let Some(goal) = self.goals.iter_mut().find(|goal| goal.reached_at.is_none())
+            && (goal.x - robot_x).powi(2) + (goal.y - robot_y).powi(2) < tolerance * tolerance {
+            goal.reached_at = Some(elapsed_time);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.goals.clear();
+        self.elapsed_time = 0.0;
+        self.distance_traveled = 0.0;
+        self.prev_position = None;
+    }
+}
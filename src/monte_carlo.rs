@@ -0,0 +1,107 @@
+use std::ops::Range;
+use nalgebra::{Matrix3, Vector3};
+
+use crate::config::Config;
+use crate::math::{Pose2, normalize_angle};
+use crate::scenario::{self, Scenario, TIME_STEP};
+use crate::simulation::Robot;
+use crate::slam::SlamFilter;
+use crate::utils::sample_normal;
+
+/*
+ * one sampled time across every run: how the pose errors actually spread
+ * next to what the filter claimed
+ */
+pub struct CovarianceSample {
+    pub time: f32,
+    pub empirical: Matrix3<f32>, // mean of e e^T over runs, e = estimate - truth; includes any bias
+    pub mean_error: Vector3<f32>,
+    pub reported: Matrix3<f32>, // mean of the filter's pose covariance over runs
+}
+
+pub struct CovarianceValidation {
+    pub runs: usize,
+    pub samples: Vec<CovarianceSample>,
+}
+
+impl CovarianceValidation {
+    /*
+     * actual over reported variance of x, y and heading, averaged over the run;
+     * above 1 the filter is overconfident, below 1 it is too cautious
+     */
+    pub fn variance_ratios(&self) -> [f32; 3] {
+        let count = self.samples.len().max(1) as f32;
+        std::array::from_fn(|state| {
+            self.samples.iter()
+                .map(|sample| sample.empirical[(state, state)] / sample.reported[(state, state)].max(1e-9))
+                .sum::<f32>() / count
+        })
+    }
+
+    pub fn empirical_stdev(&self, state: usize) -> Vec<(f32, f32)> {
+        self.samples.iter().map(|sample| (sample.time, sample.empirical[(state, state)].sqrt())).collect()
+    }
+
+    pub fn reported_stdev(&self, state: usize) -> Vec<(f32, f32)> {
+        self.samples.iter().map(|sample| (sample.time, sample.reported[(state, state)].sqrt())).collect()
+    }
+}
+
+/*
+ * runs a fresh filter through the scenario once per seed and compares, every
+ * `sample_every` frames, the empirical covariance of the pose errors with the
+ * covariance the filter reported. `make_filter` gets a starting pose drawn from
+ * the configured prior around the true one, so the initial error is as
+ * uncertain as the filter is told it is
+ */
+pub fn validate<S: SlamFilter>(
+    scenario: &Scenario,
+    make_filter: impl Fn(Pose2) -> S,
+    seeds: Range<u64>,
+    sample_every: usize,
+    cfg: &Config,
+) -> CovarianceValidation {
+    let mut error_sums: Vec<(Matrix3<f32>, Vector3<f32>, Matrix3<f32>)> = Vec::new();
+    let runs = seeds.end.saturating_sub(seeds.start) as usize;
+
+    for seed in seeds {
+        scenario::seed_prior(seed);
+        let start = Robot::new();
+        let prior = Pose2::new(
+            start.x + sample_normal(0.0, cfg.initial_stdev_position),
+            start.y + sample_normal(0.0, cfg.initial_stdev_position),
+            start.theta + sample_normal(0.0, cfg.initial_stdev_heading),
+        );
+
+        let mut frame = 0;
+        scenario::run_observed(scenario, &mut make_filter(prior), seed, cfg, |slam, robot| {
+            if frame % sample_every == 0 {
+                let Pose2 { x, y, theta } = slam.estimated_pose();
+                let heading_error = theta - robot.theta;
+                let error = Vector3::new(x - robot.x, y - robot.y, normalize_angle(heading_error));
+
+                let index = frame / sample_every;
+                if index == error_sums.len() { error_sums.push((Matrix3::zeros(), Vector3::zeros(), Matrix3::zeros())); }
+                let (squared, sum, reported) = &mut error_sums[index];
+                *squared += error * error.transpose();
+                *sum += error;
+                *reported += slam.pose_covariance();
+            }
+            frame += 1;
+            Ok(())
+        }).ok();
+    }
+
+    let count = runs.max(1) as f32;
+    CovarianceValidation {
+        runs,
+        samples: error_sums.into_iter().enumerate()
+            .map(|(index, (squared, sum, reported))| CovarianceSample {
+                time: (index * sample_every) as f32 * TIME_STEP,
+                empirical: squared / count,
+                mean_error: sum / count,
+                reported: reported / count,
+            })
+            .collect(),
+    }
+}
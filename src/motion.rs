@@ -0,0 +1,57 @@
+use crate::math::{Pose2, normalize_angle};
+
+/*
+ * ways of integrating the unicycle model over one step of constant linear
+ * and angular velocity
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Integrator {
+    Euler,    // heading at the start of the step
+    Midpoint, // heading halfway through the step
+    Rk4,      // classic Runge-Kutta; Simpson's rule on the heading since it is linear in time
+    ExactArc, // closed form circular arc
+}
+
+impl Integrator {
+    pub const ALL: [Integrator; 4] = [Integrator::Euler, Integrator::Midpoint, Integrator::Rk4, Integrator::ExactArc];
+
+    /*
+     * (c, s) such that the step displaces the robot by (v dt c, v dt s); every
+     * integrator here satisfies d(c, s)/d(theta) = (-s, c), which the EKF
+     * motion jacobian relies on
+     */
+    pub fn direction(&self, theta: f32, angular_velocity: f32, delta_time: f32) -> (f32, f32) {
+        let turn = angular_velocity * delta_time;
+        let theta_half = theta + 0.5 * turn;
+        let theta_end = theta + turn;
+
+        match self {
+            Integrator::Euler => (theta.cos(), theta.sin()),
+            Integrator::Midpoint => (theta_half.cos(), theta_half.sin()),
+            Integrator::Rk4 => (
+                (theta.cos() + 4.0 * theta_half.cos() + theta_end.cos()) / 6.0,
+                (theta.sin() + 4.0 * theta_half.sin() + theta_end.sin()) / 6.0,
+            ),
+            // chord of the arc; falls back to the midpoint limit when barely turning
+            Integrator::ExactArc if turn.abs() < 1e-4 => (theta_half.cos(), theta_half.sin()),
+            Integrator::ExactArc => (
+                (theta_end.sin() - theta.sin()) / turn,
+                (theta.cos() - theta_end.cos()) / turn,
+            ),
+        }
+    }
+
+    /*
+     * pose after one step, with the heading normalized to (-PI, PI]
+     */
+    pub fn step(&self, pose: Pose2, linear_velocity: f32, angular_velocity: f32, delta_time: f32) -> Pose2 {
+        let (dir_x, dir_y) = self.direction(pose.theta, angular_velocity, delta_time);
+        let new_theta = pose.theta + angular_velocity * delta_time;
+
+        Pose2::new(
+            pose.x + linear_velocity * delta_time * dir_x,
+            pose.y + linear_velocity * delta_time * dir_y,
+            normalize_angle(new_theta),
+        )
+    }
+}
@@ -0,0 +1,36 @@
+use crate::config::Config;
+use crate::math::normalize_angle;
+
+/*
+ * holds a speed and a heading setpoint; arrow key taps move the setpoints
+ * and a proportional controller steers toward the heading
+ */
+pub struct CruiseControl {
+    pub speed: f32,
+    pub heading: f32,
+}
+
+impl CruiseControl {
+    pub fn new(speed: f32, heading: f32) -> Self {
+        Self { speed, heading }
+    }
+
+    pub fn adjust_speed(&mut self, steps: f32, cfg: &Config) {
+        self.speed = (self.speed + steps * cfg.cruise_speed_step).clamp(-cfg.max_linear_speed, cfg.max_linear_speed);
+    }
+
+    pub fn adjust_heading(&mut self, steps: f32, cfg: &Config) {
+        let heading = self.heading + steps * cfg.cruise_heading_step;
+        self.heading = normalize_angle(heading);
+    }
+
+    /*
+     * commanded (linear, angular) velocity for a robot currently facing `theta`
+     */
+    pub fn command(&self, theta: f32, cfg: &Config) -> (f32, f32) {
+        let error = self.heading - theta;
+        let error = normalize_angle(error);
+
+        (self.speed, (cfg.cruise_heading_gain * error).clamp(-cfg.max_angular_speed, cfg.max_angular_speed))
+    }
+}
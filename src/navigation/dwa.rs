@@ -0,0 +1,69 @@
+use crate::config::Config;
+
+/*
+ * Dynamic Window Approach: samples the velocities reachable within one control
+ * window, rolls each out over a short horizon against the lidar hit points
+ * (robot frame), and picks the admissible pair closest to the reference
+ * command while preferring clearance.
+ * https://www.ri.cmu.edu/pub_files/pub1/fox_dieter_1997_1/fox_dieter_1997_1.pdf
+ */
+pub fn dynamic_window(
+    current: (f32, f32),
+    reference: (f32, f32),
+    obstacles: &[(f32, f32)],
+    cfg: &Config,
+) -> (f32, f32) {
+    const LINEAR_SAMPLES: usize = 7;
+    const ANGULAR_SAMPLES: usize = 11;
+    const STEP: f32 = 0.1;
+
+    let (v_now, w_now) = current;
+    let (v_ref, w_ref) = reference;
+
+    // velocities reachable within the window, bounded by the speed caps
+    let v_low = (v_now - cfg.linear_acc * cfg.dwa_window).max(0.0);
+    let v_high = (v_now + cfg.linear_acc * cfg.dwa_window).min(cfg.max_linear_speed);
+    let w_low = (w_now - cfg.angular_acc * cfg.dwa_window).max(-cfg.max_angular_speed);
+    let w_high = (w_now + cfg.angular_acc * cfg.dwa_window).min(cfg.max_angular_speed);
+
+    let mut best = (0.0, 0.0);
+    let mut best_cost = f32::INFINITY;
+
+    for i in 0..LINEAR_SAMPLES {
+        let v = v_low + (v_high - v_low) * (i as f32) / ((LINEAR_SAMPLES - 1) as f32);
+
+        for j in 0..ANGULAR_SAMPLES {
+            let w = w_low + (w_high - w_low) * (j as f32) / ((ANGULAR_SAMPLES - 1) as f32);
+
+            // roll out the unicycle model from the robot frame origin
+            let (mut x, mut y, mut theta) = (0.0f32, 0.0f32, 0.0f32);
+            let mut clearance = f32::INFINITY;
+            let mut t = 0.0;
+            while t < cfg.dwa_horizon {
+                x += v * STEP * theta.cos();
+                y += v * STEP * theta.sin();
+                theta += w * STEP;
+                t += STEP;
+
+                for &(ox, oy) in obstacles.iter() {
+                    let distance = ((ox - x).powi(2) + (oy - y).powi(2)).sqrt() - cfg.robot_radius;
+                    clearance = clearance.min(distance);
+                }
+            }
+
+            // must not collide and must be able to stop before the closest obstacle
+            if clearance <= 0.0 || v > (2.0 * clearance * cfg.linear_acc).sqrt() { continue; }
+
+            let tracking = ((v - v_ref) / cfg.max_linear_speed).powi(2) + ((w - w_ref) / cfg.max_angular_speed).powi(2);
+            let safety = clearance.min(cfg.dwa_clearance_cap) / cfg.dwa_clearance_cap;
+            let cost = tracking - cfg.dwa_clearance_weight * safety;
+
+            if cost < best_cost {
+                best_cost = cost;
+                best = (v, w);
+            }
+        }
+    }
+
+    best
+}
@@ -0,0 +1,38 @@
+use crate::math::Pose2;
+
+/*
+ * pure pursuit: steer along the arc that passes through the path point one
+ * lookahead distance ahead of the robot
+ * https://www.ri.cmu.edu/pub_files/pub3/coulter_r_craig_1992_1/coulter_r_craig_1992_1.pdf
+ */
+pub fn pure_pursuit(
+    pose: Pose2,
+    path: &[(f32, f32)],
+    lookahead: f32,
+    cruise_speed: f32,
+    max_angular_speed: f32,
+) -> (f32, f32) {
+    let Pose2 { x, y, .. } = pose;
+
+    // first path point at least one lookahead away, or the final point
+    let target = path.iter()
+        .find(|point| (point.0 - x).powi(2) + (point.1 - y).powi(2) >= lookahead * lookahead)
+        .or(path.last());
+
+    let Some(&(target_x, target_y)) = target else { return (0.0, 0.0); };
+
+    let (distance, alpha) = pose.range_bearing((target_x, target_y));
+    let distance = distance.max(1e-3);
+
+    // turn in place when the target is behind the robot
+    if alpha.abs() > std::f32::consts::FRAC_PI_2 {
+        return (0.0, max_angular_speed.copysign(alpha));
+    }
+
+    // slow down on the final approach
+    let speed = cruise_speed.min(distance);
+    let curvature = 2.0 * alpha.sin() / distance;
+    let angular = (speed * curvature).clamp(-max_angular_speed, max_angular_speed);
+
+    (speed, angular)
+}
@@ -0,0 +1,103 @@
+pub mod planner;
+pub mod follower;
+pub mod dwa;
+pub mod cruise;
+
+use crate::config::Config;
+use crate::math::Pose2;
+use crate::simulation::Scan;
+use planner::BelievedMap;
+
+/*
+ * click-to-go autopilot: plans on the believed map from the estimated pose and
+ * follows the plan, replanning periodically as the estimate and the map move
+ */
+pub struct Navigator {
+    pub goal: Option<(f32, f32)>,
+    pub path: Vec<(f32, f32)>,
+    replan_timer: f32,
+}
+
+impl Navigator {
+    pub fn new() -> Self {
+        Self {
+            goal: None,
+            path: Vec::new(),
+            replan_timer: 0.0,
+        }
+    }
+
+    pub fn set_goal(&mut self, x: f32, y: f32) {
+        self.goal = Some((x, y));
+        self.path.clear();
+        self.replan_timer = 0.0;
+    }
+
+    pub fn cancel(&mut self) {
+        self.goal = None;
+        self.path.clear();
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.goal.is_some()
+    }
+
+    /*
+     * returns the commanded (linear, angular) velocity, or None when idle; with
+     * a scan the pure pursuit command is filtered through the dynamic window
+     */
+    pub fn update(
+        &mut self,
+        pose: Pose2,
+        current_velocity: (f32, f32),
+        map: &BelievedMap,
+        scan: Option<&Scan>,
+        cfg: &Config,
+        delta_time: f32
+    ) -> Option<(f32, f32)> {
+        let (goal_x, goal_y) = self.goal?;
+        let Pose2 { x, y, .. } = pose;
+
+        if (goal_x - x).powi(2) + (goal_y - y).powi(2) < cfg.goal_tolerance * cfg.goal_tolerance {
+            self.cancel();
+            return None;
+        }
+
+        self.replan_timer -= delta_time;
+        if self.replan_timer <= 0.0 {
+            self.replan_timer = cfg.nav_replan_interval;
+            match planner::plan_path(
+                (x, y),
+                (goal_x, goal_y),
+                map,
+                cfg.robot_radius + cfg.nav_clearance,
+                cfg.nav_cell_size,
+                cfg.nav_search_margin
+            ) {
+                Some(path) => self.path = path,
+                None => {
+                    self.cancel();
+                    return None;
+                }
+            }
+        }
+
+        // drop waypoints that have been passed
+        while self.path.len() > 1 && (self.path[0].0 - x).powi(2) + (self.path[0].1 - y).powi(2) < cfg.nav_cell_size * cfg.nav_cell_size {
+            self.path.remove(0);
+        }
+
+        let reference = follower::pure_pursuit(
+            pose,
+            &self.path,
+            cfg.nav_lookahead,
+            cfg.nav_cruise_speed,
+            cfg.max_angular_speed
+        );
+
+        match scan {
+            Some(scan) => Some(dwa::dynamic_window(current_velocity, reference, &scan.hit_points(), cfg)),
+            None => Some(reference),
+        }
+    }
+}
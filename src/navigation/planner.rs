@@ -0,0 +1,113 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use crate::scan_map::ScanMap;
+
+/*
+ * the obstacles as the robot believes them to be: its mapped landmarks and the
+ * lidar hits of the scan map, both placed by the pose estimate. Walls the robot
+ * hasn't scanned yet aren't there as far as the planner knows
+ */
+pub struct BelievedMap<'a> {
+    pub landmarks: &'a [(usize, f32, f32)], // id, x, y
+    pub landmark_radius: f32,
+    pub scan_map: &'a ScanMap,
+}
+
+impl BelievedMap<'_> {
+    // (center, radius) of every obstacle
+    fn obstacles(&self) -> impl Iterator<Item = ((f32, f32), f32)> + '_ {
+        self.landmarks.iter()
+            .map(|&(_, x, y)| ((x, y), self.landmark_radius))
+            .chain(self.scan_map.points.iter().map(|&point| (point, 0.0)))
+    }
+}
+
+/*
+ * A* over an 8-connected grid; cells closer than `clearance` to an obstacle of
+ * the believed map are blocked. The search is limited to a box around the start and
+ * goal so an unreachable goal can't stall the frame.
+ */
+pub fn plan_path(
+    start: (f32, f32),
+    goal: (f32, f32),
+    map: &BelievedMap,
+    clearance: f32,
+    cell_size: f32,
+    search_margin: f32,
+) -> Option<Vec<(f32, f32)>> {
+    let to_cell = |x: f32, y: f32| ((x / cell_size).floor() as i32, (y / cell_size).floor() as i32);
+    let to_world = |cell: (i32, i32)| ((cell.0 as f32 + 0.5) * cell_size, (cell.1 as f32 + 0.5) * cell_size);
+
+    let start_cell = to_cell(start.0, start.1);
+    let goal_cell = to_cell(goal.0, goal.1);
+
+    // search bounds in cells
+    let margin = (search_margin / cell_size).ceil() as i32;
+    let min_i = start_cell.0.min(goal_cell.0) - margin;
+    let max_i = start_cell.0.max(goal_cell.0) + margin;
+    let min_j = start_cell.1.min(goal_cell.1) - margin;
+    let max_j = start_cell.1.max(goal_cell.1) + margin;
+
+    // the cells inside the bounds whose centers come within clearance of an obstacle
+    let mut blocked_cells = HashSet::new();
+    for ((x, y), radius) in map.obstacles() {
+        let reach = clearance + radius;
+        let (low_i, low_j) = to_cell(x - reach, y - reach);
+        let (high_i, high_j) = to_cell(x + reach, y + reach);
+        for i in low_i.max(min_i)..=high_i.min(max_i) {
+            for j in low_j.max(min_j)..=high_j.min(max_j) {
+                let (center_x, center_y) = to_world((i, j));
+                if (center_x - x).powi(2) + (center_y - y).powi(2) < reach * reach { blocked_cells.insert((i, j)); }
+            }
+        }
+    }
+    let blocked = |cell: (i32, i32)| blocked_cells.contains(&cell);
+
+    if blocked(goal_cell) { return None; }
+
+    // octile distance heuristic, costs kept as integers (10 straight, 14 diagonal)
+    let heuristic = |cell: (i32, i32)| {
+        let di = (cell.0 - goal_cell.0).abs();
+        let dj = (cell.1 - goal_cell.1).abs();
+        10 * di.max(dj) + 4 * di.min(dj)
+    };
+
+    let mut open = BinaryHeap::new();
+    let mut cost_so_far: HashMap<(i32, i32), i32> = HashMap::new();
+    let mut came_from: HashMap<(i32, i32), (i32, i32)> = HashMap::new();
+
+    open.push(Reverse((heuristic(start_cell), start_cell)));
+    cost_so_far.insert(start_cell, 0);
+
+    while let Some(Reverse((_, cell))) = open.pop() {
+        if cell == goal_cell {
+            // walk back to the start
+            let mut path = vec![goal];
+            let mut current = cell;
+            while let Some(&previous) = came_from.get(&current) {
+                if previous != start_cell { path.push(to_world(previous)); }
+                current = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        let cost = cost_so_far[&cell];
+        for (di, dj) in [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)] {
+            let next = (cell.0 + di, cell.1 + dj);
+            if next.0 < min_i || next.0 > max_i || next.1 < min_j || next.1 > max_j { continue; }
+            if blocked(next) { continue; }
+
+            let step = if di != 0 && dj != 0 { 14 } else { 10 };
+            let new_cost = cost + step;
+            if cost_so_far.get(&next).is_none_or(|&old| new_cost < old) {
+                cost_so_far.insert(next, new_cost);
+                came_from.insert(next, cell);
+                open.push(Reverse((new_cost + heuristic(next), next)));
+            }
+        }
+    }
+
+    None
+}
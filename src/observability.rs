@@ -0,0 +1,74 @@
+use nalgebra::{DVector, SymmetricEigen};
+
+use crate::math::Pose2;
+use crate::slam::EkfSlam;
+
+/*
+ * one direction in the EKF state that the accumulated information barely
+ * constrains: an eigenvector of the covariance (equivalently of the information
+ * matrix, with the smallest information), and how much of it is the global
+ * translation and rotation that range-bearing measurements can never observe
+ */
+pub struct WeakDirection {
+    pub stdev: f32, // uncertainty along the direction
+    pub robot: Pose2, // robot x, y, heading components
+    pub landmarks: Vec<(f32, f32, f32, f32)>, // estimated x, y and the x, y components
+    pub translation_share: f32, // squared length of the projection onto global translation
+    pub rotation_share: f32, // ... and onto global rotation about the map's centroid
+}
+
+/*
+ * unit state directions that move the whole estimate rigidly: translation
+ * along x, along y, and rotation about the centroid of the estimated points
+ */
+pub fn gauge_directions(ekf: &EkfSlam) -> [DVector<f32>; 3] {
+    let size = ekf.state.nrows();
+    let points: Vec<usize> = std::iter::once(0).chain(ekf.observed_landmarks.iter().map(|(_, index)| index)).collect();
+
+    let mut translation_x = DVector::zeros(size);
+    let mut translation_y = DVector::zeros(size);
+    for &index in &points {
+        translation_x[index] = 1.0;
+        translation_y[index + 1] = 1.0;
+    }
+
+    // rotating about the centroid keeps the direction orthogonal to both translations
+    let count = points.len() as f32;
+    let center_x = points.iter().map(|&index| ekf.state[index]).sum::<f32>() / count;
+    let center_y = points.iter().map(|&index| ekf.state[index + 1]).sum::<f32>() / count;
+    let mut rotation = DVector::zeros(size);
+    for &index in &points {
+        rotation[index] = -(ekf.state[index + 1] - center_y);
+        rotation[index + 1] = ekf.state[index] - center_x;
+    }
+    rotation[2] = 1.0;
+
+    [translation_x.normalize(), translation_y.normalize(), rotation.normalize()]
+}
+
+/*
+ * the `count` least constrained directions of the EKF state, weakest first
+ */
+pub fn weakest_directions(ekf: &EkfSlam, count: usize) -> Vec<WeakDirection> {
+    let eigen = SymmetricEigen::new(ekf.covariance.clone());
+    let [translation_x, translation_y, rotation] = gauge_directions(ekf);
+
+    let mut order: Vec<usize> = (0..eigen.eigenvalues.len()).collect();
+    order.sort_by(|&a, &b| eigen.eigenvalues[b].total_cmp(&eigen.eigenvalues[a]));
+
+    order.into_iter().take(count).map(|i| {
+        let direction = eigen.eigenvectors.column(i);
+        let mut landmarks: Vec<(usize, usize)> = ekf.observed_landmarks.iter().collect();
+        landmarks.sort_unstable();
+
+        WeakDirection {
+            stdev: eigen.eigenvalues[i].max(0.0).sqrt(),
+            robot: Pose2::new(direction[0], direction[1], direction[2]),
+            landmarks: landmarks.into_iter()
+                .map(|(_, index)| (ekf.state[index], ekf.state[index + 1], direction[index], direction[index + 1]))
+                .collect(),
+            translation_share: direction.dot(&translation_x).powi(2) + direction.dot(&translation_y).powi(2),
+            rotation_share: direction.dot(&rotation).powi(2),
+        }
+    }).collect()
+}
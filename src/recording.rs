@@ -0,0 +1,282 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::math::Pose2;
+use crate::simulation::Observation;
+use crate::slam::{EkfSlam, SlamFilter};
+use crate::snapshot;
+
+#[derive(Serialize, Deserialize)]
+pub struct RecordedFrame {
+    pub time: f32,
+    pub ground_truth: Pose2,
+    pub estimates: Vec<Pose2>, // one pose per estimator, in `estimator_names` order
+}
+
+/*
+ * what the filters were fed in one frame, enough to run them again offline
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FrameInputs {
+    pub delta_time: f32,
+    pub odometry: Option<(f32, f32)>, // linear and angular velocity; None when the filters skipped the prediction
+    pub observations: Vec<Observation>,
+}
+
+/*
+ * a moment of the session marked while it ran, with what the user noticed there
+ */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub time: f32,
+    pub note: String,
+}
+
+/*
+ * per-frame ground truth and estimated poses of a session, stored as CSV with
+ * one x/y/theta column triple per pose. Bookmarks go in a `.bookmarks.csv` next to it,
+ * and the filter inputs, one per frame, in a `.inputs.json`
+ */
+#[derive(Serialize, Deserialize)]
+pub struct Recording {
+    pub estimator_names: Vec<String>,
+    pub frames: Vec<RecordedFrame>,
+    #[serde(default)]
+    pub bookmarks: Vec<Bookmark>,
+    #[serde(default)]
+    pub inputs: Vec<FrameInputs>, // empty for recordings made before inputs were kept
+    #[serde(default)]
+    pub start: Vec<Pose2>, // each estimator's pose when recording began, kept with the inputs
+}
+
+// the `.inputs.json` sidecar, with the start poses as [x, y, theta] arrays
+#[derive(Serialize, Deserialize)]
+struct InputsFile {
+    start: Vec<(f32, f32, f32)>,
+    frames: Vec<FrameInputs>,
+}
+
+pub struct ErrorSummary {
+    pub rmse: f32,
+    pub mean: f32,
+    pub max: f32,
+    pub last: f32,
+}
+
+impl Recording {
+    pub fn new(estimator_names: &[&str]) -> Self {
+        Self {
+            estimator_names: estimator_names.iter().map(|name| name.to_string()).collect(),
+            frames: Vec::new(),
+            bookmarks: Vec::new(),
+            inputs: Vec::new(),
+            start: Vec::new(),
+        }
+    }
+
+    /*
+     * the estimators' poses before the first frame, which a replay starts from
+     */
+    pub fn starting_from(mut self, start: Vec<Pose2>) -> Self {
+        debug_assert_eq!(start.len(), self.estimator_names.len(), "One start per estimator.");
+        self.start = start;
+        self
+    }
+
+    /*
+     * the inputs behind the frame pushed last
+     */
+    pub fn push_inputs(&mut self, inputs: FrameInputs) {
+        debug_assert_eq!(self.inputs.len() + 1, self.frames.len(), "One set of inputs per frame.");
+        self.inputs.push(inputs);
+    }
+
+    pub fn bookmark(&mut self, time: f32, note: &str) {
+        self.bookmarks.push(Bookmark { time, note: note.to_string() });
+    }
+
+    /*
+     * `run.csv` keeps its bookmarks in `run.bookmarks.csv`
+     */
+    pub fn bookmarks_path(path: &Path) -> PathBuf {
+        path.with_extension("bookmarks.csv")
+    }
+
+    pub fn inputs_path(path: &Path) -> PathBuf {
+        path.with_extension("inputs.json")
+    }
+
+    pub fn push(&mut self, time: f32, ground_truth: Pose2, estimates: Vec<Pose2>) {
+        debug_assert_eq!(estimates.len(), self.estimator_names.len(), "One estimate per estimator.");
+        self.frames.push(RecordedFrame { time, ground_truth, estimates });
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut header = String::from("time,gt_x,gt_y,gt_theta");
+        for name in &self.estimator_names {
+            header += &format!(",{name}_x,{name}_y,{name}_theta");
+        }
+
+        let mut lines = vec![header];
+        for frame in &self.frames {
+            let Pose2 { x, y, theta } = frame.ground_truth;
+            let mut line = format!("{},{x},{y},{theta}", frame.time);
+            for Pose2 { x, y, theta } in &frame.estimates {
+                line += &format!(",{x},{y},{theta}");
+            }
+            lines.push(line);
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, lines.join("\n") + "\n")?;
+
+        if !self.inputs.is_empty() {
+            let inputs = InputsFile { start: self.start.iter().map(|&pose| pose.into()).collect(), frames: self.inputs.clone() };
+            snapshot::save(&inputs, &Self::inputs_path(path))?;
+        }
+
+        if self.bookmarks.is_empty() { return Ok(()); }
+        // the note is the rest of the line, so it may hold commas
+        let mut lines = vec!["time,note".to_string()];
+        for bookmark in &self.bookmarks {
+            lines.push(format!("{},{}", bookmark.time, bookmark.note.replace(['\n', '\r'], " ")));
+        }
+        fs::write(Self::bookmarks_path(path), lines.join("\n") + "\n")
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), message));
+
+        let contents = fs::read_to_string(path)?;
+        let mut lines = contents.lines();
+
+        let header: Vec<&str> = lines.next().ok_or_else(|| invalid("empty recording"))?.split(',').collect();
+        if header.len() < 4 || !(header.len() - 4).is_multiple_of(3) {
+            return Err(invalid("malformed header"));
+        }
+        let estimator_names: Vec<String> = header[4..]
+            .chunks(3)
+            .map(|columns| columns[0].trim_end_matches("_x").to_string())
+            .collect();
+
+        let mut frames = Vec::new();
+        for line in lines.filter(|line| !line.is_empty()) {
+            let values: Vec<f32> = line.split(',')
+                .map(|value| value.parse::<f32>())
+                .collect::<Result<_, _>>()
+                .map_err(|_| invalid("non-numeric value"))?;
+            if values.len() != header.len() {
+                return Err(invalid("row length does not match header"));
+            }
+
+            frames.push(RecordedFrame {
+                time: values[0],
+                ground_truth: Pose2::new(values[1], values[2], values[3]),
+                estimates: values[4..].chunks(3).map(|pose| Pose2::new(pose[0], pose[1], pose[2])).collect(),
+            });
+        }
+
+        let bookmarks = match fs::read_to_string(Self::bookmarks_path(path)) {
+            Ok(contents) => contents.lines()
+                .skip(1)
+                .filter(|line| !line.is_empty())
+                .map(|line| {
+                    let (time, note) = line.split_once(',').unwrap_or((line, ""));
+                    let time = time.parse().map_err(|_| invalid("non-numeric bookmark time"))?;
+                    Ok(Bookmark { time, note: note.to_string() })
+                })
+                .collect::<io::Result<_>>()?,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(error) => return Err(error),
+        };
+
+        let InputsFile { start, frames: inputs } = match snapshot::load(&Self::inputs_path(path)) {
+            Ok(inputs) => inputs,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => InputsFile { start: Vec::new(), frames: Vec::new() },
+            Err(error) => return Err(error),
+        };
+        if !inputs.is_empty() && (inputs.len() != frames.len() || start.len() != estimator_names.len()) {
+            return Err(invalid("inputs do not match the frames"));
+        }
+
+        let start = start.into_iter().map(Pose2::from).collect();
+        Ok(Self { estimator_names, frames, bookmarks, inputs, start })
+    }
+
+    /*
+     * (time, position error) of the given estimator for every frame
+     */
+    pub fn position_errors(&self, estimator: usize) -> Vec<(f32, f32)> {
+        self.frames.iter()
+            .map(|frame| {
+                let Pose2 { x, y, .. } = frame.estimates[estimator];
+                let Pose2 { x: gt_x, y: gt_y, .. } = frame.ground_truth;
+                (frame.time, ((x - gt_x).powi(2) + (y - gt_y).powi(2)).sqrt())
+            })
+            .collect()
+    }
+
+    pub fn error_summary(&self, estimator: usize) -> ErrorSummary {
+        let errors: Vec<f32> = self.position_errors(estimator).iter().map(|(_, error)| *error).collect();
+        let count = errors.len().max(1) as f32;
+
+        ErrorSummary {
+            rmse: (errors.iter().map(|e| e * e).sum::<f32>() / count).sqrt(),
+            mean: errors.iter().sum::<f32>() / count,
+            max: errors.iter().cloned().fold(0.0, f32::max),
+            last: errors.last().cloned().unwrap_or(0.0),
+        }
+    }
+
+    pub fn estimator_index(&self, name: &str) -> Option<usize> {
+        self.estimator_names.iter().position(|n| n == name)
+    }
+
+    /*
+     * where the estimator was when recording began, or for recordings that don't
+     * keep it, where the robot truly was in the first frame
+     */
+    pub fn start_pose(&self, estimator: &str) -> Pose2 {
+        self.estimator_index(estimator)
+            .and_then(|index| self.start.get(index).copied())
+            .unwrap_or(self.frames.first().map_or(Pose2::IDENTITY, |frame| frame.ground_truth))
+    }
+
+    /*
+     * feeds the inputs to an EKF started from the "ekf" estimator's `start_pose`
+     * with the prior's uncertainty and an empty map. Each frame, after the
+     * prediction, `before_update` sees the filter and its frame index, and
+     * returning false ends the replay there; `after_update` sees it once the
+     * frame's observations are in
+     */
+    pub fn replay_ekf(
+        &self,
+        cfg: &Config,
+        mut before_update: impl FnMut(usize, &RecordedFrame, &FrameInputs, &mut EkfSlam) -> bool,
+        mut after_update: impl FnMut(usize, &RecordedFrame, &FrameInputs, &mut EkfSlam),
+    ) {
+        let mut ekf = EkfSlam::with_prior(self.start_pose("ekf"), cfg.initial_stdev_position, cfg.initial_stdev_heading);
+        for (index, (frame, inputs)) in self.frames.iter().zip(&self.inputs).enumerate() {
+            if let Some((linear, angular)) = inputs.odometry {
+                ekf.predict(linear, angular, inputs.delta_time, cfg);
+            }
+            if !before_update(index, frame, inputs, &mut ekf) { break; }
+            ekf.update(&inputs.observations, cfg);
+            after_update(index, frame, inputs, &mut ekf);
+        }
+    }
+}
+
+/*
+ * `<dir>/<prefix>_<unix seconds>.<extension>`
+ */
+pub fn timestamped_path(dir: &str, prefix: &str, extension: &str) -> PathBuf {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    Path::new(dir).join(format!("{prefix}_{seconds}.{extension}"))
+}
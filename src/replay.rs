@@ -0,0 +1,33 @@
+use crate::config::Config;
+use crate::recording::Recording;
+use crate::slam::SlamFilter;
+
+/*
+ * runs a recording's inputs through `filters` again, headlessly, so a change to
+ * the filter code or the settings can be judged on the exact same data. Each
+ * filter should start from its recorded counterpart's `start`. The result is a
+ * recording with the same ground truth, bookmarks, and inputs, and one
+ * estimator per filter under the given name, ready to compare against the original
+ */
+pub fn replay(recording: &Recording, filters: &mut [(&str, &mut dyn SlamFilter)], cfg: &Config) -> Result<Recording, String> {
+    if recording.inputs.is_empty() {
+        return Err("the recording has no filter inputs; record it again to replay it".to_string());
+    }
+
+    let names: Vec<&str> = filters.iter().map(|(name, _)| *name).collect();
+    let start = filters.iter().map(|(_, slam)| slam.estimated_pose()).collect();
+    let mut replayed = Recording::new(&names).starting_from(start);
+    replayed.bookmarks = recording.bookmarks.clone();
+    replayed.inputs = recording.inputs.clone();
+
+    for (frame, inputs) in recording.frames.iter().zip(&recording.inputs) {
+        for (_, slam) in filters.iter_mut() {
+            if let Some((linear, angular)) = inputs.odometry {
+                slam.predict(linear, angular, inputs.delta_time, cfg);
+            }
+            slam.update(&inputs.observations, cfg);
+        }
+        replayed.push(frame.time, frame.ground_truth, filters.iter().map(|(_, slam)| slam.estimated_pose()).collect());
+    }
+    Ok(replayed)
+}
@@ -0,0 +1,374 @@
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use macroquad::prelude::{Color, Rect};
+
+use crate::config::Config;
+use crate::metrics;
+use crate::scenario::{self, LandmarkEvent, RunReport, Scenario, TIME_STEP};
+use crate::simulation::{DropoutZone, Landmark};
+use crate::slam::SlamFilter;
+use crate::summary::SessionSummary;
+
+const PLOT_WIDTH: f32 = 640.0;
+const PLOT_HEIGHT: f32 = 200.0;
+const MAP_SIZE: f32 = 640.0;
+
+#[derive(Clone, Copy, Debug)]
+pub struct TraceSample {
+    pub time: f32,
+    pub truth: (f32, f32),
+    pub estimate: (f32, f32),
+    pub error: f32,
+    pub covariance_trace: f32, // of the position covariance
+}
+
+/*
+ * a filter's path through a run, kept every `interval` seconds so an hour of
+ * driving still makes a report of reasonable size
+ */
+pub struct TraceRecorder {
+    interval: f32,
+    next_time: f32,
+    pub samples: Vec<TraceSample>,
+}
+
+impl TraceRecorder {
+    pub fn new(interval: f32) -> Self {
+        Self { interval, next_time: 0.0, samples: Vec::new() }
+    }
+
+    pub fn record<S: SlamFilter + ?Sized>(&mut self, time: f32, truth: (f32, f32), slam: &S) {
+        // on a grid of `interval`, with a little slack so float time steps don't slip a frame
+        if time + 1e-3 < self.next_time { return; }
+        self.next_time = (self.next_time + self.interval).max(time);
+
+        self.samples.push(TraceSample {
+            time,
+            truth,
+            estimate: slam.estimated_pose().position(),
+            error: metrics::position_error(slam, truth),
+            covariance_trace: slam.pose_covariance().fixed_view::<2, 2>(0, 0).trace(),
+        });
+    }
+}
+
+/*
+ * one filter's part of a report: its sampled path, final map, and headline numbers
+ */
+pub struct FilterRun {
+    pub name: String,
+    pub color: Color,
+    pub samples: Vec<TraceSample>,
+    pub landmarks: Vec<(usize, f32, f32)>,
+    pub metrics: Vec<(&'static str, String)>,
+}
+
+impl FilterRun {
+    pub fn new<S: SlamFilter + ?Sized>(name: &str, slam: &S, samples: Vec<TraceSample>, metrics: Vec<(&'static str, String)>) -> Self {
+        Self { name: name.to_string(), color: slam.color(), samples, landmarks: slam.estimated_map(), metrics }
+    }
+}
+
+pub fn run_report_metrics(report: &RunReport) -> Vec<(&'static str, String)> {
+    vec![
+        ("ATE (position RMSE)", format!("{:.2}", report.ate)),
+        ("Dead reckoning ATE", format!("{:.2}", report.odometry_ate)),
+        ("Final position error", format!("{:.2}", report.final_error)),
+        ("Average pose NEES", format!("{:.2}", report.mean_nees)),
+        ("Landmarks initialized", format!("{} of {}", report.landmarks_initialized, report.landmarks_total)),
+        ("Map RMSE", format!("{:.2} ({} unmatched, {} spurious)", report.map.rmse, report.map.unmatched, report.map.spurious)),
+        ("Frames", report.frames.to_string()),
+        ("Converged at", report.convergence_time.map_or("never".to_string(), |time| format!("{time:.1} s"))),
+    ]
+}
+
+pub fn summary_metrics(summary: &SessionSummary) -> Vec<(&'static str, String)> {
+    vec![
+        ("Duration", format!("{:.1} s", summary.duration)),
+        ("Distance traveled", format!("{:.0}", summary.distance_traveled)),
+        ("ATE (position RMSE)", format!("{:.2}", summary.ate)),
+        ("Dead reckoning ATE", format!("{:.2}", summary.odometry_ate)),
+        ("Final position error", format!("{:.2}", summary.final_error)),
+        ("Average pose NEES", format!("{:.2}", summary.mean_nees)),
+        ("Landmarks mapped", format!("{} of {}", summary.landmarks_mapped, summary.landmarks_total)),
+        (
+            "Map RMSE",
+            format!("{:.2} ({} unmatched, {} spurious)", summary.map_rmse, summary.unmatched_landmarks, summary.spurious_landmarks),
+        ),
+        ("Loop closures", summary.loop_closures.to_string()),
+        ("Peak position covariance trace", format!("{:.1}", summary.peak_covariance_trace)),
+    ]
+}
+
+/*
+ * one world and every filter that was run through it
+ */
+pub struct ReportSection {
+    pub title: String,
+    pub landmarks: Vec<(f32, f32)>, // the true ones
+    pub obstructions: Vec<Rect>,
+    pub dropout_zones: Vec<DropoutZone>,
+    pub filters: Vec<FilterRun>,
+}
+
+impl ReportSection {
+    // the world alone, for the filters to be added to
+    pub fn new(title: &str, landmarks: &[Landmark], obstructions: Vec<Rect>, dropout_zones: Vec<DropoutZone>) -> Self {
+        Self {
+            title: title.to_string(),
+            landmarks: landmarks.iter().map(|landmark| (landmark.x, landmark.y)).collect(),
+            obstructions,
+            dropout_zones,
+            filters: Vec::new(),
+        }
+    }
+
+    /*
+     * runs each filter through the scenario from the same seed, the way
+     * `scenario::run` does, tracing its path as it goes
+     */
+    pub fn from_scenario(scenario: &Scenario, filters: Vec<(&str, Box<dyn SlamFilter>)>, seed: u64, cfg: &Config) -> Self {
+        // every landmark that was in the world at some point
+        let appearing = scenario.landmark_events.iter().filter_map(|event| match event {
+            LandmarkEvent::Appear { landmark, .. } => Some(landmark.clone()),
+            _ => None,
+        });
+        let landmarks: Vec<Landmark> = scenario.landmarks.iter().cloned().chain(appearing).collect();
+        let title = format!("{} (seed {seed})", scenario.name);
+        let mut section = Self::new(&title, &landmarks, scenario.world_obstructions(cfg), scenario.dropout_zones.clone());
+
+        section.filters = filters.into_iter()
+            .map(|(name, mut slam)| {
+                let mut recorder = TraceRecorder::new(cfg.report_sample_interval);
+                let mut frames = 0;
+                let report = scenario::run_observed(scenario, slam.as_mut(), seed, cfg, |slam, robot| {
+                    frames += 1;
+                    recorder.record(frames as f32 * TIME_STEP, (robot.x, robot.y), slam);
+                    Ok(())
+                }).expect("the recorder never stops a run");
+                FilterRun::new(name, slam.as_ref(), recorder.samples, run_report_metrics(&report))
+            })
+            .collect();
+        section
+    }
+}
+
+/*
+ * the EKF's and dead reckoning's paths through an interactive session
+ */
+pub struct SessionTraces {
+    pub ekf: TraceRecorder,
+    pub odometry: TraceRecorder,
+}
+
+impl SessionTraces {
+    pub fn new(cfg: &Config) -> Self {
+        Self { ekf: TraceRecorder::new(cfg.report_sample_interval), odometry: TraceRecorder::new(cfg.report_sample_interval) }
+    }
+
+    pub fn record(&mut self, time: f32, truth: (f32, f32), ekf: &dyn SlamFilter, odometry: &dyn SlamFilter) {
+        self.ekf.record(time, truth, ekf);
+        self.odometry.record(time, truth, odometry);
+    }
+
+    // the summary is the EKF's; dead reckoning only gets its ATE
+    pub fn filter_runs(&self, summary: &SessionSummary, ekf: &dyn SlamFilter, odometry: &dyn SlamFilter) -> Vec<FilterRun> {
+        vec![
+            FilterRun::new("EKF-SLAM", ekf, self.ekf.samples.clone(), summary_metrics(summary)),
+            FilterRun::new("Dead reckoning", odometry, self.odometry.samples.clone(), vec![("ATE (position RMSE)", format!("{:.2}", summary.odometry_ate))]),
+        ]
+    }
+}
+
+/*
+ * a completed run or batch as a single HTML file: the plots and map are inline
+ * SVG and the styling is inline CSS, so it opens anywhere without the simulator
+ * or a network connection
+ */
+pub struct Report {
+    pub title: String,
+    pub sections: Vec<ReportSection>,
+    pub config: String, // the `Config` the runs used, as written by its Debug impl
+}
+
+impl Report {
+    pub fn new(title: &str, sections: Vec<ReportSection>, cfg: &Config) -> Self {
+        Self { title: title.to_string(), sections, config: format!("{cfg:#?}") }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_html())
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n",
+            title = escape(&self.title),
+        );
+
+        for section in &self.sections {
+            let _ = writeln!(html, "<section>\n<h2>{}</h2>", escape(&section.title));
+            html.push_str(&metrics_table(&section.filters));
+            html.push_str(&legend(&section.filters));
+            html.push_str(&line_plot("Position error", &section.filters, |sample| sample.error));
+            html.push_str(&line_plot("Position covariance trace", &section.filters, |sample| sample.covariance_trace));
+            html.push_str(&map_snapshot(section));
+            html.push_str("</section>\n");
+        }
+
+        let _ = write!(html, "<h2>Configuration</h2>\n<pre>{}</pre>\n</body>\n</html>\n", escape(&self.config));
+        html
+    }
+}
+
+const STYLE: &str = "body{font-family:sans-serif;background:#1a1a1a;color:#ddd;margin:2em}\
+table{border-collapse:collapse;margin:1em 0}td,th{border:1px solid #444;padding:4px 10px;text-align:right}\
+th:first-child,td:first-child{text-align:left}svg{background:#111;margin:0.5em 0;display:block}\
+.legend span{margin-right:1.5em}pre{background:#111;padding:1em;font-size:12px}";
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn css(color: Color) -> String {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!("rgb({},{},{})", channel(color.r), channel(color.g), channel(color.b))
+}
+
+/*
+ * metric names down the side, one column per filter; a filter without a
+ * metric gets a blank cell
+ */
+fn metrics_table(filters: &[FilterRun]) -> String {
+    let mut names: Vec<&str> = Vec::new();
+    for (name, _) in filters.iter().flat_map(|filter| &filter.metrics) {
+        if !names.contains(name) { names.push(name); }
+    }
+
+    let mut table = String::from("<table>\n<tr><th></th>");
+    for filter in filters {
+        let _ = write!(table, "<th style=\"color:{}\">{}</th>", css(filter.color), escape(&filter.name));
+    }
+    table.push_str("</tr>\n");
+    for name in names {
+        let _ = write!(table, "<tr><td>{}</td>", escape(name));
+        for filter in filters {
+            let value = filter.metrics.iter().find(|(metric, _)| *metric == name).map_or("", |(_, value)| value.as_str());
+            let _ = write!(table, "<td>{}</td>", escape(value));
+        }
+        table.push_str("</tr>\n");
+    }
+    table.push_str("</table>\n");
+    table
+}
+
+fn legend(filters: &[FilterRun]) -> String {
+    let mut legend = String::from("<p class=\"legend\"><span style=\"color:#fff\">&#9632; truth</span>");
+    for filter in filters {
+        let _ = write!(legend, "<span style=\"color:{}\">&#9632; {}</span>", css(filter.color), escape(&filter.name));
+    }
+    legend.push_str("</p>\n");
+    legend
+}
+
+/*
+ * every filter's `value` over time on shared axes, clipped at the largest
+ * finite value
+ */
+fn line_plot(title: &str, filters: &[FilterRun], value: impl Fn(&TraceSample) -> f32) -> String {
+    let samples = || filters.iter().flat_map(|filter| filter.samples.iter());
+    let max_time = samples().map(|sample| sample.time).fold(1e-3f32, f32::max);
+    let max_value = samples().map(&value).filter(|value| value.is_finite()).fold(1e-3f32, f32::max);
+
+    let mut svg = format!(
+        "<h3>{}</h3>\n<svg width=\"{PLOT_WIDTH}\" height=\"{PLOT_HEIGHT}\" viewBox=\"0 0 {PLOT_WIDTH} {PLOT_HEIGHT}\">\n",
+        escape(title)
+    );
+    let _ = writeln!(svg, "<text x=\"4\" y=\"14\" fill=\"#888\" font-size=\"12\">{max_value:.2}</text>");
+    let _ = writeln!(
+        svg,
+        "<text x=\"{}\" y=\"{}\" fill=\"#888\" font-size=\"12\" text-anchor=\"end\">{max_time:.1} s</text>",
+        PLOT_WIDTH - 4.0,
+        PLOT_HEIGHT - 4.0
+    );
+
+    for filter in filters {
+        let points: Vec<String> = filter.samples.iter()
+            .map(|sample| {
+                let x = sample.time / max_time * PLOT_WIDTH;
+                let y = PLOT_HEIGHT - value(sample).clamp(0.0, max_value) / max_value * PLOT_HEIGHT;
+                format!("{x:.1},{y:.1}")
+            })
+            .collect();
+        let _ = writeln!(
+            svg,
+            "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\" points=\"{}\"/>",
+            css(filter.color),
+            points.join(" ")
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/*
+ * the world from above at the end of the run: obstructions, dropout zones,
+ * true landmarks (white) with each filter's estimates, the true path of the
+ * first filter's run and every filter's estimated path. World y points up, so
+ * the drawing is flipped to match the simulator's view
+ */
+fn map_snapshot(section: &ReportSection) -> String {
+    let points = section.landmarks.iter().copied()
+        .chain(section.filters.iter().flat_map(|filter| filter.samples.iter().flat_map(|sample| [sample.truth, sample.estimate])))
+        .chain(section.filters.iter().flat_map(|filter| filter.landmarks.iter().map(|&(_, x, y)| (x, y))))
+        .chain(section.obstructions.iter().flat_map(|rect| [(rect.x, rect.y), (rect.x + rect.w, rect.y + rect.h)]))
+        .filter(|(x, y)| x.is_finite() && y.is_finite());
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (f32::INFINITY, f32::INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
+    for (x, y) in points {
+        (min_x, min_y, max_x, max_y) = (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y));
+    }
+    if !min_x.is_finite() { return String::new(); }
+
+    let margin = 20.0;
+    let extent = (max_x - min_x).max(max_y - min_y).max(1.0) + 2.0 * margin;
+    let dot = extent / 150.0;
+
+    let mut svg = format!(
+        "<h3>Map</h3>\n<svg width=\"{MAP_SIZE}\" height=\"{MAP_SIZE}\" viewBox=\"{} {} {extent} {extent}\">\n<g transform=\"scale(1,-1)\">\n",
+        min_x - margin,
+        -(min_y - margin + extent),
+    );
+    for rect in &section.obstructions {
+        let _ = writeln!(svg, "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#666\"/>", rect.x, rect.y, rect.w, rect.h);
+    }
+    for zone in &section.dropout_zones {
+        let _ = writeln!(svg, "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"#b0bcd8\" fill-opacity=\"0.12\"/>", zone.x, zone.y, zone.radius);
+    }
+
+    let path = |points: &mut dyn Iterator<Item = (f32, f32)>| points.map(|(x, y)| format!("{x:.1},{y:.1}")).collect::<Vec<_>>().join(" ");
+    if let Some(first) = section.filters.first() {
+        let truth = path(&mut first.samples.iter().map(|sample| sample.truth));
+        let _ = writeln!(svg, "<polyline fill=\"none\" stroke=\"#fff\" stroke-width=\"{}\" points=\"{truth}\"/>", dot / 2.0);
+    }
+    for filter in &section.filters {
+        let estimate = path(&mut filter.samples.iter().map(|sample| sample.estimate).filter(|(x, y)| x.is_finite() && y.is_finite()));
+        let color = css(filter.color);
+        let _ = writeln!(svg, "<polyline fill=\"none\" stroke=\"{color}\" stroke-width=\"{}\" points=\"{estimate}\"/>", dot / 2.0);
+        for &(_, x, y) in filter.landmarks.iter().filter(|(_, x, y)| x.is_finite() && y.is_finite()) {
+            let _ = writeln!(svg, "<circle cx=\"{x}\" cy=\"{y}\" r=\"{}\" fill=\"{color}\" fill-opacity=\"0.7\"/>", dot * 0.8);
+        }
+    }
+    for &(x, y) in &section.landmarks {
+        let _ = writeln!(svg, "<circle cx=\"{x}\" cy=\"{y}\" r=\"{dot}\" fill=\"none\" stroke=\"#fff\" stroke-width=\"{}\"/>", dot / 3.0);
+    }
+    svg.push_str("</g>\n</svg>\n");
+    svg
+}
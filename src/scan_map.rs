@@ -0,0 +1,50 @@
+use std::collections::VecDeque;
+use macroquad::prelude::Rect;
+
+use crate::config::Config;
+use crate::math::Pose2;
+use crate::simulation::Scan;
+
+/*
+ * the world as the robot believes it to be: lidar hits placed from the pose
+ * estimate the robot had when it took each scan. Where the estimate drifted
+ * the walls come out bent, doubled, or smeared
+ */
+pub struct ScanMap {
+    pub points: VecDeque<(f32, f32)>,
+    last_pose: Option<Pose2>,
+    capacity: usize,
+}
+
+impl ScanMap {
+    pub fn new(capacity: usize) -> Self {
+        Self { points: VecDeque::with_capacity(capacity), last_pose: None, capacity }
+    }
+
+    /*
+     * adds the scan's hits seen from `estimate`, unless the estimate has moved
+     * less than `scan_map_spacing` and turned less than `scan_map_turn` since
+     * the last scan kept; the oldest hits go first once full
+     */
+    pub fn record(&mut self, scan: &Scan, estimate: Pose2, cfg: &Config) {
+        if let Some(last) = self.last_pose {
+            let moved = last.between(&estimate);
+            if moved.x.hypot(moved.y) < cfg.scan_map_spacing && moved.theta.abs() < cfg.scan_map_turn { return; }
+        }
+        self.last_pose = Some(estimate);
+
+        for hit in scan.hit_points() {
+            if self.points.len() == self.capacity { self.points.pop_front(); }
+            self.points.push_back(estimate.transform_point(hit));
+        }
+    }
+}
+
+/*
+ * the corners of `rect` moved by the pose error, i.e. where a wall seen from
+ * the true pose appears when the robot takes itself to be at `estimate`
+ */
+pub fn believed_corners(rect: Rect, truth: Pose2, estimate: Pose2) -> [(f32, f32); 4] {
+    [(rect.x, rect.y), (rect.x + rect.w, rect.y), (rect.x + rect.w, rect.y + rect.h), (rect.x, rect.y + rect.h)]
+        .map(|corner| estimate.transform_point(truth.inverse_transform_point(corner)))
+}
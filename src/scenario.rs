@@ -0,0 +1,629 @@
+use std::collections::VecDeque;
+
+use macroquad::prelude::{Rect, rand};
+use nalgebra::Matrix3;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::math::Pose2;
+use crate::exploration::CoverageMap;
+use crate::fleet::{Fleet, RobotSpec};
+use crate::metrics;
+use crate::simulation::{self, DropoutZone, Landmark, Robot, World};
+use crate::slam::{OdometryBaseline, SlamFilter};
+use crate::snapshot;
+
+// fixed step used by headless runs so results don't depend on frame timing
+pub const TIME_STEP: f32 = 1.0 / 60.0;
+
+/*
+ * commanded velocities held for `duration` seconds
+ */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ControlSegment {
+    pub duration: f32,
+    pub linear: f32,
+    pub angular: f32,
+}
+
+/*
+ * scripted change to the world at `time` seconds into the run
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub enum LandmarkEvent {
+    Appear { time: f32, landmark: Landmark },
+    Remove { time: f32, id: usize },
+}
+
+impl LandmarkEvent {
+    pub fn time(&self) -> f32 {
+        match self {
+            LandmarkEvent::Appear { time, .. } | LandmarkEvent::Remove { time, .. } => *time,
+        }
+    }
+
+    pub fn apply(&self, landmarks: &mut Vec<Landmark>) {
+        match self {
+            LandmarkEvent::Appear { landmark, .. } => landmarks.push(landmark.clone()),
+            LandmarkEvent::Remove { id, .. } => landmarks.retain(|landmark| landmark.id != *id),
+        }
+    }
+}
+
+/*
+ * a point in a run where the runner snapshots the filter and logs the metrics
+ * so far: at a time, or the first time the robot comes into `radius` of a
+ * waypoint from outside it
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub enum Checkpoint {
+    Time(f32),
+    Waypoint { x: f32, y: f32, radius: f32 },
+}
+
+/*
+ * what a run has to achieve for the scenario to pass; unset criteria aren't checked
+ */
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Criteria {
+    pub max_ate: Option<f32>,
+    pub max_mean_nees: Option<f32>,
+    pub min_landmarks: Option<usize>, // mapped by the end, out of every landmark ever present
+}
+
+impl Criteria {
+    /*
+     * a line per criterion the run missed; a NaN metric misses its bound
+     */
+    pub fn failures(&self, report: &RunReport) -> Vec<String> {
+        let mut failures = Vec::new();
+        if let Some(max) = self.max_ate && (report.ate > max || report.ate.is_nan()) {
+            failures.push(format!("ATE {:.2} above {max:.2}", report.ate));
+        }
+        if let Some(max) = self.max_mean_nees && (report.mean_nees > max || report.mean_nees.is_nan()) {
+            failures.push(format!("mean NEES {:.2} above {max:.2}", report.mean_nees));
+        }
+        if let Some(min) = self.min_landmarks && report.landmarks_initialized < min {
+            failures.push(format!("{} of {} landmarks mapped, {min} required", report.landmarks_initialized, report.landmarks_total));
+        }
+        failures
+    }
+}
+
+/*
+ * what the filter believed at a checkpoint, in terms every backend can report
+ */
+#[derive(Clone, Debug, Serialize)]
+pub struct FilterSnapshot {
+    pub pose: Pose2,
+    pub pose_covariance: Matrix3<f32>,
+    pub landmarks: Vec<(usize, f32, f32)>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CheckpointReport {
+    pub checkpoint: usize, // index into the scenario's checkpoints
+    pub time: f32,
+    pub frame: usize, // frames run so far
+    pub ate: f32, // over the run so far
+    pub error: f32,
+    pub mean_nees: f32, // over the run so far
+    pub landmarks_initialized: usize,
+    pub snapshot: FilterSnapshot,
+}
+
+/*
+ * a canned world plus a scripted drive through it
+ */
+#[derive(Clone)]
+pub struct Scenario {
+    pub name: String,
+    pub landmarks: Vec<Landmark>,
+    pub obstructions: Vec<Rect>,
+    pub boundary: Option<Rect>, // walled-in area of the world, unbounded if None
+    pub dropout_zones: Vec<DropoutZone>,
+    pub controls: Vec<ControlSegment>,
+    pub landmark_events: Vec<LandmarkEvent>, // in time order
+    pub checkpoints: Vec<Checkpoint>,
+    pub criteria: Criteria, // checked at the end of headless runs
+    pub robots: Vec<RobotSpec>, // besides the one the filters estimate, which the controls drive
+}
+
+/*
+ * a scenario as stored on disk, with the rects as tuples; everything but the
+ * name can be left out
+ */
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScenarioFile {
+    pub name: String,
+    pub landmarks: Vec<Landmark>,
+    pub obstructions: Vec<(f32, f32, f32, f32)>, // x, y, w, h
+    pub boundary: Option<(f32, f32, f32, f32)>,
+    pub dropout_zones: Vec<DropoutZone>,
+    pub controls: Vec<ControlSegment>,
+    pub landmark_events: Vec<LandmarkEvent>,
+    pub checkpoints: Vec<Checkpoint>,
+    pub criteria: Criteria,
+    pub robots: Vec<RobotSpec>,
+}
+
+impl From<&Scenario> for ScenarioFile {
+    fn from(scenario: &Scenario) -> Self {
+        let tuple = |rect: &Rect| (rect.x, rect.y, rect.w, rect.h);
+        Self {
+            name: scenario.name.clone(),
+            landmarks: scenario.landmarks.clone(),
+            obstructions: scenario.obstructions.iter().map(tuple).collect(),
+            boundary: scenario.boundary.as_ref().map(tuple),
+            dropout_zones: scenario.dropout_zones.clone(),
+            controls: scenario.controls.clone(),
+            landmark_events: scenario.landmark_events.clone(),
+            checkpoints: scenario.checkpoints.clone(),
+            criteria: scenario.criteria.clone(),
+            robots: scenario.robots.clone(),
+        }
+    }
+}
+
+impl From<ScenarioFile> for Scenario {
+    fn from(file: ScenarioFile) -> Self {
+        let rect = |(x, y, w, h): (f32, f32, f32, f32)| Rect::new(x, y, w, h);
+        let mut landmark_events = file.landmark_events;
+        landmark_events.sort_by(|a, b| a.time().total_cmp(&b.time()));
+        Self {
+            name: file.name,
+            landmarks: file.landmarks,
+            obstructions: file.obstructions.into_iter().map(rect).collect(),
+            boundary: file.boundary.map(rect),
+            dropout_zones: file.dropout_zones,
+            controls: file.controls,
+            landmark_events,
+            checkpoints: file.checkpoints,
+            criteria: file.criteria,
+            robots: file.robots,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct RunReport {
+    pub ate: f32, // absolute trajectory error (RMSE of position error)
+    pub odometry_ate: f32, // of dead reckoning from the filter's start, the baseline `ate` should beat
+    pub final_error: f32,
+    pub mean_nees: f32,
+    pub landmarks_initialized: usize,
+    pub landmarks_total: usize,
+    pub frames: usize,
+    pub infeasible_frames: usize, // ground truth frames the dynamics limits had to correct
+    pub checkpoints: Vec<CheckpointReport>, // in the order they were reached
+    pub map: metrics::MapQuality, // against the landmarks present at the end, ignoring ids
+    pub convergence_time: Option<f32>, // when coverage, mapping, and the ATE plateaued, if they did
+}
+
+impl Scenario {
+    /*
+     * four straights joined by left turns, with landmarks scattered along
+     * the inside and outside of the loop
+     */
+    pub fn square_loop() -> Self {
+        let mut landmarks = Vec::new();
+        for (i, (x, y)) in [
+            (50.0, -60.0), (150.0, 60.0), (250.0, -40.0), (-40.0, 80.0),
+            (220.0, 180.0), (100.0, 120.0), (-60.0, 220.0), (60.0, 300.0),
+            (200.0, 320.0), (-80.0, -20.0), (300.0, 100.0), (120.0, 240.0),
+        ].into_iter().enumerate() {
+            landmarks.push(Landmark::new(i, x, y));
+        }
+
+        let mut controls = Vec::new();
+        for _ in 0..4 {
+            controls.push(ControlSegment { duration: 5.0, linear: 40.0, angular: 0.0 });
+            controls.push(ControlSegment { duration: 1.8, linear: 10.0, angular: 1.0 });
+        }
+
+        Self {
+            name: "square loop".to_string(),
+            landmarks,
+            obstructions: Vec::new(),
+            boundary: Some(Rect::new(-150.0, -150.0, 550.0, 550.0)),
+            dropout_zones: Vec::new(),
+            controls,
+            landmark_events: Vec::new(),
+            checkpoints: Vec::new(),
+            criteria: Criteria { max_ate: Some(25.0), max_mean_nees: Some(30.0), min_landmarks: Some(12) },
+            robots: Vec::new(),
+        }
+    }
+
+    /*
+     * long straight between two rows of evenly spaced landmarks
+     */
+    pub fn corridor() -> Self {
+        let landmarks = (0..20)
+            .map(|i| Landmark::new(i, 60.0 * (i / 2) as f32, if i % 2 == 0 { -70.0 } else { 70.0 }))
+            .collect();
+
+        Self {
+            name: "corridor".to_string(),
+            landmarks,
+            obstructions: Vec::new(),
+            boundary: Some(Rect::new(-100.0, -150.0, 800.0, 300.0)),
+            dropout_zones: Vec::new(),
+            controls: vec![ControlSegment { duration: 14.0, linear: 40.0, angular: 0.0 }],
+            landmark_events: Vec::new(),
+            checkpoints: Vec::new(),
+            criteria: Criteria { max_ate: Some(25.0), max_mean_nees: Some(15.0), min_landmarks: Some(20) },
+            robots: Vec::new(),
+        }
+    }
+
+    /*
+     * the square loop, but two landmarks near the start are taken away during
+     * the first lap and two new ones appear on the second
+     */
+    pub fn changing_loop() -> Self {
+        let mut scenario = Self::square_loop();
+        scenario.name = "changing loop".to_string();
+        scenario.controls.extend(scenario.controls.clone());
+        scenario.landmark_events = vec![
+            LandmarkEvent::Remove { time: 8.0, id: 0 },
+            LandmarkEvent::Remove { time: 8.0, id: 9 },
+            LandmarkEvent::Appear { time: 20.0, landmark: Landmark::new(12, 0.0, 40.0) },
+            LandmarkEvent::Appear { time: 20.0, landmark: Landmark::new(13, 180.0, -60.0) },
+        ];
+        // the removed landmarks leave the EKF overconfident, so only the error and the map are held to
+        scenario.criteria = Criteria { max_ate: Some(30.0), max_mean_nees: None, min_landmarks: Some(14) };
+        scenario
+    }
+
+    /*
+     * five laps of the square loop; long enough for linearization errors to
+     * pile up into an overconfident world-centric EKF. Checkpoints at the end
+     * of every lap
+     */
+    pub fn long_loop() -> Self {
+        let mut scenario = Self::square_loop();
+        scenario.name = "long loop".to_string();
+        let lap = scenario.duration();
+        scenario.controls = scenario.controls.iter().cycle().take(5 * scenario.controls.len()).cloned().collect();
+        scenario.checkpoints = (1..=5).map(|laps| Checkpoint::Time(laps as f32 * lap)).collect();
+        // it's there to show the overconfidence, so NEES isn't held to
+        scenario.criteria = Criteria { max_ate: Some(30.0), max_mean_nees: None, min_landmarks: Some(12) };
+        scenario
+    }
+
+    /*
+     * two laps of the square loop with a fog bank over its far side, where
+     * nothing is seen; the drift grows through it and is pulled back on leaving
+     */
+    pub fn fog_loop() -> Self {
+        let mut scenario = Self::square_loop();
+        scenario.name = "fog loop".to_string();
+        scenario.controls.extend(scenario.controls.clone());
+        scenario.dropout_zones = (0..5)
+            .map(|i| DropoutZone { x: 50.0 * i as f32, y: 220.0, radius: 60.0 })
+            .collect();
+        // a landmark deep in the fog may never be seen
+        scenario.criteria = Criteria { max_ate: Some(30.0), max_mean_nees: Some(40.0), min_landmarks: Some(11) };
+        scenario
+    }
+
+    /*
+     * three laps of the square loop while every landmark creeps along in its
+     * own direction, for `landmark_aging_rate`: a filter that never forgets
+     * holds on to where the map was
+     */
+    pub fn drifting_loop() -> Self {
+        let mut scenario = Self::square_loop();
+        scenario.name = "drifting loop".to_string();
+        scenario.controls = scenario.controls.iter().cycle().take(3 * scenario.controls.len()).cloned().collect();
+        for landmark in &mut scenario.landmarks {
+            let heading = 2.4 * landmark.id as f32;
+            landmark.velocity = (0.75 * heading.cos(), 0.75 * heading.sin());
+        }
+        scenario.criteria = Criteria { max_ate: Some(40.0), max_mean_nees: None, min_landmarks: Some(12) };
+        scenario
+    }
+
+    pub fn all() -> Vec<Self> {
+        vec![Self::square_loop(), Self::corridor(), Self::changing_loop(), Self::long_loop(), Self::fog_loop(), Self::drifting_loop()]
+    }
+
+    /*
+     * canned scenario by name, spaces written as underscores or dashes
+     */
+    pub fn by_name(name: &str) -> Option<Self> {
+        Self::all()
+            .into_iter()
+            .find(|scenario| scenario.name == name.replace(['_', '-'], " "))
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        snapshot::load::<ScenarioFile>(path).map(Self::from)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        snapshot::save(&ScenarioFile::from(self), path)
+    }
+
+    /*
+     * a canned scenario by name, or a scenario file if the argument ends in .json
+     */
+    pub fn resolve(name: &str) -> Result<Self, String> {
+        if name.ends_with(".json") {
+            return Self::load(Path::new(name)).map_err(|error| format!("could not load scenario {name}: {error}"));
+        }
+        Self::by_name(name).ok_or_else(|| format!("unknown scenario {name}"))
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.controls.iter().map(|segment| segment.duration).sum()
+    }
+
+    /*
+     * the obstructions plus the boundary walls, everything the robot and its sensors run into
+     */
+    pub fn world_obstructions(&self, cfg: &Config) -> Vec<Rect> {
+        self.world(cfg).solid().copied().collect()
+    }
+
+    /*
+     * the scenario's world as it starts, before any landmark events, with the robot at the origin
+     */
+    pub fn world(&self, cfg: &Config) -> World {
+        World {
+            robot: Robot::new(),
+            landmarks: self.landmarks.clone(),
+            obstructions: self.obstructions.clone(),
+            walls: self.boundary.map(|area| simulation::boundary_walls(area, cfg.boundary_thickness)).unwrap_or_default(),
+            dropout_zones: self.dropout_zones.clone(),
+        }
+    }
+}
+
+/*
+ * steps through a scenario's controls at the fixed time step. Landmark events
+ * are keyed on simulated time, not wall time, so while the caller doesn't step
+ * (a paused window) they stay queued, and each is applied on the first step at
+ * or after its time, the same step a headless run applies it on
+ */
+pub struct Playback {
+    pub scenario: Scenario,
+    segment: usize,
+    segment_step: usize,
+    frame: usize,
+    next_event: usize,
+}
+
+impl Playback {
+    pub fn new(scenario: Scenario) -> Self {
+        Self { scenario, segment: 0, segment_step: 0, frame: 0, next_event: 0 }
+    }
+
+    pub fn time(&self) -> f32 {
+        self.frame as f32 * TIME_STEP
+    }
+
+    /*
+     * events whose time has not come yet
+     */
+    pub fn pending_events(&self) -> &[LandmarkEvent] {
+        &self.scenario.landmark_events[self.next_event..]
+    }
+
+    /*
+     * applies the events due by now to `landmarks`, drifts the ones with a
+     * velocity by a step, and returns the control for this step, or None once
+     * the controls run out
+     */
+    pub fn step(&mut self, landmarks: &mut Vec<Landmark>) -> Option<ControlSegment> {
+        // segments shorter than half a step are skipped
+        let segment = loop {
+            let segment = self.scenario.controls.get(self.segment)?;
+            if self.segment_step < (segment.duration / TIME_STEP).round() as usize { break segment.clone(); }
+            self.segment += 1;
+            self.segment_step = 0;
+        };
+
+        let time = self.time();
+        while let Some(event) = self.scenario.landmark_events.get(self.next_event).filter(|event| event.time() <= time) {
+            event.apply(landmarks);
+            self.next_event += 1;
+        }
+        for landmark in landmarks.iter_mut() {
+            landmark.x += landmark.velocity.0 * TIME_STEP;
+            landmark.y += landmark.velocity.1 * TIME_STEP;
+        }
+
+        self.segment_step += 1;
+        self.frame += 1;
+        Some(segment)
+    }
+}
+
+/*
+ * plateau detector for headless runs: converged once a whole window of frames
+ * has neither covered a new cell nor mapped a new landmark, and the ATE so far
+ * moved by at most `tolerance` of itself across it
+ */
+pub struct Convergence {
+    window: usize, // in frames
+    tolerance: f32,
+    history: VecDeque<(usize, usize, f32)>, // covered cells, mapped landmarks, ATE
+}
+
+impl Convergence {
+    /*
+     * a window of `window` seconds of fixed steps; 0 never converges
+     */
+    pub fn new(window: f32, tolerance: f32) -> Self {
+        Self { window: (window / TIME_STEP).round() as usize, tolerance, history: VecDeque::new() }
+    }
+
+    /*
+     * adds a frame's metrics and tells whether the run has converged by it
+     */
+    pub fn step(&mut self, covered_cells: usize, landmarks: usize, ate: f32) -> bool {
+        if self.window == 0 { return false; }
+        self.history.push_back((covered_cells, landmarks, ate));
+        if self.history.len() <= self.window { return false; }
+        self.history.pop_front();
+
+        let (first_cells, first_landmarks, first_ate) = self.history[0];
+        covered_cells == first_cells && landmarks == first_landmarks && (ate - first_ate).abs() <= self.tolerance * ate
+    }
+}
+
+fn true_positions(landmarks: &[Landmark]) -> Vec<(usize, f32, f32)> {
+    landmarks.iter().map(|landmark| (landmark.id, landmark.x, landmark.y)).collect()
+}
+
+/*
+ * seeds the stream filters draw their prior samples from as they are made, like
+ * FastSLAM's particles, for a run with `seed`: a stream of its own, so the prior
+ * doesn't reuse the run's first noise samples, and every command that makes a
+ * filter for the same seed starts it from the same samples
+ */
+pub fn seed_prior(seed: u64) {
+    rand::srand(!seed);
+}
+
+/*
+ * drives the scenario with a fixed seed and time step, feeding the filter
+ * exactly what the interactive loop would, and scores it against ground truth
+ */
+pub fn run(scenario: &Scenario, slam: &mut dyn SlamFilter, seed: u64, cfg: &Config) -> RunReport {
+    match run_observed(scenario, slam, seed, cfg, |_, _| Ok(())) {
+        Ok(report) => report,
+        Err(_) => unreachable!("the no-op hook never fails"),
+    }
+}
+
+/*
+ * same as `run`, but calls `on_step` with the filter and the true robot after
+ * every update and stops at the first error it returns
+ */
+pub fn run_observed<S: SlamFilter + ?Sized>(
+    scenario: &Scenario,
+    slam: &mut S,
+    seed: u64,
+    cfg: &Config,
+    mut on_step: impl FnMut(&S, &Robot) -> Result<(), String>,
+) -> Result<RunReport, String> {
+    rand::srand(seed);
+
+    let mut squared_error_sum = 0.0;
+    let mut odometry_squared_error_sum = 0.0;
+    let mut nees_sum = 0.0;
+    let mut nees_count = 0;
+    let mut frames = 0;
+    let mut final_error = 0.0;
+
+    let mut world = scenario.world(cfg);
+
+    // every landmark that was in the world at some point
+    let mut ever_present: Vec<usize> = scenario.landmarks.iter().map(|landmark| landmark.id).collect();
+    for event in &scenario.landmark_events {
+        if let LandmarkEvent::Appear { landmark, .. } = event { ever_present.push(landmark.id); }
+    }
+    ever_present.sort();
+    ever_present.dedup();
+    let landmarks_initialized = |slam: &S| {
+        let mapped = slam.estimated_map();
+        ever_present.iter().filter(|&&id| mapped.iter().any(|(mapped_id, _, _)| *mapped_id == id)).count()
+    };
+
+    let mut checkpoints = Vec::new();
+    let mut reached = vec![false; scenario.checkpoints.len()];
+    let mut been_outside = vec![false; scenario.checkpoints.len()];
+
+    let mut odometry = OdometryBaseline::from_slam(slam);
+    let mut coverage = CoverageMap::new(cfg.coverage_cell_size);
+    let mut convergence = Convergence::new(cfg.convergence_window, cfg.convergence_tolerance);
+    let mut convergence_time = None;
+
+    // the other robots move as in the window, untouched by the keyboard
+    let mut fleet = Fleet::new(&scenario.robots);
+
+    let mut playback = Playback::new(scenario.clone());
+    while let Some(segment) = playback.step(&mut world.landmarks) {
+        world.robot.command_velocity(segment.linear, segment.angular, cfg, TIME_STEP);
+        world.step_robot(TIME_STEP, cfg);
+        fleet.step(TIME_STEP, cfg, &world, |_| false);
+        let robot = &world.robot;
+
+        slam.predict(robot.linear_velocity, robot.angular_velocity, TIME_STEP, cfg);
+        odometry.predict(robot.linear_velocity, robot.angular_velocity, TIME_STEP, cfg);
+        let observations = world.sense(robot, cfg);
+        slam.update(&observations, cfg);
+        on_step(slam, robot).map_err(|error| format!("frame {frames}: {error}"))?;
+
+        final_error = metrics::position_error(slam, (robot.x, robot.y));
+        squared_error_sum += final_error * final_error;
+        odometry_squared_error_sum += metrics::position_error(&odometry, (robot.x, robot.y)).powi(2);
+        frames += 1;
+
+        if let Some(nees) = metrics::pose_nees(slam, robot.pose()) {
+            nees_sum += nees;
+            nees_count += 1;
+        }
+
+        // checkpoints snapshot the filter and the metrics so far
+        let time = playback.time();
+        for (index, checkpoint) in scenario.checkpoints.iter().enumerate() {
+            if reached[index] { continue; }
+            reached[index] = match *checkpoint {
+                Checkpoint::Time(at) => time >= at - 0.5 * TIME_STEP,
+                Checkpoint::Waypoint { x, y, radius } => {
+                    let inside = (robot.x - x).hypot(robot.y - y) <= radius;
+                    been_outside[index] |= !inside;
+                    inside && been_outside[index]
+                }
+            };
+            if !reached[index] { continue; }
+
+            checkpoints.push(CheckpointReport {
+                checkpoint: index,
+                time,
+                frame: frames,
+                ate: (squared_error_sum / frames as f32).sqrt(),
+                error: final_error,
+                mean_nees: nees_sum / nees_count.max(1) as f32,
+                landmarks_initialized: landmarks_initialized(slam),
+                snapshot: FilterSnapshot {
+                    pose: slam.estimated_pose(),
+                    pose_covariance: slam.pose_covariance(),
+                    landmarks: slam.estimated_map(),
+                },
+            });
+        }
+
+        // coverage follows the estimated pose, as the interactive coverage map does
+        let Pose2 { x, y, .. } = slam.estimated_pose();
+        coverage.mark_observed(x, y, cfg.sensor_range);
+        let ate = (squared_error_sum / frames as f32).sqrt();
+        if convergence_time.is_none() && convergence.step(coverage.covered.len(), landmarks_initialized(slam), ate) {
+            convergence_time = Some(time);
+            if cfg.stop_at_convergence { break; }
+        }
+    }
+
+    Ok(RunReport {
+        ate: (squared_error_sum / frames.max(1) as f32).sqrt(),
+        odometry_ate: (odometry_squared_error_sum / frames.max(1) as f32).sqrt(),
+        final_error,
+        mean_nees: nees_sum / nees_count.max(1) as f32,
+        landmarks_initialized: landmarks_initialized(slam),
+        landmarks_total: ever_present.len(),
+        frames,
+        infeasible_frames: world.robot.feasibility.infeasible_frames(),
+        checkpoints,
+        map: metrics::map_quality(&slam.estimated_map(), &true_positions(&world.landmarks), cfg.map_match_gate),
+        convergence_time,
+    })
+}
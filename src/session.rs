@@ -0,0 +1,72 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use macroquad::prelude::Rect;
+use serde::{Deserialize, Serialize};
+
+use crate::app::user_settings::UserSettings;
+use crate::simulation::{DropoutZone, Landmark, Robot};
+use crate::slam::{Backend, SlamFilter};
+use crate::snapshot;
+
+pub const RECOVERY_PATH: &str = "recovery/session.json";
+
+/*
+ * everything needed to pick a session back up: the world, the ground truth
+ * robot, the filters, and the user's settings
+ */
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    pub sim_time: f32,
+    pub robot: Robot,
+    pub landmarks: Vec<Landmark>,
+    pub obstructions: Vec<(f32, f32, f32, f32)>, // x, y, w, h
+    #[serde(default)]
+    pub dropout_zones: Vec<DropoutZone>,
+    pub user_settings: UserSettings,
+    pub filters: Vec<(Backend, serde_json::Value)>, // each running filter's `SlamFilter::snapshot`
+}
+
+impl Session {
+    pub fn obstruction_rects(&self) -> Vec<Rect> {
+        self.obstructions.iter().map(|&(x, y, w, h)| Rect::new(x, y, w, h)).collect()
+    }
+
+    /*
+     * snapshots of the running filters that can be saved
+     */
+    pub fn snapshots(filters: &[(Backend, Box<dyn SlamFilter>)]) -> Vec<(Backend, serde_json::Value)> {
+        filters.iter().filter_map(|(backend, slam)| Some((*backend, slam.snapshot()?))).collect()
+    }
+
+    /*
+     * the saved filters, without any whose snapshot no longer parses
+     */
+    pub fn restore(snapshots: Vec<(Backend, serde_json::Value)>) -> Vec<(Backend, Box<dyn SlamFilter>)> {
+        snapshots.into_iter()
+            .filter_map(|(backend, snapshot)| Some((backend, backend.restore(snapshot)?)))
+            .collect()
+    }
+
+    pub fn rects_to_tuples(obstructions: &[Rect]) -> Vec<(f32, f32, f32, f32)> {
+        obstructions.iter().map(|rect| (rect.x, rect.y, rect.w, rect.h)).collect()
+    }
+
+    /*
+     * writes to a temporary file first so a crash mid-write can't corrupt the
+     * previous recovery point
+     */
+    pub fn save_recovery(&self, path: &Path) -> io::Result<()> {
+        let temporary = path.with_extension("tmp");
+        snapshot::save(self, &temporary)?;
+        fs::rename(temporary, path)
+    }
+
+    pub fn load_recovery(path: &Path) -> Option<Self> {
+        snapshot::load(path).ok()
+    }
+
+    pub fn discard_recovery(path: &Path) {
+        let _ = fs::remove_file(path);
+    }
+}
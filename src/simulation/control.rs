@@ -0,0 +1,83 @@
+use std::collections::VecDeque;
+
+use crate::config::Config;
+
+/*
+ * actuator between whatever commands the robot and its wheels: velocity changes
+ * are sampled at `command_rate`, held until the next sample, and executed
+ * `control_latency` seconds after they were commanded
+ */
+#[derive(Clone, Default)]
+pub struct Actuator {
+    pub commanded: (f32, f32), // velocities as commanded, before latency
+    settled: (f32, f32), // velocities the wheels ended the last frame at
+    pending: VecDeque<(f32, (f32, f32))>, // (start time, held acceleration)
+    executing: (f32, f32), // acceleration currently applied
+    accumulated: (f32, f32), // change commanded since the last sample
+    since_sample: f32,
+    time: f32,
+}
+
+impl Actuator {
+    pub fn is_active(cfg: &Config) -> bool {
+        cfg.control_latency > 0.0 || cfg.command_rate > 0.0
+    }
+
+    /*
+     * replaces velocities set by the controller since the last frame with the
+     * ones the wheels reach this frame
+     */
+    pub fn actuate(&mut self, velocity: (f32, f32), delta_time: f32, cfg: &Config) -> (f32, f32) {
+        let commanded = (velocity.0 - self.settled.0, velocity.1 - self.settled.1);
+        let executed = self.step(commanded, delta_time, cfg);
+        if !Self::is_active(cfg) { self.commanded = velocity; }
+        (self.settled.0 + executed.0, self.settled.1 + executed.1)
+    }
+
+    pub fn settled(&self) -> (f32, f32) {
+        self.settled
+    }
+
+    /*
+     * speed caps and drag act on the commanded velocities too, as the controller
+     * would expect them to
+     */
+    pub fn settle(&mut self, executed: (f32, f32), limit: impl Fn((f32, f32)) -> (f32, f32)) {
+        self.settled = executed;
+        self.commanded = limit(self.commanded);
+    }
+
+    /*
+     * takes the velocity change commanded this frame and returns the change the
+     * wheels actually make
+     */
+    pub fn step(&mut self, commanded: (f32, f32), delta_time: f32, cfg: &Config) -> (f32, f32) {
+        self.commanded.0 += commanded.0;
+        self.commanded.1 += commanded.1;
+        if !Self::is_active(cfg) {
+            self.pending.clear();
+            self.executing = (0.0, 0.0);
+            return commanded;
+        }
+
+        self.time += delta_time;
+        self.accumulated.0 += commanded.0;
+        self.accumulated.1 += commanded.1;
+        self.since_sample += delta_time;
+
+        // sample the mean commanded acceleration once per command period
+        let period = if cfg.command_rate > 0.0 { 1.0 / cfg.command_rate } else { 0.0 };
+        if self.since_sample >= period - 1e-4 {
+            let acceleration = (self.accumulated.0 / self.since_sample, self.accumulated.1 / self.since_sample);
+            self.pending.push_back((self.time + cfg.control_latency, acceleration));
+            self.accumulated = (0.0, 0.0);
+            self.since_sample = 0.0;
+        }
+
+        while let Some(&(start, acceleration)) = self.pending.front() && start <= self.time + 1e-4 {
+            self.executing = acceleration;
+            self.pending.pop_front();
+        }
+        (self.executing.0 * delta_time, self.executing.1 * delta_time)
+    }
+}
@@ -0,0 +1,61 @@
+use crate::config::Config;
+
+/*
+ * keeps the ground truth dynamically feasible: velocities may only change as
+ * fast as the actuators allow and must pass through rest to change direction.
+ * Counts every frame it had to step in, so evaluation runs can tell whether
+ * their trajectories were ever physically impossible
+ */
+#[derive(Clone, Default)]
+pub struct Feasibility {
+    pub frames: usize,
+    pub clipped_frames: usize, // asked for more acceleration than the actuators have
+    pub blocked_flips: usize, // asked to reverse within a frame
+    pub saturated_frames: usize, // limited by the motors' torque falling off with speed
+}
+
+impl Feasibility {
+    pub fn infeasible_frames(&self) -> usize {
+        self.clipped_frames + self.blocked_flips
+    }
+
+    /*
+     * the velocities the robot can actually reach this frame from `previous`
+     * when `requested` is asked of it
+     */
+    pub fn enforce(&mut self, previous: (f32, f32), requested: (f32, f32), delta_time: f32, cfg: &Config) -> (f32, f32) {
+        if !cfg.enforce_dynamics { return requested; }
+        self.frames += 1;
+
+        let mut clipped = false;
+        let mut flipped = false;
+        let mut saturated = false;
+        let mut axis = |previous: f32, requested: f32, acceleration: f32, max_speed: f32| {
+            let change = requested - previous;
+
+            // speeding up, the available torque drops linearly to nothing at top speed
+            let mut limit = acceleration * delta_time;
+            if cfg.actuator_saturation && change * previous > 0.0 {
+                let available = limit * (1.0 - previous.abs() / max_speed).max(0.0);
+                saturated |= change.abs() > available + 1e-4;
+                limit = available;
+            }
+            clipped |= change.abs() > acceleration * delta_time * 1.001 + 1e-4;
+
+            let reached = previous + change.clamp(-limit, limit);
+            if reached * previous < 0.0 {
+                flipped = true;
+                return 0.0;
+            }
+            reached
+        };
+
+        let linear = axis(previous.0, requested.0, cfg.linear_acc, cfg.max_linear_speed);
+        let angular = axis(previous.1, requested.1, cfg.angular_acc, cfg.max_angular_speed);
+
+        if clipped { self.clipped_frames += 1; }
+        if flipped { self.blocked_flips += 1; }
+        if saturated { self.saturated_frames += 1; }
+        (linear, angular)
+    }
+}
@@ -0,0 +1,254 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::config::Config;
+use crate::math::Pose2;
+use crate::utils::sample_normal;
+
+pub mod control;
+pub mod feasibility;
+pub mod sensor;
+pub mod world;
+
+pub use control::Actuator;
+pub use feasibility::Feasibility;
+pub use sensor::{DropoutZone, Observation, SensorOverrides, VisibilitySector};
+pub use world::World;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Robot {
+    pub x: f32,
+    pub y: f32,
+    pub theta: f32,
+    pub linear_velocity: f32,
+    pub angular_velocity: f32,
+    pub bumped: bool, // bump sensor, true while touching an obstruction
+    prev_linear_velocity: f32,
+    prev_angular_velocity: f32,
+    #[serde(skip)]
+    pub acceleration: (f32, f32), // commanded this frame, (linear, angular)
+    #[serde(skip)]
+    pub actuator: Actuator,
+    #[serde(skip)]
+    pub feasibility: Feasibility,
+}
+
+/*
+ * planar lidar sweep; beam i points at `angle(i)` relative to the robot heading
+ */
+pub struct Scan {
+    pub ranges: Vec<f32>,
+    pub max_range: f32,
+}
+
+impl Scan {
+    pub fn angle(&self, i: usize) -> f32 {
+        -std::f32::consts::PI + (i as f32) * std::f32::consts::TAU / (self.ranges.len() as f32)
+    }
+
+    /*
+     * beam endpoints that hit something, in the robot frame
+     */
+    pub fn hit_points(&self) -> Vec<(f32, f32)> {
+        self.ranges.iter()
+            .enumerate()
+            .filter(|(_, range)| **range < self.max_range)
+            .map(|(i, range)| (range * self.angle(i).cos(), range * self.angle(i).sin()))
+            .collect()
+    }
+}
+
+/*
+ * flags the robot as stuck when it has been commanded to move for a while
+ * without the ground truth pose actually going anywhere
+ */
+pub struct StuckDetector {
+    pub stuck: bool,
+    stalled_time: f32,
+    prev_position: Option<(f32, f32)>,
+}
+
+impl StuckDetector {
+    pub fn new() -> Self {
+        Self {
+            stuck: false,
+            stalled_time: 0.0,
+            prev_position: None,
+        }
+    }
+
+    pub fn update(&mut self, robot: &Robot, delta_time: f32, cfg: &Config) {
+        let (prev_x, prev_y) = self.prev_position.unwrap_or((robot.x, robot.y));
+        let displacement = ((robot.x - prev_x).powi(2) + (robot.y - prev_y).powi(2)).sqrt();
+        let expected = robot.linear_velocity.abs() * delta_time;
+        self.prev_position = Some((robot.x, robot.y));
+
+        let stalled = robot.linear_velocity.abs() > cfg.stuck_min_speed && displacement < 0.2 * expected;
+        self.stalled_time = if stalled { self.stalled_time + delta_time } else { 0.0 };
+        self.stuck = self.stalled_time > cfg.stuck_time;
+    }
+}
+
+/*
+ * four walls of `thickness` just outside `area`, so the edge of the world
+ * collides, stops lidar beams, and occludes landmarks like any obstruction
+ */
+pub fn boundary_walls(area: Rect, thickness: f32) -> Vec<Rect> {
+    vec![
+        Rect::new(area.x - thickness, area.y - thickness, area.w + 2.0 * thickness, thickness),
+        Rect::new(area.x - thickness, area.y + area.h, area.w + 2.0 * thickness, thickness),
+        Rect::new(area.x - thickness, area.y, thickness, area.h),
+        Rect::new(area.x + area.w, area.y, thickness, area.h),
+    ]
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Landmark {
+    pub id: usize,
+    pub x: f32,
+    pub y: f32,
+    #[serde(default)]
+    pub sensor: SensorOverrides,
+    #[serde(default)]
+    pub velocity: (f32, f32), // drift in world units per second, moved by scenario playback
+}
+
+impl Landmark {
+    pub fn new(id: usize, x: f32, y: f32) -> Self {
+        Self { id, x, y, sensor: SensorOverrides::default(), velocity: (0.0, 0.0) }
+    }
+}
+
+impl Default for Robot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Robot {
+    pub fn new() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            theta: 0.0,
+            linear_velocity: 0.0,
+            angular_velocity: 0.0,
+            bumped: false,
+            prev_linear_velocity: 0.0,
+            prev_angular_velocity: 0.0,
+            acceleration: (0.0, 0.0),
+            actuator: Actuator::default(),
+            feasibility: Feasibility::default(),
+        }
+    }
+
+    /*
+     * puts the robot down at an exact pose, at rest
+     */
+    pub fn place(&mut self, x: f32, y: f32, theta: f32) {
+        *self = Self { x, y, theta, ..Self::new() };
+    }
+
+    /*
+     * accelerates toward a commanded velocity, respecting the same acceleration
+     * limits as manual driving
+     */
+    pub fn command_velocity(&mut self, target_linear: f32, target_angular: f32, cfg: &Config, delta_time: f32) {
+        let max_linear_step = cfg.linear_acc * delta_time;
+        let max_angular_step = cfg.angular_acc * delta_time;
+
+        self.linear_velocity += (target_linear - self.linear_velocity).clamp(-max_linear_step, max_linear_step);
+        self.angular_velocity += (target_angular - self.angular_velocity).clamp(-max_angular_step, max_angular_step);
+    }
+
+    pub fn update(&mut self, delta_time: f32, cfg: &Config, world: &World) {
+        // whatever drove the robot this frame changed its velocities from where the wheels left them
+        if delta_time > 0.0 {
+            let settled = self.actuator.settled();
+            self.acceleration = ((self.linear_velocity - settled.0) / delta_time, (self.angular_velocity - settled.1) / delta_time);
+        }
+
+        // commands reach the wheels late and quantized
+        (self.linear_velocity, self.angular_velocity) = self.actuator.actuate((self.linear_velocity, self.angular_velocity), delta_time, cfg);
+
+        // the wheels can only do what is physically possible
+        (self.linear_velocity, self.angular_velocity) = self.feasibility.enforce(
+            self.actuator.settled(),
+            (self.linear_velocity, self.angular_velocity),
+            delta_time,
+            cfg
+        );
+
+        // bound velocity, then apply decay
+        let limit = |(linear, angular): (f32, f32)| (
+            linear.clamp(-cfg.max_linear_speed, cfg.max_linear_speed) * (-cfg.drag_linear * delta_time).exp(),
+            angular.clamp(-cfg.max_angular_speed, cfg.max_angular_speed) * (-cfg.drag_angular * delta_time).exp(),
+        );
+        (self.linear_velocity, self.angular_velocity) = limit((self.linear_velocity, self.angular_velocity));
+        self.actuator.settle((self.linear_velocity, self.angular_velocity), limit);
+        
+        // add noise to velocity; uses separate variable to keep struct's velocities clean
+        let noisy_linear_velocity = self.linear_velocity + sample_normal(0.0, cfg.real_stdev_linear  * self.linear_velocity.abs());
+        let noisy_angular_velocity = self.angular_velocity + sample_normal(0.0, cfg.real_stdev_angular * self.angular_velocity.abs());
+
+        // update pose with the velocities averaged over the frame
+        Pose2 { x: self.x, y: self.y, theta: self.theta } = cfg.sim_integrator.step(
+            self.pose(),
+            0.5 * (noisy_linear_velocity + self.prev_linear_velocity),
+            0.5 * (noisy_angular_velocity + self.prev_angular_velocity),
+            delta_time
+        );
+
+        // push out of whatever it ran into
+        let pushed_out = world.separate((self.x, self.y), cfg.robot_radius);
+        self.bumped = pushed_out.is_some();
+        if let Some((x, y)) = pushed_out { (self.x, self.y) = (x, y); }
+
+        // needed for calculating x, y, and dir on next frame
+        self.prev_linear_velocity = noisy_linear_velocity;
+        self.prev_angular_velocity = noisy_angular_velocity;
+    }
+    
+    pub fn pose(&self) -> Pose2 {
+        Pose2::new(self.x, self.y, self.theta)
+    }
+
+    /*
+     * velocities as last commanded, which lead the executed ones under control latency
+     */
+    pub fn commanded_velocity(&self) -> (f32, f32) {
+        self.actuator.commanded
+    }
+
+    /*
+     * velocities the pose actually moved with this frame, after the motion noise
+     */
+    pub fn noisy_velocity(&self) -> (f32, f32) {
+        (self.prev_linear_velocity, self.prev_angular_velocity)
+    }
+
+    /*
+     * drag only decays velocity exponentially, so "at rest" means below the rest speeds
+     */
+    pub fn is_at_rest(&self, cfg: &Config) -> bool {
+        self.linear_velocity.abs() < cfg.rest_linear_speed && self.angular_velocity.abs() < cfg.rest_angular_speed
+    }
+
+    /*
+     * casts `lidar_num_beams` rays against the world's obstructions and walls from the ground truth pose
+     */
+    pub fn scan(&self, world: &World, cfg: &Config) -> Scan {
+        let mut scan = Scan {
+            ranges: vec![cfg.lidar_range; cfg.lidar_num_beams],
+            max_range: cfg.lidar_range,
+        };
+
+        for i in 0..cfg.lidar_num_beams {
+            let angle = self.theta + scan.angle(i);
+            if let Some(hit) = world.raycast((self.x, self.y), angle, cfg.lidar_range) {
+                scan.ranges[i] = (hit + sample_normal(0.0, cfg.lidar_stdev_range)).clamp(0.0, cfg.lidar_range);
+            }
+        }
+
+        scan
+    }
+}
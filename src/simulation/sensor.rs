@@ -0,0 +1,405 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::config::Config;
+use crate::math::{Pose2, normalize_angle};
+use crate::simulation::{Landmark, Robot};
+use crate::utils::sample_normal;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Observation {
+    pub id: usize,
+    pub range: f32,
+    pub bearing: f32,
+}
+
+/*
+ * per-landmark replacements for the global sensor settings in `Config`;
+ * `None` falls back to the global value
+ */
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SensorOverrides {
+    pub range: Option<f32>,
+    pub stdev_range: Option<f32>,
+    pub stdev_bearing: Option<f32>,
+    pub detection_probability: Option<f32>,
+    #[serde(default)]
+    pub outlier_probability: Option<f32>,
+    #[serde(default)]
+    pub visibility: Option<VisibilitySector>,
+}
+
+/*
+ * the directions a landmark can be seen from, like a reflector that only
+ * returns light to its front: robots within `width` / 2 of `facing`, both in
+ * radians with `facing` in the world frame
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VisibilitySector {
+    pub facing: f32,
+    pub width: f32,
+}
+
+impl VisibilitySector {
+    /*
+     * whether a landmark at `landmark` shows to a robot at `from`
+     */
+    pub fn contains(&self, landmark: (f32, f32), from: (f32, f32)) -> bool {
+        let direction = f32::atan2(from.1 - landmark.1, from.0 - landmark.0) - self.facing;
+        normalize_angle(direction).abs() <= 0.5 * self.width
+    }
+}
+
+/*
+ * a sensor-denied disc, like a fog bank or a patch of interference: while the
+ * robot or a landmark is inside one, that landmark is only detected with
+ * `cfg.dropout_detection_probability`
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DropoutZone {
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+}
+
+impl DropoutZone {
+    pub fn contains(&self, point: (f32, f32)) -> bool {
+        (point.0 - self.x).powi(2) + (point.1 - self.y).powi(2) <= self.radius.powi(2)
+    }
+}
+
+pub fn in_dropout(zones: &[DropoutZone], point: (f32, f32)) -> bool {
+    zones.iter().any(|zone| zone.contains(point))
+}
+
+/*
+ * whether a reading at `bearing` from the heading falls inside the sensor's field of view
+ */
+pub fn in_field_of_view(bearing: f32, cfg: &Config) -> bool {
+    normalize_angle(bearing).abs() <= 0.5 * cfg.sensor_fov
+}
+
+/*
+ * the true range and bearing of `landmark` from `pose` if a sensor reaching
+ * `range` there could see it: no nearer than `min_sensor_range`, inside the
+ * field of view, with a clear line of sight and, for view-dependent landmarks,
+ * from inside their sector
+ */
+pub fn visible<'a>(pose: Pose2, landmark: &Landmark, range: f32, obstructions: impl IntoIterator<Item = &'a Rect>, cfg: &Config) -> Option<(f32, f32)> {
+    let (position, target) = ((pose.x, pose.y), (landmark.x, landmark.y));
+    let (gt_range, gt_bearing) = pose.range_bearing(target);
+
+    if gt_range >= range || gt_range < cfg.min_sensor_range || !in_field_of_view(gt_bearing, cfg) { return None; }
+
+    if !line_of_sight(position, target, obstructions) { return None; }
+
+    // view-dependent landmarks only show from inside their sector
+    if landmark.sensor.visibility.is_some_and(|sector| !sector.contains(target, position)) { return None; }
+
+    Some((gt_range, gt_bearing))
+}
+
+/*
+ * sensor settings after overrides, as they apply to one landmark
+ */
+pub struct SensorModel {
+    pub range: f32,
+    pub stdev_range: f32,
+    pub stdev_bearing: f32,
+    pub detection_probability: f32,
+    pub outlier_probability: f32,
+}
+
+impl SensorOverrides {
+    pub fn resolve(&self, cfg: &Config) -> SensorModel {
+        SensorModel {
+            range: self.range.unwrap_or(cfg.sensor_range),
+            stdev_range: self.stdev_range.unwrap_or(cfg.real_stdev_range),
+            stdev_bearing: self.stdev_bearing.unwrap_or(cfg.real_stdev_bearing),
+            detection_probability: self.detection_probability.unwrap_or(cfg.detection_probability),
+            outlier_probability: self.outlier_probability.unwrap_or(cfg.outlier_probability),
+        }
+    }
+}
+
+impl SensorModel {
+    /*
+     * the model as it holds while the robot moves: noisier and less reliable with speed
+     */
+    pub fn at_speed(mut self, robot: &Robot, cfg: &Config) -> Self {
+        let speed_fraction = (robot.linear_velocity.abs() / cfg.max_linear_speed).min(1.0);
+        self.stdev_range *= 1.0 + cfg.speed_noise_gain * speed_fraction;
+        self.stdev_bearing *= 1.0 + cfg.speed_noise_gain * speed_fraction;
+        self.detection_probability *= (1.0 - cfg.speed_detection_loss * speed_fraction).max(0.0);
+        self
+    }
+}
+
+/*
+ * range and bearing noise mapped to the position of a reading at `range` and
+ * `bearing`, to first order: stdev_range along the beam, range * stdev_bearing across it
+ */
+pub fn cartesian_covariance(range: f32, bearing: f32, stdev_range: f32, stdev_bearing: f32) -> nalgebra::Matrix2<f32> {
+    let jacobian = nalgebra::Matrix2::new(
+        bearing.cos(), -range * bearing.sin(),
+        bearing.sin(), range * bearing.cos(),
+    );
+    jacobian * nalgebra::Matrix2::from_diagonal(&nalgebra::Vector2::new(stdev_range.powi(2), stdev_bearing.powi(2))) * jacobian.transpose()
+}
+
+/*
+ * the sensor model for one landmark as it stands this frame: its overrides, the
+ * robot's speed, and the dropout zones either of them is in
+ */
+fn model_for(robot: &Robot, landmark: &Landmark, dropout: &[DropoutZone], cfg: &Config) -> SensorModel {
+    let mut model = landmark.sensor.resolve(cfg).at_speed(robot, cfg);
+    if in_dropout(dropout, (robot.x, robot.y)) || in_dropout(dropout, (landmark.x, landmark.y)) {
+        model.detection_probability = model.detection_probability.min(cfg.dropout_detection_probability);
+    }
+    model
+}
+
+/*
+ * whether a reading under `model` is detected this time; only draws when it can
+ * matter, so always-detected worlds keep their random sequence
+ */
+fn detected(model: &SensorModel) -> bool {
+    model.detection_probability >= 1.0 || macroquad::rand::gen_range(0.0, 1.0) < model.detection_probability
+}
+
+/*
+ * noisy range-bearing measurements of every landmark in range and line of
+ * sight, with detection all but lost at either end of a dropout zone, plus
+ * any ghosts the obstructions mirror (see `reflections`)
+ */
+pub fn sense<'a>(robot: &Robot, landmarks: &[Landmark], obstructions: impl IntoIterator<Item = &'a Rect> + Clone, dropout: &[DropoutZone], cfg: &Config) -> Vec<Observation> {
+    let mut observations = Vec::new();
+
+    for landmark in landmarks.iter() {
+        let model = model_for(robot, landmark, dropout, cfg);
+
+        let Some((gt_range, gt_bearing)) = visible(robot.pose(), landmark, model.range, obstructions.clone(), cfg) else { continue; };
+
+        if !detected(&model) { continue; }
+
+        let mut noisy_range = (gt_range + sample_normal(0.0, model.stdev_range)).max(0.0);
+        // a reading integrated over the exposure sees the landmark on average where it was mid-exposure
+        let blur = 0.5 * cfg.blur_exposure * robot.angular_velocity;
+        let mut noisy_bearing = gt_bearing + blur + sample_normal(0.0, model.stdev_bearing);
+        noisy_bearing = normalize_angle(noisy_bearing);
+
+        // clutter: a return from somewhere else in range, still attributed to the landmark
+        if model.outlier_probability > 0.0 && macroquad::rand::gen_range(0.0, 1.0) < model.outlier_probability {
+            noisy_range = macroquad::rand::gen_range(0.0, model.range);
+            let half_fov = (0.5 * cfg.sensor_fov).min(std::f32::consts::PI);
+            noisy_bearing = macroquad::rand::gen_range(-half_fov, half_fov);
+        }
+
+        // the sensor only reports whole multiples of its resolution
+        noisy_range = quantize(noisy_range, cfg.range_resolution);
+        noisy_bearing = quantize(noisy_bearing, cfg.bearing_resolution);
+        noisy_bearing = normalize_angle(noisy_bearing);
+
+        observations.push(
+            Observation {
+                id: landmark.id,
+                range: noisy_range,
+                bearing: noisy_bearing
+            }
+        )
+    }
+
+    // only draw when it can matter, so worlds without mirrors keep their random sequence
+    if cfg.reflection_probability > 0.0 {
+        observations.extend(reflections(robot, landmarks, obstructions, dropout, cfg));
+    }
+
+    observations
+}
+
+/*
+ * id of the ghost of `landmark` in face `edge` (0 to 3) of obstruction
+ * `obstruction`, counted down from the top of the id space so it never meets a
+ * real landmark. The landmark and obstruction are paired with Szudzik's
+ * pairing, which gives every pair its own number however large either side
+ * gets, and the face takes the low two bits. A plane mirror's image of a still
+ * landmark stands still too, so the same ghost keeps the same id from
+ * everywhere it shows
+ */
+pub fn ghost_id(landmark: usize, obstruction: usize, edge: usize) -> usize {
+    debug_assert!(edge < 4, "a rectangle has four faces");
+    let pair = if landmark >= obstruction {
+        landmark * landmark + landmark + obstruction
+    } else {
+        obstruction * obstruction + landmark
+    };
+    usize::MAX - (pair * 4 + edge)
+}
+
+pub fn is_ghost(id: usize) -> bool {
+    id > usize::MAX / 2
+}
+
+/*
+ * the faces of a rectangle as their two ends and outward unit normal
+ */
+fn faces(rect: &Rect) -> [(Vec2, Vec2, Vec2); 4] {
+    let (x_min, y_min, x_max, y_max) = (rect.x, rect.y, rect.x + rect.w, rect.y + rect.h);
+    [
+        (vec2(x_min, y_min), vec2(x_max, y_min), vec2(0.0, -1.0)),
+        (vec2(x_max, y_min), vec2(x_max, y_max), vec2(1.0, 0.0)),
+        (vec2(x_max, y_max), vec2(x_min, y_max), vec2(0.0, 1.0)),
+        (vec2(x_min, y_max), vec2(x_min, y_min), vec2(-1.0, 0.0)),
+    ]
+}
+
+/*
+ * ghost readings off mirror-like obstruction faces, the multipath returns that
+ * make real lidars map landmarks behind glass and polished walls: each face a
+ * landmark and the robot both stand in front of mirrors it, with
+ * `cfg.reflection_probability`, whenever the bounce point lies on the face and
+ * neither leg of the path is blocked. The reading points at the mirror image,
+ * as far away as the whole bounced path, and carries a `ghost_id`; it is
+ * detected, or lost, under the same model as the landmark's direct reading
+ */
+pub fn reflections<'a>(robot: &Robot, landmarks: &[Landmark], obstructions: impl IntoIterator<Item = &'a Rect> + Clone, dropout: &[DropoutZone], cfg: &Config) -> Vec<Observation> {
+    let mut observations = Vec::new();
+    let origin = vec2(robot.x, robot.y);
+
+    for landmark in landmarks {
+        let model = model_for(robot, landmark, dropout, cfg);
+        let position = vec2(landmark.x, landmark.y);
+
+        for (index, obstruction) in obstructions.clone().into_iter().enumerate() {
+            for (edge, (start, end, normal)) in faces(obstruction).into_iter().enumerate() {
+                let robot_height = (origin - start).dot(normal);
+                let landmark_height = (position - start).dot(normal);
+                if robot_height <= 0.0 || landmark_height <= 0.0 { continue; }
+
+                let image = position - 2.0 * landmark_height * normal;
+                let range = origin.distance(image);
+                let relative_angle = f32::atan2(image.y - origin.y, image.x - origin.x) - robot.theta;
+                if range >= model.range || range < cfg.min_sensor_range || !in_field_of_view(relative_angle, cfg) { continue; }
+
+                // where the path crosses the face's line, which has to be on the face itself
+                let bounce = origin + (image - origin) * (robot_height / (robot_height + landmark_height));
+                let along = (bounce - start).dot(end - start) / start.distance_squared(end);
+                if !(0.0..=1.0).contains(&along) { continue; }
+
+                // the face's own rectangle lies wholly behind it, so only the others can block a leg
+                let blocked = obstructions.clone().into_iter().enumerate().any(|(other, rect)| {
+                    other != index
+                        && (segment_intersects_rect(origin.x, origin.y, bounce.x, bounce.y, rect)
+                            || segment_intersects_rect(bounce.x, bounce.y, position.x, position.y, rect))
+                });
+                if blocked { continue; }
+
+                if macroquad::rand::gen_range(0.0, 1.0) >= cfg.reflection_probability || !detected(&model) { continue; }
+
+                let noisy_range = (range + sample_normal(0.0, model.stdev_range)).max(0.0);
+                let noisy_bearing = relative_angle + sample_normal(0.0, model.stdev_bearing);
+                let noisy_bearing = quantize(normalize_angle(noisy_bearing), cfg.bearing_resolution);
+
+                observations.push(Observation {
+                    id: ghost_id(landmark.id, index, edge),
+                    range: quantize(noisy_range, cfg.range_resolution),
+                    bearing: normalize_angle(noisy_bearing),
+                });
+            }
+        }
+    }
+
+    observations
+}
+
+/*
+ * whether the segment between two points misses every obstruction
+ */
+pub fn line_of_sight<'a>(from: (f32, f32), to: (f32, f32), obstructions: impl IntoIterator<Item = &'a Rect>) -> bool {
+    !obstructions.into_iter().any(|obstruction| segment_intersects_rect(from.0, from.1, to.0, to.1, obstruction))
+}
+
+/*
+ * nearest multiple of `step`, or `value` itself for a step of 0
+ */
+pub fn quantize(value: f32, step: f32) -> f32 {
+    if step > 0.0 { (value / step).round() * step } else { value }
+}
+
+/*
+ * `cfg.sensor_sweeps` readings of `sense` taken within one frame, as a sensor
+ * running faster than the filters' prediction rate would deliver them
+ */
+pub fn sense_sweeps<'a>(robot: &Robot, landmarks: &[Landmark], obstructions: impl IntoIterator<Item = &'a Rect> + Clone, dropout: &[DropoutZone], cfg: &Config) -> Vec<Observation> {
+    (0..cfg.sensor_sweeps.max(1))
+        .flat_map(|_| sense(robot, landmarks, obstructions.clone(), dropout, cfg))
+        .collect()
+}
+
+/*
+ * folds repeated sightings of a landmark into one averaged measurement and the
+ * number of readings behind it; n independent readings with noise R carry the
+ * same information as their mean with noise R / n, so one correction replaces n.
+ * Landmarks keep the order of their first sighting
+ */
+pub fn compress(observations: &[Observation]) -> Vec<(Observation, usize)> {
+    let mut order: Vec<usize> = Vec::new();
+    let mut sums: std::collections::HashMap<usize, (f32, f32, f32, usize)> = std::collections::HashMap::new();
+
+    for observation in observations {
+        let sum = sums.entry(observation.id).or_insert_with(|| {
+            order.push(observation.id);
+            (0.0, 0.0, 0.0, 0)
+        });
+        sum.0 += observation.range;
+        sum.1 += observation.bearing.sin();
+        sum.2 += observation.bearing.cos();
+        sum.3 += 1;
+    }
+
+    order.into_iter()
+        .map(|id| {
+            let (range, sin, cos, count) = sums[&id];
+            (Observation { id, range: range / count as f32, bearing: f32::atan2(sin, cos) }, count)
+        })
+        .collect()
+}
+
+/*
+ * Liang Barsky algorithm to check if segment intersects rectangle
+ * https://en.wikipedia.org/wiki/Liang%E2%80%93Barsky_algorithm
+ */
+fn segment_intersects_rect(x1: f32, y1: f32, x2: f32, y2: f32, rect: &Rect) -> bool {
+    let x_min = rect.x;
+    let y_min = rect.y;
+    let x_max = rect.x + rect.w;
+    let y_max = rect.y + rect.h;
+
+    let p = [-(x2 - x1), x2 - x1, -(y2 - y1), y2 - y1];
+    let q = [x1 - x_min, x_max - x1, y1 - y_min, y_max - y1];
+
+    let mut u1: f32 = 0.0;
+    let mut u2: f32 = 1.0;
+
+    for i in 0..4 {
+        let p_current = p[i];
+        let q_current = q[i];
+
+        if p_current == 0.0 {
+            if q_current < 0.0 {
+                return false;
+            }
+        } else {
+            let t = q_current / p_current;
+
+            if p_current < 0.0 {
+                if t > u2 { return false; }
+                if t > u1 { u1 = t; }
+            } else {
+                if t < u1 { return false; }
+                if t < u2 { u2 = t; }
+            }
+        }
+    }
+
+    u1 <= u2
+}
@@ -0,0 +1,102 @@
+use macroquad::prelude::*;
+
+use crate::config::Config;
+use crate::math::Pose2;
+use crate::simulation::{DropoutZone, Landmark, Observation, Robot, sensor};
+use crate::utils::ray_rect_distance;
+
+/*
+ * everything the robot moves through and senses, and the robot itself: the
+ * landmarks, the obstructions the tools edit, the boundary walls they can't,
+ * and the sensor-denied zones
+ */
+#[derive(Clone, Default)]
+pub struct World {
+    pub robot: Robot,
+    pub landmarks: Vec<Landmark>,
+    pub obstructions: Vec<Rect>,
+    pub walls: Vec<Rect>, // kept out of `obstructions` so the tools can't remove them
+    pub dropout_zones: Vec<DropoutZone>,
+}
+
+impl World {
+    /*
+     * the empty sandbox, walled in if `cfg.world_boundary` is set, with the robot at the origin
+     */
+    pub fn new(cfg: &Config) -> Self {
+        let walls = cfg.world_boundary
+            .map(|(width, height)| super::boundary_walls(Rect::new(-width / 2.0, -height / 2.0, width, height), cfg.boundary_thickness))
+            .unwrap_or_default();
+        Self { walls, ..Self::default() }
+    }
+
+    /*
+     * the obstructions plus the walls, everything the robot and its sensors run into
+     */
+    pub fn solid(&self) -> impl Iterator<Item = &Rect> + Clone {
+        self.obstructions.iter().chain(&self.walls)
+    }
+
+    /*
+     * the landmarks a noiseless, always detecting sensor at `pose` would see,
+     * by the same test `sensor::sense` applies
+     */
+    pub fn visible_landmarks(&self, pose: Pose2, cfg: &Config) -> Vec<&Landmark> {
+        self.landmarks.iter()
+            .filter(|landmark| sensor::visible(pose, landmark, landmark.sensor.resolve(cfg).range, self.solid(), cfg).is_some())
+            .collect()
+    }
+
+    /*
+     * distance from `position` along the world-frame `angle` to the first
+     * obstruction or wall, if one is within `max_range`
+     */
+    pub fn raycast(&self, position: (f32, f32), angle: f32, max_range: f32) -> Option<f32> {
+        self.solid()
+            .filter_map(|rect| ray_rect_distance(position.0, position.1, angle.cos(), angle.sin(), rect))
+            .fold(None, |nearest: Option<f32>, hit| Some(nearest.map_or(hit, |nearest| nearest.min(hit))))
+            .filter(|&hit| hit < max_range)
+    }
+
+    /*
+     * where a circle at `center` ends up pushed out of every obstruction and
+     * wall it overlaps, one after another, or None if it overlaps none
+     */
+    pub fn separate(&self, center: (f32, f32), radius: f32) -> Option<(f32, f32)> {
+        let (mut x, mut y) = center;
+        let mut bumped = false;
+        for rect in self.solid() {
+            let closest_x = x.clamp(rect.x, rect.x + rect.w);
+            let closest_y = y.clamp(rect.y, rect.y + rect.h);
+
+            // distance from closest point on the rectangle to the center
+            let (distance_x, distance_y) = (x - closest_x, y - closest_y);
+            let distance_sq = distance_x * distance_x + distance_y * distance_y;
+            if distance_sq >= radius * radius { continue; }
+
+            bumped = true;
+            let distance = distance_sq.sqrt();
+            if distance > 0.0 {
+                x = closest_x + radius * (distance_x / distance);
+                y = closest_y + radius * (distance_y / distance);
+            }
+        }
+        bumped.then_some((x, y))
+    }
+
+    /*
+     * moves the world's robot by one frame, bumping into whatever is solid
+     */
+    pub fn step_robot(&mut self, delta_time: f32, cfg: &Config) {
+        let mut robot = std::mem::take(&mut self.robot);
+        robot.update(delta_time, cfg, self);
+        self.robot = robot;
+    }
+
+    /*
+     * one frame of noisy measurements from `robot`, `cfg.sensor_sweeps` readings of each landmark it sees
+     */
+    pub fn sense(&self, robot: &Robot, cfg: &Config) -> Vec<Observation> {
+        sensor::sense_sweeps(robot, &self.landmarks, self.solid(), &self.dropout_zones, cfg)
+    }
+}
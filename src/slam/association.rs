@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use nalgebra::{Matrix2, Matrix3};
+use macroquad::prelude::Color;
+
+use crate::simulation::{Observation, sensor};
+use crate::config::Config;
+use crate::math::Pose2;
+use crate::slam::{EkfSlam, SlamFilter, jcbb};
+
+/*
+ * how `MultiHypothesisEkf` matches a frame's measurements to the map
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Association {
+    NearestNeighbor,    // one at a time, each to its likeliest landmark within the gate
+    JointCompatibility, // all at once by JCBB; hypotheses never split
+}
+
+/*
+ * one way of explaining every measurement so far: an EKF whose landmarks are
+ * numbered by this hypothesis rather than by the sensor
+ */
+#[derive(Clone)]
+pub struct Hypothesis {
+    pub ekf: EkfSlam,
+    pub cost: f32, // accumulated negative log-likelihood of its associations
+    pub associations: usize,
+    pub mistakes: usize, // associations that disagree with the true ids, for diagnostics only
+    true_ids: HashMap<usize, usize>, // own landmark id -> true id of the sighting that created it
+    next_id: usize,
+    seen: Vec<usize>, // own ids matched this update, for culling
+}
+
+impl Hypothesis {
+    /*
+     * applies one association, `None` starting a new landmark unless it is a
+     * culled one coming back
+     */
+    fn associate(&mut self, observation: &Observation, landmark: Option<usize>, cost: f32, readings: usize, cfg: &Config) {
+        let landmark = landmark.or_else(|| self.ekf.reidentify(observation, readings, cfg));
+        let id = landmark.unwrap_or(self.next_id);
+        let correct = match landmark {
+            Some(id) => self.true_ids.get(&id) == Some(&observation.id),
+            None => !self.true_ids.values().any(|&true_id| true_id == observation.id),
+        };
+        self.associations += 1;
+        if !correct { self.mistakes += 1; }
+        if landmark.is_none() {
+            self.true_ids.insert(id, observation.id);
+            self.next_id += 1;
+        }
+        self.seen.push(id);
+
+        self.cost += cost;
+        self.ekf.fold_observation(&Observation { id, ..observation.clone() }, readings, cfg);
+    }
+}
+
+/*
+ * EKF-SLAM without known correspondences: measurements are matched to landmarks
+ * by likelihood within a chi-squared gate. When the best and second best match
+ * are within `ambiguity_margin` of each other the hypothesis splits, and the
+ * least likely hypotheses are pruned down to `max_hypotheses` (MHT-lite). With
+ * one hypothesis this is hard nearest-neighbor association; with
+ * `Association::JointCompatibility` each hypothesis takes the JCBB matches instead
+ */
+#[derive(Clone)]
+pub struct MultiHypothesisEkf {
+    pub hypotheses: Vec<Hypothesis>, // most likely first
+    pub max_hypotheses: usize,
+}
+
+impl MultiHypothesisEkf {
+    pub const COLOR: Color = Color::new(0.0, 0.9, 0.9, 0.5);
+
+    pub fn with_prior(pose: Pose2, stdev_position: f32, stdev_heading: f32, max_hypotheses: usize) -> Self {
+        Self {
+            hypotheses: vec![Hypothesis {
+                ekf: EkfSlam::with_prior(pose, stdev_position, stdev_heading),
+                cost: 0.0,
+                associations: 0,
+                mistakes: 0,
+                true_ids: HashMap::new(),
+                next_id: 0,
+                seen: Vec::new(),
+            }],
+            max_hypotheses: max_hypotheses.max(1),
+        }
+    }
+
+    pub fn best(&self) -> &Hypothesis {
+        &self.hypotheses[0]
+    }
+
+    /*
+     * landmarks `observation` could come from, cheapest first, or when no mapped
+     * one passes `association_gate` and it is within `max_init_range`, a new landmark
+     */
+    fn candidates(ekf: &EkfSlam, observation: &Observation, readings: usize, cfg: &Config) -> Vec<(Option<usize>, f32)> {
+        let mut candidates = Vec::new();
+        for id in ekf.observed_landmarks.ids() {
+            let Some((cost, squared_distance)) = ekf.association_cost(observation, id, readings, cfg) else { continue; };
+            if squared_distance < cfg.association_gate { candidates.push((Some(id), cost)); }
+        }
+
+        if candidates.is_empty() && observation.range <= cfg.max_init_range {
+            candidates.push((None, 0.0));
+        }
+        candidates.sort_by(|a, b| a.1.total_cmp(&b.1));
+        candidates
+    }
+
+    /*
+     * the JCBB matches for a whole frame, decided on the hypothesis as it was
+     * before the frame, with `candidates`' rule for what an unmatched
+     * measurement does
+     */
+    fn associate_jointly(hypothesis: &mut Hypothesis, compressed: &[(Observation, usize)], cfg: &Config) {
+        let matches = jcbb::associate(&hypothesis.ekf, compressed, cfg);
+        let decisions: Vec<Option<(Option<usize>, f32)>> = compressed.iter()
+            .zip(matches)
+            .map(|((observation, readings), landmark)| match landmark {
+                Some(id) => hypothesis.ekf.association_cost(observation, id, *readings, cfg).map(|(cost, _)| (Some(id), cost)),
+                None => Self::candidates(&hypothesis.ekf, observation, *readings, cfg)
+                    .into_iter()
+                    .find(|(landmark, _)| landmark.is_none()),
+            })
+            .collect();
+        for ((observation, readings), decision) in compressed.iter().zip(decisions) {
+            let Some((landmark, cost)) = decision else { continue; };
+            hypothesis.associate(observation, landmark, cost, *readings, cfg);
+        }
+    }
+}
+
+impl SlamFilter for MultiHypothesisEkf {
+    fn predict(&mut self, linear_velocity: f32, angular_velocity: f32, delta_time: f32, cfg: &Config) {
+        for hypothesis in &mut self.hypotheses {
+            hypothesis.ekf.predict(linear_velocity, angular_velocity, delta_time, cfg);
+        }
+    }
+
+    /*
+     * sightings are grouped per landmark within a frame, as the sensor's sweeps
+     * track them, but the ids are never used to match them to the map
+     */
+    fn update(&mut self, observations: &[Observation], cfg: &Config) {
+        let compressed = sensor::compress(observations);
+        let mut next = Vec::with_capacity(2 * self.hypotheses.len());
+
+        for mut hypothesis in std::mem::take(&mut self.hypotheses) {
+            if cfg.association == Association::JointCompatibility {
+                Self::associate_jointly(&mut hypothesis, &compressed, cfg);
+                next.push(hypothesis);
+                continue;
+            }
+            let mut branches = Vec::new();
+            for (observation, readings) in &compressed {
+                let candidates = Self::candidates(&hypothesis.ekf, observation, *readings, cfg);
+                let Some(&(best, best_cost)) = candidates.first() else { continue; };
+
+                // split at most once per frame, so the count grows slowly
+                if branches.is_empty() && let Some(&(second, second_cost)) = candidates.get(1)
+                    && second_cost - best_cost < cfg.ambiguity_margin
+                {
+                    let mut branch = hypothesis.clone();
+                    branch.associate(observation, second, second_cost, *readings, cfg);
+                    branches.push(branch);
+                } else {
+                    for branch in &mut branches {
+                        let candidates = Self::candidates(&branch.ekf, observation, *readings, cfg);
+                        if let Some(&(landmark, cost)) = candidates.first() {
+                            branch.associate(observation, landmark, cost, *readings, cfg);
+                        }
+                    }
+                }
+                hypothesis.associate(observation, best, best_cost, *readings, cfg);
+            }
+            next.push(hypothesis);
+            next.extend(branches);
+        }
+        for hypothesis in &mut next {
+            let seen = std::mem::take(&mut hypothesis.seen);
+            hypothesis.ekf.cull_unseen(&seen, cfg);
+            hypothesis.ekf.prune_unconfirmed(&seen, cfg);
+            // a pruned landmark is gone for good, so its sighting starts a fresh one if it returns
+            let ekf = &hypothesis.ekf;
+            hypothesis.true_ids.retain(|&id, _| ekf.observed_landmarks.contains(id) || ekf.archive.iter().any(|archived| archived.id == id));
+        }
+
+        // keep the likeliest few, and drop any far behind the best
+        next.sort_by(|a, b| a.cost.total_cmp(&b.cost));
+        let best_cost = next[0].cost;
+        next.retain(|hypothesis| hypothesis.cost - best_cost < cfg.hypothesis_prune);
+        next.truncate(self.max_hypotheses);
+        for hypothesis in &mut next { hypothesis.cost -= best_cost; }
+        self.hypotheses = next;
+    }
+
+    fn estimated_pose(&self) -> Pose2 {
+        self.best().ekf.estimated_pose()
+    }
+
+    fn pose_covariance(&self) -> Matrix3<f32> {
+        self.best().ekf.pose_covariance()
+    }
+
+    fn estimated_map(&self) -> Vec<(usize, f32, f32)> {
+        self.best().ekf.estimated_map()
+    }
+
+    fn landmark_covariance(&self, id: usize) -> Option<Matrix2<f32>> {
+        self.best().ekf.landmark_covariance(id)
+    }
+
+    fn color(&self) -> Color {
+        Self::COLOR
+    }
+
+    fn hypotheses(&self) -> &[Hypothesis] {
+        &self.hypotheses
+    }
+}
@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::config::Config;
+use crate::math::Pose2;
+use crate::slam::{EkfSlam, FastSlam, MultiHypothesisEkf, RobocentricEkf, SeifSlam, SlamFilter, UkfSlam};
+
+/*
+ * the estimators that run interchangeably behind `SlamFilter`: headless runs build
+ * the one `--filter` or `Config::headless_filter` names, and `stress` runs them all
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Backend {
+    Ekf,
+    FejEkf,
+    RobocentricEkf,
+    Ukf,
+    MhtEkf,
+    Seif,
+    FastSlam,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 7] = [
+        Backend::Ekf,
+        Backend::FejEkf,
+        Backend::RobocentricEkf,
+        Backend::Ukf,
+        Backend::MhtEkf,
+        Backend::Seif,
+        Backend::FastSlam,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backend::Ekf => "EKF",
+            Backend::FejEkf => "FEJ EKF",
+            Backend::RobocentricEkf => "Robo EKF",
+            Backend::Ukf => "UKF",
+            Backend::MhtEkf => "MHT EKF",
+            Backend::Seif => "SEIF",
+            Backend::FastSlam => "FastSLAM",
+        }
+    }
+
+    // the short name analytics plugins and the determinism audit know the filter by
+    pub fn key(&self) -> &'static str {
+        match self {
+            Backend::Ekf => "ekf",
+            Backend::FejEkf => "fej",
+            Backend::RobocentricEkf => "robo",
+            Backend::Ukf => "ukf",
+            Backend::MhtEkf => "mht",
+            Backend::Seif => "seif",
+            Backend::FastSlam => "fast",
+        }
+    }
+
+    // in any case, spaces written as underscores or dashes, like `StressKind::by_name`
+    pub fn by_name(name: &str) -> Option<Self> {
+        let name = name.replace(['_', '-'], " ");
+        Self::ALL.into_iter().find(|backend| backend.name().eq_ignore_ascii_case(&name))
+    }
+
+    /*
+     * a fresh filter believing the robot is at `pose`, with the configured
+     * prior and an empty map
+     */
+    pub fn build(&self, pose: Pose2, cfg: &Config) -> Box<dyn SlamFilter> {
+        let (position, heading) = (cfg.initial_stdev_position, cfg.initial_stdev_heading);
+        match self {
+            Backend::Ekf => Box::new(EkfSlam::with_prior(pose, position, heading)),
+            Backend::FejEkf => Box::new(EkfSlam::with_prior(pose, position, heading).with_first_estimates()),
+            Backend::RobocentricEkf => Box::new(RobocentricEkf::with_prior(pose, position, heading)),
+            Backend::Ukf => Box::new(UkfSlam::with_prior(pose, position, heading)),
+            Backend::MhtEkf => Box::new(MultiHypothesisEkf::with_prior(pose, position, heading, cfg.max_hypotheses)),
+            Backend::Seif => Box::new(SeifSlam::with_prior(pose, position, heading)),
+            Backend::FastSlam => Box::new(FastSlam::with_prior(cfg.fast_slam_particles, pose, position, heading)),
+        }
+    }
+
+    /*
+     * the filter a `SlamFilter::snapshot` of this backend was taken from, or
+     * None if it doesn't parse as one or the backend can't be saved
+     */
+    pub fn restore(&self, snapshot: serde_json::Value) -> Option<Box<dyn SlamFilter>> {
+        fn parse<T: SlamFilter + DeserializeOwned + 'static>(snapshot: serde_json::Value) -> Option<Box<dyn SlamFilter>> {
+            serde_json::from_value::<T>(snapshot).ok().map(|slam| Box::new(slam) as Box<dyn SlamFilter>)
+        }
+        match self {
+            Backend::Ekf | Backend::FejEkf => parse::<EkfSlam>(snapshot),
+            Backend::RobocentricEkf => parse::<RobocentricEkf>(snapshot),
+            Backend::Ukf => parse::<UkfSlam>(snapshot),
+            Backend::MhtEkf => None,
+            Backend::Seif => parse::<SeifSlam>(snapshot),
+            Backend::FastSlam => parse::<FastSlam>(snapshot),
+        }
+    }
+}
@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+use nalgebra::{DMatrix, DVector, Matrix2, Matrix2x3, Matrix3, Vector2};
+use macroquad::prelude::Color;
+
+use crate::simulation::Observation;
+use crate::config::Config;
+use crate::math::{Pose2, normalize_angle};
+use crate::slam::{SlamFilter, linalg};
+
+/*
+ * how a bearing-only EKF puts a landmark on the map, having no range to place it with
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Initialization {
+    // at once, as the ray it was seen along with an uncertain inverse depth
+    // (Civera et al., 2008), which stays close to gaussian out to infinity
+    InverseDepth,
+    // once a second ray at enough parallax crosses the first, at their intersection
+    Triangulation,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Parameters {
+    Cartesian,    // x, y
+    InverseDepth, // position first seen from x0, y0, ray heading, inverse depth
+}
+
+impl Parameters {
+    fn size(self) -> usize {
+        match self {
+            Parameters::Cartesian => 2,
+            Parameters::InverseDepth => 4,
+        }
+    }
+}
+
+/*
+ * the first ray a triangulating filter saw a landmark along, waiting for a second
+ */
+#[derive(Clone, Debug)]
+pub struct FirstSighting {
+    pub pose: Pose2,
+    pub pose_covariance: Matrix3<f32>,
+    pub bearing: f32,
+}
+
+// a crossing point and its jacobians with respect to each ray
+pub type Crossing = ((f32, f32), Matrix2x3<f32>, Matrix2x3<f32>);
+
+/*
+ * where two rays, each from a position along a world heading (a pose looking
+ * down the ray), cross, and the jacobians of the crossing with respect to each
+ * ray's (x, y, heading). None when they are too close to parallel or meet
+ * behind either origin
+ */
+pub fn triangulate(first: Pose2, second: Pose2) -> Option<Crossing> {
+    let m0 = Vector2::new(first.theta.cos(), first.theta.sin());
+    let m1 = Vector2::new(second.theta.cos(), second.theta.sin());
+    let normal0 = Vector2::new(-m0.y, m0.x);
+    let d = Vector2::new(second.x - first.x, second.y - first.y);
+    let cross = |a: Vector2<f32>, b: Vector2<f32>| a.x * b.y - a.y * b.x;
+
+    let c = cross(m0, m1);
+    if c.abs() < 1e-4 { return None; }
+    let t0 = cross(d, m1) / c;
+    let t1 = cross(d, m0) / c;
+    if t0 <= 0.0 || t1 <= 0.0 { return None; }
+    let point = Vector2::new(first.x, first.y) + t0 * m0;
+
+    // t0 = cross(p1 - p0, m1) / cross(m0, m1), and the point is p0 + t0 m0
+    let t0_by_p1 = Vector2::new(m1.y, -m1.x) / c;
+    let t0_by_heading0 = t0 * m0.dot(&m1) / c;
+    let t0_by_heading1 = (d.dot(&m1) - t0 * m0.dot(&m1)) / c;
+
+    let by_p1 = m0 * t0_by_p1.transpose();
+    let by_p0 = Matrix2::identity() - by_p1;
+    let by_heading0 = m0 * t0_by_heading0 + t0 * normal0;
+    let by_heading1 = m0 * t0_by_heading1;
+
+    let first_jacobian = Matrix2x3::new(
+        by_p0[(0, 0)], by_p0[(0, 1)], by_heading0.x,
+        by_p0[(1, 0)], by_p0[(1, 1)], by_heading0.y
+    );
+    let second_jacobian = Matrix2x3::new(
+        by_p1[(0, 0)], by_p1[(0, 1)], by_heading1.x,
+        by_p1[(1, 0)], by_p1[(1, 1)], by_heading1.y
+    );
+    Some(((point.x, point.y), first_jacobian, second_jacobian))
+}
+
+/*
+ * EKF-SLAM for a sensor that only gives bearings: the ranges in the
+ * observations are ignored. A single bearing leaves a landmark's depth unknown,
+ * so landmarks are initialized per `Initialization`; two of these run side by
+ * side to compare the two
+ */
+#[derive(Clone)]
+pub struct BearingOnlyEkf {
+    pub state: DVector<f32>, // robot x, y, heading, then each landmark's parameters
+    pub covariance: DMatrix<f32>,
+    pub initialization: Initialization,
+    landmarks: HashMap<usize, (usize, Parameters)>, // maps ids to state index and parametrization
+    pub pending: HashMap<usize, FirstSighting>, // triangulation only: landmarks seen once so far
+}
+
+impl BearingOnlyEkf {
+    pub const INVERSE_DEPTH_COLOR: Color = Color::new(0.4, 1.0, 0.8, 0.5);
+    pub const TRIANGULATION_COLOR: Color = Color::new(1.0, 0.5, 0.8, 0.5);
+
+    pub fn with_prior(pose: Pose2, stdev_position: f32, stdev_heading: f32, initialization: Initialization) -> Self {
+        Self {
+            state: DVector::from_column_slice(&[pose.x, pose.y, pose.theta]),
+            covariance: DMatrix::from_diagonal(&DVector::from_column_slice(&[
+                stdev_position.powi(2), stdev_position.powi(2), stdev_heading.powi(2)
+            ])),
+            initialization,
+            landmarks: HashMap::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    pub fn landmark_count(&self) -> usize {
+        self.landmarks.len()
+    }
+
+    /*
+     * a landmark's position and its jacobian with respect to its parameters
+     */
+    fn position(&self, index: usize, parameters: Parameters) -> ((f32, f32), DMatrix<f32>) {
+        match parameters {
+            Parameters::Cartesian => ((self.state[index], self.state[index + 1]), DMatrix::identity(2, 2)),
+            Parameters::InverseDepth => {
+                let (x0, y0, heading, inverse_depth) = (self.state[index], self.state[index + 1], self.state[index + 2], self.state[index + 3]);
+                // a landmark pushed to or past infinity sits far out along its ray
+                let inverse_depth = inverse_depth.max(1e-4);
+                let (sin, cos) = heading.sin_cos();
+                let jacobian = DMatrix::from_row_slice(2, 4, &[
+                    1.0, 0.0, -sin / inverse_depth, -cos / inverse_depth.powi(2),
+                    0.0, 1.0, cos / inverse_depth, -sin / inverse_depth.powi(2),
+                ]);
+                ((x0 + cos / inverse_depth, y0 + sin / inverse_depth), jacobian)
+            }
+        }
+    }
+
+    /*
+     * appends a landmark block with mean `mean` and covariance `block`, whose
+     * correlations follow from its jacobian with respect to the current pose
+     */
+    fn append(&mut self, id: usize, parameters: Parameters, mean: &[f32], pose_jacobian: &DMatrix<f32>, block: DMatrix<f32>) {
+        let n = self.state.nrows();
+        let size = parameters.size();
+
+        // the new landmark is correlated with everything the pose is
+        let cross = pose_jacobian * self.covariance.rows(0, 3);
+
+        let mut state = std::mem::take(&mut self.state).resize_vertically(n + size, 0.0);
+        state.rows_mut(n, size).copy_from_slice(mean);
+        self.state = state;
+
+        let mut covariance = std::mem::take(&mut self.covariance).resize(n + size, n + size, 0.0);
+        covariance.view_mut((n, 0), (size, n)).copy_from(&cross);
+        covariance.view_mut((0, n), (n, size)).copy_from(&cross.transpose());
+        covariance.view_mut((n, n), (size, size)).copy_from(&block);
+        self.covariance = covariance;
+
+        self.landmarks.insert(id, (n, parameters));
+    }
+
+    fn initialize_inverse_depth(&mut self, observation: &Observation, cfg: &Config) {
+        let (x, y, theta) = (self.state[0], self.state[1], self.state[2]);
+        let inverse_depth = 1.0 / cfg.inverse_depth_initial_range;
+
+        // x0, y0 and the ray heading come from the pose, the heading also from the bearing
+        let pose_jacobian = DMatrix::from_row_slice(4, 3, &[
+            1.0, 0.0, 0.0,
+            0.0, 1.0, 0.0,
+            0.0, 0.0, 1.0,
+            0.0, 0.0, 0.0,
+        ]);
+        let mut block = &pose_jacobian * self.covariance.view((0, 0), (3, 3)) * pose_jacobian.transpose();
+        block[(2, 2)] += cfg.est_stdev_bearing.powi(2);
+        block[(3, 3)] += (cfg.inverse_depth_stdev * inverse_depth).powi(2);
+
+        self.append(observation.id, Parameters::InverseDepth, &[x, y, normalize_angle(theta + observation.bearing), inverse_depth], &pose_jacobian, block);
+    }
+
+    /*
+     * places a landmark seen before at the crossing of the two rays, once they
+     * are at least `triangulation_min_parallax` apart; otherwise keeps waiting.
+     * The first pose is taken as independent of the current state, which
+     * understates the correlation between the two sightings
+     */
+    fn triangulate_landmark(&mut self, observation: &Observation, cfg: &Config) {
+        let pose = self.estimated_pose();
+        let heading = normalize_angle(pose.theta + observation.bearing);
+
+        let Some(first) = self.pending.get(&observation.id) else {
+            let pose_covariance = self.covariance.fixed_view::<3, 3>(0, 0).into_owned();
+            self.pending.insert(observation.id, FirstSighting { pose, pose_covariance, bearing: observation.bearing });
+            return;
+        };
+        let first_heading = normalize_angle(first.pose.theta + first.bearing);
+        if normalize_angle(heading - first_heading).abs() < cfg.triangulation_min_parallax { return; }
+
+        let Some((point, first_jacobian, second_jacobian)) = triangulate(Pose2 { theta: first_heading, ..first.pose }, Pose2 { theta: heading, ..pose }) else {
+            // rays that don't meet in front of both poses: start over from this one
+            let pose_covariance = self.covariance.fixed_view::<3, 3>(0, 0).into_owned();
+            self.pending.insert(observation.id, FirstSighting { pose, pose_covariance, bearing: observation.bearing });
+            return;
+        };
+
+        // each ray's heading is its pose heading plus its bearing, so the bearing
+        // noise enters through the heading column
+        let bearing_variance = cfg.est_stdev_bearing.powi(2);
+        let first_heading_column = first_jacobian.column(2);
+        let second_heading_column = second_jacobian.column(2);
+        let block = first_jacobian * first.pose_covariance * first_jacobian.transpose()
+            + second_jacobian * self.covariance.fixed_view::<3, 3>(0, 0) * second_jacobian.transpose()
+            + (first_heading_column * first_heading_column.transpose() + second_heading_column * second_heading_column.transpose()) * bearing_variance;
+
+        let pose_jacobian = DMatrix::from_fn(2, 3, |i, j| second_jacobian[(i, j)]);
+        let block = DMatrix::from_fn(2, 2, |i, j| block[(i, j)]);
+        self.pending.remove(&observation.id);
+        self.append(observation.id, Parameters::Cartesian, &[point.0, point.1], &pose_jacobian, block);
+    }
+
+    fn correct_landmark(&mut self, observation: &Observation, index: usize, parameters: Parameters, cfg: &Config) {
+        let n = self.state.nrows();
+        let (x, y, theta) = (self.state[0], self.state[1], self.state[2]);
+
+        // jacobian of the bearing with respect to the robot and the landmark position
+        let ((landmark_x, landmark_y), position_jacobian) = self.position(index, parameters);
+        let (dx, dy) = (landmark_x - x, landmark_y - y);
+        let distance_sq = (dx * dx + dy * dy).max(1e-6);
+        let by_position = DMatrix::from_row_slice(1, 2, &[-dy / distance_sq, dx / distance_sq]);
+
+        let mut h = DMatrix::zeros(1, n);
+        h[(0, 0)] = dy / distance_sq;
+        h[(0, 1)] = -dx / distance_sq;
+        h[(0, 2)] = -1.0;
+        h.view_mut((0, index), (1, parameters.size())).copy_from(&(by_position * position_jacobian));
+
+        let innovation = normalize_angle(observation.bearing - normalize_angle(dy.atan2(dx) - theta));
+
+        let ph_t = &self.covariance * h.transpose();
+        let s = (&h * &ph_t)[(0, 0)] + cfg.est_stdev_bearing.powi(2);
+        let gain = ph_t / s;
+
+        self.state += &gain * innovation;
+        self.state[2] = normalize_angle(self.state[2]);
+        self.covariance -= &gain * (h * &self.covariance);
+        linalg::symmetrize(&mut self.covariance);
+    }
+}
+
+impl SlamFilter for BearingOnlyEkf {
+    fn predict(&mut self, linear_velocity: f32, angular_velocity: f32, delta_time: f32, cfg: &Config) {
+        let (dir_x, dir_y) = cfg.filter_integrator.direction(self.state[2], angular_velocity, delta_time);
+        let pose = cfg.filter_integrator.step(self.estimated_pose(), linear_velocity, angular_velocity, delta_time);
+        (self.state[0], self.state[1], self.state[2]) = (pose.x, pose.y, pose.theta);
+
+        // same motion model and control noise as the EKF
+        let (displacement_x, displacement_y) = (linear_velocity * delta_time * dir_x, linear_velocity * delta_time * dir_y);
+        let f_x = Matrix3::new(
+            1.0, 0.0, -displacement_y,
+            0.0, 1.0, displacement_x,
+            0.0, 0.0, 1.0
+        );
+        let sigma_linear_velocity = cfg.est_stdev_linear * linear_velocity.abs() + 0.01;
+        let sigma_angular_velocity = cfg.est_stdev_angular * angular_velocity.abs() + 0.01;
+        let n = Matrix2::new(
+            sigma_linear_velocity.powi(2), 0.0,
+            0.0, sigma_angular_velocity.powi(2)
+        );
+        let f_n = nalgebra::Matrix3x2::new(
+            dir_x * delta_time, 0.0,
+            dir_y * delta_time, 0.0,
+            0.0, delta_time
+        );
+
+        let p_rr = self.covariance.fixed_view::<3, 3>(0, 0);
+        let new_p_rr = f_x * p_rr * f_x.transpose() + f_n * n * f_n.transpose();
+        self.covariance.fixed_view_mut::<3, 3>(0, 0).copy_from(&new_p_rr);
+
+        let map_size = self.covariance.ncols() - 3;
+        if map_size > 0 {
+            let new_p_rm = f_x * self.covariance.view((0, 3), (3, map_size));
+            self.covariance.view_mut((0, 3), (3, map_size)).copy_from(&new_p_rm);
+            self.covariance.view_mut((3, 0), (map_size, 3)).copy_from(&new_p_rm.transpose());
+        }
+    }
+
+    fn update(&mut self, observations: &[Observation], cfg: &Config) {
+        for observation in observations {
+            match self.landmarks.get(&observation.id).copied() {
+                Some((index, parameters)) => self.correct_landmark(observation, index, parameters, cfg),
+                None => match self.initialization {
+                    Initialization::InverseDepth => self.initialize_inverse_depth(observation, cfg),
+                    Initialization::Triangulation => self.triangulate_landmark(observation, cfg),
+                },
+            }
+        }
+    }
+
+    fn estimated_pose(&self) -> Pose2 {
+        Pose2::new(self.state[0], self.state[1], self.state[2])
+    }
+
+    fn pose_covariance(&self) -> Matrix3<f32> {
+        self.covariance.fixed_view::<3, 3>(0, 0).into_owned()
+    }
+
+    fn estimated_map(&self) -> Vec<(usize, f32, f32)> {
+        let mut landmarks: Vec<_> = self.landmarks.iter()
+            .map(|(&id, &(index, parameters))| {
+                let ((x, y), _) = self.position(index, parameters);
+                (id, x, y)
+            })
+            .collect();
+        landmarks.sort_by_key(|&(id, _, _)| id);
+        landmarks
+    }
+
+    fn landmark_covariance(&self, id: usize) -> Option<Matrix2<f32>> {
+        let &(index, parameters) = self.landmarks.get(&id)?;
+        let (_, jacobian) = self.position(index, parameters);
+        let size = parameters.size();
+        let covariance = &jacobian * self.covariance.view((index, index), (size, size)) * jacobian.transpose();
+        Some(Matrix2::from_fn(|i, j| covariance[(i, j)]))
+    }
+
+    fn color(&self) -> Color {
+        match self.initialization {
+            Initialization::InverseDepth => Self::INVERSE_DEPTH_COLOR,
+            Initialization::Triangulation => Self::TRIANGULATION_COLOR,
+        }
+    }
+}
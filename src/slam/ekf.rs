@@ -1,64 +1,650 @@
 use std::{collections::HashMap};
-use nalgebra::{DMatrix, DVector, Vector2, Matrix2x3, Matrix2, stack};
+use nalgebra::{DMatrix, DVector, Vector2, Matrix2x3, Matrix2, Matrix3, stack};
 use macroquad::prelude::Color;
+use serde::{Deserialize, Serialize};
 
-use crate::simulation::Observation;
+use crate::simulation::{Observation, sensor};
 use crate::config::Config;
-use crate::slam::Slam;
-use crate::utils::{relative_to_absolute, absolute_to_relative};
+use crate::math::{Pose2, normalize_angle};
+use crate::slam::{LandmarkRegistry, SlamFilter, linalg};
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct EkfSlam {
     pub state: DVector<f32>,
     pub covariance: DMatrix<f32>,
-    pub observed_landmarks: HashMap<usize, usize>, // maps ids to state index
+    pub observed_landmarks: LandmarkRegistry,
+    #[serde(default)]
+    pub first_estimates: Option<FirstEstimates>, // set when jacobians are evaluated at first estimates
+    #[serde(default)]
+    pub anchors: Vec<usize>, // ids of landmarks pinned by `anchor_landmark`
+    #[serde(default)]
+    pub adaptive_noise: Option<AdaptiveNoise>, // set when R is estimated from the residuals
+    #[serde(default)]
+    pub sightings: HashMap<usize, u32>, // corrections per landmark, kept across culling
+    #[serde(default)]
+    pub misses: HashMap<usize, u32>, // consecutive updates a landmark in range went unseen
+    #[serde(default)]
+    pub unseen: HashMap<usize, u32>, // consecutive updates a landmark went unseen anywhere
+    #[serde(default)]
+    pub archive: Vec<ArchivedLandmark>, // culled landmarks, for re-identification
+    #[serde(default)]
+    pub next_id: usize, // id for the next landmark started without known ids
+    #[serde(default)]
+    pub provisional: HashMap<usize, ProvisionalLandmark>, // landmarks so far seen only beyond `max_init_range`
+    #[serde(skip)]
+    pub trace: Option<Vec<CorrectionTrace>>, // set while every correction is being traced
+}
+
+/*
+ * every quantity of one landmark correction, matrices as lists of rows, so the
+ * update can be checked against an independent implementation: `columns` are
+ * the state entries H's columns belong to (robot x, y, heading, landmark x, y),
+ * `p_columns` those columns of P before the correction, `r` the measurement
+ * noise after the robust kernel, and with FEJ `h` is taken at the first estimates
+ */
+#[derive(Clone, Debug, Serialize)]
+pub struct CorrectionTrace {
+    pub landmark: usize,
+    pub readings: usize,
+    pub z: (f32, f32), // measured range and bearing
+    pub z_pred: (f32, f32),
+    pub innovation: (f32, f32),
+    pub state: Vec<f32>, // before the correction
+    pub columns: [usize; 5],
+    pub p_columns: Vec<Vec<f32>>,
+    pub h: Vec<Vec<f32>>,
+    pub r: Vec<Vec<f32>>,
+    pub s: Vec<Vec<f32>>,
+    pub k: Vec<Vec<f32>>,
+    pub state_delta: Vec<f32>,
+}
+
+/*
+ * information-weighted mean of two position estimates, or None if a covariance
+ * is singular or the two disagree by more than the chi-squared `gate`
+ */
+fn fuse_positions(a: (f32, f32), a_covariance: &Matrix2<f32>, b: (f32, f32), b_covariance: &Matrix2<f32>, gate: f32) -> Option<(f32, f32)> {
+    let difference = Vector2::new(a.0 - b.0, a.1 - b.1);
+    let squared_distance = (difference.transpose() * (a_covariance + b_covariance).try_inverse()? * difference)[(0, 0)];
+    if squared_distance >= gate { return None; }
+
+    let (a_information, b_information) = (a_covariance.try_inverse()?, b_covariance.try_inverse()?);
+    let fused = (a_information + b_information).try_inverse()? * (a_information * Vector2::new(a.0, a.1) + b_information * Vector2::new(b.0, b.1));
+    Some((fused.x, fused.y))
+}
+
+fn matrix_rows<R: nalgebra::Dim, C: nalgebra::Dim, S: nalgebra::RawStorage<f32, R, C>>(matrix: &nalgebra::Matrix<f32, R, C, S>) -> Vec<Vec<f32>> {
+    matrix.row_iter().map(|row| row.iter().copied().collect()).collect()
+}
+
+/*
+ * a landmark dropped from the state after going unseen where it should have
+ * been visible, kept so it can be recognized when it turns up again
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ArchivedLandmark {
+    pub id: usize,
+    pub position: (f32, f32),
+    pub covariance: Matrix2<f32>,
+    pub sightings: u32,
+}
+
+/*
+ * a landmark so far seen only too far away to initialize well: its sightings
+ * fused into one world-frame position, held until a reading inside
+ * `max_init_range` starts it
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ProvisionalLandmark {
+    pub position: (f32, f32),
+    pub covariance: Matrix2<f32>,
+    pub sightings: u32,
+}
+
+/*
+ * linearization points for First-Estimates-Jacobian EKF (Huang et al., 2010):
+ * landmarks keep the position they were initialized at, and the motion jacobian
+ * uses the previous prediction instead of the corrected pose, so corrections
+ * can't make the global position and heading look observable
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FirstEstimates {
+    pub predicted_position: (f32, f32), // robot position after the latest prediction
+    pub landmarks: HashMap<usize, (f32, f32)>,
+}
+
+/*
+ * measurement noise learned online by residual-based covariance matching
+ * (Akhlaghi et al., 2017): after each correction R <- a R + (1 - a) (e e^T + H P H^T)
+ * with the post-fit residual e, which stays positive unlike the innovation-based form.
+ * Only the diagonal is kept, per single reading
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AdaptiveNoise {
+    pub range_variance: f32,
+    pub bearing_variance: f32,
+}
+
+impl AdaptiveNoise {
+    pub fn stdevs(&self) -> (f32, f32) {
+        (self.range_variance.sqrt(), self.bearing_variance.sqrt())
+    }
 }
 
 impl EkfSlam {
     pub const COLOR: Color = Color::new(0.0, 1.0, 0.0, 0.5);
 
     pub fn new() -> Self {
+        Self::with_prior(Pose2::IDENTITY, 0.1, 0.1)
+    }
+
+    /*
+     * filter that believes the robot starts at `pose` with independent position
+     * and heading uncertainty
+     */
+    pub fn with_prior(pose: Pose2, stdev_position: f32, stdev_heading: f32) -> Self {
+        let variances = DVector::from_vec(vec![stdev_position.powi(2), stdev_position.powi(2), stdev_heading.powi(2)]);
+
         Self {
-            state: DVector::from_element(3, 0.0), // initial state vector contains robot x, y, angle
-            covariance: DMatrix::identity(3, 3) * 0.01, // size is 3 + 2L where L is the number of landmarks
-            observed_landmarks: HashMap::new(),
+            state: DVector::from_column_slice(&[pose.x, pose.y, pose.theta]), // initial state vector contains robot x, y, angle
+            covariance: DMatrix::from_diagonal(&variances), // size is 3 + 2L where L is the number of landmarks
+            observed_landmarks: LandmarkRegistry::new(),
+            first_estimates: None,
+            anchors: Vec::new(),
+            adaptive_noise: None,
+            sightings: HashMap::new(),
+            misses: HashMap::new(),
+            unseen: HashMap::new(),
+            archive: Vec::new(),
+            next_id: 0,
+            provisional: HashMap::new(),
+            trace: None,
+        }
+    }
+
+    pub fn with_first_estimates(mut self) -> Self {
+        self.set_first_estimates(true);
+        self
+    }
+
+    /*
+     * switches FEJ on or off; switching on mid-run takes the current estimates
+     * as the first ones
+     */
+    pub fn set_first_estimates(&mut self, enabled: bool) {
+        if enabled == self.first_estimates.is_some() { return; }
+
+        self.first_estimates = enabled.then(|| FirstEstimates {
+            predicted_position: (self.state[0], self.state[1]),
+            landmarks: self.observed_landmarks.iter()
+                .map(|(id, index)| (id, (self.state[index], self.state[index + 1])))
+                .collect(),
+        });
+    }
+
+    /*
+     * switches R adaptation on or off; it starts from the configured noise
+     */
+    pub fn set_adaptive_noise(&mut self, enabled: bool, cfg: &Config) {
+        if enabled == self.adaptive_noise.is_some() { return; }
+
+        self.adaptive_noise = enabled.then(|| AdaptiveNoise {
+            range_variance: cfg.est_stdev_range.powi(2),
+            bearing_variance: cfg.est_stdev_bearing.powi(2),
+        });
+    }
+
+    /*
+     * sensor noise of a measurement averaged from `readings` readings, adapted or configured
+     */
+    pub fn measurement_noise(&self, readings: usize, cfg: &Config) -> Matrix2<f32> {
+        let (range_variance, bearing_variance) = match &self.adaptive_noise {
+            Some(adaptive) => (adaptive.range_variance, adaptive.bearing_variance),
+            None => (cfg.est_stdev_range.powi(2), cfg.est_stdev_bearing.powi(2)),
+        };
+        Matrix2::new(range_variance, 0.0, 0.0, bearing_variance) / readings as f32
+    }
+
+    /*
+     * folds the post-fit residual of a correction into the adapted R; `h` is the
+     * correction's jacobian with respect to the robot and the landmark
+     */
+    fn adapt_noise(&mut self, observation: &Observation, landmark_index: usize, h: &nalgebra::Matrix2x5<f32>, readings: usize, cfg: &Config) {
+        let (range, bearing) = self.estimated_pose().range_bearing((self.state[landmark_index], self.state[landmark_index + 1]));
+        let bearing_residual = observation.bearing - bearing;
+        let residual = Vector2::new(observation.range - range, normalize_angle(bearing_residual));
+
+        let indices = [0, 1, 2, landmark_index, landmark_index + 1];
+        let p_block = nalgebra::Matrix5::from_fn(|i, j| self.covariance[(indices[i], indices[j])]);
+        let h_p_ht = h * p_block * h.transpose();
+
+        // the averaged measurement has 1 / readings of a single reading's variance
+        let forgetting = cfg.adaptive_noise_forgetting;
+        let scale = readings as f32;
+        let Some(adaptive) = self.adaptive_noise.as_mut() else { return; };
+        adaptive.range_variance = forgetting * adaptive.range_variance + (1.0 - forgetting) * scale * (residual[0].powi(2) + h_p_ht[(0, 0)]);
+        adaptive.bearing_variance = forgetting * adaptive.bearing_variance + (1.0 - forgetting) * scale * (residual[1].powi(2) + h_p_ht[(1, 1)]);
+    }
+
+    /*
+     * pins a mapped landmark to its current estimate with a direct measurement of its
+     * position, `stdev` sharp; this fixes the gauge at the landmark, so the rest of the
+     * map and the pose are left uncertain only relative to it. Returns false for
+     * landmarks the filter hasn't mapped
+     */
+    pub fn anchor_landmark(&mut self, id: usize, stdev: f32) -> bool {
+        let Some(index) = self.observed_landmarks.get(id) else { return false; };
+
+        // H selects the landmark, so P H^T is its columns and the innovation is zero
+        let r = Matrix2::identity() * stdev.powi(2);
+        let p_ht = self.covariance.fixed_columns::<2>(index).into_owned();
+        let innovation_covariance = self.covariance.fixed_view::<2, 2>(index, index) + r;
+        let Some(innovation_inverse) = innovation_covariance.try_inverse() else { return false; };
+        let k = &p_ht * innovation_inverse;
+
+        // Joseph form, as in `correct_landmark`; the mean doesn't move
+        linalg::rank2_update(&mut self.covariance, -1.0, &k, &p_ht);
+        let a_ht = self.covariance.fixed_columns::<2>(index).into_owned();
+        linalg::rank2_update(&mut self.covariance, -1.0, &a_ht, &k);
+        linalg::rank2_update(&mut self.covariance, 1.0, &(&k * r), &k);
+        linalg::symmetrize(&mut self.covariance);
+
+        if !self.anchors.contains(&id) { self.anchors.push(id); }
+        true
+    }
+
+    /*
+     * what-if edit: scales the variance of the pose (`None`) or of one mapped
+     * landmark by `factor`, and its correlations with everything else by the
+     * square root of it. That is D P D for a diagonal D, so the covariance stays
+     * positive semi-definite and the filter can carry on from it. Returns false
+     * for landmarks the filter hasn't mapped and factors that aren't positive
+     */
+    pub fn scale_covariance(&mut self, landmark: Option<usize>, factor: f32) -> bool {
+        if !(factor.is_finite() && factor > 0.0) { return false; }
+        let (index, size) = match landmark {
+            None => (0, 3),
+            Some(id) => match self.observed_landmarks.get(id) {
+                Some(index) => (index, 2),
+                None => return false,
+            },
+        };
+
+        let root = factor.sqrt();
+        self.covariance.rows_mut(index, size).scale_mut(root);
+        self.covariance.columns_mut(index, size).scale_mut(root);
+        linalg::symmetrize(&mut self.covariance);
+        true
+    }
+
+    /*
+     * exponential forgetting for maps that drift: `scale_covariance` by e^aging
+     * for every landmark but the anchors, so each landmark's variance grows by
+     * that factor and its correlations keep their coefficients
+     */
+    pub fn age_landmarks(&mut self, aging: f32) {
+        let root = (0.5 * aging).exp();
+        for (id, index) in self.observed_landmarks.iter() {
+            if self.anchors.contains(&id) { continue; }
+            self.covariance.rows_mut(index, 2).scale_mut(root);
+            self.covariance.columns_mut(index, 2).scale_mut(root);
+        }
+    }
+
+    /*
+     * whether the landmark registry, the state, and the covariance agree
+     */
+    pub fn check_layout(&self) -> Result<(), String> {
+        self.observed_landmarks.check(self.state.nrows())?;
+        self.observed_landmarks.check_matrix(&self.covariance)
+    }
+
+    /*
+     * structural and numerical sanity of the filter, used by the fuzz harness
+     */
+    pub fn check_invariants(&self) -> Result<(), String> {
+        self.check_layout()?;
+        let size = self.state.nrows();
+
+        if self.state.iter().any(|value| !value.is_finite()) {
+            return Err("state contains non-finite values".to_string());
         }
+        if self.covariance.iter().any(|value| !value.is_finite()) {
+            return Err("covariance contains non-finite values".to_string());
+        }
+        if let Some(i) = (0..size).find(|&i| self.covariance[(i, i)] < -1e-3) {
+            return Err(format!("negative variance {} at {}", self.covariance[(i, i)], i));
+        }
+
+        let asymmetry = (&self.covariance - self.covariance.transpose()).abs().max();
+        if asymmetry > 1e-2 * self.covariance.abs().max().max(1.0) {
+            return Err(format!("covariance asymmetric by {}", asymmetry));
+        }
+
+        Ok(())
     }
 
     /*
-     * ekf landmark initialization step for full observations
+     * corrects with, or initializes, the landmark `observation.id`; `readings` is
+     * the number of measurements averaged into `observation`
      */
-    fn initialize_landmark(&mut self, observation: &Observation, cfg: &Config) {
-        let old_len = self.state.nrows(); // old length of state vector
-        let (x, y) = relative_to_absolute(
-            self.state[0],
-            self.state[1],
-            self.state[2],
-            observation.range,
-            observation.bearing
+    pub fn fold_observation(&mut self, observation: &Observation, readings: usize, cfg: &Config) {
+        match self.observed_landmarks.get(observation.id) {
+            Some(landmark_index) => {
+                if self.correct_landmark(observation, landmark_index, readings, cfg) {
+                    *self.sightings.entry(observation.id).or_default() += 1;
+                }
+            }
+            // too far to initialize well; it waits in the provisional buffer until seen closer
+            None if observation.range > cfg.max_init_range => self.hold_provisional(observation, readings, cfg),
+            None => {
+                self.initialize_landmark(observation, readings, cfg);
+                // a landmark coming back from the archive keeps its history
+                self.archive.retain(|archived| archived.id != observation.id);
+                *self.sightings.entry(observation.id).or_default() += 1;
+
+                // the far sightings it waited through move where it starts
+                if let Some(provisional) = self.provisional.remove(&observation.id) {
+                    self.start_from_provisional(observation.id, &provisional, cfg);
+                    *self.sightings.entry(observation.id).or_default() += provisional.sightings;
+                }
+            }
+        }
+    }
+
+    /*
+     * fuses a reading too far to initialize from into its landmark's provisional
+     * position, unless it falls outside `association_gate` of it. The sightings
+     * share the pose's error, so the fused covariance is kept no tighter than the
+     * sharpest single sighting's rather than shrinking as if they were independent
+     */
+    fn hold_provisional(&mut self, observation: &Observation, readings: usize, cfg: &Config) {
+        let ((x, y), p_ll) = self.landmark_belief(observation, readings, cfg);
+
+        let fused = match self.provisional.get(&observation.id) {
+            None => ProvisionalLandmark { position: (x, y), covariance: p_ll, sightings: 1 },
+            Some(held) => {
+                let Some(position) = fuse_positions(held.position, &held.covariance, (x, y), &p_ll, cfg.association_gate) else { return; };
+                let covariance = if p_ll.determinant() < held.covariance.determinant() { p_ll } else { held.covariance };
+                ProvisionalLandmark { position, covariance, sightings: held.sightings + 1 }
+            }
+        };
+        self.provisional.insert(observation.id, fused);
+    }
+
+    /*
+     * moves a just initialized landmark to the information-weighted mean of its
+     * first near reading and its provisional position, if they agree within
+     * `association_gate`. Only the mean: the far sightings were taken from poses
+     * no longer in the state, so folding them in as a measurement would claim
+     * knowledge of the map's absolute position that range-bearing readings never
+     * give. With FEJ the jacobians stay at the near reading, clear of the
+     * far-field skew
+     */
+    fn start_from_provisional(&mut self, id: usize, provisional: &ProvisionalLandmark, cfg: &Config) {
+        let Some(index) = self.observed_landmarks.get(id) else { return; };
+        let initial = (self.state[index], self.state[index + 1]);
+        let initial_covariance = self.covariance.fixed_view::<2, 2>(index, index).into_owned();
+        let Some((x, y)) = fuse_positions(initial, &initial_covariance, provisional.position, &provisional.covariance, cfg.association_gate) else { return; };
+
+        self.state[index] = x;
+        self.state[index + 1] = y;
+    }
+
+    /*
+     * counts a miss for every mapped landmark that should have been in sensor
+     * range and field of view but wasn't in `seen`, and culls those missed `cull_after_misses`
+     * updates in a row; 0 disables culling
+     */
+    pub fn cull_unseen(&mut self, seen: &[usize], cfg: &Config) {
+        if cfg.cull_after_misses == 0 { return; }
+
+        let mut culled = Vec::new();
+        for (id, index) in self.observed_landmarks.iter() {
+            let (range, bearing) = self.estimated_pose().range_bearing((self.state[index], self.state[index + 1]));
+            let misses = self.misses.entry(id).or_default();
+            if seen.contains(&id) || range > cfg.sensor_range || !sensor::in_field_of_view(bearing, cfg) {
+                *misses = 0;
+            } else {
+                *misses += 1;
+                if *misses >= cfg.cull_after_misses && !self.anchors.contains(&id) { culled.push(id); }
+            }
+        }
+        // the registry iterates in no particular order, and the archive's order decides re-identification ties
+        culled.sort_unstable();
+        for id in culled { self.cull_landmark(id); }
+    }
+
+    /*
+     * discards every landmark seen in fewer than `prune_min_sightings` updates
+     * that has then gone `prune_after_updates` updates in a row without a
+     * sighting, wherever it is: a one-off or spurious reading, not a landmark the
+     * robot merely left behind. Unlike culling nothing is archived, so it can't
+     * come back under its old id; 0 disables pruning
+     */
+    pub fn prune_unconfirmed(&mut self, seen: &[usize], cfg: &Config) {
+        if cfg.prune_after_updates == 0 { return; }
+
+        let mut pruned = Vec::new();
+        for id in self.observed_landmarks.ids() {
+            let unseen = self.unseen.entry(id).or_default();
+            if seen.contains(&id) {
+                *unseen = 0;
+                continue;
+            }
+            *unseen += 1;
+            let confirmed = self.sightings.get(&id).copied().unwrap_or(0) >= cfg.prune_min_sightings;
+            if *unseen >= cfg.prune_after_updates && !confirmed && !self.anchors.contains(&id) { pruned.push(id); }
+        }
+        for id in pruned {
+            self.remove_landmark(id);
+            self.sightings.remove(&id);
+        }
+    }
+
+    /*
+     * drops a landmark from the state into the archive
+     */
+    pub fn cull_landmark(&mut self, id: usize) {
+        let Some(index) = self.observed_landmarks.get(id) else { return; };
+        self.archive.push(ArchivedLandmark {
+            id,
+            position: (self.state[index], self.state[index + 1]),
+            covariance: self.covariance.fixed_view::<2, 2>(index, index).into_owned(),
+            sightings: self.sightings.get(&id).copied().unwrap_or(0),
+        });
+        self.remove_landmark(id);
+    }
+
+    /*
+     * takes a landmark out of the state and covariance, and everything kept
+     * about it while mapped
+     */
+    fn remove_landmark(&mut self, id: usize) {
+        if self.observed_landmarks.remove(id, &mut [&mut self.state], &mut [&mut self.covariance]).is_none() { return; }
+        self.misses.remove(&id);
+        self.unseen.remove(&id);
+        if let Some(first_estimates) = self.first_estimates.as_mut() { first_estimates.landmarks.remove(&id); }
+    }
+
+    /*
+     * the archived landmark a new one initialized from `observation` would most
+     * likely be, within `reid_gate` of its archived estimate
+     */
+    pub fn reidentify(&self, observation: &Observation, readings: usize, cfg: &Config) -> Option<usize> {
+        let (x, y) = self.estimated_pose().from_range_bearing(observation.range, observation.bearing);
+        let absolute_angle = self.state[2] + observation.bearing;
+        let g_r = Matrix2x3::new(
+            1.0, 0.0, -observation.range * absolute_angle.sin(),
+            0.0, 1.0, observation.range * absolute_angle.cos()
+        );
+        let g_y = Matrix2::new(
+            absolute_angle.cos(), -observation.range * absolute_angle.sin(),
+            absolute_angle.sin(), observation.range * absolute_angle.cos()
         );
+        let p_rr = self.covariance.fixed_view::<3, 3>(0, 0);
+        let new_covariance = g_r * p_rr * g_r.transpose() + g_y * self.measurement_noise(readings, cfg) * g_y.transpose();
+
+        self.archive.iter()
+            .filter_map(|archived| {
+                let difference = Vector2::new(x - archived.position.0, y - archived.position.1);
+                let squared_distance = (difference.transpose() * (new_covariance + archived.covariance).try_inverse()? * difference)[(0, 0)];
+                (squared_distance < cfg.reid_gate).then_some((archived.id, squared_distance))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(id, _)| id)
+    }
 
-        // update hashmap
-        self.observed_landmarks.insert(observation.id, old_len);
-        
-        // take ownership of state because resize_vertically requires value, not reference
-        let mut state = std::mem::take(&mut self.state);
+    /*
+     * the mapped landmark nearest `observation` by Mahalanobis distance, if any
+     * passes the chi-squared `association_gate`
+     */
+    pub fn nearest_neighbor(&self, observation: &Observation, readings: usize, cfg: &Config) -> Option<usize> {
+        self.observed_landmarks.ids()
+            .filter_map(|id| self.association_cost(observation, id, readings, cfg).map(|(_, squared_distance)| (id, squared_distance)))
+            .filter(|&(_, squared_distance)| squared_distance < cfg.association_gate)
+            .min_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)))
+            .map(|(id, _)| id)
+    }
 
-        // update state vector
-        state = state.resize_vertically(old_len + 2, 0.0);
-        state[old_len] = x;
-        state[old_len + 1] = y;
-        self.state = state; // return ownership
+    /*
+     * a frame's raw readings with the ids the filter gives them when it isn't
+     * told the ids, each decided on the map as it was before the frame: its
+     * nearest neighbor, else a landmark started earlier in the frame whose first
+     * reading it agrees with, else a culled landmark it re-identifies, else a new
+     * one. Readings that match nothing and are too far to start a landmark are dropped
+     */
+    fn relabel(&mut self, observations: &[Observation], cfg: &Config) -> Vec<Observation> {
+        // two readings of one landmark differ by noise of twice R
+        let pair_information = (2.0 * self.measurement_noise(1, cfg)).try_inverse().unwrap_or_else(Matrix2::zeros);
+        let mut started: Vec<Observation> = Vec::new();
+        let mut relabeled = Vec::with_capacity(observations.len());
+
+        for observation in observations {
+            let id = match self.nearest_neighbor(observation, 1, cfg) {
+                Some(id) => id,
+                None if observation.range > cfg.max_init_range => continue,
+                None => {
+                    let agrees = |first: &&Observation| {
+                        let z = Vector2::new(observation.range - first.range, normalize_angle(observation.bearing - first.bearing));
+                        (z.transpose() * pair_information * z)[(0, 0)] < cfg.association_gate
+                    };
+                    match started.iter().find(agrees) {
+                        Some(first) => first.id,
+                        None => {
+                            let id = self.reidentify(observation, 1, cfg).unwrap_or_else(|| self.fresh_id());
+                            started.push(Observation { id, ..observation.clone() });
+                            id
+                        }
+                    }
+                }
+            };
+            relabeled.push(Observation { id, ..observation.clone() });
+        }
+        relabeled
+    }
 
+    /*
+     * an id no mapped or archived landmark has, counting on from `next_id`, so
+     * landmarks mapped before the ids went unknown keep theirs
+     */
+    fn fresh_id(&mut self) -> usize {
+        let taken = self.observed_landmarks.ids()
+            .chain(self.archive.iter().map(|archived| archived.id))
+            .max()
+            .map_or(0, |id| id + 1);
+        let id = self.next_id.max(taken);
+        self.next_id = id + 1;
+        id
+    }
+
+    /*
+     * innovation of `observation` against mapped landmark `id`, its jacobian with
+     * respect to the robot and the landmark, and the landmark's index in the state
+     */
+    pub fn innovation(&self, observation: &Observation, id: usize) -> Option<(Vector2<f32>, nalgebra::Matrix2x5<f32>, usize)> {
+        let index = self.observed_landmarks.get(id)?;
+        let (landmark_x, landmark_y) = (self.state[index], self.state[index + 1]);
+        let (predicted_range, predicted_bearing) = self.estimated_pose().range_bearing((landmark_x, landmark_y));
+        let bearing_difference = observation.bearing - predicted_bearing;
+        let z = Vector2::new(observation.range - predicted_range, normalize_angle(bearing_difference));
+
+        let distance_x = landmark_x - self.state[0];
+        let distance_y = landmark_y - self.state[1];
+        let distance_sq = (distance_x * distance_x + distance_y * distance_y).max(1e-6);
+        let distance = distance_sq.sqrt();
+        let h_block = nalgebra::Matrix2x5::new(
+            -distance_x / distance, -distance_y / distance, 0.0, distance_x / distance, distance_y / distance,
+            distance_y / distance_sq, -distance_x / distance_sq, -1.0, -distance_y / distance_sq, distance_x / distance_sq
+        );
+        Some((z, h_block, index))
+    }
+
+    /*
+     * negative log-likelihood of `observation` coming from landmark `id`, up to a
+     * constant, and its squared Mahalanobis distance; None if `id` isn't mapped
+     */
+    #[allow(clippy::toplevel_ref_arg)] // triggered from inside nalgebra's stack! macro
+    pub fn association_cost(&self, observation: &Observation, id: usize, readings: usize, cfg: &Config) -> Option<(f32, f32)> {
+        let (z, h_block, index) = self.innovation(observation, id)?;
+        let p_block = stack![
+            self.covariance.fixed_view::<3, 3>(0, 0), self.covariance.fixed_view::<3, 2>(0, index);
+            self.covariance.fixed_view::<2, 3>(index, 0), self.covariance.fixed_view::<2, 2>(index, index)
+        ];
+
+        let innovation_covariance = h_block * p_block * h_block.transpose() + self.measurement_noise(readings, cfg);
+        let squared_distance = (z.transpose() * innovation_covariance.try_inverse()? * z)[(0, 0)];
+        Some((0.5 * squared_distance + 0.5 * innovation_covariance.determinant().ln(), squared_distance))
+    }
+
+    /*
+     * ekf landmark initialization step for full observations; `readings` is the
+     * number of measurements averaged into `observation`
+     */
+    fn initialize_landmark(&mut self, observation: &Observation, readings: usize, cfg: &Config) {
+        let ((x, y), p_ll) = self.landmark_belief(observation, readings, cfg);
+
+        if let Some(first_estimates) = self.first_estimates.as_mut() {
+            first_estimates.landmarks.insert(observation.id, (x, y));
+        }
+
+        // landmark-map covariance, from the robot-map covariance
+        let p_lx = Self::landmark_pose_jacobian(observation, self.state[2]) * self.covariance.rows(0, 3);
+
+        // grow the state and covariance by the new landmark's pair
+        let index = self.observed_landmarks.insert(observation.id, &mut [&mut self.state], &mut [&mut self.covariance]);
+        self.state[index] = x;
+        self.state[index + 1] = y;
+
+        self.covariance.view_mut((index, 0), (2, index)).copy_from(&p_lx);
+        self.covariance.view_mut((0, index), (index, 2)).copy_from(&p_lx.transpose());
+        self.covariance.view_mut((index, index), (2, 2)).copy_from(&p_ll);
+    }
+
+    /*
+     * jacobian of the position a reading puts its landmark at with respect to the robot state
+     */
+    fn landmark_pose_jacobian(observation: &Observation, theta: f32) -> Matrix2x3<f32> {
+        let absolute_angle = theta + observation.bearing;
+        Matrix2x3::new(
+            1.0, 0.0, -observation.range * absolute_angle.sin(),
+            0.0, 1.0, observation.range * absolute_angle.cos()
+        )
+    }
+
+    /*
+     * where a reading puts its landmark from the estimated pose, and the
+     * covariance of that position from the pose's and the reading's uncertainty
+     */
+    fn landmark_belief(&self, observation: &Observation, readings: usize, cfg: &Config) -> ((f32, f32), Matrix2<f32>) {
+        let position = self.estimated_pose().from_range_bearing(observation.range, observation.bearing);
 
         // calculate new values for covariance
         let theta = self.state[2];
         let absolute_angle = theta + observation.bearing;
 
         // jacobian of landmark position with respect to robot state
-        let g_r = Matrix2x3::new(
-            1.0, 0.0, -observation.range * absolute_angle.sin(),
-            0.0, 1.0, observation.range * absolute_angle.cos()
-        );
+        let g_r = Self::landmark_pose_jacobian(observation, theta);
 
         // jacobian of landmark position with respect to observation
         let g_y = Matrix2::new(
@@ -69,38 +655,21 @@ impl EkfSlam {
         // covariance of landmark
         let p_rr = self.covariance.fixed_view::<3, 3>(0, 0);
         
-        // sensor noise
-        let r = Matrix2::new(
-            cfg.est_stdev_range.powi(2), 0.0,
-            0.0, cfg.est_stdev_bearing.powi(2)
-        );
+        // sensor noise of the averaged measurement
+        let r = self.measurement_noise(readings, cfg);
 
         // landmark covariance
         let p_ll = (g_r * p_rr * g_r.transpose()) + (g_y * r * g_y.transpose());
 
-        // robot-map covariance
-        let p_rx = self.covariance.view((0, 0), (3, old_len));
-
-        // landmark-map covariance
-        let p_lx = g_r * p_rx;
-
-        // take ownership of covariance
-        let mut covariance = std::mem::take(&mut self.covariance);
-
-        // update covariance
-        covariance = covariance.resize(old_len + 2, old_len + 2, 0.0);
-
-        covariance.view_mut((old_len, 0), (2, old_len)).copy_from(&p_lx);
-        covariance.view_mut((0, old_len), (old_len, 2)).copy_from(&p_lx.transpose());
-        covariance.view_mut((old_len, old_len), (2, 2)).copy_from(&p_ll);
-
-        self.covariance = covariance; // return ownership
+        (position, p_ll)
     }
     
     /*
-     * ekf correction step
+     * ekf correction step; `readings` is the number of measurements averaged into `observation`.
+     * Skipped, returning false, when the innovation covariance is singular
      */
-    fn correct_landmark(&mut self, observation: &Observation, landmark_index: usize, cfg: &Config) {
+    #[allow(clippy::toplevel_ref_arg)] // triggered from inside nalgebra's stack! macro
+    fn correct_landmark(&mut self, observation: &Observation, landmark_index: usize, readings: usize, cfg: &Config) -> bool {
         let robot_x = self.state[0];
         let robot_y = self.state[1];
 
@@ -108,19 +677,22 @@ impl EkfSlam {
         let landmark_y = self.state[landmark_index + 1];
 
         // predicted measurement and innovation
-        let (predicted_range, predicted_bearing) = absolute_to_relative(
-            robot_x,
-            robot_y,
-            self.state[2],
-            landmark_x,
-            landmark_y
-        );
+        let (predicted_range, predicted_bearing) = self.estimated_pose().range_bearing((landmark_x, landmark_y));
         let range_difference = observation.range - predicted_range;
-        let bearing_difference = f32::atan2((observation.bearing - predicted_bearing).sin(), (observation.bearing - predicted_bearing).cos());
+        let bearing_difference = normalize_angle(observation.bearing - predicted_bearing);
 
         // innovation vector
         let z = Vector2::new(range_difference, bearing_difference);
 
+        // linearization point; FEJ swaps in the predicted robot position and the first landmark estimate
+        let ((robot_x, robot_y), (landmark_x, landmark_y)) = match &self.first_estimates {
+            Some(first_estimates) => (
+                first_estimates.predicted_position,
+                first_estimates.landmarks.get(&observation.id).copied().unwrap_or((landmark_x, landmark_y))
+            ),
+            None => ((robot_x, robot_y), (landmark_x, landmark_y)),
+        };
+
         // distance to landmark
         let distance_x = landmark_x - robot_x;
         let distance_y = landmark_y - robot_y;
@@ -145,11 +717,8 @@ impl EkfSlam {
         let p_rl = self.covariance.fixed_view::<3, 2>(0, landmark_index); // robot-landmark covariance
         let p_lr = p_rl.transpose(); // landmark-robot covariance
 
-        // sensor noise
-        let r = Matrix2::new(
-            cfg.est_stdev_range.powi(2), 0.0,
-            0.0, cfg.est_stdev_bearing.powi(2)
-        );
+        // sensor noise of the averaged measurement
+        let r = self.measurement_noise(readings, cfg);
 
         // block matrices
         let h_block = stack![h_r, h_l];
@@ -159,8 +728,11 @@ impl EkfSlam {
         ];
         let h_t_block = h_block.transpose();
 
-        // innovation matrix
-        let z_matrix = h_block * p_block * h_t_block + r;
+        // innovation matrix; a robust kernel inflates R for innovations that look like outliers
+        let predicted_covariance = h_block * p_block * h_t_block;
+        let squared_distance = (predicted_covariance + r).try_inverse().map_or(0.0, |inverse| (z.transpose() * inverse * z)[(0, 0)]);
+        let r = r / cfg.landmark_kernel.weight(squared_distance);
+        let z_matrix = predicted_covariance + r;
         
         // calculate product of covariance with jacobian transpose (PH^T)
         let total_map_size = self.state.nrows();
@@ -169,21 +741,54 @@ impl EkfSlam {
         let p_ht = (p_cols_robot * h_r.transpose()) + (p_cols_landmark * h_l.transpose());
 
         // Kalman gain
-        let k = p_ht * z_matrix.try_inverse().unwrap();
+        let Some(z_inverse) = z_matrix.try_inverse() else { return false; };
+        let k = p_ht * z_inverse;
+
+        if let Some(trace) = self.trace.as_mut() {
+            let columns = [0, 1, 2, landmark_index, landmark_index + 1];
+            trace.push(CorrectionTrace {
+                landmark: observation.id,
+                readings,
+                z: (observation.range, observation.bearing),
+                z_pred: (predicted_range, predicted_bearing),
+                innovation: (z[0], z[1]),
+                state: self.state.iter().copied().collect(),
+                columns,
+                p_columns: matrix_rows(&self.covariance.select_columns(columns.iter())),
+                h: matrix_rows(&h_block),
+                r: matrix_rows(&r),
+                s: matrix_rows(&z_matrix),
+                k: matrix_rows(&k),
+                state_delta: (&k * z).iter().copied().collect(),
+            });
+        }
 
-        // update state and covariance
+        // update state
         self.state = &self.state + &k * z;
-        self.covariance = &self.covariance - &k * z_matrix * k.transpose();
+
+        // Joseph form (I - KH) P (I - KH)^T + K R K^T keeps the covariance positive
+        // semi-definite in f32 when the measurement is much sharper than the prior;
+        // H only touches the robot and landmark columns, so every product stays O(n^2)
+        let hp_t = (h_r * self.covariance.rows(0, 3) + h_l * self.covariance.rows(landmark_index, 2)).transpose();
+        linalg::rank2_update(&mut self.covariance, -1.0, &k, &hp_t); // A = P - K H P
+        let a_ht = self.covariance.columns(0, 3) * h_r.transpose() + self.covariance.columns(landmark_index, 2) * h_l.transpose();
+        linalg::rank2_update(&mut self.covariance, -1.0, &a_ht, &k);
+        linalg::rank2_update(&mut self.covariance, 1.0, &(&k * r), &k);
 
         // force matrix to be symmetric to (hopefully) prevent covariance from exploding
-        self.covariance = (&self.covariance + self.covariance.transpose()) / 2.0;
+        linalg::symmetrize(&mut self.covariance);
 
         // normalize angle
-        self.state[2] = f32::atan2(self.state[2].sin(), self.state[2].cos());
+        self.state[2] = normalize_angle(self.state[2]);
+
+        if self.adaptive_noise.is_some() {
+            self.adapt_noise(observation, landmark_index, &h_block, readings, cfg);
+        }
+        true
     }
 }
 
-impl Slam for EkfSlam {
+impl SlamFilter for EkfSlam {
     /*
      * follows the EKF sparse prediction equations from
      * https://www.iri.upc.edu/people/jsola/JoanSola/objectes/curs_SLAM/SLAM2D/SLAM%20course.pdf
@@ -191,21 +796,27 @@ impl Slam for EkfSlam {
     fn predict(&mut self, linear_velocity: f32, angular_velocity: f32, delta_time: f32, cfg: &Config) {
         debug_assert!(self.covariance.is_square(), "Covariance must be square matrix.");
 
-        let theta = self.state[2];
-        let theta_half = theta + 0.5 * angular_velocity * delta_time; // approximate heading of the robot at the middle of the frame
-        
-        // update position estimate
-        self.state[0] += linear_velocity * delta_time * theta_half.cos();
-        self.state[1] += linear_velocity * delta_time * theta_half.sin();
-        self.state[2] += angular_velocity * delta_time;
-        
-        // normalize angle to (-PI, PI]
-        self.state[2] = f32::atan2(self.state[2].sin(), self.state[2].cos());
-        
+        // average direction of travel over the frame, as the configured integrator sees it
+        let (dir_x, dir_y) = cfg.filter_integrator.direction(self.state[2], angular_velocity, delta_time);
+
+        // update pose estimate, angle normalized to (-PI, PI]
+        let pose = cfg.filter_integrator.step(self.estimated_pose(), linear_velocity, angular_velocity, delta_time);
+        (self.state[0], self.state[1], self.state[2]) = (pose.x, pose.y, pose.theta);
+
+        // displacement the jacobian is built from; FEJ measures it from the previous prediction
+        let (displacement_x, displacement_y) = match self.first_estimates.as_mut() {
+            Some(first_estimates) => {
+                let (previous_x, previous_y) = first_estimates.predicted_position;
+                first_estimates.predicted_position = (self.state[0], self.state[1]);
+                (self.state[0] - previous_x, self.state[1] - previous_y)
+            }
+            None => (linear_velocity * delta_time * dir_x, linear_velocity * delta_time * dir_y),
+        };
+
         // jacobian of the motion model function
         let f_x = nalgebra::Matrix3::new(
-            1.0, 0.0, -linear_velocity * delta_time * theta_half.sin(),
-            0.0, 1.0, linear_velocity * delta_time * theta_half.cos(),
+            1.0, 0.0, -displacement_y,
+            0.0, 1.0, displacement_x,
             0.0, 0.0, 1.0
         );
 
@@ -220,8 +831,8 @@ impl Slam for EkfSlam {
         // jacobian of control noise (assumes noise is on controls, not state 
         // and noise is indepentend between linear velocity and angular velocity)
         let f_n = nalgebra::Matrix3x2::new(
-            theta_half.cos() * delta_time, 0.0,
-            theta_half.sin() * delta_time, 0.0,
+            dir_x * delta_time, 0.0,
+            dir_y * delta_time, 0.0,
             0.0, delta_time
         );
         
@@ -241,6 +852,8 @@ impl Slam for EkfSlam {
 
             // update map-robot cross-covariance
             self.covariance.view_mut((3, 0), (map_size, 3)).copy_from(&new_p_rm.transpose());
+
+            if cfg.landmark_aging_rate > 0.0 { self.age_landmarks(cfg.landmark_aging_rate * delta_time); }
         }
     }
 
@@ -249,33 +862,65 @@ impl Slam for EkfSlam {
      * https://www.iri.upc.edu/people/jsola/JoanSola/objectes/curs_SLAM/SLAM2D/SLAM%20course.pdf
      */
     fn update(&mut self, observations: &[Observation], cfg: &Config) {
-        for observation in observations.iter() {
-            match self.observed_landmarks.get(&observation.id) {
-                Some(&landmark_index) => {
-                    self.correct_landmark(observation, landmark_index, cfg);
-                }
-                None => {
-                    self.initialize_landmark(observation, cfg);
-                }
-            }
+        // stays serial: each correction moves the linearization point of the next;
+        // repeated sightings of a landmark are folded into one correction first.
+        // Without known ids every reading is matched on its own before any are
+        // folded, so only readings of the same track are averaged
+        let relabeled;
+        let observations = if cfg.known_ids {
+            observations
+        } else {
+            relabeled = self.relabel(observations, cfg);
+            &relabeled
+        };
+        let mut seen = Vec::new();
+        for (observation, readings) in sensor::compress(observations) {
+            self.fold_observation(&observation, readings, cfg);
+            seen.push(observation.id);
         }
+        self.cull_unseen(&seen, cfg);
+        self.prune_unconfirmed(&seen, cfg);
     }
     
-    fn get_state(&self) -> (f32, f32, f32) {
-        (self.state[0], self.state[1], self.state[2])
+    fn estimated_pose(&self) -> Pose2 {
+        Pose2::new(self.state[0], self.state[1], self.state[2])
+    }
+
+    fn pose_covariance(&self) -> Matrix3<f32> {
+        self.covariance.fixed_view::<3, 3>(0, 0).into_owned()
     }
 
-    fn get_landmarks(&self) -> Vec<(usize, f32, f32)> {
+    fn estimated_map(&self) -> Vec<(usize, f32, f32)> {
         let mut landmarks = Vec::new();
 
-        for (id, &index) in &self.observed_landmarks {
-            landmarks.push((*id, self.state[index], self.state[index + 1]));
+        for (id, index) in self.observed_landmarks.iter() {
+            landmarks.push((id, self.state[index], self.state[index + 1]));
         }
 
+        // by id, so everything iterating the estimates sees them in the same order every run
+        landmarks.sort_by_key(|&(id, _, _)| id);
         landmarks
     }
 
+    fn landmark_covariance(&self, id: usize) -> Option<Matrix2<f32>> {
+        self.observed_landmarks
+            .get(id)
+            .map(|index| self.covariance.fixed_view::<2, 2>(index, index).into_owned())
+    }
+
     fn color(&self) -> Color {
         Self::COLOR
     }
+
+    fn as_ekf(&self) -> Option<&EkfSlam> {
+        Some(self)
+    }
+
+    fn as_ekf_mut(&mut self) -> Option<&mut EkfSlam> {
+        Some(self)
+    }
+
+    fn snapshot(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
 }
@@ -1,19 +1,22 @@
 use std::collections::HashMap;
-use nalgebra::{Matrix2, Vector2};
+use nalgebra::{Matrix2, Matrix3, Vector2, Vector3};
+use rayon::prelude::*;
 use macroquad::prelude::Color;
+use serde::{Deserialize, Serialize};
 
-use crate::slam::Slam;
+use crate::slam::SlamFilter;
 use crate::simulation::Observation;
 use crate::config::Config;
-use crate::utils::{absolute_to_relative, relative_to_absolute, sample_normal};
+use crate::math::{Pose2, normalize_angle};
+use crate::utils::sample_normal;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct LandmarkEstimate {
     pub mu: Vector2<f32>,
     pub sigma: Matrix2<f32>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Particle {
     pub x: f32,
     pub y: f32,
@@ -22,21 +25,22 @@ pub struct Particle {
     pub landmarks: HashMap<usize, LandmarkEstimate>,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct FastSlam {
     pub particles: Vec<Particle>,
     pub num_particles: usize,
+    #[serde(default)]
+    pub best: usize, // index of a copy of the heaviest particle before the last resampling
 }
 
 impl Particle {
+    fn pose(&self) -> Pose2 {
+        Pose2::new(self.x, self.y, self.theta)
+    }
+
     fn initialize_landmark(&mut self, observation: &Observation, cfg: &Config) {
         let absolute_angle = self.theta + observation.bearing;
-        let (landmark_x, landmark_y) = relative_to_absolute(
-            self.x,
-            self.y,
-            self.theta,
-            observation.range,
-            observation.bearing
-        );
+        let (landmark_x, landmark_y) = self.pose().from_range_bearing(observation.range, observation.bearing);
 
         // jacobian of landmark position with respect to observation
         let g_y = Matrix2::new(
@@ -63,20 +67,15 @@ impl Particle {
     }
 
     fn correct_landmark(&mut self, observation: &Observation, cfg: &Config) {
+        let pose = self.pose();
         if let Some(landmark) = self.landmarks.get_mut(&observation.id) {
             // compute distances
             let distance_x = landmark.mu.x - self.x;
             let distance_y = landmark.mu.y - self.y;
-            let distance_sq = (distance_x * distance_x) + (distance_y * distance_y);
+            let distance_sq = ((distance_x * distance_x) + (distance_y * distance_y)).max(1e-6);
             let distance = distance_sq.sqrt();
 
-            let (predicted_range, predicted_bearing) = absolute_to_relative(
-                self.x,
-                self.y,
-                self.theta,
-                landmark.mu.x,
-                landmark.mu.y
-            );
+            let (predicted_range, predicted_bearing) = pose.range_bearing((landmark.mu.x, landmark.mu.y));
 
             let range_difference = observation.range - predicted_range;
             let bearing_difference = f32::atan2(
@@ -105,7 +104,7 @@ impl Particle {
             // innovation matrix
             let z_matrix = h_l * p_ll * h_l.transpose() + r;
 
-            let z_inverse = z_matrix.try_inverse().unwrap();
+            let Some(z_inverse) = z_matrix.try_inverse() else { return; };
 
             // weight update
             let determinant = z_matrix.determinant().max(1e-6);
@@ -144,6 +143,28 @@ impl FastSlam {
         Self {
             particles,
             num_particles,
+            best: 0,
+        }
+    }
+
+    /*
+     * particles drawn around `pose` with independent position and heading spread
+     */
+    pub fn with_prior(num_particles: usize, pose: Pose2, stdev_position: f32, stdev_heading: f32) -> Self {
+        let particles = (0..num_particles)
+            .map(|_| Particle {
+                x: sample_normal(pose.x, stdev_position),
+                y: sample_normal(pose.y, stdev_position),
+                theta: sample_normal(pose.theta, stdev_heading),
+                weight: 1.0,
+                landmarks: HashMap::new(),
+            })
+            .collect();
+
+        Self {
+            particles,
+            num_particles,
+            best: 0,
         }
     }
 
@@ -156,6 +177,10 @@ impl FastSlam {
             return;
         }
 
+        // weighing at least the average, the heaviest particle always gets a copy
+        let heaviest = (0..self.particles.len()).max_by(|&a, &b| self.particles[a].weight.total_cmp(&self.particles[b].weight)).unwrap_or(0);
+        let mut best = None;
+
         let mut new_particles = Vec::with_capacity(self.num_particles);
         let step = total_weight / (self.num_particles as f32);
         let mut position = macroquad::rand::gen_range(0.0, step);
@@ -168,46 +193,69 @@ impl FastSlam {
                 current_index = (current_index + 1) % self.num_particles;
             }
 
+            if current_index == heaviest && best.is_none() { best = Some(new_particles.len()); }
             let mut particle = self.particles[current_index].clone();
             particle.weight = 1.0;
             new_particles.push(particle);
             position += step;
         }
+
+        self.particles = new_particles;
+        self.best = best.unwrap_or(0);
+    }
+
+    /*
+     * rescales weights so the largest is 1; many likelihood factors in one frame
+     * would otherwise overflow or underflow f32
+     */
+    fn normalize_weights(&mut self) {
+        let max_weight = self.particles.iter().map(|particle| particle.weight).fold(0.0, f32::max);
+        if max_weight > 0.0 && max_weight.is_finite() {
+            for particle in &mut self.particles { particle.weight /= max_weight; }
+        }
     }
 }
 
-impl Slam for FastSlam {
+impl SlamFilter for FastSlam {
     fn predict(&mut self, linear_velocity: f32, angular_velocity: f32, delta_time: f32, cfg: &Config) {
         for particle in &mut self.particles {
             let noisy_linear_velocity = linear_velocity + sample_normal(0.0, (cfg.est_stdev_linear * linear_velocity.abs()).max(0.01));
             let noisy_angular_velocity  = angular_velocity + sample_normal(0.0, (cfg.est_stdev_angular * angular_velocity.abs()).max(0.01));
 
-            let theta_half = particle.theta + 0.5 * noisy_angular_velocity * delta_time;
-        
-            // update position estimate
-            particle.x += noisy_linear_velocity * delta_time * theta_half.cos();
-            particle.y += noisy_linear_velocity * delta_time * theta_half.sin();
-            particle.theta += noisy_angular_velocity * delta_time;
-
-            // normalize angle to (-PI, PI]
-            particle.theta = f32::atan2(particle.theta.sin(), particle.theta.cos());
+            // update pose estimate, angle normalized to (-PI, PI]
+            let pose = cfg.filter_integrator.step(
+                Pose2::new(particle.x, particle.y, particle.theta),
+                noisy_linear_velocity,
+                noisy_angular_velocity,
+                delta_time
+            );
+            (particle.x, particle.y, particle.theta) = (pose.x, pose.y, pose.theta);
         }
     }
 
     fn update(&mut self, observations: &[Observation], cfg: &Config) {
         for observation in observations {
-            for particle in &mut self.particles {
-                if particle.landmarks.contains_key(&observation.id) {
-                    particle.correct_landmark(observation, cfg);
-                } else {
-                    particle.initialize_landmark(observation, cfg);
-                }
-            }
+            // every particle has seen the same landmarks, so the first one speaks for all
+            let known = self.particles.first().is_some_and(|particle| particle.landmarks.contains_key(&observation.id));
+            if !known && observation.range > cfg.max_init_range { continue; }
+
+            // given its own pose every particle's landmark filters are independent
+            self.particles
+                .par_iter_mut()
+                .with_min_len(cfg.parallel_chunk_size)
+                .for_each(|particle| {
+                    if particle.landmarks.contains_key(&observation.id) {
+                        particle.correct_landmark(observation, cfg);
+                    } else {
+                        particle.initialize_landmark(observation, cfg);
+                    }
+                });
+            self.normalize_weights();
         }
         self.resample();
     }
 
-    fn get_state(&self) -> (f32, f32, f32) {
+    fn estimated_pose(&self) -> Pose2 {
         let mut x = 0.0;
         let mut y = 0.0;
         let mut dir_x = 0.0;
@@ -224,12 +272,36 @@ impl Slam for FastSlam {
             total_weight += particle.weight;
         }
 
-        if total_weight < 1e-10 { return (0.0, 0.0, 0.0); }
+        if total_weight < 1e-10 { return Pose2::IDENTITY; }
 
-        (x / total_weight, y / total_weight, f32::atan2(dir_y, dir_x))
+        Pose2::new(x / total_weight, y / total_weight, f32::atan2(dir_y, dir_x))
     }
 
-    fn get_landmarks(&self) -> Vec<(usize, f32, f32)> {
+    /*
+     * weighted sample covariance of the particle poses around the mean pose
+     */
+    fn pose_covariance(&self) -> Matrix3<f32> {
+        let Pose2 { x: mean_x, y: mean_y, theta: mean_theta } = self.estimated_pose();
+        let mut covariance = Matrix3::zeros();
+        let mut total_weight = 0.0;
+
+        for particle in &self.particles {
+            let angle_difference = particle.theta - mean_theta;
+            let difference = Vector3::new(
+                particle.x - mean_x,
+                particle.y - mean_y,
+                normalize_angle(angle_difference)
+            );
+            covariance += difference * difference.transpose() * particle.weight;
+            total_weight += particle.weight;
+        }
+
+        if total_weight < 1e-10 { return Matrix3::zeros(); }
+
+        covariance / total_weight
+    }
+
+    fn estimated_map(&self) -> Vec<(usize, f32, f32)> {
         let mut total_weight = 0.0;
         let mut hashmap: HashMap<usize, (f32, f32)> = std::collections::HashMap::new();
         let mut landmarks = Vec::new();
@@ -252,10 +324,55 @@ impl Slam for FastSlam {
 
         }
 
+        landmarks.sort_by_key(|&(id, _, _)| id);
         landmarks
     }
 
+    /*
+     * weighted mixture of the per-particle landmark EKFs; the spread of the
+     * particle means is added on top of their averaged covariance
+     */
+    fn landmark_covariance(&self, id: usize) -> Option<Matrix2<f32>> {
+        let mut total_weight = 0.0;
+        let mut mean = Vector2::zeros();
+
+        for particle in &self.particles {
+            if let Some(landmark) = particle.landmarks.get(&id) {
+                mean += landmark.mu * particle.weight;
+                total_weight += particle.weight;
+            }
+        }
+
+        if total_weight < 1e-10 { return None; }
+        mean /= total_weight;
+
+        let mut covariance = Matrix2::zeros();
+        for particle in &self.particles {
+            if let Some(landmark) = particle.landmarks.get(&id) {
+                let difference = landmark.mu - mean;
+                covariance += (landmark.sigma + difference * difference.transpose()) * particle.weight;
+            }
+        }
+
+        Some(covariance / total_weight)
+    }
+
     fn color(&self) -> macroquad::prelude::Color {
         Self::COLOR
     }
+
+    fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /*
+     * the particle that weighed most at the last update, whose map is the
+     * likeliest single one; `estimated_map` averages over all of them instead
+     */
+    fn best_particle(&self) -> Option<&Particle> {
+        self.particles.get(self.best)
+    }
+    fn snapshot(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
 }
@@ -0,0 +1,375 @@
+use std::collections::BTreeMap;
+use std::ops::AddAssign;
+use nalgebra::{DMatrix, DVector, Matrix2, Matrix2x3, Matrix3, Matrix3x2, Vector2, Vector3};
+use macroquad::prelude::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::Observation;
+use crate::config::Config;
+use crate::math::{Pose2, normalize_angle};
+use crate::slam::SlamFilter;
+
+/*
+ * the motion between two keyframes, in the frame of the first, as odometry measured it
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OdometryFactor {
+    pub from: usize,
+    pub to: usize,
+    pub motion: Vector3<f32>,
+    pub information: Matrix3<f32>,
+}
+
+/*
+ * a range-bearing measurement of a landmark from a keyframe
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LandmarkFactor {
+    pub pose: usize,
+    pub landmark: usize, // id
+    pub range: f32,
+    pub bearing: f32,
+}
+
+/*
+ * GraphSLAM over keyframes (Thrun et al., Probabilistic Robotics, chapter 11;
+ * Grisetti et al., "A Tutorial on Graph-Based SLAM"): a keyframe every
+ * `graph_keyframe_distance` or `graph_keyframe_angle` of motion, tied to the
+ * one before by the odometry between them and to the landmarks it measured,
+ * and every `graph_optimize_every` keyframes all of them and the landmarks are
+ * solved for at once by Levenberg-Marquardt. Unlike the filters it relinearizes
+ * the whole past, so a loop closure straightens the trajectory that led to it.
+ * Between keyframes the pose is dead reckoned from the newest one
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GraphSlam {
+    pub poses: Vec<Vector3<f32>>, // keyframes, oldest first
+    pub landmarks: BTreeMap<usize, Vector2<f32>>, // by id, ordered so the solve doesn't depend on insertion
+    pub odometry: Vec<OdometryFactor>,
+    pub measurements: Vec<LandmarkFactor>,
+    prior: (Vector3<f32>, Matrix3<f32>), // on the first keyframe, as information; it fixes the gauge
+    head: Vector3<f32>, // the current pose, dead reckoned from the newest keyframe
+    motion_covariance: Matrix3<f32>, // of the head relative to the newest keyframe, in the world frame
+    covariance: Matrix3<f32>, // of the head
+    keyframe_covariance: Matrix3<f32>, // of the newest keyframe, from the last solve
+    landmark_covariances: BTreeMap<usize, Matrix2<f32>>, // from the last solve
+    since_optimization: usize, // keyframes
+    pub iterations: usize, // taken by the last solve
+}
+
+impl GraphSlam {
+    pub const COLOR: Color = Color::new(0.2, 0.9, 0.8, 0.5);
+
+    pub fn new() -> Self {
+        Self::with_prior(Pose2::IDENTITY, 0.1, 0.1)
+    }
+
+    /*
+     * the same prior as `EkfSlam::with_prior`, on the first keyframe
+     */
+    pub fn with_prior(pose: Pose2, stdev_position: f32, stdev_heading: f32) -> Self {
+        let pose = pose.to_vector();
+        let covariance = Matrix3::from_diagonal(&Vector3::new(stdev_position.powi(2), stdev_position.powi(2), stdev_heading.powi(2)));
+        Self {
+            poses: vec![pose],
+            landmarks: BTreeMap::new(),
+            odometry: Vec::new(),
+            measurements: Vec::new(),
+            prior: (pose, covariance.try_inverse().unwrap_or_else(Matrix3::identity)),
+            head: pose,
+            motion_covariance: Matrix3::zeros(),
+            covariance,
+            keyframe_covariance: covariance,
+            landmark_covariances: BTreeMap::new(),
+            since_optimization: 0,
+            iterations: 0,
+        }
+    }
+
+    /*
+     * the head relative to the newest keyframe, in the keyframe's frame
+     */
+    fn motion_since_keyframe(&self) -> Vector3<f32> {
+        relative_pose(&self.poses[self.poses.len() - 1], &self.head)
+    }
+
+    /*
+     * starts a keyframe at the head, tied to the previous one by the odometry since
+     */
+    fn add_keyframe(&mut self) {
+        let from = self.poses.len() - 1;
+        let rotation = frame_rotation(self.poses[from][2]);
+        // a small floor keeps a keyframe reached without moving from having infinite information
+        let covariance = rotation * self.motion_covariance * rotation.transpose() + Matrix3::from_diagonal_element(1e-4);
+        self.odometry.push(OdometryFactor {
+            from,
+            to: from + 1,
+            motion: self.motion_since_keyframe(),
+            information: covariance.try_inverse().unwrap_or_else(Matrix3::identity),
+        });
+        self.poses.push(self.head);
+        self.keyframe_covariance = self.covariance;
+        self.motion_covariance = Matrix3::zeros();
+        self.since_optimization += 1;
+    }
+
+    /*
+     * Levenberg-Marquardt over every keyframe and landmark, up to
+     * `graph_max_iterations` steps; the head keeps its offset from the newest
+     * keyframe, and the pose and landmark covariances are read off the
+     * inverse of the final information matrix
+     */
+    pub fn optimize(&mut self, cfg: &Config) {
+        let offset = self.motion_since_keyframe();
+        let ids: Vec<usize> = self.landmarks.keys().copied().collect();
+        let landmark_index: BTreeMap<usize, usize> = ids.iter().enumerate().map(|(i, &id)| (id, 3 * self.poses.len() + 2 * i)).collect();
+        let mut state = DVector::from_iterator(
+            3 * self.poses.len() + 2 * ids.len(),
+            self.poses.iter().flat_map(|pose| pose.iter().copied()).chain(self.landmarks.values().flat_map(|landmark| landmark.iter().copied()))
+        );
+
+        let mut damping = 1e-3;
+        let (mut information, mut gradient, mut error) = self.linearize(&state, &landmark_index, cfg);
+        self.iterations = 0;
+        while self.iterations < cfg.graph_max_iterations {
+            self.iterations += 1;
+            let mut damped = information.clone();
+            for i in 0..damped.nrows() {
+                damped[(i, i)] += damping * information[(i, i)].max(1e-6);
+            }
+            let Some(cholesky) = damped.cholesky() else { damping *= 10.0; continue; };
+            let step = -cholesky.solve(&gradient);
+
+            let mut candidate = &state + &step;
+            for pose in 0..self.poses.len() {
+                candidate[3 * pose + 2] = normalize_angle(candidate[3 * pose + 2]);
+            }
+            let linearized = self.linearize(&candidate, &landmark_index, cfg);
+            if linearized.2 < error {
+                // done once a step barely lowers the error
+                let converged = error - linearized.2 < 1e-4 * error;
+                state = candidate;
+                (information, gradient, error) = linearized;
+                damping = (damping / 10.0).max(1e-7);
+                if converged { break; }
+            } else {
+                damping *= 10.0;
+                if damping > 1e6 { break; }
+            }
+        }
+
+        for (pose, estimate) in self.poses.iter_mut().enumerate() {
+            *estimate = state.fixed_rows::<3>(3 * pose).into_owned();
+        }
+        for (id, &index) in &landmark_index {
+            self.landmarks.insert(*id, state.fixed_rows::<2>(index).into_owned());
+        }
+        self.head = compose(&self.poses[self.poses.len() - 1], &offset);
+
+        // marginals of the newest keyframe and every landmark, solved for only their columns
+        let newest = 3 * (self.poses.len() - 1);
+        let columns: Vec<usize> = (newest..newest + 3).chain(3 * self.poses.len()..state.nrows()).collect();
+        if let Some(cholesky) = information.cholesky() {
+            let mut selection = DMatrix::zeros(state.nrows(), columns.len());
+            for (column, &index) in columns.iter().enumerate() {
+                selection[(index, column)] = 1.0;
+            }
+            let covariance = cholesky.solve(&selection);
+            self.keyframe_covariance = covariance.fixed_view::<3, 3>(newest, 0).into_owned();
+            self.covariance = self.keyframe_covariance + self.motion_covariance;
+            self.landmark_covariances = landmark_index.iter()
+                .map(|(&id, &index)| (id, covariance.fixed_view::<2, 2>(index, index - 3 * self.poses.len() + 3).into_owned()))
+                .collect();
+        }
+        self.since_optimization = 0;
+    }
+
+    /*
+     * the information matrix, the gradient, and the squared error of the whole
+     * graph at `state`
+     */
+    fn linearize(&self, state: &DVector<f32>, landmark_index: &BTreeMap<usize, usize>, cfg: &Config) -> (DMatrix<f32>, DVector<f32>, f32) {
+        let size = state.nrows();
+        let mut information = DMatrix::zeros(size, size);
+        let mut gradient = DVector::zeros(size);
+        let mut error = 0.0;
+        let pose = |index: usize| state.fixed_rows::<3>(3 * index).into_owned();
+
+        // the prior on the first keyframe
+        let (prior_pose, prior_information) = &self.prior;
+        let mut residual = pose(0) - prior_pose;
+        residual[2] = normalize_angle(residual[2]);
+        information.fixed_view_mut::<3, 3>(0, 0).add_assign(prior_information);
+        gradient.fixed_rows_mut::<3>(0).add_assign(&(prior_information * residual));
+        error += residual.dot(&(prior_information * residual));
+
+        for factor in &self.odometry {
+            let (from, to) = (pose(factor.from), pose(factor.to));
+            let mut residual = relative_pose(&from, &to) - factor.motion;
+            residual[2] = normalize_angle(residual[2]);
+
+            let (sin, cos) = from[2].sin_cos();
+            let (dx, dy) = (to[0] - from[0], to[1] - from[1]);
+            let a = Matrix3::new(
+                -cos, -sin, -sin * dx + cos * dy,
+                sin, -cos, -cos * dx - sin * dy,
+                0.0, 0.0, -1.0
+            );
+            let b = frame_rotation(from[2]);
+            let blocks = [(3 * factor.from, a), (3 * factor.to, b)];
+            for (row, jacobian_row) in blocks {
+                gradient.fixed_rows_mut::<3>(row).add_assign(&(jacobian_row.transpose() * factor.information * residual));
+                for (column, jacobian_column) in blocks {
+                    information.fixed_view_mut::<3, 3>(row, column).add_assign(&(jacobian_row.transpose() * factor.information * jacobian_column));
+                }
+            }
+            error += residual.dot(&(factor.information * residual));
+        }
+
+        let measurement_information = Matrix2::new(
+            1.0 / cfg.est_stdev_range.powi(2), 0.0,
+            0.0, 1.0 / cfg.est_stdev_bearing.powi(2)
+        );
+        for factor in &self.measurements {
+            let robot = pose(factor.pose);
+            let index = landmark_index[&factor.landmark];
+            let (landmark_x, landmark_y) = (state[index], state[index + 1]);
+            let (range, bearing) = Pose2::from_vector(&robot).range_bearing((landmark_x, landmark_y));
+            let residual = Vector2::new(range - factor.range, normalize_angle(bearing - factor.bearing));
+
+            let distance_x = landmark_x - robot[0];
+            let distance_y = landmark_y - robot[1];
+            let distance_sq = (distance_x * distance_x + distance_y * distance_y).max(1e-6);
+            let distance = distance_sq.sqrt();
+            let h_r = Matrix2x3::new(
+                -distance_x / distance, -distance_y / distance, 0.0,
+                distance_y / distance_sq, -distance_x / distance_sq, -1.0
+            );
+            let h_l = Matrix2::new(
+                distance_x / distance, distance_y / distance,
+                -distance_y / distance_sq, distance_x / distance_sq
+            );
+
+            let row_r = 3 * factor.pose;
+            gradient.fixed_rows_mut::<3>(row_r).add_assign(&(h_r.transpose() * measurement_information * residual));
+            gradient.fixed_rows_mut::<2>(index).add_assign(&(h_l.transpose() * measurement_information * residual));
+            information.fixed_view_mut::<3, 3>(row_r, row_r).add_assign(&(h_r.transpose() * measurement_information * h_r));
+            information.fixed_view_mut::<2, 2>(index, index).add_assign(&(h_l.transpose() * measurement_information * h_l));
+            let cross: Matrix3x2<f32> = h_r.transpose() * measurement_information * h_l;
+            information.fixed_view_mut::<3, 2>(row_r, index).add_assign(&cross);
+            information.fixed_view_mut::<2, 3>(index, row_r).add_assign(&cross.transpose());
+            error += residual.dot(&(measurement_information * residual));
+        }
+
+        (information, gradient, error)
+    }
+
+    /*
+     * the optimized trajectory, keyframe by keyframe, then the head
+     */
+    pub fn trajectory(&self) -> Vec<(f32, f32)> {
+        self.poses.iter().chain(std::iter::once(&self.head)).map(|pose| (pose[0], pose[1])).collect()
+    }
+}
+
+impl SlamFilter for GraphSlam {
+    fn predict(&mut self, linear_velocity: f32, angular_velocity: f32, delta_time: f32, cfg: &Config) {
+        let (dir_x, dir_y) = cfg.filter_integrator.direction(self.head[2], angular_velocity, delta_time);
+        self.head = cfg.filter_integrator.step(Pose2::from_vector(&self.head), linear_velocity, angular_velocity, delta_time).to_vector();
+
+        // the EKF's covariance growth, both of the head and of the motion since the keyframe
+        let f_x = Matrix3::new(
+            1.0, 0.0, -linear_velocity * delta_time * dir_y,
+            0.0, 1.0, linear_velocity * delta_time * dir_x,
+            0.0, 0.0, 1.0
+        );
+        let sigma_linear_velocity = cfg.est_stdev_linear * linear_velocity.abs() + 0.01;
+        let sigma_angular_velocity = cfg.est_stdev_angular * angular_velocity.abs() + 0.01;
+        let n = Matrix2::new(
+            sigma_linear_velocity.powi(2), 0.0,
+            0.0, sigma_angular_velocity.powi(2)
+        );
+        let f_n = Matrix3x2::new(
+            dir_x * delta_time, 0.0,
+            dir_y * delta_time, 0.0,
+            0.0, delta_time
+        );
+        let noise = f_n * n * f_n.transpose();
+        self.covariance = f_x * self.covariance * f_x.transpose() + noise;
+        self.motion_covariance = f_x * self.motion_covariance * f_x.transpose() + noise;
+    }
+
+    /*
+     * only keyframes carry measurements; the first sightings get one of their
+     * own wherever the robot is, so the graph starts out anchored
+     */
+    fn update(&mut self, observations: &[Observation], cfg: &Config) {
+        let motion = self.motion_since_keyframe();
+        let far_enough = motion[0].hypot(motion[1]) >= cfg.graph_keyframe_distance || motion[2].abs() >= cfg.graph_keyframe_angle;
+        let first_sight = self.measurements.is_empty() && !observations.is_empty();
+        if far_enough || (first_sight && motion != Vector3::zeros()) {
+            self.add_keyframe();
+        } else if !first_sight {
+            return;
+        }
+
+        let keyframe = self.poses.len() - 1;
+        let pose = self.poses[keyframe];
+        for observation in observations {
+            self.landmarks.entry(observation.id).or_insert_with(|| {
+                let (x, y) = Pose2::from_vector(&pose).from_range_bearing(observation.range, observation.bearing);
+                Vector2::new(x, y)
+            });
+            self.measurements.push(LandmarkFactor { pose: keyframe, landmark: observation.id, range: observation.range, bearing: observation.bearing });
+        }
+
+        if self.since_optimization >= cfg.graph_optimize_every { self.optimize(cfg); }
+    }
+
+    fn estimated_pose(&self) -> Pose2 {
+        Pose2::new(self.head[0], self.head[1], normalize_angle(self.head[2]))
+    }
+
+    fn pose_covariance(&self) -> Matrix3<f32> {
+        self.covariance
+    }
+
+    fn estimated_map(&self) -> Vec<(usize, f32, f32)> {
+        self.landmarks.iter().map(|(&id, landmark)| (id, landmark[0], landmark[1])).collect()
+    }
+
+    fn landmark_covariance(&self, id: usize) -> Option<Matrix2<f32>> {
+        self.landmark_covariances.get(&id).copied()
+    }
+
+    fn color(&self) -> Color {
+        Self::COLOR
+    }
+}
+
+/*
+ * rotates world-frame offsets into the frame of a pose with heading `theta`,
+ * leaving the heading alone
+ */
+fn frame_rotation(theta: f32) -> Matrix3<f32> {
+    let (sin, cos) = theta.sin_cos();
+    Matrix3::new(
+        cos, sin, 0.0,
+        -sin, cos, 0.0,
+        0.0, 0.0, 1.0
+    )
+}
+
+/*
+ * `to` seen from `from`
+ */
+fn relative_pose(from: &Vector3<f32>, to: &Vector3<f32>) -> Vector3<f32> {
+    Pose2::from_vector(from).between(&Pose2::from_vector(to)).to_vector()
+}
+
+/*
+ * the inverse of `relative_pose`
+ */
+fn compose(from: &Vector3<f32>, relative: &Vector3<f32>) -> Vector3<f32> {
+    Pose2::from_vector(from).compose(&Pose2::from_vector(relative)).to_vector()
+}
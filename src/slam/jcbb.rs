@@ -0,0 +1,133 @@
+use nalgebra::{DMatrix, DVector, Matrix2, Matrix2x5, Matrix5, Vector2};
+
+use crate::config::Config;
+use crate::simulation::Observation;
+use crate::slam::EkfSlam;
+
+/*
+ * a measurement and a landmark it is individually compatible with
+ */
+struct Pairing {
+    observation: usize,
+    landmark: usize,
+    innovation: Vector2<f32>,
+    jacobian: Matrix2x5<f32>,
+    index: usize, // of the landmark in the state
+}
+
+/*
+ * the state of the search: the pairing chosen for each measurement decided so
+ * far, and the best complete assignment found yet
+ */
+struct Search {
+    chosen: Vec<Option<usize>>,
+    best: Vec<Option<usize>>,
+    best_pairings: usize,
+    best_distance: f32,
+}
+
+/*
+ * chi-squared gate for `pairings` measurements at once, at the confidence
+ * `gate` has for one: the Wilson-Hilferty approximation of the quantile,
+ * solved for the normal deviate that makes it exact at 2 degrees of freedom
+ */
+pub fn joint_gate(pairings: usize, gate: f32) -> f32 {
+    let cube = |dof: f32, deviate: f32| dof * (1.0 - 2.0 / (9.0 * dof) + deviate * (2.0 / (9.0 * dof)).sqrt()).powi(3);
+    let deviate = ((gate / 2.0).cbrt() - (1.0 - 1.0 / 9.0)) / (1.0f32 / 9.0).sqrt();
+    cube(2.0 * pairings as f32, deviate)
+}
+
+/*
+ * Joint Compatibility Branch and Bound (Neira and Tardós, 2001): the landmark
+ * for each of a frame's measurements, or None, that pairs the most measurements
+ * while the stacked innovation of all pairings passes the joint chi-squared
+ * gate, the smallest joint distance among equals. Nearest neighbor judges
+ * every measurement on its own, so a pose error that shifts them all alike
+ * can pair each with the wrong neighbor; jointly, the shift has to explain
+ * all of them at once. `observations` are compressed, with their reading counts
+ */
+pub fn associate(ekf: &EkfSlam, observations: &[(Observation, usize)], cfg: &Config) -> Vec<Option<usize>> {
+    // individually compatible pairings, in landmark id order so the search doesn't depend on HashMap order
+    let mut ids: Vec<usize> = ekf.observed_landmarks.ids().collect();
+    ids.sort_unstable();
+    let mut pairings = Vec::new();
+    let mut options = vec![Vec::new(); observations.len()];
+    for (i, (observation, readings)) in observations.iter().enumerate() {
+        for &id in &ids {
+            let Some((_, squared_distance)) = ekf.association_cost(observation, id, *readings, cfg) else { continue; };
+            if squared_distance >= cfg.association_gate { continue; }
+            let Some((innovation, jacobian, index)) = ekf.innovation(observation, id) else { continue; };
+            options[i].push(pairings.len());
+            pairings.push(Pairing { observation: i, landmark: id, innovation, jacobian, index });
+        }
+    }
+
+    // covariance between the innovations of every two pairings, the measurement noise on the diagonal
+    let blocks: Vec<Vec<Matrix2<f32>>> = pairings.iter()
+        .map(|a| pairings.iter()
+            .map(|b| {
+                let rows = [0, 1, 2, a.index, a.index + 1];
+                let columns = [0, 1, 2, b.index, b.index + 1];
+                let p = Matrix5::from_fn(|row, column| ekf.covariance[(rows[row], columns[column])]);
+                let cross = a.jacobian * p * b.jacobian.transpose();
+                if a.observation == b.observation { cross + ekf.measurement_noise(observations[a.observation].1, cfg) } else { cross }
+            })
+            .collect())
+        .collect();
+
+    let joint_distance = |chosen: &[usize]| -> Option<f32> {
+        let size = 2 * chosen.len();
+        let innovation = DVector::from_iterator(size, chosen.iter().flat_map(|&pairing| pairings[pairing].innovation.iter().copied()));
+        let covariance = DMatrix::from_fn(size, size, |row, column| blocks[chosen[row / 2]][chosen[column / 2]][(row % 2, column % 2)]);
+        let solved = covariance.cholesky()?.solve(&innovation);
+        Some(innovation.dot(&solved))
+    };
+
+    let landmarks: Vec<usize> = pairings.iter().map(|pairing| pairing.landmark).collect();
+    let mut search = Search { chosen: Vec::new(), best: vec![None; observations.len()], best_pairings: 0, best_distance: 0.0 };
+    branch(0, &options, &landmarks, &joint_distance, cfg.association_gate, &mut search);
+    search.best.iter().map(|pairing| pairing.map(|pairing| pairings[pairing].landmark)).collect()
+}
+
+/*
+ * decides measurement `observation` and every one after it: first each of its
+ * pairings that keeps the whole jointly compatible with the choices so far,
+ * then leaving it unpaired if the rest could still beat the best assignment
+ */
+fn branch(
+    observation: usize,
+    options: &[Vec<usize>],
+    landmarks: &[usize], // of each pairing
+    joint_distance: &dyn Fn(&[usize]) -> Option<f32>,
+    gate: f32,
+    search: &mut Search,
+) {
+    let paired: Vec<usize> = search.chosen.iter().flatten().copied().collect();
+    if observation == options.len() {
+        let distance = if paired.is_empty() { 0.0 } else { joint_distance(&paired).unwrap_or(f32::MAX) };
+        if paired.len() > search.best_pairings || (paired.len() == search.best_pairings && distance < search.best_distance) {
+            search.best = search.chosen.clone();
+            search.best_pairings = paired.len();
+            search.best_distance = distance;
+        }
+        return;
+    }
+
+    for &pairing in &options[observation] {
+        // one landmark explains at most one measurement
+        if paired.iter().any(|&other| landmarks[other] == landmarks[pairing]) { continue; }
+        let mut candidate = paired.clone();
+        candidate.push(pairing);
+        if joint_distance(&candidate).is_some_and(|distance| distance < joint_gate(candidate.len(), gate)) {
+            search.chosen.push(Some(pairing));
+            branch(observation + 1, options, landmarks, joint_distance, gate, search);
+            search.chosen.pop();
+        }
+    }
+
+    if paired.len() + options.len() - observation - 1 > search.best_pairings {
+        search.chosen.push(None);
+        branch(observation + 1, options, landmarks, joint_distance, gate, search);
+        search.chosen.pop();
+    }
+}
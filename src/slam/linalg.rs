@@ -0,0 +1,54 @@
+use nalgebra::{DMatrix, MatrixXx2};
+
+/*
+ * the dense covariance operations that dominate EKF cost on large maps;
+ * the backend is picked at compile time with the `faer` cargo feature
+ */
+
+#[cfg(not(feature = "faer"))]
+pub const BACKEND: &str = "nalgebra";
+#[cfg(feature = "faer")]
+pub const BACKEND: &str = "faer";
+
+/*
+ * p += alpha * u * v^T, a rank-2 update of a dense n x n matrix
+ */
+pub fn rank2_update(p: &mut DMatrix<f32>, alpha: f32, u: &MatrixXx2<f32>, v: &MatrixXx2<f32>) {
+    #[cfg(not(feature = "faer"))]
+    rank2_update_nalgebra(p, alpha, u, v);
+    #[cfg(feature = "faer")]
+    rank2_update_faer(p, alpha, u, v);
+}
+
+/*
+ * the two backends' rank-2 updates, both public so the bench can time them side by side
+ */
+pub fn rank2_update_nalgebra(p: &mut DMatrix<f32>, alpha: f32, u: &MatrixXx2<f32>, v: &MatrixXx2<f32>) {
+    p.gemm(alpha, u, &v.transpose(), 1.0);
+}
+
+#[cfg(feature = "faer")]
+pub fn rank2_update_faer(p: &mut DMatrix<f32>, alpha: f32, u: &MatrixXx2<f32>, v: &MatrixXx2<f32>) {
+    use faer::{Accum, MatMut, MatRef, Par, linalg::matmul::matmul};
+
+    // nalgebra and faer both default to column-major storage, so these are zero-copy views
+    let (rows, cols) = p.shape();
+    let dst = MatMut::from_column_major_slice_mut(p.as_mut_slice(), rows, cols);
+    let lhs = MatRef::from_column_major_slice(u.as_slice(), u.nrows(), 2);
+    let rhs = MatRef::from_column_major_slice(v.as_slice(), v.nrows(), 2);
+    matmul(dst, Accum::Add, lhs, rhs.transpose(), alpha, Par::Seq);
+}
+
+/*
+ * averages p with its transpose in place, without the temporary of (P + P^T) / 2
+ */
+pub fn symmetrize(p: &mut DMatrix<f32>) {
+    let size = p.nrows();
+    for col in 0..size {
+        for row in 0..col {
+            let mean = 0.5 * (p[(row, col)] + p[(col, row)]);
+            p[(row, col)] = mean;
+            p[(col, row)] = mean;
+        }
+    }
+}
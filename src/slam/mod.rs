@@ -1,8 +1,30 @@
 pub mod trait_def;
 pub mod ekf;
 pub mod fast;
+pub mod seif;
+pub mod graph;
+pub mod ukf;
+pub mod robocentric;
+pub mod linalg;
+pub mod robust;
+pub mod association;
+pub mod jcbb;
+pub mod odometry;
+pub mod bearing_only;
+pub mod backends;
+pub mod registry;
 
-pub use trait_def::Slam;
-pub use ekf::EkfSlam;
+pub use trait_def::SlamFilter;
+pub use ekf::{AdaptiveNoise, ArchivedLandmark, CorrectionTrace, EkfSlam};
 pub use fast::FastSlam;
+pub use seif::SeifSlam;
+pub use graph::GraphSlam;
+pub use ukf::UkfSlam;
+pub use robocentric::RobocentricEkf;
+pub use robust::RobustKernel;
+pub use association::{Association, MultiHypothesisEkf};
+pub use odometry::OdometryBaseline;
+pub use bearing_only::{BearingOnlyEkf, Initialization};
+pub use backends::Backend;
+pub use registry::LandmarkRegistry;
 
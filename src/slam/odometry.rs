@@ -0,0 +1,82 @@
+use nalgebra::{Matrix2, Matrix3, Matrix3x2};
+use macroquad::prelude::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::Observation;
+use crate::config::Config;
+use crate::math::Pose2;
+use crate::slam::SlamFilter;
+
+/*
+ * dead reckoning: the EKF's motion model with every measurement ignored. Runs
+ * next to the filters as the baseline their error is measured against, so a
+ * session always shows how much the landmarks actually helped
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OdometryBaseline {
+    pub pose: Pose2,
+    pub covariance: Matrix3<f32>,
+}
+
+impl OdometryBaseline {
+    pub const COLOR: Color = Color::new(1.0, 0.6, 0.3, 0.6);
+
+    pub fn new(pose: Pose2, covariance: Matrix3<f32>) -> Self {
+        Self { pose, covariance }
+    }
+
+    /*
+     * starts from wherever `slam` is, as sure of it as `slam` is
+     */
+    pub fn from_slam<S: SlamFilter + ?Sized>(slam: &S) -> Self {
+        Self::new(slam.estimated_pose(), slam.pose_covariance())
+    }
+}
+
+impl SlamFilter for OdometryBaseline {
+    fn predict(&mut self, linear_velocity: f32, angular_velocity: f32, delta_time: f32, cfg: &Config) {
+        let (dir_x, dir_y) = cfg.filter_integrator.direction(self.pose.theta, angular_velocity, delta_time);
+        self.pose = cfg.filter_integrator.step(self.pose, linear_velocity, angular_velocity, delta_time);
+
+        // the same covariance growth as the EKF's prediction
+        let f_x = Matrix3::new(
+            1.0, 0.0, -linear_velocity * delta_time * dir_y,
+            0.0, 1.0, linear_velocity * delta_time * dir_x,
+            0.0, 0.0, 1.0
+        );
+        let sigma_linear_velocity = cfg.est_stdev_linear * linear_velocity.abs() + 0.01;
+        let sigma_angular_velocity = cfg.est_stdev_angular * angular_velocity.abs() + 0.01;
+        let n = Matrix2::new(
+            sigma_linear_velocity.powi(2), 0.0,
+            0.0, sigma_angular_velocity.powi(2)
+        );
+        let f_n = Matrix3x2::new(
+            dir_x * delta_time, 0.0,
+            dir_y * delta_time, 0.0,
+            0.0, delta_time
+        );
+        self.covariance = f_x * self.covariance * f_x.transpose() + f_n * n * f_n.transpose();
+    }
+
+    fn update(&mut self, _observations: &[Observation], _cfg: &Config) {}
+
+    fn estimated_pose(&self) -> Pose2 {
+        self.pose
+    }
+
+    fn pose_covariance(&self) -> Matrix3<f32> {
+        self.covariance
+    }
+
+    fn estimated_map(&self) -> Vec<(usize, f32, f32)> {
+        Vec::new()
+    }
+
+    fn landmark_covariance(&self, _id: usize) -> Option<Matrix2<f32>> {
+        None
+    }
+
+    fn color(&self) -> Color {
+        Self::COLOR
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use nalgebra::{DMatrix, DVector};
+use serde::{Deserialize, Serialize};
+
+/*
+ * which pair of state entries each mapped landmark owns, for the filters that
+ * keep the robot's pose and then every landmark in one vector. Ids are the
+ * landmarks' stable handles; their indices move up whenever one before them is
+ * removed, so every insert and removal goes through here, growing or shrinking
+ * the filter's vectors and square matrices with the map, and the pairs always
+ * tile the state after the pose with no gaps
+ */
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct LandmarkRegistry {
+    indices: HashMap<usize, usize>, // maps ids to state index
+}
+
+impl LandmarkRegistry {
+    // robot x, y, heading
+    pub const POSE_SIZE: usize = 3;
+
+    pub fn new() -> Self {
+        Self { indices: HashMap::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    // the size of the state holding the pose and every mapped landmark
+    pub fn state_size(&self) -> usize {
+        Self::POSE_SIZE + 2 * self.len()
+    }
+
+    // index of the landmark's x in the state; y follows it
+    pub fn get(&self, id: usize) -> Option<usize> {
+        self.indices.get(&id).copied()
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        self.indices.contains_key(&id)
+    }
+
+    // in no particular order
+    pub fn ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.indices.keys().copied()
+    }
+
+    // (id, index) pairs, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.indices.iter().map(|(&id, &index)| (id, index))
+    }
+
+    // (id, index) pairs in the order the landmarks sit in the state
+    pub fn in_state_order(&self) -> Vec<(usize, usize)> {
+        let mut landmarks: Vec<(usize, usize)> = self.iter().collect();
+        landmarks.sort_unstable_by_key(|&(_, index)| index);
+        landmarks
+    }
+
+    /*
+     * maps `id` to a new pair of entries at the end of the state, growing each of
+     * `vectors` by two zeros and each of `matrices` by two zero rows and columns
+     * for the filter to fill in; the pair's index
+     */
+    pub fn insert(&mut self, id: usize, vectors: &mut [&mut DVector<f32>], matrices: &mut [&mut DMatrix<f32>]) -> usize {
+        debug_assert!(!self.contains(id), "landmark {id} is already mapped");
+        let index = self.state_size();
+        self.indices.insert(id, index);
+
+        for vector in vectors.iter_mut() {
+            **vector = std::mem::take(*vector).resize_vertically(index + 2, 0.0);
+        }
+        for matrix in matrices.iter_mut() {
+            **matrix = std::mem::take(*matrix).resize(index + 2, index + 2, 0.0);
+        }
+        debug_assert_eq!(self.check_all(vectors, matrices), Ok(()));
+        index
+    }
+
+    /*
+     * takes `id`'s entries out of each of `vectors` and its rows and columns out
+     * of each of `matrices`, and moves the landmarks after it up; the index it
+     * had, or None if it isn't mapped
+     */
+    pub fn remove(&mut self, id: usize, vectors: &mut [&mut DVector<f32>], matrices: &mut [&mut DMatrix<f32>]) -> Option<usize> {
+        let index = self.indices.remove(&id)?;
+        for other in self.indices.values_mut() {
+            if *other > index { *other -= 2; }
+        }
+
+        for vector in vectors.iter_mut() {
+            **vector = std::mem::take(*vector).remove_rows(index, 2);
+        }
+        for matrix in matrices.iter_mut() {
+            **matrix = std::mem::take(*matrix).remove_rows(index, 2).remove_columns(index, 2);
+        }
+        debug_assert_eq!(self.check_all(vectors, matrices), Ok(()));
+        Some(index)
+    }
+
+    /*
+     * whether a state of `size` entries fits the map: every landmark owns its
+     * own pair of entries, and the pairs fill the state after the pose with no gaps
+     */
+    pub fn check(&self, size: usize) -> Result<(), String> {
+        if size != self.state_size() {
+            return Err(format!("state size {} does not match {} landmarks", size, self.len()));
+        }
+        let indices = self.in_state_order();
+        if let Some((slot, &(_, index))) = indices.iter().enumerate().find(|&(slot, &(_, index))| index != Self::POSE_SIZE + 2 * slot) {
+            return Err(format!("landmark index {} where {} belongs in a state of {}", index, Self::POSE_SIZE + 2 * slot, size));
+        }
+        Ok(())
+    }
+
+    /*
+     * `check` of a square matrix over the state, like its covariance
+     */
+    pub fn check_matrix(&self, matrix: &DMatrix<f32>) -> Result<(), String> {
+        if !matrix.is_square() {
+            return Err(format!("{}x{} matrix for a state of {}", matrix.nrows(), matrix.ncols(), self.state_size()));
+        }
+        self.check(matrix.nrows())
+    }
+
+    fn check_all(&self, vectors: &[&mut DVector<f32>], matrices: &[&mut DMatrix<f32>]) -> Result<(), String> {
+        vectors.iter().try_for_each(|vector| self.check(vector.nrows()))?;
+        matrices.iter().try_for_each(|matrix| self.check_matrix(matrix))
+    }
+}
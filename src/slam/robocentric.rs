@@ -0,0 +1,272 @@
+use nalgebra::{DMatrix, DVector, Matrix2, Matrix2x3, Matrix3, Matrix3x2, Vector2};
+use macroquad::prelude::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::Observation;
+use crate::config::Config;
+use crate::math::{Pose2, normalize_angle};
+use crate::slam::{LandmarkRegistry, SlamFilter, linalg};
+
+/*
+ * robocentric EKF-SLAM (Castellanos et al., 2007): the state holds the world
+ * origin and the landmarks expressed in the current robot frame, so the robot
+ * itself is always at the origin. Motion moves everything else and measurements
+ * depend on a single landmark only, which keeps the linearization errors behind
+ * world-centric EKF inconsistency small
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RobocentricEkf {
+    pub state: DVector<f32>, // world origin x, y, heading, then landmark x, y pairs, all in the robot frame
+    pub covariance: DMatrix<f32>,
+    pub observed_landmarks: LandmarkRegistry,
+}
+
+fn rotation(angle: f32) -> Matrix2<f32> {
+    Matrix2::new(
+        angle.cos(), -angle.sin(),
+        angle.sin(), angle.cos()
+    )
+}
+
+/*
+ * derivative of `rotation` with respect to the angle
+ */
+fn rotation_derivative(angle: f32) -> Matrix2<f32> {
+    Matrix2::new(
+        -angle.sin(), -angle.cos(),
+        angle.cos(), -angle.sin()
+    )
+}
+
+/*
+ * inverse of a planar pose, (x, y, theta) -> (-R(-theta) (x, y), -theta), and its jacobian;
+ * maps the robot pose in the world to the world origin in the robot frame and back
+ */
+fn inverse_pose(pose: Pose2) -> (Pose2, Matrix3<f32>) {
+    let translation = Vector2::new(pose.x, pose.y);
+    let position_by_angle = rotation_derivative(-pose.theta) * translation;
+    let r = rotation(-pose.theta);
+
+    let jacobian = Matrix3::new(
+        -r[(0, 0)], -r[(0, 1)], position_by_angle[0],
+        -r[(1, 0)], -r[(1, 1)], position_by_angle[1],
+        0.0, 0.0, -1.0
+    );
+
+    (pose.inverse(), jacobian)
+}
+
+impl RobocentricEkf {
+    pub const COLOR: Color = Color::new(1.0, 0.85, 0.0, 0.5);
+
+    pub fn new() -> Self {
+        Self::with_prior(Pose2::IDENTITY, 0.1, 0.1)
+    }
+
+    /*
+     * same world-frame prior as `EkfSlam::with_prior`, moved into the robot frame
+     */
+    pub fn with_prior(pose: Pose2, stdev_position: f32, stdev_heading: f32) -> Self {
+        let (Pose2 { x, y, theta }, jacobian) = inverse_pose(pose);
+        let prior = Matrix3::from_diagonal(&nalgebra::Vector3::new(stdev_position.powi(2), stdev_position.powi(2), stdev_heading.powi(2)));
+        let covariance = jacobian * prior * jacobian.transpose();
+
+        Self {
+            state: DVector::from_column_slice(&[x, y, theta]),
+            covariance: DMatrix::from_fn(3, 3, |i, j| covariance[(i, j)]),
+            observed_landmarks: LandmarkRegistry::new(),
+        }
+    }
+
+    /*
+     * state indices of every point in the robot frame: the world origin and the
+     * landmarks, in state order so the rounding doesn't follow the HashMap's order
+     */
+    fn point_indices(&self) -> Vec<usize> {
+        std::iter::once(0).chain(self.observed_landmarks.in_state_order().into_iter().map(|(_, index)| index)).collect()
+    }
+
+    /*
+     * a new landmark's position in the robot frame comes from the measurement alone,
+     * so it starts uncorrelated with the rest of the state
+     */
+    fn initialize_landmark(&mut self, observation: &Observation, cfg: &Config) {
+        let (sin, cos) = observation.bearing.sin_cos();
+
+        let index = self.observed_landmarks.insert(observation.id, &mut [&mut self.state], &mut [&mut self.covariance]);
+        self.state[index] = observation.range * cos;
+        self.state[index + 1] = observation.range * sin;
+
+        // jacobian of landmark position with respect to observation
+        let g_y = Matrix2::new(
+            cos, -observation.range * sin,
+            sin, observation.range * cos
+        );
+        let r = Matrix2::new(
+            cfg.est_stdev_range.powi(2), 0.0,
+            0.0, cfg.est_stdev_bearing.powi(2)
+        );
+
+        self.covariance.fixed_view_mut::<2, 2>(index, index).copy_from(&(g_y * r * g_y.transpose()));
+    }
+
+    /*
+     * range and bearing depend only on the landmark's own robot-frame position
+     */
+    fn correct_landmark(&mut self, observation: &Observation, landmark_index: usize, cfg: &Config) {
+        let landmark = self.state.fixed_rows::<2>(landmark_index).into_owned();
+        let distance_sq = landmark.norm_squared().max(1e-6);
+        let distance = distance_sq.sqrt();
+
+        let bearing_difference = observation.bearing - f32::atan2(landmark[1], landmark[0]);
+        let z = Vector2::new(
+            observation.range - distance,
+            normalize_angle(bearing_difference)
+        );
+
+        let h_l = Matrix2::new(
+            landmark[0] / distance, landmark[1] / distance,
+            -landmark[1] / distance_sq, landmark[0] / distance_sq
+        );
+        let r = Matrix2::new(
+            cfg.est_stdev_range.powi(2), 0.0,
+            0.0, cfg.est_stdev_bearing.powi(2)
+        );
+
+        let p_ll = self.covariance.fixed_view::<2, 2>(landmark_index, landmark_index);
+        let innovation_covariance = h_l * p_ll * h_l.transpose() + r;
+        let Some(innovation_inverse) = innovation_covariance.try_inverse() else { return; };
+
+        let p_ht = self.covariance.columns(landmark_index, 2) * h_l.transpose();
+        let k = &p_ht * innovation_inverse;
+
+        self.state += &k * z;
+
+        // Joseph form, as in the world-centric filter
+        let hp_t = p_ht.clone();
+        linalg::rank2_update(&mut self.covariance, -1.0, &k, &hp_t);
+        let a_ht = self.covariance.columns(landmark_index, 2) * h_l.transpose();
+        linalg::rank2_update(&mut self.covariance, -1.0, &a_ht, &k);
+        linalg::rank2_update(&mut self.covariance, 1.0, &(&k * r), &k);
+        linalg::symmetrize(&mut self.covariance);
+    }
+}
+
+impl SlamFilter for RobocentricEkf {
+    /*
+     * the robot moves by u in its old frame, so every point p becomes R(-u_theta) (p - u_xy)
+     * and the world origin's heading drops by u_theta
+     */
+    fn predict(&mut self, linear_velocity: f32, angular_velocity: f32, delta_time: f32, cfg: &Config) {
+        let (dir_x, dir_y) = cfg.filter_integrator.direction(0.0, angular_velocity, delta_time);
+        let Pose2 { x: motion_x, y: motion_y, .. } = cfg.filter_integrator.step(Pose2::IDENTITY, linear_velocity, angular_velocity, delta_time);
+        let motion_theta = angular_velocity * delta_time;
+        let motion = Vector2::new(motion_x, motion_y);
+
+        let r = rotation(-motion_theta);
+        let r_derivative = -rotation_derivative(-motion_theta);
+
+        // jacobian of each point with respect to the motion, before moving anything
+        let size = self.state.nrows();
+        let mut f_u = DMatrix::zeros(size, 3);
+        for index in self.point_indices() {
+            let point = self.state.fixed_rows::<2>(index) - motion;
+            let by_motion = Matrix2x3::from_columns(&[-r.column(0), -r.column(1), r_derivative * point]);
+            f_u.fixed_view_mut::<2, 3>(index, 0).copy_from(&by_motion);
+
+            let moved = r * point;
+            self.state.fixed_rows_mut::<2>(index).copy_from(&moved);
+        }
+        f_u[(2, 2)] = -1.0;
+        self.state[2] -= motion_theta;
+        self.state[2] = normalize_angle(self.state[2]);
+
+        // the state jacobian rotates every point block and leaves the world heading alone
+        for index in self.point_indices() {
+            let rows = r * self.covariance.rows(index, 2);
+            self.covariance.rows_mut(index, 2).copy_from(&rows);
+            let columns = self.covariance.columns(index, 2) * r.transpose();
+            self.covariance.columns_mut(index, 2).copy_from(&columns);
+        }
+
+        // control noise, as in the world-centric filter
+        let sigma_linear_velocity = cfg.est_stdev_linear * linear_velocity.abs() + 0.01;
+        let sigma_angular_velocity = cfg.est_stdev_angular * angular_velocity.abs() + 0.01;
+        let f_n = Matrix3x2::new(
+            dir_x * delta_time, 0.0,
+            dir_y * delta_time, 0.0,
+            0.0, delta_time
+        );
+        let motion_covariance = f_n * Matrix2::new(
+            sigma_linear_velocity.powi(2), 0.0,
+            0.0, sigma_angular_velocity.powi(2)
+        ) * f_n.transpose();
+
+        self.covariance += &f_u * motion_covariance * f_u.transpose();
+        linalg::symmetrize(&mut self.covariance);
+    }
+
+    fn update(&mut self, observations: &[Observation], cfg: &Config) {
+        for observation in observations.iter() {
+            match self.observed_landmarks.get(observation.id) {
+                Some(landmark_index) => {
+                    self.correct_landmark(observation, landmark_index, cfg);
+                }
+                None if observation.range > cfg.max_init_range => {}
+                None => {
+                    self.initialize_landmark(observation, cfg);
+                }
+            }
+        }
+    }
+
+    fn estimated_pose(&self) -> Pose2 {
+        inverse_pose(Pose2::new(self.state[0], self.state[1], self.state[2])).0
+    }
+
+    fn pose_covariance(&self) -> Matrix3<f32> {
+        let (_, jacobian) = inverse_pose(Pose2::new(self.state[0], self.state[1], self.state[2]));
+        jacobian * self.covariance.fixed_view::<3, 3>(0, 0) * jacobian.transpose()
+    }
+
+    /*
+     * landmarks are moved into the world frame through the estimated world origin
+     */
+    fn estimated_map(&self) -> Vec<(usize, f32, f32)> {
+        let r = rotation(-self.state[2]);
+        let origin = self.state.fixed_rows::<2>(0);
+
+        let mut landmarks: Vec<_> = self.observed_landmarks.iter()
+            .map(|(id, index)| {
+                let world = r * (self.state.fixed_rows::<2>(index) - origin);
+                (id, world[0], world[1])
+            })
+            .collect();
+        landmarks.sort_by_key(|&(id, _, _)| id);
+        landmarks
+    }
+
+    fn landmark_covariance(&self, id: usize) -> Option<Matrix2<f32>> {
+        let index = self.observed_landmarks.get(id)?;
+        let theta = self.state[2];
+        let r = rotation(-theta);
+        let relative = self.state.fixed_rows::<2>(index) - self.state.fixed_rows::<2>(0);
+
+        // jacobian with respect to the world origin (x, y, heading) and the landmark
+        let by_origin = Matrix2x3::from_columns(&[-r.column(0), -r.column(1), -rotation_derivative(-theta) * relative]);
+        let mut jacobian = nalgebra::Matrix2x5::zeros();
+        jacobian.fixed_view_mut::<2, 3>(0, 0).copy_from(&by_origin);
+        jacobian.fixed_view_mut::<2, 2>(0, 3).copy_from(&r);
+
+        let indices = [0, 1, 2, index, index + 1];
+        let covariance = nalgebra::Matrix5::from_fn(|i, j| self.covariance[(indices[i], indices[j])]);
+        Some(jacobian * covariance * jacobian.transpose())
+    }
+
+    fn color(&self) -> Color {
+        Self::COLOR
+    }
+    fn snapshot(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+}
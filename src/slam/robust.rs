@@ -0,0 +1,28 @@
+/*
+ * how a measurement update weighs an innovation by its size; heavy tails and
+ * clutter make a plain Gaussian update trust gross errors as much as good readings
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RobustKernel {
+    Gaussian,   // every innovation at full weight
+    Huber(f32), // quadratic up to this many standard deviations, linear beyond
+    Dcs(f32),   // dynamic covariance scaling (Agarwal et al., 2013) with this phi
+}
+
+impl RobustKernel {
+    /*
+     * factor in (0, 1] to scale the measurement information by, from the innovation's
+     * squared Mahalanobis distance; the update divides R by it
+     */
+    pub fn weight(&self, squared_distance: f32) -> f32 {
+        match *self {
+            RobustKernel::Gaussian => 1.0,
+            RobustKernel::Huber(threshold) => {
+                let distance = squared_distance.max(0.0).sqrt();
+                if distance <= threshold { 1.0 } else { threshold / distance }
+            }
+            // DCS scales the residual by s, so the information by s^2
+            RobustKernel::Dcs(phi) => (2.0 * phi / (phi + squared_distance.max(0.0))).min(1.0).powi(2),
+        }
+    }
+}
@@ -0,0 +1,344 @@
+use nalgebra::{DMatrix, DVector, Matrix2, Matrix2x3, Matrix3, Matrix3x2, Vector2, Vector3};
+use macroquad::prelude::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::Observation;
+use crate::config::Config;
+use crate::math::{Pose2, normalize_angle};
+use crate::slam::{LandmarkRegistry, SlamFilter};
+
+/*
+ * Sparse Extended Information Filter, following Thrun et al., Probabilistic
+ * Robotics, chapter 12: the belief is kept as an information matrix and vector,
+ * the robot stays linked to at most `seif_max_active` landmarks, and the mean is
+ * recovered approximately by relaxation instead of inverting the matrix
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SeifSlam {
+    pub information: DMatrix<f32>,       // omega
+    pub information_vector: DVector<f32>, // xi
+    pub mean: DVector<f32>,              // mu; the heading is left unwrapped so xi stays consistent
+    pub observed_landmarks: LandmarkRegistry,
+    pub active: Vec<usize>, // ids of landmarks linked to the robot, least recently seen first
+}
+
+impl SeifSlam {
+    pub const COLOR: Color = Color::new(0.7, 0.3, 1.0, 0.5);
+
+    pub fn new() -> Self {
+        Self::with_prior(Pose2::IDENTITY, 0.1, 0.1)
+    }
+
+    /*
+     * same prior as `EkfSlam::with_prior`, held as its inverse
+     */
+    pub fn with_prior(pose: Pose2, stdev_position: f32, stdev_heading: f32) -> Self {
+        let mean = DVector::from_column_slice(&[pose.x, pose.y, pose.theta]);
+        let information = DMatrix::from_diagonal(&DVector::from_vec(vec![
+            stdev_position.powi(-2), stdev_position.powi(-2), stdev_heading.powi(-2)
+        ]));
+
+        Self {
+            information_vector: &information * &mean,
+            information,
+            mean,
+            observed_landmarks: LandmarkRegistry::new(),
+            active: Vec::new(),
+        }
+    }
+
+    /*
+     * covariance columns for `indices`, solved from the information matrix
+     */
+    fn covariance_columns(&self, indices: &[usize]) -> Option<DMatrix<f32>> {
+        let cholesky = self.information.clone().cholesky()?;
+        let mut selection = DMatrix::zeros(self.mean.nrows(), indices.len());
+        for (column, &index) in indices.iter().enumerate() {
+            selection[(index, column)] = 1.0;
+        }
+        Some(cholesky.solve(&selection))
+    }
+
+    /*
+     * grows the state with a landmark placed by inverting the observation; it
+     * carries no information until the measurement that created it is applied;
+     * its index in the state
+     */
+    fn add_landmark(&mut self, observation: &Observation) -> usize {
+        let (x, y) = self.estimated_pose().from_range_bearing(observation.range, observation.bearing);
+
+        let index = self.observed_landmarks.insert(
+            observation.id,
+            &mut [&mut self.mean, &mut self.information_vector],
+            &mut [&mut self.information],
+        );
+        self.mean[index] = x;
+        self.mean[index + 1] = y;
+        index
+    }
+
+    /*
+     * adds one range-bearing measurement's information (Table 12.2, lines 7-14)
+     */
+    fn incorporate(&mut self, observation: &Observation, landmark_index: usize, cfg: &Config) {
+        let (robot_x, robot_y) = (self.mean[0], self.mean[1]);
+        let (landmark_x, landmark_y) = (self.mean[landmark_index], self.mean[landmark_index + 1]);
+
+        let (predicted_range, predicted_bearing) = self.estimated_pose().range_bearing((landmark_x, landmark_y));
+        let bearing_difference = observation.bearing - predicted_bearing;
+        let innovation = Vector2::new(
+            observation.range - predicted_range,
+            normalize_angle(bearing_difference)
+        );
+
+        let distance_x = landmark_x - robot_x;
+        let distance_y = landmark_y - robot_y;
+        let distance_sq = (distance_x * distance_x + distance_y * distance_y).max(1e-6);
+        let distance = distance_sq.sqrt();
+
+        // jacobians with respect to robot and landmark
+        let h_r = Matrix2x3::new(
+            -distance_x / distance, -distance_y / distance, 0.0,
+            distance_y / distance_sq, -distance_x / distance_sq, -1.0
+        );
+        let h_l = Matrix2::new(
+            distance_x / distance, distance_y / distance,
+            -distance_y / distance_sq, distance_x / distance_sq
+        );
+
+        let q_inverse = Matrix2::new(
+            1.0 / cfg.est_stdev_range.powi(2), 0.0,
+            0.0, 1.0 / cfg.est_stdev_bearing.powi(2)
+        );
+
+        // xi += H^T Q^-1 (z - z_hat + H mu), omega += H^T Q^-1 H, touching only the two blocks
+        let h_mu = h_r * self.mean.fixed_rows::<3>(0) + h_l * self.mean.fixed_rows::<2>(landmark_index);
+        let weighted = q_inverse * (innovation + h_mu);
+        let mut xi_robot = self.information_vector.fixed_rows_mut::<3>(0);
+        xi_robot += h_r.transpose() * weighted;
+        let mut xi_landmark = self.information_vector.fixed_rows_mut::<2>(landmark_index);
+        xi_landmark += h_l.transpose() * weighted;
+
+        let robot_robot = h_r.transpose() * q_inverse * h_r;
+        let robot_landmark = h_r.transpose() * q_inverse * h_l;
+        let landmark_landmark = h_l.transpose() * q_inverse * h_l;
+        let mut block = self.information.fixed_view_mut::<3, 3>(0, 0);
+        block += robot_robot;
+        let mut block = self.information.fixed_view_mut::<3, 2>(0, landmark_index);
+        block += robot_landmark;
+        let mut block = self.information.fixed_view_mut::<2, 3>(landmark_index, 0);
+        block += robot_landmark.transpose();
+        let mut block = self.information.fixed_view_mut::<2, 2>(landmark_index, landmark_index);
+        block += landmark_landmark;
+    }
+
+    /*
+     * Gauss-Seidel sweeps over the robot and landmark blocks of omega mu = xi
+     * (Table 12.4); cheap, but only approximately converged
+     */
+    fn recover_mean(&mut self, sweeps: usize) {
+        // in state order, so the result doesn't depend on the HashMap's order
+        let mut blocks = vec![(0, 3)];
+        blocks.extend(self.observed_landmarks.in_state_order().into_iter().map(|(_, index)| (index, 2)));
+
+        for _ in 0..sweeps {
+            for &(start, size) in &blocks {
+                let residual = self.information_vector.rows(start, size) - self.information.rows(start, size) * &self.mean;
+                let Some(block_inverse) = self.information.view((start, start), (size, size)).into_owned().try_inverse() else { continue; };
+                let step = block_inverse * residual;
+                let mut mean = self.mean.rows_mut(start, size);
+                mean += step;
+            }
+        }
+    }
+
+    /*
+     * deactivates the least recently seen landmarks beyond the active limit by
+     * removing their links to the robot (Table 12.3)
+     */
+    fn sparsify(&mut self, observed_now: &[usize], cfg: &Config) {
+        if self.active.len() <= cfg.seif_max_active { return; }
+
+        let excess = self.active.len() - cfg.seif_max_active;
+        let deactivated: Vec<usize> = self.active.iter()
+            .filter(|id| !observed_now.contains(id))
+            .take(excess)
+            .copied()
+            .collect();
+        if deactivated.is_empty() { return; }
+        self.active.retain(|id| !deactivated.contains(id));
+
+        let indices_of = |ids: &[usize]| -> Vec<usize> {
+            ids.iter().flat_map(|id| { let index = self.observed_landmarks.get(*id).expect("active landmarks are mapped"); [index, index + 1] }).collect()
+        };
+        let robot = vec![0, 1, 2];
+        let m0 = indices_of(&deactivated);
+        let m_plus = indices_of(&self.active);
+
+        // omega restricted to the robot, the active, and the deactivated landmarks
+        let kept: Vec<usize> = robot.iter().chain(&m_plus).chain(&m0).copied().collect();
+        let mut omega_0 = DMatrix::zeros(self.information.nrows(), self.information.ncols());
+        for &row in &kept {
+            for &column in &kept {
+                omega_0[(row, column)] = self.information[(row, column)];
+            }
+        }
+
+        let robot_and_m0: Vec<usize> = robot.iter().chain(&m0).copied().collect();
+        let (Some(term_m0), Some(term_robot_m0), Some(term_robot)) = (
+            schur_term(&omega_0, &m0),
+            schur_term(&omega_0, &robot_and_m0),
+            schur_term(&self.information, &robot),
+        ) else { return; };
+
+        // the robot-m0 links cancel exactly in theory; clear the f32 residue so they stay structural zeros
+        let mut sparsified = &self.information - term_m0 + term_robot_m0 - term_robot;
+        for &row in &robot {
+            for &column in &m0 {
+                sparsified[(row, column)] = 0.0;
+                sparsified[(column, row)] = 0.0;
+            }
+        }
+        self.information_vector += (&sparsified - &self.information) * &self.mean;
+        self.information = sparsified;
+    }
+}
+
+/*
+ * omega[:, s] omega[s, s]^-1 omega[s, :] for the index set s
+ */
+fn schur_term(omega: &DMatrix<f32>, indices: &[usize]) -> Option<DMatrix<f32>> {
+    let columns = omega.select_columns(indices);
+    let block = columns.select_rows(indices);
+    let block_inverse = block.try_inverse()?;
+    Some(&columns * block_inverse * columns.transpose())
+}
+
+impl SlamFilter for SeifSlam {
+    /*
+     * motion update in information form (Table 12.2, lines 2-6); the prediction
+     * only couples the robot block with whatever it is already linked to
+     */
+    fn predict(&mut self, linear_velocity: f32, angular_velocity: f32, delta_time: f32, cfg: &Config) {
+        let size = self.mean.nrows();
+        let (dir_x, dir_y) = cfg.filter_integrator.direction(self.mean[2], angular_velocity, delta_time);
+
+        // heading change is taken straight from the controls so the mean stays unwrapped
+        let Pose2 { x, y, .. } = cfg.filter_integrator.step(Pose2::new(self.mean[0], self.mean[1], self.mean[2]), linear_velocity, angular_velocity, delta_time);
+        let motion = Vector3::new(x - self.mean[0], y - self.mean[1], angular_velocity * delta_time);
+
+        // the motion jacobian is I + delta with delta nonzero only at (0, 2) and (1, 2);
+        // delta squares to zero, so psi = (I + delta)^-1 - I = -delta
+        let psi = (linear_velocity * delta_time * dir_y, -linear_velocity * delta_time * dir_x);
+
+        // process noise in state space, as in the EKF
+        let sigma_linear_velocity = cfg.est_stdev_linear * linear_velocity.abs() + 0.01;
+        let sigma_angular_velocity = cfg.est_stdev_angular * angular_velocity.abs() + 0.01;
+        let f_n = Matrix3x2::new(
+            dir_x * delta_time, 0.0,
+            dir_y * delta_time, 0.0,
+            0.0, delta_time
+        );
+        let process_noise = f_n * Matrix2::new(
+            sigma_linear_velocity.powi(2), 0.0,
+            0.0, sigma_angular_velocity.powi(2)
+        ) * f_n.transpose();
+
+        // lambda = psi^T omega + omega psi + psi^T omega psi; omega psi is a single column
+        let omega_psi = self.information.column(0) * psi.0 + self.information.column(1) * psi.1;
+        let mut lambda = DMatrix::zeros(size, size);
+        lambda.column_mut(2).copy_from(&omega_psi);
+        let mut row = lambda.row_mut(2);
+        row += omega_psi.transpose();
+        lambda[(2, 2)] += psi.0 * omega_psi[0] + psi.1 * omega_psi[1];
+        let phi = &self.information + &lambda;
+
+        // kappa = phi Fx^T R (I + Fx phi Fx^T R)^-1 Fx phi; this form tolerates the singular R
+        let phi_robot_columns = phi.columns(0, 3).into_owned();
+        let Some(inner_inverse) = (Matrix3::identity() + phi.fixed_view::<3, 3>(0, 0) * process_noise).try_inverse() else { return; };
+        let kappa = &phi_robot_columns * (process_noise * inner_inverse) * phi_robot_columns.transpose();
+
+        let information = phi - &kappa;
+        self.information_vector += (lambda - kappa) * &self.mean + information.columns(0, 3) * motion;
+        self.information = information;
+
+        let mut pose = self.mean.fixed_rows_mut::<3>(0);
+        pose += motion;
+    }
+
+    /*
+     * measurement update, then mean recovery and sparsification (Table 12.1)
+     */
+    fn update(&mut self, observations: &[Observation], cfg: &Config) {
+        let mut observed_now = Vec::new();
+
+        for observation in observations {
+            let landmark_index = match self.observed_landmarks.get(observation.id) {
+                Some(index) => index,
+                None if observation.range > cfg.max_init_range => continue,
+                None => self.add_landmark(observation),
+            };
+
+            self.incorporate(observation, landmark_index, cfg);
+
+            // most recently seen landmarks go to the back of the active list
+            self.active.retain(|&id| id != observation.id);
+            self.active.push(observation.id);
+            observed_now.push(observation.id);
+        }
+
+        self.recover_mean(cfg.seif_recovery_sweeps);
+        self.sparsify(&observed_now, cfg);
+    }
+
+    fn estimated_pose(&self) -> Pose2 {
+        Pose2::new(self.mean[0], self.mean[1], normalize_angle(self.mean[2]))
+    }
+
+    fn pose_covariance(&self) -> Matrix3<f32> {
+        self.covariance_columns(&[0, 1, 2])
+            .map(|columns| columns.fixed_view::<3, 3>(0, 0).into_owned())
+            .unwrap_or_else(Matrix3::zeros)
+    }
+
+    fn estimated_map(&self) -> Vec<(usize, f32, f32)> {
+        let mut landmarks: Vec<_> = self.observed_landmarks.iter()
+            .map(|(id, index)| (id, self.mean[index], self.mean[index + 1]))
+            .collect();
+        landmarks.sort_by_key(|&(id, _, _)| id);
+        landmarks
+    }
+
+    fn landmark_covariance(&self, id: usize) -> Option<Matrix2<f32>> {
+        let index = self.observed_landmarks.get(id)?;
+        let columns = self.covariance_columns(&[index, index + 1])?;
+        Some(columns.fixed_view::<2, 2>(index, 0).into_owned())
+    }
+
+    fn color(&self) -> Color {
+        Self::COLOR
+    }
+
+    /*
+     * whether the information matrix links two state blocks, for the sparsity view;
+     * blocks are the robot followed by the landmarks in state order
+     */
+    fn link_pattern(&self) -> Option<(Vec<usize>, Vec<Vec<bool>>)> {
+        let landmarks: Vec<(usize, usize)> = self.observed_landmarks.in_state_order().into_iter().map(|(id, index)| (index, id)).collect();
+
+        let mut blocks = vec![(0, 3)];
+        blocks.extend(landmarks.iter().map(|&(index, _)| (index, 2)));
+
+        let pattern = blocks.iter().map(|&(row, rows)| {
+            blocks.iter().map(|&(column, columns)| {
+                self.information.view((row, column), (rows, columns)).iter().any(|value| value.abs() > 1e-6)
+            }).collect()
+        }).collect();
+
+        Some((landmarks.into_iter().map(|(_, id)| id).collect(), pattern))
+    }
+
+    fn snapshot(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+}
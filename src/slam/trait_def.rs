@@ -1,12 +1,43 @@
 use crate::simulation::Observation;
 use crate::config::Config;
+use crate::math::Pose2;
+use crate::slam::EkfSlam;
+use crate::slam::association::Hypothesis;
+use crate::slam::fast::Particle;
 use macroquad::color::Color;
+use nalgebra::{Matrix2, Matrix3};
 
-pub trait Slam {
+// Send + Sync so estimates can be read from rayon workers
+pub trait SlamFilter: Send + Sync {
     fn predict(&mut self, linear_velocity: f32, angular_velocity: f32, delta_time: f32, cfg: &Config);
     fn update(&mut self, observations: &[Observation], cfg: &Config);
-    fn get_state(&self) -> (f32, f32, f32);
-    fn get_landmarks(&self) -> Vec<(usize, f32, f32)>;
+    fn estimated_pose(&self) -> Pose2;
+    fn pose_covariance(&self) -> Matrix3<f32>;
+    fn estimated_map(&self) -> Vec<(usize, f32, f32)>;
+    fn landmark_covariance(&self, id: usize) -> Option<Matrix2<f32>>;
     fn color(&self) -> Color;
-}
 
+    /*
+     * the EKF behind the filter, for the tools that edit its covariance directly
+     */
+    fn as_ekf(&self) -> Option<&EkfSlam> { None }
+    fn as_ekf_mut(&mut self) -> Option<&mut EkfSlam> { None }
+
+    // the particle cloud, empty for the Gaussian filters
+    fn particles(&self) -> &[Particle] { &[] }
+
+    // the particle whose map is the likeliest single one
+    fn best_particle(&self) -> Option<&Particle> { None }
+
+    // the ways of explaining the measurements, most likely first; empty without data association
+    fn hypotheses(&self) -> &[Hypothesis] { &[] }
+
+    /*
+     * the landmark ids in state order and whether the information matrix links
+     * each pair of state blocks, the robot first; None for moment-form filters
+     */
+    fn link_pattern(&self) -> Option<(Vec<usize>, Vec<Vec<bool>>)> { None }
+
+    // the filter as JSON for `Backend::restore`, or None if it can't be saved
+    fn snapshot(&self) -> Option<serde_json::Value> { None }
+}
@@ -0,0 +1,294 @@
+use nalgebra::{DMatrix, DVector, Matrix2, Matrix3, SMatrix, SVector, Vector2, Vector3};
+use macroquad::prelude::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::{Observation, sensor};
+use crate::config::Config;
+use crate::math::{Pose2, normalize_angle};
+use crate::slam::{LandmarkRegistry, SlamFilter, linalg};
+
+/*
+ * sigma points of a D-dimensional Gaussian and their weights for the mean and
+ * the covariance (the scaled unscented transform, Wan and van der Merwe)
+ */
+struct SigmaPoints<const D: usize> {
+    points: Vec<SVector<f32, D>>,
+    mean_weights: Vec<f32>,
+    covariance_weights: Vec<f32>,
+}
+
+impl<const D: usize> SigmaPoints<D> {
+    fn new(mean: &SVector<f32, D>, covariance: &SMatrix<f32, D, D>, cfg: &Config) -> Self {
+        let dimension = D as f32;
+        let lambda = cfg.ukf_alpha.powi(2) * (dimension + cfg.ukf_kappa) - dimension;
+        // a covariance that lost definiteness to rounding gets a little jitter
+        let scaled = (dimension + lambda) * covariance;
+        let root = scaled.cholesky()
+            .or_else(|| (scaled + SMatrix::<f32, D, D>::identity() * 1e-6).cholesky())
+            .map_or_else(SMatrix::<f32, D, D>::zeros, |cholesky| cholesky.l());
+
+        let mut points = vec![*mean];
+        for column in root.column_iter() {
+            points.push(mean + column);
+            points.push(mean - column);
+        }
+        let weight = 0.5 / (dimension + lambda);
+        let mut mean_weights = vec![weight; 2 * D + 1];
+        let mut covariance_weights = mean_weights.clone();
+        mean_weights[0] = lambda / (dimension + lambda);
+        covariance_weights[0] = mean_weights[0] + 1.0 - cfg.ukf_alpha.powi(2) + cfg.ukf_beta;
+        Self { points, mean_weights, covariance_weights }
+    }
+
+    /*
+     * the weighted mean of the transformed points, with the angle at `angle`
+     * (if any) averaged on the circle
+     */
+    fn mean<const E: usize>(&self, transformed: &[SVector<f32, E>], angle: Option<usize>) -> SVector<f32, E> {
+        let mut mean = SVector::<f32, E>::zeros();
+        let (mut sin, mut cos) = (0.0, 0.0);
+        for (point, &weight) in transformed.iter().zip(&self.mean_weights) {
+            mean += weight * point;
+            if let Some(angle) = angle {
+                sin += weight * point[angle].sin();
+                cos += weight * point[angle].cos();
+            }
+        }
+        if let Some(angle) = angle { mean[angle] = f32::atan2(sin, cos); }
+        mean
+    }
+
+    /*
+     * the weighted covariance between two transformed sets about their means,
+     * wrapping the angle differences
+     */
+    fn cross<const E: usize, const F: usize>(
+        &self,
+        a: (&[SVector<f32, E>], &SVector<f32, E>, Option<usize>),
+        b: (&[SVector<f32, F>], &SVector<f32, F>, Option<usize>),
+    ) -> SMatrix<f32, E, F> {
+        let mut covariance = SMatrix::<f32, E, F>::zeros();
+        for ((point_a, point_b), &weight) in a.0.iter().zip(b.0).zip(&self.covariance_weights) {
+            covariance += weight * wrapped(point_a - a.1, a.2) * wrapped(point_b - b.1, b.2).transpose();
+        }
+        covariance
+    }
+}
+
+/*
+ * UKF-SLAM: the EKF's state and covariance, but the motion model, the
+ * measurement model, and landmark initialization are pushed through sigma
+ * points instead of jacobians, so large heading uncertainty bends the
+ * predicted distributions instead of being linearized away. Only the pose
+ * (and, in a correction, the one landmark) is sampled; the rest of the map
+ * follows through its cross-covariance, keeping every step O(n^2)
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UkfSlam {
+    pub state: DVector<f32>,
+    pub covariance: DMatrix<f32>,
+    pub observed_landmarks: LandmarkRegistry,
+}
+
+impl UkfSlam {
+    pub const COLOR: Color = Color::new(0.4, 0.7, 1.0, 0.5);
+
+    pub fn new() -> Self {
+        Self::with_prior(Pose2::IDENTITY, 0.1, 0.1)
+    }
+
+    /*
+     * same prior as `EkfSlam::with_prior`
+     */
+    pub fn with_prior(pose: Pose2, stdev_position: f32, stdev_heading: f32) -> Self {
+        Self {
+            state: DVector::from_column_slice(&[pose.x, pose.y, pose.theta]),
+            covariance: DMatrix::from_diagonal(&DVector::from_vec(vec![
+                stdev_position.powi(2), stdev_position.powi(2), stdev_heading.powi(2)
+            ])),
+            observed_landmarks: LandmarkRegistry::new(),
+        }
+    }
+
+    fn pose(&self) -> Vector3<f32> {
+        self.state.fixed_rows::<3>(0).into_owned()
+    }
+
+    /*
+     * appends a landmark placed by pushing the pose and the measurement noise
+     * through the inverse measurement model; its correlation with the rest of
+     * the state goes through the pose
+     */
+    fn initialize_landmark(&mut self, observation: &Observation, readings: usize, cfg: &Config) {
+        let p_rr = self.covariance.fixed_view::<3, 3>(0, 0).into_owned();
+        let mut augmented_covariance = SMatrix::<f32, 5, 5>::zeros();
+        augmented_covariance.fixed_view_mut::<3, 3>(0, 0).copy_from(&p_rr);
+        augmented_covariance.fixed_view_mut::<2, 2>(3, 3).copy_from(&measurement_noise(readings, cfg));
+        let pose = self.pose();
+        let mean = SVector::<f32, 5>::new(pose[0], pose[1], pose[2], 0.0, 0.0);
+
+        let sigma = SigmaPoints::new(&mean, &augmented_covariance, cfg);
+        let landmarks: Vec<Vector2<f32>> = sigma.points.iter()
+            .map(|point| {
+                let (x, y) = Pose2::new(point[0], point[1], point[2]).from_range_bearing(observation.range + point[3], observation.bearing + point[4]);
+                Vector2::new(x, y)
+            })
+            .collect();
+        let poses: Vec<Vector3<f32>> = sigma.points.iter().map(|point| point.fixed_rows::<3>(0).into_owned()).collect();
+        let landmark = sigma.mean(&landmarks, None);
+        let p_ll = sigma.cross((&landmarks, &landmark, None), (&landmarks, &landmark, None));
+        let p_lr = sigma.cross((&landmarks, &landmark, None), (&poses, &pose, Some(2)));
+
+        // cross-covariance with everything else, through the pose: P_lx = P_lr P_rr^-1 P_rx
+        let gain = p_lr * p_rr.try_inverse().unwrap_or_else(Matrix3::zeros);
+        let p_lx = gain * self.covariance.rows(0, 3);
+
+        let index = self.observed_landmarks.insert(observation.id, &mut [&mut self.state], &mut [&mut self.covariance]);
+        self.state.fixed_rows_mut::<2>(index).copy_from(&landmark);
+
+        self.covariance.view_mut((index, 0), (2, index)).copy_from(&p_lx);
+        self.covariance.view_mut((0, index), (index, 2)).copy_from(&p_lx.transpose());
+        self.covariance.fixed_view_mut::<2, 2>(index, index).copy_from(&p_ll);
+    }
+
+    /*
+     * the unscented correction with one measurement of a mapped landmark
+     */
+    fn correct_landmark(&mut self, observation: &Observation, landmark_index: usize, readings: usize, cfg: &Config) {
+        let indices = [0, 1, 2, landmark_index, landmark_index + 1];
+        let p_ss = SMatrix::<f32, 5, 5>::from_fn(|row, column| self.covariance[(indices[row], indices[column])]);
+        let mean = SVector::<f32, 5>::from_fn(|row, _| self.state[indices[row]]);
+
+        let sigma = SigmaPoints::new(&mean, &p_ss, cfg);
+        let measurements: Vec<Vector2<f32>> = sigma.points.iter()
+            .map(|point| {
+                let (range, bearing) = Pose2::new(point[0], point[1], point[2]).range_bearing((point[3], point[4]));
+                Vector2::new(range, bearing)
+            })
+            .collect();
+        let predicted = sigma.mean(&measurements, Some(1));
+        let p_zz = sigma.cross((&measurements, &predicted, Some(1)), (&measurements, &predicted, Some(1)));
+        let p_sz = sigma.cross((&sigma.points, &mean, Some(2)), (&measurements, &predicted, Some(1)));
+
+        let innovation = Vector2::new(observation.range - predicted[0], normalize_angle(observation.bearing - predicted[1]));
+
+        // a robust kernel inflates R for innovations that look like outliers, as in the EKF
+        let r = measurement_noise(readings, cfg);
+        let squared_distance = (p_zz + r).try_inverse().map_or(0.0, |inverse| (innovation.transpose() * inverse * innovation)[(0, 0)]);
+        let s = p_zz + r / cfg.landmark_kernel.weight(squared_distance);
+        let Some(s_inverse) = s.try_inverse() else { return; };
+
+        // cross-covariance of the whole state with the measurement, through the sampled block
+        let Some(p_ss_inverse) = p_ss.try_inverse() else { return; };
+        let size = self.state.nrows();
+        let p_xs = DMatrix::from_fn(size, 5, |row, column| self.covariance[(row, indices[column])]);
+        let p_xz = p_xs * (p_ss_inverse * p_sz);
+        let k = &p_xz * s_inverse;
+
+        self.state += &k * innovation;
+        self.state[2] = normalize_angle(self.state[2]);
+
+        // P -= K S K^T, as the rank-2 update K (S K^T)
+        let s_k = (&k * s).into_owned();
+        linalg::rank2_update(&mut self.covariance, -1.0, &s_k, &k);
+        linalg::symmetrize(&mut self.covariance);
+    }
+}
+
+impl SlamFilter for UkfSlam {
+    /*
+     * pushes the pose and the control noise through the motion model; the map
+     * keeps its cross-covariance with the pose through the statistical
+     * linearization P_r'r P_rr^-1
+     */
+    fn predict(&mut self, linear_velocity: f32, angular_velocity: f32, delta_time: f32, cfg: &Config) {
+        let p_rr = self.covariance.fixed_view::<3, 3>(0, 0).into_owned();
+        let sigma_linear_velocity = cfg.est_stdev_linear * linear_velocity.abs() + 0.01;
+        let sigma_angular_velocity = cfg.est_stdev_angular * angular_velocity.abs() + 0.01;
+        let mut augmented_covariance = SMatrix::<f32, 5, 5>::zeros();
+        augmented_covariance.fixed_view_mut::<3, 3>(0, 0).copy_from(&p_rr);
+        augmented_covariance[(3, 3)] = sigma_linear_velocity.powi(2);
+        augmented_covariance[(4, 4)] = sigma_angular_velocity.powi(2);
+        let pose = self.pose();
+        let mean = SVector::<f32, 5>::new(pose[0], pose[1], pose[2], 0.0, 0.0);
+
+        let sigma = SigmaPoints::new(&mean, &augmented_covariance, cfg);
+        let moved: Vec<Vector3<f32>> = sigma.points.iter()
+            .map(|point| {
+                let Pose2 { x, y, theta } = cfg.filter_integrator.step(
+                    Pose2::new(point[0], point[1], point[2]),
+                    linear_velocity + point[3],
+                    angular_velocity + point[4],
+                    delta_time
+                );
+                Vector3::new(x, y, theta)
+            })
+            .collect();
+        let poses: Vec<Vector3<f32>> = sigma.points.iter().map(|point| point.fixed_rows::<3>(0).into_owned()).collect();
+        let new_pose = sigma.mean(&moved, Some(2));
+        let new_p_rr = sigma.cross((&moved, &new_pose, Some(2)), (&moved, &new_pose, Some(2)));
+        let transition = sigma.cross((&moved, &new_pose, Some(2)), (&poses, &pose, Some(2))) * p_rr.try_inverse().unwrap_or_else(Matrix3::identity);
+
+        self.state.fixed_rows_mut::<3>(0).copy_from(&new_pose);
+        self.covariance.fixed_view_mut::<3, 3>(0, 0).copy_from(&new_p_rr);
+        let map_size = self.state.nrows() - 3;
+        if map_size > 0 {
+            let p_rm = transition * self.covariance.view((0, 3), (3, map_size));
+            self.covariance.view_mut((0, 3), (3, map_size)).copy_from(&p_rm);
+            self.covariance.view_mut((3, 0), (map_size, 3)).copy_from(&p_rm.transpose());
+        }
+    }
+
+    fn update(&mut self, observations: &[Observation], cfg: &Config) {
+        for (observation, readings) in sensor::compress(observations) {
+            match self.observed_landmarks.get(observation.id) {
+                Some(landmark_index) => self.correct_landmark(&observation, landmark_index, readings, cfg),
+                None if observation.range > cfg.max_init_range => {}
+                None => {
+                    self.initialize_landmark(&observation, readings, cfg);
+                }
+            }
+        }
+    }
+
+    fn estimated_pose(&self) -> Pose2 {
+        Pose2::new(self.state[0], self.state[1], self.state[2])
+    }
+
+    fn pose_covariance(&self) -> Matrix3<f32> {
+        self.covariance.fixed_view::<3, 3>(0, 0).into_owned()
+    }
+
+    fn estimated_map(&self) -> Vec<(usize, f32, f32)> {
+        let mut landmarks: Vec<_> = self.observed_landmarks.iter()
+            .map(|(id, index)| (id, self.state[index], self.state[index + 1]))
+            .collect();
+        landmarks.sort_by_key(|&(id, _, _)| id);
+        landmarks
+    }
+
+    fn landmark_covariance(&self, id: usize) -> Option<Matrix2<f32>> {
+        self.observed_landmarks
+            .get(id)
+            .map(|index| self.covariance.fixed_view::<2, 2>(index, index).into_owned())
+    }
+
+    fn color(&self) -> Color {
+        Self::COLOR
+    }
+    fn snapshot(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+}
+
+/*
+ * the configured sensor noise of a measurement averaged from `readings` readings
+ */
+fn measurement_noise(readings: usize, cfg: &Config) -> Matrix2<f32> {
+    Matrix2::new(cfg.est_stdev_range.powi(2), 0.0, 0.0, cfg.est_stdev_bearing.powi(2)) / readings as f32
+}
+
+fn wrapped<const E: usize>(mut deviation: SVector<f32, E>, angle: Option<usize>) -> SVector<f32, E> {
+    if let Some(angle) = angle { deviation[angle] = normalize_angle(deviation[angle]); }
+    deviation
+}
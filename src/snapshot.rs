@@ -0,0 +1,27 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use serde::{Serialize, de::DeserializeOwned};
+
+/*
+ * shared JSON serialization path for filter snapshots, recordings, and
+ * anything else that leaves the process
+ */
+pub fn to_json<T: Serialize>(value: &T) -> io::Result<String> {
+    serde_json::to_string(value).map_err(io::Error::other)
+}
+
+pub fn from_json<T: DeserializeOwned>(json: &str) -> io::Result<T> {
+    serde_json::from_str(json).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+pub fn save<T: Serialize>(value: &T, path: &Path) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, to_json(value)?)
+}
+
+pub fn load<T: DeserializeOwned>(path: &Path) -> io::Result<T> {
+    from_json(&fs::read_to_string(path)?)
+}
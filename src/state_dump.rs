@@ -0,0 +1,56 @@
+use nalgebra::{DMatrix, DVector};
+
+use crate::slam::EkfSlam;
+
+/*
+ * a value as a Rust float literal that reads back to the same f32; Debug keeps
+ * the decimal point and every digit needed
+ */
+fn literal(value: f32) -> String {
+    if value.is_nan() { "f32::NAN".to_string() }
+    else if value == f32::INFINITY { "f32::INFINITY".to_string() }
+    else if value == f32::NEG_INFINITY { "f32::NEG_INFINITY".to_string() }
+    else { format!("{value:?}") }
+}
+
+fn row(values: impl Iterator<Item = f32>) -> String {
+    values.map(literal).collect::<Vec<_>>().join(", ")
+}
+
+/*
+ * the EKF's state vector and covariance as nalgebra literals, ready to paste
+ * into a test or to read with a script. With a `landmark` only the pose and that
+ * landmark are dumped, i.e. the joint block of the two; None when the landmark
+ * isn't mapped
+ */
+pub fn dump(ekf: &EkfSlam, landmark: Option<usize>) -> Option<String> {
+    // (label, first index, size) of each block dumped, in state order
+    let mut blocks = vec![("pose".to_string(), 0, 3)];
+    match landmark {
+        Some(id) => blocks.push((format!("landmark {id}"), ekf.observed_landmarks.get(id)?, 2)),
+        None => {
+            let landmarks = ekf.observed_landmarks.in_state_order();
+            blocks.extend(landmarks.into_iter().map(|(id, index)| (format!("landmark {id}"), index, 2)));
+        }
+    }
+    let indices: Vec<usize> = blocks.iter().flat_map(|&(_, start, size)| start..start + size).collect();
+    let n = indices.len();
+    let state = DVector::from_iterator(n, indices.iter().map(|&i| ekf.state[i]));
+    let covariance = DMatrix::from_fn(n, n, |r, c| ekf.covariance[(indices[r], indices[c])]);
+
+    let mut text = format!("// EKF-SLAM state, {n} of {} entries\n", ekf.state.len());
+    text += "let state = DVector::<f32>::from_row_slice(&[\n";
+    let mut offset = 0;
+    for (label, _, size) in &blocks {
+        text += &format!("    {}, // {label}\n", row(state.rows(offset, *size).iter().copied()));
+        offset += size;
+    }
+    text += "]);\n";
+
+    text += &format!("let covariance = DMatrix::<f32>::from_row_slice({n}, {n}, &[\n");
+    for r in 0..n {
+        text += &format!("    {},\n", row(covariance.row(r).iter().copied()));
+    }
+    text += "]);\n";
+    Some(text)
+}
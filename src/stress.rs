@@ -0,0 +1,225 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use macroquad::prelude::{Rect, rand};
+
+use crate::config::Config;
+use crate::scenario::{self, ControlSegment, Criteria, RunReport, Scenario};
+use crate::simulation::Landmark;
+use crate::slam::SlamFilter;
+
+const SPEED: f32 = 40.0;
+
+/*
+ * worlds built to make a filter fail: the landmarks are there, but they can't
+ * be told apart or are too few to lean on
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StressKind {
+    AliasedCorridor, // evenly spaced landmarks down both walls, driven out and back
+    SymmetricRoom,   // a walled room whose landmarks look the same from four sides
+    SparseDesert,    // a wide loop with hardly any landmarks near it
+}
+
+impl StressKind {
+    pub const ALL: [StressKind; 3] = [StressKind::AliasedCorridor, StressKind::SymmetricRoom, StressKind::SparseDesert];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StressKind::AliasedCorridor => "aliased corridor",
+            StressKind::SymmetricRoom => "symmetric room",
+            StressKind::SparseDesert => "sparse desert",
+        }
+    }
+
+    // spaces written as underscores or dashes, like `Scenario::by_name`
+    pub fn by_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name.replace(['_', '-'], " "))
+    }
+
+    /*
+     * the scenario at `difficulty` from 0 (easy) to 1 (as adversarial as it
+     * gets); `seed` only moves the landmarks around, so one seed at growing
+     * difficulties is one world getting harder
+     */
+    pub fn generate(&self, difficulty: f32, seed: u64) -> Scenario {
+        let difficulty = difficulty.clamp(0.0, 1.0);
+        rand::srand(seed);
+        let mut scenario = match self {
+            StressKind::AliasedCorridor => aliased_corridor(difficulty),
+            StressKind::SymmetricRoom => symmetric_room(difficulty),
+            StressKind::SparseDesert => sparse_desert(difficulty),
+        };
+        scenario.name = format!("{} {difficulty:.2}", self.name());
+        scenario
+    }
+}
+
+fn straight(distance: f32) -> ControlSegment {
+    ControlSegment { duration: distance / SPEED, linear: SPEED, angular: 0.0 }
+}
+
+// left turn on the spot, give or take a robot radius
+fn turn(angle: f32) -> ControlSegment {
+    ControlSegment { duration: angle, linear: 10.0, angular: 1.0 }
+}
+
+fn jitter(amount: f32) -> f32 {
+    if amount > 0.0 { rand::gen_range(-amount, amount) } else { 0.0 }
+}
+
+/*
+ * landmarks every `spacing` along both walls, closer and longer with
+ * difficulty; below full difficulty they are nudged off the grid, which is
+ * what tells one from the next
+ */
+fn aliased_corridor(difficulty: f32) -> Scenario {
+    let length = 600.0 + 1400.0 * difficulty;
+    let spacing = 90.0 - 50.0 * difficulty;
+    let half_width = 70.0;
+    let nudge = 0.3 * spacing * (1.0 - difficulty);
+
+    let mut landmarks = Vec::new();
+    for k in 0..=(length / spacing) as usize {
+        for side in [-1.0, 1.0] {
+            let id = landmarks.len();
+            landmarks.push(Landmark::new(id, k as f32 * spacing + jitter(nudge), side * half_width + jitter(nudge)));
+        }
+    }
+
+    Scenario {
+        name: String::new(),
+        landmarks,
+        obstructions: Vec::new(),
+        boundary: Some(Rect::new(-100.0, -half_width - 80.0, length + 200.0, 2.0 * half_width + 160.0)),
+        dropout_zones: Vec::new(),
+        controls: vec![straight(length), turn(PI), straight(length)],
+        landmark_events: Vec::new(),
+        checkpoints: Vec::new(),
+        criteria: Criteria::default(),
+        robots: Vec::new(),
+    }
+}
+
+/*
+ * a few landmarks in one quarter of a square room, repeated at every quarter
+ * turn around its center; the robot laps the room, more often with
+ * difficulty, and the copies drift apart less
+ */
+fn symmetric_room(difficulty: f32) -> Scenario {
+    let half_size = 220.0;
+    let center = (120.0, 120.0);
+    let nudge = 25.0 * (1.0 - difficulty);
+
+    let pattern: Vec<(f32, f32)> = (0..3)
+        .map(|_| (rand::gen_range(40.0, 190.0), rand::gen_range(40.0, 190.0)))
+        .collect();
+    let mut landmarks = Vec::new();
+    for quarter in 0..4 {
+        let (sin, cos) = (quarter as f32 * FRAC_PI_2).sin_cos();
+        for &(x, y) in &pattern {
+            let id = landmarks.len();
+            landmarks.push(Landmark::new(id, center.0 + x * cos - y * sin + jitter(nudge), center.1 + x * sin + y * cos + jitter(nudge)));
+        }
+    }
+
+    // out from the corner of the lap, then around it
+    let laps = 1 + (2.0 * difficulty).round() as usize;
+    let mut controls = Vec::new();
+    for _ in 0..4 * laps {
+        controls.push(straight(240.0));
+        controls.push(turn(FRAC_PI_2));
+    }
+
+    Scenario {
+        name: String::new(),
+        landmarks,
+        obstructions: Vec::new(),
+        boundary: Some(Rect::new(center.0 - half_size, center.1 - half_size, 2.0 * half_size, 2.0 * half_size)),
+        dropout_zones: Vec::new(),
+        controls,
+        landmark_events: Vec::new(),
+        checkpoints: Vec::new(),
+        criteria: Criteria::default(),
+        robots: Vec::new(),
+    }
+}
+
+/*
+ * one big square lap through open ground, with fewer landmarks scattered
+ * around it the harder it gets, down to two
+ */
+fn sparse_desert(difficulty: f32) -> Scenario {
+    let side = 800.0;
+    let count = 2 + (28.0 * (1.0 - difficulty)).round() as usize;
+
+    let landmarks = (0..count)
+        .map(|id| {
+            let angle = rand::gen_range(0.0, TAU);
+            let radius = rand::gen_range(0.0, 0.75 * side);
+            Landmark::new(id, 0.5 * side + radius * angle.cos(), 0.5 * side + radius * angle.sin())
+        })
+        .collect();
+
+    let mut controls = Vec::new();
+    for _ in 0..4 {
+        controls.push(straight(side));
+        controls.push(turn(FRAC_PI_2));
+    }
+
+    Scenario {
+        name: String::new(),
+        landmarks,
+        obstructions: Vec::new(),
+        boundary: None,
+        dropout_zones: Vec::new(),
+        controls,
+        landmark_events: Vec::new(),
+        checkpoints: Vec::new(),
+        criteria: Criteria::default(),
+        robots: Vec::new(),
+    }
+}
+
+pub struct StressPoint {
+    pub difficulty: f32,
+    pub report: RunReport,
+}
+
+impl StressPoint {
+    /*
+     * whether the filter has lost it here: a non-finite estimate, or one off by
+     * more than `stress_max_ate` on average. The map doesn't count, since the
+     * sparsest worlds can keep most landmarks out of sensor range for good
+     */
+    pub fn broken(&self, cfg: &Config) -> bool {
+        !self.report.ate.is_finite() || self.report.ate > cfg.stress_max_ate
+    }
+}
+
+/*
+ * runs a fresh filter from `make_filter` through `kind` at `steps` evenly
+ * spaced difficulties from 0 to 1, all from the same seed
+ */
+pub fn characterize(
+    kind: StressKind,
+    mut make_filter: impl FnMut() -> Box<dyn SlamFilter>,
+    steps: usize,
+    seed: u64,
+    cfg: &Config,
+) -> Vec<StressPoint> {
+    (0..steps)
+        .map(|step| {
+            let difficulty = if steps > 1 { step as f32 / (steps - 1) as f32 } else { 0.0 };
+            let scenario = kind.generate(difficulty, seed);
+            let report = scenario::run(&scenario, make_filter().as_mut(), seed, cfg);
+            StressPoint { difficulty, report }
+        })
+        .collect()
+}
+
+/*
+ * the easiest difficulty the filter broke down at, if any
+ */
+pub fn breaking_point(points: &[StressPoint], cfg: &Config) -> Option<f32> {
+    points.iter().find(|point| point.broken(cfg)).map(|point| point.difficulty)
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::metrics;
+use crate::simulation::{Landmark, Observation, Robot};
+use crate::slam::{OdometryBaseline, SlamFilter};
+
+/*
+ * the takeaway of one run, shown when it ends and exportable as JSON
+ */
+#[derive(Clone, Debug, Serialize)]
+pub struct SessionSummary {
+    pub duration: f32,
+    pub distance_traveled: f32,
+    pub landmarks_mapped: usize,
+    pub landmarks_total: usize,
+    pub map_rmse: f32, // after matching estimates to true landmarks by position
+    pub unmatched_landmarks: usize,
+    pub spurious_landmarks: usize,
+    pub final_error: f32,
+    pub ate: f32, // RMSE of the position error over the run
+    pub odometry_ate: f32, // the same for dead reckoning alone, what the filter is up against
+    pub loop_closures: usize,
+    pub mean_nees: f32,
+    pub peak_covariance_trace: f32, // of the position covariance
+}
+
+impl SessionSummary {
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+}
+
+/*
+ * running totals for a `SessionSummary`. A loop closure is a landmark seen
+ * again after going unseen for `loop_closure_gap` seconds
+ */
+pub struct SessionStats {
+    time: f32,
+    distance_traveled: f32,
+    last_position: Option<(f32, f32)>,
+    squared_error_sum: f32,
+    odometry_squared_error_sum: f32,
+    frames: usize,
+    final_error: f32,
+    nees_sum: f32,
+    nees_count: usize,
+    peak_covariance_trace: f32,
+    last_seen: HashMap<usize, f32>,
+    loop_closures: usize,
+}
+
+impl SessionStats {
+    pub fn new() -> Self {
+        Self {
+            time: 0.0,
+            distance_traveled: 0.0,
+            last_position: None,
+            squared_error_sum: 0.0,
+            odometry_squared_error_sum: 0.0,
+            frames: 0,
+            final_error: 0.0,
+            nees_sum: 0.0,
+            nees_count: 0,
+            peak_covariance_trace: 0.0,
+            last_seen: HashMap::new(),
+            loop_closures: 0,
+        }
+    }
+
+    pub fn record<S: SlamFilter + ?Sized>(&mut self, time: f32, robot: &Robot, slam: &S, odometry: &OdometryBaseline, observations: &[Observation], cfg: &Config) {
+        self.time = time;
+        if let Some((x, y)) = self.last_position {
+            self.distance_traveled += (robot.x - x).hypot(robot.y - y);
+        }
+        self.last_position = Some((robot.x, robot.y));
+
+        self.final_error = metrics::position_error(slam, (robot.x, robot.y));
+        self.squared_error_sum += self.final_error.powi(2);
+        self.odometry_squared_error_sum += metrics::position_error(odometry, (robot.x, robot.y)).powi(2);
+        self.frames += 1;
+        if let Some(nees) = metrics::pose_nees(slam, robot.pose()) {
+            self.nees_sum += nees;
+            self.nees_count += 1;
+        }
+        let trace = slam.pose_covariance().fixed_view::<2, 2>(0, 0).trace();
+        self.peak_covariance_trace = self.peak_covariance_trace.max(trace);
+
+        for observation in observations {
+            if let Some(last) = self.last_seen.insert(observation.id, time)
+                && time - last > cfg.loop_closure_gap
+            {
+                self.loop_closures += 1;
+            }
+        }
+    }
+
+    pub fn summary<S: SlamFilter + ?Sized>(&self, slam: &S, landmarks: &[Landmark], cfg: &Config) -> SessionSummary {
+        let estimated = slam.estimated_map();
+        let truth: Vec<_> = landmarks.iter().map(|landmark| (landmark.id, landmark.x, landmark.y)).collect();
+        let map = metrics::map_quality(&estimated, &truth, cfg.map_match_gate);
+        SessionSummary {
+            duration: self.time,
+            distance_traveled: self.distance_traveled,
+            landmarks_mapped: estimated.len(),
+            landmarks_total: landmarks.len(),
+            map_rmse: map.rmse,
+            unmatched_landmarks: map.unmatched,
+            spurious_landmarks: map.spurious,
+            final_error: self.final_error,
+            ate: (self.squared_error_sum / self.frames.max(1) as f32).sqrt(),
+            odometry_ate: (self.odometry_squared_error_sum / self.frames.max(1) as f32).sqrt(),
+            loop_closures: self.loop_closures,
+            mean_nees: self.nees_sum / self.nees_count.max(1) as f32,
+            peak_covariance_trace: self.peak_covariance_trace,
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use macroquad::prelude::{Rect, Vec2, rand};
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::Landmark;
+use crate::snapshot;
+
+pub const TEMPLATE_DIR: &str = "templates";
+
+/*
+ * a group of landmarks and obstructions stored relative to its own center,
+ * for copy/paste in the editor and reusable world pieces on disk
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Template {
+    pub landmarks: Vec<Landmark>,
+    pub obstructions: Vec<(f32, f32, f32, f32)>, // x, y, w, h
+}
+
+impl Template {
+    /*
+     * everything whose position (obstruction center) lies inside `area`
+     */
+    pub fn capture(area: Rect, landmarks: &[Landmark], obstructions: &[Rect]) -> Self {
+        let origin = area.center();
+
+        let landmarks = landmarks.iter()
+            .filter(|landmark| area.contains(Vec2::new(landmark.x, landmark.y)))
+            .map(|landmark| Landmark { x: landmark.x - origin.x, y: landmark.y - origin.y, ..landmark.clone() })
+            .collect();
+
+        let obstructions = obstructions.iter()
+            .filter(|rect| area.contains(rect.center()))
+            .map(|rect| (rect.x - origin.x, rect.y - origin.y, rect.w, rect.h))
+            .collect();
+
+        Self { landmarks, obstructions }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.landmarks.is_empty() && self.obstructions.is_empty()
+    }
+
+    /*
+     * places the group centered on `at`; pasted landmarks get fresh ids after
+     * the largest existing one
+     */
+    pub fn paste(&self, at: Vec2, landmarks: &mut Vec<Landmark>, obstructions: &mut Vec<Rect>) {
+        let first_id = landmarks.iter().map(|landmark| landmark.id + 1).max().unwrap_or(0);
+
+        for (id, landmark) in (first_id..).zip(&self.landmarks) {
+            landmarks.push(Landmark { id, x: landmark.x + at.x, y: landmark.y + at.y, ..landmark.clone() });
+        }
+
+        for &(x, y, w, h) in &self.obstructions {
+            obstructions.push(Rect::new(x + at.x, y + at.y, w, h));
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        snapshot::save(self, path)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        snapshot::load(path)
+    }
+}
+
+/*
+ * sprinkles landmarks over `area`, `density` of them per 100 x 100 units, none
+ * closer than `separation` to another, old or new. Candidates are drawn
+ * uniformly and the crowded ones thrown away, so a separation too large for
+ * the density places fewer; returns how many were placed
+ */
+pub fn scatter(area: Rect, density: f32, separation: f32, landmarks: &mut Vec<Landmark>) -> usize {
+    let target = (density * area.w * area.h / 10_000.0).round() as usize;
+    let first_id = landmarks.iter().map(|landmark| landmark.id + 1).max().unwrap_or(0);
+    let mut placed = 0;
+
+    for _ in 0..30 * target {
+        if placed == target { break; }
+
+        let x = rand::gen_range(area.x, area.x + area.w);
+        let y = rand::gen_range(area.y, area.y + area.h);
+        let crowded = landmarks.iter().any(|landmark| (landmark.x - x).powi(2) + (landmark.y - y).powi(2) < separation.powi(2));
+        if !crowded {
+            landmarks.push(Landmark::new(first_id + placed, x, y));
+            placed += 1;
+        }
+    }
+    placed
+}
+
+/*
+ * saved templates in `dir`, sorted by file name
+ */
+pub fn list(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new(); };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .collect();
+    paths.sort();
+    paths
+}
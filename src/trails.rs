@@ -0,0 +1,128 @@
+use std::collections::VecDeque;
+use macroquad::prelude::Color;
+
+use crate::config::Config;
+use crate::math::Pose2;
+use crate::metrics::track_stdevs;
+use crate::simulation::Robot;
+use crate::slam::{OdometryBaseline, SlamFilter};
+
+/*
+ * recent trajectories that separate the sources of estimation error:
+ * - commanded: the noise-free motion model driven by the commands, from the true start
+ * - dead reckoning: the odometry baseline, the filter's prediction alone from its prior
+ * - the ground truth and the EKF estimate, to compare them against
+ * ground truth vs commanded is actuation noise, latency and contact; commanded
+ * vs dead reckoning is the prior and the filter's motion model; dead reckoning
+ * vs estimate is what the measurements corrected
+ */
+pub struct Trails {
+    pub ground_truth: VecDeque<(f32, f32)>,
+    pub estimate: VecDeque<(f32, f32)>,
+    pub dead_reckoning: VecDeque<(f32, f32)>,
+    pub commanded: VecDeque<(f32, f32)>,
+    commanded_pose: Pose2,
+    prev_commanded: (f32, f32),
+    capacity: usize,
+}
+
+impl Trails {
+    pub const GROUND_TRUTH_COLOR: Color = Color::new(0.0, 0.47, 0.95, 0.6);
+    pub const DEAD_RECKONING_COLOR: Color = OdometryBaseline::COLOR;
+    pub const COMMANDED_COLOR: Color = Color::new(0.8, 0.5, 1.0, 0.6);
+    pub const TAIL_OPACITY: f32 = 0.1;
+
+    /*
+     * starts the trails over, the commanded ghost at the true pose
+     */
+    pub fn new(true_pose: Pose2, capacity: usize) -> Self {
+        Self {
+            ground_truth: VecDeque::with_capacity(capacity),
+            estimate: VecDeque::with_capacity(capacity),
+            dead_reckoning: VecDeque::with_capacity(capacity),
+            commanded: VecDeque::with_capacity(capacity),
+            commanded_pose: true_pose,
+            prev_commanded: (0.0, 0.0),
+            capacity,
+        }
+    }
+
+    pub fn commanded_pose(&self) -> Pose2 {
+        self.commanded_pose
+    }
+
+    /*
+     * opacity of the `index`th of `len` trail points, fading from `TAIL_OPACITY`
+     * at the oldest to full at the newest
+     */
+    pub fn fade(index: usize, len: usize) -> f32 {
+        if len < 2 { return 1.0; }
+        Self::TAIL_OPACITY + (1.0 - Self::TAIL_OPACITY) * index as f32 / (len - 1) as f32
+    }
+
+    /*
+     * advances the commanded ghost by one frame of the robot's commands, then
+     * extends every trail
+     */
+    pub fn record(&mut self, robot: &Robot, dead_reckoning: Pose2, estimate: Pose2, delta_time: f32, cfg: &Config) {
+        // averaged over the frame, as the simulation moves the robot
+        let (linear, angular) = robot.commanded_velocity();
+        self.commanded_pose = cfg.sim_integrator.step(
+            self.commanded_pose,
+            0.5 * (linear + self.prev_commanded.0),
+            0.5 * (angular + self.prev_commanded.1),
+            delta_time
+        );
+        self.prev_commanded = (linear, angular);
+
+        let points = [
+            (&mut self.ground_truth, (robot.x, robot.y)),
+            (&mut self.estimate, estimate.position()),
+            (&mut self.dead_reckoning, dead_reckoning.position()),
+            (&mut self.commanded, self.commanded_pose.position()),
+        ];
+        for (trail, point) in points {
+            if trail.len() == self.capacity { trail.pop_front(); }
+            trail.push_back(point);
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TubeSample {
+    pub x: f32,
+    pub y: f32,
+    pub theta: f32,
+    pub cross: f32, // position stdev across the heading
+}
+
+/*
+ * the estimated trajectory with how unsure the filter was of it at the time:
+ * a sample every `tube_spacing` of travel, the last `tube_length` of them, each
+ * holding the cross-track stdev, so the tube drawn through them narrows where
+ * measurements pinned the pose down and widens through dead reckoning
+ */
+pub struct UncertaintyTube {
+    pub samples: VecDeque<TubeSample>,
+    capacity: usize,
+    spacing: f32,
+}
+
+impl UncertaintyTube {
+    pub fn new(cfg: &Config) -> Self {
+        Self { samples: VecDeque::with_capacity(cfg.tube_length), capacity: cfg.tube_length, spacing: cfg.tube_spacing }
+    }
+
+    /*
+     * adds the filter's pose once it is `spacing` from the last sample; turning
+     * on the spot adds nothing
+     */
+    pub fn record(&mut self, slam: &dyn SlamFilter) {
+        let Pose2 { x, y, theta } = slam.estimated_pose();
+        if let Some(last) = self.samples.back() && (x - last.x).hypot(y - last.y) < self.spacing {
+            return;
+        }
+        if self.samples.len() == self.capacity { self.samples.pop_front(); }
+        self.samples.push_back(TubeSample { x, y, theta, cross: track_stdevs(slam).cross });
+    }
+}
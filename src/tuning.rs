@@ -0,0 +1,201 @@
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+use macroquad::prelude::rand;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::metrics;
+use crate::recording::Recording;
+use crate::scenario::{self, Scenario};
+use crate::slam::EkfSlam;
+use crate::snapshot;
+use crate::utils::sample_normal;
+
+/*
+ * the EKF's belief noise, the part of `Config` the tuner searches over
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NoiseParameters {
+    pub est_stdev_linear: f32,
+    pub est_stdev_angular: f32,
+    pub est_stdev_range: f32,
+    pub est_stdev_bearing: f32,
+}
+
+impl NoiseParameters {
+    pub fn from_config(cfg: &Config) -> Self {
+        Self {
+            est_stdev_linear: cfg.est_stdev_linear,
+            est_stdev_angular: cfg.est_stdev_angular,
+            est_stdev_range: cfg.est_stdev_range,
+            est_stdev_bearing: cfg.est_stdev_bearing,
+        }
+    }
+
+    pub fn apply(&self, cfg: &Config) -> Config {
+        Config {
+            est_stdev_linear: self.est_stdev_linear,
+            est_stdev_angular: self.est_stdev_angular,
+            est_stdev_range: self.est_stdev_range,
+            est_stdev_bearing: self.est_stdev_bearing,
+            ..cfg.clone()
+        }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        snapshot::load(path)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        snapshot::save(self, path)
+    }
+
+    // the search runs on logarithms, so every step is relative and no stdev goes negative
+    fn to_log(self) -> [f32; 4] {
+        [self.est_stdev_linear, self.est_stdev_angular, self.est_stdev_range, self.est_stdev_bearing].map(f32::ln)
+    }
+
+    fn from_log(log: [f32; 4]) -> Self {
+        let [est_stdev_linear, est_stdev_angular, est_stdev_range, est_stdev_bearing] = log.map(f32::exp);
+        Self { est_stdev_linear, est_stdev_angular, est_stdev_range, est_stdev_bearing }
+    }
+}
+
+/*
+ * what the filter is tuned against: a scripted scenario, run once per seed, or
+ * a recording, whose inputs are replayed as they are
+ */
+pub enum TuningSource {
+    Scenario(Scenario),
+    Recording(String, Recording),
+}
+
+impl TuningSource {
+    /*
+     * a recording if the argument ends in .csv, otherwise a scenario name or file
+     */
+    pub fn resolve(name: &str) -> Result<Self, String> {
+        if name.ends_with(".csv") {
+            let recording = Recording::load(Path::new(name)).map_err(|error| format!("could not load recording {name}: {error}"))?;
+            if recording.inputs.is_empty() {
+                return Err(format!("{name} has no filter inputs; record it again to tune on it"));
+            }
+            return Ok(Self::Recording(name.to_string(), recording));
+        }
+        Scenario::resolve(name).map(Self::Scenario)
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Scenario(scenario) => &scenario.name,
+            Self::Recording(name, _) => name,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Score {
+    pub ate: f32, // mean over the runs
+    pub mean_nees: f32, // mean over the runs
+    pub cost: f32,
+}
+
+/*
+ * the ATE plus `tune_nees_weight` times how far the pose NEES strays from 3,
+ * its expected value for a consistent filter, in log terms so that over- and
+ * underconfidence by the same factor cost the same; a run that blows up costs
+ * infinitely much
+ */
+pub fn cost(ate: f32, mean_nees: f32, cfg: &Config) -> f32 {
+    let cost = ate + cfg.tune_nees_weight * (mean_nees / 3.0).ln().abs();
+    if cost.is_finite() { cost } else { f32::INFINITY }
+}
+
+/*
+ * the EKF's ATE and mean pose NEES on a recording, replayed from the
+ * estimator's recorded start
+ */
+fn replay_errors(recording: &Recording, cfg: &Config) -> (f32, f32) {
+    let (mut squared_error, mut nees, mut frames) = (0.0, 0.0, 0);
+    recording.replay_ekf(
+        cfg,
+        |_, _, _, _| true,
+        |_, frame, _, ekf| {
+            squared_error += metrics::position_error(ekf, frame.ground_truth.position()).powi(2);
+            nees += metrics::pose_nees(ekf, frame.ground_truth).unwrap_or(f32::INFINITY);
+            frames += 1;
+        },
+    );
+    let frames = frames.max(1) as f32;
+    ((squared_error / frames).sqrt(), nees / frames)
+}
+
+/*
+ * scores the noise parameters on every source: each scenario once per seed,
+ * each recording once
+ */
+pub fn evaluate(sources: &[TuningSource], parameters: &NoiseParameters, seeds: Range<u64>, cfg: &Config) -> Score {
+    let cfg = parameters.apply(cfg);
+    let (mut ate, mut mean_nees, mut runs) = (0.0, 0.0, 0);
+    for source in sources {
+        match source {
+            TuningSource::Scenario(scenario) => for seed in seeds.clone() {
+                let report = scenario::run(scenario, &mut EkfSlam::new(), seed, &cfg);
+                ate += report.ate;
+                mean_nees += report.mean_nees;
+                runs += 1;
+            },
+            TuningSource::Recording(_, recording) => {
+                let (recording_ate, recording_nees) = replay_errors(recording, &cfg);
+                ate += recording_ate;
+                mean_nees += recording_nees;
+                runs += 1;
+            }
+        }
+    }
+    let runs = runs.max(1) as f32;
+    let (ate, mean_nees) = (ate / runs, mean_nees / runs);
+    Score { ate, mean_nees, cost: cost(ate, mean_nees, &cfg) }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TuningResult {
+    pub initial: NoiseParameters,
+    pub initial_score: Score,
+    pub best: NoiseParameters,
+    pub best_score: Score,
+    pub history: Vec<f32>, // the best cost after each iteration
+}
+
+/*
+ * a (1+1) evolution strategy, the simplest relative of CMA-ES: each iteration
+ * perturbs the best parameters so far by a random relative step and keeps the
+ * candidate if it scores better. The step grows after a success and shrinks
+ * after a failure (the one-fifth rule), so it settles to the scale the cost
+ * changes on. Candidates are drawn from `tune_seed`, so a tuning run repeats
+ */
+pub fn tune(sources: &[TuningSource], cfg: &Config) -> TuningResult {
+    let seeds = 0..cfg.tune_seeds;
+    let initial = NoiseParameters::from_config(cfg);
+    let initial_score = evaluate(sources, &initial, seeds.clone(), cfg);
+
+    let (mut best, mut best_score) = (initial, initial_score);
+    let mut step = cfg.tune_initial_step;
+    let mut history = Vec::with_capacity(cfg.tune_iterations);
+    for iteration in 0..cfg.tune_iterations {
+        // the runs reseed the global generator, so the search keeps its own stream
+        rand::srand(cfg.tune_seed.wrapping_add(iteration as u64));
+        let candidate = NoiseParameters::from_log(best.to_log().map(|log| log + sample_normal(0.0, step)));
+
+        let score = evaluate(sources, &candidate, seeds.clone(), cfg);
+        if score.cost < best_score.cost {
+            (best, best_score) = (candidate, score);
+            step *= 1.5;
+        } else {
+            step *= 1.5f32.powf(-0.25);
+        }
+        history.push(best_score.cost);
+    }
+    TuningResult { initial, initial_score, best, best_score, history }
+}
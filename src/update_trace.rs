@@ -0,0 +1,61 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::recording::Recording;
+use crate::slam::CorrectionTrace;
+use crate::snapshot;
+
+/*
+ * a traced correction with when it happened, on the recording's clock: `frame`
+ * counts the recording's frames, so corrections of the same update share it
+ */
+#[derive(Clone, Debug, Serialize)]
+pub struct TraceEntry {
+    pub time: f32,
+    pub frame: usize,
+    #[serde(flatten)]
+    pub correction: CorrectionTrace,
+}
+
+/*
+ * replays the recording's inputs through an EKF from its recorded start and
+ * traces every landmark correction of the frames whose recorded time falls in
+ * [`start`, `end`] seconds
+ */
+pub fn trace_recording(recording: &Recording, start: f32, end: f32, cfg: &Config) -> Result<Vec<TraceEntry>, String> {
+    if recording.inputs.is_empty() {
+        return Err("the recording has no filter inputs; record it again to trace it".to_string());
+    }
+
+    let mut entries = Vec::new();
+    recording.replay_ekf(
+        cfg,
+        |_, frame, _, ekf| {
+            ekf.trace = (frame.time >= start).then(Vec::new);
+            frame.time <= end
+        },
+        |index, frame, _, ekf| {
+            let corrections = ekf.trace.take().unwrap_or_default();
+            entries.extend(corrections.into_iter().map(|correction| TraceEntry { time: frame.time, frame: index, correction }));
+        },
+    );
+    Ok(entries)
+}
+
+/*
+ * the entries as JSON lines, one correction per line
+ */
+pub fn save(entries: &[TraceEntry], path: &Path) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut text = String::new();
+    for entry in entries {
+        text += &snapshot::to_json(entry)?;
+        text.push('\n');
+    }
+    fs::write(path, text)
+}
@@ -14,33 +14,28 @@ pub fn sample_normal(mean: f32, std_dev: f32) -> f32 {
 }
 
 /*
- * helper that converts relative position of landmark (range and bearing)
- * to absolute (x, y) coordinates
+ * distance along a ray (origin, unit direction) to the first intersection
+ * with a rectangle, using the slab method
  */
-pub fn relative_to_absolute(robot_x: f32, robot_y: f32, robot_theta: f32, range: f32, bearing: f32) -> (f32, f32) {
-    let absolute_angle = robot_theta + bearing;
-
-    let x = robot_x + range * absolute_angle.cos();
-    let y = robot_y + range * absolute_angle.sin();
-
-    (x, y)
-}
-
-/*
- * helper that converts absolute position of landmark (x and y) to
- * tuple of form (range, bearing)
- */
-pub fn absolute_to_relative(robot_x: f32, robot_y: f32, robot_theta: f32, landmark_x: f32, landmark_y: f32) -> (f32, f32) {
-    
-    // distance to landmark
-    let distance_x = landmark_x - robot_x;
-    let distance_y = landmark_y - robot_y;
-    let range = (distance_x * distance_x + distance_y * distance_y).sqrt();
-    
-    // calculate relative angle
-    let absolute_angle = f32::atan2(distance_y, distance_x);
-    let mut bearing = absolute_angle - robot_theta;
-    bearing = f32::atan2(bearing.sin(), bearing.cos()); // normalize to (-PI, PI]
-
-    (range, bearing)
+pub fn ray_rect_distance(origin_x: f32, origin_y: f32, dir_x: f32, dir_y: f32, rect: &Rect) -> Option<f32> {
+    let mut t_min = f32::NEG_INFINITY;
+    let mut t_max = f32::INFINITY;
+
+    for (origin, dir, low, high) in [
+        (origin_x, dir_x, rect.x, rect.x + rect.w),
+        (origin_y, dir_y, rect.y, rect.y + rect.h)
+    ] {
+        if dir.abs() < 1e-9 {
+            if origin < low || origin > high { return None; }
+        } else {
+            let t1 = (low - origin) / dir;
+            let t2 = (high - origin) / dir;
+            t_min = t_min.max(t1.min(t2));
+            t_max = t_max.min(t1.max(t2));
+        }
+    }
+
+    if t_max < t_min.max(0.0) { return None; }
+
+    Some(t_min.max(0.0))
 }
@@ -0,0 +1,106 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use macroquad::prelude::Rect;
+
+use crate::config::Config;
+use crate::math::Pose2;
+use crate::recording::Recording;
+
+pub const VIDEO_DIR: &str = "videos";
+
+/*
+ * what an exported video looks like: its frame size in pixels, independent of
+ * the window, and its frame rate
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VideoSettings {
+    pub width: u32,
+    pub height: u32,
+    pub fps: f32,
+}
+
+impl VideoSettings {
+    /*
+     * the configured defaults, overridden by `--size <width>x<height>` and
+     * `--fps <rate>` anywhere in `args`
+     */
+    pub fn from_args(args: &[String], cfg: &Config) -> Result<Self, String> {
+        let mut settings = Self { width: cfg.video_width, height: cfg.video_height, fps: cfg.video_fps };
+
+        let value = |flag: &str| args.iter().position(|arg| arg == flag).map(|i| args.get(i + 1).ok_or(format!("{flag} needs a value")));
+        if let Some(size) = value("--size") {
+            let size = size?;
+            let (width, height) = size.split_once('x').ok_or(format!("bad size {size}, expected e.g. 1920x1080"))?;
+            settings.width = width.parse().map_err(|_| format!("bad width {width}"))?;
+            settings.height = height.parse().map_err(|_| format!("bad height {height}"))?;
+        }
+        if let Some(fps) = value("--fps") {
+            let fps = fps?;
+            settings.fps = fps.parse().map_err(|_| format!("bad frame rate {fps}"))?;
+        }
+
+        if settings.width == 0 || settings.height == 0 || settings.fps.is_nan() || settings.fps <= 0.0 {
+            return Err("the size and frame rate must be positive".to_string());
+        }
+        Ok(settings)
+    }
+}
+
+/*
+ * for each video frame, at a fixed 1/fps apart from the first recorded frame,
+ * the index of the recorded frame on screen then: the last one at or before
+ * the frame's time. A recorded frame is never shown early, and the video lasts
+ * exactly as long as the recording whatever the frame times were
+ */
+pub fn frame_schedule(times: &[f32], fps: f32) -> Vec<usize> {
+    let (Some(&first), Some(&last)) = (times.first(), times.last()) else { return Vec::new(); };
+    let frames = ((last - first) * fps + 1e-3).floor() as usize + 1;
+
+    (0..frames)
+        .map(|frame| {
+            let time = first + frame as f32 / fps;
+            // slack for frame times that fall a rounding error short of the grid
+            times.partition_point(|&recorded| recorded <= time + 1e-4).max(1) - 1
+        })
+        .collect()
+}
+
+/*
+ * the area every recorded pose lies in, grown by `margin` on all sides
+ */
+pub fn world_bounds(recording: &Recording, margin: f32) -> Rect {
+    let points = recording.frames.iter()
+        .flat_map(|frame| std::iter::once(frame.ground_truth).chain(frame.estimates.iter().copied()))
+        .filter(|pose| pose.x.is_finite() && pose.y.is_finite());
+
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (f32::INFINITY, f32::INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
+    for Pose2 { x, y, .. } in points {
+        min_x = min_x.min(x);
+        min_y = min_y.min(y);
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+    }
+    if !min_x.is_finite() { return Rect::new(-margin, -margin, 2.0 * margin, 2.0 * margin); }
+
+    Rect::new(min_x - margin, min_y - margin, max_x - min_x + 2.0 * margin, max_y - min_y + 2.0 * margin)
+}
+
+pub fn frame_path(dir: &Path, frame: usize) -> PathBuf {
+    dir.join(format!("frame_{frame:05}.png"))
+}
+
+/*
+ * encodes the image sequence in `dir` into an H.264 MP4 with ffmpeg, if it is
+ * installed; the frames are kept either way
+ */
+pub fn encode_mp4(dir: &Path, fps: f32, output: &Path) -> Result<(), String> {
+    let status = Command::new("ffmpeg")
+        .args(["-y", "-loglevel", "error", "-framerate", &fps.to_string(), "-i"])
+        .arg(dir.join("frame_%05d.png"))
+        .args(["-c:v", "libx264", "-pix_fmt", "yuv420p", "-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2"])
+        .arg(output)
+        .status()
+        .map_err(|error| format!("could not run ffmpeg: {error}"))?;
+
+    if status.success() { Ok(()) } else { Err(format!("ffmpeg failed ({status})")) }
+}
@@ -0,0 +1,140 @@
+use slam_simulator::config::Config;
+use slam_simulator::scenario::{self, Scenario};
+use slam_simulator::simulation::{Actuator, Feasibility, Robot, World};
+use slam_simulator::slam::EkfSlam;
+
+const DELTA_TIME: f32 = 1.0 / 60.0;
+
+#[test]
+fn latency_delays_commands_without_losing_them() {
+    let mut cfg = Config::default();
+    cfg.control_latency = 0.25;
+
+    let mut actuator = Actuator::default();
+    let mut executed = 0.0;
+    for frame in 0..60 {
+        let commanded = if frame == 0 { (6.0, 0.0) } else { (0.0, 0.0) };
+        let (linear, _) = actuator.step(commanded, DELTA_TIME, &cfg);
+        if (frame as f32) * DELTA_TIME < cfg.control_latency - 1e-3 {
+            assert_eq!(linear, 0.0, "frame {frame} executed early");
+        }
+        executed += linear;
+    }
+    assert!((executed - 6.0).abs() < 1e-3, "executed {executed}");
+}
+
+#[test]
+fn command_rate_holds_commands_between_samples() {
+    let mut cfg = Config::default();
+    cfg.command_rate = 10.0;
+
+    let mut actuator = Actuator::default();
+    let executed: Vec<f32> = (0..60).map(|_| actuator.step((1.0, 0.5), DELTA_TIME, &cfg).0).collect();
+
+    // nothing until the first sample at 0.1 s, then the sampled acceleration is held
+    assert!(executed[..5].iter().all(|&linear| linear == 0.0), "{executed:?}");
+    assert!(executed[5..].iter().all(|&linear| (linear - 1.0).abs() < 1e-3), "{executed:?}");
+}
+
+#[test]
+fn commanded_velocity_leads_executed_under_latency() {
+    let mut cfg = Config::default();
+    let drive = |cfg: &Config, frames: usize| {
+        let mut robot = Robot::new();
+        for _ in 0..frames {
+            robot.command_velocity(100.0, 1.0, cfg, DELTA_TIME);
+            robot.update(DELTA_TIME, cfg, &World::default());
+        }
+        robot
+    };
+
+    let robot = drive(&cfg, 30);
+    assert_eq!(robot.commanded_velocity(), (robot.linear_velocity, robot.angular_velocity));
+
+    cfg.control_latency = 0.2;
+    let robot = drive(&cfg, 6);
+    assert_eq!((robot.linear_velocity, robot.angular_velocity), (0.0, 0.0));
+    assert!(robot.commanded_velocity().0 > 5.0 && robot.commanded_velocity().1 > 0.05);
+
+    // driven open loop, as from the keyboard, the wheels replay the commanded velocities a latency later
+    let mut robot = Robot::new();
+    let mut commanded = Vec::new();
+    let mut executed = Vec::new();
+    for frame in 0..120 {
+        if frame < 40 { robot.linear_velocity += 1.5; }
+        robot.update(DELTA_TIME, &cfg, &World::default());
+        commanded.push(robot.commanded_velocity().0);
+        executed.push(robot.linear_velocity);
+    }
+    let lag = (cfg.control_latency / DELTA_TIME).round() as usize;
+    for frame in 0..120 - lag {
+        assert!((executed[frame + lag] - commanded[frame]).abs() < 1e-2, "frame {frame}: {} vs {}", executed[frame + lag], commanded[frame]);
+    }
+}
+
+#[test]
+fn dynamics_limit_acceleration_and_reversals() {
+    let cfg = Config::default();
+
+    // asking for full speed from rest only gets one frame's worth of acceleration
+    let mut robot = Robot::new();
+    robot.linear_velocity = cfg.max_linear_speed;
+    robot.update(DELTA_TIME, &cfg, &World::default());
+    assert!(robot.linear_velocity <= cfg.linear_acc * DELTA_TIME + 1e-4, "{}", robot.linear_velocity);
+    assert_eq!(robot.feasibility.clipped_frames, 1);
+
+    // reversing within a frame stops at rest first
+    let mut feasibility = Feasibility::default();
+    let reached = feasibility.enforce((0.5, -0.01), (-1.0, 0.05), DELTA_TIME, &cfg);
+    assert_eq!(reached, (0.0, 0.0));
+    assert_eq!(feasibility.blocked_flips, 1);
+    assert_eq!(feasibility.clipped_frames, 0);
+}
+
+#[test]
+fn saturated_actuators_lose_torque_toward_top_speed() {
+    let mut cfg = Config::default();
+    cfg.actuator_saturation = true;
+    let step = cfg.linear_acc * DELTA_TIME;
+
+    let mut feasibility = Feasibility::default();
+    let (from_rest, _) = feasibility.enforce((0.0, 0.0), (step, 0.0), DELTA_TIME, &cfg);
+    let (near_top, _) = feasibility.enforce((0.9 * cfg.max_linear_speed, 0.0), (0.9 * cfg.max_linear_speed + step, 0.0), DELTA_TIME, &cfg);
+    let (braking, _) = feasibility.enforce((0.9 * cfg.max_linear_speed, 0.0), (0.9 * cfg.max_linear_speed - step, 0.0), DELTA_TIME, &cfg);
+
+    assert!((from_rest - step).abs() < 1e-4);
+    assert!((near_top - 0.9 * cfg.max_linear_speed - 0.1 * step).abs() < 1e-3, "{near_top}");
+    assert!((braking - 0.9 * cfg.max_linear_speed + step).abs() < 1e-3, "{braking}");
+    assert_eq!(feasibility.saturated_frames, 1);
+    assert_eq!(feasibility.infeasible_frames(), 0);
+}
+
+#[test]
+fn canned_scenarios_are_dynamically_feasible() {
+    let cfg = Config::default();
+    for scenario in [Scenario::square_loop(), Scenario::corridor(), Scenario::changing_loop(), Scenario::long_loop()] {
+        let report = scenario::run(&scenario, &mut EkfSlam::new(), 1, &cfg);
+        assert_eq!(report.infeasible_frames, 0, "{}", scenario.name);
+    }
+}
+
+#[test]
+fn motion_gauges_read_acceleration_and_noisy_velocity() {
+    let cfg = Config::default();
+    let mut robot = Robot::new();
+    for _ in 0..10 {
+        robot.command_velocity(cfg.max_linear_speed, cfg.max_angular_speed, &cfg, DELTA_TIME);
+        robot.update(DELTA_TIME, &cfg, &World::default());
+        assert!((robot.acceleration.0 - cfg.linear_acc).abs() < 1e-2, "linear acceleration {}", robot.acceleration.0);
+        assert!((robot.acceleration.1 - cfg.angular_acc).abs() < 1e-2, "angular acceleration {}", robot.acceleration.1);
+    }
+
+    // the pose moved with noise on top of the clean velocity
+    let (noisy_linear, _) = robot.noisy_velocity();
+    assert!(noisy_linear != robot.linear_velocity);
+    assert!((noisy_linear - robot.linear_velocity).abs() < 0.5 * robot.linear_velocity);
+
+    // coasting, only drag slows the wheels, so the input is zero
+    robot.update(DELTA_TIME, &cfg, &World::default());
+    assert_eq!(robot.acceleration, (0.0, 0.0));
+}
@@ -0,0 +1,60 @@
+use slam_simulator::analytics::{Analytics, AnalyticsRegistry, ErrorEnvelope, FrameContext};
+use slam_simulator::simulation::{Landmark, Observation, Robot};
+use slam_simulator::slam::{EkfSlam, SlamFilter};
+
+// counts frames and observations, the smallest useful plugin
+struct Counter {
+    frames: usize,
+    observations: usize,
+}
+
+impl Analytics for Counter {
+    fn name(&self) -> &str {
+        "counter"
+    }
+
+    fn on_frame(&mut self, frame: &FrameContext) {
+        self.frames += 1;
+        self.observations += frame.observations.len();
+    }
+
+    fn metrics(&self) -> Vec<(String, f32)> {
+        vec![("frames".to_string(), self.frames as f32), ("observations".to_string(), self.observations as f32)]
+    }
+}
+
+#[test]
+fn registered_plugins_see_every_frame() {
+    let mut registry = AnalyticsRegistry::new();
+    assert!(registry.is_empty());
+    registry.register(Counter { frames: 0, observations: 0 });
+    registry.register(ErrorEnvelope::new("ekf"));
+
+    let mut robot = Robot::new();
+    let ekf = EkfSlam::with_prior(robot.pose(), 1.0, 0.01);
+    let landmarks = [Landmark::new(0, 50.0, 0.0)];
+    let observations = [Observation { id: 0, range: 50.0, bearing: 0.0 }];
+    let filters: [(&str, &dyn SlamFilter); 1] = [("ekf", &ekf)];
+
+    for (i, offset) in [0.0, 4.0, 2.0].into_iter().enumerate() {
+        robot.x = offset;
+        registry.on_frame(&FrameContext { time: i as f32, robot: &robot, landmarks: &landmarks, observations: &observations, filters: &filters });
+    }
+
+    let metrics = registry.metrics();
+    let value = |name: &str| metrics.iter().find(|(metric, _)| metric == name).unwrap_or_else(|| panic!("no {name} in {metrics:?}")).1;
+    assert_eq!(value("counter: frames"), 3.0);
+    assert_eq!(value("counter: observations"), 3.0);
+    assert_eq!(value("error envelope: ekf error"), 2.0);
+    assert_eq!(value("error envelope: ekf peak error"), 4.0);
+    // with a stdev of 1, only the error of 4 falls outside 3 sigma
+    assert!((value("error envelope: ekf within 3 sigma %") - 200.0 / 3.0).abs() < 1e-3);
+}
+
+#[test]
+fn missing_filters_are_skipped() {
+    let mut envelope = ErrorEnvelope::new("robo");
+    let robot = Robot::new();
+    envelope.on_frame(&FrameContext { time: 0.0, robot: &robot, landmarks: &[], observations: &[], filters: &[] });
+    assert_eq!(envelope.peak_at, None);
+}
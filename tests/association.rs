@@ -0,0 +1,136 @@
+use slam_simulator::config::Config;
+use slam_simulator::math::Pose2;
+use slam_simulator::scenario::{self, Scenario};
+use slam_simulator::simulation::{Landmark, Observation, Robot, sensor};
+use slam_simulator::slam::{Association, EkfSlam, MultiHypothesisEkf, SlamFilter, jcbb};
+
+#[test]
+fn association_without_ids() {
+    let cfg = Config::default();
+
+    // well separated landmarks are matched almost without mistakes, with or without hypotheses
+    for max_hypotheses in [1, cfg.max_hypotheses] {
+        let mut mht = MultiHypothesisEkf::with_prior(Pose2::IDENTITY, cfg.initial_stdev_position, cfg.initial_stdev_heading, max_hypotheses);
+        let report = scenario::run(&Scenario::square_loop(), &mut mht, 7, &cfg);
+        let best = mht.best();
+        println!("{max_hypotheses} hypotheses: ate {:.1}, {} of {} associations wrong", report.ate, best.mistakes, best.associations);
+        assert!(best.mistakes * 50 < best.associations);
+        assert!(report.ate < 30.0);
+        assert_eq!(mht.estimated_map().len(), 12, "no duplicates");
+    }
+
+    // pairs of nearby landmarks make matches ambiguous, which splits hypotheses
+    let mut scenario = Scenario::square_loop();
+    let twins: Vec<_> = scenario.landmarks.iter().map(|landmark| Landmark::new(landmark.id + 100, landmark.x + 40.0, landmark.y)).collect();
+    scenario.landmarks.extend(twins);
+
+    let mut mht = MultiHypothesisEkf::with_prior(Pose2::IDENTITY, cfg.initial_stdev_position, cfg.initial_stdev_heading, cfg.max_hypotheses);
+    let mut peak = 0;
+    scenario::run_observed(&scenario, &mut mht, 7, &cfg, |mht, _| {
+        if mht.hypotheses.len() > cfg.max_hypotheses { return Err(format!("{} hypotheses", mht.hypotheses.len())); }
+        if mht.hypotheses.windows(2).any(|pair| pair[0].cost > pair[1].cost) { return Err("hypotheses out of order".to_string()); }
+        peak = peak.max(mht.hypotheses.len());
+        Ok(())
+    }).unwrap();
+    println!("peak {peak} hypotheses, {} of {} associations wrong", mht.best().mistakes, mht.best().associations);
+    assert!(peak > 1);
+    assert_eq!(mht.best().cost, 0.0);
+}
+
+#[test]
+fn joint_gate_matches_chi_squared_quantiles() {
+    // 99% quantiles for 2, 4, and 10 degrees of freedom
+    assert!((jcbb::joint_gate(1, 9.21) - 9.21).abs() < 1e-3);
+    assert!((jcbb::joint_gate(2, 9.21) - 13.28).abs() < 0.1, "{}", jcbb::joint_gate(2, 9.21));
+    assert!((jcbb::joint_gate(5, 9.21) - 23.21).abs() < 0.2, "{}", jcbb::joint_gate(5, 9.21));
+}
+
+#[test]
+fn jcbb_pairs_a_frame_jointly() {
+    let cfg = Config::default();
+    let landmarks = [Landmark::new(0, 80.0, 0.0), Landmark::new(1, 80.0, 30.0), Landmark::new(2, 0.0, 90.0)];
+    macroquad::rand::srand(2);
+    let mut ekf = EkfSlam::new();
+    for _ in 0..20 {
+        ekf.update(&sensor::sense(&Robot::new(), &landmarks[..2], &[], &[], &cfg), &cfg);
+    }
+
+    // the mapped landmarks get their own measurements back, the unmapped one gets none
+    let frame = sensor::compress(&sensor::sense(&Robot::new(), &landmarks, &[], &[], &cfg));
+    let matches = jcbb::associate(&ekf, &frame, &cfg);
+    let expected: Vec<Option<usize>> = frame.iter().map(|(observation, _)| (observation.id < 2).then_some(observation.id)).collect();
+    assert_eq!(matches, expected);
+}
+
+#[test]
+fn jcbb_makes_fewer_mistakes_among_close_landmarks() {
+    let mut scenario = Scenario::square_loop();
+    let twins: Vec<_> = scenario.landmarks.iter().map(|landmark| Landmark::new(landmark.id + 100, landmark.x + 15.0, landmark.y)).collect();
+    scenario.landmarks.extend(twins);
+
+    // (wrong associations, spurious landmarks) with one hypothesis over a few seeds
+    let totals = |association: Association| {
+        let cfg = Config { association, ..Config::default() };
+        (2..6).fold((0, 0), |(mistakes, spurious), seed| {
+            let mut ekf = MultiHypothesisEkf::with_prior(Pose2::IDENTITY, cfg.initial_stdev_position, cfg.initial_stdev_heading, 1);
+            let report = scenario::run(&scenario, &mut ekf, seed, &cfg);
+            (mistakes + ekf.best().mistakes, spurious + report.map.spurious)
+        })
+    };
+    let (nearest_mistakes, nearest_spurious) = totals(Association::NearestNeighbor);
+    let (joint_mistakes, joint_spurious) = totals(Association::JointCompatibility);
+    println!("nearest neighbor: {nearest_mistakes} mistakes, {nearest_spurious} spurious; JCBB: {joint_mistakes}, {joint_spurious}");
+    assert!(2 * joint_mistakes < nearest_mistakes);
+    assert!(joint_spurious <= nearest_spurious);
+}
+
+#[test]
+fn ekf_matches_by_nearest_neighbor_without_ids() {
+    let cfg = Config { known_ids: false, ..Config::default() };
+    let landmarks = [Landmark::new(0, 80.0, 0.0), Landmark::new(1, 0.0, 90.0)];
+    macroquad::rand::srand(3);
+    let mut ekf = EkfSlam::new();
+    ekf.update(&sensor::sense(&Robot::new(), &landmarks[..1], &[], &[], &cfg), &cfg);
+    assert_eq!(ekf.estimated_map().len(), 1);
+
+    // the sensor's id counts for nothing: the same landmark under another id is matched,
+    // a measurement that passes no gate starts a landmark of its own
+    for _ in 0..10 {
+        let mut frame = sensor::sense(&Robot::new(), &landmarks, &[], &[], &cfg);
+        for observation in &mut frame { observation.id += 50; }
+        ekf.update(&frame, &cfg);
+    }
+    assert_eq!(ekf.estimated_map().len(), 2);
+    assert_eq!(ekf.sightings.values().sum::<u32>(), 21);
+
+    let report = scenario::run(&Scenario::square_loop(), &mut EkfSlam::new(), 7, &cfg);
+    println!("ate {:.1}, {} unmatched, {} spurious", report.ate, report.map.unmatched, report.map.spurious);
+    assert!(report.ate < 30.0);
+    assert_eq!(report.map.spurious, 0);
+}
+
+#[test]
+fn readings_are_matched_one_by_one_before_they_are_averaged() {
+    let cfg = Config { known_ids: false, ..Config::default() };
+
+    // two landmarks the sensor reports under one id aren't averaged into one between them,
+    // while repeated readings of either are
+    let mut ekf = EkfSlam::new();
+    let frame = [
+        Observation { id: 7, range: 80.0, bearing: 0.0 },
+        Observation { id: 7, range: 90.0, bearing: 1.5 },
+        Observation { id: 7, range: 80.2, bearing: 0.001 },
+    ];
+    ekf.update(&frame, &cfg);
+    let map = ekf.estimated_map();
+    assert_eq!(map.len(), 2, "{map:?}");
+    assert!(map.iter().any(|&(_, x, y)| (x - 80.1).abs() < 0.5 && y.abs() < 0.5), "{map:?}");
+    assert_eq!(ekf.sightings.values().sum::<u32>(), 2);
+
+    // landmarks mapped while the ids were known keep theirs; new ones count on past them
+    let mut ekf = EkfSlam::new();
+    ekf.update(&[Observation { id: 4, range: 80.0, bearing: 0.0 }], &Config::default());
+    ekf.update(&[Observation { id: 0, range: 60.0, bearing: 2.0 }], &cfg);
+    let ids: Vec<usize> = ekf.estimated_map().iter().map(|&(id, _, _)| id).collect();
+    assert_eq!(ids, [4, 5]);
+}
@@ -0,0 +1,67 @@
+use slam_simulator::config::Config;
+use slam_simulator::math::Pose2;
+use slam_simulator::simulation::Observation;
+use slam_simulator::slam::Backend;
+
+#[test]
+fn backends_are_found_by_any_spelling() {
+    for backend in Backend::ALL {
+        assert_eq!(Backend::by_name(backend.name()), Some(backend));
+        assert_eq!(Backend::by_name(&backend.name().to_lowercase().replace(' ', "_")), Some(backend));
+    }
+    assert_eq!(Backend::by_name("fej-ekf"), Some(Backend::FejEkf));
+    assert_eq!(Backend::by_name("kalman"), None);
+}
+
+#[test]
+fn every_backend_runs_behind_the_trait() {
+    let cfg = Config::default();
+    let pose = Pose2::new(10.0, -5.0, 0.5);
+    let observation = Observation { id: 3, range: 100.0, bearing: 0.2 };
+    for backend in Backend::ALL {
+        let mut slam = backend.build(pose, &cfg);
+        // FastSLAM draws its particles from the prior, so its mean is only near the pose
+        let Pose2 { x, y, theta } = slam.estimated_pose();
+        let near = |value: f32, expected: f32, stdev: f32| (value - expected).abs() < stdev;
+        assert!(
+            near(x, pose.x, cfg.initial_stdev_position) && near(y, pose.y, cfg.initial_stdev_position) && near(theta, pose.theta, cfg.initial_stdev_heading),
+            "{} starts at ({x}, {y}, {theta})", backend.name()
+        );
+        assert!(slam.estimated_map().is_empty());
+
+        for _ in 0..30 {
+            slam.predict(10.0, 0.0, 1.0 / 60.0, &cfg);
+            slam.update(std::slice::from_ref(&observation), &cfg);
+        }
+        let landmarks = slam.estimated_map();
+        assert_eq!(landmarks.len(), 1, "{} mapped {landmarks:?}", backend.name());
+        assert!(slam.landmark_covariance(landmarks[0].0).is_some(), "{}", backend.name());
+        assert!(slam.pose_covariance().iter().all(|value| value.is_finite()), "{}", backend.name());
+    }
+}
+
+#[test]
+fn fast_slam_takes_its_particle_count_from_the_config() {
+    let cfg = Config { fast_slam_particles: 7, ..Config::default() };
+    let slam = Backend::FastSlam.build(Pose2::IDENTITY, &cfg);
+    assert_eq!(slam.particles().len(), 7);
+}
+
+#[test]
+fn snapshots_restore_the_same_estimates() {
+    let cfg = Config::default();
+    let observations = [Observation { id: 0, range: 40.0, bearing: 0.3 }, Observation { id: 1, range: 60.0, bearing: -0.8 }];
+    for backend in Backend::ALL {
+        let mut slam = backend.build(Pose2::new(10.0, -5.0, 0.5), &cfg);
+        slam.predict(20.0, 0.1, 0.1, &cfg);
+        slam.update(&observations, &cfg);
+
+        let Some(snapshot) = slam.snapshot() else {
+            assert_eq!(backend, Backend::MhtEkf, "only the MHT EKF can't be saved");
+            continue;
+        };
+        let restored = backend.restore(snapshot).unwrap_or_else(|| panic!("{} did not restore", backend.name()));
+        assert_eq!(restored.estimated_pose(), slam.estimated_pose(), "{}", backend.name());
+        assert_eq!(restored.estimated_map(), slam.estimated_map(), "{}", backend.name());
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use slam_simulator::bag::BagWriter;
+use slam_simulator::config::Config;
+use slam_simulator::scenario::{self, Scenario, TIME_STEP};
+use slam_simulator::slam::{EkfSlam, SlamFilter};
+use slam_simulator::simulation::World;
+
+#[test]
+fn bag_holds_every_topic_as_cdr_messages() {
+    let cfg = Config::default();
+    let scenario = Scenario::square_loop();
+    let mut bag = BagWriter::new(&cfg);
+    let mut ground_truth = Vec::new();
+
+    scenario::run_observed(&scenario, &mut EkfSlam::new(), 7, &cfg, |ekf, robot| {
+        let time = ground_truth.len() as f32 * TIME_STEP;
+        let estimators: [(&str, &dyn SlamFilter); 1] = [("ekf", ekf)];
+        bag.push(time, robot.pose(), &estimators, &scenario.landmarks, Some(&robot.scan(&World::default(), &cfg)));
+        ground_truth.push(robot.x);
+        Ok(())
+    }).unwrap();
+    let bytes = bag.finish();
+
+    let magic = b"\x89MCAP0\r\n";
+    assert!(bytes.starts_with(magic) && bytes.ends_with(magic));
+
+    // walk the records: opcode, u64 length, content
+    let u16_at = |bytes: &[u8], at: usize| u16::from_le_bytes(bytes[at..at + 2].try_into().unwrap());
+    let u32_at = |bytes: &[u8], at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as usize;
+    let mut topics = HashMap::new();
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut ground_truth_x = Vec::new();
+    let mut offset = magic.len();
+    let mut last_opcode = 0;
+    while offset < bytes.len() - magic.len() {
+        let opcode = bytes[offset];
+        let length = u64::from_le_bytes(bytes[offset + 1..offset + 9].try_into().unwrap()) as usize;
+        let content = &bytes[offset + 9..offset + 9 + length];
+        match opcode {
+            0x04 => {
+                let topic_length = u32_at(content, 4);
+                topics.insert(u16_at(content, 0), String::from_utf8(content[8..8 + topic_length].to_vec()).unwrap());
+            }
+            0x05 => {
+                let topic = topics[&u16_at(content, 0)].clone();
+                let data = &content[22..];
+                assert_eq!(&data[..4], &[0, 1, 0, 0], "little-endian CDR");
+                if topic == "/ground_truth/pose" {
+                    // stamp (8 bytes) and "map" (4 + 4 bytes), then position.x
+                    ground_truth_x.push(f64::from_le_bytes(data[20..28].try_into().unwrap()));
+                }
+                *counts.entry(topic).or_default() += 1;
+            }
+            _ => {}
+        }
+        last_opcode = opcode;
+        offset += 9 + length;
+    }
+    assert_eq!(offset, bytes.len() - magic.len());
+    assert_eq!(last_opcode, 0x02, "ends with the footer");
+
+    let frames = ground_truth.len();
+    for topic in ["/tf", "/ground_truth/pose", "/ekf/pose", "/scan"] {
+        assert_eq!(counts[topic], frames, "{topic}");
+    }
+    // markers are throttled to `bag_marker_period`
+    let expected_markers = (frames as f32 * TIME_STEP / cfg.bag_marker_period).ceil() as usize;
+    assert!(counts["/landmarks"].abs_diff(expected_markers) <= 1);
+    assert_eq!(counts["/landmarks"], counts["/ekf/landmarks"]);
+
+    for (x, truth) in ground_truth_x.iter().zip(&ground_truth) {
+        assert!((x - *truth as f64 * cfg.bag_meters_per_unit as f64).abs() < 1e-6);
+    }
+}
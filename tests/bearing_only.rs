@@ -0,0 +1,85 @@
+use slam_simulator::config::Config;
+use slam_simulator::math::Pose2;
+use slam_simulator::simulation::Observation;
+use slam_simulator::slam::{BearingOnlyEkf, Initialization, SlamFilter};
+use slam_simulator::slam::bearing_only::triangulate;
+
+#[test]
+fn rays_cross_where_they_should() {
+    // from the origin heading 45 degrees and from (10, 0) heading 135 degrees
+    let (point, first, second) = triangulate(Pose2::new(0.0, 0.0, std::f32::consts::FRAC_PI_4), Pose2::new(10.0, 0.0, 3.0 * std::f32::consts::FRAC_PI_4)).unwrap();
+    assert!((point.0 - 5.0).abs() < 1e-4 && (point.1 - 5.0).abs() < 1e-4, "{point:?}");
+
+    // the jacobians match finite differences
+    let rays = [0.0, 0.0, std::f32::consts::FRAC_PI_4, 10.0, 0.0, 3.0 * std::f32::consts::FRAC_PI_4];
+    for k in 0..6 {
+        let step = 1e-3;
+        let shifted = |delta: f32| {
+            let mut rays = rays;
+            rays[k] += delta;
+            triangulate(Pose2::new(rays[0], rays[1], rays[2]), Pose2::new(rays[3], rays[4], rays[5])).unwrap().0
+        };
+        let (plus, minus) = (shifted(step), shifted(-step));
+        let numeric = ((plus.0 - minus.0) / (2.0 * step), (plus.1 - minus.1) / (2.0 * step));
+        let analytic = if k < 3 { first.column(k) } else { second.column(k - 3) };
+        assert!((numeric.0 - analytic[0]).abs() < 1e-2 && (numeric.1 - analytic[1]).abs() < 1e-2, "parameter {k}: {numeric:?} vs {analytic:?}");
+    }
+
+    // parallel rays, and rays that only meet behind the robot
+    assert!(triangulate(Pose2::IDENTITY, Pose2::new(0.0, 5.0, 0.0)).is_none());
+    assert!(triangulate(Pose2::IDENTITY, Pose2::new(10.0, 0.0, 0.5)).is_none());
+}
+
+/*
+ * drives a circle past a few landmarks with exact odometry and noiseless
+ * bearings, and returns the filter
+ */
+fn drive(initialization: Initialization, steps: usize) -> BearingOnlyEkf {
+    let cfg = Config::default();
+    let landmarks = [(0, 150.0, 50.0), (1, -40.0, 160.0), (2, 80.0, -120.0)];
+    let mut pose = Pose2::IDENTITY;
+    let mut ekf = BearingOnlyEkf::with_prior(pose, 0.1, 0.01, initialization);
+    let (linear, angular, delta_time) = (40.0, 0.3, 0.05);
+
+    for _ in 0..steps {
+        pose = cfg.filter_integrator.step(pose, linear, angular, delta_time);
+        ekf.predict(linear, angular, delta_time, &cfg);
+        let observations: Vec<_> = landmarks.iter().map(|&(id, x, y)| {
+            let (dx, dy): (f32, f32) = (x - pose.x, y - pose.y);
+            let bearing = dy.atan2(dx) - pose.theta;
+            Observation { id, range: dx.hypot(dy), bearing: bearing.sin().atan2(bearing.cos()) }
+        }).collect();
+        ekf.update(&observations, &cfg);
+    }
+
+    ekf
+}
+
+fn assert_mapped(ekf: &BearingOnlyEkf) {
+    let landmarks = [(150.0, 50.0), (-40.0, 160.0), (80.0, -120.0)];
+    for (id, x, y) in ekf.estimated_map() {
+        let (true_x, true_y) = landmarks[id];
+        assert!((x - true_x).hypot(y - true_y) < 15.0, "{:?} put landmark {id} at ({x}, {y})", ekf.initialization);
+    }
+}
+
+#[test]
+fn inverse_depth_maps_at_once_and_converges() {
+    assert_eq!(drive(Initialization::InverseDepth, 1).landmark_count(), 3);
+    let ekf = drive(Initialization::InverseDepth, 400);
+    assert_mapped(&ekf);
+    assert!(ekf.landmark_covariance(0).unwrap().trace().is_finite());
+}
+
+#[test]
+fn triangulation_waits_for_parallax() {
+    // the first frames see each landmark from nearly the same place
+    let early = drive(Initialization::Triangulation, 2);
+    assert_eq!(early.landmark_count(), 0);
+    assert_eq!(early.pending.len(), 3);
+
+    let ekf = drive(Initialization::Triangulation, 400);
+    assert_eq!(ekf.landmark_count(), 3);
+    assert_mapped(&ekf);
+    assert!(ekf.pending.is_empty());
+}
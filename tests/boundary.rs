@@ -0,0 +1,56 @@
+use macroquad::prelude::Rect;
+use slam_simulator::config::Config;
+use slam_simulator::scenario::{self, Scenario};
+use slam_simulator::simulation::{self, Landmark, Robot, World, sensor};
+use slam_simulator::slam::EkfSlam;
+
+fn walled_world(cfg: &Config) -> World {
+    World { walls: simulation::boundary_walls(Rect::new(-200.0, -200.0, 400.0, 400.0), cfg.boundary_thickness), ..World::default() }
+}
+
+#[test]
+fn boundary_stops_the_robot_like_a_wall() {
+    let cfg = Config::default();
+    let walls = walled_world(&cfg);
+
+    let mut robot = Robot::new();
+    let mut bumped = false;
+    for _ in 0..600 {
+        robot.command_velocity(100.0, 0.0, &cfg, 1.0 / 60.0);
+        robot.update(1.0 / 60.0, &cfg, &walls);
+        bumped |= robot.bumped;
+    }
+    assert!(bumped);
+    assert!(robot.x <= 200.0 - cfg.robot_radius + 0.5, "robot went through the boundary to x = {}", robot.x);
+}
+
+#[test]
+fn boundary_stops_beams_and_hides_landmarks_outside() {
+    let cfg = Config { outlier_probability: 0.0, detection_probability: 1.0, ..Config::default() };
+    let walls = walled_world(&cfg);
+
+    let mut robot = Robot::new();
+    robot.place(100.0, 0.0, 0.0);
+
+    // the beam straight ahead hits the wall 100 away
+    let scan = robot.scan(&walls, &cfg);
+    let ahead = (0..scan.ranges.len()).min_by(|&a, &b| scan.angle(a).abs().total_cmp(&scan.angle(b).abs())).unwrap();
+    assert!((scan.ranges[ahead] - 100.0).abs() < 5.0 * cfg.lidar_stdev_range, "{}", scan.ranges[ahead]);
+
+    let landmarks = [Landmark::new(0, 150.0, 0.0), Landmark::new(1, 250.0, 0.0)];
+    let seen: Vec<usize> = sensor::sense(&robot, &landmarks, walls.solid(), &[], &cfg).iter().map(|observation| observation.id).collect();
+    assert!(seen.contains(&0) && !seen.contains(&1), "{seen:?}");
+}
+
+#[test]
+fn canned_scenarios_stay_inside_their_boundaries() {
+    let cfg = Config::default();
+    for scenario in [Scenario::square_loop(), Scenario::corridor()] {
+        let area = scenario.boundary.unwrap();
+        scenario::run_observed(&scenario, &mut EkfSlam::new(), 1, &cfg, |_, robot| {
+            if robot.bumped { return Err(format!("{} hit its boundary at ({}, {})", scenario.name, robot.x, robot.y)); }
+            Ok(())
+        }).unwrap();
+        assert!(scenario.landmarks.iter().all(|landmark| area.contains(macroquad::math::vec2(landmark.x, landmark.y))));
+    }
+}
@@ -0,0 +1,35 @@
+use slam_simulator::config::Config;
+use slam_simulator::math::Pose2;
+use slam_simulator::recording::{FrameInputs, Recording};
+use slam_simulator::scenario::{Playback, Scenario, TIME_STEP};
+use slam_simulator::simulation::{Robot, sensor};
+use slam_simulator::slam::{EkfSlam, SlamFilter};
+
+/*
+ * records the EKF through `scenario` the way the simulator does, inputs included
+ */
+pub fn record(scenario: &Scenario, seed: u64, cfg: &Config) -> Recording {
+    macroquad::rand::srand(seed);
+    let mut robot = Robot::new();
+    let mut ekf = EkfSlam::with_prior(Pose2::IDENTITY, cfg.initial_stdev_position, cfg.initial_stdev_heading);
+    let mut recording = Recording::new(&["ekf"]).starting_from(vec![ekf.estimated_pose()]);
+    let mut landmarks = scenario.landmarks.clone();
+    let world = scenario.world(cfg);
+
+    let mut playback = Playback::new(scenario.clone());
+    while let Some(segment) = playback.step(&mut landmarks) {
+        robot.command_velocity(segment.linear, segment.angular, cfg, TIME_STEP);
+        robot.update(TIME_STEP, cfg, &world);
+        ekf.predict(robot.linear_velocity, robot.angular_velocity, TIME_STEP, cfg);
+        let observations = sensor::sense_sweeps(&robot, &landmarks, world.solid(), &scenario.dropout_zones, cfg);
+        ekf.update(&observations, cfg);
+
+        recording.push(playback.time(), robot.pose(), vec![ekf.estimated_pose()]);
+        recording.push_inputs(FrameInputs {
+            delta_time: TIME_STEP,
+            odometry: Some((robot.linear_velocity, robot.angular_velocity)),
+            observations,
+        });
+    }
+    recording
+}
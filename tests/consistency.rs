@@ -0,0 +1,71 @@
+use slam_simulator::config::Config;
+use slam_simulator::consistency::{ConsistencyMonitor, Inconsistency, chi_square_quantile, mean_bounds};
+use slam_simulator::math::Pose2;
+use slam_simulator::simulation::Observation;
+use slam_simulator::slam::{EkfSlam, SlamFilter};
+use slam_simulator::utils::sample_normal;
+
+const DELTA_TIME: f32 = 1.0 / 60.0;
+const LANDMARKS: [(f32, f32); 4] = [(80.0, 0.0), (0.0, 90.0), (-70.0, -20.0), (30.0, -100.0)];
+
+/*
+ * a robot standing at the origin, measuring every landmark each frame with
+ * `noise` times the configured noise
+ */
+fn run(cfg: &Config, noise: f32, frames: usize) -> ConsistencyMonitor {
+    macroquad::rand::srand(11);
+    let mut ekf = EkfSlam::new();
+    let mut monitor = ConsistencyMonitor::new(frames);
+    for frame in 0..frames {
+        ekf.predict(0.0, 0.0, DELTA_TIME, cfg);
+        let observations: Vec<Observation> = LANDMARKS.iter().enumerate().map(|(id, &point)| {
+            let (range, bearing) = Pose2::IDENTITY.range_bearing(point);
+            Observation {
+                id,
+                range: range + sample_normal(0.0, noise * cfg.real_stdev_range),
+                bearing: bearing + sample_normal(0.0, noise * cfg.real_stdev_bearing),
+            }
+        }).collect();
+        monitor.record_innovations(&ekf, &observations, cfg);
+        ekf.update(&observations, cfg);
+        monitor.record(frame as f32 * DELTA_TIME, &ekf, Pose2::IDENTITY, cfg);
+    }
+    monitor
+}
+
+#[test]
+fn chi_square_quantiles_are_close() {
+    // tabulated 2.5% and 97.5% quantiles for 10 and 100 degrees of freedom
+    for (dof, lower, upper) in [(10, 3.247, 20.483), (100, 74.222, 129.561)] {
+        assert!((chi_square_quantile(-1.96, dof) - lower).abs() < 0.02 * lower, "{dof}");
+        assert!((chi_square_quantile(1.96, dof) - upper).abs() < 0.02 * upper, "{dof}");
+    }
+
+    // the bounds on a mean tighten around the degrees of freedom as it takes more samples
+    let (lower, upper) = mean_bounds(10, 2);
+    let (tight_lower, tight_upper) = mean_bounds(1000, 2);
+    assert!(lower < tight_lower && tight_lower < 2.0 && 2.0 < tight_upper && tight_upper < upper);
+}
+
+#[test]
+fn consistent_filter_stays_inside_its_bounds() {
+    let cfg = Config::default();
+    let monitor = run(&cfg, 1.0, 600);
+    let nis = monitor.latest().unwrap().nis.unwrap();
+    println!("{nis:?}");
+    assert!(nis.is_inside());
+    assert_eq!(monitor.warning, None);
+}
+
+#[test]
+fn overconfident_filter_raises_a_warning() {
+    let cfg = Config::default();
+    let monitor = run(&cfg, 3.0, 600);
+    let nis = monitor.latest().unwrap().nis.unwrap();
+    assert!(nis.mean > nis.upper, "{nis:?}");
+    assert_eq!(monitor.warning, Some(Inconsistency::Overconfident));
+
+    // but only once it has lasted `consistency_warn_time`
+    let cfg = Config { consistency_warn_time: 60.0, ..Config::default() };
+    assert_eq!(run(&cfg, 3.0, 600).warning, None);
+}
@@ -0,0 +1,51 @@
+use slam_simulator::config::Config;
+use slam_simulator::scenario::{self, Scenario};
+use slam_simulator::slam::{EkfSlam, SlamFilter};
+
+#[test]
+fn scaling_a_block_keeps_the_covariance_valid() {
+    let cfg = Config::default();
+    let mut ekf = EkfSlam::new();
+    scenario::run(&Scenario::square_loop(), &mut ekf, 7, &cfg);
+    let before = ekf.clone();
+
+    assert!(ekf.scale_covariance(Some(3), 4.0));
+    let index = ekf.observed_landmarks.get(3).unwrap();
+    let other = ekf.observed_landmarks.get(5).unwrap();
+
+    // the block by the factor, its correlations by the root of it, the rest untouched
+    let block = ekf.covariance.fixed_view::<2, 2>(index, index) - 4.0 * before.covariance.fixed_view::<2, 2>(index, index);
+    assert!(block.abs().max() < 1e-3);
+    let cross = ekf.covariance.fixed_view::<2, 2>(index, 0) - 2.0 * before.covariance.fixed_view::<2, 2>(index, 0);
+    assert!(cross.abs().max() < 1e-3);
+    assert_eq!(ekf.covariance.fixed_view::<2, 2>(other, other), before.covariance.fixed_view::<2, 2>(other, other));
+    assert_eq!(ekf.state, before.state);
+    ekf.check_invariants().unwrap();
+
+    assert!(ekf.scale_covariance(None, 0.25));
+    assert!((ekf.pose_covariance().trace() - 0.25 * before.pose_covariance().trace()).abs() < 1e-3);
+    ekf.check_invariants().unwrap();
+
+    assert!(!ekf.scale_covariance(Some(99), 2.0), "landmark 99 was never mapped");
+    assert!(!ekf.scale_covariance(None, 0.0));
+    assert!(!ekf.scale_covariance(None, f32::NAN));
+}
+
+
+#[test]
+fn aging_inflates_every_landmark_but_the_anchors() {
+    let cfg = Config::default();
+    let mut ekf = EkfSlam::new();
+    scenario::run(&Scenario::square_loop(), &mut ekf, 7, &cfg);
+    assert!(ekf.anchor_landmark(5, 0.1));
+    let before = ekf.clone();
+
+    ekf.age_landmarks(0.5);
+    let (aged, anchor) = (ekf.observed_landmarks.get(3).unwrap(), ekf.observed_landmarks.get(5).unwrap());
+    let factor = 0.5f32.exp();
+    let block = ekf.covariance.fixed_view::<2, 2>(aged, aged) - factor * before.covariance.fixed_view::<2, 2>(aged, aged);
+    assert!(block.abs().max() < 1e-3);
+    assert_eq!(ekf.covariance.fixed_view::<3, 3>(0, 0), before.covariance.fixed_view::<3, 3>(0, 0), "the pose doesn't age");
+    assert_eq!(ekf.covariance.fixed_view::<2, 2>(anchor, anchor), before.covariance.fixed_view::<2, 2>(anchor, anchor));
+    assert!(ekf.check_invariants().is_ok());
+}
@@ -0,0 +1,109 @@
+use slam_simulator::config::Config;
+use slam_simulator::math::Pose2;
+use slam_simulator::scenario::{self, LandmarkEvent, Scenario};
+use slam_simulator::simulation::Landmark;
+use slam_simulator::slam::{EkfSlam, MultiHypothesisEkf, SlamFilter};
+
+/*
+ * the square loop with its first landmark taken away after it is mapped and
+ * put back while the robot is on the far side
+ */
+fn vanishing_landmark() -> Scenario {
+    let mut scenario = Scenario::square_loop();
+    let landmark = scenario.landmarks[0].clone();
+    scenario.landmark_events = vec![
+        LandmarkEvent::Remove { time: 3.0, id: landmark.id },
+        LandmarkEvent::Appear { time: 15.0, landmark: Landmark::new(landmark.id, landmark.x, landmark.y) },
+    ];
+    scenario
+}
+
+#[test]
+fn culled_landmark_keeps_its_history() {
+    let cfg = Config { cull_after_misses: 60, ..Config::default() };
+    let mut ekf = EkfSlam::new();
+    let mut archived_sightings = None;
+    scenario::run_observed(&vanishing_landmark(), &mut ekf, 7, &cfg, |ekf, _| {
+        if let Some(archived) = ekf.archive.iter().find(|archived| archived.id == 0) {
+            archived_sightings = Some(archived.sightings);
+        }
+        ekf.check_invariants()
+    }).unwrap();
+
+    let archived_sightings = archived_sightings.expect("landmark 0 was culled");
+    println!("archived with {archived_sightings} sightings, {} at the end", ekf.sightings[&0]);
+    assert!(ekf.observed_landmarks.contains(0) && ekf.archive.is_empty());
+    assert!(ekf.sightings[&0] > archived_sightings);
+}
+
+#[test]
+fn culled_landmark_is_reidentified_without_ids() {
+    let cfg = Config { cull_after_misses: 60, ..Config::default() };
+    let mut mht = MultiHypothesisEkf::with_prior(Pose2::IDENTITY, cfg.initial_stdev_position, cfg.initial_stdev_heading, cfg.max_hypotheses);
+    let mut culled = false;
+    scenario::run_observed(&vanishing_landmark(), &mut mht, 7, &cfg, |mht, _| {
+        culled |= !mht.best().ekf.archive.is_empty();
+        Ok(())
+    }).unwrap();
+
+    // the landmark came back under the id it had, not a fresh one
+    let best = mht.best();
+    let ids: Vec<usize> = best.ekf.estimated_map().iter().map(|(id, _, _)| *id).collect();
+    println!("ids {ids:?}, {} of {} associations wrong", best.mistakes, best.associations);
+    assert!(culled);
+    assert!(best.ekf.archive.is_empty());
+    assert_eq!(ids.len(), 12);
+    assert!(ids.iter().all(|&id| id < 12));
+}
+
+#[test]
+fn one_off_landmark_is_pruned() {
+    // a landmark next to the first one that flickers into view for a few frames
+    let mut scenario = Scenario::square_loop();
+    let first = scenario.landmarks[0].clone();
+    let spurious = Landmark::new(100, first.x + 20.0, first.y);
+    scenario.landmark_events = vec![
+        LandmarkEvent::Appear { time: 2.0, landmark: spurious },
+        LandmarkEvent::Remove { time: 2.03, id: 100 },
+    ];
+
+    let cfg = Config { prune_after_updates: 120, ..Config::default() };
+    let mut ekf = EkfSlam::new();
+    let mut mapped = false;
+    scenario::run_observed(&scenario, &mut ekf, 7, &cfg, |ekf, _| {
+        mapped |= ekf.observed_landmarks.contains(100);
+        ekf.check_invariants()
+    }).unwrap();
+
+    // gone for good, while every real landmark, seen again and again, stays
+    let ids: Vec<usize> = ekf.estimated_map().iter().map(|(id, _, _)| *id).collect();
+    assert!(mapped, "the one-off landmark was never mapped");
+    assert!(!ids.contains(&100) && ekf.archive.is_empty());
+    assert_eq!(ids.len(), scenario.landmarks.len());
+}
+
+#[test]
+fn one_off_landmark_is_pruned_from_the_best_hypothesis() {
+    let mut scenario = Scenario::square_loop();
+    let first = scenario.landmarks[0].clone();
+    let spurious = Landmark::new(100, first.x + 20.0, first.y);
+    scenario.landmark_events = vec![
+        LandmarkEvent::Appear { time: 2.0, landmark: spurious.clone() },
+        LandmarkEvent::Remove { time: 2.03, id: 100 },
+    ];
+
+    let cfg = Config { prune_after_updates: 120, ..Config::default() };
+    let mut mht = MultiHypothesisEkf::with_prior(Pose2::IDENTITY, cfg.initial_stdev_position, cfg.initial_stdev_heading, cfg.max_hypotheses);
+    let near_spurious = |mht: &MultiHypothesisEkf| mht.best().ekf.estimated_map().iter()
+        .any(|&(_, x, y)| (x - spurious.x).hypot(y - spurious.y) < 8.0);
+    let mut mapped = false;
+    scenario::run_observed(&scenario, &mut mht, 7, &cfg, |mht, _| {
+        mapped |= near_spurious(mht);
+        mht.best().ekf.check_invariants()
+    }).unwrap();
+
+    // the hypothesis' own id for it is unknown, so it is found by position
+    assert!(mapped, "the one-off landmark was never mapped");
+    assert!(!near_spurious(&mht));
+    assert_eq!(mht.estimated_map().len(), scenario.landmarks.len());
+}
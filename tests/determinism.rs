@@ -0,0 +1,90 @@
+use std::hash::BuildHasher;
+use std::collections::hash_map::RandomState;
+
+use macroquad::prelude::Color;
+use nalgebra::{Matrix2, Matrix3};
+use slam_simulator::config::Config;
+use slam_simulator::determinism::{self, DivergenceKind};
+use slam_simulator::math::Pose2;
+use slam_simulator::scenario::Scenario;
+use slam_simulator::simulation::Observation;
+use slam_simulator::slam::{EkfSlam, SlamFilter};
+
+/*
+ * an EKF whose heading picks up a bit of its HashMap hasher keys, the way
+ * arithmetic in iteration order would
+ */
+struct HasherDependent {
+    ekf: EkfSlam,
+    offset: f32,
+}
+
+impl HasherDependent {
+    fn new() -> Self {
+        let offset = (RandomState::new().hash_one(0u8) % 1_000_000) as f32 * 1e-7;
+        Self { ekf: EkfSlam::new(), offset }
+    }
+}
+
+impl SlamFilter for HasherDependent {
+    fn predict(&mut self, linear_velocity: f32, angular_velocity: f32, delta_time: f32, cfg: &Config) {
+        self.ekf.predict(linear_velocity, angular_velocity, delta_time, cfg);
+    }
+
+    fn update(&mut self, observations: &[Observation], cfg: &Config) {
+        self.ekf.update(observations, cfg);
+    }
+
+    fn estimated_pose(&self) -> Pose2 {
+        let Pose2 { x, y, theta } = self.ekf.estimated_pose();
+        Pose2::new(x, y, theta + self.offset)
+    }
+
+    fn pose_covariance(&self) -> Matrix3<f32> {
+        self.ekf.pose_covariance()
+    }
+
+    fn estimated_map(&self) -> Vec<(usize, f32, f32)> {
+        self.ekf.estimated_map()
+    }
+
+    fn landmark_covariance(&self, id: usize) -> Option<Matrix2<f32>> {
+        self.ekf.landmark_covariance(id)
+    }
+
+    fn color(&self) -> Color {
+        self.ekf.color()
+    }
+}
+
+#[test]
+fn checksums_see_every_bit() {
+    assert_eq!(determinism::checksum([1.0, 2.0]), determinism::checksum([1.0, 2.0]));
+    assert_ne!(determinism::checksum([1.0, 2.0]), determinism::checksum([2.0, 1.0]));
+    assert_ne!(determinism::checksum([0.0]), determinism::checksum([-0.0]));
+    assert_ne!(determinism::checksum([1.0]), determinism::checksum([1.0 + f32::EPSILON]));
+}
+
+#[test]
+fn every_filter_evolves_bit_identically() {
+    let cfg = Config::default();
+    for scenario in [Scenario::square_loop(), Scenario::changing_loop()] {
+        let report = determinism::audit(&scenario, || determinism::default_filters(&cfg), 1, &cfg);
+        assert!(report.frames > 0);
+        assert!(report.divergences.is_empty(), "{}: {:?}", scenario.name, report.divergences);
+    }
+}
+
+#[test]
+fn hasher_dependence_is_flagged() {
+    let cfg = Config::default();
+    let filters = || -> Vec<(&'static str, Box<dyn SlamFilter>)> {
+        vec![("ekf", Box::new(EkfSlam::new())), ("hashed", Box::new(HasherDependent::new()))]
+    };
+    let report = determinism::audit(&Scenario::square_loop(), filters, 0, &cfg);
+
+    assert!(!report.is_deterministic());
+    assert_eq!(report.divergences.len(), 1, "{:?}", report.divergences);
+    let divergence = &report.divergences[0];
+    assert_eq!((divergence.component.as_str(), divergence.frame, divergence.kind), ("hashed", 0, DivergenceKind::State));
+}
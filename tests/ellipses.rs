@@ -0,0 +1,83 @@
+use macroquad::prelude::{Rect, WHITE};
+use nalgebra::Matrix2;
+use slam_simulator::app::ellipses::{self, EllipseBatch};
+use slam_simulator::config::Config;
+use slam_simulator::scenario::{self, Scenario};
+use slam_simulator::slam::{EkfSlam, SlamFilter};
+
+#[test]
+fn detail_follows_on_screen_size() {
+    let cfg = Config::default();
+    assert_eq!(ellipses::lod_segments(0.5 * cfg.ellipse_min_pixels, &cfg), 0);
+    assert_eq!(ellipses::lod_segments(f32::NAN, &cfg), 0);
+
+    let small = ellipses::lod_segments(cfg.ellipse_min_pixels, &cfg);
+    let medium = ellipses::lod_segments(30.0, &cfg);
+    let large = ellipses::lod_segments(1000.0, &cfg);
+    assert!(small > 0 && small <= medium && medium <= large, "{small} {medium} {large}");
+    assert_eq!(large, ellipses::lod_segments(1e6, &cfg), "detail is capped");
+}
+
+#[test]
+fn large_maps_split_into_meshes_with_valid_indices() {
+    let mut batch = EllipseBatch::new();
+    let covariance = Matrix2::new(4.0, 1.0, 1.0, 2.0);
+    for i in 0..1000 {
+        batch.add((i as f32, 0.0), &covariance, 3.0, 0.5, 48, WHITE);
+    }
+    assert_eq!(batch.vertex_count(), 1000 * 2 * 48);
+    assert!(batch.meshes().len() > 1);
+    for mesh in batch.meshes() {
+        assert!(mesh.indices.iter().all(|&index| (index as usize) < mesh.vertices.len()));
+    }
+
+    batch.add((0.0, 0.0), &covariance, 3.0, 0.5, 0, WHITE);
+    assert_eq!(batch.vertex_count(), 1000 * 2 * 48, "zero segments skips the ellipse");
+}
+
+#[test]
+fn ellipses_outside_the_view_are_culled() {
+    let cfg = Config::default();
+    let mut ekf = EkfSlam::new();
+    scenario::run(&Scenario::square_loop(), &mut ekf, 7, &cfg);
+    let mapped = ekf.estimated_map().len();
+
+    let mut everything = EllipseBatch::new();
+    ellipses::add_landmark_ellipses(&mut everything, &ekf, Rect::new(-1000.0, -1000.0, 2000.0, 2000.0), 100.0, &cfg);
+    let mut nothing = EllipseBatch::new();
+    ellipses::add_landmark_ellipses(&mut nothing, &ekf, Rect::new(5000.0, 5000.0, 100.0, 100.0), 100.0, &cfg);
+    // zoomed far out every ellipse is below a pixel
+    let mut distant = EllipseBatch::new();
+    ellipses::add_landmark_ellipses(&mut distant, &ekf, Rect::new(-1000.0, -1000.0, 2000.0, 2000.0), 1e-3, &cfg);
+
+    assert!(everything.vertex_count() >= mapped * 2 * 8);
+    assert_eq!(nothing.vertex_count(), 0);
+    assert_eq!(distant.vertex_count(), 0);
+}
+
+#[test]
+fn every_whole_sigma_up_to_the_outermost() {
+    assert_eq!(ellipses::sigma_levels(&Config::default()), vec![1.0, 2.0, 3.0]);
+    assert_eq!(ellipses::sigma_levels(&Config { ellipse_sigmas: 2.5, ..Config::default() }), vec![1.0, 2.0, 2.5]);
+    assert_eq!(ellipses::sigma_levels(&Config { ellipse_sigmas: 0.5, ..Config::default() }), vec![0.5]);
+}
+
+#[test]
+fn robot_pose_gets_nested_ellipses() {
+    let cfg = Config::default();
+    let mut ekf = EkfSlam::new();
+    for _ in 0..600 {
+        ekf.predict(30.0, 0.2, 1.0 / 60.0, &cfg);
+    }
+    let everywhere = Rect::new(-1000.0, -1000.0, 2000.0, 2000.0);
+
+    let mut batch = EllipseBatch::new();
+    ellipses::add_pose_ellipses(&mut batch, &ekf, everywhere, 100.0, &cfg);
+    let levels = ellipses::sigma_levels(&cfg).len();
+    // every level, each at least the minimum detail
+    assert!(batch.vertex_count() >= levels * 2 * 8, "{}", batch.vertex_count());
+
+    let mut elsewhere = EllipseBatch::new();
+    ellipses::add_pose_ellipses(&mut elsewhere, &ekf, Rect::new(5000.0, 5000.0, 100.0, 100.0), 100.0, &cfg);
+    assert_eq!(elsewhere.vertex_count(), 0);
+}
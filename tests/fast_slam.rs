@@ -0,0 +1,37 @@
+use slam_simulator::config::Config;
+use slam_simulator::math::Pose2;
+use slam_simulator::scenario::{self, Scenario};
+use slam_simulator::simulation::{Landmark, Robot, sensor};
+use slam_simulator::slam::{FastSlam, SlamFilter};
+
+#[test]
+fn best_particle_is_the_one_that_fit_the_measurements() {
+    let cfg = Config::default();
+    macroquad::rand::srand(4);
+    let landmarks = [Landmark::new(0, 80.0, 0.0), Landmark::new(1, 0.0, 60.0)];
+
+    // every particle maps the landmarks from the true pose, then half of them get knocked well off it
+    let mut fast = FastSlam::new(20);
+    fast.update(&sensor::sense(&Robot::new(), &landmarks, &[], &[], &cfg), &cfg);
+    for particle in fast.particles.iter_mut().take(10) {
+        particle.x = 30.0;
+        particle.theta = 0.5;
+    }
+    fast.update(&sensor::sense(&Robot::new(), &landmarks, &[], &[], &cfg), &cfg);
+
+    let best = fast.best_particle().unwrap();
+    assert!(best.x.abs() < 1e-3 && best.theta.abs() < 1e-3, "best particle at ({}, {}, {})", best.x, best.y, best.theta);
+    assert_eq!(best.landmarks.len(), 2);
+}
+
+#[test]
+fn best_particle_map_covers_the_square_loop() {
+    let cfg = Config::default();
+    let mut fast = FastSlam::with_prior(50, Pose2::IDENTITY, cfg.initial_stdev_position, cfg.initial_stdev_heading);
+    scenario::run(&Scenario::square_loop(), &mut fast, 3, &cfg);
+
+    let best = fast.best_particle().unwrap();
+    assert_eq!(best.landmarks.len(), 12);
+    let Pose2 { x, y, .. } = fast.estimated_pose();
+    assert!((best.x - x).hypot(best.y - y) < 20.0, "best particle ({}, {}) far from the mean ({x}, {y})", best.x, best.y);
+}
@@ -0,0 +1,105 @@
+use macroquad::prelude::KeyCode;
+use slam_simulator::config::Config;
+use slam_simulator::fleet::{ControlSource, Fleet, KeySet, RobotSpec};
+use slam_simulator::math::Pose2;
+use slam_simulator::scenario::{self, ControlSegment, Scenario};
+use slam_simulator::simulation::{SensorOverrides, World};
+use slam_simulator::slam::EkfSlam;
+
+fn spec(name: &str, start: Pose2, control: ControlSource) -> RobotSpec {
+    RobotSpec { name: name.to_string(), start, control, sensor: SensorOverrides::default() }
+}
+
+#[test]
+fn scenario_files_round_trip_with_their_robots() {
+    let path = std::env::temp_dir().join(format!("slam_scenario_{}.json", std::process::id()));
+    let mut scenario = Scenario::square_loop();
+    scenario.robots = vec![
+        spec("scout", Pose2::new(10.0, 20.0, 1.0), ControlSource::Keyboard(KeySet::Numpad)),
+        spec("pad", Pose2::new(0.0, 0.0, 0.0), ControlSource::Gamepad(1)),
+        spec("patrol", Pose2::new(-50.0, 0.0, 0.0), ControlSource::Script(vec![ControlSegment { duration: 2.0, linear: 20.0, angular: 0.0 }])),
+    ];
+    scenario.robots[0].sensor.range = Some(80.0);
+
+    scenario.save(&path).unwrap();
+    let loaded = Scenario::resolve(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.name, scenario.name);
+    assert_eq!(loaded.landmarks.len(), scenario.landmarks.len());
+    assert_eq!(loaded.boundary, scenario.boundary);
+    assert_eq!(loaded.controls, scenario.controls);
+    assert_eq!(loaded.criteria, scenario.criteria);
+    assert_eq!(loaded.robots, scenario.robots);
+}
+
+#[test]
+fn scenario_files_only_need_a_name() {
+    let path = std::env::temp_dir().join(format!("slam_scenario_minimal_{}.json", std::process::id()));
+    std::fs::write(&path, r#"{ "name": "pair", "robots": [{ "name": "b", "start": [5.0, 0.0, 0.0], "control": { "Keyboard": "Arrows" } }] }"#).unwrap();
+    let loaded = Scenario::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(loaded.landmarks.is_empty() && loaded.controls.is_empty() && loaded.boundary.is_none());
+    assert_eq!(loaded.robots[0].start, Pose2::new(5.0, 0.0, 0.0));
+    assert_eq!(loaded.robots[0].control, ControlSource::Keyboard(KeySet::Arrows));
+    assert_eq!(loaded.robots[0].sensor, SensorOverrides::default());
+
+    assert!(Scenario::resolve("missing_scenario").is_err());
+    assert!(Scenario::resolve("square_loop").is_ok());
+}
+
+#[test]
+fn robots_answer_to_their_own_control_source() {
+    let cfg = Config::default();
+    let mut fleet = Fleet::new(&[
+        spec("numpad", Pose2::new(0.0, 0.0, 0.0), ControlSource::Keyboard(KeySet::Numpad)),
+        spec("arrows", Pose2::new(0.0, 100.0, 0.0), ControlSource::Keyboard(KeySet::Arrows)),
+        spec("pad", Pose2::new(0.0, 200.0, 0.0), ControlSource::Gamepad(0)),
+        spec("script", Pose2::new(0.0, 300.0, 0.0), ControlSource::Script(vec![ControlSegment { duration: 1.0, linear: 30.0, angular: 0.0 }])),
+    ]);
+
+    for _ in 0..60 {
+        fleet.step(scenario::TIME_STEP, &cfg, &World::default(), |key| key == KeyCode::Kp8);
+    }
+    let moved = |index: usize| fleet.members[index].robot.x;
+    assert!(moved(0) > 5.0, "the numpad robot drives forward");
+    assert_eq!(moved(1), 0.0, "the arrow keys weren't pressed");
+    assert_eq!(moved(2), 0.0, "gamepads aren't read");
+    assert!(moved(3) > 5.0, "the script drives its robot");
+
+    // once the script runs out, its robot comes to rest
+    for _ in 0..600 {
+        fleet.step(scenario::TIME_STEP, &cfg, &World::default(), |_| false);
+    }
+    assert!(fleet.members[3].robot.linear_velocity.abs() < 0.1);
+}
+
+#[test]
+fn each_robot_senses_with_its_own_sensor() {
+    let cfg = Config { detection_probability: 1.0, outlier_probability: 0.0, ..Config::default() };
+    let mut world = World::new(&cfg);
+    world.landmarks = Scenario::square_loop().landmarks;
+
+    let mut blind = spec("blind", Pose2::new(0.0, 0.0, 0.0), ControlSource::Gamepad(0));
+    blind.sensor.range = Some(1.0);
+    let sighted = spec("sighted", Pose2::new(0.0, 0.0, 0.0), ControlSource::Gamepad(0));
+    let fleet = Fleet::new(&[blind, sighted]);
+
+    let observations = fleet.sense(&world, &cfg);
+    assert!(observations[0].is_empty());
+    assert!(!observations[1].is_empty());
+    assert_eq!(fleet.members[0].spec.sensor_config(&cfg).sensor_range, 1.0);
+}
+
+#[test]
+fn declared_robots_run_alongside_headless_scenarios() {
+    let cfg = Config::default();
+    let mut scenario = Scenario::corridor();
+    scenario.robots = vec![spec("follower", Pose2::new(-50.0, 0.0, 0.0), ControlSource::Script(scenario.controls.clone()))];
+
+    // the other robot takes its own share of the motion noise, but the run still holds up
+    let report = scenario::run(&scenario, &mut EkfSlam::new(), 3, &cfg);
+    assert_eq!(report.frames, scenario::run(&Scenario::corridor(), &mut EkfSlam::new(), 3, &cfg).frames);
+    assert!(scenario.criteria.failures(&report).is_empty(), "{:?}", scenario.criteria.failures(&report));
+}
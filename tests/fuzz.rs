@@ -0,0 +1,40 @@
+use slam_simulator::config::Config;
+use slam_simulator::fuzz;
+use slam_simulator::math::Pose2;
+use slam_simulator::simulation::Observation;
+use slam_simulator::slam::{EkfSlam, SlamFilter};
+
+#[test]
+fn filters_survive_random_worlds() {
+    let failures = fuzz::soak(0, 200);
+
+    for failure in &failures {
+        let scenario = &failure.case.scenario;
+        println!(
+            "seed {}: {} (minimized to {} landmarks, {} obstructions, {} segments)",
+            failure.case.seed,
+            failure.error,
+            scenario.landmarks.len(),
+            scenario.obstructions.len(),
+            scenario.controls.len()
+        );
+    }
+
+    assert!(failures.is_empty(), "{} of 200 fuzz cases failed", failures.len());
+}
+
+#[test]
+fn singular_innovation_covariance_skips_the_correction() {
+    // a filter certain of its pose and its sensor maps landmarks with zero
+    // covariance, so re-observing one gives a zero innovation covariance
+    let cfg = Config { est_stdev_range: 0.0, est_stdev_bearing: 0.0, ..Config::default() };
+    let mut ekf = EkfSlam::with_prior(Pose2::IDENTITY, 0.0, 0.0);
+    let observation = Observation { id: 0, range: 50.0, bearing: 0.3 };
+
+    ekf.update(std::slice::from_ref(&observation), &cfg);
+    ekf.update(&[Observation { range: 52.0, ..observation }], &cfg);
+
+    assert_eq!(ekf.check_invariants(), Ok(()));
+    assert_eq!(ekf.estimated_map().len(), 1);
+    assert_eq!(fuzz::check_estimate(&ekf), Ok(()));
+}
@@ -0,0 +1,69 @@
+use slam_simulator::config::Config;
+use slam_simulator::gating::{GatePoint, GateSample, collect, recommend, sweep, thresholds};
+use slam_simulator::math::Pose2;
+use slam_simulator::recording::{FrameInputs, Recording};
+use slam_simulator::simulation::Observation;
+
+fn sample(true_distance: f32, impostor_distance: Option<f32>) -> GateSample {
+    GateSample { true_distance, impostor_distance }
+}
+
+#[test]
+fn thresholds_are_log_spaced() {
+    let values = thresholds(1.0, 100.0, 3);
+    assert!((values[0] - 1.0).abs() < 1e-4 && (values[1] - 10.0).abs() < 1e-3 && (values[2] - 100.0).abs() < 1e-2, "{values:?}");
+}
+
+#[test]
+fn sweep_counts_acceptances_and_wrong_associations() {
+    let samples = [
+        sample(1.0, None),
+        sample(2.0, Some(30.0)),
+        sample(8.0, Some(6.0)), // a wrong landmark is closer
+        sample(20.0, Some(50.0)),
+    ];
+    let points = sweep(&samples, &[0.5, 5.0, 7.0, 25.0, 60.0]);
+
+    let rates: Vec<(f32, f32)> = points.iter().map(|point| (point.acceptance, point.error_rate)).collect();
+    assert_eq!(rates, vec![(0.0, 0.0), (0.5, 0.0), (0.5, 0.25), (1.0, 0.25), (1.0, 0.25)]);
+}
+
+#[test]
+fn recommendation_balances_rejections_and_errors() {
+    let samples = [
+        sample(1.0, None),
+        sample(3.0, Some(40.0)),
+        sample(4.0, None),
+        sample(30.0, Some(12.0)), // only accepted once an impostor is too
+    ];
+    let points = sweep(&samples, &thresholds(0.5, 100.0, 50));
+    let best = recommend(&points).unwrap();
+
+    // every good one in, no wrong association yet: the smallest gate past 4
+    assert!(best.threshold >= 4.0 && best.threshold < 12.0, "{best:?}");
+    assert_eq!((best.acceptance, best.error_rate), (0.75, 0.0));
+
+    assert_eq!(recommend(&[]), None::<GatePoint>);
+}
+
+#[test]
+fn samples_come_from_mapped_landmarks_only() {
+    let cfg = Config::default();
+    let mut recording = Recording::new(&["ekf"]).starting_from(vec![Pose2::IDENTITY]);
+    assert!(collect(&recording, &cfg).is_err());
+
+    // a robot standing still between two landmarks, seeing both every frame
+    let observations = vec![Observation { id: 0, range: 50.0, bearing: 0.0 }, Observation { id: 1, range: 50.0, bearing: 3.0 }];
+    for frame in 0..4 {
+        recording.push(frame as f32, Pose2::IDENTITY, vec![Pose2::IDENTITY]);
+        recording.push_inputs(FrameInputs { delta_time: 0.1, odometry: Some((0.0, 0.0)), observations: observations.clone() });
+    }
+
+    // the first frame maps them, the other three measure them
+    let samples = collect(&recording, &cfg).unwrap();
+    assert_eq!(samples.len(), 6);
+    for sample in samples {
+        assert!(sample.true_distance < 1.0, "{sample:?}");
+        assert!(sample.impostor_distance.unwrap() > 100.0, "{sample:?}");
+    }
+}
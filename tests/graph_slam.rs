@@ -0,0 +1,77 @@
+use slam_simulator::config::Config;
+use slam_simulator::math::Pose2;
+use slam_simulator::scenario::{self, Scenario};
+use slam_simulator::simulation::Observation;
+use slam_simulator::slam::{GraphSlam, SlamFilter};
+
+const LANDMARKS: [(f32, f32); 6] = [(0.0, 60.0), (60.0, -50.0), (110.0, 70.0), (160.0, -60.0), (210.0, 50.0), (250.0, -40.0)];
+
+/*
+ * drives straight along x at 40 units/s for five seconds while odometry
+ * reports `odometry_scale` times the true speed, measuring every landmark
+ * exactly from the true pose
+ */
+fn drive(odometry_scale: f32, cfg: &Config) -> GraphSlam {
+    let mut graph = GraphSlam::new();
+    let delta_time = scenario::TIME_STEP;
+    for step in 1..=300 {
+        graph.predict(40.0 * odometry_scale, 0.0, delta_time, cfg);
+        let x = 40.0 * delta_time * step as f32;
+        let observations: Vec<Observation> = LANDMARKS.iter()
+            .enumerate()
+            .map(|(id, &(landmark_x, landmark_y))| {
+                let (range, bearing) = Pose2::new(x, 0.0, 0.0).range_bearing((landmark_x, landmark_y));
+                Observation { id, range, bearing }
+            })
+            .collect();
+        graph.update(&observations, cfg);
+    }
+    graph
+}
+
+#[test]
+fn keyframes_follow_the_configured_spacing() {
+    let cfg = Config::default();
+    let graph = drive(1.0, &cfg);
+
+    // the prior's keyframe, one for the first sightings a step later, then one every 20 units of the 200 driven
+    assert_eq!(graph.poses.len(), 1 + (200.0 / cfg.graph_keyframe_distance) as usize);
+    assert_eq!(graph.odometry.len(), graph.poses.len() - 1);
+    assert_eq!(graph.measurements.len(), LANDMARKS.len() * (graph.poses.len() - 1));
+    assert_eq!(graph.estimated_map().len(), LANDMARKS.len());
+}
+
+#[test]
+fn optimization_overrules_biased_odometry() {
+    // odometry that admits to being rough, and a sharp sensor
+    let cfg = Config { est_stdev_linear: 0.2, est_stdev_range: 0.5, est_stdev_bearing: 0.01, ..Config::default() };
+    let mut graph = drive(1.1, &cfg);
+    graph.optimize(&cfg);
+
+    // odometry alone would put the robot 20 units ahead
+    let Pose2 { x, y, theta } = graph.estimated_pose();
+    assert!((x - 200.0).abs() < 5.0 && y.abs() < 2.0 && theta.abs() < 0.02, "{x} {y} {theta}");
+    for (id, landmark_x, landmark_y) in graph.estimated_map() {
+        let (true_x, true_y) = LANDMARKS[id];
+        assert!((landmark_x - true_x).hypot(landmark_y - true_y) < 5.0, "landmark {id} at {landmark_x} {landmark_y}");
+        assert!(graph.landmark_covariance(id).is_some_and(|covariance| covariance.trace() > 0.0));
+    }
+
+    // the keyframes march along x, spaced by what the landmarks say rather than the odometry
+    let trajectory = graph.trajectory();
+    assert_eq!(trajectory.len(), graph.poses.len() + 1);
+    assert!(trajectory.windows(2).all(|pair| pair[1].0 >= pair[0].0 - 1.0));
+}
+
+#[test]
+fn graph_slam_tracks_the_square_loop() {
+    let cfg = Config::default();
+    for seed in 0..3 {
+        let mut graph = GraphSlam::new();
+        let report = scenario::run(&Scenario::square_loop(), &mut graph, seed, &cfg);
+        assert!(report.ate < 8.0, "seed {seed}: ATE {}", report.ate);
+        assert!(report.mean_nees < 8.0, "seed {seed}: NEES {}", report.mean_nees);
+        assert_eq!(report.landmarks_initialized, 12);
+        assert!(graph.iterations > 0 && graph.iterations <= cfg.graph_max_iterations);
+    }
+}
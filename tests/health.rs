@@ -0,0 +1,72 @@
+use slam_simulator::config::Config;
+use slam_simulator::health::{Health, LocalizationHealth};
+use slam_simulator::math::Pose2;
+use slam_simulator::simulation::Observation;
+use slam_simulator::slam::{EkfSlam, SlamFilter};
+use slam_simulator::utils::sample_normal;
+
+const DELTA_TIME: f32 = 1.0 / 60.0;
+const LANDMARKS: [(f32, f32); 4] = [(80.0, 0.0), (0.0, 90.0), (-70.0, -20.0), (30.0, -100.0)];
+
+/*
+ * a robot standing at the origin that measures every landmark each frame with
+ * the given noise, for `frames` frames, then drives blind for `blind_frames`
+ */
+fn run(cfg: &Config, noise: (f32, f32), frames: usize, blind_frames: usize) -> (EkfSlam, LocalizationHealth) {
+    macroquad::rand::srand(3);
+    let mut ekf = EkfSlam::new();
+    let mut health = LocalizationHealth::new();
+    let mut time = 0.0;
+    for frame in 0..frames + blind_frames {
+        time += DELTA_TIME;
+        let observations: Vec<Observation> = if frame < frames {
+            ekf.predict(0.0, 0.0, DELTA_TIME, cfg);
+            LANDMARKS.iter().enumerate().map(|(id, &point)| {
+                let (range, bearing) = Pose2::IDENTITY.range_bearing(point);
+                Observation { id, range: range + sample_normal(0.0, noise.0), bearing: bearing + sample_normal(0.0, noise.1) }
+            }).collect()
+        } else {
+            ekf.predict(60.0, 0.3, DELTA_TIME, cfg);
+            Vec::new()
+        };
+        health.record(time, &ekf, &observations, cfg);
+        ekf.update(&observations, cfg);
+    }
+    (ekf, health)
+}
+
+#[test]
+fn well_observed_filter_is_trusted() {
+    let cfg = Config::default();
+    let (ekf, health) = run(&cfg, (cfg.real_stdev_range, cfg.real_stdev_bearing), 300, 0);
+    let report = health.report(&ekf, &cfg).unwrap();
+    println!("{report:?}");
+    let nis = report.mean_nis.unwrap();
+    assert!(nis > 1.0 && nis < 4.0, "consistent filter has NIS {nis}");
+    assert_eq!(report.health(), Health::Good);
+}
+
+#[test]
+fn overconfident_or_blind_filter_is_not() {
+    // measurements ten times noisier than the filter models
+    let cfg = Config::default();
+    let (ekf, health) = run(&cfg, (10.0 * cfg.real_stdev_range, 10.0 * cfg.real_stdev_bearing), 300, 0);
+    let report = health.report(&ekf, &cfg).unwrap();
+    assert!(report.mean_nis.unwrap() > 10.0 * cfg.health_max_nis, "{report:?}");
+    assert_eq!(report.health(), Health::Poor);
+
+    // nor one that has driven out of sight of every landmark
+    let (ekf, health) = run(&cfg, (cfg.real_stdev_range, cfg.real_stdev_bearing), 300, 600);
+    let report = health.report(&ekf, &cfg).unwrap();
+    assert_eq!(report.measurement_rate, 0.0);
+    assert!(report.mean_nis.is_none());
+    assert_eq!(report.health(), Health::Poor);
+}
+
+#[test]
+fn score_is_the_weakest_part() {
+    assert_eq!(Health::from_score(1.0), Health::Good);
+    assert_eq!(Health::from_score(0.45), Health::Fair);
+    assert_eq!(Health::from_score(0.0), Health::Poor);
+    assert!(Health::Good > Health::Fair && Health::Fair > Health::Poor);
+}
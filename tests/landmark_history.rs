@@ -0,0 +1,53 @@
+use slam_simulator::config::Config;
+use slam_simulator::metrics::LandmarkHistory;
+use slam_simulator::scenario::{self, Scenario, TIME_STEP};
+use slam_simulator::simulation::Observation;
+use slam_simulator::slam::{EkfSlam, SlamFilter};
+
+#[test]
+fn history_is_capped_and_uncertainty_shrinks_with_sightings() {
+    let cfg = Config::default();
+    let mut history = LandmarkHistory::new(600);
+    let mut frame = 0;
+    scenario::run_observed(&Scenario::square_loop(), &mut EkfSlam::new(), 1, &cfg, |ekf, _| {
+        frame += 1;
+        history.record(frame as f32 * TIME_STEP, ekf);
+        Ok(())
+    }).unwrap();
+
+    assert_eq!(history.landmarks.len(), 12);
+    for (&id, samples) in &history.landmarks {
+        assert!(samples.len() <= history.capacity);
+        assert!(samples.iter().zip(samples.iter().skip(1)).all(|(a, b)| b.time > a.time), "landmark {id} out of order");
+    }
+
+    // the first landmark is seen from the start, so its whole history is its last 600 frames
+    let series = history.series(0, |sample| sample.trace);
+    assert_eq!(series.len(), 600);
+    assert!(series.iter().all(|(_, trace)| trace.is_finite()));
+    assert!(history.series(99, |sample| sample.x).is_empty());
+    assert!(history.largest_jump(99).is_none());
+}
+
+#[test]
+fn a_bad_update_shows_as_the_largest_jump() {
+    let cfg = Config::default();
+    let mut ekf = EkfSlam::new();
+    let mut history = LandmarkHistory::new(100);
+
+    for frame in 0..40 {
+        // frame 30 reads the landmark well off to the side, as a misassociation would
+        let bearing = if frame == 30 { 0.4 } else { 0.0 };
+        ekf.update(&[Observation { id: 0, range: 50.0, bearing }], &cfg);
+        history.record(frame as f32, &ekf);
+    }
+
+    let (time, distance) = history.largest_jump(0).unwrap();
+    assert_eq!(time, 30.0);
+    assert!(distance > 0.3, "{distance}");
+    let samples = history.samples(0).unwrap();
+    assert!(samples.back().unwrap().trace < samples.front().unwrap().trace);
+
+    history.clear();
+    assert!(history.samples(0).is_none());
+}
@@ -0,0 +1,58 @@
+use slam_simulator::app::layout::{Anchor, HudLayout, Panel};
+
+const SCREEN: (f32, f32) = (1000.0, 800.0);
+
+#[test]
+fn panels_start_at_their_default_corners() {
+    let layout = HudLayout::new();
+
+    // top left, clear of the settings cog, rows running down
+    let first = layout.row(Panel::Readouts, 0.0, 3.0, 100.0, 30.0, SCREEN);
+    let second = layout.row(Panel::Readouts, 1.0, 3.0, 100.0, 30.0, SCREEN);
+    assert_eq!((first.x, first.y), (45.0, 20.0));
+    assert_eq!(second.y - first.y, 30.0);
+
+    // bottom right: the block ends at the margin and lines end at the right edge
+    let last = layout.row(Panel::Legend, 2.0, 3.0, 100.0, 30.0, SCREEN);
+    assert_eq!((last.x, last.y + 30.0), (880.0, 780.0));
+}
+
+#[test]
+fn moved_panels_follow_their_anchor() {
+    let mut layout = HudLayout::new();
+
+    layout.set_anchor(Panel::Mission, Anchor::Top);
+    let at = layout.row(Panel::Mission, 0.0, 4.0, 200.0, 30.0, SCREEN);
+    assert_eq!((at.x, at.y), (400.0, 20.0));
+
+    layout.set_anchor(Panel::Mission, Anchor::Left);
+    let at = layout.row(Panel::Mission, 0.0, 4.0, 200.0, 30.0, SCREEN);
+    assert_eq!((at.x, at.y), (20.0, 340.0));
+
+    // the margin grows with the scale
+    layout.set_scale(2.0);
+    layout.set_anchor(Panel::Mission, Anchor::BottomRight);
+    let at = layout.row(Panel::Mission, 0.0, 1.0, 200.0, 60.0, SCREEN);
+    assert_eq!((at.x, at.y), (760.0, 700.0));
+}
+
+#[test]
+fn scale_is_clamped() {
+    let mut layout = HudLayout::new();
+    layout.set_scale(10.0);
+    assert_eq!(layout.scale, HudLayout::MAX_SCALE);
+    layout.set_scale(0.1);
+    assert_eq!(layout.scale, HudLayout::MIN_SCALE);
+}
+
+#[test]
+fn anchors_cycle_around_the_screen() {
+    let mut anchor = Anchor::TopLeft;
+    let mut seen = Vec::new();
+    for _ in 0..Anchor::ALL.len() {
+        seen.push(anchor);
+        anchor = anchor.next();
+    }
+    assert_eq!(anchor, Anchor::TopLeft);
+    assert_eq!(seen, Anchor::ALL);
+}
@@ -0,0 +1,73 @@
+use std::collections::HashSet;
+
+use slam_simulator::app::layout::{Anchor, Panel};
+use slam_simulator::app::input::Tool;
+use slam_simulator::app::locale::Language;
+use slam_simulator::events::EventKind;
+
+// the sources whose `tr("...")` calls put text on screen
+const HUD_SOURCES: [&str; 3] = [
+    include_str!("../src/app/hud.rs"),
+    include_str!("../src/app/summary_view.rs"),
+    include_str!("../src/main.rs"),
+];
+
+/*
+ * string literals passed straight to `tr`
+ */
+fn translated_literals() -> Vec<&'static str> {
+    HUD_SOURCES.iter()
+        .flat_map(|source| source.split(".tr(\"").skip(1))
+        .map(|rest| &rest[..rest.find("\")").unwrap()])
+        .collect()
+}
+
+#[test]
+fn english_is_the_text_itself() {
+    assert_eq!(Language::English.tr("Tool"), "Tool");
+    assert_eq!(Language::Spanish.tr("Tool"), "Herramienta");
+    assert_eq!(Language::Spanish.tr("not in any table"), "not in any table", "falls back to English");
+}
+
+#[test]
+fn switching_cycles_through_every_language() {
+    let mut language = Language::default();
+    let mut seen = HashSet::new();
+    for _ in 0..Language::ALL.len() {
+        assert!(seen.insert(language), "{language:?} came up twice");
+        language = language.next();
+    }
+    assert_eq!(language, Language::default());
+}
+
+#[test]
+fn tables_have_one_nonempty_entry_per_string() {
+    for language in Language::ALL {
+        let mut keys = HashSet::new();
+        for (english, translated) in language.table() {
+            assert!(keys.insert(english), "{language:?} translates {english:?} twice");
+            assert!(!translated.trim().is_empty(), "{language:?} translates {english:?} to nothing");
+        }
+    }
+}
+
+#[test]
+fn every_hud_string_is_translated() {
+    let literals = translated_literals();
+    assert!(literals.len() > 50, "only found {} strings", literals.len());
+
+    // the ones handed to `tr` through a variable
+    let indirect = [
+        Tool::Obstruction, Tool::Goal, Tool::Navigate, Tool::Inject, Tool::Select, Tool::Anchor, Tool::Dropout, Tool::Scatter,
+    ].map(|tool| tool.label()).into_iter()
+        .chain(EventKind::ALL.map(|kind| kind.label()))
+        .chain(Panel::ALL.map(Panel::label))
+        .chain(Anchor::ALL.map(Anchor::label))
+        .chain(["in progress", "complete", "event queued", "events queued"]);
+
+    for language in Language::ALL.into_iter().filter(|&language| language != Language::English) {
+        let keys: HashSet<_> = language.table().iter().map(|(english, _)| *english).collect();
+        let missing: Vec<_> = literals.iter().copied().chain(indirect.clone()).filter(|text| !keys.contains(text)).collect();
+        assert!(missing.is_empty(), "{language:?} is missing {missing:?}");
+    }
+}
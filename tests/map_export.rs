@@ -0,0 +1,29 @@
+use slam_simulator::config::Config;
+use slam_simulator::map_export::ProbabilityMap;
+use slam_simulator::scenario::{self, Scenario};
+use slam_simulator::slam::{EkfSlam, SlamFilter};
+
+#[test]
+fn exported_map_marks_landmarks_occupied() {
+    let cfg = Config::default();
+    let mut ekf = EkfSlam::new();
+    assert!(ProbabilityMap::from_slam(&ekf, &cfg).is_none(), "nothing mapped yet");
+    scenario::run(&Scenario::square_loop(), &mut ekf, 7, &cfg);
+
+    let map = ProbabilityMap::from_slam(&ekf, &cfg).unwrap();
+    assert_eq!(map.cells.len(), map.width * map.height);
+    for (_, x, y) in ekf.estimated_map() {
+        assert!(map.probability_at(x, y).unwrap() > 0.65, "landmark at ({x}, {y}) should be occupied");
+    }
+    // the corners lie three standard deviations past every landmark
+    assert!(map.probability_at(map.origin.0 + 0.1, map.origin.1 + 0.1).unwrap() < 0.196);
+
+    let path = std::env::temp_dir().join("slam_simulator_map_export").join("map.pgm");
+    map.save(&path).unwrap();
+    let image = std::fs::read(&path).unwrap();
+    let header = format!("P5\n{} {}\n255\n", map.width, map.height);
+    assert!(image.starts_with(header.as_bytes()));
+    assert_eq!(image.len(), header.len() + map.width * map.height);
+    let metadata = std::fs::read_to_string(path.with_extension("yaml")).unwrap();
+    assert!(metadata.contains("image: map.pgm") && metadata.contains("resolution: 2"));
+}
@@ -0,0 +1,57 @@
+use slam_simulator::config::Config;
+use slam_simulator::math::Pose2;
+use slam_simulator::metrics::{self, hungarian};
+use slam_simulator::scenario::{self, Scenario};
+use slam_simulator::slam::MultiHypothesisEkf;
+
+#[test]
+fn hungarian_finds_the_optimal_assignment() {
+    // greedy would take the 1 in row 0 and be left with 100 in row 1
+    let cost = vec![
+        vec![1.0, 2.0, 50.0],
+        vec![3.0, 100.0, 50.0],
+    ];
+    assert_eq!(hungarian(&cost), vec![1, 0]);
+
+    // every permutation of a 4x4, brute forced
+    let cost: Vec<Vec<f32>> = (0..4).map(|i| (0..4).map(|j| ((i * 7 + j * 13) % 11) as f32).collect()).collect();
+    let assignment = hungarian(&cost);
+    let total = |columns: &[usize]| columns.iter().enumerate().map(|(row, &column)| cost[row][column]).sum::<f32>();
+    let mut best = f32::MAX;
+    for a in 0..4 { for b in 0..4 { for c in 0..4 { for d in 0..4 {
+        let columns = [a, b, c, d];
+        if (0..4).all(|column| columns.contains(&column)) { best = best.min(total(&columns)); }
+    } } } }
+    assert_eq!(total(&assignment), best);
+}
+
+#[test]
+fn map_quality_ignores_ids_and_counts_leftovers() {
+    let truth = [(0, 0.0, 0.0), (1, 100.0, 0.0), (2, 200.0, 0.0)];
+    // ids shuffled, one landmark missed, one estimate far from anything
+    let estimated = [(7, 103.0, 4.0), (3, -3.0, -4.0), (9, 500.0, 500.0)];
+
+    let quality = metrics::map_quality(&estimated, &truth, 30.0);
+    assert_eq!(quality.matched, 2);
+    assert_eq!(quality.unmatched, 1);
+    assert_eq!(quality.spurious, 1);
+    assert!((quality.rmse - 5.0).abs() < 1e-4);
+    let mut pairs = quality.pairs.clone();
+    pairs.sort();
+    assert_eq!(pairs, vec![(3, 0), (7, 1)]);
+
+    // more estimates than true landmarks
+    let quality = metrics::map_quality(&truth, &truth[..1], 30.0);
+    assert_eq!((quality.matched, quality.unmatched, quality.spurious), (1, 0, 2));
+}
+
+#[test]
+fn mht_map_matches_the_truth_without_ids() {
+    let cfg = Config::default();
+    let mut mht = MultiHypothesisEkf::with_prior(Pose2::IDENTITY, cfg.initial_stdev_position, cfg.initial_stdev_heading, cfg.max_hypotheses);
+    let report = scenario::run(&Scenario::square_loop(), &mut mht, 7, &cfg);
+
+    assert_eq!(report.map.matched, 12);
+    assert_eq!((report.map.unmatched, report.map.spurious), (0, 0));
+    assert!(report.map.rmse < 30.0, "map rmse {}", report.map.rmse);
+}
@@ -0,0 +1,54 @@
+use std::f32::consts::PI;
+use slam_simulator::math::{Pose2, normalize_angle};
+
+fn assert_pose_eq(a: Pose2, b: Pose2) {
+    assert!(
+        (a.x - b.x).abs() < 1e-3 && (a.y - b.y).abs() < 1e-3 && normalize_angle(a.theta - b.theta).abs() < 1e-4,
+        "{a:?} != {b:?}"
+    );
+}
+
+#[test]
+fn angles_are_wrapped_to_a_half_open_turn() {
+    for angle in [0.0, 3.0, -3.0, 7.0, -7.0, 4.0 * PI + 0.5, -9.0 * PI + 0.1] {
+        let wrapped = normalize_angle(angle);
+        assert!(wrapped > -PI - 1e-6 && wrapped <= PI + 1e-6, "{angle} wrapped to {wrapped}");
+        assert!((wrapped.sin() - angle.sin()).abs() < 1e-4 && (wrapped.cos() - angle.cos()).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn between_undoes_compose() {
+    let a = Pose2::new(12.0, -4.0, 2.8);
+    let b = Pose2::new(-30.0, 8.5, -2.9);
+    assert_pose_eq(a.compose(&a.between(&b)), b);
+    assert_pose_eq(a.compose(&a.inverse()), Pose2::IDENTITY);
+    assert_pose_eq(a.inverse().inverse(), a);
+
+    // the composed heading wraps instead of running past PI
+    assert!(a.compose(&b).theta.abs() <= PI);
+}
+
+#[test]
+fn matrices_compose_like_poses() {
+    let a = Pose2::new(3.0, 1.0, 0.7);
+    let b = Pose2::new(-2.0, 5.0, -1.9);
+    assert_pose_eq(Pose2::from_matrix(&(a.to_matrix() * b.to_matrix())), a.compose(&b));
+    assert_pose_eq(Pose2::from_vector(&a.to_vector()), a);
+}
+
+#[test]
+fn range_and_bearing_round_trip() {
+    let pose = Pose2::new(5.0, -2.0, -2.5);
+    let point = (40.0, 18.0);
+    let (range, bearing) = pose.range_bearing(point);
+    let (x, y) = pose.from_range_bearing(range, bearing);
+    assert!((x - point.0).abs() < 1e-3 && (y - point.1).abs() < 1e-3);
+
+    // a point straight ahead is at bearing zero in the pose's own frame
+    let ahead = pose.transform_point((10.0, 0.0));
+    let (range, bearing) = pose.range_bearing(ahead);
+    assert!((range - 10.0).abs() < 1e-3 && bearing.abs() < 1e-4);
+    let (x, y) = pose.inverse_transform_point(ahead);
+    assert!((x - 10.0).abs() < 1e-3 && y.abs() < 1e-3);
+}
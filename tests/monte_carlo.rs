@@ -0,0 +1,25 @@
+use slam_simulator::config::Config;
+use slam_simulator::monte_carlo;
+use slam_simulator::scenario::{Scenario, TIME_STEP};
+use slam_simulator::slam::{EkfSlam, RobocentricEkf};
+
+#[test]
+fn world_centric_ekf_is_overconfident_next_to_robocentric() {
+    let cfg = Config::default();
+    let scenario = Scenario::square_loop();
+
+    let world_centric = monte_carlo::validate(&scenario, |pose| EkfSlam::with_prior(pose, cfg.initial_stdev_position, cfg.initial_stdev_heading), 0..10, 60, &cfg);
+    let robocentric = monte_carlo::validate(&scenario, |pose| RobocentricEkf::with_prior(pose, cfg.initial_stdev_position, cfg.initial_stdev_heading), 0..10, 60, &cfg);
+    println!("actual / reported variance: world-centric {:?}, robocentric {:?}", world_centric.variance_ratios(), robocentric.variance_ratios());
+
+    assert_eq!(world_centric.runs, 10);
+    assert_eq!(world_centric.samples.len(), (scenario.duration() / TIME_STEP / 60.0).ceil() as usize);
+
+    // position errors spread wider than the world-centric filter admits
+    let world_ratios = world_centric.variance_ratios();
+    let robocentric_ratios = robocentric.variance_ratios();
+    for state in 0..2 {
+        assert!(world_ratios[state] > 1.0, "state {state}: {}", world_ratios[state]);
+        assert!(robocentric_ratios[state] < world_ratios[state], "state {state}: {}", robocentric_ratios[state]);
+    }
+}
@@ -0,0 +1,41 @@
+use std::f32::consts::PI;
+
+use slam_simulator::math::Pose2;
+use slam_simulator::motion::Integrator;
+
+/*
+ * drives a quarter circle of radius 100 in `steps` steps and returns how far
+ * from the true end point (100, 100) it ends up
+ */
+fn quarter_circle_error(integrator: Integrator, steps: usize) -> f32 {
+    let (linear, angular) = (100.0, 1.0);
+    let delta_time = 0.5 * PI / steps as f32;
+
+    let mut pose = Pose2::IDENTITY;
+    for _ in 0..steps {
+        pose = integrator.step(pose, linear, angular, delta_time);
+    }
+
+    (pose.x - 100.0).hypot(pose.y - 100.0)
+}
+
+#[test]
+fn integrators_agree_on_straight_lines() {
+    for integrator in Integrator::ALL {
+        let Pose2 { x, y, theta } = integrator.step(Pose2::new(1.0, 2.0, PI / 4.0), 10.0, 0.0, 0.5);
+        assert!((x - (1.0 + 5.0 / 2f32.sqrt())).abs() < 1e-4, "{integrator:?} x {x}");
+        assert!((y - (2.0 + 5.0 / 2f32.sqrt())).abs() < 1e-4, "{integrator:?} y {y}");
+        assert!((theta - PI / 4.0).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn higher_order_integrators_follow_arcs_better() {
+    let euler = quarter_circle_error(Integrator::Euler, 16);
+    let midpoint = quarter_circle_error(Integrator::Midpoint, 16);
+    let rk4 = quarter_circle_error(Integrator::Rk4, 16);
+    let exact = quarter_circle_error(Integrator::ExactArc, 16);
+
+    assert!(exact < 1e-2, "exact arc misses by {exact}");
+    assert!(rk4 < midpoint && midpoint < euler, "euler {euler}, midpoint {midpoint}, rk4 {rk4}");
+}
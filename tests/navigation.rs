@@ -0,0 +1,38 @@
+use slam_simulator::navigation::planner::{BelievedMap, plan_path};
+use slam_simulator::scan_map::ScanMap;
+
+// lidar hits every 2 units along x = 50 for y in [-40, 40]
+fn scanned_wall() -> ScanMap {
+    let mut scan_map = ScanMap::new(100);
+    scan_map.points.extend((0..=40).map(|step| (50.0, -40.0 + 2.0 * step as f32)));
+    scan_map
+}
+
+#[test]
+fn paths_go_around_scanned_walls_and_keep_clear_of_them() {
+    let scan_map = scanned_wall();
+    let map = BelievedMap { landmarks: &[], landmark_radius: 6.0, scan_map: &scan_map };
+
+    let path = plan_path((5.0, 5.0), (95.0, 5.0), &map, 10.0, 5.0, 100.0).expect("a way around the wall");
+    assert_eq!(path.last(), Some(&(95.0, 5.0)));
+    assert!(path.iter().any(|&(_, y)| y.abs() > 40.0), "the path cuts through the wall: {path:?}");
+    for &(x, y) in &path[..path.len() - 1] {
+        assert!(scan_map.points.iter().all(|&(hit_x, hit_y)| (x - hit_x).hypot(y - hit_y) >= 10.0), "({x}, {y}) is too close to the wall");
+    }
+
+    // without the scan the planner knows of no wall and goes straight
+    let empty = ScanMap::new(100);
+    let map = BelievedMap { landmarks: &[], landmark_radius: 6.0, scan_map: &empty };
+    let path = plan_path((5.0, 5.0), (95.0, 5.0), &map, 10.0, 5.0, 100.0).unwrap();
+    assert!(path.iter().all(|&(_, y)| (y - 5.0).abs() < 5.0), "{path:?}");
+}
+
+#[test]
+fn goals_on_a_mapped_landmark_are_unreachable() {
+    let scan_map = ScanMap::new(100);
+    let landmarks = [(3, 60.0, 0.0)];
+    let map = BelievedMap { landmarks: &landmarks, landmark_radius: 6.0, scan_map: &scan_map };
+
+    assert!(plan_path((0.0, 0.0), (62.0, 1.0), &map, 10.0, 5.0, 100.0).is_none());
+    assert!(plan_path((0.0, 0.0), (0.0, 60.0), &map, 10.0, 5.0, 100.0).is_some());
+}
@@ -0,0 +1,42 @@
+use slam_simulator::config::Config;
+use slam_simulator::observability;
+use slam_simulator::scenario::{self, Scenario};
+use slam_simulator::slam::EkfSlam;
+
+#[test]
+fn weakest_directions_after_a_loop_are_the_gauge() {
+    let cfg = Config::default();
+    let mut ekf = EkfSlam::new();
+    scenario::run(&Scenario::square_loop(), &mut ekf, 7, &cfg);
+
+    // range-bearing measurements only constrain the map relative to the robot,
+    // so the three least constrained directions move everything rigidly
+    for direction in observability::weakest_directions(&ekf, 3) {
+        println!("stdev {:.3} translation {:.2} rotation {:.2}", direction.stdev, direction.translation_share, direction.rotation_share);
+        assert!(direction.translation_share + direction.rotation_share > 0.8);
+    }
+}
+
+#[test]
+fn anchoring_a_landmark_fixes_the_gauge() {
+    let cfg = Config::default();
+    let mut ekf = EkfSlam::new();
+    scenario::run(&Scenario::square_loop(), &mut ekf, 7, &cfg);
+    let before = ekf.clone();
+
+    assert!(ekf.anchor_landmark(0, cfg.anchor_stdev));
+    assert!(!ekf.anchor_landmark(99, cfg.anchor_stdev), "landmark 99 was never mapped");
+
+    // the anchor is pinned where it was, and with it the rigid motion of the whole map
+    let index = ekf.observed_landmarks.get(0).unwrap();
+    assert_eq!(ekf.state, before.state);
+    assert!(ekf.covariance.fixed_view::<2, 2>(index, index).trace() < 3.0 * cfg.anchor_stdev.powi(2));
+    let [translation_x, translation_y, _] = observability::gauge_directions(&ekf);
+    for direction in [translation_x, translation_y] {
+        let variance = |covariance: &nalgebra::DMatrix<f32>| (direction.transpose() * covariance * &direction)[(0, 0)];
+        println!("translation variance {:.2} -> {:.2}", variance(&before.covariance), variance(&ekf.covariance));
+        assert!(variance(&ekf.covariance) < 0.1 * variance(&before.covariance));
+    }
+    assert!(ekf.covariance.trace() < 0.1 * before.covariance.trace());
+    ekf.check_invariants().unwrap();
+}
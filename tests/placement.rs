@@ -0,0 +1,56 @@
+use slam_simulator::app::estimates::Estimates;
+use slam_simulator::app::input::Placement;
+use slam_simulator::config::Config;
+use slam_simulator::math::Pose2;
+use slam_simulator::simulation::{Observation, Robot};
+use slam_simulator::slam::{Backend, SlamFilter};
+
+#[test]
+fn typed_pose_round_trips_and_places_the_robot() {
+    let mut robot = Robot::new();
+    robot.linear_velocity = 30.0;
+
+    let mut placement = Placement::new(&robot);
+    assert_eq!(placement.text, "0.0 0.0 0.0");
+
+    placement.text = "120.5, -40 90".to_string();
+    let Pose2 { x, y, theta } = placement.pose().unwrap();
+    assert_eq!((x, y), (120.5, -40.0));
+    assert!((theta - std::f32::consts::FRAC_PI_2).abs() < 1e-6);
+
+    robot.place(x, y, theta);
+    assert_eq!((robot.x, robot.y, robot.theta, robot.linear_velocity), (x, y, theta, 0.0));
+    assert_eq!(Placement::new(&robot).text, "120.5 -40.0 90.0");
+
+    for text in ["1 2", "1 2 3 4", "1 - 2", ""] {
+        placement.text = text.to_string();
+        assert!(placement.pose().is_none(), "{text:?}");
+    }
+}
+
+#[test]
+fn restarting_the_filters_starts_everything_over_at_the_new_pose() {
+    let cfg = Config { fast_slam_particles: 10, ..Config::default() };
+    let mut estimates = Estimates::new(Robot::new().pose(), &cfg);
+    let observation = Observation { id: 0, range: 50.0, bearing: 0.3 };
+    estimates.ekf_mut().update(std::slice::from_ref(&observation), &cfg);
+    estimates.record_history(1.0, Robot::new().pose(), &cfg);
+    estimates.set_running(Backend::Ukf, true, Robot::new().pose(), &cfg);
+
+    let mut robot = Robot::new();
+    robot.place(120.0, -40.0, 1.0);
+    estimates.restart_filters(robot.pose(), &cfg);
+
+    // FastSLAM's particles scatter around the prior, so only its count is exact
+    let mut filters: Vec<&dyn SlamFilter> = estimates.filters.iter()
+        .filter(|(backend, _)| *backend != Backend::FastSlam)
+        .map(|(_, slam)| slam.as_ref())
+        .collect();
+    filters.extend([&estimates.graph_slam as &dyn SlamFilter, &estimates.odometry_baseline]);
+    for slam in filters {
+        assert_eq!(slam.estimated_pose(), Pose2::new(120.0, -40.0, 1.0));
+        assert!(slam.estimated_map().is_empty());
+    }
+    assert_eq!(estimates.running(Backend::FastSlam).particles().len(), 10);
+    assert!(!estimates.is_running(Backend::Ukf) && estimates.landmark_history.landmarks.is_empty());
+}
@@ -0,0 +1,26 @@
+use slam_simulator::math::Pose2;
+use slam_simulator::recording::{Bookmark, Recording};
+
+#[test]
+fn bookmarks_are_saved_with_the_recording() {
+    let path = std::env::temp_dir().join(format!("slam_recording_{}.csv", std::process::id()));
+
+    let mut recording = Recording::new(&["ekf"]);
+    recording.push(0.0, Pose2::IDENTITY, vec![Pose2::new(1.0, 0.0, 0.0)]);
+    recording.push(0.5, Pose2::new(1.0, 0.0, 0.0), vec![Pose2::new(1.0, 1.0, 0.1)]);
+    recording.bookmark(0.25, "divergence started here, maybe");
+    recording.bookmark(0.5, "");
+    recording.save(&path).unwrap();
+
+    let loaded = Recording::load(&path).unwrap();
+    assert_eq!(loaded.frames.len(), 2);
+    assert_eq!(loaded.bookmarks, vec![
+        Bookmark { time: 0.25, note: "divergence started here, maybe".to_string() },
+        Bookmark { time: 0.5, note: String::new() },
+    ]);
+
+    // recordings from before bookmarks load without any
+    std::fs::remove_file(Recording::bookmarks_path(&path)).unwrap();
+    assert!(Recording::load(&path).unwrap().bookmarks.is_empty());
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,28 @@
+use nalgebra::{DMatrix, DVector};
+use slam_simulator::slam::LandmarkRegistry;
+
+#[test]
+fn removal_closes_the_gap() {
+    let mut registry = LandmarkRegistry::new();
+    let mut state = DVector::from_column_slice(&[0.0, 1.0, 2.0]);
+    let mut covariance = DMatrix::identity(3, 3);
+
+    for (id, value) in [(7, 10.0), (3, 20.0), (9, 30.0)] {
+        let index = registry.insert(id, &mut [&mut state], &mut [&mut covariance]);
+        state[index] = value;
+        state[index + 1] = value + 1.0;
+        covariance[(index, index)] = value;
+    }
+    assert_eq!(registry.get(3), Some(5));
+    assert_eq!(registry.check_matrix(&covariance), Ok(()));
+
+    assert_eq!(registry.remove(3, &mut [&mut state], &mut [&mut covariance]), Some(5));
+    assert_eq!(registry.remove(3, &mut [&mut state], &mut [&mut covariance]), None);
+
+    // the landmark after the removed one moved up into its place, values and all
+    assert_eq!(registry.in_state_order(), vec![(7, 3), (9, 5)]);
+    assert_eq!(state.as_slice(), &[0.0, 1.0, 2.0, 10.0, 11.0, 30.0, 31.0]);
+    assert_eq!(covariance[(5, 5)], 30.0);
+    assert_eq!(registry.check(state.nrows()), Ok(()));
+    assert!(registry.check(state.nrows() + 2).is_err());
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use common::record;
+use slam_simulator::config::Config;
+use slam_simulator::recording::Recording;
+use slam_simulator::replay;
+use slam_simulator::scenario::Scenario;
+use slam_simulator::slam::EkfSlam;
+
+fn replay_ekf(recording: &Recording, cfg: &Config) -> Recording {
+    let start = recording.start[0];
+    let mut ekf = EkfSlam::with_prior(start, cfg.initial_stdev_position, cfg.initial_stdev_heading);
+    replay::replay(recording, &mut [("ekf", &mut ekf)], cfg).unwrap()
+}
+
+#[test]
+fn replay_with_unchanged_settings_reproduces_the_run_exactly() {
+    let cfg = Config::default();
+    let path = std::env::temp_dir().join(format!("slam_replay_{}.csv", std::process::id()));
+    record(&Scenario::square_loop(), 4, &cfg).save(&path).unwrap();
+    let original = Recording::load(&path).unwrap();
+    for file in [path.clone(), Recording::inputs_path(&path)] { std::fs::remove_file(file).unwrap(); }
+
+    let replayed = replay_ekf(&original, &cfg);
+    assert_eq!(replayed.frames.len(), original.frames.len());
+    let (before, after) = (original.error_summary(0), replayed.error_summary(0));
+    println!("RMSE {:.3} replayed {:.3}", before.rmse, after.rmse);
+    assert_eq!(before.rmse, after.rmse);
+    assert_eq!(replayed.frames.last().unwrap().estimates, original.frames.last().unwrap().estimates);
+}
+
+#[test]
+fn replay_reflects_changed_settings() {
+    let cfg = Config::default();
+    let original = record(&Scenario::square_loop(), 4, &cfg);
+
+    // a filter that barely trusts its measurements is left with dead reckoning
+    let distrustful = Config { est_stdev_range: 500.0, est_stdev_bearing: 3.0, ..Config::default() };
+    let replayed = replay_ekf(&original, &distrustful);
+    assert_ne!(replayed.frames.last().unwrap().estimates, original.frames.last().unwrap().estimates);
+
+    let without_inputs = Recording::new(&["ekf"]);
+    assert!(replay::replay(&without_inputs, &mut [("ekf", &mut EkfSlam::new())], &cfg).is_err());
+}
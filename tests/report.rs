@@ -0,0 +1,50 @@
+use slam_simulator::config::Config;
+use slam_simulator::math::Pose2;
+use slam_simulator::report::{self, Report, ReportSection};
+use slam_simulator::scenario::{self, Scenario};
+use slam_simulator::slam::{EkfSlam, FastSlam, OdometryBaseline, SlamFilter};
+
+#[test]
+fn report_is_self_contained_html() {
+    let cfg = Config::default();
+    let scenario = Scenario::corridor();
+    let filters: Vec<(&str, Box<dyn SlamFilter>)> = vec![("ekf", Box::new(EkfSlam::new())), ("odom <dr>", Box::new(OdometryBaseline::from_slam(&EkfSlam::new())))];
+    let section = ReportSection::from_scenario(&scenario, filters, 3, &cfg);
+
+    // one sample per interval, not per frame
+    let samples = &section.filters[0].samples;
+    let expected = scenario.duration() / cfg.report_sample_interval;
+    assert!((samples.len() as f32 - expected).abs() <= 2.0, "{} samples for {expected} intervals", samples.len());
+    assert!(samples.windows(2).all(|pair| pair[0].time < pair[1].time));
+
+    let html = Report::new("corridor & co", vec![section], &cfg).to_html();
+    assert!(html.starts_with("<!DOCTYPE html>") && html.trim_end().ends_with("</html>"));
+    assert!(html.contains("<title>corridor &amp; co</title>"));
+    assert!(html.contains("odom &lt;dr&gt;"), "names are escaped");
+
+    // two plots and the map, all inline
+    assert_eq!(html.matches("<svg").count(), 3);
+    assert!(html.contains("ATE (position RMSE)"));
+    assert!(html.contains("report_sample_interval"), "the configuration is included");
+    assert!(!html.contains("src=") && !html.contains("href="), "nothing is loaded from elsewhere");
+}
+
+#[test]
+fn escape_covers_markup() {
+    assert_eq!(report::escape("<a href=\"x\">&</a>"), "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;");
+}
+
+#[test]
+fn report_numbers_match_a_plain_run_with_the_same_seed() {
+    let cfg = Config::default();
+    let scenario = Scenario::corridor();
+    // FastSLAM draws its particles as it is made, so its prior has to be seeded alike too
+    let fast = || {
+        scenario::seed_prior(5);
+        FastSlam::with_prior(10, Pose2::IDENTITY, cfg.initial_stdev_position, cfg.initial_stdev_heading)
+    };
+
+    let section = ReportSection::from_scenario(&scenario, vec![("fast", Box::new(fast()))], 5, &cfg);
+    let run = scenario::run(&scenario, &mut fast(), 5, &cfg);
+    assert_eq!(section.filters[0].metrics, report::run_report_metrics(&run));
+}
@@ -0,0 +1,54 @@
+use std::f32::consts::FRAC_PI_2;
+
+use macroquad::prelude::Rect;
+use slam_simulator::config::Config;
+use slam_simulator::math::Pose2;
+use slam_simulator::scan_map::{ScanMap, believed_corners};
+use slam_simulator::simulation::Scan;
+
+fn close(a: (f32, f32), b: (f32, f32)) -> bool {
+    (a.0 - b.0).hypot(a.1 - b.1) < 1e-3
+}
+
+#[test]
+fn walls_move_with_the_pose_error() {
+    let rect = Rect::new(10.0, 0.0, 5.0, 20.0);
+    let truth = Pose2::IDENTITY;
+
+    // no error, no distortion
+    let corners = believed_corners(rect, truth, truth);
+    assert!(close(corners[0], (10.0, 0.0)) && close(corners[2], (15.0, 20.0)));
+
+    // taking itself to be turned a quarter left, the wall ahead appears to its left
+    let corners = believed_corners(rect, truth, Pose2::new(0.0, 0.0, FRAC_PI_2));
+    assert!(close(corners[0], (0.0, 10.0)), "{:?}", corners[0]);
+    assert!(close(corners[2], (-20.0, 15.0)), "{:?}", corners[2]);
+
+    // and offset, the wall shifts along
+    let corners = believed_corners(rect, Pose2::new(5.0, 5.0, 0.0), Pose2::new(8.0, 1.0, 0.0));
+    assert!(close(corners[0], (13.0, -4.0)), "{:?}", corners[0]);
+}
+
+#[test]
+fn scans_are_placed_from_the_estimate_and_thinned() {
+    let cfg = Config::default();
+    // one hit straight ahead at 50, the other beams out of range
+    let mut ranges = vec![cfg.lidar_range; 4];
+    ranges[2] = 50.0;
+    let scan = Scan { ranges, max_range: cfg.lidar_range };
+
+    let mut map = ScanMap::new(2);
+    map.record(&scan, Pose2::new(100.0, 0.0, FRAC_PI_2), &cfg);
+    assert_eq!(map.points.len(), 1);
+    assert!(close(map.points[0], (100.0, 50.0)), "{:?}", map.points[0]);
+
+    // barely moved: skipped
+    map.record(&scan, Pose2::new(101.0, 0.0, FRAC_PI_2), &cfg);
+    assert_eq!(map.points.len(), 1);
+
+    // moved on: kept, and the oldest dropped past capacity
+    map.record(&scan, Pose2::new(120.0, 0.0, FRAC_PI_2), &cfg);
+    map.record(&scan, Pose2::new(140.0, 0.0, FRAC_PI_2), &cfg);
+    assert_eq!(map.points.len(), 2);
+    assert!(close(map.points[0], (120.0, 50.0)) && close(map.points[1], (140.0, 50.0)));
+}
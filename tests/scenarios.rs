@@ -0,0 +1,355 @@
+use slam_simulator::config::Config;
+use slam_simulator::math::Pose2;
+use slam_simulator::scenario::{self, Checkpoint, Convergence, Criteria, Scenario, TIME_STEP};
+use slam_simulator::slam::{EkfSlam, FastSlam, RobocentricEkf, RobustKernel, SeifSlam, SlamFilter};
+
+const SEEDS: [u64; 3] = [1, 7, 42];
+
+#[test]
+fn ekf_square_loop_stays_accurate() {
+    let cfg = Config::default();
+    let scenario = Scenario::square_loop();
+
+    for seed in SEEDS {
+        let report = scenario::run(&scenario, &mut EkfSlam::new(), seed, &cfg);
+        println!("{} seed {seed}: ate {:.2} final {:.2} nees {:.2}", scenario.name, report.ate, report.final_error, report.mean_nees);
+
+        assert_eq!(report.landmarks_initialized, report.landmarks_total, "seed {seed}");
+        assert!(report.ate < 20.0, "seed {seed}: ATE {}", report.ate);
+        assert!(report.final_error < 5.0, "seed {seed}: final error {}", report.final_error);
+        assert!(report.mean_nees < 25.0, "seed {seed}: mean NEES {}", report.mean_nees);
+    }
+}
+
+#[test]
+fn dead_reckoning_baseline_drifts_where_slam_does_not() {
+    // odometry noisy enough for dead reckoning to drift over the laps, which the landmarks correct
+    let cfg = Config { real_stdev_linear: 0.3, real_stdev_angular: 0.3, est_stdev_linear: 0.3, est_stdev_angular: 0.3, ..Config::default() };
+    let scenario = Scenario::long_loop();
+
+    for seed in SEEDS {
+        let report = scenario::run(&scenario, &mut EkfSlam::new(), seed, &cfg);
+        println!("{} seed {seed}: ate {:.2}, dead reckoning {:.2}", scenario.name, report.ate, report.odometry_ate);
+        assert!(report.ate < report.odometry_ate, "seed {seed}: ATE {} vs {}", report.ate, report.odometry_ate);
+    }
+}
+
+#[test]
+fn ekf_corridor_stays_accurate() {
+    let cfg = Config::default();
+    let scenario = Scenario::corridor();
+
+    for seed in SEEDS {
+        let report = scenario::run(&scenario, &mut EkfSlam::new(), seed, &cfg);
+        println!("{} seed {seed}: ate {:.2} final {:.2} nees {:.2}", scenario.name, report.ate, report.final_error, report.mean_nees);
+
+        // without a loop closure heading drift accumulates along the corridor
+        assert_eq!(report.landmarks_initialized, report.landmarks_total, "seed {seed}");
+        assert!(report.ate < 40.0, "seed {seed}: ATE {}", report.ate);
+        assert!(report.mean_nees < 60.0, "seed {seed}: mean NEES {}", report.mean_nees);
+    }
+}
+
+#[test]
+fn fast_slam_square_loop_maps_every_landmark() {
+    let cfg = Config::default();
+    let scenario = Scenario::square_loop();
+
+    for seed in SEEDS {
+        let report = scenario::run(&scenario, &mut FastSlam::new(50), seed, &cfg);
+        println!("{} seed {seed}: ate {:.2} final {:.2}", scenario.name, report.ate, report.final_error);
+
+        assert_eq!(report.landmarks_initialized, report.landmarks_total, "seed {seed}");
+        assert!(report.ate < 20.0, "seed {seed}: ATE {}", report.ate);
+    }
+}
+
+#[test]
+fn robocentric_ekf_square_loop_stays_accurate() {
+    let cfg = Config::default();
+    let scenario = Scenario::square_loop();
+
+    for seed in SEEDS {
+        let report = scenario::run(&scenario, &mut RobocentricEkf::new(), seed, &cfg);
+        println!("{} seed {seed}: ate {:.2} final {:.2} nees {:.2}", scenario.name, report.ate, report.final_error, report.mean_nees);
+
+        assert_eq!(report.landmarks_initialized, report.landmarks_total, "seed {seed}");
+        assert!(report.ate < 20.0, "seed {seed}: ATE {}", report.ate);
+        assert!(report.final_error < 5.0, "seed {seed}: final error {}", report.final_error);
+        assert!(report.mean_nees < 25.0, "seed {seed}: mean NEES {}", report.mean_nees);
+    }
+}
+
+#[test]
+fn seif_square_loop_stays_accurate() {
+    let cfg = Config::default();
+    let scenario = Scenario::square_loop();
+
+    for seed in SEEDS {
+        let mut seif = SeifSlam::new();
+        let report = scenario::run(&scenario, &mut seif, seed, &cfg);
+        println!("{} seed {seed}: ate {:.2} final {:.2} nees {:.2}", scenario.name, report.ate, report.final_error, report.mean_nees);
+
+        assert_eq!(report.landmarks_initialized, report.landmarks_total, "seed {seed}");
+        assert!(report.ate < 20.0, "seed {seed}: ATE {}", report.ate);
+        assert!(seif.active.len() <= cfg.seif_max_active, "seed {seed}: {} active landmarks", seif.active.len());
+
+        // sparsification leaves the robot linked to the active landmarks only
+        let (ids, pattern) = seif.link_pattern().expect("the SEIF keeps an information matrix");
+        for (block, id) in ids.iter().enumerate() {
+            assert_eq!(pattern[0][block + 1], seif.active.contains(id), "seed {seed}: landmark {id}");
+        }
+    }
+}
+
+#[test]
+fn first_estimates_jacobians_improve_long_loop_consistency() {
+    let cfg = Config::default();
+    let scenario = Scenario::long_loop();
+
+    // NEES consistency is a Monte Carlo property, so compare averages over runs;
+    // a consistent filter averages about 3, the pose dimension
+    let runs = 8;
+    let (mut standard_nees, mut fej_nees) = (0.0, 0.0);
+    for seed in 0..runs {
+        let standard = scenario::run(&scenario, &mut EkfSlam::new(), seed, &cfg);
+        let fej = scenario::run(&scenario, &mut EkfSlam::new().with_first_estimates(), seed, &cfg);
+        println!("{} seed {seed}: nees {:.2} standard, {:.2} fej", scenario.name, standard.mean_nees, fej.mean_nees);
+
+        assert_eq!(fej.landmarks_initialized, fej.landmarks_total, "seed {seed}");
+        standard_nees += standard.mean_nees / runs as f32;
+        fej_nees += fej.mean_nees / runs as f32;
+    }
+
+    assert!(fej_nees < 10.0, "FEJ mean NEES {fej_nees}");
+    assert!(fej_nees < standard_nees, "FEJ mean NEES {fej_nees} vs {standard_nees}");
+}
+
+#[test]
+fn same_seed_reproduces_the_same_run() {
+    let cfg = Config::default();
+    let scenario = Scenario::square_loop();
+
+    let first = scenario::run(&scenario, &mut EkfSlam::new(), 3, &cfg);
+    let second = scenario::run(&scenario, &mut EkfSlam::new(), 3, &cfg);
+
+    assert_eq!(first.ate, second.ate);
+    assert_eq!(first.final_error, second.final_error);
+}
+
+#[test]
+fn ekf_handles_landmarks_coming_and_going() {
+    let cfg = Config::default();
+    let scenario = Scenario::changing_loop();
+
+    for seed in SEEDS {
+        let report = scenario::run(&scenario, &mut EkfSlam::new(), seed, &cfg);
+        println!("{} seed {seed}: ate {:.2} final {:.2} nees {:.2}", scenario.name, report.ate, report.final_error, report.mean_nees);
+
+        // landmarks that appear mid-run are mapped; removed ones stay in the map
+        assert_eq!(report.landmarks_initialized, report.landmarks_total, "seed {seed}");
+        assert!(report.ate < 20.0, "seed {seed}: ATE {}", report.ate);
+        assert!(report.mean_nees < 25.0, "seed {seed}: mean NEES {}", report.mean_nees);
+    }
+}
+
+#[test]
+fn adaptive_noise_recovers_misconfigured_measurement_noise() {
+    let mut cfg = Config::default();
+    cfg.est_stdev_range = cfg.real_stdev_range / 5.0;
+    cfg.est_stdev_bearing = cfg.real_stdev_bearing * 4.0;
+    let scenario = Scenario::square_loop();
+
+    for seed in SEEDS {
+        let mut ekf = EkfSlam::new();
+        ekf.set_adaptive_noise(true, &cfg);
+        scenario::run(&scenario, &mut ekf, seed, &cfg);
+
+        let (range, bearing) = ekf.adaptive_noise.as_ref().unwrap().stdevs();
+        println!("{} seed {seed}: adapted range stdev {range:.2}, bearing stdev {bearing:.3}", scenario.name);
+
+        assert!((range / cfg.real_stdev_range - 1.0).abs() < 0.15, "seed {seed}: range stdev {range}");
+        assert!((bearing / cfg.real_stdev_bearing - 1.0).abs() < 0.15, "seed {seed}: bearing stdev {bearing}");
+    }
+}
+
+#[test]
+fn robust_kernels_survive_clutter() {
+    let mut cfg = Config::default();
+    cfg.outlier_probability = 0.05;
+    let scenario = Scenario::square_loop();
+
+    let mut mean_ate = |kernel| {
+        cfg.landmark_kernel = kernel;
+        let ate = (0..8).map(|seed| scenario::run(&scenario, &mut EkfSlam::new(), seed, &cfg).ate).sum::<f32>() / 8.0;
+        println!("{} with {kernel:?}: mean ate {ate:.2}", scenario.name);
+        ate
+    };
+    let gaussian = mean_ate(RobustKernel::Gaussian);
+    let huber = mean_ate(RobustKernel::Huber(2.0));
+    let dcs = mean_ate(RobustKernel::Dcs(5.0));
+
+    // Huber still lets gross errors pull linearly; DCS all but ignores them
+    assert!(huber < gaussian, "Huber {huber} vs Gaussian {gaussian}");
+    assert!(dcs < 0.3 * gaussian, "DCS {dcs} vs Gaussian {gaussian}");
+    assert!(dcs < 20.0, "DCS {dcs}");
+}
+
+#[test]
+fn playback_applies_events_on_simulated_time() {
+    let scenario = Scenario::changing_loop();
+    let mut playback = scenario::Playback::new(scenario.clone());
+    let mut landmarks = scenario.landmarks.clone();
+
+    // however long the caller waits between steps, each event lands on the first step at or after its time
+    let mut steps = 0;
+    let mut changes = Vec::new();
+    let mut count = landmarks.len();
+    while playback.step(&mut landmarks).is_some() {
+        if landmarks.len() != count {
+            changes.push((steps, playback.pending_events().len()));
+            count = landmarks.len();
+        }
+        steps += 1;
+    }
+
+    let first_step_at = |time: f32| (time / scenario::TIME_STEP).ceil() as usize;
+    assert_eq!(changes, vec![(first_step_at(8.0), 2), (first_step_at(20.0), 0)]);
+    assert_eq!(steps, scenario.controls.iter().map(|segment| (segment.duration / scenario::TIME_STEP).round() as usize).sum::<usize>());
+    assert!((playback.time() - steps as f32 * scenario::TIME_STEP).abs() < 1e-3);
+    assert!(Scenario::by_name("changing_loop").is_some() && Scenario::by_name("nowhere").is_none());
+}
+
+#[test]
+fn checkpoints_log_a_metric_timeline() {
+    let cfg = Config::default();
+    let report = scenario::run(&Scenario::long_loop(), &mut EkfSlam::new(), 2, &cfg);
+
+    // one per lap, the last one at the end of the run
+    assert_eq!(report.checkpoints.len(), 5);
+    assert!(report.checkpoints.windows(2).all(|pair| pair[0].frame < pair[1].frame));
+    let last = report.checkpoints.last().unwrap();
+    assert_eq!(last.frame, report.frames);
+    assert!((last.ate - report.ate).abs() < 1e-4 && (last.mean_nees - report.mean_nees).abs() < 1e-4);
+    assert_eq!(last.snapshot.landmarks.len(), report.landmarks_initialized);
+}
+
+#[test]
+fn waypoint_checkpoints_fire_once_on_arrival() {
+    let cfg = Config::default();
+    let mut scenario = Scenario::square_loop();
+    scenario.checkpoints = vec![
+        Checkpoint::Waypoint { x: 150.0, y: 0.0, radius: 20.0 },
+        Checkpoint::Waypoint { x: 5000.0, y: 0.0, radius: 20.0 }, // never reached
+    ];
+
+    let report = scenario::run(&scenario, &mut EkfSlam::new(), 2, &cfg);
+    assert_eq!(report.checkpoints.len(), 1);
+    let Pose2 { x, y, .. } = report.checkpoints[0].snapshot.pose;
+    assert!((x - 130.0).abs() < 15.0 && y.abs() < 15.0, "estimate at the waypoint ({x}, {y})");
+}
+
+#[test]
+fn fog_bank_grows_drift_that_clears_after_it() {
+    let cfg = Config::default();
+    let fog = Scenario::fog_loop();
+    let mut clear = fog.clone();
+    clear.dropout_zones.clear();
+
+    // position covariance trace of the EKF every frame
+    let traces = |scenario: &Scenario| {
+        let mut traces = Vec::new();
+        scenario::run_observed(scenario, &mut EkfSlam::new(), 5, &cfg, |ekf, _| {
+            traces.push(ekf.pose_covariance().fixed_view::<2, 2>(0, 0).trace());
+            Ok(())
+        }).unwrap();
+        traces
+    };
+    let (fog, clear) = (traces(&fog), traces(&clear));
+
+    let lap = fog.len() / 2;
+    let peak = |traces: &[f32]| traces[..lap].iter().copied().fold(0.0f32, f32::max);
+    assert!(peak(&fog) > 1.3 * peak(&clear), "fog peaked at {}, clear skies at {}", peak(&fog), peak(&clear));
+
+    // back at the landmarks near the start, outside the fog
+    let closed = fog[3 * lap / 4..lap].iter().copied().fold(f32::INFINITY, f32::min);
+    assert!(closed < 0.05 * peak(&fog), "only came down to {closed} from {}", peak(&fog));
+}
+
+#[test]
+fn aging_lets_the_filter_follow_a_drifting_map() {
+    let drifting = Scenario::drifting_loop();
+    assert!(drifting.landmarks.iter().all(|landmark| landmark.velocity != (0.0, 0.0)));
+
+    // ATE and map error summed over a few seeds, since single runs are noisy
+    let errors = |landmark_aging_rate: f32| {
+        let cfg = Config { landmark_aging_rate, ..Config::default() };
+        (0..4).fold((0.0, 0.0), |(ate, map), seed| {
+            let report = scenario::run(&drifting, &mut EkfSlam::new(), seed, &cfg);
+            (ate + report.ate, map + report.map.rmse)
+        })
+    };
+    let (remembering, forgetting) = (errors(0.0), errors(0.2));
+    assert!(forgetting.0 < remembering.0, "ATE {forgetting:?} with aging against {remembering:?} without");
+    assert!(forgetting.1 < 0.8 * remembering.1, "map error {forgetting:?} with aging against {remembering:?} without");
+}
+
+#[test]
+fn criteria_report_what_a_run_missed() {
+    let cfg = Config::default();
+    let mut report = scenario::run(&Scenario::corridor(), &mut EkfSlam::new(), 0, &cfg);
+    assert_eq!(Criteria::default().failures(&report), Vec::<String>::new());
+
+    let strict = Criteria { max_ate: Some(report.ate / 2.0), max_mean_nees: Some(report.mean_nees + 1.0), min_landmarks: Some(report.landmarks_total + 1) };
+    let failures = strict.failures(&report);
+    assert_eq!(failures.len(), 2, "{failures:?}");
+    assert!(failures[0].starts_with("ATE") && failures[1].contains("landmarks mapped"), "{failures:?}");
+
+    // a diverged filter fails rather than slipping past the comparison
+    report.mean_nees = f32::NAN;
+    assert_eq!(strict.failures(&report).len(), 3);
+}
+
+#[test]
+fn canned_scenarios_pass_their_criteria() {
+    let cfg = Config::default();
+    for scenario in Scenario::all() {
+        let report = scenario::run(&scenario, &mut EkfSlam::new(), 0, &cfg);
+        let failures = scenario.criteria.failures(&report);
+        assert!(failures.is_empty(), "{}: {failures:?}", scenario.name);
+    }
+}
+
+#[test]
+fn convergence_waits_out_a_quiet_window() {
+    let mut convergence = Convergence::new(1.0, 0.05);
+    let window = (1.0 / TIME_STEP).round() as usize;
+
+    // a new landmark restarts the wait, a creeping ATE does not
+    for frame in 0..window {
+        assert!(!convergence.step(10, usize::from(frame >= window / 2), 2.0));
+    }
+    for _ in 0..window / 2 - 1 {
+        assert!(!convergence.step(10, 1, 2.0));
+    }
+    assert!(convergence.step(10, 1, 2.05));
+
+    // a drifting ATE never settles, and a zero window never converges
+    let mut drifting = Convergence::new(1.0, 0.05);
+    assert!((0..5 * window).all(|frame| !drifting.step(10, 1, 1.0 + 0.1 * frame as f32)));
+    let mut disabled = Convergence::new(0.0, 0.05);
+    assert!((0..5 * window).all(|_| !disabled.step(10, 1, 2.0)));
+}
+
+#[test]
+fn runs_can_stop_at_convergence() {
+    let cfg = Config::default();
+    let full = scenario::run(&Scenario::long_loop(), &mut EkfSlam::new(), 0, &cfg);
+    let converged_at = full.convergence_time.expect("the long loop plateaus before its last lap");
+
+    let stopping = Config { stop_at_convergence: true, ..cfg };
+    let early = scenario::run(&Scenario::long_loop(), &mut EkfSlam::new(), 0, &stopping);
+    assert_eq!(early.convergence_time, Some(converged_at));
+    assert_eq!(early.frames, (converged_at / TIME_STEP).round() as usize);
+    assert!(early.frames < full.frames && early.landmarks_initialized == full.landmarks_initialized);
+}
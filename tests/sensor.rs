@@ -0,0 +1,347 @@
+use macroquad::prelude::Rect;
+use slam_simulator::config::Config;
+use slam_simulator::math::Pose2;
+use slam_simulator::scenario::{self, Scenario};
+use slam_simulator::simulation::{DropoutZone, Landmark, Observation, Robot, SensorOverrides, VisibilitySector, sensor};
+use slam_simulator::slam::{EkfSlam, SlamFilter};
+
+fn seen_ids(landmarks: &[Landmark], cfg: &Config) -> Vec<usize> {
+    let robot = Robot::new();
+    let mut ids: Vec<usize> = sensor::sense(&robot, landmarks, &[], &[], cfg).iter().map(|observation| observation.id).collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn overrides_replace_global_range() {
+    let cfg = Config::default();
+    let mut near_sighted = Landmark::new(0, 100.0, 0.0);
+    near_sighted.sensor.range = Some(50.0);
+    let mut far_sighted = Landmark::new(1, cfg.sensor_range * 2.0, 0.0);
+    far_sighted.sensor.range = Some(cfg.sensor_range * 3.0);
+    let plain = Landmark::new(2, 0.0, 100.0);
+
+    assert_eq!(seen_ids(&[near_sighted, far_sighted, plain], &cfg), vec![1, 2]);
+}
+
+#[test]
+fn detection_probability_is_per_landmark() {
+    let cfg = Config::default();
+    macroquad::rand::srand(3);
+
+    let mut hidden = Landmark::new(0, 50.0, 0.0);
+    hidden.sensor.detection_probability = Some(0.0);
+    let mut flaky = Landmark::new(1, 0.0, 50.0);
+    flaky.sensor.detection_probability = Some(0.5);
+    let landmarks = [hidden, flaky, Landmark::new(2, -50.0, 0.0)];
+
+    let mut counts = [0; 3];
+    for _ in 0..1000 {
+        for id in seen_ids(&landmarks, &cfg) { counts[id] += 1; }
+    }
+
+    assert_eq!(counts[0], 0);
+    assert!((400..600).contains(&counts[1]), "flaky landmark seen {} times", counts[1]);
+    assert_eq!(counts[2], 1000);
+}
+
+#[test]
+fn visibility_sectors_hide_landmarks_from_behind() {
+    let cfg = Config::default();
+    let half_turn = VisibilitySector { facing: 0.0, width: std::f32::consts::PI };
+
+    // all face +x: the robot at the origin is in front of the first, behind the second, and on the edge of the third
+    let mut front = Landmark::new(0, -100.0, 0.0);
+    front.sensor.visibility = Some(half_turn);
+    let mut back = Landmark::new(1, 100.0, 0.0);
+    back.sensor.visibility = Some(half_turn);
+    let mut edge = Landmark::new(2, 0.0, -100.0);
+    edge.sensor.visibility = Some(half_turn);
+
+    assert_eq!(seen_ids(&[front, back, edge], &cfg), vec![0, 2]);
+    assert!(!half_turn.contains((0.0, 0.0), (-1.0, 0.01)));
+}
+
+#[test]
+fn field_of_view_limits_bearings() {
+    // a forward-looking sensor, 120 degrees wide, at a robot facing +x
+    let cfg = Config { sensor_fov: 120f32.to_radians(), outlier_probability: 0.0, ..Config::default() };
+    let landmarks = [
+        Landmark::new(0, 100.0, 0.0),   // dead ahead
+        Landmark::new(1, 50.0, 80.0),   // 58 degrees to the left
+        Landmark::new(2, 0.0, 100.0),   // abeam
+        Landmark::new(3, -100.0, 10.0), // behind
+        Landmark::new(4, 50.0, -80.0),  // 58 degrees to the right
+    ];
+    assert_eq!(seen_ids(&landmarks, &cfg), vec![0, 1, 4]);
+    assert_eq!(seen_ids(&landmarks, &Config::default()), vec![0, 1, 2, 3, 4], "the default sees all around");
+
+    // clutter stays inside the field of view too
+    let cfg = Config { outlier_probability: 1.0, ..cfg };
+    macroquad::rand::srand(1);
+    for _ in 0..200 {
+        let observations = sensor::sense(&Robot::new(), &landmarks, &[], &[], &cfg);
+        assert!(observations.iter().all(|observation| sensor::in_field_of_view(observation.bearing, &cfg)));
+    }
+}
+
+#[test]
+fn unset_overrides_match_config() {
+    let cfg = Config::default();
+    let model = SensorOverrides::default().resolve(&cfg);
+
+    assert_eq!(model.range, cfg.sensor_range);
+    assert_eq!(model.stdev_range, cfg.real_stdev_range);
+    assert_eq!(model.stdev_bearing, cfg.real_stdev_bearing);
+    assert_eq!(model.detection_probability, cfg.detection_probability);
+    assert_eq!(model.outlier_probability, cfg.outlier_probability);
+}
+
+#[test]
+fn compress_averages_repeated_sightings() {
+    let observations = [
+        Observation { id: 4, range: 100.0, bearing: 3.1 },
+        Observation { id: 2, range: 50.0, bearing: 0.0 },
+        Observation { id: 4, range: 110.0, bearing: -3.1 },
+    ];
+
+    let compressed = sensor::compress(&observations);
+
+    assert_eq!(compressed.iter().map(|(observation, readings)| (observation.id, *readings)).collect::<Vec<_>>(), vec![(4, 2), (2, 1)]);
+    assert_eq!(compressed[0].0.range, 105.0);
+    // bearings either side of +-PI average to PI, not 0
+    assert!(compressed[0].0.bearing.abs() > 3.1, "bearing {}", compressed[0].0.bearing);
+}
+
+#[test]
+fn compressed_update_matches_sequential_updates() {
+    let cfg = Config::default();
+    let robot = Robot::new();
+    let landmarks = [Landmark::new(0, 80.0, 30.0), Landmark::new(1, -40.0, 90.0)];
+    macroquad::rand::srand(11);
+
+    // both filters start from the same settled map; early on each correction moves
+    // the linearization point enough that sequential and batched updates part ways
+    let mut sequential = EkfSlam::new();
+    for _ in 0..30 {
+        sequential.update(&sensor::sense(&robot, &landmarks, &[], &[], &cfg), &cfg);
+    }
+    let mut compressed = sequential.clone();
+
+    let repeated: Vec<Observation> = (0..6).flat_map(|_| sensor::sense(&robot, &landmarks, &[], &[], &cfg)).collect();
+    for observation in &repeated {
+        sequential.update(std::slice::from_ref(observation), &cfg);
+    }
+    compressed.update(&repeated, &cfg);
+
+    // relinearizing between sequential corrections leaves only a small fraction of a standard deviation
+    for i in 0..sequential.state.nrows() {
+        let stdev = sequential.covariance[(i, i)].sqrt();
+        let difference = (sequential.state[i] - compressed.state[i]).abs();
+        assert!(difference < 0.05 * stdev, "state {i} differs by {difference} with stdev {stdev}");
+    }
+    let covariance_difference = (&sequential.covariance - &compressed.covariance).abs().max();
+    assert!(covariance_difference < 0.01 * sequential.covariance.abs().max(), "covariances differ by {covariance_difference}");
+}
+
+#[test]
+fn sensor_degrades_with_speed() {
+    let mut cfg = Config::default();
+    cfg.speed_noise_gain = 2.0;
+    cfg.speed_detection_loss = 0.5;
+    cfg.blur_exposure = 0.2;
+    let landmarks = [Landmark::new(0, 100.0, 0.0)];
+    macroquad::rand::srand(5);
+
+    let parked = Robot::new();
+    let mut fast = Robot::new();
+    fast.linear_velocity = cfg.max_linear_speed;
+    fast.angular_velocity = 1.0;
+
+    // (detections, range variance, mean bearing) over many single readings
+    let statistics = |robot: &Robot| {
+        let readings: Vec<Observation> = (0..4000).flat_map(|_| sensor::sense(robot, &landmarks, &[], &[], &cfg)).collect();
+        let count = readings.len() as f32;
+        let range_variance = readings.iter().map(|reading| (reading.range - 100.0).powi(2)).sum::<f32>() / count;
+        let mean_bearing = readings.iter().map(|reading| reading.bearing).sum::<f32>() / count;
+        (readings.len(), range_variance, mean_bearing)
+    };
+    let (parked_count, parked_variance, parked_bearing) = statistics(&parked);
+    let (fast_count, fast_variance, fast_bearing) = statistics(&fast);
+
+    assert_eq!(parked_count, 4000);
+    assert!((1800..2200).contains(&fast_count), "{fast_count} detections at full speed");
+    // noise stdev triples at full speed, so the variance grows ninefold
+    assert!((7.0..11.0).contains(&(fast_variance / parked_variance)), "variance {parked_variance} -> {fast_variance}");
+    assert!(parked_bearing.abs() < 0.01, "parked bearing {parked_bearing}");
+    assert!((fast_bearing - 0.1).abs() < 0.01, "blurred bearing {fast_bearing}");
+}
+
+#[test]
+fn cartesian_noise_stretches_across_the_beam_with_range() {
+    let covariance = sensor::cartesian_covariance(100.0, std::f32::consts::FRAC_PI_2, 5.0, 0.05);
+
+    // pointing along +y, range noise is vertical and bearing noise horizontal
+    assert!((covariance[(1, 1)] - 25.0).abs() < 1e-3, "{covariance}");
+    assert!((covariance[(0, 0)] - 25.0).abs() < 1e-2, "{covariance}");
+    assert!(covariance[(0, 1)].abs() < 1e-3, "{covariance}");
+
+    let farther = sensor::cartesian_covariance(200.0, std::f32::consts::FRAC_PI_2, 5.0, 0.05);
+    assert!((farther[(0, 0)] - 100.0).abs() < 1e-2, "{farther}");
+    assert!((farther[(1, 1)] - 25.0).abs() < 1e-3, "{farther}");
+}
+
+#[test]
+fn readings_come_quantized_with_a_blind_zone() {
+    let cfg = Config {
+        range_resolution: 4.0,
+        bearing_resolution: 1f32.to_radians(),
+        min_sensor_range: 30.0,
+        ..Config::default()
+    };
+    let landmarks = [Landmark::new(0, 20.0, 0.0), Landmark::new(1, 100.0, 37.0), Landmark::new(2, -50.0, -120.0)];
+
+    let observations = sensor::sense(&Robot::new(), &landmarks, &[], &[], &cfg);
+    assert!(observations.iter().all(|observation| observation.id != 0), "landmark 0 is in the blind zone");
+    assert_eq!(observations.len(), 2);
+    for observation in &observations {
+        let range_steps = observation.range / cfg.range_resolution;
+        let bearing_steps = observation.bearing / cfg.bearing_resolution;
+        assert!((range_steps - range_steps.round()).abs() < 1e-3, "range {}", observation.range);
+        assert!((bearing_steps - bearing_steps.round()).abs() < 1e-2, "bearing {}", observation.bearing);
+    }
+}
+
+#[test]
+fn dropout_zones_deny_detection_from_either_end() {
+    let cfg = Config::default();
+    macroquad::rand::srand(4);
+    let landmarks = [Landmark::new(0, 100.0, 0.0), Landmark::new(1, 0.0, 100.0)];
+    let sightings = |zones: &[DropoutZone]| {
+        (0..500).flat_map(|_| sensor::sense(&Robot::new(), &landmarks, &[], zones, &cfg)).map(|observation| observation.id).collect::<Vec<_>>()
+    };
+
+    assert_eq!(sightings(&[]).len(), 1000);
+
+    // fog around the robot hides everything, fog around a landmark only that one
+    let around_robot = sightings(&[DropoutZone { x: 0.0, y: 0.0, radius: 30.0 }]);
+    assert!(around_robot.len() < 50, "saw {} readings from inside the fog", around_robot.len());
+    let around_landmark = sightings(&[DropoutZone { x: 100.0, y: 0.0, radius: 30.0 }]);
+    assert_eq!(around_landmark.iter().filter(|&&id| id == 1).count(), 500);
+    assert!(around_landmark.iter().filter(|&&id| id == 0).count() < 25);
+}
+
+#[test]
+fn obstruction_faces_mirror_landmarks_into_ghosts() {
+    let cfg = Config { reflection_probability: 1.0, real_stdev_range: 0.0, real_stdev_bearing: 0.0, ..Config::default() };
+    let mirror = Rect::new(100.0, -200.0, 20.0, 400.0);
+
+    // the image of (50, 50) in the face at x = 100 stands at (150, 50)
+    let ghosts = sensor::reflections(&Robot::new(), &[Landmark::new(0, 50.0, 50.0)], &[mirror], &[], &cfg);
+    assert_eq!(ghosts.len(), 1);
+    assert_eq!(ghosts[0].id, sensor::ghost_id(0, 0, 3));
+    assert!(sensor::is_ghost(ghosts[0].id) && !sensor::is_ghost(0));
+    assert!((ghosts[0].range - 50f32.hypot(150.0)).abs() < 1e-3, "range {}", ghosts[0].range);
+    assert!((ghosts[0].bearing - 50f32.atan2(150.0)).abs() < 1e-4, "bearing {}", ghosts[0].bearing);
+
+    // sense reports the landmark itself alongside its ghost
+    let observations = sensor::sense(&Robot::new(), &[Landmark::new(0, 50.0, 50.0)], &[mirror], &[], &cfg);
+    assert_eq!(observations.iter().map(|observation| observation.id).collect::<Vec<_>>(), vec![0, ghosts[0].id]);
+
+    // the path has to bounce off the face itself, with both legs clear
+    let short = Rect::new(100.0, -10.0, 20.0, 20.0);
+    assert!(sensor::reflections(&Robot::new(), &[Landmark::new(0, 50.0, 100.0)], &[short], &[], &cfg).is_empty());
+    let screen = Rect::new(70.0, 20.0, 5.0, 40.0);
+    let screened = sensor::reflections(&Robot::new(), &[Landmark::new(0, 50.0, 50.0)], &[mirror, screen], &[], &cfg);
+    assert!(screened.iter().all(|ghost| ghost.id != sensor::ghost_id(0, 0, 3)));
+    // and the landmark has to stand in front of it
+    assert!(sensor::reflections(&Robot::new(), &[Landmark::new(0, 150.0, 50.0)], &[mirror], &[], &cfg).is_empty());
+}
+
+#[test]
+fn ghosts_are_detected_like_their_landmarks() {
+    let cfg = Config { reflection_probability: 1.0, dropout_detection_probability: 0.0, speed_detection_loss: 1.0, ..Config::default() };
+    let mirror = Rect::new(100.0, -200.0, 20.0, 400.0);
+    let landmarks = [Landmark::new(0, 50.0, 50.0)];
+    assert_eq!(sensor::reflections(&Robot::new(), &landmarks, &[mirror], &[], &cfg).len(), 1);
+
+    // fog at either end of the direct reading hides the ghost too
+    let fog = DropoutZone { x: 0.0, y: 0.0, radius: 10.0 };
+    assert!(sensor::reflections(&Robot::new(), &landmarks, &[mirror], &[fog], &cfg).is_empty());
+    let fog = DropoutZone { x: 50.0, y: 50.0, radius: 10.0 };
+    assert!(sensor::reflections(&Robot::new(), &landmarks, &[mirror], &[fog], &cfg).is_empty());
+
+    // and so does full speed, when it costs every detection
+    let mut fast = Robot::new();
+    fast.linear_velocity = cfg.max_linear_speed;
+    assert!(sensor::reflections(&fast, &landmarks, &[mirror], &[], &cfg).is_empty());
+}
+
+#[test]
+fn ghost_ids_never_collide() {
+    let mut ids = std::collections::HashSet::new();
+    for landmark in (0..40).chain([1023, 1024, 1025, 5000]) {
+        for obstruction in (0..40).chain([1023, 1024, 1025, 5000]) {
+            for edge in 0..4 {
+                let id = sensor::ghost_id(landmark, obstruction, edge);
+                assert!(sensor::is_ghost(id));
+                assert!(ids.insert(id), "ghost {landmark}, {obstruction}, {edge} reuses an id");
+            }
+        }
+    }
+}
+
+#[test]
+fn ghost_landmarks_get_culled() {
+    let mut scenario = Scenario::square_loop();
+    scenario.obstructions.push(Rect::new(70.0, 70.0, 40.0, 40.0));
+    let clean_cfg = Config { cull_after_misses: 30, ..Config::default() };
+    let cfg = Config { reflection_probability: 0.2, ..clean_cfg.clone() };
+
+    let clean = scenario::run(&scenario, &mut EkfSlam::new(), 3, &clean_cfg);
+    let mut ekf = EkfSlam::new();
+    let mut mapped = false;
+    let report = scenario::run_observed(&scenario, &mut ekf, 3, &cfg, |ekf, _| {
+        mapped |= ekf.observed_landmarks.ids().any(sensor::is_ghost);
+        ekf.check_invariants()
+    }).unwrap();
+
+    // near ghosts get mapped and culled once the mirror stops showing them
+    assert!(mapped);
+    assert!(ekf.observed_landmarks.ids().all(|id| !sensor::is_ghost(id)), "ghosts left in the map");
+    assert!(ekf.archive.iter().any(|archived| sensor::is_ghost(archived.id)));
+    // a plane mirror's ghost stands still, so mapping it does not throw off the pose
+    assert!(report.ate < 1.5 * clean.ate, "ate {} with ghosts, {} without", report.ate, clean.ate);
+}
+
+#[test]
+fn far_landmarks_wait_until_seen_closer() {
+    let cfg = Config::default();
+    let mut ekf = EkfSlam::new();
+    // two readings of one landmark a range stdev either side of `max_init_range`
+    let far = Observation { id: 0, range: cfg.max_init_range + cfg.est_stdev_range, bearing: 0.2 };
+    let near = Observation { range: cfg.max_init_range - cfg.est_stdev_range, ..far };
+
+    for _ in 0..5 { ekf.update(std::slice::from_ref(&far), &cfg); }
+    assert!(ekf.estimated_map().is_empty());
+    assert_eq!(ekf.provisional[&0].sightings, 5);
+
+    // a stray far reading that disagrees with the held ones is left out
+    ekf.update(&[Observation { range: cfg.max_init_range + 100.0, ..far }], &cfg);
+    assert_eq!(ekf.provisional[&0].sightings, 5);
+
+    // the far sightings aren't lost: the landmark starts between them and the near
+    // reading, with the near reading's uncertainty
+    ekf.update(std::slice::from_ref(&near), &cfg);
+    assert!(ekf.provisional.is_empty());
+    assert_eq!(ekf.sightings[&0], 6);
+
+    let mut near_only = EkfSlam::new();
+    near_only.update(std::slice::from_ref(&near), &cfg);
+    let (far_x, _) = Pose2::IDENTITY.from_range_bearing(far.range, far.bearing);
+    let [(_, started_x, _)] = ekf.estimated_map()[..] else { panic!("one landmark expected") };
+    let [(_, near_x, _)] = near_only.estimated_map()[..] else { panic!("one landmark expected") };
+    assert!(near_x < started_x && started_x < far_x, "{started_x} is not between {near_x} and {far_x}");
+    assert_eq!(ekf.landmark_covariance(0), near_only.landmark_covariance(0));
+    ekf.check_invariants().unwrap();
+}
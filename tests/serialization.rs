@@ -0,0 +1,39 @@
+use slam_simulator::config::Config;
+use slam_simulator::scenario::{self, Scenario};
+use slam_simulator::simulation::Observation;
+use slam_simulator::slam::{EkfSlam, SlamFilter};
+use slam_simulator::snapshot;
+
+#[test]
+fn ekf_round_trips_through_json() {
+    let cfg = Config::default();
+    let mut ekf = EkfSlam::new();
+    scenario::run(&Scenario::square_loop(), &mut ekf, 5, &cfg);
+
+    let restored: EkfSlam = snapshot::from_json(&snapshot::to_json(&ekf).unwrap()).unwrap();
+
+    assert_eq!(restored.state, ekf.state);
+    assert_eq!(restored.covariance, ekf.covariance);
+    assert_eq!(restored.observed_landmarks, ekf.observed_landmarks);
+    assert_eq!(restored.estimated_pose(), ekf.estimated_pose());
+}
+
+#[test]
+fn observations_round_trip_through_json() {
+    let observations = vec![
+        Observation { id: 3, range: 42.5, bearing: -0.25 },
+        Observation { id: 9, range: 120.0, bearing: 3.0 },
+    ];
+
+    let restored: Vec<Observation> = snapshot::from_json(&snapshot::to_json(&observations).unwrap()).unwrap();
+
+    assert_eq!(restored.len(), 2);
+    for (a, b) in restored.iter().zip(&observations) {
+        assert_eq!((a.id, a.range, a.bearing), (b.id, b.range, b.bearing));
+    }
+}
+
+#[test]
+fn malformed_snapshot_is_an_error() {
+    assert!(snapshot::from_json::<EkfSlam>("{\"state\": 1}").is_err());
+}
@@ -0,0 +1,52 @@
+use slam_simulator::config::Config;
+use slam_simulator::math::Pose2;
+use slam_simulator::simulation::Observation;
+use slam_simulator::slam::{EkfSlam, SlamFilter};
+use slam_simulator::state_dump::dump;
+
+// the numbers inside the brackets of the literal starting with `start`
+fn numbers(text: &str, start: &str) -> Vec<f32> {
+    let body = &text[text.find(start).unwrap()..];
+    let body = &body[body.find("&[").unwrap() + 2..body.find("]);").unwrap()];
+    body.lines()
+        .map(|line| line.split("//").next().unwrap())
+        .flat_map(|line| line.split(','))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| value.parse().unwrap())
+        .collect()
+}
+
+fn mapped_ekf() -> EkfSlam {
+    let cfg = Config::default();
+    let mut ekf = EkfSlam::with_prior(Pose2::new(1.0, 2.0, 0.3), 2.0, 0.05);
+    ekf.update(&[Observation { id: 3, range: 50.0, bearing: 0.2 }, Observation { id: 7, range: 60.0, bearing: -0.4 }], &cfg);
+    assert_eq!(ekf.observed_landmarks.len(), 2);
+    ekf
+}
+
+#[test]
+fn full_dump_reads_back_exactly() {
+    let ekf = mapped_ekf();
+    let text = dump(&ekf, None).unwrap();
+
+    assert!(text.contains("// landmark 3") && text.contains("// landmark 7"), "{text}");
+    assert!(text.contains("from_row_slice(7, 7,"), "{text}");
+    assert_eq!(numbers(&text, "let state"), ekf.state.iter().copied().collect::<Vec<_>>());
+    // row-major, so the transpose's column order; the covariance is symmetric anyway
+    assert_eq!(numbers(&text, "let covariance"), ekf.covariance.transpose().iter().copied().collect::<Vec<_>>());
+}
+
+#[test]
+fn landmark_dump_is_the_joint_block_with_the_pose() {
+    let ekf = mapped_ekf();
+    let index = ekf.observed_landmarks.get(7).unwrap();
+    let text = dump(&ekf, Some(7)).unwrap();
+
+    assert!(text.contains("from_row_slice(5, 5,") && !text.contains("landmark 3"), "{text}");
+    let covariance = numbers(&text, "let covariance");
+    assert_eq!(covariance[3], ekf.covariance[(0, index)]);
+    assert_eq!(covariance[5 * 4 + 4], ekf.covariance[(index + 1, index + 1)]);
+
+    assert!(dump(&ekf, Some(42)).is_none());
+}
@@ -0,0 +1,69 @@
+use slam_simulator::config::Config;
+use slam_simulator::slam::{EkfSlam, RobocentricEkf, SlamFilter};
+use slam_simulator::stress::{self, StressKind};
+
+fn positions(kind: StressKind, difficulty: f32, seed: u64) -> Vec<(f32, f32)> {
+    kind.generate(difficulty, seed).landmarks.iter().map(|landmark| (landmark.x, landmark.y)).collect()
+}
+
+#[test]
+fn generation_is_seeded_and_named() {
+    for kind in StressKind::ALL {
+        assert_eq!(positions(kind, 0.5, 3), positions(kind, 0.5, 3), "{}", kind.name());
+        assert_eq!(StressKind::by_name(&kind.name().replace(' ', "_")), Some(kind));
+        assert!(kind.generate(0.5, 3).duration() > 0.0);
+    }
+    assert_ne!(positions(StressKind::SparseDesert, 0.5, 3), positions(StressKind::SparseDesert, 0.5, 4));
+}
+
+#[test]
+fn difficulty_makes_worlds_more_adversarial() {
+    // the corridor grows longer and its landmarks closer together
+    let (easy, hard) = (StressKind::AliasedCorridor.generate(0.0, 1), StressKind::AliasedCorridor.generate(1.0, 1));
+    assert!(hard.duration() > easy.duration());
+    assert!(hard.landmarks.len() > easy.landmarks.len());
+
+    // and at full difficulty it is a perfect grid
+    let spacing = hard.landmarks[2].x - hard.landmarks[0].x;
+    for pair in hard.landmarks.chunks(2).collect::<Vec<_>>().windows(2) {
+        assert!((pair[1][0].x - pair[0][0].x - spacing).abs() < 1e-3);
+    }
+
+    // the desert empties out
+    let counts: Vec<usize> = [0.0, 0.5, 1.0].map(|difficulty| StressKind::SparseDesert.generate(difficulty, 1).landmarks.len()).to_vec();
+    assert!(counts.windows(2).all(|pair| pair[1] < pair[0]), "{counts:?}");
+    assert_eq!(counts[2], 2);
+}
+
+#[test]
+fn the_hardest_room_looks_the_same_from_every_side() {
+    let room = StressKind::SymmetricRoom.generate(1.0, 5);
+    let center = room.boundary.unwrap().center();
+
+    for landmark in &room.landmarks {
+        // a quarter turn about the center lands on another landmark
+        let (x, y) = (center.x - (landmark.y - center.y), center.y + (landmark.x - center.x));
+        assert!(
+            room.landmarks.iter().any(|other| (other.x - x).hypot(other.y - y) < 1e-2),
+            "landmark {} has no rotated twin", landmark.id
+        );
+    }
+}
+
+#[test]
+fn breaking_points_tell_backends_apart() {
+    let cfg = Config::default();
+    let robocentric = || Box::new(RobocentricEkf::new()) as Box<dyn SlamFilter>;
+    let world_centric = || Box::new(EkfSlam::new()) as Box<dyn SlamFilter>;
+
+    // the robocentric EKF holds up everywhere, the world-centric one loses its heading in the corridor
+    for kind in StressKind::ALL {
+        let points = stress::characterize(kind, robocentric, 3, 0, &cfg);
+        for point in &points {
+            println!("{} {:.1}: robocentric ATE {:.1}", kind.name(), point.difficulty, point.report.ate);
+        }
+        assert_eq!(stress::breaking_point(&points, &cfg), None, "{}", kind.name());
+    }
+    let points = stress::characterize(StressKind::AliasedCorridor, world_centric, 3, 0, &cfg);
+    assert!(stress::breaking_point(&points, &cfg).is_some());
+}
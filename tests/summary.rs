@@ -0,0 +1,63 @@
+use slam_simulator::config::Config;
+use slam_simulator::scenario::{self, Playback, Scenario, TIME_STEP};
+use slam_simulator::simulation::{Robot, sensor};
+use slam_simulator::slam::{EkfSlam, OdometryBaseline, SlamFilter};
+use slam_simulator::summary::{SessionStats, SessionSummary};
+
+/*
+ * drives `scenario` the way `scenario::run` does, keeping session statistics
+ */
+fn summarize(scenario: &Scenario, seed: u64, cfg: &Config) -> SessionSummary {
+    macroquad::rand::srand(seed);
+    let mut robot = Robot::new();
+    let mut ekf = EkfSlam::new();
+    let mut odometry = OdometryBaseline::from_slam(&ekf);
+    let mut stats = SessionStats::new();
+    let mut landmarks = scenario.landmarks.clone();
+    let world = scenario.world(cfg);
+
+    let mut playback = Playback::new(scenario.clone());
+    while let Some(segment) = playback.step(&mut landmarks) {
+        robot.command_velocity(segment.linear, segment.angular, cfg, TIME_STEP);
+        robot.update(TIME_STEP, cfg, &world);
+        ekf.predict(robot.linear_velocity, robot.angular_velocity, TIME_STEP, cfg);
+        odometry.predict(robot.linear_velocity, robot.angular_velocity, TIME_STEP, cfg);
+        let observations = sensor::sense_sweeps(&robot, &landmarks, world.solid(), &scenario.dropout_zones, cfg);
+        ekf.update(&observations, cfg);
+        stats.record(playback.time(), &robot, &ekf, &odometry, &observations, cfg);
+    }
+    stats.summary(&ekf, &landmarks, cfg)
+}
+
+#[test]
+fn summary_matches_the_headless_report() {
+    let cfg = Config::default();
+    let scenario = Scenario::square_loop();
+    let summary = summarize(&scenario, 3, &cfg);
+    let report = scenario::run(&scenario, &mut EkfSlam::new(), 3, &cfg);
+
+    assert!((summary.ate - report.ate).abs() < 1e-3, "{} vs {}", summary.ate, report.ate);
+    assert!((summary.odometry_ate - report.odometry_ate).abs() < 1e-3);
+    assert!((summary.mean_nees - report.mean_nees).abs() < 1e-3);
+    assert!((summary.duration - scenario.duration()).abs() < 0.1);
+    assert_eq!(summary.landmarks_mapped, report.landmarks_initialized);
+    assert!(summary.peak_covariance_trace > 0.0);
+
+    // around the loop once, seeing the first landmarks again at the end
+    let nominal: f32 = scenario.controls.iter().map(|segment| segment.linear * segment.duration).sum();
+    assert!(summary.distance_traveled > 0.8 * nominal && summary.distance_traveled < 1.1 * nominal, "{} of {nominal}", summary.distance_traveled);
+    assert!(summary.loop_closures >= 1);
+}
+
+#[test]
+fn summary_exports_as_json() {
+    let cfg = Config::default();
+    let summary = summarize(&Scenario::square_loop(), 1, &cfg);
+
+    let path = std::env::temp_dir().join("slam_simulator_summary").join("summary.json");
+    summary.save(&path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(json["landmarks_mapped"], summary.landmarks_mapped);
+    assert_eq!(json["loop_closures"], summary.loop_closures);
+    assert!((json["ate"].as_f64().unwrap() as f32 - summary.ate).abs() < 1e-4);
+}
@@ -0,0 +1,58 @@
+use macroquad::prelude::{Rect, Vec2};
+use slam_simulator::simulation::Landmark;
+use slam_simulator::template::{self, Template};
+
+#[test]
+fn copy_paste_moves_group_and_renumbers_landmarks() {
+    let mut landmarks = vec![Landmark::new(0, 10.0, 10.0), Landmark::new(4, 30.0, 20.0), Landmark::new(7, 500.0, 500.0)];
+    let mut obstructions = vec![Rect::new(0.0, 0.0, 20.0, 20.0), Rect::new(400.0, 400.0, 20.0, 20.0)];
+
+    let copied = Template::capture(Rect::new(0.0, 0.0, 40.0, 40.0), &landmarks, &obstructions);
+    assert_eq!(copied.landmarks.len(), 2);
+    assert_eq!(copied.obstructions.len(), 1);
+
+    copied.paste(Vec2::new(120.0, 20.0), &mut landmarks, &mut obstructions);
+
+    assert_eq!(landmarks.len(), 5);
+    assert_eq!((landmarks[3].id, landmarks[3].x, landmarks[3].y), (8, 110.0, 10.0));
+    assert_eq!((landmarks[4].id, landmarks[4].x, landmarks[4].y), (9, 130.0, 20.0));
+    assert_eq!(obstructions[2], Rect::new(100.0, 0.0, 20.0, 20.0));
+}
+
+#[test]
+fn templates_round_trip_through_disk() {
+    let path = std::env::temp_dir().join(format!("slam_template_{}.json", std::process::id()));
+    let mut landmark = Landmark::new(3, 1.0, 2.0);
+    landmark.sensor.detection_probability = Some(0.5);
+    let template = Template { landmarks: vec![landmark], obstructions: vec![(1.0, 2.0, 3.0, 4.0)] };
+
+    template.save(&path).unwrap();
+    let loaded = Template::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.obstructions, template.obstructions);
+    assert_eq!(loaded.landmarks[0].sensor.detection_probability, Some(0.5));
+}
+
+#[test]
+fn scatter_fills_area_at_density_with_minimum_separation() {
+    let mut landmarks = vec![Landmark::new(9, 100.0, 100.0)];
+    let area = Rect::new(0.0, 0.0, 200.0, 200.0);
+
+    let placed = template::scatter(area, 2.0, 20.0, &mut landmarks);
+    assert_eq!(placed, 8);
+    assert_eq!(landmarks.len(), 9);
+    assert!(landmarks[1..].iter().all(|landmark| area.contains(Vec2::new(landmark.x, landmark.y))));
+    assert_eq!(landmarks.iter().map(|landmark| landmark.id).collect::<Vec<_>>(), [9, 10, 11, 12, 13, 14, 15, 16, 17]);
+
+    for (i, a) in landmarks.iter().enumerate() {
+        for b in &landmarks[i + 1..] {
+            assert!((a.x - b.x).hypot(a.y - b.y) >= 20.0, "{} and {} too close", a.id, b.id);
+        }
+    }
+
+    // more than fits at that spacing places fewer, never closer
+    let mut crowded = Vec::new();
+    let placed = template::scatter(Rect::new(0.0, 0.0, 100.0, 100.0), 50.0, 40.0, &mut crowded);
+    assert!((4..50).contains(&placed), "placed {placed}");
+}
@@ -0,0 +1,37 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use nalgebra::{Matrix2, Rotation2};
+use slam_simulator::metrics::project_onto_track;
+
+#[test]
+fn splits_along_and_across_the_heading() {
+    // long and thin along x
+    let covariance = Matrix2::new(9.0, 0.0, 0.0, 1.0);
+
+    let east = project_onto_track(&covariance, 0.0);
+    assert!((east.along - 3.0).abs() < 1e-5 && (east.cross - 1.0).abs() < 1e-5, "{east:?}");
+
+    let north = project_onto_track(&covariance, FRAC_PI_2);
+    assert!((north.along - 1.0).abs() < 1e-5 && (north.cross - 3.0).abs() < 1e-5, "{north:?}");
+
+    let west = project_onto_track(&covariance, PI);
+    assert!((west.along - east.along).abs() < 1e-5 && (west.cross - east.cross).abs() < 1e-5, "reversing changed {west:?}");
+}
+
+#[test]
+fn turns_with_the_covariance() {
+    let covariance = Matrix2::new(4.0, 1.5, 1.5, 2.0);
+    let heading = 0.4;
+    let reference = project_onto_track(&covariance, heading);
+
+    for angle in [0.3f32, 1.7, -2.5] {
+        let rotation = Rotation2::new(angle).into_inner();
+        let turned = project_onto_track(&(rotation * covariance * rotation.transpose()), heading + angle);
+        assert!((turned.along - reference.along).abs() < 1e-4, "{turned:?} vs {reference:?}");
+        assert!((turned.cross - reference.cross).abs() < 1e-4, "{turned:?} vs {reference:?}");
+    }
+
+    // the two directions are orthogonal, so they add up to the trace
+    let total = reference.along.powi(2) + reference.cross.powi(2);
+    assert!((total - covariance.trace()).abs() < 1e-4);
+}
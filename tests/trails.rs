@@ -0,0 +1,89 @@
+use slam_simulator::config::Config;
+use slam_simulator::math::Pose2;
+use slam_simulator::simulation::{Robot, World};
+use slam_simulator::slam::{EkfSlam, OdometryBaseline, SlamFilter};
+use slam_simulator::trails::{Trails, UncertaintyTube};
+
+const DELTA_TIME: f32 = 1.0 / 60.0;
+
+/*
+ * drives an arc with no landmarks around, so the EKF only ever predicts
+ */
+fn drive(cfg: &Config, frames: usize) -> (Robot, EkfSlam, OdometryBaseline, Trails) {
+    let mut robot = Robot::new();
+    let mut ekf = EkfSlam::new();
+    let mut odometry = OdometryBaseline::from_slam(&ekf);
+    let mut trails = Trails::new(robot.pose(), 100);
+    for _ in 0..frames {
+        robot.command_velocity(60.0, 0.5, cfg, DELTA_TIME);
+        robot.update(DELTA_TIME, cfg, &World::default());
+        ekf.predict(robot.linear_velocity, robot.angular_velocity, DELTA_TIME, cfg);
+        ekf.update(&[], cfg);
+        odometry.predict(robot.linear_velocity, robot.angular_velocity, DELTA_TIME, cfg);
+        trails.record(&robot, odometry.estimated_pose(), ekf.estimated_pose(), DELTA_TIME, cfg);
+    }
+    (robot, ekf, odometry, trails)
+}
+
+#[test]
+fn dead_reckoning_is_the_filter_without_measurements() {
+    let cfg = Config::default();
+    let (_, ekf, odometry, trails) = drive(&cfg, 300);
+
+    let Pose2 { x, y, theta } = ekf.estimated_pose();
+    let ghost = odometry.estimated_pose();
+    assert!((ghost.x - x).abs() < 1e-2 && (ghost.y - y).abs() < 1e-2 && (ghost.theta - theta).abs() < 1e-4, "{ghost:?} vs ({x}, {y}, {theta})");
+    assert!((odometry.pose_covariance() - ekf.pose_covariance()).abs().max() < 1e-2);
+    assert_eq!(trails.dead_reckoning.back(), Some(&(ghost.x, ghost.y)));
+    assert_eq!(trails.dead_reckoning.len(), 100, "trails keep their capacity");
+}
+
+#[test]
+fn commanded_ghost_separates_actuation_noise() {
+    // without actuation noise the commanded motion is the ground truth
+    let cfg = Config { real_stdev_linear: 0.0, real_stdev_angular: 0.0, ..Config::default() };
+    let (robot, _, _, trails) = drive(&cfg, 300);
+    let ghost = trails.commanded_pose();
+    assert!((ghost.x - robot.x).abs() < 1e-2 && (ghost.y - robot.y).abs() < 1e-2, "{ghost:?} vs ({}, {})", robot.x, robot.y);
+
+    // with it, the ground truth wanders off the commanded motion
+    let cfg = Config { real_stdev_linear: 0.2, real_stdev_angular: 0.2, ..Config::default() };
+    macroquad::rand::srand(5);
+    let (robot, _, _, trails) = drive(&cfg, 300);
+    let ghost = trails.commanded_pose();
+    assert!((ghost.x - robot.x).hypot(ghost.y - robot.y) > 0.1);
+}
+
+#[test]
+fn uncertainty_tube_widens_through_dead_reckoning() {
+    let cfg = Config { tube_length: 10, tube_spacing: 5.0, ..Config::default() };
+    let mut robot = Robot::new();
+    let mut ekf = EkfSlam::new();
+    let mut tube = UncertaintyTube::new(&cfg);
+    for _ in 0..600 {
+        robot.command_velocity(60.0, 0.0, &cfg, DELTA_TIME);
+        robot.update(DELTA_TIME, &cfg, &World::default());
+        ekf.predict(robot.linear_velocity, robot.angular_velocity, DELTA_TIME, &cfg);
+        tube.record(&ekf);
+    }
+
+    assert_eq!(tube.samples.len(), 10, "the tube keeps its capacity");
+    for (before, after) in tube.samples.iter().zip(tube.samples.iter().skip(1)) {
+        assert!((after.x - before.x).hypot(after.y - before.y) >= cfg.tube_spacing);
+        assert!(after.cross >= before.cross, "{before:?} then {after:?}");
+    }
+    assert!(tube.samples.back().unwrap().cross > tube.samples.front().unwrap().cross);
+
+    // standing still adds nothing
+    let last = *tube.samples.back().unwrap();
+    tube.record(&ekf);
+    assert_eq!(tube.samples.back(), Some(&last));
+}
+
+#[test]
+fn trails_fade_towards_their_oldest_point() {
+    assert_eq!(Trails::fade(0, 100), Trails::TAIL_OPACITY);
+    assert_eq!(Trails::fade(99, 100), 1.0);
+    assert!((1..100).all(|i| Trails::fade(i, 100) > Trails::fade(i - 1, 100)));
+    assert_eq!(Trails::fade(0, 1), 1.0, "a lone point is the newest one");
+}
@@ -0,0 +1,49 @@
+use slam_simulator::config::Config;
+use slam_simulator::scenario::Scenario;
+use slam_simulator::tuning::{self, NoiseParameters, TuningSource};
+
+#[test]
+fn cost_rewards_accuracy_and_consistency() {
+    let cfg = Config::default();
+    assert_eq!(tuning::cost(5.0, 3.0, &cfg), 5.0);
+    assert!(tuning::cost(5.0, 30.0, &cfg) > tuning::cost(5.0, 3.0, &cfg));
+    // over- and underconfidence by the same factor cost the same
+    assert!((tuning::cost(5.0, 30.0, &cfg) - tuning::cost(5.0, 0.3, &cfg)).abs() < 1e-4);
+    assert_eq!(tuning::cost(f32::NAN, 3.0, &cfg), f32::INFINITY);
+}
+
+#[test]
+fn tuning_recovers_from_badly_set_noise() {
+    // the filter starts out sure its range readings are ten times better than they are
+    let cfg = Config { est_stdev_range: 0.5, tune_iterations: 12, tune_seeds: 2, ..Config::default() };
+    let sources = [TuningSource::Scenario(Scenario::square_loop())];
+    let result = tuning::tune(&sources, &cfg);
+    println!("{:?} -> {:?}", result.initial_score, result.best_score);
+
+    assert_eq!(result.initial, NoiseParameters::from_config(&cfg));
+    assert_eq!(result.history.len(), cfg.tune_iterations);
+    assert!(result.history.windows(2).all(|pair| pair[1] <= pair[0]));
+    assert!(result.best_score.cost < result.initial_score.cost);
+    assert!(result.best.est_stdev_range > cfg.est_stdev_range);
+
+    // the best score is the one the tuned parameters get, and the search repeats
+    assert_eq!(tuning::evaluate(&sources, &result.best, 0..cfg.tune_seeds, &cfg), result.best_score);
+    assert_eq!(tuning::tune(&sources, &cfg).best, result.best);
+}
+
+#[test]
+fn tuned_noise_round_trips_through_a_file() {
+    let path = std::env::temp_dir().join(format!("slam_noise_{}.json", std::process::id()));
+    let noise = NoiseParameters { est_stdev_linear: 0.05, est_stdev_angular: 0.02, est_stdev_range: 3.0, est_stdev_bearing: 0.04 };
+    noise.save(&path).unwrap();
+    let loaded = NoiseParameters::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded, noise);
+    let cfg = loaded.apply(&Config::default());
+    assert_eq!(NoiseParameters::from_config(&cfg), noise);
+    assert_eq!(cfg.sensor_range, Config::default().sensor_range);
+
+    assert!(TuningSource::resolve("missing.csv").is_err());
+    assert_eq!(TuningSource::resolve("corridor").unwrap().name(), Scenario::corridor().name);
+}
@@ -0,0 +1,82 @@
+use nalgebra::Matrix2;
+use slam_simulator::config::Config;
+use slam_simulator::math::Pose2;
+use slam_simulator::scenario::{self, Scenario};
+use slam_simulator::simulation::Observation;
+use slam_simulator::slam::{EkfSlam, SlamFilter, UkfSlam};
+
+#[test]
+fn ukf_slam_passes_the_square_loop() {
+    let cfg = Config::default();
+    let scenario = Scenario::square_loop();
+    for seed in 0..3 {
+        let report = scenario::run(&scenario, &mut UkfSlam::new(), seed, &cfg);
+        assert!(scenario.criteria.failures(&report).is_empty(), "seed {seed}: {:?}", scenario.criteria.failures(&report));
+    }
+}
+
+#[test]
+fn ukf_slam_is_more_consistent_than_the_ekf() {
+    let cfg = Config::default();
+    for scenario in [Scenario::square_loop(), Scenario::corridor()] {
+        let (mut ukf_nees, mut ekf_nees) = (0.0, 0.0);
+        for seed in 0..8 {
+            ukf_nees += scenario::run(&scenario, &mut UkfSlam::new(), seed, &cfg).mean_nees;
+            ekf_nees += scenario::run(&scenario, &mut EkfSlam::new(), seed, &cfg).mean_nees;
+        }
+        println!("{}: mean NEES UKF {:.2} EKF {:.2}", scenario.name, ukf_nees / 8.0, ekf_nees / 8.0);
+        assert!(ukf_nees < ekf_nees);
+    }
+}
+
+/*
+ * one landmark dead ahead, sighted by a robot whose heading is known only to
+ * within a radian
+ */
+#[test]
+fn heading_uncertainty_bends_the_new_landmark() {
+    let cfg = Config::default();
+    let mut ekf = EkfSlam::with_prior(Pose2::IDENTITY, 0.1, 1.0);
+    let mut ukf = UkfSlam::with_prior(Pose2::IDENTITY, 0.1, 1.0);
+    let observation = [Observation { id: 0, range: 100.0, bearing: 0.0 }];
+    ekf.update(&observation, &cfg);
+    ukf.update(&observation, &cfg);
+
+    // the linearization puts the landmark at full range; the sigma points see the arc pull it in
+    let (_, ekf_x, _) = ekf.estimated_map()[0];
+    let (_, ukf_x, ukf_y) = ukf.estimated_map()[0];
+    assert!((ekf_x - 100.0).abs() < 1e-3);
+    assert!(ukf_x < 90.0 && ukf_y.abs() < 1e-3, "{ukf_x} {ukf_y}");
+
+    let covariance: Matrix2<f32> = ukf.landmark_covariance(0).unwrap();
+    assert!((covariance - covariance.transpose()).abs().max() < 1e-3);
+    assert!(covariance.cholesky().is_some());
+    assert!(ukf.covariance.clone().cholesky().is_some());
+}
+
+#[test]
+fn corrections_pull_the_pose_toward_the_measurements() {
+    let cfg = Config::default();
+    let landmarks = [(50.0, 40.0), (80.0, -30.0), (-20.0, 60.0)];
+    let sightings = |x: f32| -> Vec<Observation> {
+        landmarks.iter()
+            .enumerate()
+            .map(|(id, &(landmark_x, landmark_y))| {
+                let (range, bearing) = Pose2::new(x, 0.0, 0.0).range_bearing((landmark_x, landmark_y));
+                Observation { id, range, bearing }
+            })
+            .collect()
+    };
+
+    // map the landmarks from a known start, then drive on odometry that overshoots
+    let mut ukf = UkfSlam::with_prior(Pose2::IDENTITY, 0.01, 0.01);
+    ukf.update(&sightings(0.0), &cfg);
+    for _ in 0..60 {
+        ukf.predict(22.0, 0.0, scenario::TIME_STEP, &cfg);
+    }
+    let before = ukf.estimated_pose().x;
+    ukf.update(&sightings(20.0), &cfg);
+    let after = ukf.estimated_pose().x;
+    assert!((after - 20.0).abs() < (before - 20.0).abs(), "{before} -> {after}");
+    assert!(ukf.pose_covariance().trace() > 0.0);
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::record;
+use nalgebra::{DMatrix, DVector};
+use slam_simulator::config::Config;
+use slam_simulator::math::normalize_angle;
+use slam_simulator::scenario::{Scenario, TIME_STEP};
+use slam_simulator::update_trace;
+
+fn matrix(rows: &[Vec<f32>]) -> DMatrix<f32> {
+    DMatrix::from_fn(rows.len(), rows[0].len(), |r, c| rows[r][c])
+}
+
+#[test]
+fn traced_corrections_recompute_from_their_own_inputs() {
+    let cfg = Config::default();
+    let recording = record(&Scenario::square_loop(), 2, &cfg);
+    // the recorded estimates are the EKF's as it ran, not its prior
+    assert!(recording.frames.iter().any(|frame| frame.estimates[0] != recording.start[0]));
+    assert!(recording.error_summary(0).max < 30.0);
+
+    let entries = update_trace::trace_recording(&recording, 5.0, 6.0, &cfg).unwrap();
+    assert!(entries.len() > 20, "only {} corrections traced", entries.len());
+    assert!(entries.iter().all(|entry| (5.0..=6.0).contains(&entry.time)));
+
+    for entry in &entries {
+        let trace = &entry.correction;
+        let innovation = DVector::from_column_slice(&[trace.z.0 - trace.z_pred.0, normalize_angle(trace.z.1 - trace.z_pred.1)]);
+        assert!((innovation[0] - trace.innovation.0).abs() < 1e-4 && (innovation[1] - trace.innovation.1).abs() < 1e-5);
+
+        // S = H P H^T + R and K = P H^T S^-1 from the traced P columns alone
+        let (h, r, p_columns) = (matrix(&trace.h), matrix(&trace.r), matrix(&trace.p_columns));
+        let p_block = DMatrix::from_fn(5, 5, |i, j| p_columns[(trace.columns[i], j)]);
+        let s = &h * p_block * h.transpose() + r;
+        assert!((&s - matrix(&trace.s)).abs().max() < 1e-3 * s.abs().max(), "landmark {} at {}", trace.landmark, entry.time);
+        let k = &p_columns * h.transpose() * s.try_inverse().unwrap();
+        assert!((&k - matrix(&trace.k)).abs().max() < 1e-3 * k.abs().max().max(1e-3));
+
+        let delta = &k * innovation;
+        assert_eq!(delta.len(), trace.state.len());
+        assert!(delta.iter().zip(&trace.state_delta).all(|(a, b)| (a - b).abs() < 1e-3 * (1.0 + b.abs())));
+    }
+}
+
+#[test]
+fn the_trace_window_follows_the_recorded_clock() {
+    let cfg = Config::default();
+    let recording = record(&Scenario::square_loop(), 2, &cfg);
+    // half a step off the frames, so rounding can't move a frame across the edges
+    let (start, end) = (5.0 + 0.5 * TIME_STEP, 6.0 + 0.5 * TIME_STEP);
+    let expected = update_trace::trace_recording(&recording, start, end, &cfg).unwrap();
+
+    // recorded 100 s into a session, the same corrections sit 100 s later
+    let mut later = record(&Scenario::square_loop(), 2, &cfg);
+    for frame in &mut later.frames { frame.time += 100.0; }
+    assert!(update_trace::trace_recording(&later, start, end, &cfg).unwrap().is_empty());
+    let entries = update_trace::trace_recording(&later, start + 100.0, end + 100.0, &cfg).unwrap();
+    assert_eq!(entries.len(), expected.len());
+    for (entry, expected) in entries.iter().zip(&expected) {
+        assert_eq!((entry.time, entry.frame), (expected.time + 100.0, expected.frame));
+        assert_eq!(later.frames[entry.frame].time, entry.time);
+    }
+}
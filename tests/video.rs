@@ -0,0 +1,54 @@
+use slam_simulator::config::Config;
+use slam_simulator::math::Pose2;
+use slam_simulator::recording::Recording;
+use slam_simulator::video::{VideoSettings, frame_schedule, world_bounds};
+
+fn args(line: &str) -> Vec<String> {
+    line.split_whitespace().map(str::to_string).collect()
+}
+
+#[test]
+fn frames_show_the_recorded_frame_due_at_their_time() {
+    // uneven live frame times, 1.2 s long
+    let times = [0.0, 0.01, 0.05, 0.3, 0.31, 0.6, 1.0, 1.2];
+    let schedule = frame_schedule(&times, 10.0);
+
+    // a frame at every 0.1 s from the first recorded one through the last
+    assert_eq!(schedule.len(), 13);
+    assert_eq!(schedule, [0, 2, 2, 3, 4, 4, 5, 5, 5, 5, 6, 6, 7]);
+    for (frame, &index) in schedule.iter().enumerate() {
+        assert!(times[index] <= frame as f32 / 10.0 + 1e-4, "frame {frame} shows a recorded frame early");
+    }
+
+    // a steady 60 Hz recording at 30 fps shows every other frame
+    let times: Vec<f32> = (0..121).map(|frame| 5.0 + frame as f32 / 60.0).collect();
+    let schedule = frame_schedule(&times, 30.0);
+    assert_eq!(schedule.len(), 61);
+    assert!(schedule.iter().enumerate().all(|(frame, &index)| index == 2 * frame), "{schedule:?}");
+
+    assert!(frame_schedule(&[], 30.0).is_empty());
+}
+
+#[test]
+fn settings_come_from_the_arguments_over_the_config() {
+    let cfg = Config::default();
+    let defaults = VideoSettings::from_args(&args("sim video run.csv"), &cfg).unwrap();
+    assert_eq!(defaults, VideoSettings { width: cfg.video_width, height: cfg.video_height, fps: cfg.video_fps });
+
+    let custom = VideoSettings::from_args(&args("sim video run.csv --fps 60 --size 3840x2160"), &cfg).unwrap();
+    assert_eq!(custom, VideoSettings { width: 3840, height: 2160, fps: 60.0 });
+
+    assert!(VideoSettings::from_args(&args("sim video run.csv --size 1920"), &cfg).is_err());
+    assert!(VideoSettings::from_args(&args("sim video run.csv --fps 0"), &cfg).is_err());
+    assert!(VideoSettings::from_args(&args("sim video run.csv --fps"), &cfg).is_err());
+}
+
+#[test]
+fn bounds_hold_every_pose_with_a_margin() {
+    let mut recording = Recording::new(&["ekf"]);
+    recording.push(0.0, Pose2::IDENTITY, vec![Pose2::new(-10.0, 5.0, 0.0)]);
+    recording.push(0.1, Pose2::new(100.0, 40.0, 0.0), vec![Pose2::new(f32::NAN, f32::NAN, 0.0)]);
+
+    let bounds = world_bounds(&recording, 20.0);
+    assert_eq!((bounds.x, bounds.y, bounds.w, bounds.h), (-30.0, -20.0, 150.0, 80.0));
+}
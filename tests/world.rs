@@ -0,0 +1,106 @@
+use macroquad::prelude::Rect;
+use slam_simulator::config::Config;
+use slam_simulator::math::Pose2;
+use slam_simulator::scenario::Scenario;
+use slam_simulator::simulation::{Landmark, Robot, VisibilitySector, World, sensor};
+
+fn walled_room() -> World {
+    let cfg = Config { world_boundary: Some((400.0, 400.0)), ..Config::default() };
+    let mut world = World::new(&cfg);
+    world.obstructions.push(Rect::new(50.0, -10.0, 20.0, 20.0));
+    world.landmarks = vec![Landmark::new(0, 100.0, 0.0), Landmark::new(1, 0.0, 100.0), Landmark::new(2, 0.0, -150.0)];
+    world
+}
+
+#[test]
+fn visible_landmarks_respect_range_occlusion_and_facing() {
+    let mut world = walled_room();
+    let origin = Pose2::new(0.0, 0.0, 0.0);
+    let ids = |world: &World, pose: Pose2, cfg: &Config| world.visible_landmarks(pose, cfg).iter().map(|landmark| landmark.id).collect::<Vec<_>>();
+    let cfg = Config { sensor_range: 200.0, min_sensor_range: 0.0, sensor_fov: std::f32::consts::TAU, ..Config::default() };
+
+    // landmark 0 hides behind the obstruction, landmark 2 is out of the shorter range
+    assert_eq!(ids(&world, origin, &cfg), vec![1, 2]);
+    assert_eq!(ids(&world, origin, &Config { sensor_range: 120.0, ..cfg.clone() }), vec![1]);
+
+    // nor does one in the blind zone or outside the field of view
+    assert_eq!(ids(&world, origin, &Config { min_sensor_range: 120.0, ..cfg.clone() }), vec![2]);
+    let facing_up = Pose2::new(0.0, 0.0, std::f32::consts::FRAC_PI_2);
+    assert_eq!(ids(&world, facing_up, &Config { sensor_fov: 1.0, ..cfg.clone() }), vec![1]);
+
+    // a reflector facing away from the origin doesn't show
+    world.landmarks[1].sensor.visibility = Some(VisibilitySector { facing: std::f32::consts::FRAC_PI_2, width: 1.0 });
+    assert_eq!(ids(&world, origin, &cfg), vec![2]);
+}
+
+#[test]
+fn visible_landmarks_are_what_a_perfect_sensor_reads() {
+    let cfg = Config { sensor_fov: 2.0, min_sensor_range: 20.0, detection_probability: 1.0, outlier_probability: 0.0, ..Config::default() };
+    let mut world = Scenario::corridor().world(&cfg);
+    let mut seen = 0;
+    for (x, y, theta) in [(0.0, 0.0, 0.0), (150.0, 20.0, 1.0), (300.0, -10.0, 3.0), (80.0, 0.0, -2.0)] {
+        world.robot.place(x, y, theta);
+        let visible: Vec<usize> = world.visible_landmarks(world.robot.pose(), &cfg).iter().map(|landmark| landmark.id).collect();
+        let mut sensed: Vec<usize> = sensor::sense(&world.robot, &world.landmarks, world.solid(), &[], &cfg).iter().map(|observation| observation.id).collect();
+        sensed.sort_unstable();
+        assert_eq!(visible, sensed, "from ({x}, {y}, {theta})");
+        seen += sensed.len();
+    }
+    assert!(seen > 0);
+}
+
+#[test]
+fn raycast_stops_at_obstructions_and_walls() {
+    let world = walled_room();
+
+    let hit = world.raycast((0.0, 0.0), 0.0, 500.0).unwrap();
+    assert!((hit - 50.0).abs() < 1e-4, "hit the obstruction at {hit}");
+    // the walls stand just outside the 400 by 400 area
+    let wall = world.raycast((0.0, 0.0), std::f32::consts::FRAC_PI_2, 500.0).unwrap();
+    assert!((wall - 200.0).abs() < 1e-4, "hit the wall at {wall}");
+    assert_eq!(world.raycast((0.0, 0.0), std::f32::consts::FRAC_PI_2, 150.0), None);
+}
+
+#[test]
+fn separates_from_obstructions_and_walls() {
+    let world = walled_room();
+
+    assert_eq!(world.separate((45.0, 0.0), 10.0), Some((40.0, 0.0)));
+    assert_eq!(world.separate((30.0, 0.0), 10.0), None);
+    assert_eq!(world.separate((0.0, 195.0), 10.0), Some((0.0, 190.0)));
+    assert_eq!(World::default().separate((0.0, 195.0), 10.0), None);
+}
+
+#[test]
+fn world_sensing_matches_the_sensor() {
+    let cfg = Config::default();
+    let scenario = Scenario::corridor();
+    let world = scenario.world(&cfg);
+    assert_eq!(world.solid().copied().collect::<Vec<_>>(), scenario.world_obstructions(&cfg));
+
+    let robot = Robot::new();
+    macroquad::rand::srand(8);
+    let from_world = world.sense(&robot, &cfg);
+    macroquad::rand::srand(8);
+    let from_sensor = sensor::sense_sweeps(&robot, &world.landmarks, world.solid(), &world.dropout_zones, &cfg);
+    assert_eq!(
+        from_world.iter().map(|observation| (observation.id, observation.range, observation.bearing)).collect::<Vec<_>>(),
+        from_sensor.iter().map(|observation| (observation.id, observation.range, observation.bearing)).collect::<Vec<_>>()
+    );
+    assert!(!from_world.is_empty());
+}
+
+#[test]
+fn world_moves_its_robot_into_walls_not_through() {
+    let cfg = Config::default();
+    let mut world = walled_room();
+    for _ in 0..300 {
+        world.robot.command_velocity(100.0, 0.0, &cfg, 1.0 / 60.0);
+        world.step_robot(1.0 / 60.0, &cfg);
+    }
+
+    // stopped against the obstruction ahead
+    assert!(world.robot.bumped);
+    assert_eq!(world.separate((world.robot.x, world.robot.y), cfg.robot_radius - 0.5), None);
+    assert!(world.robot.x < 50.0);
+}